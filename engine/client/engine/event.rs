@@ -1,4 +1,8 @@
-use core::{ffi::c_int, mem::MaybeUninit};
+use core::{
+    ffi::{c_int, c_ushort},
+    mem::MaybeUninit,
+    ptr,
+};
 
 use bitflags::bitflags;
 use xash3d_shared::{
@@ -341,24 +345,46 @@ impl EventApi {
         unsafe { unwrap!(self, EV_WeaponAnimation)(sequence, body) }
     }
 
-    // pub EV_PrecacheEvent:
-    //     Option<unsafe extern "C" fn(type_: c_int, psz: *const c_char) -> c_ushort>,
-    // pub EV_PlaybackEvent: Option<
-    //     unsafe extern "C" fn(
-    //         flags: c_int,
-    //         pInvoker: *const edict_s,
-    //         eventindex: c_ushort,
-    //         delay: f32,
-    //         origin: *mut f32,
-    //         angles: *mut f32,
-    //         fparam1: f32,
-    //         fparam2: f32,
-    //         iparam1: c_int,
-    //         iparam2: c_int,
-    //         bparam1: c_int,
-    //         bparam2: c_int,
-    //     ),
-    // >,
+    /// Returns an index of an event precached by the server.
+    pub fn precache_event(&self, name: impl ToEngineStr) -> c_ushort {
+        let name = name.to_engine_str();
+        unsafe { unwrap!(self, EV_PrecacheEvent)(1, name.as_ptr()) }
+    }
+
+    /// Plays an event invoked by the local player.
+    #[allow(clippy::too_many_arguments)]
+    pub fn playback_event(
+        &self,
+        flags: c_int,
+        event_index: c_ushort,
+        delay: f32,
+        mut origin: vec3_t,
+        mut angles: vec3_t,
+        fparam1: f32,
+        fparam2: f32,
+        iparam1: c_int,
+        iparam2: c_int,
+        bparam1: c_int,
+        bparam2: c_int,
+    ) {
+        unsafe {
+            // FIXME: ffi: why origin and angles are mutable?
+            unwrap!(self, EV_PlaybackEvent)(
+                flags,
+                ptr::null(),
+                event_index,
+                delay,
+                origin.as_mut().as_mut_ptr(),
+                angles.as_mut().as_mut_ptr(),
+                fparam1,
+                fparam2,
+                iparam1,
+                iparam2,
+                bparam1,
+                bparam2,
+            )
+        }
+    }
 
     pub fn trace_texture(
         &self,
@@ -4,6 +4,7 @@ use core::{
     mem, ptr, slice,
 };
 
+use xash3d_player_move::MovementConfig;
 use xash3d_shared::{
     csz::CStrThin,
     engine::net::netadr_s,
@@ -48,7 +49,13 @@ pub trait ClientDll: UnsyncGlobal {
         true
     }
 
+    /// Movement parameters used by the player movement code.
+    fn movement_config(&self) -> MovementConfig {
+        MovementConfig::default()
+    }
+
     fn player_move_init(&self, pm: *mut playermove_s) {
+        xash3d_player_move::set_movement_config(self.movement_config());
         xash3d_player_move::player_move_init(unsafe { &mut *pm.cast() });
     }
 
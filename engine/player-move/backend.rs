@@ -0,0 +1,91 @@
+use core::ffi::{CStr, c_int};
+
+use alloc::vec::Vec;
+use xash3d_shared::{
+    ffi::{
+        common::{pmtrace_s, trace_t, vec3_t},
+        player_move::{physent_s, playermove_s},
+    },
+    model::ModelType,
+    sound::{Attenuation, Channel, Pitch, SoundFlags},
+};
+
+/// Services the player movement code needs from the outside world.
+///
+/// The engine implementation is [EngineBackend](crate::raw::EngineBackend), it calls
+/// function pointers stored in [playermove_s]. Other implementations can answer traces
+/// and contents checks without the engine, see [sim](crate::sim) module.
+pub trait Backend {
+    /// Returns the current time in seconds.
+    fn system_time_f64(&self, pm: &playermove_s) -> f64;
+
+    /// Returns a random integer in the inclusive range `min..=max`.
+    fn random_int(&self, pm: &playermove_s, min: c_int, max: c_int) -> c_int;
+
+    /// Returns a random float in the range `min..max`.
+    fn random_float(&self, pm: &playermove_s, min: f32, max: f32) -> f32;
+
+    #[allow(clippy::too_many_arguments)]
+    fn play_sound(
+        &self,
+        pm: &playermove_s,
+        channel: Channel,
+        sample: &CStr,
+        volume: f32,
+        attenuation: Attenuation,
+        flags: SoundFlags,
+        pitch: Pitch,
+    );
+
+    /// Returns the texture name of the surface hit by a line from `start` to `end`.
+    fn trace_texture(
+        &self,
+        pm: &playermove_s,
+        ground: bool,
+        start: vec3_t,
+        end: vec3_t,
+    ) -> Option<&CStr>;
+
+    /// Returns the contents and the true contents (currents are not converted to water)
+    /// at the given point.
+    fn point_contents(&self, pm: &playermove_s, point: vec3_t) -> (c_int, c_int);
+
+    /// Returns the contents of the physent hull used by the player at the given point.
+    fn physent_point_contents(&self, pm: &playermove_s, pe: &physent_s, point: vec3_t) -> c_int;
+
+    fn load_file(&self, pm: &playermove_s, path: &CStr) -> Option<Vec<u8>>;
+
+    fn player_trace(
+        &self,
+        pm: &playermove_s,
+        start: vec3_t,
+        end: vec3_t,
+        flags: c_int,
+        ignore_pe: c_int,
+    ) -> pmtrace_s;
+
+    /// Returns the index of a physent that blocks the player at the given point or `-1`.
+    fn test_player_position(&self, pm: &playermove_s, point: vec3_t) -> (c_int, pmtrace_s);
+
+    /// Returns `None` if the physent does not have a model.
+    fn model_type(&self, pm: &playermove_s, pe: &physent_s) -> Option<ModelType>;
+
+    /// Returns `None` if the physent does not have a model.
+    fn model_bounds(&self, pm: &playermove_s, pe: &physent_s) -> Option<(vec3_t, vec3_t)>;
+
+    /// Traces a point-sized line against a single physent.
+    fn trace_model(&self, pm: &playermove_s, pe: &physent_s, start: vec3_t, end: vec3_t)
+    -> trace_t;
+
+    fn stuck_touch(&self, pm: &playermove_s, hitent: c_int, trace: &mut pmtrace_s);
+
+    fn particle(
+        &self,
+        pm: &playermove_s,
+        origin: vec3_t,
+        color: c_int,
+        life: f32,
+        zpos: c_int,
+        zvel: c_int,
+    );
+}
@@ -0,0 +1,77 @@
+use core::ffi::c_int;
+
+use xash3d_shared::ffi::common::vec3_t;
+
+use crate::{
+    DUCK_HULL_MAX, DUCK_HULL_MIN, DUCK_VIEW_OFFSET, HULL_MAX, HULL_MIN, PlayerMove, VIEW_OFFSET,
+};
+
+/// Movement parameters supplied by the game at init.
+///
+/// The default values match Half-Life.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MovementConfig {
+    /// Time in seconds to fully duck.
+    pub time_to_duck: f32,
+    /// Scales the movement speed while ducking.
+    pub ducking_multiplier: f32,
+    pub max_climb_speed: f32,
+    pub longjump_speed: f32,
+    /// Limits the speed after a jump to `factor * maxspeed`.
+    ///
+    /// If `None` bunny jumping is not limited.
+    pub bunnyjump_max_speed_factor: Option<f32>,
+    pub hull_min: vec3_t,
+    pub hull_max: vec3_t,
+    pub duck_hull_min: vec3_t,
+    pub duck_hull_max: vec3_t,
+    pub view_offset: vec3_t,
+    pub duck_view_offset: vec3_t,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            time_to_duck: 0.4,
+            ducking_multiplier: 0.333,
+            max_climb_speed: 200.0,
+            longjump_speed: 350.0,
+            bunnyjump_max_speed_factor: Some(1.7),
+            hull_min: HULL_MIN,
+            hull_max: HULL_MAX,
+            duck_hull_min: DUCK_HULL_MIN,
+            duck_hull_max: DUCK_HULL_MAX,
+            view_offset: VIEW_OFFSET,
+            duck_view_offset: DUCK_VIEW_OFFSET,
+        }
+    }
+}
+
+impl MovementConfig {
+    pub fn hull_bounds(&self, hullnumber: c_int) -> Option<(vec3_t, vec3_t)> {
+        const NORMAL_PLAYER_HULL: c_int = 0;
+        const CROUCHED_PLAYER_HULL: c_int = 1;
+        const POINT_BASED_HULL: c_int = 2;
+
+        match hullnumber {
+            NORMAL_PLAYER_HULL => Some((self.hull_min, self.hull_max)),
+            CROUCHED_PLAYER_HULL => Some((self.duck_hull_min, self.duck_hull_max)),
+            POINT_BASED_HULL => Some((vec3_t::ZERO, vec3_t::ZERO)),
+            _ => None,
+        }
+    }
+}
+
+/// A custom player movement mode.
+///
+/// Modes are checked in the order they were added to
+/// [PlayerMoveContext](crate::PlayerMoveContext). The first active mode replaces the
+/// ladder, fly and walk movement for the frame. Stuck checks, ducking and spectator
+/// movement are done before modes are checked.
+pub trait MoveMode {
+    /// Returns `true` if this mode moves the player in the current frame.
+    fn is_active(&self, pm: &PlayerMove) -> bool;
+
+    /// Moves the player for one frame.
+    fn run(&mut self, pm: &mut PlayerMove);
+}
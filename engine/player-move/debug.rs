@@ -42,10 +42,7 @@ impl super::PlayerMove<'_> {
 
         let pe = &self.raw.physents[num as usize];
 
-        if !pe.model.is_null() {
-            let model = unsafe { &*pe.model };
-            let (mins, maxs) = self.get_model_bounds(model);
-
+        if let Some((mins, maxs)) = self.model_bounds(pe) {
             for (i, point) in points.iter_mut().enumerate() {
                 let x = if i & 1 != 0 {
                     mins[0] - gap
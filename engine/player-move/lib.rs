@@ -2,30 +2,46 @@
 
 extern crate alloc;
 
+#[cfg(any(test, feature = "std"))]
+#[allow(unused_imports)]
+#[macro_use]
+extern crate std;
+
 #[macro_use]
 extern crate log;
 
+mod backend;
+mod config;
 mod debug;
 
+pub mod materials;
 pub mod raw;
+#[cfg(any(test, feature = "std"))]
+pub mod sim;
+
+pub use crate::{
+    backend::Backend,
+    config::{MoveMode, MovementConfig},
+    materials::{Material, Materials},
+    raw::EngineBackend,
+};
 
 use core::{
+    cell::RefCell,
     cmp,
     ffi::{CStr, c_char, c_int},
-    ptr, str,
-    sync::atomic::{AtomicU8, Ordering},
+    mem, ptr,
 };
 
-use alloc::vec::Vec;
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
 use xash3d_shared::{
-    cell::SyncOnceCell,
     consts::{
         CONTENTS_CURRENT_0, CONTENTS_CURRENT_DOWN, CONTENTS_EMPTY, CONTENTS_LADDER, CONTENTS_LAVA,
         CONTENTS_SLIME, CONTENTS_SOLID, CONTENTS_TRANSLUCENT, CONTENTS_WATER, DEAD_DISCARDBODY,
         IN_ATTACK, IN_BACK, IN_DUCK, IN_FORWARD, IN_JUMP, IN_MOVELEFT, IN_MOVERIGHT, IN_USE,
-        MAX_CLIP_PLANES, MAX_PHYSENTS, PITCH, PM_NORMAL, ROLL, YAW,
+        MAX_CLIP_PLANES, MAX_PHYSENTS, MAX_PLAYERS, PITCH, PM_NORMAL, ROLL, YAW,
     },
-    csz::{CStrArray, CStrThin},
+    csz::CStrThin,
     entity::{EdictFlags, MoveType},
     ffi::{
         common::{pmtrace_s, qboolean, vec3_t},
@@ -36,9 +52,7 @@ use xash3d_shared::{
     sound::{Attenuation, Channel, Pitch, SoundFlags},
 };
 
-const TIME_TO_DUCK: f32 = 0.4;
 const PM_DEAD_VIEWHEIGHT: f32 = -8.0;
-const MAX_CLIMB_SPEED: f32 = 200.0;
 const STUCK_MOVEUP: c_int = 1;
 // const STUCK_MOVEDOWN: c_int = -1;
 
@@ -69,12 +83,6 @@ const PLAYER_MAX_SAFE_FALL_SPEED: f32 = 580.0;
 const PLAYER_MIN_BOUNCE_SPEED: f32 = 200.0;
 const PLAYER_FALL_PUNCH_THRESHOLD: f32 = 350.0;
 
-const PLAYER_LONGJUMP_SPEED: f32 = 350.0;
-
-const PLAYER_DUCKING_MULTIPLIER: f32 = 0.333;
-
-const MAX_CLIENTS: usize = 32;
-
 pub const CHAR_TEX_CONCRETE: c_char = b'C' as c_char;
 pub const CHAR_TEX_METAL: c_char = b'M' as c_char;
 pub const CHAR_TEX_DIRT: c_char = b'D' as c_char;
@@ -87,52 +95,42 @@ pub const CHAR_TEX_COMPUTER: c_char = b'P' as c_char;
 pub const CHAR_TEX_GLASS: c_char = b'Y' as c_char;
 pub const CHAR_TEX_FLESH: c_char = b'F' as c_char;
 
-/// Max number of textures loaded.
-const CTEXTURESMAX: usize = 512;
-/// Only load first n chars of name.
-const CBTEXTURENAMEMAX: usize = 13;
-
-/// Default step sound.
-const STEP_CONCRETE: c_int = 0;
-/// Metal floor.
-const STEP_METAL: c_int = 1;
-/// Dirt, sand or rock.
-const STEP_DIRT: c_int = 2;
-/// Ventillation duct.
-const STEP_VENT: c_int = 3;
-/// Metal grating.
-const STEP_GRATE: c_int = 4;
-// Floor tiles.
-const STEP_TILE: c_int = 5;
-/// Shallow liquid puddle.
-const STEP_SLOSH: c_int = 6;
-/// Wading in liquid.
-const STEP_WADE: c_int = 7;
-/// Climbing ladder.
-const STEP_LADDER: c_int = 8;
-
-static TEXTURES: SyncOnceCell<Vec<(u8, CStrArray<CBTEXTURENAMEMAX>)>> =
-    unsafe { SyncOnceCell::new() };
-
-static STUCK_TABLE: SyncOnceCell<[vec3_t; 54]> = unsafe { SyncOnceCell::new() };
-static mut STUCK_LAST: [[c_int; 2]; MAX_CLIENTS] = [[0; 2]; MAX_CLIENTS];
-
-fn trim_ascii_start(s: &[u8]) -> &[u8] {
-    s.iter()
-        .position(|i| !i.is_ascii_whitespace())
-        .map_or(s, |i| &s[i..])
+/// The size of a texture name buffer including the terminating nul.
+///
+/// Only the first `CBTEXTURENAMEMAX - 1` characters of texture names are compared.
+pub const CBTEXTURENAMEMAX: usize = 13;
+
+const WADE_STEP_SOUNDS: [&CStr; 4] = [
+    c"player/pl_wade1.wav",
+    c"player/pl_wade3.wav",
+    c"player/pl_wade2.wav",
+    c"player/pl_wade4.wav",
+];
+
+const LADDER_STEP_SOUNDS: [&CStr; 4] = [
+    c"player/pl_ladder1.wav",
+    c"player/pl_ladder3.wav",
+    c"player/pl_ladder2.wav",
+    c"player/pl_ladder4.wav",
+];
+
+#[derive(Copy, Clone, Debug)]
+enum Step {
+    /// Walking on a surface with the given material.
+    Material(u8),
+    /// Wading in liquid.
+    Wade,
+    /// Climbing ladder.
+    Ladder,
 }
 
-fn map_texture_type_step_type(texture_type: c_char) -> c_int {
-    match texture_type {
-        CHAR_TEX_METAL => STEP_METAL,
-        CHAR_TEX_DIRT => STEP_DIRT,
-        CHAR_TEX_VENT => STEP_VENT,
-        CHAR_TEX_GRATE => STEP_GRATE,
-        CHAR_TEX_TILE => STEP_TILE,
-        CHAR_TEX_SLOSH => STEP_SLOSH,
-        _ => STEP_CONCRETE,
-    }
+/// Used by [player_move], [player_move_init] and [find_texture_type].
+static DEFAULT_CONTEXT: xash3d_shared::cell::Sync<RefCell<Option<PlayerMoveContext>>> =
+    unsafe { xash3d_shared::cell::Sync::new(RefCell::new(None)) };
+
+fn with_default_context<R>(f: impl FnOnce(&mut PlayerMoveContext) -> R) -> R {
+    let mut ctx = DEFAULT_CONTEXT.borrow_mut();
+    f(ctx.get_or_insert_with(|| PlayerMoveContext::new(MAX_PLAYERS)))
 }
 
 fn clip_velocity(input: vec3_t, normal: vec3_t, overbounce: f32) -> (c_int, vec3_t) {
@@ -159,6 +157,61 @@ fn clip_velocity(input: vec3_t, normal: vec3_t, overbounce: f32) -> (c_int, vec3
     (blocked, output)
 }
 
+fn create_stuck_table() -> [vec3_t; 54] {
+    let mut table = [vec3_t::ZERO; 54];
+    let mut idx = 0;
+
+    let i = [-0.125, 0.0, 0.125];
+    for z in i {
+        table[idx] = vec3_t::new(0.0, 0.0, z);
+        idx += 1;
+    }
+    for y in i {
+        table[idx] = vec3_t::new(0.0, y, 0.0);
+        idx += 1;
+    }
+    for x in i {
+        table[idx] = vec3_t::new(x, 0.0, 0.0);
+        idx += 1;
+    }
+
+    let i = [-0.125, 0.125];
+    for x in i {
+        for y in i {
+            for z in i {
+                table[idx] = vec3_t::new(x, y, z);
+                idx += 1;
+            }
+        }
+    }
+
+    let zi = [0.0, 1.0, 6.0];
+    for z in zi {
+        table[idx] = vec3_t::new(0.0, 0.0, z);
+        idx += 1;
+    }
+    let i = [-2.0, 0.0, 2.0];
+    for y in i {
+        table[idx] = vec3_t::new(0.0, y, 0.0);
+        idx += 1;
+    }
+    for x in i {
+        table[idx] = vec3_t::new(x, 0.0, 0.0);
+        idx += 1;
+    }
+
+    for z in zi {
+        for x in i {
+            for y in i {
+                table[idx] = vec3_t::new(x, y, z);
+                idx += 1;
+            }
+        }
+    }
+
+    table
+}
+
 fn spline_fraction(value: f32, scale: f32) -> f32 {
     let value = value * scale;
     let value_squared = value * value;
@@ -182,140 +235,182 @@ impl WishMove {
     }
 }
 
-struct PlayerMove<'a> {
-    raw: &'a mut playermove_s,
-    ladder: bool,
+/// Player movement state kept between frames.
+///
+/// The client and the server can hold their own copies and several simulations can run
+/// side by side, each with its own number of clients.
+pub struct PlayerMoveContext {
+    materials: Arc<Materials>,
+    materials_loaded: bool,
+    stuck_table: [vec3_t; 54],
+    /// The next stuck offset for each client on the client and the server sides.
+    stuck_last: Vec<[c_int; 2]>,
+    /// The last time a stuck check was made for each client on the server and the client
+    /// sides.
+    stuck_check_time: Vec<[f32; 2]>,
+    skip_step: u8,
+    config: MovementConfig,
+    modes: Vec<Box<dyn MoveMode>>,
 }
 
-impl<'a> PlayerMove<'a> {
-    fn new(raw: &'a mut playermove_s) -> Self {
-        Self { raw, ladder: false }
+impl PlayerMoveContext {
+    pub fn new(max_clients: usize) -> Self {
+        Self::with_config(max_clients, MovementConfig::default())
     }
 
-    fn create_stuck_table(&self) {
-        let mut table = [vec3_t::ZERO; 54];
-        let mut idx = 0;
-
-        let i = [-0.125, 0.0, 0.125];
-        for z in i {
-            table[idx] = vec3_t::new(0.0, 0.0, z);
-            idx += 1;
-        }
-        for y in i {
-            table[idx] = vec3_t::new(0.0, y, 0.0);
-            idx += 1;
-        }
-        for x in i {
-            table[idx] = vec3_t::new(x, 0.0, 0.0);
-            idx += 1;
+    pub fn with_config(max_clients: usize, config: MovementConfig) -> Self {
+        Self {
+            materials: Arc::new(Materials::new()),
+            materials_loaded: false,
+            stuck_table: create_stuck_table(),
+            stuck_last: vec![[0; 2]; max_clients],
+            stuck_check_time: vec![[0.0; 2]; max_clients],
+            skip_step: 0,
+            config,
+            modes: Vec::new(),
         }
+    }
 
-        let i = [-0.125, 0.125];
-        for x in i {
-            for y in i {
-                for z in i {
-                    table[idx] = vec3_t::new(x, y, z);
-                    idx += 1;
-                }
-            }
-        }
+    pub fn config(&self) -> &MovementConfig {
+        &self.config
+    }
 
-        let zi = [0.0, 1.0, 6.0];
-        for z in zi {
-            table[idx] = vec3_t::new(0.0, 0.0, z);
-            idx += 1;
-        }
-        let i = [-2.0, 0.0, 2.0];
-        for y in i {
-            table[idx] = vec3_t::new(0.0, y, 0.0);
-            idx += 1;
-        }
-        for x in i {
-            table[idx] = vec3_t::new(x, 0.0, 0.0);
-            idx += 1;
-        }
+    pub fn set_config(&mut self, config: MovementConfig) {
+        self.config = config;
+    }
 
-        for z in zi {
-            for x in i {
-                for y in i {
-                    table[idx] = vec3_t::new(x, y, z);
-                    idx += 1;
-                }
-            }
-        }
+    /// Adds a custom movement mode.
+    ///
+    /// Modes added first have a higher priority.
+    pub fn add_move_mode(&mut self, mode: impl MoveMode + 'static) {
+        self.modes.push(Box::new(mode));
+    }
+
+    pub fn max_clients(&self) -> usize {
+        self.stuck_last.len()
+    }
 
-        if STUCK_TABLE.set(table).is_err() {
-            warn!("stuck table initialized twice");
+    pub fn init(&mut self, pm: &mut playermove_s) {
+        self.init_with_backend(pm, &EngineBackend);
+    }
+
+    pub fn init_with_backend(&mut self, pm: &mut playermove_s, backend: &dyn Backend) {
+        PlayerMove::new(pm, backend, self).init_texture_types();
+    }
+
+    pub fn player_move(&mut self, pm: &mut playermove_s, is_server: bool) {
+        self.player_move_with_backend(pm, &EngineBackend, is_server);
+    }
+
+    pub fn player_move_with_backend(
+        &mut self,
+        pm: &mut playermove_s,
+        backend: &dyn Backend,
+        is_server: bool,
+    ) {
+        let mut pm = PlayerMove::new(pm, backend, self);
+        pm.player_move(is_server);
+        let onground = pm.raw.onground != -1;
+        pm.flags_mut().set(EdictFlags::ONGROUND, onground);
+
+        if pm.is_singleplayer() && pm.raw.movetype == MoveType::Walk as c_int {
+            pm.raw.friction = 1.0;
         }
     }
 
-    fn reset_stuck_offsets(&self) {
-        unsafe {
-            STUCK_LAST[self.raw.player_index as usize][self.is_server() as usize] = 0;
+    pub fn materials(&self) -> &Arc<Materials> {
+        &self.materials
+    }
+
+    /// Replaces the materials, `sound/materials.txt` will not be loaded on init.
+    pub fn set_materials(&mut self, materials: Arc<Materials>) {
+        self.materials = materials;
+        self.materials_loaded = true;
+    }
+
+    pub fn find_texture_type(&self, name: &CStrThin) -> c_char {
+        self.materials.find_type(name.to_bytes()) as c_char
+    }
+}
+
+/// The player movement state for the current frame.
+///
+/// Passed to [MoveMode] implementations.
+pub struct PlayerMove<'a> {
+    raw: &'a mut playermove_s,
+    backend: &'a dyn Backend,
+    ctx: &'a mut PlayerMoveContext,
+    ladder: bool,
+}
+
+impl<'a> PlayerMove<'a> {
+    fn new(
+        raw: &'a mut playermove_s,
+        backend: &'a dyn Backend,
+        ctx: &'a mut PlayerMoveContext,
+    ) -> Self {
+        Self {
+            raw,
+            backend,
+            ctx,
+            ladder: false,
         }
     }
 
-    fn get_random_stuck_offsets(&self) -> (c_int, vec3_t) {
+    pub fn raw(&self) -> &playermove_s {
+        self.raw
+    }
+
+    pub fn raw_mut(&mut self) -> &mut playermove_s {
+        self.raw
+    }
+
+    pub fn config(&self) -> &MovementConfig {
+        &self.ctx.config
+    }
+
+    /// Returns `true` if the player is on a ladder in the current frame.
+    pub fn is_on_ladder(&self) -> bool {
+        self.ladder
+    }
+
+    fn reset_stuck_offsets(&mut self) {
+        let server = self.is_server() as usize;
+        self.ctx.stuck_last[self.raw.player_index as usize][server] = 0;
+    }
+
+    fn get_random_stuck_offsets(&mut self) -> (c_int, vec3_t) {
         let index = self.raw.player_index as usize;
         let server = self.is_server() as usize;
-        let v = unsafe { &mut STUCK_LAST[index][server] };
+        let v = &mut self.ctx.stuck_last[index][server];
         let index = *v % 54;
         *v += 1;
-        let offset = STUCK_TABLE.get().unwrap()[index as usize];
+        let offset = self.ctx.stuck_table[index as usize];
         (index, offset)
     }
 
-    fn init_texture_types(&self) {
-        if TEXTURES.get().is_some() {
+    fn init_texture_types(&mut self) {
+        if self.ctx.materials_loaded {
             return;
         }
 
-        let Some(file) = self.load_file(c"sound/materials.txt", 5) else {
+        let Some(file) = self.load_file(c"sound/materials.txt") else {
             return;
         };
 
-        let mut textures = Vec::with_capacity(CTEXTURESMAX);
-        for line in file.as_slice().split(|&c| c == b'\n') {
-            let (ty, name) = match trim_ascii_start(line).split_first() {
-                Some((&c, tail)) if c.is_ascii_alphabetic() => {
-                    let name = trim_ascii_start(tail);
-                    (c, name.strip_suffix(b"\r").unwrap_or(name))
-                }
-                _ => continue,
-            };
-            if name.is_empty() || name.iter().any(|i| i.is_ascii_whitespace()) {
-                continue;
-            }
-            if textures.len() >= CTEXTURESMAX {
-                break;
-            }
-            let Ok(name) = CStrArray::from_bytes(name) else {
-                continue;
-            };
-            textures.push((ty.to_ascii_uppercase(), name));
-        }
-        textures.sort_by(|(_, a), (_, b)| a.cmp_ignore_case(b));
-        textures.shrink_to_fit();
-        TEXTURES.set(textures).ok();
+        self.ctx.materials = Arc::new(Materials::parse(&file));
+        self.ctx.materials_loaded = true;
     }
 
     fn ladder(&self) -> *const physent_s {
         for pe in &self.raw.moveents[..self.raw.nummoveent as usize] {
-            if pe.model.is_null() {
-                continue;
-            }
-            if self.get_model_type(unsafe { &*pe.model }) != ModelType::Brush {
+            if self.model_type(pe) != Some(ModelType::Brush) {
                 continue;
             }
             if pe.skin != CONTENTS_LADDER {
                 continue;
             }
-
-            let (hull, mut test) = self.hull_for_bsp(pe);
-            let hull = unsafe { &*hull };
-            let num = hull.firstclipnode;
-            test = self.raw.origin - test;
-            if self.hull_point_contents(hull, num, test) != CONTENTS_EMPTY {
+            if self.physent_point_contents(pe, self.raw.origin) != CONTENTS_EMPTY {
                 return pe;
             }
         }
@@ -427,7 +522,7 @@ impl<'a> PlayerMove<'a> {
         trace
     }
 
-    fn check_velocity(&mut self) {
+    pub fn check_velocity(&mut self) {
         fn fix_nan(v: &mut vec3_t, name: &str) {
             if v.is_nan() {
                 debug!("PM  Got a NaN {name} {v:?}");
@@ -479,7 +574,7 @@ impl<'a> PlayerMove<'a> {
                 );
                 vol = 1.0;
             } else if self.raw.flFallVelocity > PLAYER_MAX_SAFE_FALL_SPEED / 2.0 {
-                if let Some(1) = self.info_value_for_key::<i32>(self.physinfo(), c"tfc") {
+                if let Some(1) = self.info_value_for_key::<i32>(self.physinfo(), "tfc") {
                     let s = c"player/pl_fallpain3.wav";
                     self.play_sound(
                         Channel::Voice,
@@ -499,7 +594,7 @@ impl<'a> PlayerMove<'a> {
                 self.raw.flTimeStepSound = 0;
 
                 self.update_step_sound();
-                self.play_step_sound(map_texture_type_step_type(self.raw.chtexturetype), vol);
+                self.play_step_sound(Step::Material(self.raw.chtexturetype as u8), vol);
 
                 self.raw.punchangle[2] = self.raw.flFallVelocity * 0.013;
                 if self.raw.punchangle[0] > 8.0 {
@@ -513,7 +608,7 @@ impl<'a> PlayerMove<'a> {
         }
     }
 
-    fn check_water(&mut self) -> qboolean {
+    pub fn check_water(&mut self) -> qboolean {
         let usehull = self.raw.usehull as usize;
         let max = self.raw.player_maxs[usehull];
         let tmp = vec3_t::new(max[0] * 0.5, max[1] * 0.5, 1.0);
@@ -601,8 +696,6 @@ impl<'a> PlayerMove<'a> {
     fn check_stuck(&mut self) -> c_int {
         const PM_CHECKSTUCK_MINTIME: f32 = 0.05;
 
-        static mut STUCK_CHECK_TIME: [[f32; 2]; MAX_CLIENTS] = [[0.0; 2]; MAX_CLIENTS];
-
         let (hitent, mut trace_result) = self.test_player_position(self.raw.origin);
         if hitent == -1 {
             self.reset_stuck_offsets();
@@ -611,9 +704,8 @@ impl<'a> PlayerMove<'a> {
 
         let base = self.raw.origin;
 
-        if (self.is_client() || self.is_singleplayer())
-            && (hitent == 0 || !self.raw.physents[hitent as usize].model.is_null())
-        {
+        let has_model = |ent: c_int| self.model_type(&self.raw.physents[ent as usize]).is_some();
+        if (self.is_client() || self.is_singleplayer()) && (hitent == 0 || has_model(hitent)) {
             self.reset_stuck_offsets();
             for _ in 0..=54 {
                 let (_, offset) = self.get_random_stuck_offsets();
@@ -632,12 +724,11 @@ impl<'a> PlayerMove<'a> {
         let time = self.system_time_f64();
 
         let player_index = self.raw.player_index as usize;
-        if unsafe { STUCK_CHECK_TIME[player_index][idx] } >= time as f32 - PM_CHECKSTUCK_MINTIME {
+        let check_time = &mut self.ctx.stuck_check_time[player_index][idx];
+        if *check_time >= time as f32 - PM_CHECKSTUCK_MINTIME {
             return 1;
         }
-        unsafe {
-            STUCK_CHECK_TIME[player_index][idx] = time as f32;
-        }
+        *check_time = time as f32;
 
         self.stuck_touch(hitent, &mut trace_result);
 
@@ -682,7 +773,7 @@ impl<'a> PlayerMove<'a> {
         1
     }
 
-    fn friction(&mut self) {
+    pub fn friction(&mut self) {
         if self.raw.waterjumptime != 0.0 {
             return;
         }
@@ -714,7 +805,7 @@ impl<'a> PlayerMove<'a> {
         self.raw.velocity *= fmaxf(speed - drop, 0.0) / speed;
     }
 
-    fn accelerate(&mut self, wishdir: &vec3_t, wishspeed: f32, accel: f32) {
+    pub fn accelerate(&mut self, wishdir: &vec3_t, wishspeed: f32, accel: f32) {
         if self.is_dead() || self.raw.waterjumptime != 0.0 {
             return;
         }
@@ -727,7 +818,7 @@ impl<'a> PlayerMove<'a> {
         }
     }
 
-    fn air_accelerate(&mut self, wishdir: &vec3_t, wishspeed: f32, accel: f32) {
+    pub fn air_accelerate(&mut self, wishdir: &vec3_t, wishspeed: f32, accel: f32) {
         if self.is_dead() || self.raw.waterjumptime != 0.0 {
             return;
         }
@@ -742,7 +833,7 @@ impl<'a> PlayerMove<'a> {
         self.raw.velocity += *wishdir * accel_speed;
     }
 
-    fn catagorize_position(&mut self) {
+    pub fn catagorize_position(&mut self) {
         self.check_water();
 
         let mut point = self.raw.origin;
@@ -789,7 +880,7 @@ impl<'a> PlayerMove<'a> {
             .cursor()
             .write_bytes(name)
             .unwrap();
-        self.raw.chtexturetype = find_texture_type(self.texture_name());
+        self.raw.chtexturetype = self.ctx.find_texture_type(self.texture_name());
     }
 
     fn fix_player_crouch_stuck(&mut self, direction: c_int) {
@@ -816,7 +907,7 @@ impl<'a> PlayerMove<'a> {
         self.check_velocity();
     }
 
-    fn add_correct_gravity(&mut self) {
+    pub fn add_correct_gravity(&mut self) {
         if self.raw.waterjumptime != 0.0 {
             return;
         }
@@ -834,7 +925,7 @@ impl<'a> PlayerMove<'a> {
         self.check_velocity();
     }
 
-    fn fixup_gravity_velocity(&mut self) {
+    pub fn fixup_gravity_velocity(&mut self) {
         if self.raw.waterjumptime != 0.0 {
             return;
         }
@@ -850,7 +941,7 @@ impl<'a> PlayerMove<'a> {
         self.check_velocity();
     }
 
-    fn play_step_sound(&mut self, step: c_int, vol: f32) {
+    fn play_step_sound(&mut self, step: Step, vol: f32) {
         self.raw.iStepLeft = (self.raw.iStepLeft == 0) as c_int;
 
         if self.raw.runfuncs == 0 {
@@ -865,114 +956,39 @@ impl<'a> PlayerMove<'a> {
             }
         }
 
-        let play = |i, samples: &[&CStr]| {
-            self.play_sound(
-                Channel::Body,
-                samples[i as usize],
-                vol,
-                Attenuation::NORM,
-                SoundFlags::NONE,
-                Pitch::NORM,
-            );
+        let skip_step = matches!(step, Step::Wade) && {
+            let n = self.ctx.skip_step;
+            self.ctx.skip_step = if n >= 3 { 0 } else { n + 1 };
+            n == 0
         };
-        let rand = self.random_int(0, 1) + self.raw.iStepLeft * 2;
-
-        match step {
-            STEP_CONCRETE => {
-                let samples = &[
-                    c"player/pl_step1.wav",
-                    c"player/pl_step3.wav",
-                    c"player/pl_step2.wav",
-                    c"player/pl_step4.wav",
-                ];
-                play(rand, samples);
-            }
-            STEP_METAL => {
-                let samples = &[
-                    c"player/pl_metal1.wav",
-                    c"player/pl_metal3.wav",
-                    c"player/pl_metal2.wav",
-                    c"player/pl_metal4.wav",
-                ];
-                play(rand, samples);
-            }
-            STEP_DIRT => {
-                let samples = &[
-                    c"player/pl_dirt1.wav",
-                    c"player/pl_dirt3.wav",
-                    c"player/pl_dirt2.wav",
-                    c"player/pl_dirt4.wav",
-                ];
-                play(rand, samples);
-            }
-            STEP_VENT => {
-                let samples = &[
-                    c"player/pl_duct1.wav",
-                    c"player/pl_duct3.wav",
-                    c"player/pl_duct2.wav",
-                    c"player/pl_duct4.wav",
-                ];
-                play(rand, samples);
-            }
-            STEP_GRATE => {
-                let samples = &[
-                    c"player/pl_grate1.wav",
-                    c"player/pl_grate3.wav",
-                    c"player/pl_grate2.wav",
-                    c"player/pl_grate4.wav",
-                ];
-                play(rand, samples);
-            }
-            STEP_TILE => {
-                let rand = if self.random_int(0, 4) != 0 { 4 } else { rand };
-                let samples = &[
-                    c"player/pl_tile1.wav",
-                    c"player/pl_tile3.wav",
-                    c"player/pl_tile2.wav",
-                    c"player/pl_tile4.wav",
-                    c"player/pl_tile5.wav",
-                ];
-                play(rand, samples);
-            }
-            STEP_SLOSH => {
-                let samples = &[
-                    c"player/pl_slosh1.wav",
-                    c"player/pl_slosh3.wav",
-                    c"player/pl_slosh2.wav",
-                    c"player/pl_slosh4.wav",
-                ];
-                play(rand, samples);
-            }
-            STEP_WADE => {
-                // FIXME: shared global state
-                static SKIP_STEP: AtomicU8 = AtomicU8::new(0);
-                match SKIP_STEP.fetch_add(1, Ordering::Relaxed) {
-                    0 => {}
-                    n => {
-                        if n == 3 {
-                            SKIP_STEP.store(0, Ordering::Relaxed);
-                        }
-                        let samples = &[
-                            c"player/pl_wade1.wav",
-                            c"player/pl_wade3.wav",
-                            c"player/pl_wade2.wav",
-                            c"player/pl_wade4.wav",
-                        ];
-                        play(rand, samples);
-                    }
-                }
-            }
-            STEP_LADDER => {
-                let samples = &[
-                    c"player/pl_ladder1.wav",
-                    c"player/pl_ladder3.wav",
-                    c"player/pl_ladder2.wav",
-                    c"player/pl_ladder4.wav",
-                ];
-                play(rand, samples);
+
+        let rand = (self.random_int(0, 1) + self.raw.iStepLeft * 2) as usize;
+        let sample = match step {
+            Step::Wade if skip_step => return,
+            Step::Wade => WADE_STEP_SOUNDS[rand],
+            Step::Ladder => LADDER_STEP_SOUNDS[rand],
+            Step::Material(id) => {
+                let samples = &self.ctx.materials.get(id).step_sounds;
+                let i = match samples.len() {
+                    0 => return,
+                    n if n > 4 => match self.random_int(0, n as c_int - 1) as usize {
+                        i if i >= 4 => i,
+                        _ => rand,
+                    },
+                    n => rand % n,
+                };
+                samples[i].as_c_str()
             }
-            _ => warn!("unimplemented play sound step({step})"),
-        }
+        };
+
+        self.play_sound(
+            Channel::Body,
+            sample,
+            vol,
+            Attenuation::NORM,
+            SoundFlags::NONE,
+            Pitch::NORM,
+        );
     }
 
     fn play_water_sounds(&mut self) {
@@ -1025,29 +1041,22 @@ impl<'a> PlayerMove<'a> {
         let height = self.raw.player_maxs[usehull][2] - self.raw.player_mins[usehull][2];
         let knee = self.raw.origin - vec3_t::new(0.0, 0.0, 0.3 * height);
         let feet = self.raw.origin - vec3_t::new(0.0, 0.0, 0.5 * height);
-        let volume = |walk, run| if walking { walk } else { run };
-        let time = |walk, run| if walking { walk } else { run };
+        let material = |id: c_char| {
+            let material = self.ctx.materials.get(id as u8);
+            (
+                Step::Material(material.id),
+                material.step_volume(walking),
+                material.step_interval(walking) as c_int,
+            )
+        };
         let (step, mut vol, time_step_sound) = if ladder {
-            (STEP_LADDER, 0.35, 350)
+            (Step::Ladder, 0.35, 350)
         } else if let (CONTENTS_WATER, _) = self.point_contents(knee) {
-            (STEP_WADE, 0.65, 600)
+            (Step::Wade, 0.65, 600)
         } else if let (CONTENTS_WATER, _) = self.point_contents(feet) {
-            (STEP_SLOSH, volume(0.2, 0.5), time(400, 300))
+            material(CHAR_TEX_SLOSH)
         } else {
-            let (vol, time) = match self.raw.chtexturetype {
-                CHAR_TEX_METAL => (volume(0.2, 0.5), time(400, 300)),
-                CHAR_TEX_DIRT => (volume(0.25, 0.55), time(400, 300)),
-                CHAR_TEX_VENT => (volume(0.4, 0.7), time(400, 300)),
-                CHAR_TEX_GRATE => (volume(0.2, 0.5), time(400, 300)),
-                CHAR_TEX_TILE => (volume(0.2, 0.5), time(400, 300)),
-                CHAR_TEX_SLOSH => (volume(0.2, 0.5), time(400, 300)),
-                _ => (volume(0.2, 0.5), time(400, 300)),
-            };
-            (
-                map_texture_type_step_type(self.raw.chtexturetype),
-                vol,
-                time,
-            )
+            material(self.raw.chtexturetype)
         };
         self.raw.flTimeStepSound = time_step_sound + flduck;
 
@@ -1068,18 +1077,18 @@ impl<'a> PlayerMove<'a> {
     // #[no_mangle]
     // static mut vJumpAngles: vec3_t = vec3_t::ZERO;
 
-    fn normalize_angle_vectors(&mut self) {
+    pub fn normalize_angle_vectors(&mut self) {
         self.raw.forward = self.raw.forward.normalize();
         self.raw.right = self.raw.right.normalize();
     }
 
-    fn normalize_angle_vectors_no_z(&mut self) {
+    pub fn normalize_angle_vectors_no_z(&mut self) {
         self.raw.forward[2] = 0.0;
         self.raw.right[2] = 0.0;
         self.normalize_angle_vectors();
     }
 
-    fn wish_vel(&self) -> vec3_t {
+    pub fn wish_vel(&self) -> vec3_t {
         let mut vel = self.raw.forward * self.raw.cmd.forwardmove;
         vel += self.raw.right * self.raw.cmd.sidemove;
         vel[2] += self.raw.cmd.upmove;
@@ -1165,9 +1174,10 @@ impl<'a> PlayerMove<'a> {
         }
 
         if self.flags().contains(Flags::DUCKING) {
-            self.raw.cmd.forwardmove *= PLAYER_DUCKING_MULTIPLIER;
-            self.raw.cmd.sidemove *= PLAYER_DUCKING_MULTIPLIER;
-            self.raw.cmd.upmove *= PLAYER_DUCKING_MULTIPLIER;
+            let multiplier = self.config().ducking_multiplier;
+            self.raw.cmd.forwardmove *= multiplier;
+            self.raw.cmd.sidemove *= multiplier;
+            self.raw.cmd.upmove *= multiplier;
         }
 
         if self.is_button(IN_DUCK) {
@@ -1177,9 +1187,12 @@ impl<'a> PlayerMove<'a> {
             }
 
             if self.raw.bInDuck != 0 {
-                if self.raw.flDuckTime / 1000.0 <= 1.0 - TIME_TO_DUCK || self.raw.onground == -1 {
+                let config = *self.config();
+                if self.raw.flDuckTime / 1000.0 <= 1.0 - config.time_to_duck
+                    || self.raw.onground == -1
+                {
                     self.raw.usehull = 1;
-                    self.raw.view_ofs[2] = DUCK_VIEW_OFFSET.z;
+                    self.raw.view_ofs[2] = config.duck_view_offset.z;
                     self.flags_mut().insert(Flags::DUCKING);
                     self.raw.bInDuck = false.into();
 
@@ -1189,11 +1202,11 @@ impl<'a> PlayerMove<'a> {
                         self.catagorize_position();
                     }
                 } else {
-                    let more = DUCK_HULL_MIN.z - HULL_MIN.z;
+                    let more = config.duck_hull_min.z - config.hull_min.z;
                     let time = fmaxf(0.0, 1.0 - self.raw.flDuckTime / 1000.0);
-                    let duck_fraction = spline_fraction(time, 1.0 / TIME_TO_DUCK);
-                    self.raw.view_ofs[2] = (DUCK_VIEW_OFFSET.z - more) * duck_fraction
-                        + VIEW_OFFSET.z * (1.0 - duck_fraction);
+                    let duck_fraction = spline_fraction(time, 1.0 / config.time_to_duck);
+                    self.raw.view_ofs[2] = (config.duck_view_offset.z - more) * duck_fraction
+                        + config.view_offset.z * (1.0 - duck_fraction);
                 }
             }
         } else if self.raw.bInDuck != 0 || self.flags().contains(Flags::DUCKING) {
@@ -1218,7 +1231,7 @@ impl<'a> PlayerMove<'a> {
 
             self.flags_mut().remove(EdictFlags::DUCKING);
             self.raw.bInDuck = false.into();
-            self.raw.view_ofs[2] = VIEW_OFFSET.z;
+            self.raw.view_ofs[2] = self.config().view_offset.z;
             self.raw.flDuckTime = 0.0;
             self.raw.origin = new_origin;
 
@@ -1236,7 +1249,9 @@ impl<'a> PlayerMove<'a> {
         self.raw.movetype = MoveType::Fly as c_int;
         self.raw.gravity = 0.0;
 
-        let (model_mins, model_maxs) = self.get_model_bounds(unsafe { &*ladder.model });
+        let Some((model_mins, model_maxs)) = self.model_bounds(ladder) else {
+            return;
+        };
         let ladder_center = (model_mins + model_maxs) * 0.5;
         let trace = self.trace_model(ladder, self.raw.origin, ladder_center).0;
         if trace.fraction == 1.0 {
@@ -1247,9 +1262,9 @@ impl<'a> PlayerMove<'a> {
             return;
         }
 
-        let mut speed = fminf(self.raw.maxspeed, MAX_CLIMB_SPEED);
+        let mut speed = fminf(self.raw.maxspeed, self.config().max_climb_speed);
         if self.flags().contains(Flags::DUCKING) {
-            speed *= PLAYER_DUCKING_MULTIPLIER;
+            speed *= self.config().ducking_multiplier;
         }
 
         let mut forward = 0.0;
@@ -1279,7 +1294,7 @@ impl<'a> PlayerMove<'a> {
             floor[2] += self.raw.player_mins[self.usehull()][2] - 1.0;
             let on_floor = self.point_contents(floor).0 == CONTENTS_SOLID;
             if on_floor && normal > 0.0 {
-                self.raw.velocity += trace.plane.normal * MAX_CLIMB_SPEED;
+                self.raw.velocity += trace.plane.normal * self.config().max_climb_speed;
             }
         } else {
             self.raw.velocity = vec3_t::ZERO;
@@ -1359,7 +1374,7 @@ impl<'a> PlayerMove<'a> {
         self.check_water();
     }
 
-    fn fly_move(&mut self) -> c_int {
+    pub fn fly_move(&mut self) -> c_int {
         let mut planes = [vec3_t::ZERO; MAX_CLIP_PLANES];
         let mut blocked = 0;
         let mut numplanes = 0;
@@ -1479,9 +1494,11 @@ impl<'a> PlayerMove<'a> {
     }
 
     fn prevent_mega_bunny_jumping(&mut self) {
-        const BUNNYJUMP_MAX_SPEED_FACTOR: f32 = 1.7;
+        let Some(factor) = self.config().bunnyjump_max_speed_factor else {
+            return;
+        };
 
-        let max_scaled_speed = BUNNYJUMP_MAX_SPEED_FACTOR * self.raw.maxspeed;
+        let max_scaled_speed = factor * self.raw.maxspeed;
         if max_scaled_speed > 0.0 {
             let speed = self.raw.velocity.length();
             if speed > max_scaled_speed {
@@ -1490,13 +1507,13 @@ impl<'a> PlayerMove<'a> {
         }
     }
 
-    fn jump(&mut self) {
+    pub fn jump(&mut self) {
         if self.is_dead() {
             self.raw.oldbuttons |= IN_JUMP as c_int;
             return;
         }
 
-        let tfc = self.info_value_for_key(self.physinfo(), c"tfc");
+        let tfc = self.info_value_for_key(self.physinfo(), "tfc");
         let tfc = tfc == Some(1_i32);
         if tfc && self.raw.deadflag == DEAD_DISCARDBODY + 1 {
             return;
@@ -1562,10 +1579,10 @@ impl<'a> PlayerMove<'a> {
                 Pitch::NORM,
             );
         } else {
-            self.play_step_sound(map_texture_type_step_type(self.raw.chtexturetype), 1.0);
+            self.play_step_sound(Step::Material(self.raw.chtexturetype as u8), 1.0);
         }
 
-        let cansuperjump = self.info_value_for_key::<i32>(self.physinfo(), c"slj");
+        let cansuperjump = self.info_value_for_key::<i32>(self.physinfo(), "slj");
         if self.raw.bInDuck != 0 || self.flags().contains(EdictFlags::DUCKING) {
             if cansuperjump == Some(1)
                 && self.is_button(IN_DUCK)
@@ -1573,7 +1590,7 @@ impl<'a> PlayerMove<'a> {
                 && self.raw.velocity.length() > 50.0
             {
                 self.raw.punchangle[0] = -5.0;
-                self.raw.velocity = self.raw.forward * PLAYER_LONGJUMP_SPEED * 1.6;
+                self.raw.velocity = self.raw.forward * self.config().longjump_speed * 1.6;
                 self.raw.velocity[2] = sqrtf(2.0 * 800.0 * 56.0);
             } else {
                 self.raw.velocity[2] = sqrtf(2.0 * 800.0 * 45.0);
@@ -1587,7 +1604,7 @@ impl<'a> PlayerMove<'a> {
         self.raw.oldbuttons |= IN_JUMP as c_int;
     }
 
-    fn walk_move(&mut self) {
+    pub fn walk_move(&mut self) {
         self.normalize_angle_vectors_no_z();
         let wish = self.wish_move(self.wish_vel().with_z(0.0));
 
@@ -1662,7 +1679,7 @@ impl<'a> PlayerMove<'a> {
         }
     }
 
-    fn air_move(&mut self) {
+    pub fn air_move(&mut self) {
         self.normalize_angle_vectors_no_z();
         let wish = self.wish_move(self.wish_vel().with_z(0.0));
         self.air_accelerate(&wish.dir, wish.speed, self.movevars().airaccelerate);
@@ -1670,7 +1687,7 @@ impl<'a> PlayerMove<'a> {
         self.fly_move();
     }
 
-    fn water_move(&mut self) {
+    pub fn water_move(&mut self) {
         let mut wish = {
             let wishvel = if self.move_vector() != vec3_t::ZERO {
                 self.wish_vel()
@@ -1772,6 +1789,10 @@ impl<'a> PlayerMove<'a> {
 
         self.duck();
 
+        if self.run_move_mode() {
+            return;
+        }
+
         if self.is_alive() && !self.flags().contains(EdictFlags::ONTRAIN) {
             if !ladder.is_null() {
                 self.ladder_move(unsafe { &*ladder });
@@ -1787,113 +1808,125 @@ impl<'a> PlayerMove<'a> {
             MoveType::NoClip => self.no_clip(),
             MoveType::Toss => self.physics_toss(),
             MoveType::Bounce => self.physics_toss(),
-            MoveType::Fly => {
-                self.check_water();
+            MoveType::Fly => self.fly(),
+            MoveType::Walk => self.walk(),
+            _ => {
+                let s = ["client", "server"][is_server as usize];
+                error!("invalid player move type {:?} on {s}", self.raw.movetype);
+            }
+        }
+    }
 
-                if self.is_button(IN_JUMP) {
-                    if ladder.is_null() {
-                        self.jump();
-                    }
-                } else {
-                    self.raw.oldbuttons &= !IN_JUMP as c_int;
-                }
+    /// Runs the first active custom movement mode.
+    fn run_move_mode(&mut self) -> bool {
+        if self.ctx.modes.is_empty() {
+            return false;
+        }
+        let mut modes = mem::take(&mut self.ctx.modes);
+        let active = modes.iter_mut().find(|mode| mode.is_active(self));
+        let found = active.is_some();
+        if let Some(mode) = active {
+            mode.run(self);
+        }
+        self.ctx.modes = modes;
+        found
+    }
 
-                self.raw.velocity += self.raw.basevelocity;
-                self.fly_move();
-                self.raw.velocity -= self.raw.basevelocity;
-            }
-            MoveType::Walk => {
-                if !self.in_water() {
-                    self.add_correct_gravity();
-                }
+    /// The default movement for [MoveType::Fly].
+    pub fn fly(&mut self) {
+        self.check_water();
 
-                if self.raw.waterjumptime != 0.0 {
-                    self.water_jump();
-                    self.fly_move();
+        if self.is_button(IN_JUMP) {
+            if !self.ladder {
+                self.jump();
+            }
+        } else {
+            self.raw.oldbuttons &= !IN_JUMP as c_int;
+        }
 
-                    self.check_water();
-                    return;
-                }
+        self.raw.velocity += self.raw.basevelocity;
+        self.fly_move();
+        self.raw.velocity -= self.raw.basevelocity;
+    }
 
-                if self.raw.waterlevel >= 2 {
-                    if self.raw.waterlevel == 2 {
-                        self.check_water_jump();
-                    }
+    /// The default movement for [MoveType::Walk].
+    pub fn walk(&mut self) {
+        if !self.in_water() {
+            self.add_correct_gravity();
+        }
 
-                    if self.raw.velocity[2] < 0.0 && self.raw.waterjumptime != 0.0 {
-                        self.raw.waterjumptime = 0.0;
-                    }
+        if self.raw.waterjumptime != 0.0 {
+            self.water_jump();
+            self.fly_move();
 
-                    if self.is_button(IN_JUMP) {
-                        self.jump();
-                    } else {
-                        self.raw.oldbuttons &= !IN_JUMP as c_int;
-                    }
+            self.check_water();
+            return;
+        }
 
-                    self.water_move();
-                    self.raw.velocity -= self.raw.basevelocity;
+        if self.raw.waterlevel >= 2 {
+            if self.raw.waterlevel == 2 {
+                self.check_water_jump();
+            }
 
-                    self.catagorize_position();
-                } else {
-                    if self.is_button(IN_JUMP) {
-                        if ladder.is_null() {
-                            self.jump();
-                        }
-                    } else {
-                        self.raw.oldbuttons &= !IN_JUMP as c_int;
-                    }
+            if self.raw.velocity[2] < 0.0 && self.raw.waterjumptime != 0.0 {
+                self.raw.waterjumptime = 0.0;
+            }
 
-                    if self.raw.onground != -1 {
-                        self.raw.velocity[2] = 0.0;
-                        self.friction();
-                    }
+            if self.is_button(IN_JUMP) {
+                self.jump();
+            } else {
+                self.raw.oldbuttons &= !IN_JUMP as c_int;
+            }
 
-                    self.check_velocity();
+            self.water_move();
+            self.raw.velocity -= self.raw.basevelocity;
 
-                    if self.raw.onground != -1 {
-                        self.walk_move();
-                    } else {
-                        self.air_move();
-                    }
+            self.catagorize_position();
+        } else {
+            if self.is_button(IN_JUMP) {
+                if !self.ladder {
+                    self.jump();
+                }
+            } else {
+                self.raw.oldbuttons &= !IN_JUMP as c_int;
+            }
 
-                    self.catagorize_position();
+            if self.raw.onground != -1 {
+                self.raw.velocity[2] = 0.0;
+                self.friction();
+            }
 
-                    self.raw.velocity -= self.raw.basevelocity;
+            self.check_velocity();
 
-                    self.check_velocity();
+            if self.raw.onground != -1 {
+                self.walk_move();
+            } else {
+                self.air_move();
+            }
 
-                    if !self.in_water() {
-                        self.fixup_gravity_velocity();
-                    }
+            self.catagorize_position();
 
-                    if self.raw.onground != -1 {
-                        self.raw.velocity[2] = 0.0;
-                    }
+            self.raw.velocity -= self.raw.basevelocity;
 
-                    self.check_falling();
-                }
+            self.check_velocity();
 
-                self.play_water_sounds();
+            if !self.in_water() {
+                self.fixup_gravity_velocity();
             }
-            _ => {
-                let s = ["client", "server"][is_server as usize];
-                error!("invalid player move type {:?} on {s}", self.raw.movetype);
+
+            if self.raw.onground != -1 {
+                self.raw.velocity[2] = 0.0;
             }
+
+            self.check_falling();
         }
+
+        self.play_water_sounds();
     }
 }
 
 pub fn get_hull_bounds(hullnumber: c_int) -> Option<(vec3_t, vec3_t)> {
-    const NORMAL_PLAYER_HULL: c_int = 0;
-    const CROUCHED_PLAYER_HULL: c_int = 1;
-    const POINT_BASED_HULL: c_int = 2;
-
-    match hullnumber {
-        NORMAL_PLAYER_HULL => Some((HULL_MIN, HULL_MAX)),
-        CROUCHED_PLAYER_HULL => Some((DUCK_HULL_MIN, DUCK_HULL_MAX)),
-        POINT_BASED_HULL => Some((vec3_t::ZERO, vec3_t::ZERO)),
-        _ => None,
-    }
+    with_default_context(|ctx| ctx.config().hull_bounds(hullnumber))
 }
 
 pub fn get_hull_bounds_ffi(hullnumber: c_int, mins: &mut vec3_t, maxs: &mut vec3_t) -> c_int {
@@ -1911,18 +1944,16 @@ pub fn strip_texture_prefix(mut name: &[u8]) -> &[u8] {
     if matches!(name.first(), Some(b'{' | b'!' | b'~' | b' ')) {
         name = &name[1..];
     }
-    if name.len() >= CBTEXTURENAMEMAX {
-        name = &name[..CBTEXTURENAMEMAX - 1];
-    }
-    name
+    materials::truncate_texture_name(name)
 }
 
 pub fn find_texture_type(name: &CStrThin) -> c_char {
-    let textures = TEXTURES.get().unwrap();
-    textures
-        .binary_search_by(|(_, s)| s.cmp_ignore_case(name))
-        .map(|i| textures[i].0 as c_char)
-        .unwrap_or(CHAR_TEX_CONCRETE)
+    with_default_context(|ctx| ctx.find_texture_type(name))
+}
+
+/// Returns the materials used by [player_move].
+pub fn materials() -> Arc<Materials> {
+    with_default_context(|ctx| ctx.materials().clone())
 }
 
 // pub fn get_vis_ent_info(ent: c_int) -> c_int {
@@ -1944,18 +1975,24 @@ pub fn find_texture_type(name: &CStrThin) -> c_char {
 // }
 
 pub fn player_move(pm: &mut playermove_s, is_server: bool) {
-    let mut pm = PlayerMove::new(pm);
-    pm.player_move(is_server);
-    let onground = pm.raw.onground != -1;
-    pm.flags_mut().set(EdictFlags::ONGROUND, onground);
-
-    if pm.is_singleplayer() && pm.raw.movetype == MoveType::Walk as c_int {
-        pm.raw.friction = 1.0;
-    }
+    with_default_context(|ctx| ctx.player_move(pm, is_server));
 }
 
 pub fn player_move_init(pm: &mut playermove_s) {
-    let pm = PlayerMove::new(pm);
-    pm.create_stuck_table();
-    pm.init_texture_types();
+    with_default_context(|ctx| ctx.init(pm));
+}
+
+/// Returns the movement config used by [player_move].
+pub fn movement_config() -> MovementConfig {
+    with_default_context(|ctx| *ctx.config())
+}
+
+/// Sets the movement config used by [player_move].
+pub fn set_movement_config(config: MovementConfig) {
+    with_default_context(|ctx| ctx.set_config(config));
+}
+
+/// Adds a custom movement mode used by [player_move].
+pub fn add_move_mode(mode: impl MoveMode + 'static) {
+    with_default_context(|ctx| ctx.add_move_mode(mode));
 }
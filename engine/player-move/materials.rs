@@ -0,0 +1,564 @@
+//! Surface materials loaded from `sound/materials.txt`.
+//!
+//! Each line of the file maps a texture name to a material letter:
+//!
+//! ```text
+//! // comment
+//! M metal1
+//! M crate*        // all textures with the "crate" prefix
+//! D ?dirt         // any character followed by "dirt"
+//! ```
+//!
+//! Names are matched without case and only the first 12 characters are compared like in
+//! Half-Life. Exact names take priority over wildcard patterns, patterns are checked in the
+//! order they are listed.
+//!
+//! Mods can define new material letters or change built-in ones with `$material`:
+//!
+//! ```text
+//! $material N step=player/pl_snow1.wav,player/pl_snow2.wav step_volume=0.3,0.6
+//! N snow*
+//! ```
+//!
+//! Supported keys:
+//!
+//! * `step` — step sounds, the first four are picked by the foot, the rest randomly;
+//! * `step_volume` — step volume for walking and running;
+//! * `step_interval` — time between steps in milliseconds for walking and running;
+//! * `impact` — bullet impact sounds;
+//! * `impact_volume` — bullet impact volume;
+//! * `impact_volume_bar` — bullet impact volume heard by the attacker.
+//!
+//! Unset keys are copied from the existing material or from concrete.
+
+use core::{cmp::Ordering, ffi::CStr, str};
+
+use alloc::{ffi::CString, vec, vec::Vec};
+
+use crate::{
+    CBTEXTURENAMEMAX, CHAR_TEX_COMPUTER, CHAR_TEX_CONCRETE, CHAR_TEX_DIRT, CHAR_TEX_FLESH,
+    CHAR_TEX_GLASS, CHAR_TEX_GRATE, CHAR_TEX_METAL, CHAR_TEX_SLOSH, CHAR_TEX_TILE, CHAR_TEX_VENT,
+    CHAR_TEX_WOOD,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    /// The material letter.
+    pub id: u8,
+    pub step_sounds: Vec<CString>,
+    pub step_volume_walk: f32,
+    pub step_volume_run: f32,
+    /// Time in milliseconds between steps when walking.
+    pub step_interval_walk: u16,
+    /// Time in milliseconds between steps when running.
+    pub step_interval_run: u16,
+    pub impact_sounds: Vec<CString>,
+    pub impact_volume: f32,
+    /// The volume of the impact sound played to the attacker.
+    pub impact_volume_bar: f32,
+}
+
+impl Material {
+    fn builtin(
+        id: u8,
+        step_sounds: &[&CStr],
+        step_volume: (f32, f32),
+        impact_sounds: &[&CStr],
+        impact_volume: (f32, f32),
+    ) -> Self {
+        Self {
+            id,
+            step_sounds: step_sounds.iter().map(|&i| i.into()).collect(),
+            step_volume_walk: step_volume.0,
+            step_volume_run: step_volume.1,
+            step_interval_walk: 400,
+            step_interval_run: 300,
+            impact_sounds: impact_sounds.iter().map(|&i| i.into()).collect(),
+            impact_volume: impact_volume.0,
+            impact_volume_bar: impact_volume.1,
+        }
+    }
+
+    pub fn step_volume(&self, walking: bool) -> f32 {
+        if walking {
+            self.step_volume_walk
+        } else {
+            self.step_volume_run
+        }
+    }
+
+    pub fn step_interval(&self, walking: bool) -> u16 {
+        if walking {
+            self.step_interval_walk
+        } else {
+            self.step_interval_run
+        }
+    }
+
+    fn set_key(&mut self, key: &str, value: &str) -> bool {
+        fn sounds(value: &str) -> Vec<CString> {
+            value
+                .split(',')
+                .filter(|i| !i.is_empty())
+                .filter_map(|i| CString::new(i).ok())
+                .collect()
+        }
+
+        fn pair<T: str::FromStr + Copy>(value: &str) -> Option<(T, T)> {
+            match value.split_once(',') {
+                Some((a, b)) => Some((a.parse().ok()?, b.parse().ok()?)),
+                None => value.parse().ok().map(|v| (v, v)),
+            }
+        }
+
+        match key {
+            "step" => self.step_sounds = sounds(value),
+            "step_volume" => {
+                let Some((walk, run)) = pair(value) else {
+                    return false;
+                };
+                self.step_volume_walk = walk;
+                self.step_volume_run = run;
+            }
+            "step_interval" => {
+                let Some((walk, run)) = pair(value) else {
+                    return false;
+                };
+                self.step_interval_walk = walk;
+                self.step_interval_run = run;
+            }
+            "impact" => self.impact_sounds = sounds(value),
+            "impact_volume" => match value.parse() {
+                Ok(v) => self.impact_volume = v,
+                Err(_) => return false,
+            },
+            "impact_volume_bar" => match value.parse() {
+                Ok(v) => self.impact_volume_bar = v,
+                Err(_) => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// A registry of surface materials and the textures that use them.
+#[derive(Clone, Debug)]
+pub struct Materials {
+    /// Sorted by id.
+    materials: Vec<Material>,
+    /// Sorted by name without case.
+    names: Vec<(Vec<u8>, u8)>,
+    /// In the order they were added.
+    patterns: Vec<(Vec<u8>, u8)>,
+}
+
+impl Default for Materials {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Materials {
+    /// Creates a registry with Half-Life materials and no textures.
+    pub fn new() -> Self {
+        let concrete = &[
+            c"player/pl_step1.wav",
+            c"player/pl_step3.wav",
+            c"player/pl_step2.wav",
+            c"player/pl_step4.wav",
+        ];
+        let mut materials = vec![
+            Material::builtin(
+                CHAR_TEX_CONCRETE as u8,
+                concrete,
+                (0.2, 0.5),
+                &[c"player/pl_step1.wav", c"player/pl_step2.wav"],
+                (0.9, 0.6),
+            ),
+            Material::builtin(
+                CHAR_TEX_METAL as u8,
+                &[
+                    c"player/pl_metal1.wav",
+                    c"player/pl_metal3.wav",
+                    c"player/pl_metal2.wav",
+                    c"player/pl_metal4.wav",
+                ],
+                (0.2, 0.5),
+                &[c"player/pl_metal1.wav", c"player/pl_metal2.wav"],
+                (0.9, 0.3),
+            ),
+            Material::builtin(
+                CHAR_TEX_DIRT as u8,
+                &[
+                    c"player/pl_dirt1.wav",
+                    c"player/pl_dirt3.wav",
+                    c"player/pl_dirt2.wav",
+                    c"player/pl_dirt4.wav",
+                ],
+                (0.25, 0.55),
+                &[
+                    c"player/pl_dirt1.wav",
+                    c"player/pl_dirt2.wav",
+                    c"player/pl_dirt3.wav",
+                ],
+                (0.9, 0.1),
+            ),
+            Material::builtin(
+                CHAR_TEX_VENT as u8,
+                &[
+                    c"player/pl_duct1.wav",
+                    c"player/pl_duct3.wav",
+                    c"player/pl_duct2.wav",
+                    c"player/pl_duct4.wav",
+                ],
+                (0.4, 0.7),
+                &[c"player/pl_duct1.wav", c"player/pl_duct2.wav"],
+                (0.5, 0.3),
+            ),
+            Material::builtin(
+                CHAR_TEX_GRATE as u8,
+                &[
+                    c"player/pl_grate1.wav",
+                    c"player/pl_grate3.wav",
+                    c"player/pl_grate2.wav",
+                    c"player/pl_grate4.wav",
+                ],
+                (0.2, 0.5),
+                &[c"player/pl_grate1.wav", c"player/pl_grate4.wav"],
+                (0.9, 0.5),
+            ),
+            Material::builtin(
+                CHAR_TEX_TILE as u8,
+                &[
+                    c"player/pl_tile1.wav",
+                    c"player/pl_tile3.wav",
+                    c"player/pl_tile2.wav",
+                    c"player/pl_tile4.wav",
+                    c"player/pl_tile5.wav",
+                ],
+                (0.2, 0.5),
+                &[
+                    c"player/pl_tile1.wav",
+                    c"player/pl_tile3.wav",
+                    c"player/pl_tile2.wav",
+                    c"player/pl_tile4.wav",
+                ],
+                (0.8, 0.2),
+            ),
+            Material::builtin(
+                CHAR_TEX_SLOSH as u8,
+                &[
+                    c"player/pl_slosh1.wav",
+                    c"player/pl_slosh3.wav",
+                    c"player/pl_slosh2.wav",
+                    c"player/pl_slosh4.wav",
+                ],
+                (0.2, 0.5),
+                &[
+                    c"player/pl_slosh1.wav",
+                    c"player/pl_slosh3.wav",
+                    c"player/pl_slosh2.wav",
+                    c"player/pl_slosh4.wav",
+                ],
+                (0.9, 0.0),
+            ),
+            Material::builtin(
+                CHAR_TEX_WOOD as u8,
+                concrete,
+                (0.2, 0.5),
+                &[
+                    c"debris/wood1.wav",
+                    c"debris/wood2.wav",
+                    c"debris/wood3.wav",
+                ],
+                (0.9, 0.2),
+            ),
+        ];
+        let glass = &[
+            c"debris/glass1.wav",
+            c"debris/glass1.wav",
+            c"debris/glass2.wav",
+            c"debris/glass3.wav",
+        ];
+        for id in [CHAR_TEX_GLASS, CHAR_TEX_COMPUTER] {
+            materials.push(Material::builtin(
+                id as u8,
+                concrete,
+                (0.2, 0.5),
+                glass,
+                (0.8, 0.2),
+            ));
+        }
+        materials.push(Material::builtin(
+            CHAR_TEX_FLESH as u8,
+            concrete,
+            (0.2, 0.5),
+            &[c"weapons/bullet_hit1.wav", c"weapons/bullet_hit2.wav"],
+            (1.0, 0.2),
+        ));
+        materials.sort_by_key(|i| i.id);
+
+        Self {
+            materials,
+            names: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Creates a registry with Half-Life materials and textures from `materials.txt`.
+    pub fn parse(data: &[u8]) -> Self {
+        let mut materials = Self::new();
+        materials.parse_append(data);
+        materials
+    }
+
+    /// Adds materials and textures from `materials.txt`.
+    pub fn parse_append(&mut self, data: &[u8]) {
+        for line in data.split(|&c| c == b'\n') {
+            let Ok(line) = str::from_utf8(line) else {
+                continue;
+            };
+            let line = line.trim();
+            if let Some(tail) = line.strip_prefix("$material") {
+                self.parse_material(tail);
+                continue;
+            }
+            let mut chars = line.chars();
+            let (Some(id), Some(tail)) = (chars.next(), chars.as_str().split_whitespace().next())
+            else {
+                continue;
+            };
+            if !id.is_ascii_alphabetic() || !line[1..].starts_with(char::is_whitespace) {
+                continue;
+            }
+            self.add_texture(tail.as_bytes(), id as u8);
+        }
+    }
+
+    fn parse_material(&mut self, line: &str) {
+        let mut tokens = line.split_whitespace();
+        let id = match tokens.next().map(str::as_bytes) {
+            Some(&[id]) if id.is_ascii_alphabetic() => id.to_ascii_uppercase(),
+            _ => {
+                warn!("materials: invalid material letter in \"$material{line}\"");
+                return;
+            }
+        };
+        let mut material = self.get(id).clone();
+        material.id = id;
+        for token in tokens {
+            if token.starts_with("//") {
+                break;
+            }
+            let valid = match token.split_once('=') {
+                Some((key, value)) => material.set_key(key, value),
+                None => false,
+            };
+            if !valid {
+                warn!(
+                    "materials: invalid key \"{token}\" for material {}",
+                    id as char
+                );
+            }
+        }
+        self.insert(material);
+    }
+
+    /// Adds or replaces a material.
+    pub fn insert(&mut self, material: Material) {
+        match self.materials.binary_search_by_key(&material.id, |i| i.id) {
+            Ok(i) => self.materials[i] = material,
+            Err(i) => self.materials.insert(i, material),
+        }
+    }
+
+    /// Maps a texture name to a material.
+    ///
+    /// Names with `*` or `?` are wildcard patterns.
+    pub fn add_texture(&mut self, name: &[u8], id: u8) {
+        let id = id.to_ascii_uppercase();
+        let name = name.to_ascii_lowercase();
+        if name.iter().any(|&c| c == b'*' || c == b'?') {
+            self.patterns.push((name, id));
+            return;
+        }
+        let name = truncate_texture_name(&name).to_vec();
+        match self
+            .names
+            .binary_search_by(|(i, _)| i.as_slice().cmp(&name))
+        {
+            Ok(i) => self.names[i].1 = id,
+            Err(i) => self.names.insert(i, (name, id)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Material> {
+        self.materials.iter()
+    }
+
+    pub fn contains(&self, id: u8) -> bool {
+        self.materials.binary_search_by_key(&id, |i| i.id).is_ok()
+    }
+
+    /// Returns the material for the letter or concrete if it is not defined.
+    pub fn get(&self, id: u8) -> &Material {
+        let find = |id| {
+            self.materials
+                .binary_search_by_key(&id, |i: &Material| i.id)
+        };
+        let i = find(id)
+            .or_else(|_| find(CHAR_TEX_CONCRETE as u8))
+            .unwrap_or(0);
+        &self.materials[i]
+    }
+
+    /// Returns the material letter for the texture or concrete if the texture is unknown.
+    pub fn find_type(&self, texture_name: &[u8]) -> u8 {
+        let texture_name = truncate_texture_name(texture_name);
+        let exact = self
+            .names
+            .binary_search_by(|(i, _)| cmp_ignore_case(i, texture_name))
+            .map(|i| self.names[i].1);
+        exact
+            .ok()
+            .or_else(|| {
+                self.patterns
+                    .iter()
+                    .find(|(pattern, _)| wildcard_match(pattern, texture_name))
+                    .map(|(_, id)| *id)
+            })
+            .unwrap_or(CHAR_TEX_CONCRETE as u8)
+    }
+
+    /// Returns the material for the texture.
+    pub fn find(&self, texture_name: &[u8]) -> &Material {
+        self.get(self.find_type(texture_name))
+    }
+}
+
+/// Returns the part of the texture name that is compared with material names.
+pub fn truncate_texture_name(name: &[u8]) -> &[u8] {
+    &name[..name.len().min(CBTEXTURENAMEMAX - 1)]
+}
+
+/// Compares lowercase `a` with `b` without case.
+fn cmp_ignore_case(a: &[u8], b: &[u8]) -> Ordering {
+    a.iter()
+        .copied()
+        .cmp(b.iter().map(|c| c.to_ascii_lowercase()))
+}
+
+/// Matches lowercase `pattern` with `*` and `?` wildcards against `name` without case.
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        let c = name[n].to_ascii_lowercase();
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&i) if i == b'?' || i == c => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard() {
+        assert!(wildcard_match(b"crate*", b"CRATE1"));
+        assert!(wildcard_match(b"crate*", b"crate"));
+        assert!(wildcard_match(b"*metal*", b"c1a0_METALFLOOR"));
+        assert!(wildcard_match(b"?dirt", b"xdirt"));
+        assert!(!wildcard_match(b"?dirt", b"dirt"));
+        assert!(!wildcard_match(b"crate*", b"bigcrate"));
+        assert!(wildcard_match(b"a*b*c", b"aXbYbZc"));
+        assert!(!wildcard_match(b"a*b*c", b"aXbYbZ"));
+    }
+
+    #[test]
+    fn parse() {
+        let materials = Materials::parse(
+            b"// comment\r\n\
+              M metal1\r\n\
+              m crate*\n\
+              D ?dirt // trailing comment\n\
+              V averylongtexturename_vent\n\
+              1 invalid\n\
+              Tbad\n",
+        );
+        assert_eq!(materials.find_type(b"METAL1"), b'M');
+        assert_eq!(materials.find_type(b"crate02"), b'M');
+        assert_eq!(materials.find_type(b"xdirt"), b'D');
+        assert_eq!(materials.find_type(b"averylongtexturename_vent"), b'V');
+        assert_eq!(materials.find_type(b"averylongtexturename"), b'V');
+        assert_eq!(materials.find_type(b"averylongtex"), b'V');
+        assert_eq!(materials.find_type(b"averylongte"), b'C');
+        assert_eq!(materials.find_type(b"invalid"), b'C');
+        assert_eq!(materials.find_type(b"bad"), b'C');
+    }
+
+    #[test]
+    fn long_texture_name() {
+        // 15 characters, only the first 12 are compared
+        let materials = Materials::parse(b"G c1a0_labwall_01\n");
+        assert_eq!(materials.find_type(b"C1A0_LABWALL_01"), b'G');
+        assert_eq!(materials.find_type(b"c1a0_labwall_02"), b'G');
+        assert_eq!(materials.find_type(b"c1a0_labwall"), b'G');
+        assert_eq!(materials.find_type(b"c1a0_labwal"), b'C');
+        assert_eq!(
+            crate::strip_texture_prefix(b"{c1a0_labwall_01"),
+            b"c1a0_labwall"
+        );
+    }
+
+    #[test]
+    fn exact_before_pattern() {
+        let materials = Materials::parse(b"W wood*\nG woodgrate\n");
+        assert_eq!(materials.find_type(b"woodgrate"), b'G');
+        assert_eq!(materials.find_type(b"woodfloor"), b'W');
+    }
+
+    #[test]
+    fn custom_material() {
+        let materials = Materials::parse(
+            b"$material n step=player/snow1.wav,player/snow2.wav step_volume=0.3,0.6 step_interval=500\n\
+              $material M impact_volume=0.5\n\
+              N snow*\n",
+        );
+        let snow = materials.find(b"snow_ground");
+        assert_eq!(snow.id, b'N');
+        assert_eq!(snow.step_sounds.len(), 2);
+        assert_eq!(snow.step_sounds[1].as_c_str(), c"player/snow2.wav");
+        assert_eq!((snow.step_volume_walk, snow.step_volume_run), (0.3, 0.6));
+        assert_eq!(
+            (snow.step_interval_walk, snow.step_interval_run),
+            (500, 500)
+        );
+        let concrete = materials.get(CHAR_TEX_CONCRETE as u8);
+        assert_eq!(snow.impact_sounds, concrete.impact_sounds);
+
+        let metal = materials.get(CHAR_TEX_METAL as u8);
+        assert_eq!(metal.impact_volume, 0.5);
+        assert_eq!(metal.impact_volume_bar, 0.3);
+
+        assert_eq!(materials.get(b'X').id, CHAR_TEX_CONCRETE as u8);
+    }
+}
@@ -5,26 +5,26 @@
 #![allow(clippy::type_complexity)]
 
 use core::{
-    ffi::{CStr, c_char, c_int, c_void},
+    ffi::{CStr, c_int, c_void},
     mem::{self, MaybeUninit},
-    slice,
+    slice, str,
     str::FromStr,
 };
 
+use alloc::vec::Vec;
 use xash3d_shared::{
     csz::{CStrSlice, CStrThin},
     entity::{EdictFlags, MoveType},
     ffi::{
-        common::{hull_s, model_s, movevars_s, pmtrace_s, trace_t, vec3_t},
+        common::{hull_s, movevars_s, pmtrace_s, trace_t, vec3_t},
         player_move::{physent_s, playermove_s},
     },
     macros::const_assert_size_of_field_eq,
     model::ModelType,
     sound::{Attenuation, Channel, Pitch, SoundFlags},
-    str::{AsCStrPtr, ToEngineStr},
 };
 
-use crate::PlayerMove;
+use crate::{PlayerMove, backend::Backend};
 
 pub struct MemFile {
     data: *mut u8,
@@ -138,32 +138,34 @@ impl PlayerMove<'_> {
 }
 
 macro_rules! pm_unwrap {
-    ($self:expr, $name:ident) => {
-        match $self.raw.$name {
+    ($pm:expr, $name:ident) => {
+        match $pm.$name {
             Some(func) => func,
             None => panic!("playermove_s.{} is null", stringify!($name)),
         }
     };
 }
 
-#[allow(dead_code)]
-impl PlayerMove<'_> {
-    pub fn system_time_f64(&self) -> f64 {
-        unsafe { pm_unwrap!(self, Sys_FloatTime)() }
+/// Uses engine functions stored in [playermove_s].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EngineBackend;
+
+impl Backend for EngineBackend {
+    fn system_time_f64(&self, pm: &playermove_s) -> f64 {
+        unsafe { pm_unwrap!(pm, Sys_FloatTime)() }
     }
 
-    pub fn random_int(&self, min: c_int, max: c_int) -> c_int {
-        assert!(min >= 0, "min must be greater than or equal to zero");
-        assert!(min <= max, "min must be less than or equal to max");
-        unsafe { pm_unwrap!(self, RandomLong)(min, max) }
+    fn random_int(&self, pm: &playermove_s, min: c_int, max: c_int) -> c_int {
+        unsafe { pm_unwrap!(pm, RandomLong)(min, max) }
     }
 
-    pub fn random_float(&self, min: f32, max: f32) -> f32 {
-        unsafe { pm_unwrap!(self, RandomFloat)(min, max) }
+    fn random_float(&self, pm: &playermove_s, min: f32, max: f32) -> f32 {
+        unsafe { pm_unwrap!(pm, RandomFloat)(min, max) }
     }
 
-    pub fn play_sound(
+    fn play_sound(
         &self,
+        pm: &playermove_s,
         channel: Channel,
         sample: &CStr,
         volume: f32,
@@ -172,7 +174,7 @@ impl PlayerMove<'_> {
         pitch: Pitch,
     ) {
         unsafe {
-            pm_unwrap!(self, PM_PlaySound)(
+            pm_unwrap!(pm, PM_PlaySound)(
                 channel.into(),
                 sample.as_ptr(),
                 volume,
@@ -183,12 +185,18 @@ impl PlayerMove<'_> {
         }
     }
 
-    pub fn trace_texture(&self, ground: bool, start: vec3_t, end: vec3_t) -> Option<&'static CStr> {
+    fn trace_texture(
+        &self,
+        pm: &playermove_s,
+        ground: bool,
+        start: vec3_t,
+        end: vec3_t,
+    ) -> Option<&CStr> {
         let mut start = start;
         let mut end = end;
         unsafe {
             // FIXME: ffi: why start and end are mutable?
-            let p = pm_unwrap!(self, PM_TraceTexture)(
+            let p = pm_unwrap!(pm, PM_TraceTexture)(
                 ground.into(),
                 start.as_mut().as_mut_ptr(),
                 end.as_mut().as_mut_ptr(),
@@ -201,12 +209,12 @@ impl PlayerMove<'_> {
         }
     }
 
-    pub fn point_contents(&self, point: vec3_t) -> (c_int, c_int) {
+    fn point_contents(&self, pm: &playermove_s, point: vec3_t) -> (c_int, c_int) {
         let mut point = point;
         unsafe {
             let mut truecont = MaybeUninit::uninit();
             // FIXME: ffi: why point is mutable?
-            let cont = pm_unwrap!(self, PM_PointContents)(
+            let cont = pm_unwrap!(pm, PM_PointContents)(
                 point.as_mut().as_mut_ptr(),
                 truecont.as_mut_ptr(),
             );
@@ -214,37 +222,39 @@ impl PlayerMove<'_> {
         }
     }
 
-    pub fn hull_point_contents(&self, hull: &hull_s, num: c_int, test: vec3_t) -> c_int {
-        let mut hull = *hull;
-        let mut test = test;
-        // FIXME: ffi: why hull and test are mutable?
+    fn physent_point_contents(&self, pm: &playermove_s, pe: &physent_s, point: vec3_t) -> c_int {
+        let pe = pe as *const physent_s;
+        // FIXME: ffi: why pe, hull and test are mutable?
         unsafe {
-            pm_unwrap!(self, PM_HullPointContents)(&mut hull, num, test.as_mut().as_mut_ptr())
+            let mut offset = MaybeUninit::<vec3_t>::uninit();
+            let hull = pm_unwrap!(pm, PM_HullForBsp)(pe.cast_mut(), offset.as_mut_ptr().cast());
+            let hull = hull.cast::<hull_s>();
+            let num = (*hull).firstclipnode;
+            let mut test = point - offset.assume_init();
+            pm_unwrap!(pm, PM_HullPointContents)(hull, num, test.as_mut().as_mut_ptr())
         }
     }
 
-    pub fn file_size(&self, path: &CStr) -> c_int {
-        unsafe { pm_unwrap!(self, COM_FileSize)(path.as_ptr()) }
-    }
-
-    pub fn load_file(&self, path: &CStr, usehunk: c_int) -> Option<MemFile> {
+    fn load_file(&self, pm: &playermove_s, path: &CStr) -> Option<Vec<u8>> {
         unsafe {
             let mut len = MaybeUninit::uninit();
-            let data = pm_unwrap!(self, COM_LoadFile)(path.as_ptr(), usehunk, len.as_mut_ptr());
+            let data = pm_unwrap!(pm, COM_LoadFile)(path.as_ptr(), 5, len.as_mut_ptr());
             if !data.is_null() {
-                Some(MemFile {
+                let file = MemFile {
                     data,
                     len: len.assume_init() as usize,
-                    free: self.raw.COM_FreeFile.unwrap(),
-                })
+                    free: pm.COM_FreeFile.unwrap(),
+                };
+                Some(file.as_slice().to_vec())
             } else {
                 None
             }
         }
     }
 
-    pub fn player_trace(
+    fn player_trace(
         &self,
+        pm: &playermove_s,
         start: vec3_t,
         end: vec3_t,
         flags: c_int,
@@ -254,7 +264,7 @@ impl PlayerMove<'_> {
         let mut end = end;
         // FIXME: ffi: why start and end are mutable?
         unsafe {
-            pm_unwrap!(self, PM_PlayerTrace)(
+            pm_unwrap!(pm, PM_PlayerTrace)(
                 start.as_mut().as_mut_ptr(),
                 end.as_mut().as_mut_ptr(),
                 flags,
@@ -263,12 +273,12 @@ impl PlayerMove<'_> {
         }
     }
 
-    pub fn test_player_position(&self, point: vec3_t) -> (c_int, pmtrace_s) {
+    fn test_player_position(&self, pm: &playermove_s, point: vec3_t) -> (c_int, pmtrace_s) {
         let mut point = point;
         // FIXME: ffi: why point is mutable?
         unsafe {
             let mut trace = MaybeUninit::uninit();
-            let hitent = pm_unwrap!(self, PM_TestPlayerPosition)(
+            let hitent = pm_unwrap!(pm, PM_TestPlayerPosition)(
                 point.as_mut().as_mut_ptr(),
                 trace.as_mut_ptr(),
             );
@@ -276,84 +286,170 @@ impl PlayerMove<'_> {
         }
     }
 
-    pub fn get_model_type(&self, model: &model_s) -> ModelType {
-        let model = model as *const model_s;
-        // FIXME: ffi: why model is mutable?
-        let raw = unsafe { pm_unwrap!(self, PM_GetModelType)(model.cast_mut()) };
-        ModelType::from_raw(raw).unwrap()
+    fn model_type(&self, pm: &playermove_s, pe: &physent_s) -> Option<ModelType> {
+        if pe.model.is_null() {
+            return None;
+        }
+        let raw = unsafe { pm_unwrap!(pm, PM_GetModelType)(pe.model) };
+        ModelType::from_raw(raw)
     }
 
-    pub fn get_model_bounds(&self, model: &model_s) -> (vec3_t, vec3_t) {
-        let model = model as *const model_s;
-        // FIXME: ffi: why model is mutable?
+    fn model_bounds(&self, pm: &playermove_s, pe: &physent_s) -> Option<(vec3_t, vec3_t)> {
+        if pe.model.is_null() {
+            return None;
+        }
         unsafe {
             let mut min = MaybeUninit::<vec3_t>::uninit();
             let mut max = MaybeUninit::<vec3_t>::uninit();
-            pm_unwrap!(self, PM_GetModelBounds)(
-                model.cast_mut(),
+            pm_unwrap!(pm, PM_GetModelBounds)(
+                pe.model,
                 min.as_mut_ptr().cast(),
                 max.as_mut_ptr().cast(),
             );
-            (min.assume_init(), max.assume_init())
-        }
-    }
-
-    pub fn hull_for_bsp(&self, pe: &physent_s) -> (*mut hull_s, vec3_t) {
-        let pe = pe as *const physent_s;
-        // FIXME: ffi: why pe is mutable?
-        unsafe {
-            let mut vec = MaybeUninit::<vec3_t>::uninit();
-            let hull = pm_unwrap!(self, PM_HullForBsp)(pe.cast_mut(), vec.as_mut_ptr().cast());
-            (hull.cast(), vec.assume_init())
+            Some((min.assume_init(), max.assume_init()))
         }
     }
 
-    pub fn trace_model(&self, pe: &physent_s, start: vec3_t, end: vec3_t) -> (trace_t, f32) {
+    fn trace_model(
+        &self,
+        pm: &playermove_s,
+        pe: &physent_s,
+        start: vec3_t,
+        end: vec3_t,
+    ) -> trace_t {
         let pe = pe as *const physent_s;
         let mut start = start;
         let mut end = end;
         // FIXME: ffi: why pe, start and end are mutable?
         unsafe {
             let mut trace = MaybeUninit::uninit();
-            let ret = pm_unwrap!(self, PM_TraceModel)(
+            pm_unwrap!(pm, PM_TraceModel)(
                 pe.cast_mut(),
                 start.as_mut().as_mut_ptr(),
                 end.as_mut().as_mut_ptr(),
                 trace.as_mut_ptr(),
             );
-            (trace.assume_init(), ret)
+            trace.assume_init()
+        }
+    }
+
+    fn stuck_touch(&self, pm: &playermove_s, hitent: c_int, trace: &mut pmtrace_s) {
+        unsafe {
+            pm_unwrap!(pm, PM_StuckTouch)(hitent, trace);
         }
     }
 
-    pub fn info_value_for_key_raw(
+    fn particle(
         &self,
-        physinfo: impl ToEngineStr,
-        key: impl ToEngineStr,
-    ) -> *const c_char {
-        let physinfo = physinfo.to_engine_str();
-        let key = key.to_engine_str();
-        unsafe { pm_unwrap!(self, PM_Info_ValueForKey)(physinfo.as_ptr(), key.as_ptr()) }
+        pm: &playermove_s,
+        origin: vec3_t,
+        color: c_int,
+        life: f32,
+        zpos: c_int,
+        zvel: c_int,
+    ) {
+        unsafe {
+            pm_unwrap!(pm, PM_Particle)(origin.as_ref().as_ptr(), color, life, zpos, zvel);
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<'a> PlayerMove<'a> {
+    pub fn system_time_f64(&self) -> f64 {
+        self.backend.system_time_f64(self.raw)
     }
 
-    pub fn info_value_for_key<T: FromStr>(
+    pub fn random_int(&self, min: c_int, max: c_int) -> c_int {
+        assert!(min >= 0, "min must be greater than or equal to zero");
+        assert!(min <= max, "min must be less than or equal to max");
+        self.backend.random_int(self.raw, min, max)
+    }
+
+    pub fn random_float(&self, min: f32, max: f32) -> f32 {
+        self.backend.random_float(self.raw, min, max)
+    }
+
+    pub fn play_sound(
         &self,
-        physinfo: impl ToEngineStr,
-        key: impl ToEngineStr,
-    ) -> Option<T> {
-        let raw = self.info_value_for_key_raw(physinfo, key);
-        let s = unsafe { CStr::from_ptr(raw) }.to_str().ok()?;
-        s.parse::<T>().ok()
+        channel: Channel,
+        sample: &CStr,
+        volume: f32,
+        attenuation: Attenuation,
+        flags: SoundFlags,
+        pitch: Pitch,
+    ) {
+        let backend = self.backend;
+        backend.play_sound(self.raw, channel, sample, volume, attenuation, flags, pitch);
+    }
+
+    pub fn trace_texture(&self, ground: bool, start: vec3_t, end: vec3_t) -> Option<&'a CStr> {
+        let backend = self.backend;
+        backend.trace_texture(self.raw, ground, start, end)
+    }
+
+    pub fn point_contents(&self, point: vec3_t) -> (c_int, c_int) {
+        self.backend.point_contents(self.raw, point)
+    }
+
+    pub fn physent_point_contents(&self, pe: &physent_s, point: vec3_t) -> c_int {
+        self.backend.physent_point_contents(self.raw, pe, point)
+    }
+
+    pub fn load_file(&self, path: &CStr) -> Option<Vec<u8>> {
+        self.backend.load_file(self.raw, path)
+    }
+
+    pub fn player_trace(
+        &self,
+        start: vec3_t,
+        end: vec3_t,
+        flags: c_int,
+        ignore_pe: c_int,
+    ) -> pmtrace_s {
+        self.backend
+            .player_trace(self.raw, start, end, flags, ignore_pe)
+    }
+
+    pub fn test_player_position(&self, point: vec3_t) -> (c_int, pmtrace_s) {
+        self.backend.test_player_position(self.raw, point)
+    }
+
+    pub fn model_type(&self, pe: &physent_s) -> Option<ModelType> {
+        self.backend.model_type(self.raw, pe)
+    }
+
+    pub fn model_bounds(&self, pe: &physent_s) -> Option<(vec3_t, vec3_t)> {
+        self.backend.model_bounds(self.raw, pe)
+    }
+
+    pub fn trace_model(&self, pe: &physent_s, start: vec3_t, end: vec3_t) -> trace_t {
+        self.backend.trace_model(self.raw, pe, start, end)
+    }
+
+    pub fn info_value_for_key<T: FromStr>(&self, info: &CStrThin, key: &str) -> Option<T> {
+        let value = info_value_for_key(info.to_bytes(), key.as_bytes())?;
+        str::from_utf8(value).ok()?.parse::<T>().ok()
     }
 
     pub fn stuck_touch(&self, hitent: c_int, trace_result: &mut pmtrace_s) {
-        unsafe {
-            pm_unwrap!(self, PM_StuckTouch)(hitent, trace_result);
-        }
+        self.backend.stuck_touch(self.raw, hitent, trace_result);
     }
 
     pub fn particle(&self, origin: vec3_t, color: c_int, life: f32, zpos: c_int, zvel: c_int) {
-        unsafe {
-            pm_unwrap!(self, PM_Particle)(origin.as_ref().as_ptr(), color, life, zpos, zvel);
+        let backend = self.backend;
+        backend.particle(self.raw, origin, color, life, zpos, zvel);
+    }
+}
+
+/// Returns a value for the key from an info string in the form `\key1\value1\key2\value2`.
+fn info_value_for_key<'a>(info: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+    let info = info.strip_prefix(b"\\").unwrap_or(info);
+    let mut iter = info.split(|&c| c == b'\\');
+    while let (Some(k), Some(v)) = (iter.next(), iter.next()) {
+        if k == key {
+            return Some(v);
         }
     }
+    None
 }
@@ -0,0 +1,993 @@
+//! Headless player movement simulation.
+//!
+//! The [World] is made of axis-aligned boxes and implements [Backend] in pure Rust, so
+//! [PlayerMoveContext::player_move_with_backend] can be driven by scripted [usercmd_s]
+//! sequences without the engine.
+
+use core::{
+    cell::{Cell, RefCell},
+    ffi::{CStr, c_int},
+    mem,
+};
+
+use std::{boxed::Box, ffi::CString, vec::Vec};
+
+use xash3d_shared::{
+    consts::{
+        CONTENTS_CURRENT_0, CONTENTS_CURRENT_DOWN, CONTENTS_EMPTY, CONTENTS_LADDER, CONTENTS_SOLID,
+        CONTENTS_WATER, PM_WORLD_ONLY,
+    },
+    entity::MoveType,
+    ffi::{
+        common::{movevars_s, pmtrace_s, trace_t, usercmd_s, vec3_t},
+        player_move::{physent_s, playermove_s},
+    },
+    model::ModelType,
+    sound::{Attenuation, Channel, Pitch, SoundFlags},
+};
+
+use crate::{Backend, PlayerMoveContext};
+
+/// The distance the traces stop before a surface.
+const DIST_EPSILON: f32 = 1.0 / 32.0;
+
+const LARGE_HULL_MIN: vec3_t = vec3_t::new(-32.0, -32.0, -32.0);
+const LARGE_HULL_MAX: vec3_t = vec3_t::new(32.0, 32.0, 32.0);
+
+/// An axis-aligned box with contents.
+#[derive(Clone, Debug)]
+pub struct Brush {
+    pub mins: vec3_t,
+    pub maxs: vec3_t,
+    pub contents: c_int,
+    pub texture: Option<CString>,
+}
+
+impl Brush {
+    pub fn new(mins: vec3_t, maxs: vec3_t, contents: c_int) -> Self {
+        Self {
+            mins,
+            maxs,
+            contents,
+            texture: None,
+        }
+    }
+
+    pub fn solid(mins: vec3_t, maxs: vec3_t) -> Self {
+        Self::new(mins, maxs, CONTENTS_SOLID)
+    }
+
+    pub fn water(mins: vec3_t, maxs: vec3_t) -> Self {
+        Self::new(mins, maxs, CONTENTS_WATER)
+    }
+
+    /// Sets the texture name returned for traces hitting this brush.
+    pub fn with_texture(mut self, name: &str) -> Self {
+        self.texture = CString::new(name).ok();
+        self
+    }
+
+    fn is_solid(&self) -> bool {
+        self.contents == CONTENTS_SOLID
+    }
+
+    fn contains(&self, point: vec3_t) -> bool {
+        (0..3).all(|i| self.mins[i] < point[i] && point[i] < self.maxs[i])
+    }
+
+    /// Returns the brush grown by the player hull size.
+    fn expand(&self, mins: vec3_t, maxs: vec3_t) -> Self {
+        Self {
+            mins: self.mins - maxs,
+            maxs: self.maxs - mins,
+            contents: self.contents,
+            texture: None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Hit {
+    fraction: f32,
+    normal: vec3_t,
+    startsolid: bool,
+    allsolid: bool,
+}
+
+impl Hit {
+    const NONE: Self = Self {
+        fraction: 1.0,
+        normal: vec3_t::ZERO,
+        startsolid: false,
+        allsolid: false,
+    };
+
+    fn merge(&mut self, other: Hit) {
+        self.startsolid |= other.startsolid;
+        self.allsolid |= other.allsolid;
+        if other.fraction < self.fraction {
+            self.fraction = other.fraction;
+            self.normal = other.normal;
+        }
+    }
+}
+
+/// Sweeps a point from `start` to `end` against the box.
+fn trace_box(brush: &Brush, start: vec3_t, end: vec3_t) -> Hit {
+    if brush.contains(start) {
+        let allsolid = brush.contains(end);
+        return Hit {
+            fraction: if allsolid { 0.0 } else { 1.0 },
+            normal: vec3_t::ZERO,
+            startsolid: true,
+            allsolid,
+        };
+    }
+
+    let dir = end - start;
+    let mut enter = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut axis = 0;
+    for i in 0..3 {
+        if dir[i] == 0.0 {
+            if start[i] <= brush.mins[i] || start[i] >= brush.maxs[i] {
+                return Hit::NONE;
+            }
+            continue;
+        }
+        let t0 = (brush.mins[i] - start[i]) / dir[i];
+        let t1 = (brush.maxs[i] - start[i]) / dir[i];
+        let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+        if near > enter {
+            enter = near;
+            axis = i;
+        }
+        if far < exit {
+            exit = far;
+        }
+    }
+
+    if enter >= exit || enter >= 1.0 || exit <= 0.0 || enter < 0.0 {
+        return Hit::NONE;
+    }
+
+    let mut normal = vec3_t::ZERO;
+    normal[axis] = if dir[axis] > 0.0 { -1.0 } else { 1.0 };
+    let fraction = enter - DIST_EPSILON / dir[axis].abs();
+    Hit {
+        fraction: fraction.clamp(0.0, 1.0),
+        normal,
+        startsolid: false,
+        allsolid: false,
+    }
+}
+
+/// A brush model placed in the world.
+#[derive(Clone, Debug, Default)]
+pub struct Model {
+    pub origin: vec3_t,
+    /// Brushes are expanded to the size of the current player hull.
+    pub brushes: Vec<Brush>,
+    /// Precomputed clipping boxes for each hull, used as is.
+    pub hulls: [Vec<Brush>; 4],
+    /// The contents of a non-solid brush entity, for example [CONTENTS_LADDER].
+    pub skin: c_int,
+}
+
+impl Model {
+    pub fn new(brushes: Vec<Brush>) -> Self {
+        Self {
+            brushes,
+            ..Self::default()
+        }
+    }
+
+    fn hull_brushes(
+        &self,
+        hull: usize,
+        mins: vec3_t,
+        maxs: vec3_t,
+    ) -> impl Iterator<Item = Brush> + '_ {
+        let expanded = self.brushes.iter().map(move |i| i.expand(mins, maxs));
+        expanded.chain(self.hulls[hull].iter().cloned())
+    }
+
+    fn trace(&self, hull: usize, mins: vec3_t, maxs: vec3_t, start: vec3_t, end: vec3_t) -> Hit {
+        let start = start - self.origin;
+        let end = end - self.origin;
+        let mut hit = Hit::NONE;
+        for brush in self.hull_brushes(hull, mins, maxs).filter(|i| i.is_solid()) {
+            hit.merge(trace_box(&brush, start, end));
+        }
+        hit
+    }
+
+    fn bounds(&self) -> (vec3_t, vec3_t) {
+        let mut iter = self.brushes.iter();
+        let Some(first) = iter.next() else {
+            return (vec3_t::ZERO, vec3_t::ZERO);
+        };
+        iter.fold((first.mins, first.maxs), |(mins, maxs), i| {
+            (mins.min(i.mins), maxs.max(i.maxs))
+        })
+    }
+}
+
+/// A sound played by the player movement code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sound {
+    pub channel: Channel,
+    pub sample: CString,
+    pub volume: f32,
+}
+
+/// A collision world made of axis-aligned brushes.
+///
+/// The first model is the world, solid models are linked as physents and non-solid ones
+/// (ladders, water volumes) as moveents.
+pub struct World {
+    models: Vec<Model>,
+    files: Vec<(CString, Vec<u8>)>,
+    time: Cell<f64>,
+    seed: Cell<u32>,
+    sounds: RefCell<Vec<Sound>>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            models: vec![Model::default()],
+            files: Vec::new(),
+            time: Cell::new(0.0),
+            seed: Cell::new(0x2545_f491),
+            sounds: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Adds a brush to the world model.
+    pub fn add_brush(&mut self, brush: Brush) -> &mut Self {
+        self.models[0].brushes.push(brush);
+        self
+    }
+
+    /// Adds a precomputed clipping box to the world model.
+    pub fn add_hull_box(&mut self, hull: usize, brush: Brush) -> &mut Self {
+        self.models[0].hulls[hull].push(brush);
+        self
+    }
+
+    /// Adds a brush entity and returns its model index.
+    pub fn add_model(&mut self, model: Model) -> usize {
+        self.models.push(model);
+        self.models.len() - 1
+    }
+
+    /// Adds a `func_ladder` entity.
+    pub fn add_ladder(&mut self, mins: vec3_t, maxs: vec3_t) -> usize {
+        self.add_model(Model {
+            skin: CONTENTS_LADDER,
+            ..Model::new(vec![Brush::solid(mins, maxs)])
+        })
+    }
+
+    /// Adds a file available through [Backend::load_file].
+    pub fn add_file(&mut self, path: &str, data: &[u8]) -> &mut Self {
+        self.files
+            .push((CString::new(path).unwrap(), data.to_vec()));
+        self
+    }
+
+    pub fn model(&self, index: usize) -> &Model {
+        &self.models[index]
+    }
+
+    pub fn model_mut(&mut self, index: usize) -> &mut Model {
+        &mut self.models[index]
+    }
+
+    /// Returns sounds played since the last call.
+    pub fn take_sounds(&self) -> Vec<Sound> {
+        mem::take(&mut *self.sounds.borrow_mut())
+    }
+
+    fn pe_model(&self, pe: &physent_s) -> Option<&Model> {
+        self.models.get(usize::try_from(pe.info).ok()?)
+    }
+
+    fn link(&self, pm: &mut playermove_s) {
+        let mut numphysent = 0;
+        let mut nummoveent = 0;
+        for (index, model) in self.models.iter().enumerate() {
+            let pe = if model.skin == 0 {
+                numphysent += 1;
+                &mut pm.physents[numphysent - 1]
+            } else {
+                nummoveent += 1;
+                &mut pm.moveents[nummoveent - 1]
+            };
+            *pe = unsafe { mem::zeroed() };
+            pe.info = index as c_int;
+            pe.origin = model.origin;
+            pe.skin = model.skin;
+        }
+        pm.numphysent = numphysent as c_int;
+        pm.nummoveent = nummoveent as c_int;
+    }
+
+    fn player_hull(&self, pm: &playermove_s) -> (usize, vec3_t, vec3_t) {
+        let hull = pm.usehull as usize;
+        (hull, pm.player_mins[hull], pm.player_maxs[hull])
+    }
+
+    fn trace(
+        &self,
+        pm: &playermove_s,
+        start: vec3_t,
+        end: vec3_t,
+        flags: c_int,
+        ignore_pe: c_int,
+    ) -> (c_int, Hit) {
+        let (hull, mins, maxs) = self.player_hull(pm);
+        let mut ent = -1;
+        let mut hit = Hit::NONE;
+        for (i, pe) in pm.physents[..pm.numphysent as usize].iter().enumerate() {
+            if i as c_int == ignore_pe || (i != 0 && flags & PM_WORLD_ONLY as c_int != 0) {
+                continue;
+            }
+            let Some(model) = self.pe_model(pe) else {
+                continue;
+            };
+            let h = model.trace(hull, mins, maxs, start, end);
+            if h.fraction < hit.fraction || (h.startsolid && ent == -1) {
+                ent = i as c_int;
+            }
+            hit.merge(h);
+        }
+        (ent, hit)
+    }
+
+    fn contents(&self, model: &Model, point: vec3_t) -> c_int {
+        let point = point - model.origin;
+        let mut contents = CONTENTS_EMPTY;
+        for brush in model.brushes.iter().filter(|i| i.contains(point)) {
+            if brush.is_solid() {
+                return CONTENTS_SOLID;
+            }
+            contents = brush.contents;
+        }
+        contents
+    }
+}
+
+fn pmtrace(ent: c_int, hit: Hit, start: vec3_t, end: vec3_t) -> pmtrace_s {
+    let mut trace: pmtrace_s = unsafe { mem::zeroed() };
+    trace.allsolid = hit.allsolid.into();
+    trace.startsolid = hit.startsolid.into();
+    trace.inopen = (!hit.allsolid).into();
+    trace.fraction = hit.fraction;
+    trace.endpos = start + (end - start) * hit.fraction;
+    trace.plane.normal = hit.normal;
+    trace.plane.dist = hit.normal.dot(trace.endpos);
+    trace.ent = if hit.fraction < 1.0 || hit.startsolid {
+        ent
+    } else {
+        -1
+    };
+    trace
+}
+
+impl Backend for World {
+    fn system_time_f64(&self, _: &playermove_s) -> f64 {
+        self.time.get()
+    }
+
+    fn random_int(&self, _: &playermove_s, min: c_int, max: c_int) -> c_int {
+        // xorshift32
+        let mut x = self.seed.get();
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.seed.set(x);
+        min + (x % (max - min + 1) as u32) as c_int
+    }
+
+    fn random_float(&self, pm: &playermove_s, min: f32, max: f32) -> f32 {
+        let x = self.random_int(pm, 0, 0x7fff) as f32 / 0x7fff as f32;
+        min + (max - min) * x
+    }
+
+    fn play_sound(
+        &self,
+        _: &playermove_s,
+        channel: Channel,
+        sample: &CStr,
+        volume: f32,
+        _: Attenuation,
+        _: SoundFlags,
+        _: Pitch,
+    ) {
+        self.sounds.borrow_mut().push(Sound {
+            channel,
+            sample: sample.into(),
+            volume,
+        });
+    }
+
+    fn trace_texture(
+        &self,
+        pm: &playermove_s,
+        _: bool,
+        start: vec3_t,
+        end: vec3_t,
+    ) -> Option<&CStr> {
+        let mut result = None;
+        let mut fraction = 1.0;
+        for pe in &pm.physents[..pm.numphysent as usize] {
+            let Some(model) = self.pe_model(pe) else {
+                continue;
+            };
+            for brush in model.brushes.iter().filter(|i| i.is_solid()) {
+                let hit = trace_box(brush, start - model.origin, end - model.origin);
+                if hit.fraction < fraction {
+                    fraction = hit.fraction;
+                    result = brush.texture.as_deref();
+                }
+            }
+        }
+        result
+    }
+
+    fn point_contents(&self, pm: &playermove_s, point: vec3_t) -> (c_int, c_int) {
+        let mut truecont = self.contents(&self.models[0], point);
+        if truecont == CONTENTS_EMPTY {
+            for pe in &pm.moveents[..pm.nummoveent as usize] {
+                if pe.skin == CONTENTS_LADDER {
+                    continue;
+                }
+                if let Some(model) = self.pe_model(pe) {
+                    if self.contents(model, point) != CONTENTS_EMPTY {
+                        truecont = pe.skin;
+                        break;
+                    }
+                }
+            }
+        }
+        let cont = if (CONTENTS_CURRENT_DOWN..=CONTENTS_CURRENT_0).contains(&truecont) {
+            CONTENTS_WATER
+        } else {
+            truecont
+        };
+        (cont, truecont)
+    }
+
+    fn physent_point_contents(&self, pm: &playermove_s, pe: &physent_s, point: vec3_t) -> c_int {
+        let Some(model) = self.pe_model(pe) else {
+            return CONTENTS_EMPTY;
+        };
+        let (hull, mins, maxs) = self.player_hull(pm);
+        let point = point - pe.origin;
+        if model
+            .hull_brushes(hull, mins, maxs)
+            .any(|i| i.contains(point))
+        {
+            CONTENTS_SOLID
+        } else {
+            CONTENTS_EMPTY
+        }
+    }
+
+    fn load_file(&self, _: &playermove_s, path: &CStr) -> Option<Vec<u8>> {
+        self.files
+            .iter()
+            .find(|(name, _)| name.as_c_str() == path)
+            .map(|(_, data)| data.clone())
+    }
+
+    fn player_trace(
+        &self,
+        pm: &playermove_s,
+        start: vec3_t,
+        end: vec3_t,
+        flags: c_int,
+        ignore_pe: c_int,
+    ) -> pmtrace_s {
+        let (ent, hit) = self.trace(pm, start, end, flags, ignore_pe);
+        pmtrace(ent, hit, start, end)
+    }
+
+    fn test_player_position(&self, pm: &playermove_s, point: vec3_t) -> (c_int, pmtrace_s) {
+        let (ent, hit) = self.trace(pm, point, point, 0, -1);
+        let trace = pmtrace(ent, hit, point, point);
+        if hit.startsolid {
+            (ent, trace)
+        } else {
+            (-1, trace)
+        }
+    }
+
+    fn model_type(&self, _: &playermove_s, pe: &physent_s) -> Option<ModelType> {
+        self.pe_model(pe).map(|_| ModelType::Brush)
+    }
+
+    fn model_bounds(&self, _: &playermove_s, pe: &physent_s) -> Option<(vec3_t, vec3_t)> {
+        self.pe_model(pe).map(|model| model.bounds())
+    }
+
+    fn trace_model(&self, _: &playermove_s, pe: &physent_s, start: vec3_t, end: vec3_t) -> trace_t {
+        let hit = match self.pe_model(pe) {
+            Some(model) => model.trace(0, vec3_t::ZERO, vec3_t::ZERO, start, end),
+            None => Hit::NONE,
+        };
+        let mut trace: trace_t = unsafe { mem::zeroed() };
+        trace.allsolid = hit.allsolid.into();
+        trace.startsolid = hit.startsolid.into();
+        trace.fraction = hit.fraction;
+        trace.endpos = start + (end - start) * hit.fraction;
+        trace.plane.normal = hit.normal;
+        trace
+    }
+
+    fn stuck_touch(&self, _: &playermove_s, _: c_int, _: &mut pmtrace_s) {}
+
+    fn particle(&self, _: &playermove_s, _: vec3_t, _: c_int, _: f32, _: c_int, _: c_int) {}
+}
+
+/// A single player moving through a [World].
+pub struct Simulation {
+    world: World,
+    ctx: PlayerMoveContext,
+    movevars: Box<movevars_s>,
+    pm: Box<playermove_s>,
+}
+
+impl Simulation {
+    pub fn new(world: World) -> Self {
+        Self::with_max_clients(world, 1)
+    }
+
+    pub fn with_max_clients(world: World, max_clients: usize) -> Self {
+        Self::with_context(world, PlayerMoveContext::new(max_clients))
+    }
+
+    /// Creates a simulation with a custom movement config and modes.
+    pub fn with_context(world: World, mut ctx: PlayerMoveContext) -> Self {
+        let config = *ctx.config();
+        let mut movevars: Box<movevars_s> = Box::new(unsafe { mem::zeroed() });
+        movevars.gravity = 800.0;
+        movevars.stopspeed = 100.0;
+        movevars.maxspeed = 320.0;
+        movevars.spectatormaxspeed = 500.0;
+        movevars.accelerate = 10.0;
+        movevars.airaccelerate = 10.0;
+        movevars.wateraccelerate = 10.0;
+        movevars.friction = 4.0;
+        movevars.edgefriction = 2.0;
+        movevars.waterfriction = 1.0;
+        movevars.entgravity = 1.0;
+        movevars.bounce = 1.0;
+        movevars.stepsize = 18.0;
+        movevars.maxvelocity = 2000.0;
+        movevars.footsteps = true.into();
+
+        let mut pm: Box<playermove_s> = unsafe { Box::new_zeroed().assume_init() };
+        pm.movevars = &mut *movevars;
+        pm.player_mins = [
+            config.hull_min,
+            config.duck_hull_min,
+            vec3_t::ZERO,
+            LARGE_HULL_MIN,
+        ];
+        pm.player_maxs = [
+            config.hull_max,
+            config.duck_hull_max,
+            vec3_t::ZERO,
+            LARGE_HULL_MAX,
+        ];
+        pm.movetype = MoveType::Walk as c_int;
+        pm.friction = 1.0;
+        pm.gravity = 1.0;
+        pm.view_ofs = config.view_offset;
+        pm.onground = -1;
+        pm.runfuncs = true.into();
+
+        world.link(&mut pm);
+        ctx.init_with_backend(&mut pm, &world);
+
+        Self {
+            world,
+            ctx,
+            movevars,
+            pm,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The world must be relinked with [Simulation::link] after changes.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn movevars_mut(&mut self) -> &mut movevars_s {
+        &mut self.movevars
+    }
+
+    pub fn pm(&self) -> &playermove_s {
+        &self.pm
+    }
+
+    pub fn pm_mut(&mut self) -> &mut playermove_s {
+        &mut self.pm
+    }
+
+    pub fn origin(&self) -> vec3_t {
+        self.pm.origin
+    }
+
+    pub fn set_origin(&mut self, origin: vec3_t) {
+        self.pm.origin = origin;
+    }
+
+    pub fn set_player_index(&mut self, index: usize) {
+        assert!(
+            index < self.ctx.max_clients(),
+            "invalid player index {index}"
+        );
+        self.pm.player_index = index as c_int;
+    }
+
+    pub fn link(&mut self) {
+        self.world.link(&mut self.pm);
+    }
+
+    /// Runs a single movement frame on the server side.
+    pub fn run(&mut self, cmd: &usercmd_s) {
+        let pm = &mut *self.pm;
+        pm.cmd = *cmd;
+        pm.maxspeed = self.movevars.maxspeed;
+        pm.oldangles = pm.angles;
+        self.ctx.player_move_with_backend(pm, &self.world, true);
+        let time = self.world.time.get() + cmd.msec as f64 * 0.001;
+        self.world.time.set(time);
+    }
+
+    /// Runs the same command for the given number of frames.
+    pub fn run_frames(&mut self, cmd: &usercmd_s, frames: usize) {
+        for _ in 0..frames {
+            self.run(cmd);
+        }
+    }
+}
+
+/// Returns a command with the given frame time in milliseconds.
+pub fn usercmd(msec: u8) -> usercmd_s {
+    let mut cmd: usercmd_s = unsafe { mem::zeroed() };
+    cmd.msec = msec;
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use xash3d_shared::{
+        consts::{IN_DUCK, IN_FORWARD, IN_JUMP},
+        entity::EdictFlags,
+    };
+
+    use super::*;
+    use crate::{MoveMode, MovementConfig, PlayerMove};
+
+    fn v(x: f32, y: f32, z: f32) -> vec3_t {
+        vec3_t::new(x, y, z)
+    }
+
+    /// A big room with the floor at zero height.
+    fn room() -> World {
+        let mut world = World::new();
+        world
+            .add_brush(
+                Brush::solid(v(-1024.0, -1024.0, -16.0), v(1024.0, 1024.0, 0.0))
+                    .with_texture("floor"),
+            )
+            .add_brush(Brush::solid(
+                v(-1024.0, -1024.0, 512.0),
+                v(1024.0, 1024.0, 528.0),
+            ))
+            .add_brush(Brush::solid(
+                v(512.0, -1024.0, 0.0),
+                v(528.0, 1024.0, 512.0),
+            ));
+        world
+    }
+
+    fn forward(msec: u8) -> usercmd_s {
+        let mut cmd = usercmd(msec);
+        cmd.forwardmove = 400.0;
+        cmd.buttons = IN_FORWARD as u16;
+        cmd
+    }
+
+    fn on_ground(sim: &Simulation) -> bool {
+        sim.pm().onground != -1
+    }
+
+    fn spawn(world: World, origin: vec3_t) -> Simulation {
+        let mut sim = Simulation::new(world);
+        sim.set_origin(origin);
+        sim.run(&usercmd(10));
+        sim
+    }
+
+    #[test]
+    fn stand_on_floor() {
+        let mut sim = spawn(room(), v(0.0, 0.0, 36.0));
+        sim.run_frames(&usercmd(10), 50);
+        assert!(on_ground(&sim));
+        assert!((sim.origin().z - 36.0).abs() < 0.1, "{:?}", sim.origin());
+    }
+
+    #[test]
+    fn fall_to_floor() {
+        let mut sim = spawn(room(), v(0.0, 0.0, 100.0));
+        assert!(!on_ground(&sim));
+        sim.run_frames(&usercmd(10), 100);
+        assert!(on_ground(&sim));
+        assert!((sim.origin().z - 36.0).abs() < 0.1, "{:?}", sim.origin());
+    }
+
+    #[test]
+    fn walk() {
+        let mut sim = spawn(room(), v(0.0, 0.0, 36.0));
+        sim.run_frames(&forward(10), 50);
+        let origin = sim.origin();
+        assert!(origin.x > 100.0, "{origin:?}");
+        assert!(origin.y.abs() < 0.1, "{origin:?}");
+        assert!(on_ground(&sim));
+        assert!(sim.pm().velocity.x <= 320.0);
+
+        let sounds = sim.world().take_sounds();
+        assert!(sounds.iter().any(|i| i.channel == Channel::Body));
+    }
+
+    #[test]
+    fn step_sounds() {
+        let step_sounds = |materials: &[u8]| {
+            let mut world = room();
+            world.add_file("sound/materials.txt", materials);
+            let mut sim = spawn(world, v(0.0, 0.0, 36.0));
+            sim.run_frames(&forward(10), 100);
+            sim.world()
+                .take_sounds()
+                .into_iter()
+                .filter(|i| i.channel == Channel::Body)
+                .map(|i| i.sample)
+                .collect::<Vec<_>>()
+        };
+
+        let sounds = step_sounds(b"M FLO*\n");
+        assert!(!sounds.is_empty());
+        for i in &sounds {
+            assert!(i.to_bytes().starts_with(b"player/pl_metal"), "{i:?}");
+        }
+
+        let sounds = step_sounds(b"$material N step=snow1.wav step_volume=0.1\nN floor\n");
+        assert!(!sounds.is_empty());
+        assert!(sounds.iter().all(|i| i.as_c_str() == c"snow1.wav"));
+    }
+
+    #[test]
+    fn walk_into_wall() {
+        let mut sim = spawn(room(), v(400.0, 0.0, 36.0));
+        sim.run_frames(&forward(10), 100);
+        let origin = sim.origin();
+        assert!(origin.x <= 512.0 - 16.0, "{origin:?}");
+        assert!(origin.x > 490.0, "{origin:?}");
+        assert!(sim.pm().velocity.x.abs() < 1.0);
+    }
+
+    #[test]
+    fn step_up() {
+        let mut world = room();
+        world.add_brush(Brush::solid(v(64.0, -128.0, 0.0), v(256.0, 128.0, 16.0)));
+        let mut sim = spawn(world, v(0.0, 0.0, 36.0));
+        sim.run_frames(&forward(10), 50);
+        let origin = sim.origin();
+        assert!(origin.x > 64.0, "{origin:?}");
+        assert!((origin.z - 52.0).abs() < 0.1, "{origin:?}");
+    }
+
+    #[test]
+    fn jump() {
+        let mut sim = spawn(room(), v(0.0, 0.0, 36.0));
+        let mut cmd = usercmd(10);
+        cmd.buttons = IN_JUMP as u16;
+        sim.run(&cmd);
+        assert!(!on_ground(&sim));
+        assert!(sim.pm().velocity.z > 0.0);
+
+        let mut max = 0.0_f32;
+        for _ in 0..100 {
+            sim.run(&cmd);
+            max = max.max(sim.origin().z);
+        }
+        assert!(max > 36.0 + 40.0, "{max}");
+        assert!(on_ground(&sim));
+
+        // holding the jump button does not jump again
+        sim.run_frames(&cmd, 10);
+        assert!(on_ground(&sim));
+    }
+
+    #[test]
+    fn duck() {
+        let mut sim = spawn(room(), v(0.0, 0.0, 36.0));
+        let mut cmd = usercmd(10);
+        cmd.buttons = IN_DUCK as u16;
+        sim.run_frames(&cmd, 50);
+        assert_eq!(sim.pm().usehull, 1);
+        assert!(sim.pm().flags & EdictFlags::DUCKING.bits() != 0);
+        assert!((sim.origin().z - 18.0).abs() < 0.1, "{:?}", sim.origin());
+
+        sim.run_frames(&usercmd(10), 10);
+        assert_eq!(sim.pm().usehull, 0);
+        assert!((sim.origin().z - 36.0).abs() < 0.1, "{:?}", sim.origin());
+    }
+
+    #[test]
+    fn stay_ducked_under_ceiling() {
+        let mut world = room();
+        world.add_brush(Brush::solid(v(32.0, -128.0, 48.0), v(256.0, 128.0, 64.0)));
+        let mut sim = spawn(world, v(0.0, 0.0, 36.0));
+        let mut cmd = forward(10);
+        cmd.buttons |= IN_DUCK as u16;
+        sim.run_frames(&cmd, 150);
+        assert!(sim.origin().x > 64.0, "{:?}", sim.origin());
+        sim.run_frames(&usercmd(10), 10);
+        assert_eq!(sim.pm().usehull, 1);
+    }
+
+    #[test]
+    fn ladder() {
+        let mut world = room();
+        world.add_brush(Brush::solid(v(48.0, -64.0, 0.0), v(64.0, 64.0, 512.0)));
+        world.add_ladder(v(44.0, -32.0, 0.0), v(48.0, 32.0, 400.0));
+        let mut sim = spawn(world, v(0.0, 0.0, 36.0));
+        sim.run_frames(&forward(10), 100);
+        assert_eq!(sim.pm().movetype, MoveType::Fly as c_int);
+        assert!(sim.origin().z > 150.0, "{:?}", sim.origin());
+    }
+
+    #[test]
+    fn water() {
+        let mut world = room();
+        world.add_brush(Brush::water(v(-512.0, -512.0, 0.0), v(512.0, 512.0, 256.0)));
+        let mut sim = spawn(world, v(0.0, 0.0, 200.0));
+        sim.run_frames(&usercmd(10), 20);
+        assert_eq!(sim.pm().waterlevel, 3);
+        assert_eq!(sim.pm().watertype, CONTENTS_WATER);
+
+        // sinks slowly
+        let z = sim.origin().z;
+        sim.run_frames(&usercmd(10), 10);
+        assert!(sim.origin().z < z);
+        assert!(z - sim.origin().z < 20.0);
+
+        // swims up
+        let mut cmd = usercmd(10);
+        cmd.buttons = IN_JUMP as u16;
+        let z = sim.origin().z;
+        sim.run_frames(&cmd, 20);
+        assert!(sim.origin().z > z);
+    }
+
+    #[test]
+    fn unstuck() {
+        let mut sim = spawn(room(), v(0.0, 0.0, 35.9));
+        sim.run_frames(&usercmd(10), 10);
+        let origin = sim.origin();
+        assert!(sim.world().test_player_position(sim.pm(), origin).0 == -1);
+        assert!(origin.z >= 36.0, "{origin:?}");
+    }
+
+    #[test]
+    fn hull_world() {
+        let mut world = World::new();
+        world.add_hull_box(
+            0,
+            Brush::solid(v(-1024.0, -1024.0, -16.0), v(1024.0, 1024.0, 36.0)),
+        );
+        let mut sim = spawn(world, v(0.0, 0.0, 100.0));
+        sim.run_frames(&usercmd(10), 100);
+        assert!(on_ground(&sim));
+        assert!((sim.origin().z - 36.0).abs() < 0.1, "{:?}", sim.origin());
+    }
+
+    #[test]
+    fn side_by_side() {
+        let mut a = spawn(room(), v(0.0, 0.0, 35.9));
+        let mut b = Simulation::with_max_clients(room(), 8);
+        b.set_player_index(7);
+        b.set_origin(v(0.0, 0.0, 35.9));
+        b.run(&usercmd(10));
+        for _ in 0..50 {
+            a.run(&forward(10));
+            b.run(&forward(10));
+        }
+        assert_eq!(a.origin(), b.origin());
+        assert_eq!(a.pm().velocity, b.pm().velocity);
+    }
+
+    #[test]
+    fn config_time_to_duck() {
+        let mut cmd = usercmd(10);
+        cmd.buttons = IN_DUCK as u16;
+
+        let mut sim = spawn(room(), v(0.0, 0.0, 36.0));
+        sim.run(&cmd);
+        assert_eq!(sim.pm().usehull, 0);
+
+        let config = MovementConfig {
+            time_to_duck: 0.0,
+            ..MovementConfig::default()
+        };
+        let ctx = PlayerMoveContext::with_config(1, config);
+        let mut sim = Simulation::with_context(room(), ctx);
+        sim.set_origin(v(0.0, 0.0, 36.0));
+        sim.run(&usercmd(10));
+        sim.run(&cmd);
+        assert_eq!(sim.pm().usehull, 1);
+    }
+
+    #[test]
+    fn move_mode() {
+        /// Flies up while the jump button is held.
+        struct Rise;
+
+        impl MoveMode for Rise {
+            fn is_active(&self, pm: &PlayerMove) -> bool {
+                pm.is_button(IN_JUMP)
+            }
+
+            fn run(&mut self, pm: &mut PlayerMove) {
+                pm.raw_mut().velocity = v(0.0, 0.0, 100.0);
+                pm.fly_move();
+            }
+        }
+
+        let mut ctx = PlayerMoveContext::new(1);
+        ctx.add_move_mode(Rise);
+        let mut sim = Simulation::with_context(room(), ctx);
+        sim.set_origin(v(0.0, 0.0, 36.0));
+        sim.run(&usercmd(10));
+
+        let mut cmd = forward(10);
+        cmd.buttons |= IN_JUMP as u16;
+        sim.run_frames(&cmd, 100);
+        assert!((sim.origin().z - 136.0).abs() < 0.1, "{:?}", sim.origin());
+        assert_eq!(sim.origin().x, 0.0);
+
+        sim.run_frames(&usercmd(10), 200);
+        assert!(on_ground(&sim));
+    }
+
+    #[test]
+    fn trace_box_fraction() {
+        let brush = Brush::solid(v(-8.0, -8.0, -8.0), v(8.0, 8.0, 8.0));
+        let hit = trace_box(&brush, v(-16.0, 0.0, 0.0), v(0.0, 0.0, 0.0));
+        assert!((hit.fraction - (0.5 - DIST_EPSILON / 16.0)).abs() < 1e-6);
+        assert_eq!(hit.normal, v(-1.0, 0.0, 0.0));
+
+        let hit = trace_box(&brush, v(-16.0, 10.0, 0.0), v(0.0, 10.0, 0.0));
+        assert_eq!(hit.fraction, 1.0);
+
+        let hit = trace_box(&brush, v(0.0, 0.0, 0.0), v(16.0, 0.0, 0.0));
+        assert!(hit.startsolid && !hit.allsolid);
+    }
+}
@@ -4,6 +4,21 @@ use syn::{DeriveInput, parse_macro_input};
 
 use crate::save_restore::SaveRestore;
 
+/// Derives `Save` for a struct or an enum.
+///
+/// Struct attributes:
+///
+/// * `#[save(version = N)]` saves the version of the struct. Implement `Migrate` to update
+///   fields restored from an older version.
+///
+/// Field attributes:
+///
+/// * `#[save(rename = "name")]` saves the field with the given name.
+/// * `#[save(alias = "name")]` restores the field from the given name too.
+/// * `#[save(default = path)]` calls `path()` if the field is not in the save.
+/// * `#[save(flatten)]` saves fields of a nested struct in the parent struct.
+/// * `#[save(skip)]`, `#[save(skip_save)]` and `#[save(skip_restore)]` skip the field.
+/// * `#[save(global)]` does not restore the field for global entities.
 #[proc_macro_derive(Save, attributes(save))]
 pub fn derive_save(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(tokens as DeriveInput);
@@ -14,6 +29,9 @@ pub fn derive_save(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     tokens.into()
 }
 
+/// Derives `Restore` for a struct or an enum.
+///
+/// See [Save](derive@Save) for attributes.
 #[proc_macro_derive(Restore, attributes(save))]
 pub fn derive_restore(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(tokens as DeriveInput);
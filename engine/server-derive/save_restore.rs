@@ -33,18 +33,27 @@ fn make_cstr(s: &CStr) -> syn::LitCStr {
 }
 
 #[derive(Default)]
-struct StructAttrs {}
+struct StructAttrs {
+    version: Option<u32>,
+}
 
 impl StructAttrs {
     fn parse(attrs: &[syn::Attribute]) -> Result<Self, Error> {
-        let ret = Self::default();
+        let mut ret = Self::default();
         let mut result = Ok(());
         for attr in attrs.iter() {
             if !attr.path().is_ident("save") {
                 continue;
             }
             let parse_result = attr.parse_nested_meta(|meta| {
-                // no attributes yet
+                // version = N
+                if meta.path.is_ident("version") {
+                    meta.input.parse::<Token![=]>()?;
+                    let lit = meta.input.parse::<syn::LitInt>()?;
+                    ret.version = Some(lit.base10_parse()?);
+                    return Ok(());
+                }
+
                 Err(Error::new(meta.path.span(), "unexpected attribute"))
             });
             result.combine(parse_result);
@@ -120,6 +129,7 @@ impl VariantAttrs {
 struct FieldAttrs {
     rename: Option<String>,
     alias: Vec<String>,
+    default: Option<syn::Path>,
     flatten: bool,
     skip_save: bool,
     skip_restore: bool,
@@ -148,6 +158,13 @@ impl FieldAttrs {
                     return Ok(());
                 }
 
+                // default = path
+                if meta.path.is_ident("default") {
+                    meta.input.parse::<Token![=]>()?;
+                    ret.default = Some(meta.input.parse::<syn::Path>()?);
+                    return Ok(());
+                }
+
                 // flatten
                 if meta.path.is_ident("flatten") {
                     ret.flatten = true;
@@ -201,7 +218,6 @@ fn parse_fields_attrs(fields: &syn::Fields) -> Result<Vec<FieldAttrs>, Error> {
 
 pub struct SaveRestore<'a> {
     input: &'a DeriveInput,
-    #[allow(dead_code)]
     struct_attrs: StructAttrs,
     enum_attrs: EnumAttrs,
     #[allow(dead_code)]
@@ -332,22 +348,35 @@ impl<'a> SaveRestore<'a> {
         if let syn::Data::Enum(..) = &self.input.data {
             let path = parse_quote!(::xash3d_server::save::RestoreWithDefault);
             let bound = make_type_param_trait_bound(path);
-            self.add_where_predicates(generics, bound, |attrs| attrs.skip_restore);
+            self.add_where_predicates(generics, bound, |attrs| {
+                attrs.skip_restore || attrs.default.is_some()
+            });
         }
     }
 
-    fn default_fields(&self, fields: &syn::Fields) -> TokenStream {
+    fn default_fields(&self, fields: &syn::Fields, attrs: &[FieldAttrs]) -> TokenStream {
         let mut tokens = TokenStream::new();
-        for (i, field) in fields.iter().enumerate() {
+        for (i, (field, attrs)) in fields.iter().zip(attrs).enumerate() {
             let name = format_ident!("f{i}");
             let ty = &field.ty;
-            tokens.extend(quote! {
-                let mut #name: #ty = RestoreWithDefault::default_for_restore(state);
-            });
+            if let Some(path) = &attrs.default {
+                tokens.extend(quote! {
+                    let mut #name: #ty = #path();
+                });
+            } else {
+                tokens.extend(quote! {
+                    let mut #name: #ty = RestoreWithDefault::default_for_restore(state);
+                });
+            }
         }
         tokens
     }
 
+    fn version_name(&self) -> syn::LitCStr {
+        let name = format!("@version({})", self.input.ident);
+        make_cstr(&CString::new(name).unwrap())
+    }
+
     fn enumerate_fields(&self, fields: &syn::Fields) -> TokenStream {
         let mut tokens = TokenStream::new();
         for (i, member) in fields.members().enumerate() {
@@ -433,8 +462,13 @@ impl<'a> SaveRestore<'a> {
             syn::Data::Struct(data) => {
                 let extract_fields = self.enumerate_fields(&data.fields);
                 let save_fields = self.save_fields(&data.fields, &self.field_attrs[0]);
+                let save_version = self.struct_attrs.version.map(|version| {
+                    let name = self.version_name();
+                    quote! { cur.write_field(state, #name, &#version)?; }
+                });
                 quote! {
                     let Self #extract_fields = self;
+                    #save_version
                     #save_fields
                 }
             }
@@ -571,17 +605,79 @@ impl<'a> SaveRestore<'a> {
         }
     }
 
-    fn restore_struct(&self) -> TokenStream {
+    fn restore_defaults(&self, data: &syn::DataStruct) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for (i, attrs) in self.field_attrs[0].iter().enumerate() {
+            let Some(path) = &attrs.default else {
+                continue;
+            };
+            let member = format_ident!("f{i}");
+            if attrs.global {
+                tokens.extend(quote! {
+                    if !state.global() {
+                        *#member = #path();
+                    }
+                });
+            } else {
+                tokens.extend(quote! { *#member = #path(); });
+            }
+        }
+        if tokens.is_empty() {
+            return tokens;
+        }
+        let extract_fields = self.enumerate_fields(&data.fields);
+        quote! {
+            {
+                let Self #extract_fields = &mut *self;
+                #tokens
+            }
+        }
+    }
+
+    fn restore_struct(&self, data: &syn::DataStruct) -> TokenStream {
+        let restore_defaults = self.restore_defaults(data);
+        let mut restore_version = TokenStream::new();
+        let mut migrate = TokenStream::new();
+        if let Some(version) = self.struct_attrs.version {
+            let name = self.version_name();
+            let ident = self.input.ident.to_string();
+            restore_version = quote! {
+                if name.as_c_str() == #name {
+                    stored_version.restore(state, &mut field.cursor())?;
+                    continue;
+                }
+            };
+            migrate = quote! {
+                if stored_version < #version {
+                    ::xash3d_server::entity::static_trait_cast!(
+                        Self, ::xash3d_server::save::Migrate, self, mut,
+                    ).map(|this| this.migrate(stored_version));
+                } else if stored_version > #version {
+                    ::log::warn!(
+                        "restore: {} version {} is newer than {}",
+                        #ident, stored_version, #version,
+                    );
+                }
+            };
+        }
+        let stored_version = (!restore_version.is_empty()).then(|| {
+            quote! { let mut stored_version = 0_u32; }
+        });
         quote! {
-            use ::xash3d_server::save::RestoreField;
+            #[allow(unused_imports)]
+            use ::xash3d_server::save::{Restore, RestoreField};
+            #restore_defaults
+            #stored_version
             while !cur.is_empty() {
                 let field = cur.read_field()?;
                 let Some(name) = state.token_str(field.token()) else {
                     ::log::warn!("restore: token({}) not found", field.token().to_u16());
                     continue;
                 };
+                #restore_version
                 self.restore_field(state, &mut field.cursor(), name.as_c_str())?;
             }
+            #migrate
             ::xash3d_server::entity::static_trait_cast!(
                 Self, ::xash3d_server::save::OnRestore, self, mut,
             ).map(|this| this.on_restore());
@@ -618,7 +714,7 @@ impl<'a> SaveRestore<'a> {
                 continue;
             }
 
-            let default_fields = self.default_fields(&variant.fields);
+            let default_fields = self.default_fields(&variant.fields, attrs);
             let restore_field = self.restore_fields(&variant.fields, attrs);
             let collect_fields = self.enumerate_fields(&variant.fields);
             tokens.extend(quote! {
@@ -661,7 +757,7 @@ impl<'a> SaveRestore<'a> {
 
     fn restore_trait_body(&self) -> TokenStream {
         match &self.input.data {
-            syn::Data::Struct(..) => self.restore_struct(),
+            syn::Data::Struct(data) => self.restore_struct(data),
             syn::Data::Enum(data) => self.restore_enum(data),
             syn::Data::Union(..) => unreachable!("union"),
         }
@@ -0,0 +1,384 @@
+//! Server-side studio model animation.
+
+use core::cell::Cell;
+
+use xash3d_shared::{
+    ffi::common::vec3_t,
+    math::{cosf, sinf},
+    studio::{EVENT_CLIENT, Event, MotionFlags, Sequence, SequenceFlags},
+};
+
+#[cfg(feature = "save")]
+use crate::save;
+use crate::{entity::EntityVars, prelude::*};
+
+/// Wraps a rotation value around the controller range and returns the value scaled to 0-255.
+fn controller_setting(ty: MotionFlags, start: f32, end: f32, mut value: f32) -> (u8, f32) {
+    if ty.intersects(MotionFlags::ROTATION) {
+        if end < start {
+            value = -value;
+        }
+        if start + 359.0 >= end {
+            let mid = (start + end) * 0.5;
+            if value > mid + 180.0 {
+                value -= 360.0;
+            }
+            if value < mid - 180.0 {
+                value += 360.0;
+            }
+        } else if value > 360.0 {
+            value -= (value / 360.0) as i32 as f32 * 360.0;
+        } else if value < 0.0 {
+            value += ((value / -360.0) as i32 + 1) as f32 * 360.0;
+        }
+    }
+
+    let setting = (255.0 * (value - start) / (end - start)).clamp(0.0, 255.0) as u8;
+    (setting, setting as f32 / 255.0 * (end - start) + start)
+}
+
+/// Returns `true` if the event is between `start` and `end` frames of the sequence.
+fn is_event_in_range(seq: &Sequence, event: &Event, start: f32, end: f32) -> bool {
+    let frame = event.frame as f32;
+    if frame >= start && frame < end {
+        return true;
+    }
+    // the end of a looping sequence wraps around to the first frame
+    let last = seq.num_frames as f32 - 1.0;
+    seq.is_looping() && end >= last && frame < end - last
+}
+
+/// Animation state of an entity with a studio model.
+#[derive(Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Animating {
+    /// The frame rate of the current sequence in `frame` units (0-255) per second.
+    frame_rate: Cell<f32>,
+    /// The movement speed of the current sequence.
+    ground_speed: Cell<f32>,
+    last_event_check: Cell<f32>,
+    sequence_finished: Cell<bool>,
+    sequence_loops: Cell<bool>,
+}
+
+impl Animating {
+    pub fn frame_rate(&self) -> f32 {
+        self.frame_rate.get()
+    }
+
+    pub fn ground_speed(&self) -> f32 {
+        self.ground_speed.get()
+    }
+
+    pub fn set_ground_speed(&self, speed: f32) {
+        self.ground_speed.set(speed);
+    }
+
+    pub fn is_sequence_finished(&self) -> bool {
+        self.sequence_finished.get()
+    }
+
+    pub fn set_sequence_finished(&self, finished: bool) {
+        self.sequence_finished.set(finished);
+    }
+
+    pub fn is_sequence_looping(&self) -> bool {
+        self.sequence_loops.get()
+    }
+
+    /// Returns the index of a sequence with the label ignoring case.
+    pub fn lookup_sequence(&self, v: &EntityVars, label: &str) -> Option<i32> {
+        let engine = v.engine();
+        let model = engine.get_studio_model(v)?;
+        model.find_sequence(label).map(|i| i as i32)
+    }
+
+    /// Returns a random sequence for the activity picked by sequence weights.
+    pub fn lookup_activity(&self, v: &EntityVars, activity: i32) -> Option<i32> {
+        let engine = v.engine();
+        let model = engine.get_studio_model(v)?;
+        let matches = || {
+            model
+                .sequences()
+                .enumerate()
+                .filter(move |(_, seq)| seq.activity == activity)
+        };
+        let total = matches()
+            .map(|(_, seq)| seq.activity_weight.max(0))
+            .sum::<i32>();
+        if total <= 0 {
+            return matches().next().map(|(i, _)| i as i32);
+        }
+        let mut pick = engine.random_int(0, total - 1);
+        for (i, seq) in matches() {
+            pick -= seq.activity_weight.max(0);
+            if pick < 0 {
+                return Some(i as i32);
+            }
+        }
+        None
+    }
+
+    /// Returns a sequence for the activity with the heaviest weight.
+    pub fn lookup_activity_heaviest(&self, v: &EntityVars, activity: i32) -> Option<i32> {
+        let engine = v.engine();
+        let model = engine.get_studio_model(v)?;
+        let mut best = None;
+        let mut best_weight = 0;
+        for (i, seq) in model.sequences().enumerate() {
+            if seq.activity == activity && (best.is_none() || seq.activity_weight > best_weight) {
+                best = Some(i as i32);
+                best_weight = seq.activity_weight;
+            }
+        }
+        best
+    }
+
+    pub fn sequence_flags(&self, v: &EntityVars) -> SequenceFlags {
+        let engine = v.engine();
+        engine
+            .get_studio_model(v)
+            .and_then(|model| model.sequence(v.sequence().try_into().ok()?))
+            .map_or(SequenceFlags::empty(), |seq| seq.flags)
+    }
+
+    /// Restarts the current sequence and reads its frame rate and movement speed.
+    pub fn reset_sequence_info(&self, v: &EntityVars) {
+        let engine = v.engine();
+        let seq = engine
+            .get_studio_model(v)
+            .and_then(|model| model.sequence(v.sequence().try_into().ok()?));
+        let now = engine.globals.map_time_f32();
+
+        match seq {
+            Some(seq) => {
+                self.frame_rate.set(seq.frame_rate());
+                self.ground_speed.set(seq.ground_speed());
+                self.sequence_loops.set(seq.is_looping());
+            }
+            None => {
+                self.frame_rate.set(0.0);
+                self.ground_speed.set(0.0);
+                self.sequence_loops.set(false);
+            }
+        }
+        v.set_animation_time(now);
+        v.set_framerate(1.0);
+        self.sequence_finished.set(false);
+        self.last_event_check.set(now);
+    }
+
+    /// Advances the frame of the current sequence and returns the elapsed interval.
+    ///
+    /// If `interval` is zero, the time since the last advance is used.
+    pub fn frame_advance(&self, v: &EntityVars, interval: f32) -> f32 {
+        let now = v.engine().globals.map_time_f32();
+        let mut interval = interval;
+        if interval == 0.0 {
+            interval = now - v.animation_time();
+            if interval <= 0.001 {
+                v.set_animation_time(now);
+                return 0.0;
+            }
+        }
+        if v.animation_time() == 0.0 {
+            interval = 0.0;
+        }
+
+        let mut frame = v.frame() + interval * self.frame_rate.get() * v.framerate();
+        v.set_animation_time(now);
+
+        if !(0.0..256.0).contains(&frame) {
+            if self.sequence_loops.get() {
+                frame -= (frame / 256.0) as i32 as f32 * 256.0;
+            } else {
+                frame = frame.clamp(0.0, 255.0);
+            }
+            self.sequence_finished.set(true);
+        }
+        v.set_frame(frame);
+
+        interval
+    }
+
+    /// Calls `handler` for server events of the current sequence passed since the last check.
+    pub fn dispatch_events(&self, v: &EntityVars, interval: f32, mut handler: impl FnMut(&Event)) {
+        let engine = v.engine();
+        let Some(model) = engine.get_studio_model(v) else {
+            return;
+        };
+        // FIXME: the interval is not known for the first dispatch
+        let interval = if interval == 0.0 { 0.1 } else { interval };
+
+        let rate = self.frame_rate.get() * v.framerate();
+        let mut start = v.frame() + (self.last_event_check.get() - v.animation_time()) * rate;
+        let mut end = v.frame() + interval * rate;
+        self.last_event_check.set(v.animation_time() + interval);
+
+        self.sequence_finished.set(end >= 256.0 || end <= 0.0);
+
+        let Some(seq) = usize::try_from(v.sequence())
+            .ok()
+            .and_then(|i| model.sequence(i))
+        else {
+            return;
+        };
+        if seq.num_frames > 1 {
+            let scale = (seq.num_frames - 1) as f32 / 256.0;
+            start *= scale;
+            end *= scale;
+        } else {
+            start = 0.0;
+            end = 1.0;
+        }
+
+        for event in seq.events() {
+            if event.event < EVENT_CLIENT && is_event_in_range(&seq, &event, start, end) {
+                handler(&event);
+            }
+        }
+    }
+
+    /// Returns the submodel index of the body group.
+    pub fn get_bodygroup(&self, v: &EntityVars, group: usize) -> i32 {
+        let engine = v.engine();
+        let Some(part) = engine.get_studio_model(v).and_then(|m| m.body_part(group)) else {
+            return 0;
+        };
+        if part.num_models <= 1 || part.base == 0 {
+            return 0;
+        }
+        (v.body() / part.base as i32) % part.num_models as i32
+    }
+
+    /// Selects the submodel of the body group.
+    pub fn set_bodygroup(&self, v: &EntityVars, group: usize, value: i32) {
+        let engine = v.engine();
+        let Some(part) = engine.get_studio_model(v).and_then(|m| m.body_part(group)) else {
+            return;
+        };
+        if value < 0 || value >= part.num_models as i32 || part.base == 0 {
+            return;
+        }
+        let base = part.base as i32;
+        let current = (v.body() / base) % part.num_models as i32;
+        v.set_body(v.body() - current * base + value * base);
+    }
+
+    /// Sets the bone controller and returns the value that the model can represent.
+    pub fn set_controller(&self, v: &EntityVars, controller: usize, value: f32) -> f32 {
+        let engine = v.engine();
+        let Some(model) = engine.get_studio_model(v) else {
+            return value;
+        };
+        let mut controllers = v.controller();
+        if controller >= controllers.len() {
+            return value;
+        }
+        let Some(info) = model.bone_controller(controller as i32) else {
+            return value;
+        };
+        let (setting, value) = controller_setting(info.ty, info.start, info.end, value);
+        controllers[controller] = setting;
+        v.set_controller(controllers);
+        value
+    }
+
+    /// Resets all bone controllers to zero.
+    pub fn init_bone_controllers(&self, v: &EntityVars) {
+        for i in 0..v.controller().len() {
+            self.set_controller(v, i, 0.0);
+        }
+    }
+
+    /// Sets the blend of the current sequence and returns the value that the model can
+    /// represent.
+    pub fn set_blending(&self, v: &EntityVars, blender: usize, value: f32) -> f32 {
+        let engine = v.engine();
+        let Some(seq) = engine
+            .get_studio_model(v)
+            .and_then(|model| model.sequence(v.sequence().try_into().ok()?))
+        else {
+            return value;
+        };
+        let mut blending = v.blending();
+        if blender >= blending.len() || seq.blend_type[blender].is_empty() {
+            return value;
+        }
+        let (setting, value) = controller_setting(
+            seq.blend_type[blender],
+            seq.blend_start[blender],
+            seq.blend_end[blender],
+            value,
+        );
+        blending[blender] = setting;
+        v.set_blending(blending);
+        value
+    }
+
+    /// Returns the bounding box of the sequence.
+    pub fn extract_bbox(&self, v: &EntityVars, sequence: i32) -> Option<(vec3_t, vec3_t)> {
+        let engine = v.engine();
+        let model = engine.get_studio_model(v)?;
+        let seq = model.sequence(sequence.try_into().ok()?)?;
+        Some((seq.bbmin, seq.bbmax))
+    }
+
+    /// Sets the entity size to the bounding box of the current sequence rotated by yaw.
+    pub fn set_sequence_box(&self, v: &EntityVars) {
+        let Some((min, max)) = self.extract_bbox(v, v.sequence()) else {
+            return;
+        };
+
+        let yaw = v.angles().y.to_radians();
+        let (sin, cos) = (sinf(yaw), cosf(yaw));
+        let mut rmin = vec3_t::splat(9999.0);
+        let mut rmax = vec3_t::splat(-9999.0);
+        for x in [min.x, max.x] {
+            for y in [min.y, max.y] {
+                let tx = cos * x - sin * y;
+                let ty = sin * x + cos * y;
+                rmin.x = rmin.x.min(tx);
+                rmin.y = rmin.y.min(ty);
+                rmax.x = rmax.x.max(tx);
+                rmax.y = rmax.y.max(ty);
+            }
+        }
+        rmin.z = 0.0;
+        rmax.z = rmin.z + 1.0;
+        v.set_size_and_link(rmin, rmax);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn controller_wrap() {
+        let yaw = MotionFlags::YR;
+        assert_eq!(controller_setting(yaw, -90.0, 90.0, 0.0).0, 127);
+        assert_eq!(controller_setting(yaw, -90.0, 90.0, -180.0).0, 0);
+        assert_eq!(controller_setting(yaw, -90.0, 90.0, 270.0).0, 0);
+        assert_eq!(controller_setting(yaw, 0.0, 360.0, 450.0).0, 63);
+        assert_eq!(controller_setting(yaw, 0.0, 720.0, 450.0).0, 31);
+        assert_eq!(controller_setting(yaw, 0.0, 720.0, -90.0).0, 95);
+
+        let mouth = MotionFlags::X;
+        let (setting, value) = controller_setting(mouth, 0.0, 64.0, 128.0);
+        assert_eq!((setting, value), (255, 64.0));
+    }
+
+    #[test]
+    fn controller_reversed_and_clamped() {
+        // a reversed rotation range negates the value
+        let yaw = MotionFlags::YR;
+        assert_eq!(controller_setting(yaw, 90.0, -90.0, 30.0).0, 170);
+        assert_eq!(controller_setting(yaw, 90.0, -90.0, -90.0).0, 0);
+
+        let mouth = MotionFlags::X;
+        assert_eq!(controller_setting(mouth, 0.0, 64.0, 32.0).0, 127);
+        let (setting, value) = controller_setting(mouth, 0.0, 64.0, -10.0);
+        assert_eq!((setting, value), (0, 0.0));
+    }
+}
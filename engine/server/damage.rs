@@ -0,0 +1,252 @@
+//! Damage dealing helpers.
+
+use core::cell::Cell;
+
+use xash3d_shared::{
+    consts::Contents, entity::DamageFlags, ffi::common::vec3_t, macros::define_enum_for_primitive,
+};
+
+use crate::{
+    engine::{TraceIgnore, TraceResult},
+    entity::{EntityHandle, EntityVars, TakeDamage, WaterLevel},
+    prelude::*,
+};
+
+define_enum_for_primitive! {
+    /// A body part hit by a trace.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub enum HitGroup: u32 {
+        #[default]
+        Generic(0),
+        Head(1),
+        Chest(2),
+        Stomach(3),
+        LeftArm(4),
+        RightArm(5),
+        LeftLeg(6),
+        RightLeg(7),
+    }
+}
+
+impl HitGroup {
+    /// Returns a hit group from [TraceResult::hit_group].
+    ///
+    /// Unknown groups are treated as [HitGroup::Generic].
+    pub fn from_trace(trace: &TraceResult) -> Self {
+        Self::from_raw(trace.hit_group()).unwrap_or_default()
+    }
+}
+
+/// Accumulates damage dealt to one entity during a single attack.
+///
+/// Shotguns and explosions hit the same entity multiple times, the damage is summed and applied
+/// once so the entity can gib or play a single pain sound.
+#[derive(Default)]
+pub struct MultiDamage {
+    entity: Cell<Option<EntityHandle>>,
+    damage: Cell<f32>,
+    damage_type: Cell<DamageFlags>,
+}
+
+impl MultiDamage {
+    /// Resets the accumulated damage.
+    ///
+    /// Must be called before an attack.
+    pub fn clear(&self) {
+        self.entity.set(None);
+        self.damage.set(0.0);
+        self.damage_type.set(DamageFlags::GENERIC);
+    }
+
+    /// Returns the type of the accumulated damage.
+    pub fn damage_type(&self) -> DamageFlags {
+        self.damage_type.get()
+    }
+
+    /// Adds damage for an entity.
+    ///
+    /// The damage accumulated for a previous entity is applied immediately.
+    pub fn add(
+        &self,
+        inflictor: &EntityVars,
+        entity: &dyn Entity,
+        damage: f32,
+        damage_type: DamageFlags,
+    ) {
+        let handle = entity.entity_handle();
+        if self.entity.get() != Some(handle) {
+            self.apply(inflictor, inflictor);
+            self.entity.set(Some(handle));
+        }
+        self.damage.set(self.damage.get() + damage);
+        self.damage_type.set(self.damage_type.get() | damage_type);
+    }
+
+    /// Applies the accumulated damage and resets it.
+    pub fn apply(&self, inflictor: &EntityVars, attacker: &EntityVars) {
+        let handle = self.entity.take();
+        let damage = self.damage.take();
+        let damage_type = self.damage_type.take();
+        if let Some(entity) = handle.get_entity() {
+            entity.take_damage(damage, damage_type, inflictor, Some(attacker));
+        }
+    }
+}
+
+/// Deals damage to all entities in a radius that can be seen from `src`.
+///
+/// The damage falls off linearly with the distance. Blasts do not travel into or out of water.
+pub fn radius_damage(
+    engine: &ServerEngine,
+    src: vec3_t,
+    inflictor: &EntityVars,
+    attacker: Option<&EntityVars>,
+    damage: f32,
+    radius: f32,
+    damage_type: DamageFlags,
+) {
+    let falloff = if radius != 0.0 { damage / radius } else { 1.0 };
+    let in_water = engine.point_contents(src) == Contents::Water;
+    // in case a grenade is lying on the ground
+    let src = src + vec3_t::new(0.0, 0.0, 1.0);
+    let attacker = attacker.unwrap_or(inflictor);
+    let global_state = engine.global_state_ref();
+    let multi_damage = global_state.multi_damage();
+
+    for entity in engine.entities().in_sphere(src, radius) {
+        let Some(entity) = entity.get_entity() else {
+            continue;
+        };
+        let v = entity.vars();
+        if v.take_damage() == TakeDamage::No {
+            continue;
+        }
+        let water_level = v.water_level();
+        if in_water && water_level == WaterLevel::Dry {
+            continue;
+        }
+        if !in_water && water_level == WaterLevel::Head {
+            continue;
+        }
+
+        let spot = entity.body_target(src);
+        let mut trace = engine.trace_line(src, spot, TraceIgnore::NONE, Some(inflictor));
+        let hit = trace.hit_entity().map(EntityHandle::from);
+        if trace.fraction() != 1.0 && hit != Some(entity.entity_handle()) {
+            continue;
+        }
+
+        // the explosion can see this entity
+        if trace.start_solid() {
+            // the blast started inside the entity
+            trace.set_end_position(src);
+            trace.set_fraction(0.0);
+        }
+        let end = trace.end_position();
+        let adjusted = (damage - (src - end).length() * falloff).max(0.0);
+        if trace.fraction() != 1.0 {
+            multi_damage.clear();
+            let dir = (end - src).normalize();
+            entity.trace_attack(inflictor, adjusted, dir, &trace, damage_type);
+            multi_damage.apply(inflictor, attacker);
+        } else {
+            entity.take_damage(adjusted, damage_type, inflictor, Some(attacker));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "save"))]
+mod tests {
+    use crate::{
+        entity::{BaseEntity, delegate_entity},
+        private::impl_private,
+        testing,
+    };
+
+    use super::*;
+
+    #[derive(Save, Restore)]
+    struct Target {
+        base: BaseEntity,
+        hits: Cell<i32>,
+        damage: Cell<f32>,
+        damage_type: Cell<DamageFlags>,
+    }
+
+    impl CreateEntity for Target {
+        fn create(base: BaseEntity) -> Self {
+            Self {
+                base,
+                hits: Cell::new(0),
+                damage: Cell::new(0.0),
+                damage_type: Cell::default(),
+            }
+        }
+    }
+
+    impl Entity for Target {
+        delegate_entity!(base not { take_damage });
+
+        fn take_damage(
+            &self,
+            damage: f32,
+            damage_type: DamageFlags,
+            _: &EntityVars,
+            _: Option<&EntityVars>,
+        ) -> bool {
+            self.hits.set(self.hits.get() + 1);
+            self.damage.set(self.damage.get() + damage);
+            self.damage_type.set(damage_type);
+            true
+        }
+    }
+
+    impl_private!(Target {});
+
+    #[test]
+    fn multi_damage() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let attacker = engine.new_entity::<Target>().build();
+        let a = engine.new_entity::<Target>().build();
+        let b = engine.new_entity::<Target>().build();
+        let (attacker, a, b) = (attacker.vars(), &*a, &*b);
+
+        let multi_damage = MultiDamage::default();
+        multi_damage.clear();
+        multi_damage.add(attacker, a, 10.0, DamageFlags::BULLET);
+        multi_damage.add(attacker, a, 5.0, DamageFlags::CLUB);
+        assert_eq!(a.hits.get(), 0);
+        assert_eq!(
+            multi_damage.damage_type(),
+            DamageFlags::BULLET | DamageFlags::CLUB
+        );
+
+        // the damage for the previous entity is applied once
+        multi_damage.add(attacker, b, 7.0, DamageFlags::BULLET);
+        assert_eq!(a.hits.get(), 1);
+        assert_eq!(a.damage.get(), 15.0);
+        assert_eq!(a.damage_type.get(), DamageFlags::BULLET | DamageFlags::CLUB);
+        assert_eq!(b.hits.get(), 0);
+
+        multi_damage.apply(attacker, attacker);
+        assert_eq!(b.hits.get(), 1);
+        assert_eq!(b.damage.get(), 7.0);
+        assert_eq!(b.damage_type.get(), DamageFlags::BULLET);
+
+        // nothing is left after apply
+        multi_damage.apply(attacker, attacker);
+        assert_eq!(b.hits.get(), 1);
+
+        multi_damage.add(attacker, a, 3.0, DamageFlags::BULLET);
+        multi_damage.clear();
+        multi_damage.apply(attacker, attacker);
+        assert_eq!(a.hits.get(), 1);
+
+        unsafe {
+            engine.remove_entity_now(attacker);
+            engine.remove_entity_now(a.vars());
+            engine.remove_entity_now(b.vars());
+        }
+    }
+}
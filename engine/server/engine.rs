@@ -26,6 +26,7 @@ use xash3d_shared::{
     macros::define_enum_for_primitive,
     sound::{Attenuation, Channel, Pitch, SoundFlags},
     str::{AsCStrPtr, ToEngineStr},
+    studio::StudioModel,
     user_message::{Angle, Coord, UserMessageValue, UserMessageWrite},
     utils::cstr_or_none,
 };
@@ -342,11 +343,19 @@ impl<'a> TraceResult<'a> {
         self.raw.flFraction
     }
 
+    pub fn set_fraction(&mut self, fraction: f32) {
+        self.raw.flFraction = fraction;
+    }
+
     /// Returns the final trace position.
     pub fn end_position(&self) -> vec3_t {
         self.raw.vecEndPos
     }
 
+    pub fn set_end_position(&mut self, position: vec3_t) {
+        self.raw.vecEndPos = position;
+    }
+
     pub fn plane_dist(&self) -> f32 {
         self.raw.flPlaneDist
     }
@@ -1540,6 +1549,14 @@ impl ServerEngine {
         unsafe { unwrap!(self, pfnGetModelPtr)(ent.as_entity_handle()) }
     }
 
+    /// Returns the studio model of the entity or `None` if the entity has no studio model.
+    pub fn get_studio_model(&self, ent: &impl AsEntityHandle) -> Option<StudioModel<'_>> {
+        let ptr = self.get_model_ptr(ent);
+        // SAFETY: the engine returns null or a studio model header that lives until
+        // the level changes
+        unsafe { StudioModel::from_ptr(ptr.cast()) }.ok()
+    }
+
     pub fn register_user_message<'a, T>(
         &self,
         name: impl ToEngineStr,
@@ -14,7 +14,7 @@ use bitflags::bitflags;
 use xash3d_shared::{
     csz::CStrThin,
     ffi::{
-        common::{entity_state_s, vec3_t},
+        common::{clientdata_s, entity_state_s, vec3_t, weapon_data_s},
         server::{KeyValueData, edict_s, entvars_s},
     },
     math::fabsf,
@@ -22,9 +22,11 @@ use xash3d_shared::{
 };
 
 use crate::{
-    engine::ServerEngineRef,
+    engine::{ServerEngineRef, TraceResult},
     export::dispatch_spawn,
     global_state::{EntityState, GlobalStateRef},
+    monster::Class,
+    node_graph::NodeGraph,
     prelude::*,
     private::impl_private,
 };
@@ -519,6 +521,9 @@ pub enum UseType {
     #[default]
     Off = 0,
     On,
+    /// Sets a value to the target.
+    ///
+    /// Momentary controls use it to pass their position in range from `0.0` to `1.0`.
     Set(f32),
     Toggle,
 }
@@ -647,17 +652,6 @@ define_entity_trait! {
                 let v = self.vars();
                 v.solid() == Solid::Bsp || v.move_type() == MoveType::PushStep
             }
-
-            fn killed(
-                &self,
-                _attacker: &::xash3d_server::entity::EntityVars,
-                _gib: ::xash3d_server::entity::Gib,
-            ) {
-                let v = self.vars();
-                v.set_take_damage(::xash3d_server::entity::TakeDamage::No);
-                v.set_dead(::xash3d_server::entity::Dead::Yes);
-                self.remove_from_world();
-            }
         }
 
         /// Returns a reference to the server engine.
@@ -698,6 +692,9 @@ define_entity_trait! {
 
         fn is_triggered(&self, activator: Option<&dyn ::xash3d_server::entity::Entity>) -> bool;
 
+        /// Returns a class used to find out how monsters feel about this entity.
+        fn classify(&self) -> ::xash3d_server::monster::Class;
+
         fn take_health(
             &self,
             health: f32,
@@ -712,6 +709,31 @@ define_entity_trait! {
             attacker: Option<&::xash3d_server::entity::EntityVars>,
         ) -> bool;
 
+        /// Returns a position to aim at from `src`.
+        fn body_target(
+            &self,
+            src: ::xash3d_server::ffi::common::vec3_t,
+        ) -> ::xash3d_server::ffi::common::vec3_t;
+
+        /// Called when a traced attack hits this entity.
+        ///
+        /// The damage is accumulated in [MultiDamage](::xash3d_server::damage::MultiDamage)
+        /// and applied by the attacker.
+        fn trace_attack(
+            &self,
+            attacker: &::xash3d_server::entity::EntityVars,
+            damage: f32,
+            dir: ::xash3d_server::ffi::common::vec3_t,
+            trace: &::xash3d_server::engine::TraceResult,
+            damage_type: ::xash3d_server::entity::DamageFlags,
+        );
+
+        fn killed(
+            &self,
+            attacker: &::xash3d_server::entity::EntityVars,
+            gib: ::xash3d_server::entity::Gib,
+        );
+
         fn override_reset(&self);
 
         fn set_object_collision_box(&self);
@@ -831,6 +853,10 @@ impl Entity for BaseEntity {
         true
     }
 
+    fn classify(&self) -> Class {
+        Class::None
+    }
+
     fn take_health(&self, health: f32, _damage_type: DamageFlags) -> bool {
         let v = self.vars();
         if v.take_damage() == TakeDamage::No {
@@ -863,6 +889,32 @@ impl Entity for BaseEntity {
         false
     }
 
+    fn body_target(&self, _src: vec3_t) -> vec3_t {
+        self.vars().abs_center()
+    }
+
+    fn trace_attack(
+        &self,
+        attacker: &EntityVars,
+        damage: f32,
+        _dir: vec3_t,
+        _trace: &TraceResult,
+        damage_type: DamageFlags,
+    ) {
+        if self.vars().take_damage() != TakeDamage::No {
+            let global_state = self.global_state();
+            let multi_damage = global_state.multi_damage();
+            multi_damage.add(attacker, self, damage, damage_type);
+        }
+    }
+
+    fn killed(&self, _attacker: &EntityVars, _gib: Gib) {
+        let v = self.vars();
+        v.set_take_damage(TakeDamage::No);
+        v.set_dead(Dead::Yes);
+        self.remove_from_world();
+    }
+
     fn override_reset(&self) {}
 
     fn set_object_collision_box(&self) {
@@ -894,9 +946,9 @@ impl Entity for BaseEntity {
         }
 
         if v.flags().intersects(EdictFlags::GRAPHED) {
-            // TODO: remove from the world graph
-            let name = self.pretty_name();
-            warn!("{name}: remove from the world graph is not implemented yet");
+            self.global_state()
+                .get_or_default::<NodeGraph>()
+                .remove_entity(self.entity_handle());
         }
 
         if let Some(globalname) = self.globalname() {
@@ -923,6 +975,58 @@ pub trait EntityItem: Entity {
     fn try_give(&self, other: &dyn Entity) -> bool;
 }
 
+/// An item in the player [Inventory](crate::inventory::Inventory).
+///
+/// Timers are relative to the current frame and decremented by
+/// [decrement_timers](Self::decrement_timers).
+pub trait PlayerWeapon: EntityItem {
+    /// Returns the inventory slot of this weapon.
+    fn weapon_id(&self) -> usize;
+
+    /// Returns the priority of this weapon for an automatic selection.
+    fn weight(&self) -> i32 {
+        0
+    }
+
+    /// Returns the number of rounds in the clip or `-1` if the weapon does not use clips.
+    fn clip(&self) -> i32 {
+        -1
+    }
+
+    /// Returns the inventory ammo slot used by the primary attack.
+    fn primary_ammo(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the inventory ammo slot used by the secondary attack.
+    fn secondary_ammo(&self) -> Option<usize> {
+        None
+    }
+
+    fn can_deploy(&self) -> bool {
+        true
+    }
+
+    fn can_holster(&self) -> bool {
+        true
+    }
+
+    fn deploy(&self);
+
+    fn holster(&self);
+
+    /// Called every frame after player physics if this weapon is active.
+    fn item_post_frame(&self);
+
+    fn decrement_timers(&self, time: f32);
+
+    /// Writes the weapon state for client prediction.
+    fn weapon_data(&self, data: &mut weapon_data_s);
+
+    /// Writes the active weapon state for client prediction.
+    fn client_data(&self, _data: &mut clientdata_s) {}
+}
+
 #[derive(Copy, Clone)]
 pub struct LastSound {
     /// A last sound entity that modified the player room type.
@@ -972,6 +1076,18 @@ define_entity_trait! {
         fn set_env_sound(&self, last: Option<::xash3d_server::entity::LastSound>);
 
         fn give_named_item(&self, name: &::xash3d_server::csz::CStrThin) -> bool;
+
+        fn inventory(&self) -> &::xash3d_server::inventory::Inventory;
+
+        /// Returns the random seed of the current user command.
+        fn random_seed(&self) -> u32;
+
+        fn set_random_seed(&self, seed: u32);
+
+        /// Returns `true` if the dead player is ready to respawn.
+        fn is_respawn_pending(&self) -> bool {
+            false
+        }
     }
 }
 
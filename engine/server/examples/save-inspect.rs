@@ -0,0 +1,48 @@
+//! Prints the contents of a save file.
+//!
+//! Usage: `save-inspect [--json] <file>`
+
+use std::{env, fs, process::ExitCode};
+
+use xash3d_server::save::inspect;
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("usage: save-inspect [--json] <file>");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let Some(path) = path else {
+        eprintln!("usage: save-inspect [--json] <file>");
+        return ExitCode::FAILURE;
+    };
+
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("failed to read {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = inspect::inspect(&bytes);
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print!("{report}");
+    }
+
+    if report.has_issues() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
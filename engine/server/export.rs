@@ -7,7 +7,7 @@ use core::{
     sync::atomic::{AtomicBool, Ordering},
 };
 
-use xash3d_player_move::{DUCK_HULL_MIN, HULL_MIN};
+use xash3d_player_move::MovementConfig;
 use xash3d_shared::{
     consts::{EFLAG_SLERP, ENTITY_BEAM, ENTITY_NORMAL},
     csz::{CStrArray, CStrSlice, CStrThin},
@@ -43,6 +43,9 @@ pub use xash3d_shared::export::{UnsyncGlobal, impl_unsync_global};
 #[cfg(feature = "save")]
 const ENTITY_SAVE_NAME: &CStr = c"ENTITY";
 
+/// The size of the weapon data array passed to [ServerDll::get_weapon_data].
+const MAX_LOCAL_WEAPONS: usize = 64;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SpawnResult {
     Delete,
@@ -466,7 +469,11 @@ pub trait ServerDll: UnsyncGlobal {
         true
     }
 
-    fn client_disconnect(&self, ent: EntityHandle) {}
+    fn client_disconnect(&self, ent: EntityHandle) {
+        if let Some(player) = ent.downcast_ref::<dyn EntityPlayer>() {
+            self.global_state().game_rules().client_disconnected(player);
+        }
+    }
 
     fn client_kill(&self, ent: EntityHandle) {}
 
@@ -510,9 +517,23 @@ pub trait ServerDll: UnsyncGlobal {
 
     fn server_deactivate(&self) {}
 
-    fn player_pre_think(&self, ent: EntityHandle) {
-        if let Some(player) = ent.downcast_ref::<dyn EntityPlayer>() {
-            player.pre_think();
+    fn player_pre_think(&self, mut ent: EntityHandle) {
+        let Some(player) = ent.downcast_ref::<dyn EntityPlayer>() else {
+            return;
+        };
+        player.pre_think();
+        if !player.is_respawn_pending() {
+            return;
+        }
+
+        let game_rules = self.global_state().game_rules();
+        if game_rules.is_coop() || game_rules.is_deathmatch() {
+            if let Some(player) = unsafe { ent.get_entity_mut() } {
+                player.spawn();
+            }
+        } else {
+            // restart the level in single player
+            self.engine().server_command(c"reload\n");
         }
     }
 
@@ -522,7 +543,9 @@ pub trait ServerDll: UnsyncGlobal {
         }
     }
 
-    fn start_frame(&self) {}
+    fn start_frame(&self) {
+        self.global_state().game_rules().think();
+    }
 
     fn parms_new_level(&self) {}
 
@@ -555,8 +578,14 @@ pub trait ServerDll: UnsyncGlobal {
     /// Called when the engine has encountered an error.
     fn system_error(&self, error_string: &CStrThin) {}
 
+    /// Movement parameters used by the player movement code.
+    fn movement_config(&self) -> MovementConfig {
+        MovementConfig::default()
+    }
+
     fn player_move_init(&self, pm: NonNull<playermove_s>) {
         let pm = unsafe { pm.cast().as_mut() };
+        xash3d_player_move::set_movement_config(self.movement_config());
         xash3d_player_move::player_move_init(pm);
     }
 
@@ -593,7 +622,8 @@ pub trait ServerDll: UnsyncGlobal {
         } else {
             org = view.vars().origin() + view.vars().view_ofs();
             if view.vars().flags().intersects(EdictFlags::DUCKING) {
-                org += HULL_MIN - DUCK_HULL_MIN;
+                let config = xash3d_player_move::movement_config();
+                org += config.hull_min - config.duck_hull_min;
             }
         }
 
@@ -653,11 +683,11 @@ pub trait ServerDll: UnsyncGlobal {
         cd.iuser1 = ev.iuser1();
         cd.iuser2 = ev.iuser2();
 
-        // TODO: sendweapons
-        // #[cfg(feature = "client-weapons")]
-        // if sendweapons {
-        //
-        // }
+        if send_weapons {
+            if let Some(player) = ent.downcast_ref::<dyn EntityPlayer>() {
+                player.inventory().client_data(cd);
+            }
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -818,11 +848,24 @@ pub trait ServerDll: UnsyncGlobal {
 
     fn register_encoders(&self) {}
 
-    fn get_weapon_data(&self, player: EntityHandle) -> Option<weapon_data_s> {
-        None
+    /// Fills weapon states indexed by weapon ids for client prediction.
+    ///
+    /// Returns `false` if the player has no weapons.
+    fn get_weapon_data(&self, player: EntityHandle, info: &mut [weapon_data_s]) -> bool {
+        match player.downcast_ref::<dyn EntityPlayer>() {
+            Some(player) => {
+                player.inventory().weapon_data(info);
+                true
+            }
+            None => false,
+        }
     }
 
-    fn command_start(&self, player: EntityHandle, cmd: &usercmd_s, random_seed: c_uint) {}
+    fn command_start(&self, player: EntityHandle, cmd: &usercmd_s, random_seed: c_uint) {
+        if let Some(player) = player.downcast_ref::<dyn EntityPlayer>() {
+            player.set_random_seed(random_seed);
+        }
+    }
 
     fn command_end(&self, player: EntityHandle) {}
 
@@ -1615,16 +1658,9 @@ impl<T: ServerDll> ServerDllExport for Export<T> {
             let engine = ServerEngineRef::new();
             let player = EntityHandle::new(engine, player).expect("player must be non-null");
             let dll = T::global_assume_init_ref();
-            match dll.get_weapon_data(player) {
-                Some(x) => {
-                    info.write(x);
-                    1
-                }
-                None => {
-                    info.write_bytes(0, 1);
-                    0
-                }
-            }
+            info.write_bytes(0, MAX_LOCAL_WEAPONS);
+            let info = slice::from_raw_parts_mut(info, MAX_LOCAL_WEAPONS);
+            dll.get_weapon_data(player, info).into()
         }
     }
 
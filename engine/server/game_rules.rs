@@ -3,10 +3,13 @@ use core::{any::Any, ffi::CStr};
 use xash3d_shared::ffi::common::vec3_t;
 
 use crate::{
+    csz::CStrThin,
     engine::ServerEngineRef,
-    entity::{Entity, EntityHandle, EntityPlayer},
+    entity::{Entity, EntityHandle, EntityPlayer, EntityVars, UseType},
     global_state::GlobalStateRef,
+    str::MapString,
     time::MapTime,
+    utils,
 };
 
 pub trait GameRules: Any {
@@ -45,16 +48,66 @@ pub trait GameRules: Any {
         pv.set_angles(sv.angles());
         pv.set_punch_angle(vec3_t::ZERO);
         pv.set_fix_angle(1);
+        if self.is_multiplayer() {
+            if let Some(target) = sv.target() {
+                let player = player.as_entity();
+                utils::fire_targets(&target, UseType::Toggle, Some(player), player);
+            }
+        }
         spawn_spot
     }
 
     #[allow(unused_variables)]
     fn player_spawn(&self, player: &dyn EntityPlayer) {}
 
+    /// Called once per frame.
+    fn think(&self) {}
+
+    /// Called when the HUD of the connected player is initialized.
+    #[allow(unused_variables)]
+    fn init_hud(&self, player: &dyn EntityPlayer) {}
+
+    /// Called when the player disconnects from the server.
+    #[allow(unused_variables)]
+    fn client_disconnected(&self, player: &dyn EntityPlayer) {}
+
+    /// Called when the player is killed by the `killer` with the `inflictor`.
+    #[allow(unused_variables)]
+    fn player_killed(
+        &self,
+        victim: &dyn EntityPlayer,
+        killer: &EntityVars,
+        inflictor: &EntityVars,
+    ) {
+    }
+
+    /// Returns `true` if the dead player is allowed to respawn.
+    #[allow(unused_variables)]
+    fn player_can_respawn(&self, player: &dyn EntityPlayer) -> bool {
+        true
+    }
+
+    /// Returns a map time when the dead player can respawn.
+    #[allow(unused_variables)]
+    fn player_spawn_time(&self, player: &dyn EntityPlayer) -> MapTime {
+        self.engine().globals.map_time()
+    }
+
+    /// Called by the dead player before all weapons and ammo are removed.
+    ///
+    /// Game rules can move weapons and ammo out of the player inventory to drop them.
+    #[allow(unused_variables)]
+    fn pack_dead_player_items(&self, player: &dyn EntityPlayer) {}
+
     fn allow_flashlight(&self) -> bool {
         false
     }
 
+    /// Returns `true` if monsters can be spawned.
+    fn allow_monsters(&self) -> bool {
+        true
+    }
+
     /// Returns `true` if the player can receive the given item.
     fn can_have_item(&self, player: &dyn EntityPlayer, item: &dyn Entity) -> bool;
 
@@ -65,6 +118,64 @@ pub trait GameRules: Any {
     ///
     /// The item will be removed from the world if `None` is returned.
     fn item_respawn(&self, item: &dyn Entity) -> Option<(MapTime, vec3_t)>;
+
+    /// Adds points to the player score.
+    ///
+    /// The score is not decreased below zero unless `allow_negative` is `true`.
+    fn add_points(&self, player: &dyn EntityPlayer, score: i32, allow_negative: bool) {
+        add_points_clamped(player, score, allow_negative);
+    }
+
+    /// Returns a team name of the given entity.
+    #[allow(unused_variables)]
+    fn get_team_id(&self, entity: &dyn Entity) -> Option<MapString> {
+        None
+    }
+
+    /// Returns an index of the team with the given name.
+    #[allow(unused_variables)]
+    fn get_team_index(&self, team_name: &CStrThin) -> Option<usize> {
+        None
+    }
+
+    /// Returns a team name by the team index.
+    #[allow(unused_variables)]
+    fn get_indexed_team_name(&self, index: usize) -> Option<MapString> {
+        None
+    }
+
+    #[allow(unused_variables)]
+    fn change_player_team(
+        &self,
+        player: &dyn EntityPlayer,
+        team_name: &CStrThin,
+        kill: bool,
+        gib: bool,
+    ) {
+    }
+
+    /// Ends the multiplayer game and starts an intermission.
+    fn end_multiplayer_game(&self) {}
+}
+
+/// Adds points to the player score.
+///
+/// The score is not decreased below zero unless `allow_negative` is `true`.
+pub fn add_points_clamped(player: &dyn EntityPlayer, score: i32, allow_negative: bool) {
+    let v = player.vars();
+    let frags = v.frags() as i32;
+    v.set_frags(clamped_score(frags, score, allow_negative) as f32);
+}
+
+/// Returns a new score after `score` points are added to `frags`.
+fn clamped_score(frags: i32, score: i32, allow_negative: bool) -> i32 {
+    if score >= 0 || allow_negative {
+        frags + score
+    } else if frags < 0 {
+        frags
+    } else {
+        frags + score.max(-frags)
+    }
 }
 
 impl dyn GameRules {
@@ -110,3 +221,18 @@ impl GameRules for StubGameRules {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::clamped_score;
+
+    #[test]
+    fn clamp_score() {
+        assert_eq!(clamped_score(3, 2, false), 5);
+        assert_eq!(clamped_score(3, -2, false), 1);
+        assert_eq!(clamped_score(3, -5, false), 0);
+        assert_eq!(clamped_score(-2, -1, false), -2);
+        assert_eq!(clamped_score(3, -5, true), -2);
+        assert_eq!(clamped_score(-2, -1, true), -3);
+    }
+}
@@ -17,7 +17,10 @@ use xash3d_shared::{
     global_state::CustomGlobals,
 };
 
+#[cfg(feature = "save")]
+use crate::save::{Restore, Save};
 use crate::{
+    damage::MultiDamage,
     engine::ServerEngineRef,
     entity::EntityHandle,
     game_rules::{GameRules, StubGameRules},
@@ -38,13 +41,17 @@ pub trait DefaultGlobal {
     fn default_global(engine: ServerEngineRef) -> Self;
 }
 
+/// The state of a global entity.
+///
+/// Saved as an integer field of [GlobalEntity].
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
-#[repr(C)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(i32)]
 pub enum EntityState {
     #[default]
-    Off,
-    On,
-    Dead,
+    Off = 0,
+    On = 1,
+    Dead = 2,
 }
 
 #[derive(Copy, Clone)]
@@ -190,6 +197,7 @@ pub struct GlobalState {
     talk_wait_time: Cell<MapTime>,
     decals: RefCell<Box<dyn Decals>>,
     sprites: RefCell<Box<dyn Sprites>>,
+    multi_damage: MultiDamage,
     customs: CustomGlobals,
 }
 
@@ -207,6 +215,7 @@ impl GlobalState {
             talk_wait_time: Default::default(),
             decals: RefCell::new(Box::new(StubDecals::new(engine))),
             sprites: RefCell::new(Box::new(StubSprites::new(engine))),
+            multi_damage: MultiDamage::default(),
             customs: CustomGlobals::default(),
         }
     }
@@ -235,6 +244,11 @@ impl GlobalState {
         self.sprites.replace(Box::new(sprites));
     }
 
+    /// Returns the damage accumulator for the current attack.
+    pub fn multi_damage(&self) -> &MultiDamage {
+        &self.multi_damage
+    }
+
     pub fn game_rules(&self) -> Ref<'_, dyn GameRules> {
         Ref::map(self.game_rules.borrow(), |i| i.as_ref())
     }
@@ -292,6 +306,7 @@ impl GlobalState {
         self.entities_mut().clear();
         self.init_hud.set(true);
         self.decals.replace(Box::new(StubDecals::new(self.engine)));
+        self.multi_damage.clear();
         self.customs.clear();
     }
 
@@ -33,6 +33,11 @@ impl ServerGlobals {
         MapTime::from_secs_f32(self.map_time_f32())
     }
 
+    /// Returns the duration of the current frame in seconds.
+    pub fn frame_time(&self) -> f32 {
+        unsafe { (*self.raw).frametime }
+    }
+
     pub fn map_name(&self) -> Option<MapString> {
         MapString::from_index(self.engine, unsafe { &*self.raw }.mapname)
     }
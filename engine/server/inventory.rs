@@ -0,0 +1,369 @@
+//! Player weapons and ammo.
+
+use core::cell::Cell;
+
+use xash3d_shared::{
+    csz::CStrThin,
+    ffi::common::{clientdata_s, weapon_data_s},
+};
+
+#[cfg(feature = "save")]
+use crate::save::{Restore, Save};
+use crate::{
+    entity::{EntityHandle, PlayerWeapon},
+    prelude::*,
+};
+
+/// The maximum number of weapons a player can carry.
+pub const MAX_WEAPONS: usize = 32;
+
+/// The maximum number of ammo types.
+pub const MAX_AMMO_SLOTS: usize = 32;
+
+/// Weapons and ammo carried by a player.
+///
+/// Weapons are entities owned by the player and indexed by [PlayerWeapon::weapon_id].
+#[derive(Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Inventory {
+    weapons: [Cell<Option<EntityHandle>>; MAX_WEAPONS],
+    active: Cell<Option<EntityHandle>>,
+    last: Cell<Option<EntityHandle>>,
+    ammo: [Cell<i32>; MAX_AMMO_SLOTS],
+    /// The delay before the player can use weapons.
+    next_attack: Cell<f32>,
+}
+
+impl Inventory {
+    pub fn weapon(&self, id: usize) -> Option<&dyn PlayerWeapon> {
+        self.weapons
+            .get(id)?
+            .get()
+            .downcast_ref::<dyn PlayerWeapon>()
+    }
+
+    pub fn has_weapon(&self, id: usize) -> bool {
+        self.weapon(id).is_some()
+    }
+
+    pub fn weapons(&self) -> impl Iterator<Item = &dyn PlayerWeapon> {
+        self.weapons
+            .iter()
+            .filter_map(|i| i.get().downcast_ref::<dyn PlayerWeapon>())
+    }
+
+    pub fn active(&self) -> Option<&dyn PlayerWeapon> {
+        self.active.get().downcast_ref::<dyn PlayerWeapon>()
+    }
+
+    pub fn last(&self) -> Option<&dyn PlayerWeapon> {
+        self.last.get().downcast_ref::<dyn PlayerWeapon>()
+    }
+
+    /// Adds a weapon to the inventory.
+    ///
+    /// Returns `false` if the slot is already occupied.
+    pub fn insert(&self, weapon: &dyn PlayerWeapon) -> bool {
+        let Some(slot) = self.weapons.get(weapon.weapon_id()) else {
+            error!("{}: invalid weapon id", weapon.pretty_name());
+            return false;
+        };
+        if slot.get().get_entity().is_some() {
+            return false;
+        }
+        slot.set(Some(weapon.entity_handle()));
+        true
+    }
+
+    /// Removes a weapon from the inventory and returns its entity.
+    pub fn remove(&self, id: usize) -> Option<EntityHandle> {
+        let ent = self.weapons.get(id)?.take()?;
+        if self.active.get() == Some(ent) {
+            self.active.set(None);
+        }
+        if self.last.get() == Some(ent) {
+            self.last.set(None);
+        }
+        Some(ent)
+    }
+
+    /// Holsters the active weapon and removes all weapons and ammo from the world.
+    pub fn remove_all(&self) {
+        if let Some(active) = self.active() {
+            active.holster();
+        }
+        for id in 0..MAX_WEAPONS {
+            if let Some(weapon) = self.remove(id).get_entity() {
+                weapon.remove_from_world();
+            }
+        }
+        for ammo in &self.ammo {
+            ammo.set(0);
+        }
+    }
+
+    pub fn ammo(&self, index: usize) -> i32 {
+        self.ammo.get(index).map_or(0, |i| i.get())
+    }
+
+    pub fn set_ammo(&self, index: usize, count: i32) {
+        if let Some(ammo) = self.ammo.get(index) {
+            ammo.set(count);
+        }
+    }
+
+    /// Adds ammo up to `max` and returns the added count.
+    pub fn give_ammo(&self, index: usize, count: i32, max: i32) -> i32 {
+        let Some(ammo) = self.ammo.get(index) else {
+            return 0;
+        };
+        let added = count.min(max - ammo.get()).max(0);
+        ammo.set(ammo.get() + added);
+        added
+    }
+
+    pub fn next_attack(&self) -> f32 {
+        self.next_attack.get()
+    }
+
+    pub fn set_next_attack(&self, delay: f32) {
+        self.next_attack.set(delay);
+    }
+
+    /// Holsters the active weapon and deploys the given weapon.
+    ///
+    /// Returns `false` if weapons can not be switched.
+    pub fn switch_weapon(&self, weapon: &dyn PlayerWeapon) -> bool {
+        let ent = weapon.entity_handle();
+        if self.active.get() == Some(ent) {
+            return true;
+        }
+        if !weapon.can_deploy() {
+            return false;
+        }
+        if let Some(active) = self.active() {
+            if !active.can_holster() {
+                return false;
+            }
+            active.holster();
+        }
+        self.last.set(self.active.get());
+        self.active.set(Some(ent));
+        weapon.deploy();
+        true
+    }
+
+    /// Switches to a weapon with the given class name.
+    pub fn select_weapon(&self, name: &CStrThin) -> bool {
+        match self.weapons().find(|i| i.is_classname(name)) {
+            Some(weapon) => self.switch_weapon(weapon),
+            None => false,
+        }
+    }
+
+    /// Switches to the previously active weapon.
+    pub fn select_last(&self) -> bool {
+        match self.last() {
+            Some(weapon) => self.switch_weapon(weapon),
+            None => false,
+        }
+    }
+
+    /// Switches to the weapon with the highest weight except the active one.
+    pub fn select_best(&self) -> bool {
+        let active = self.active.get();
+        let best = self
+            .weapons()
+            .filter(|i| Some(i.entity_handle()) != active && i.can_deploy())
+            .max_by_key(|i| i.weight());
+        match best {
+            Some(weapon) => self.switch_weapon(weapon),
+            None => false,
+        }
+    }
+
+    /// Runs the active weapon logic if the player can attack.
+    pub fn item_post_frame(&self) {
+        if self.next_attack.get() > 0.0 {
+            return;
+        }
+        if let Some(weapon) = self.active() {
+            weapon.item_post_frame();
+        }
+    }
+
+    pub fn decrement_timers(&self, time: f32) {
+        for weapon in self.weapons() {
+            weapon.decrement_timers(time);
+        }
+        self.next_attack
+            .set((self.next_attack.get() - time).max(-0.001));
+    }
+
+    /// Fills weapon states indexed by weapon ids.
+    pub fn weapon_data(&self, info: &mut [weapon_data_s]) {
+        for weapon in self.weapons() {
+            if let Some(data) = info.get_mut(weapon.weapon_id()) {
+                weapon.weapon_data(data);
+            }
+        }
+    }
+
+    pub fn client_data(&self, cd: &mut clientdata_s) {
+        cd.m_flNextAttack = self.next_attack.get();
+        if let Some(weapon) = self.active() {
+            cd.m_iId = weapon.weapon_id() as i32;
+            weapon.client_data(cd);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "save"))]
+mod tests {
+    use crate::{
+        entity::{BaseEntity, EntityItem, delegate_entity},
+        prelude::*,
+        private::impl_private,
+        testing,
+    };
+
+    use super::*;
+
+    #[derive(Save, Restore)]
+    struct TestWeapon {
+        base: BaseEntity,
+        id: usize,
+        deployable: Cell<bool>,
+        holsterable: Cell<bool>,
+        deploys: Cell<i32>,
+        holsters: Cell<i32>,
+    }
+
+    impl CreateEntity for TestWeapon {
+        fn create(base: BaseEntity) -> Self {
+            Self {
+                base,
+                id: 0,
+                deployable: Cell::new(true),
+                holsterable: Cell::new(true),
+                deploys: Cell::new(0),
+                holsters: Cell::new(0),
+            }
+        }
+    }
+
+    impl Entity for TestWeapon {
+        delegate_entity!(base);
+    }
+
+    impl EntityItem for TestWeapon {
+        fn try_give(&self, _: &dyn Entity) -> bool {
+            false
+        }
+    }
+
+    impl PlayerWeapon for TestWeapon {
+        fn weapon_id(&self) -> usize {
+            self.id
+        }
+
+        fn can_deploy(&self) -> bool {
+            self.deployable.get()
+        }
+
+        fn can_holster(&self) -> bool {
+            self.holsterable.get()
+        }
+
+        fn deploy(&self) {
+            self.deploys.set(self.deploys.get() + 1);
+        }
+
+        fn holster(&self) {
+            self.holsters.set(self.holsters.get() + 1);
+        }
+
+        fn item_post_frame(&self) {}
+
+        fn decrement_timers(&self, _: f32) {}
+
+        fn weapon_data(&self, _: &mut weapon_data_s) {}
+    }
+
+    impl_private!(TestWeapon {
+        EntityItem,
+        PlayerWeapon
+    });
+
+    fn active_id(inventory: &Inventory) -> Option<usize> {
+        inventory.active().map(|i| i.weapon_id())
+    }
+
+    #[test]
+    fn give_ammo() {
+        let inventory = Inventory::default();
+        assert_eq!(inventory.give_ammo(2, 10, 25), 10);
+        assert_eq!(inventory.give_ammo(2, 20, 25), 15);
+        assert_eq!(inventory.ammo(2), 25);
+        assert_eq!(inventory.give_ammo(2, 5, 25), 0);
+        assert_eq!(inventory.ammo(2), 25);
+
+        // the limit is lower than the carried ammo
+        assert_eq!(inventory.give_ammo(2, 5, 10), 0);
+        assert_eq!(inventory.ammo(2), 25);
+
+        assert_eq!(inventory.give_ammo(MAX_AMMO_SLOTS, 5, 25), 0);
+        assert_eq!(inventory.ammo(MAX_AMMO_SLOTS), 0);
+    }
+
+    #[test]
+    fn switch_weapon() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let crowbar = engine.new_entity::<TestWeapon>().build();
+        crowbar.id = 1;
+        let glock = engine.new_entity::<TestWeapon>().build();
+        glock.id = 2;
+        let (crowbar, glock) = (&*crowbar, &*glock);
+
+        let inventory = Inventory::default();
+        assert!(inventory.insert(crowbar));
+        assert!(inventory.insert(glock));
+        assert!(!inventory.insert(glock));
+
+        assert!(inventory.switch_weapon(crowbar));
+        assert_eq!(active_id(&inventory), Some(1));
+        assert_eq!(crowbar.deploys.get(), 1);
+
+        // already active
+        assert!(inventory.switch_weapon(crowbar));
+        assert_eq!(crowbar.deploys.get(), 1);
+
+        glock.deployable.set(false);
+        assert!(!inventory.switch_weapon(glock));
+        glock.deployable.set(true);
+        crowbar.holsterable.set(false);
+        assert!(!inventory.switch_weapon(glock));
+        assert_eq!(active_id(&inventory), Some(1));
+        assert_eq!(glock.deploys.get(), 0);
+
+        crowbar.holsterable.set(true);
+        assert!(inventory.switch_weapon(glock));
+        assert_eq!(active_id(&inventory), Some(2));
+        assert_eq!(crowbar.holsters.get(), 1);
+        assert_eq!(glock.deploys.get(), 1);
+        assert_eq!(inventory.last().map(|i| i.weapon_id()), Some(1));
+
+        assert!(inventory.select_last());
+        assert_eq!(active_id(&inventory), Some(1));
+
+        assert!(inventory.remove(1).is_some());
+        assert!(inventory.active().is_none());
+        assert!(!inventory.has_weapon(1));
+
+        unsafe {
+            engine.remove_entity_now(crowbar.vars());
+            engine.remove_entity_now(glock.vars());
+        }
+    }
+}
@@ -18,9 +18,11 @@ extern crate self as xash3d_server;
 #[macro_use]
 pub mod macros;
 
+pub mod animating;
 pub mod change_level;
 pub mod consts;
 pub mod cvar;
+pub mod damage;
 pub mod engine;
 pub mod entities;
 pub mod entity;
@@ -29,14 +31,20 @@ pub mod game_rules;
 pub mod global_state;
 pub mod globals;
 pub mod instance;
+pub mod inventory;
 mod logger;
+pub mod map_check;
+pub mod monster;
+pub mod node_graph;
 pub mod prelude;
 pub mod private;
 pub mod save;
 pub mod sound;
 pub mod str;
+#[cfg(all(feature = "std", feature = "save"))]
+pub mod testing;
 pub mod time;
 pub mod user_message;
 pub mod utils;
 
-pub use xash3d_shared::{cell, color, csz, ffi, math, parser, render};
+pub use xash3d_shared::{cell, color, csz, ffi, math, parser, render, studio};
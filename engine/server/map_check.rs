@@ -0,0 +1,376 @@
+//! Map entity validation.
+//!
+//! [check_entities] checks an entity list parsed with
+//! [entity_lump](xash3d_shared::entity_lump) against registered entity classes. It works without
+//! the engine if classes are given with [ClassList]. [EngineClasses] uses the server DLL exports
+//! and reports keys that entities do not handle.
+
+use core::fmt;
+
+use alloc::{
+    collections::btree_set::BTreeSet,
+    ffi::CString,
+    string::{String, ToString},
+    vec::Vec,
+};
+use xash3d_shared::{
+    bsp::{Bsp, BspError},
+    entity_lump::{self, EntityKeys},
+    ffi::server::KeyValueData,
+};
+
+use crate::{entity::KeyValue, prelude::*};
+
+/// Keys that reference other entities by a target name.
+const TARGET_KEYS: &[&str] = &["target", "killtarget", "master"];
+
+/// Registered entity classes.
+pub trait EntityClasses {
+    /// Returns keys the entity does not handle or `None` if the class is not registered.
+    fn unhandled_keys(&mut self, entity: &EntityKeys) -> Option<Vec<String>>;
+}
+
+/// A list of registered class names.
+///
+/// Keys are not checked.
+#[derive(Copy, Clone, Debug)]
+pub struct ClassList<'a>(pub &'a [&'a str]);
+
+impl EntityClasses for ClassList<'_> {
+    fn unhandled_keys(&mut self, entity: &EntityKeys) -> Option<Vec<String>> {
+        let classname = entity.classname()?;
+        self.0.contains(&classname).then(Vec::new)
+    }
+}
+
+/// Entity classes exported by the server DLL.
+///
+/// Creates a temporary entity for each checked entity and passes all keys to it. Entities are
+/// removed without spawning. The world entity is not created.
+pub struct EngineClasses {
+    engine: ServerEngineRef,
+}
+
+impl EngineClasses {
+    pub fn new(engine: ServerEngineRef) -> Self {
+        Self { engine }
+    }
+}
+
+impl EntityClasses for EngineClasses {
+    fn unhandled_keys(&mut self, entity: &EntityKeys) -> Option<Vec<String>> {
+        let classname = entity.classname()?;
+        if classname == "worldspawn" {
+            return Some(Vec::new());
+        }
+        let classname = CString::new(classname).ok()?;
+        let mut ent = self.engine.create_named_entity(classname.as_c_str())?;
+        let mut unhandled = Vec::new();
+        for (key, value) in entity.iter() {
+            if key == "classname" {
+                continue;
+            }
+            let (key, value) = engine_key_value(key, value);
+            let (Ok(c_key), Ok(c_value)) = (CString::new(key.as_str()), CString::new(value)) else {
+                unhandled.push(key);
+                continue;
+            };
+            let mut raw = KeyValueData {
+                szClassName: classname.as_ptr().cast_mut(),
+                szKeyName: c_key.as_ptr().cast_mut(),
+                szValue: c_value.as_ptr().cast_mut(),
+                fHandled: 0,
+            };
+            let data = KeyValue::new(&mut raw);
+            unsafe {
+                ent.vars().key_value(data);
+            }
+            if !data.handled() {
+                if let Some(entity) = unsafe { ent.get_entity_mut() } {
+                    entity.key_value(data);
+                }
+            }
+            if !data.handled() {
+                unhandled.push(key);
+            }
+        }
+        unsafe {
+            self.engine.remove_entity_now(&ent);
+        }
+        Some(unhandled)
+    }
+}
+
+/// Converts keys the same way as the engine before they are passed to the server DLL.
+fn engine_key_value(key: &str, value: &str) -> (String, String) {
+    if key != "angle" {
+        return (key.to_string(), value.to_string());
+    }
+    let yaw: f32 = value.trim().parse().unwrap_or(0.0);
+    let angles = if yaw >= 0.0 {
+        format!("0 {yaw} 0")
+    } else if yaw == -1.0 {
+        "-90 0 0".to_string()
+    } else if yaw == -2.0 {
+        "90 0 0".to_string()
+    } else {
+        "0 0 0".to_string()
+    };
+    ("angles".to_string(), angles)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapIssueKind {
+    MissingClassname,
+    UnknownClass,
+    UnhandledKey {
+        key: String,
+    },
+    /// No entity has the referenced target name.
+    MissingTarget {
+        key: String,
+        target: String,
+    },
+    /// A `trigger_changelevel` has no `landmark` key.
+    NoLandmark,
+    /// No `info_landmark` has the target name of the `landmark` key.
+    MissingLandmark {
+        landmark: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapIssue {
+    /// The index of the entity in the entity lump.
+    pub entity: usize,
+    pub classname: String,
+    pub kind: MapIssueKind,
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}: ", self.entity, self.classname)?;
+        match &self.kind {
+            MapIssueKind::MissingClassname => write!(f, "no classname"),
+            MapIssueKind::UnknownClass => write!(f, "unknown class"),
+            MapIssueKind::UnhandledKey { key } => write!(f, "unhandled key \"{key}\""),
+            MapIssueKind::MissingTarget { key, target } => {
+                write!(f, "{key} \"{target}\" does not exist")
+            }
+            MapIssueKind::NoLandmark => write!(f, "no landmark"),
+            MapIssueKind::MissingLandmark { landmark } => {
+                write!(f, "info_landmark \"{landmark}\" does not exist")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MapReport {
+    /// The number of checked entities.
+    pub entities: usize,
+    pub issues: Vec<MapIssue>,
+}
+
+impl MapReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for MapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{issue}")?;
+        }
+        write!(
+            f,
+            "{} entities, {} issues",
+            self.entities,
+            self.issues.len()
+        )
+    }
+}
+
+/// Checks entities against registered classes and target references.
+pub fn check_entities(entities: &[EntityKeys], classes: &mut impl EntityClasses) -> MapReport {
+    let mut names = BTreeSet::new();
+    let mut landmarks = BTreeSet::new();
+    for keys in entities {
+        if let Some(name) = keys.get("targetname") {
+            names.insert(name);
+            if keys.classname() == Some("info_landmark") {
+                landmarks.insert(name);
+            }
+        }
+    }
+
+    let mut issues = Vec::new();
+    for (index, keys) in entities.iter().enumerate() {
+        let classname = keys.classname().unwrap_or_default();
+        let mut push = |kind| {
+            issues.push(MapIssue {
+                entity: index,
+                classname: classname.to_string(),
+                kind,
+            })
+        };
+
+        if classname.is_empty() {
+            push(MapIssueKind::MissingClassname);
+            continue;
+        }
+
+        match classes.unhandled_keys(keys) {
+            Some(unhandled) => {
+                for key in unhandled {
+                    push(MapIssueKind::UnhandledKey { key });
+                }
+            }
+            None => push(MapIssueKind::UnknownClass),
+        }
+
+        for (key, target) in keys.iter() {
+            let is_reference = TARGET_KEYS.contains(&key);
+            if is_reference && !target.is_empty() && !names.contains(target) {
+                push(MapIssueKind::MissingTarget {
+                    key: key.to_string(),
+                    target: target.to_string(),
+                });
+            }
+        }
+
+        if classname == "trigger_changelevel" {
+            match keys.get("landmark") {
+                None | Some("") => push(MapIssueKind::NoLandmark),
+                Some(landmark) if !landmarks.contains(landmark) => {
+                    push(MapIssueKind::MissingLandmark {
+                        landmark: landmark.to_string(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    MapReport {
+        entities: entities.len(),
+        issues,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapCheckError {
+    LoadFile,
+    Bsp(BspError),
+    /// Failed to parse the entity lump.
+    Entities(String),
+}
+
+impl fmt::Display for MapCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LoadFile => write!(f, "failed to load the map"),
+            Self::Bsp(err) => write!(f, "invalid map, {err}"),
+            Self::Entities(err) => write!(f, "invalid entity lump, {err}"),
+        }
+    }
+}
+
+/// Checks entities of a map file with classes from [EngineClasses].
+pub fn check_map(engine: ServerEngineRef, map_name: &str) -> Result<MapReport, MapCheckError> {
+    let path = format!("maps/{map_name}.bsp");
+    let file = engine
+        .load_file(path.as_str())
+        .map_err(|_| MapCheckError::LoadFile)?;
+    let bsp = Bsp::parse(file.as_bytes()).map_err(MapCheckError::Bsp)?;
+    let src = String::from_utf8_lossy(bsp.entities());
+    let entities =
+        entity_lump::parse(&src).map_err(|err| MapCheckError::Entities(err.to_string()))?;
+    Ok(check_entities(&entities, &mut EngineClasses::new(engine)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LUMP: &str = r#"{
+"classname" "worldspawn"
+}
+{
+"classname" "trigger_once"
+"target" "door1"
+"killtarget" "missing"
+"master" ""
+}
+{
+"classname" "func_door"
+"targetname" "door1"
+}
+{
+"classname" "monster_unknown"
+}
+{
+"origin" "0 0 0"
+}
+{
+"classname" "trigger_changelevel"
+"map" "c1a1"
+"landmark" "c1a0c"
+}
+{
+"classname" "trigger_changelevel"
+"map" "c1a1"
+"landmark" "c1a0b"
+}
+{
+"classname" "trigger_changelevel"
+"map" "c1a1"
+}
+{
+"classname" "info_landmark"
+"targetname" "c1a0c"
+}
+"#;
+
+    #[test]
+    fn check_lump() {
+        let entities = entity_lump::parse(LUMP).unwrap();
+        let classes = [
+            "worldspawn",
+            "trigger_once",
+            "func_door",
+            "trigger_changelevel",
+            "info_landmark",
+        ];
+        let report = check_entities(&entities, &mut ClassList(&classes));
+        let issues: Vec<_> = report.issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            issues,
+            [
+                "#1 trigger_once: killtarget \"missing\" does not exist",
+                "#3 monster_unknown: unknown class",
+                "#4 : no classname",
+                "#6 trigger_changelevel: info_landmark \"c1a0b\" does not exist",
+                "#7 trigger_changelevel: no landmark",
+            ]
+        );
+        assert_eq!(report.entities, 9);
+    }
+
+    #[test]
+    fn angle_key() {
+        assert_eq!(
+            engine_key_value("angle", "90"),
+            ("angles".to_string(), "0 90 0".to_string())
+        );
+        assert_eq!(
+            engine_key_value("angle", "-1"),
+            ("angles".to_string(), "-90 0 0".to_string())
+        );
+        assert_eq!(
+            engine_key_value("speed", "100"),
+            ("speed".to_string(), "100".to_string())
+        );
+    }
+}
@@ -0,0 +1,2424 @@
+//! Base monster AI.
+//!
+//! A monster collects [Conditions] with its senses every think and runs a [Schedule] selected
+//! for the current [MonsterState]. A schedule is a list of [tasks](Task) and it is interrupted
+//! if one of its interrupt conditions is set.
+//!
+//! Concrete monsters wrap [BaseMonster] and override [EntityMonster] hooks.
+
+mod activity;
+mod relationship;
+mod route;
+mod schedule;
+mod script;
+mod senses;
+
+use core::cell::{Cell, RefCell};
+
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use xash3d_shared::{
+    csz::CStrThin,
+    ffi::common::vec3_t,
+    math::{ToAngleVectors, angle_mod},
+    render::RenderMode,
+    studio::Event,
+};
+
+#[cfg(feature = "save")]
+use crate::save;
+use crate::{
+    animating::Animating,
+    damage::HitGroup,
+    engine::{DropToFloorResult, TraceResult, WalkMove},
+    entity::{
+        BaseEntity, DamageFlags, Dead, EdictFlags, Effects, EntityHandle, EntityVars, Gib,
+        MoveType, Solid, TakeDamage, UseType, define_entity_trait, delegate_entity,
+    },
+    node_graph::{Hull, NodeGraph},
+    prelude::*,
+    private::impl_private,
+    sound::Attenuation,
+    str::{MapString, ToEngineStr},
+    time::MapTime,
+    utils::{self, ViewField},
+};
+
+pub use self::{
+    activity::Activity,
+    relationship::{Class, Relationship},
+    route::{LOCAL_STEP_SIZE, MoveGoal, Route, check_local_move},
+    schedule::{Schedule, ScheduleType, Task, TaskStatus},
+    script::{
+        EntityScript, InterruptLevel, SCRIPT_BREAK_CONDITIONS, ScriptMoveTo, ScriptSpawnFlags,
+        ScriptState, script_event,
+    },
+    senses::*,
+};
+
+/// A high level state of a monster.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum MonsterState {
+    #[default]
+    None,
+    Idle,
+    Combat,
+    Alert,
+    Hunt,
+    /// Held by a barnacle.
+    Prone,
+    /// Controlled by a scripted sequence.
+    Script,
+    PlayDead,
+    Dead,
+}
+
+bitflags! {
+    /// Monster spawn flags shared by all monsters.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub struct MonsterSpawnFlags: u32 {
+        /// Do not attack until the player sees the monster.
+        const WAIT_TILL_SEEN    = 1 << 0;
+        /// Do not make idle and alert sounds.
+        const GAG               = 1 << 1;
+        const HIT_MONSTER_CLIP  = 1 << 2;
+        /// Do not see anything.
+        const PRISONER          = 1 << 4;
+        const WAIT_FOR_SCRIPT   = 1 << 7;
+        const PRE_DISASTER      = 1 << 8;
+        const FADE_CORPSE       = 1 << 9;
+        const FALL_TO_GROUND    = 1 << 31;
+    }
+}
+
+bitflags! {
+    /// Things a monster knows about the world at the moment.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct Conditions: u32 {
+        const NO_AMMO_LOADED    = 1 << 0;
+        const SEE_HATE          = 1 << 1;
+        const SEE_FEAR          = 1 << 2;
+        const SEE_DISLIKE       = 1 << 3;
+        const SEE_ENEMY         = 1 << 4;
+        const ENEMY_OCCLUDED    = 1 << 5;
+        const SMELL_FOOD        = 1 << 6;
+        const ENEMY_TOO_FAR     = 1 << 7;
+        const LIGHT_DAMAGE      = 1 << 8;
+        const HEAVY_DAMAGE      = 1 << 9;
+        const CAN_RANGE_ATTACK1 = 1 << 10;
+        const CAN_MELEE_ATTACK1 = 1 << 11;
+        const CAN_RANGE_ATTACK2 = 1 << 12;
+        const CAN_MELEE_ATTACK2 = 1 << 13;
+        const PROVOKED          = 1 << 15;
+        const NEW_ENEMY         = 1 << 16;
+        const HEAR_SOUND        = 1 << 17;
+        const SMELL             = 1 << 18;
+        const ENEMY_FACING_ME   = 1 << 19;
+        const ENEMY_DEAD        = 1 << 20;
+        const SEE_CLIENT        = 1 << 21;
+        const SEE_NEMESIS       = 1 << 22;
+        const SPECIAL1          = 1 << 28;
+        const SPECIAL2          = 1 << 29;
+        const TASK_FAILED       = 1 << 30;
+        const SCHEDULE_DONE     = 1 << 31;
+
+        const ALL_SPECIAL       = Self::SPECIAL1.bits() | Self::SPECIAL2.bits();
+        const CAN_ATTACK        = Self::CAN_RANGE_ATTACK1.bits()
+                                | Self::CAN_MELEE_ATTACK1.bits()
+                                | Self::CAN_RANGE_ATTACK2.bits()
+                                | Self::CAN_MELEE_ATTACK2.bits();
+        const SEE_ANY           = Self::SEE_HATE.bits()
+                                | Self::SEE_FEAR.bits()
+                                | Self::SEE_DISLIKE.bits()
+                                | Self::SEE_ENEMY.bits()
+                                | Self::SEE_CLIENT.bits()
+                                | Self::SEE_NEMESIS.bits();
+    }
+}
+
+bitflags! {
+    /// Things a monster remembers between schedules.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct Memory: u32 {
+        /// Right now only used for houndeyes.
+        const PROVOKED      = 1 << 0;
+        /// The monster knows it is in a covered position.
+        const IN_COVER      = 1 << 1;
+        /// Ally is suspicious of the player and will not follow.
+        const SUSPICIOUS    = 1 << 2;
+        /// Finished monster path (just used by big momma for now).
+        const PATH_FINISHED = 1 << 3;
+        /// Moving on a path.
+        const ON_PATH       = 1 << 4;
+        /// Movement has already failed.
+        const MOVE_FAILED   = 1 << 5;
+        /// Has already flinched.
+        const FLINCHED      = 1 << 6;
+        /// Has already been killed, gibbed.
+        const KILLED        = 1 << 7;
+        const CUSTOM4       = 1 << 28;
+        const CUSTOM3       = 1 << 29;
+        const CUSTOM2       = 1 << 30;
+        const CUSTOM1       = 1 << 31;
+    }
+}
+
+bitflags! {
+    /// Things a monster is able to do.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct Capabilities: u32 {
+        const DUCK          = 1 << 0;
+        const JUMP          = 1 << 1;
+        const STRAFE        = 1 << 2;
+        const SQUAD         = 1 << 3;
+        const SWIM          = 1 << 4;
+        const CLIMB         = 1 << 5;
+        /// Can use buttons and doors.
+        const USE           = 1 << 6;
+        const HEAR          = 1 << 7;
+        /// Can trigger auto doors.
+        const AUTO_DOORS    = 1 << 8;
+        /// Can open manual doors.
+        const OPEN_DOORS    = 1 << 9;
+        const TURN_HEAD     = 1 << 10;
+        const RANGE_ATTACK1 = 1 << 11;
+        const RANGE_ATTACK2 = 1 << 12;
+        const MELEE_ATTACK1 = 1 << 13;
+        const MELEE_ATTACK2 = 1 << 14;
+        const FLY           = 1 << 15;
+    }
+}
+
+define_entity_trait! {
+    /// The base trait for all monsters.
+    pub trait EntityMonster(delegate_monster): (Entity) {
+        /// Returns a shared reference to the base monster.
+        fn base_monster(&self) -> &::xash3d_server::monster::BaseMonster;
+
+        /// Returns how this monster feels about `other`.
+        fn relationship(
+            &self,
+            other: &dyn ::xash3d_server::entity::Entity,
+        ) -> ::xash3d_server::monster::Relationship;
+
+        /// Selects a schedule for the current state and conditions.
+        fn get_schedule(&self) -> &'static ::xash3d_server::monster::Schedule;
+
+        /// Returns a schedule of the given type.
+        fn schedule_of_type(
+            &self,
+            ty: ::xash3d_server::monster::ScheduleType,
+        ) -> &'static ::xash3d_server::monster::Schedule;
+
+        /// Called once when the task is started.
+        fn start_task(&self, task: &::xash3d_server::monster::Task);
+
+        /// Called every think until the task is completed or failed.
+        fn run_task(&self, task: &::xash3d_server::monster::Task);
+
+        fn check_range_attack1(&self, dot: f32, dist: f32) -> bool;
+
+        fn check_range_attack2(&self, dot: f32, dist: f32) -> bool;
+
+        fn check_melee_attack1(&self, dot: f32, dist: f32) -> bool;
+
+        fn check_melee_attack2(&self, dot: f32, dist: f32) -> bool;
+
+        /// Returns conditions that are cleared right after they are collected by senses.
+        fn ignore_conditions(&self) -> ::xash3d_server::monster::Conditions;
+
+        /// Returns types of sounds this monster can hear.
+        fn sound_mask(&self) -> ::xash3d_server::monster::SoundTypes;
+
+        /// Starts a sequence for the activity.
+        fn set_activity(&self, activity: ::xash3d_server::monster::Activity);
+
+        /// Advances the current sequence by `interval` seconds and dispatches its events.
+        fn frame_advance(&self, interval: f32);
+
+        /// Handles a server event of the current sequence.
+        fn handle_anim_event(&self, event: &::xash3d_server::studio::Event);
+
+        /// Returns `true` if the monster can speak a scripted sentence now.
+        fn can_play_sentence(&self, disregard_state: bool) -> bool;
+
+        /// Speaks a sentence or a random sentence from a group for a scripted sentence.
+        fn play_scripted_sentence(
+            &self,
+            sentence: &::xash3d_server::csz::CStrThin,
+            duration: f32,
+            volume: f32,
+            attenuation: ::xash3d_server::sound::Attenuation,
+            concurrent: bool,
+            listener: Option<&dyn ::xash3d_server::entity::Entity>,
+        );
+
+        fn idle_sound(&self);
+
+        fn alert_sound(&self);
+
+        fn pain_sound(&self);
+
+        fn death_sound(&self);
+
+        /// Called if the monster is killed with too much damage.
+        fn gib(&self);
+    }
+}
+
+/// Base type for all monsters.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct BaseMonster {
+    base: BaseEntity,
+
+    started: Cell<bool>,
+    state: Cell<MonsterState>,
+    ideal_state: Cell<MonsterState>,
+    conditions: Cell<Conditions>,
+    memory: Cell<Memory>,
+    capabilities: Cell<Capabilities>,
+
+    enemy: Cell<Option<EntityHandle>>,
+    /// The last known position of the enemy.
+    enemy_lkp: Cell<vec3_t>,
+    old_enemies: Cell<[Option<EntityHandle>; Self::MAX_OLD_ENEMIES]>,
+    old_enemies_lkp: Cell<[vec3_t; Self::MAX_OLD_ENEMIES]>,
+    target_ent: Cell<Option<EntityHandle>>,
+
+    field_of_view: Cell<ViewField>,
+    dist_look: Cell<f32>,
+    dist_too_far: Cell<f32>,
+
+    activity: Cell<Activity>,
+    ideal_activity: Cell<Activity>,
+    movement_activity: Cell<Activity>,
+    animating: Animating,
+
+    wait_finished: Cell<MapTime>,
+    move_wait_finished: Cell<MapTime>,
+    fail_schedule: Cell<ScheduleType>,
+    task_status: Cell<TaskStatus>,
+    move_goal: Cell<MoveGoal>,
+    move_goal_position: Cell<vec3_t>,
+
+    /// The scripted sequence that controls the monster.
+    script: Cell<Option<EntityHandle>>,
+    script_state: Cell<ScriptState>,
+    /// The corpse is fading out after a scripted death.
+    fade_out: Cell<bool>,
+
+    // schedules and routes are not saved
+    #[cfg_attr(feature = "save", save(skip))]
+    schedule: Cell<Option<&'static Schedule>>,
+    #[cfg_attr(feature = "save", save(skip))]
+    schedule_index: Cell<usize>,
+    #[cfg_attr(feature = "save", save(skip))]
+    route: Cell<Route>,
+    /// The route ends before the goal and is rebuilt when finished.
+    #[cfg_attr(feature = "save", save(skip))]
+    route_truncated: Cell<bool>,
+    #[cfg_attr(feature = "save", save(skip))]
+    last_hit_group: Cell<HitGroup>,
+    /// Entities seen by the last look.
+    #[cfg_attr(feature = "save", save(skip))]
+    seen: RefCell<Vec<EntityHandle>>,
+    /// The nearest sound heard by the last listen.
+    #[cfg_attr(feature = "save", save(skip))]
+    best_sound: Cell<Option<AiSound>>,
+}
+
+impl CreateEntity for BaseMonster {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+
+            started: Cell::default(),
+            state: Cell::default(),
+            ideal_state: Cell::default(),
+            conditions: Cell::default(),
+            memory: Cell::default(),
+            capabilities: Cell::default(),
+
+            enemy: Cell::default(),
+            enemy_lkp: Cell::default(),
+            old_enemies: Cell::default(),
+            old_enemies_lkp: Cell::default(),
+            target_ent: Cell::default(),
+
+            field_of_view: Cell::new(ViewField::FOV),
+            dist_look: Cell::new(Self::DEFAULT_DIST_LOOK),
+            dist_too_far: Cell::new(Self::DEFAULT_DIST_TOO_FAR),
+
+            activity: Cell::default(),
+            ideal_activity: Cell::default(),
+            movement_activity: Cell::new(Activity::Walk),
+            animating: Animating::default(),
+
+            wait_finished: Cell::default(),
+            move_wait_finished: Cell::default(),
+            fail_schedule: Cell::default(),
+            task_status: Cell::default(),
+            move_goal: Cell::default(),
+            move_goal_position: Cell::default(),
+
+            script: Cell::default(),
+            script_state: Cell::default(),
+            fade_out: Cell::default(),
+
+            schedule: Cell::default(),
+            schedule_index: Cell::default(),
+            route: Cell::default(),
+            route_truncated: Cell::default(),
+            last_hit_group: Cell::default(),
+            seen: RefCell::default(),
+            best_sound: Cell::default(),
+        }
+    }
+}
+
+#[cfg(feature = "save")]
+impl save::OnRestore for BaseMonster {
+    fn on_restore(&self) {
+        self.route_clear();
+        self.schedule.set(None);
+        self.task_status.set(TaskStatus::New);
+        self.activity.set(Activity::Reset);
+        if self.enemy.get().is_none() {
+            self.conditions.set(Conditions::empty());
+        }
+    }
+}
+
+impl BaseMonster {
+    const MAX_OLD_ENEMIES: usize = 4;
+    const THINK_INTERVAL: f32 = 0.1;
+    const DEFAULT_DIST_LOOK: f32 = 2048.0;
+    const DEFAULT_DIST_TOO_FAR: f32 = 1024.0;
+    /// Health at which a monster is gibbed instead of dying normally.
+    const GIB_HEALTH: f32 = -30.0;
+    /// The maximum distance to search for cover.
+    const COVER_RADIUS: f32 = 784.0;
+    /// The distance at which a waypoint is reached.
+    const WAYPOINT_RADIUS: f32 = 8.0;
+
+    /// Returns the outermost monster to call overridden hooks.
+    fn monster(&self) -> &dyn EntityMonster {
+        self.private()
+            .downcast_ref::<dyn EntityMonster>()
+            .unwrap_or(self)
+    }
+
+    pub fn spawn_flags(&self) -> MonsterSpawnFlags {
+        MonsterSpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    /// Initializes common monster fields.
+    ///
+    /// Must be called at the end of spawn after the model, the size and the health are set.
+    pub fn monster_init(&self) {
+        let engine = self.engine();
+        if !self.global_state().game_rules().allow_monsters() {
+            self.vars().delayed_remove();
+            return;
+        }
+
+        let v = self.vars();
+        v.set_take_damage(TakeDamage::Aim);
+        v.set_ideal_yaw(v.angles().y);
+        v.set_max_health(v.health());
+        v.set_dead(Dead::No);
+        v.with_flags(|f| f | EdictFlags::MONSTER);
+
+        self.ideal_state.set(MonsterState::Idle);
+        self.ideal_activity.set(Activity::Idle);
+        self.clear_schedule();
+        self.route_clear();
+        self.enemy.set(None);
+        self.seen.borrow_mut().clear();
+        self.started.set(false);
+
+        // wait for other entities to spawn
+        v.set_next_think_time(engine.globals.map_time() + Self::THINK_INTERVAL);
+    }
+
+    fn start_monster(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+
+        if v.move_type() != MoveType::Fly
+            && !self
+                .spawn_flags()
+                .intersects(MonsterSpawnFlags::FALL_TO_GROUND)
+        {
+            v.with_origin(|o| o + vec3_t::new(0.0, 0.0, 1.0));
+            if engine.drop_to_floor(v) == DropToFloorResult::AllSolid
+                || !engine.walk_move(v, 0.0, 0.0, WalkMove::Normal)
+            {
+                let name = self.pretty_name();
+                let origin = v.origin();
+                warn!("{name}: stuck in wall at {origin}");
+            }
+        } else {
+            v.with_flags(|f| f.difference(EdictFlags::ONGROUND));
+        }
+
+        if let Some(target) = self.target_entity() {
+            self.target_ent.set(Some(target.entity_handle()));
+            if !self.build_route(target.vars().origin(), MoveGoal::Target) {
+                let name = self.pretty_name();
+                let target = target.pretty_name();
+                warn!("{name}: failed to build a route to {target}");
+            }
+        }
+
+        self.started.set(true);
+        // spread think times so monsters spawned at once do not think at the same frame
+        v.set_next_think_time_from_now(engine.random_float(0.1, 0.4));
+    }
+
+    fn monster_think(&self) {
+        let v = self.vars();
+        v.set_next_think_time_from_now(Self::THINK_INTERVAL);
+
+        let monster = self.monster();
+        self.run_ai(monster);
+        monster.frame_advance(Self::THINK_INTERVAL);
+
+        let state = self.state.get();
+        if state != MonsterState::Script
+            && state != MonsterState::Dead
+            && self.activity.get() == Activity::Idle
+            && self.animating.is_sequence_finished()
+        {
+            // restart the idle sequence
+            monster.set_activity(Activity::Idle);
+        }
+
+        if !self.is_movement_complete() {
+            self.move_step(Self::THINK_INTERVAL);
+        }
+    }
+
+    fn run_ai(&self, monster: &dyn EntityMonster) {
+        let engine = self.engine();
+        let state = self.state.get();
+
+        if matches!(state, MonsterState::Idle | MonsterState::Alert)
+            && engine.random_int(0, 99) == 0
+            && !self.spawn_flags().intersects(MonsterSpawnFlags::GAG)
+        {
+            monster.idle_sound();
+        }
+
+        if !matches!(
+            state,
+            MonsterState::None | MonsterState::Prone | MonsterState::Dead
+        ) {
+            // do not think about enemies if no player can see us
+            if engine.find_client_in_pvs(self.vars()).is_some() || state == MonsterState::Combat {
+                self.look(monster, self.dist_look.get());
+                self.listen(monster);
+                self.clear_conditions(monster.ignore_conditions());
+                self.get_enemy();
+            }
+
+            if let Some(enemy) = self.enemy.get().get_entity() {
+                self.check_enemy(monster, enemy);
+            }
+        }
+
+        self.maintain_schedule(monster);
+
+        // damage conditions are only valid for one think
+        self.clear_conditions(Conditions::LIGHT_DAMAGE | Conditions::HEAVY_DAMAGE);
+    }
+
+    pub fn state(&self) -> MonsterState {
+        self.state.get()
+    }
+
+    pub fn set_state(&self, state: MonsterState) {
+        if state != MonsterState::Combat && state != MonsterState::Alert {
+            // forget the enemy if not fighting
+            if let Some(enemy) = self.enemy.take() {
+                let name = self.pretty_name();
+                let enemy = enemy.vars();
+                trace!("{name}: stripped {}", enemy.pretty_name());
+            }
+        }
+        self.state.set(state);
+        self.ideal_state.set(state);
+    }
+
+    pub fn ideal_state(&self) -> MonsterState {
+        self.ideal_state.get()
+    }
+
+    pub fn set_ideal_state(&self, state: MonsterState) {
+        self.ideal_state.set(state);
+    }
+
+    pub fn conditions(&self) -> Conditions {
+        self.conditions.get()
+    }
+
+    pub fn has_conditions(&self, conditions: Conditions) -> bool {
+        self.conditions.get().intersects(conditions)
+    }
+
+    pub fn has_all_conditions(&self, conditions: Conditions) -> bool {
+        self.conditions.get().contains(conditions)
+    }
+
+    pub fn set_conditions(&self, conditions: Conditions) {
+        self.conditions.set(self.conditions.get() | conditions);
+    }
+
+    pub fn clear_conditions(&self, conditions: Conditions) {
+        self.conditions
+            .set(self.conditions.get().difference(conditions));
+    }
+
+    pub fn has_memory(&self, memory: Memory) -> bool {
+        self.memory.get().intersects(memory)
+    }
+
+    pub fn remember(&self, memory: Memory) {
+        self.memory.set(self.memory.get() | memory);
+    }
+
+    pub fn forget(&self, memory: Memory) {
+        self.memory.set(self.memory.get().difference(memory));
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities.get()
+    }
+
+    pub fn set_capabilities(&self, capabilities: Capabilities) {
+        self.capabilities.set(capabilities);
+    }
+
+    pub fn enemy(&self) -> Option<EntityHandle> {
+        self.enemy.get()
+    }
+
+    pub fn set_enemy(&self, enemy: Option<EntityHandle>) {
+        self.enemy.set(enemy);
+        if let Some(enemy) = enemy {
+            self.enemy_lkp.set(enemy.vars().origin());
+        }
+    }
+
+    /// Returns the last known position of the enemy.
+    pub fn enemy_lkp(&self) -> vec3_t {
+        self.enemy_lkp.get()
+    }
+
+    pub fn target_ent(&self) -> Option<EntityHandle> {
+        self.target_ent.get()
+    }
+
+    pub fn set_target_ent(&self, target: Option<EntityHandle>) {
+        self.target_ent.set(target);
+    }
+
+    pub fn set_field_of_view(&self, field_of_view: ViewField) {
+        self.field_of_view.set(field_of_view);
+    }
+
+    pub fn set_dist_look(&self, dist: f32) {
+        self.dist_look.set(dist);
+    }
+
+    pub fn set_dist_too_far(&self, dist: f32) {
+        self.dist_too_far.set(dist);
+    }
+
+    pub fn activity(&self) -> Activity {
+        self.activity.get()
+    }
+
+    pub fn ideal_activity(&self) -> Activity {
+        self.ideal_activity.get()
+    }
+
+    pub fn set_ideal_activity(&self, activity: Activity) {
+        self.ideal_activity.set(activity);
+    }
+
+    /// Sets an activity used to move along the route.
+    pub fn set_movement_activity(&self, activity: Activity) {
+        self.movement_activity.set(activity);
+    }
+
+    pub fn animating(&self) -> &Animating {
+        &self.animating
+    }
+
+    pub fn is_sequence_finished(&self) -> bool {
+        self.animating.is_sequence_finished()
+    }
+
+    pub fn set_sequence_finished(&self, finished: bool) {
+        self.animating.set_sequence_finished(finished);
+    }
+
+    /// Sets the movement speed of the current sequence.
+    pub fn set_ground_speed(&self, speed: f32) {
+        self.animating.set_ground_speed(speed);
+    }
+
+    /// Returns the body part hit by the last traced attack.
+    pub fn last_hit_group(&self) -> HitGroup {
+        self.last_hit_group.get()
+    }
+
+    /// Returns the nearest sound heard by the last listen.
+    pub fn best_sound(&self) -> Option<AiSound> {
+        self.best_sound.get()
+    }
+
+    /// Returns the scripted sequence that controls the monster.
+    pub fn script(&self) -> Option<&dyn EntityScript> {
+        self.script.get().downcast_ref::<dyn EntityScript>()
+    }
+
+    pub fn set_script(&self, script: Option<EntityHandle>) {
+        self.script.set(script);
+    }
+
+    pub fn script_state(&self) -> ScriptState {
+        self.script_state.get()
+    }
+
+    pub fn set_script_state(&self, state: ScriptState) {
+        self.script_state.set(state);
+    }
+
+    /// Returns `true` if a scripted sequence can possess the monster.
+    pub fn can_play_sequence(&self, override_state: bool, level: InterruptLevel) -> bool {
+        let state = self.state.get();
+        if self.script.get().is_some() || !self.is_alive() || state == MonsterState::Prone {
+            return false;
+        }
+        if override_state {
+            return true;
+        }
+        matches!(state, MonsterState::None | MonsterState::Idle)
+            || self.ideal_state.get() == MonsterState::Idle
+            || (state == MonsterState::Alert && level >= InterruptLevel::ByName)
+    }
+
+    /// Returns a schedule type to get to the scripted sequence and play it.
+    pub fn script_schedule_type(&self) -> ScheduleType {
+        match self.script().map(|script| script.move_to()) {
+            Some(ScriptMoveTo::Walk) => ScheduleType::ScriptedWalk,
+            Some(ScriptMoveTo::Run) => ScheduleType::ScriptedRun,
+            Some(ScriptMoveTo::Turn) => ScheduleType::ScriptedFace,
+            _ => ScheduleType::ScriptedWait,
+        }
+    }
+
+    /// Starts a sequence with the label.
+    ///
+    /// Falls back to the first sequence if the model has no sequence with the label.
+    pub fn start_named_sequence(&self, label: MapString) -> bool {
+        let v = self.vars();
+        let name = label.as_c_str().to_str().unwrap_or_default();
+        let sequence = match self.animating.lookup_sequence(v, name) {
+            Some(sequence) => sequence,
+            None => {
+                let name = self.pretty_name();
+                error!("{name}: unknown scripted sequence {label:?}");
+                0
+            }
+        };
+        v.set_sequence(sequence);
+        v.set_frame(0.0);
+        self.animating.reset_sequence_info(v);
+        true
+    }
+
+    /// Leaves the scripted sequence because of damage or a failed task.
+    ///
+    /// Returns `false` if the monster is dying and must finish the script.
+    pub fn exit_scripted_sequence(&self) -> bool {
+        if self.vars().dead() == Dead::Dying {
+            // the death animation will finish the script
+            self.ideal_state.set(MonsterState::Dead);
+            return false;
+        }
+        if let Some(script) = self.script() {
+            script.cancel();
+        }
+        true
+    }
+
+    /// Returns the monster to the normal AI after a scripted sequence.
+    ///
+    /// Returns `false` if the monster died in the script.
+    pub fn cine_cleanup(&self) -> bool {
+        let engine = self.engine();
+        let v = self.vars();
+        let old_script = self.script();
+        match old_script {
+            Some(script) => script.release(self),
+            None => {
+                v.set_move_type(MoveType::Step);
+                v.set_solid(Solid::SlideBox);
+            }
+        }
+        self.script.set(None);
+        self.target_ent.set(None);
+
+        let script_flags = old_script.map_or(ScriptSpawnFlags::empty(), |s| s.script_flags());
+        if v.dead() == Dead::Dying {
+            v.set_health(0.0);
+            v.set_framerate(0.0);
+            v.set_solid(Solid::Not);
+            self.set_state(MonsterState::Dead);
+            v.set_dead(Dead::Yes);
+            let min = v.min_size();
+            let mut max = v.max_size();
+            max.z = min.z + 2.0;
+            v.set_size_and_link(min, max);
+            if !script_flags.intersects(ScriptSpawnFlags::LEAVE_CORPSE) {
+                self.start_fade_out();
+            } else {
+                v.stop_thinking();
+            }
+            // the corpse is in its final resting place
+            v.set_move_type(MoveType::None);
+            v.with_effects(|f| f | Effects::NOINTERP);
+            return false;
+        }
+
+        let played = old_script.is_some_and(|s| s.play_sequence().is_some());
+        if played {
+            if !script_flags.intersects(ScriptSpawnFlags::NO_SCRIPT_MOVEMENT) {
+                // move to the root bone because sequences can move the model away from the origin
+                let old_origin = v.origin();
+                let mut new_origin = engine.get_bone_position(v, 0);
+                if (old_origin - new_origin).with_z(0.0).length() < 8.0 {
+                    new_origin = old_origin;
+                }
+                v.set_origin(vec3_t::new(new_origin.x, new_origin.y, old_origin.z + 1.0));
+                v.with_flags(|f| f | EdictFlags::ONGROUND);
+                match engine.drop_to_floor(v) {
+                    DropToFloorResult::AllSolid => v.set_origin(old_origin),
+                    DropToFloorResult::False => {
+                        // hanging in the air
+                        v.set_origin(v.origin().with_z(new_origin.z));
+                        v.with_flags(|f| f.difference(EdictFlags::ONGROUND));
+                    }
+                    DropToFloorResult::True => {}
+                }
+                v.set_origin_and_link(v.origin());
+                v.with_effects(|f| f | Effects::NOINTERP);
+            }
+            self.activity.set(Activity::Reset);
+        }
+
+        if v.health() > 0.0 {
+            self.ideal_state.set(MonsterState::Idle);
+        } else {
+            // killed in the script
+            self.ideal_state.set(MonsterState::Dead);
+            self.set_conditions(Conditions::LIGHT_DAMAGE);
+        }
+        v.with_spawn_flags(|f| f & !MonsterSpawnFlags::WAIT_FOR_SCRIPT.bits());
+        true
+    }
+
+    fn start_fade_out(&self) {
+        let v = self.vars();
+        if v.render_mode() == RenderMode::Normal {
+            v.set_render_mode(RenderMode::TransTexture);
+            v.set_render_amount(255.0);
+        }
+        v.set_solid(Solid::Not);
+        v.set_angular_velocity(vec3_t::ZERO);
+        v.set_next_think_time_from_now(0.1);
+        self.fade_out.set(true);
+    }
+
+    fn fade_out_think(&self) {
+        let v = self.vars();
+        let amount = v.render_amount();
+        if amount > 7.0 {
+            v.set_render_amount(amount - 7.0);
+            v.set_next_think_time_from_now(0.1);
+        } else {
+            v.set_render_amount(0.0);
+            self.remove_from_world();
+        }
+    }
+
+    /// Turns to the position.
+    pub fn make_ideal_yaw(&self, target: vec3_t) {
+        let v = self.vars();
+        let yaw = self.engine().vec_to_yaw(target - v.origin());
+        v.set_ideal_yaw(yaw);
+    }
+
+    /// Returns `true` if the monster is facing the ideal yaw.
+    pub fn is_facing_ideal(&self) -> bool {
+        let v = self.vars();
+        let delta = angle_mod(v.angles().y - v.ideal_yaw());
+        delta <= 10.0 || delta >= 350.0
+    }
+
+    /// Returns `true` if the position is in the monster field of view.
+    pub fn is_in_view_cone(&self, position: vec3_t) -> bool {
+        is_in_view_cone(self.vars(), position, self.field_of_view.get())
+    }
+
+    /// Returns `true` if nothing blocks the line of sight to the entity.
+    pub fn is_visible(&self, other: &dyn Entity) -> bool {
+        is_visible(&self.engine(), self.vars(), other.vars())
+    }
+
+    fn look(&self, monster: &dyn EntityMonster, distance: f32) {
+        self.clear_conditions(Conditions::SEE_ANY.difference(Conditions::SEE_ENEMY));
+        let mut seen = self.seen.borrow_mut();
+        seen.clear();
+
+        if self.spawn_flags().intersects(MonsterSpawnFlags::PRISONER) {
+            return;
+        }
+
+        let engine = self.engine();
+        let v = self.vars();
+        let handle = self.entity_handle();
+        let mut sighted = Conditions::empty();
+        for other in engine.entities().in_sphere(v.origin(), distance) {
+            let Some(other) = other.get_entity() else {
+                continue;
+            };
+            let ov = other.vars();
+            if other.entity_handle() == handle
+                || !ov
+                    .flags()
+                    .intersects(EdictFlags::CLIENT | EdictFlags::MONSTER)
+                || ov.flags().intersects(EdictFlags::NOTARGET)
+                || !other.is_alive()
+            {
+                continue;
+            }
+
+            let relationship = monster.relationship(other);
+            if relationship == Relationship::None
+                || !self.is_in_view_cone(ov.origin())
+                || !self.is_visible(other)
+            {
+                continue;
+            }
+
+            if other.is_player() {
+                if self
+                    .spawn_flags()
+                    .intersects(MonsterSpawnFlags::WAIT_TILL_SEEN)
+                {
+                    // attack only if the player sees us too
+                    if !is_in_view_cone(ov, v.origin(), ViewField::FOV) {
+                        continue;
+                    }
+                    v.with_spawn_flags(|f| f & !MonsterSpawnFlags::WAIT_TILL_SEEN.bits());
+                }
+                sighted |= Conditions::SEE_CLIENT;
+            }
+
+            seen.push(other.entity_handle());
+
+            if Some(other.entity_handle()) == self.enemy.get() {
+                sighted |= Conditions::SEE_ENEMY;
+            }
+
+            sighted |= match relationship {
+                Relationship::Nemesis => Conditions::SEE_NEMESIS,
+                Relationship::Hate => Conditions::SEE_HATE,
+                Relationship::Dislike => Conditions::SEE_DISLIKE,
+                Relationship::Fear => Conditions::SEE_FEAR,
+                Relationship::Ally | Relationship::None => Conditions::empty(),
+            };
+        }
+
+        self.set_conditions(sighted);
+    }
+
+    fn listen(&self, monster: &dyn EntityMonster) {
+        self.clear_conditions(Conditions::HEAR_SOUND | Conditions::SMELL | Conditions::SMELL_FOOD);
+        self.best_sound.set(None);
+
+        let mut mask = monster.sound_mask();
+        if let Some(schedule) = self.schedule.get() {
+            // the schedule decides which sounds are interesting
+            mask &= schedule.sounds;
+        }
+        if mask.is_empty() {
+            return;
+        }
+
+        let global_state = self.global_state();
+        let sounds = global_state.get_or_default::<AiSounds>();
+        let ear = eye_position(self.vars());
+        let mut best_dist = f32::MAX;
+        for sound in sounds.active().iter() {
+            if !sound.kind.intersects(mask) {
+                continue;
+            }
+            let dist = (sound.origin - ear).length();
+            if dist > sound.volume as f32 {
+                continue;
+            }
+
+            if sound.is_sound() {
+                self.set_conditions(Conditions::HEAR_SOUND);
+            } else if sound
+                .kind
+                .intersects(SoundTypes::MEAT | SoundTypes::CARCASS)
+            {
+                self.set_conditions(Conditions::SMELL_FOOD);
+            } else {
+                self.set_conditions(Conditions::SMELL);
+            }
+
+            if dist < best_dist {
+                best_dist = dist;
+                self.best_sound.set(Some(*sound));
+            }
+        }
+    }
+
+    /// Returns the most hated visible entity, the nearest one if there are many.
+    pub fn best_visible_enemy(&self) -> Option<EntityHandle> {
+        let monster = self.monster();
+        let origin = self.vars().origin();
+        let mut best = None;
+        let mut best_relationship = Relationship::Dislike;
+        let mut best_dist = f32::MAX;
+        for &handle in self.seen.borrow().iter() {
+            let Some(other) = handle.get_entity() else {
+                continue;
+            };
+            if !other.is_alive() {
+                continue;
+            }
+            let relationship = monster.relationship(other);
+            let dist = (other.vars().origin() - origin).length();
+            if relationship > best_relationship
+                || (relationship == best_relationship && dist < best_dist)
+            {
+                best = Some(handle);
+                best_relationship = relationship;
+                best_dist = dist;
+            }
+        }
+        best
+    }
+
+    fn push_enemy(&self, enemy: EntityHandle, lkp: vec3_t) {
+        let mut enemies = self.old_enemies.get();
+        let mut lkps = self.old_enemies_lkp.get();
+        if enemies.contains(&Some(enemy)) {
+            return;
+        }
+        if let Some(i) = enemies.iter().position(|i| i.get_entity().is_none()) {
+            enemies[i] = Some(enemy);
+            lkps[i] = lkp;
+            self.old_enemies.set(enemies);
+            self.old_enemies_lkp.set(lkps);
+        }
+    }
+
+    fn pop_enemy(&self) -> bool {
+        let mut enemies = self.old_enemies.get();
+        let lkps = self.old_enemies_lkp.get();
+        for i in (0..Self::MAX_OLD_ENEMIES).rev() {
+            let Some(enemy) = enemies[i].take() else {
+                continue;
+            };
+            if enemy.get_entity().is_some_and(|i| i.is_alive()) {
+                self.enemy.set(Some(enemy));
+                self.enemy_lkp.set(lkps[i]);
+                self.old_enemies.set(enemies);
+                return true;
+            }
+        }
+        self.old_enemies.set(enemies);
+        false
+    }
+
+    /// Selects a new enemy from visible entities.
+    ///
+    /// Returns `true` if the monster has an enemy.
+    pub fn get_enemy(&self) -> bool {
+        if self.has_conditions(
+            Conditions::SEE_HATE | Conditions::SEE_DISLIKE | Conditions::SEE_NEMESIS,
+        ) {
+            let new_enemy = self.best_visible_enemy();
+            if new_enemy.is_some() && new_enemy != self.enemy.get() {
+                if let Some(old) = self.enemy.get() {
+                    self.push_enemy(old, self.enemy_lkp.get());
+                }
+                self.set_conditions(Conditions::NEW_ENEMY);
+                self.set_enemy(new_enemy);
+            }
+        }
+
+        if self.enemy.get().is_none() && self.pop_enemy() {
+            let interrupt = self
+                .schedule
+                .get()
+                .map_or(Conditions::empty(), |i| i.interrupt);
+            if interrupt.intersects(Conditions::NEW_ENEMY) {
+                self.set_conditions(Conditions::NEW_ENEMY);
+            }
+        }
+
+        self.enemy.get().is_some()
+    }
+
+    fn check_enemy(&self, monster: &dyn EntityMonster, enemy: &dyn Entity) -> bool {
+        self.clear_conditions(
+            Conditions::ENEMY_FACING_ME
+                | Conditions::ENEMY_OCCLUDED
+                | Conditions::ENEMY_TOO_FAR
+                | Conditions::SEE_ENEMY
+                | Conditions::CAN_ATTACK,
+        );
+
+        if !enemy.is_alive() {
+            self.set_conditions(Conditions::ENEMY_DEAD);
+            return false;
+        }
+
+        let v = self.vars();
+        let ev = enemy.vars();
+        let enemy_pos = ev.origin();
+        let visible = self.is_visible(enemy);
+        if visible {
+            self.set_conditions(Conditions::SEE_ENEMY);
+            self.enemy_lkp.set(enemy_pos);
+        } else {
+            self.set_conditions(Conditions::ENEMY_OCCLUDED);
+        }
+
+        let dist = (enemy_pos - v.origin()).length();
+        if dist > self.dist_too_far.get() {
+            self.set_conditions(Conditions::ENEMY_TOO_FAR);
+        }
+
+        if is_in_view_cone(ev, v.origin(), ViewField::FOV) {
+            self.set_conditions(Conditions::ENEMY_FACING_ME);
+        }
+
+        if visible {
+            self.check_attacks(monster, enemy, dist);
+        }
+
+        if self.move_goal.get() == MoveGoal::Enemy {
+            // follow the enemy if it moved too far from the route goal
+            let goal = self.route.get().goal();
+            if goal.is_some_and(|goal| (goal - self.enemy_lkp.get()).length() > 80.0) {
+                self.build_route(self.enemy_lkp.get(), MoveGoal::Enemy);
+            }
+        }
+
+        true
+    }
+
+    fn check_attacks(&self, monster: &dyn EntityMonster, target: &dyn Entity, dist: f32) {
+        let v = self.vars();
+        let forward = v.angles().angle_vectors().forward().with_z(0.0).normalize();
+        let los = (target.vars().origin() - v.origin())
+            .with_z(0.0)
+            .normalize();
+        let dot = los.dot(forward);
+
+        let caps = self.capabilities.get();
+        let mut conditions = Conditions::empty();
+        if caps.intersects(Capabilities::RANGE_ATTACK1) && monster.check_range_attack1(dot, dist) {
+            conditions |= Conditions::CAN_RANGE_ATTACK1;
+        }
+        if caps.intersects(Capabilities::RANGE_ATTACK2) && monster.check_range_attack2(dot, dist) {
+            conditions |= Conditions::CAN_RANGE_ATTACK2;
+        }
+        if caps.intersects(Capabilities::MELEE_ATTACK1) && monster.check_melee_attack1(dot, dist) {
+            conditions |= Conditions::CAN_MELEE_ATTACK1;
+        }
+        if caps.intersects(Capabilities::MELEE_ATTACK2) && monster.check_melee_attack2(dot, dist) {
+            conditions |= Conditions::CAN_MELEE_ATTACK2;
+        }
+        self.set_conditions(conditions);
+    }
+
+    /// Updates the ideal monster state based on conditions.
+    pub fn get_ideal_state(&self) -> MonsterState {
+        let conditions = self.conditions.get();
+        let damaged = conditions.intersects(Conditions::LIGHT_DAMAGE | Conditions::HEAVY_DAMAGE);
+        let ideal = match self.state.get() {
+            MonsterState::Idle | MonsterState::None => {
+                if conditions.intersects(Conditions::NEW_ENEMY) {
+                    MonsterState::Combat
+                } else if damaged {
+                    // turn around to face the attacker
+                    self.make_ideal_yaw(self.enemy_lkp.get());
+                    MonsterState::Alert
+                } else if conditions.intersects(Conditions::HEAR_SOUND) {
+                    if let Some(sound) = self.best_sound.get() {
+                        self.make_ideal_yaw(sound.origin);
+                    }
+                    MonsterState::Alert
+                } else if conditions.intersects(Conditions::SMELL | Conditions::SMELL_FOOD) {
+                    MonsterState::Alert
+                } else {
+                    MonsterState::Idle
+                }
+            }
+            MonsterState::Alert => {
+                if conditions.intersects(Conditions::NEW_ENEMY | Conditions::SEE_ENEMY) {
+                    MonsterState::Combat
+                } else {
+                    if conditions.intersects(Conditions::HEAR_SOUND) {
+                        if let Some(sound) = self.best_sound.get() {
+                            self.make_ideal_yaw(sound.origin);
+                        }
+                    }
+                    MonsterState::Alert
+                }
+            }
+            MonsterState::Combat => {
+                if self.enemy.get().is_none() {
+                    // the enemy is dead or gone
+                    MonsterState::Alert
+                } else {
+                    MonsterState::Combat
+                }
+            }
+            MonsterState::Hunt => MonsterState::Hunt,
+            MonsterState::Script => {
+                if conditions.intersects(Conditions::TASK_FAILED) || damaged {
+                    // sets the ideal state
+                    self.exit_scripted_sequence();
+                    return self.ideal_state.get();
+                }
+                MonsterState::Script
+            }
+            MonsterState::Dead => MonsterState::Dead,
+            state @ (MonsterState::Prone | MonsterState::PlayDead) => state,
+        };
+        self.ideal_state.set(ideal);
+        ideal
+    }
+
+    pub fn schedule(&self) -> Option<&'static Schedule> {
+        self.schedule.get()
+    }
+
+    /// Returns `true` if the current schedule is not interrupted, done or failed.
+    pub fn is_schedule_valid(&self) -> bool {
+        match self.schedule.get() {
+            Some(schedule) => !self.has_conditions(
+                schedule.interrupt | Conditions::SCHEDULE_DONE | Conditions::TASK_FAILED,
+            ),
+            None => false,
+        }
+    }
+
+    pub fn clear_schedule(&self) {
+        self.task_status.set(TaskStatus::New);
+        self.schedule.set(None);
+        self.schedule_index.set(0);
+    }
+
+    pub fn change_schedule(&self, schedule: &'static Schedule) {
+        trace!(
+            "{}: change schedule to {}",
+            self.pretty_name(),
+            schedule.name
+        );
+        self.schedule.set(Some(schedule));
+        self.schedule_index.set(0);
+        self.task_status.set(TaskStatus::New);
+        self.conditions.set(Conditions::empty());
+        self.fail_schedule.set(ScheduleType::None);
+    }
+
+    /// Returns the current task.
+    pub fn task(&self) -> Option<&'static Task> {
+        self.schedule.get()?.task(self.schedule_index.get())
+    }
+
+    pub fn task_status(&self) -> TaskStatus {
+        self.task_status.get()
+    }
+
+    pub fn task_complete(&self) {
+        if !self.has_conditions(Conditions::TASK_FAILED) {
+            self.task_status.set(TaskStatus::Complete);
+        }
+    }
+
+    pub fn task_fail(&self) {
+        self.set_conditions(Conditions::TASK_FAILED);
+    }
+
+    fn is_task_complete(&self) -> bool {
+        self.task_status.get() == TaskStatus::Complete
+    }
+
+    fn is_task_running(&self) -> bool {
+        !matches!(
+            self.task_status.get(),
+            TaskStatus::Complete | TaskStatus::RunningMovement
+        )
+    }
+
+    fn next_scheduled_task(&self) {
+        let Some(schedule) = self.schedule.get() else {
+            return;
+        };
+        self.task_status.set(TaskStatus::New);
+        self.schedule_index.set(self.schedule_index.get() + 1);
+        if self.schedule_index.get() >= schedule.tasks.len() {
+            self.set_conditions(Conditions::SCHEDULE_DONE);
+        }
+    }
+
+    fn maintain_schedule(&self, monster: &dyn EntityMonster) {
+        // a few tasks can be completed in one think
+        for _ in 0..10 {
+            if self.schedule.get().is_some() && self.is_task_complete() {
+                self.next_scheduled_task();
+            }
+
+            if !self.is_schedule_valid() || self.state.get() != self.ideal_state.get() {
+                let ideal = self.ideal_state.get();
+                if ideal != MonsterState::Dead
+                    && (ideal != MonsterState::Script || ideal == self.state.get())
+                {
+                    let schedule_done = self
+                        .schedule
+                        .get()
+                        .is_some_and(|i| i.interrupt.intersects(Conditions::SCHEDULE_DONE));
+                    if (!self.conditions.get().is_empty()
+                        && !self.has_conditions(Conditions::SCHEDULE_DONE))
+                        || schedule_done
+                        || (self.state.get() == MonsterState::Combat && self.enemy.get().is_none())
+                    {
+                        self.get_ideal_state();
+                    }
+                }
+
+                if self.has_conditions(Conditions::TASK_FAILED)
+                    && self.state.get() == self.ideal_state.get()
+                {
+                    let fail = match self.fail_schedule.get() {
+                        ScheduleType::None => ScheduleType::Fail,
+                        fail => fail,
+                    };
+                    self.change_schedule(monster.schedule_of_type(fail));
+                } else {
+                    self.set_state(self.ideal_state.get());
+                    let schedule = if self.state.get() == MonsterState::Script {
+                        // scripts are handled by the base monster
+                        <Self as EntityMonster>::get_schedule(self)
+                    } else {
+                        monster.get_schedule()
+                    };
+                    self.change_schedule(schedule);
+                }
+            }
+
+            if self.task_status.get() == TaskStatus::New {
+                let Some(task) = self.task() else {
+                    return;
+                };
+                self.task_status.set(TaskStatus::Running);
+                monster.start_task(task);
+            }
+
+            if self.activity.get() != self.ideal_activity.get() {
+                monster.set_activity(self.ideal_activity.get());
+            }
+
+            if !self.is_task_complete() && self.task_status.get() != TaskStatus::New {
+                break;
+            }
+        }
+
+        if self.is_task_running() {
+            if let Some(task) = self.task() {
+                monster.run_task(task);
+            }
+        }
+
+        if self.activity.get() != self.ideal_activity.get() {
+            monster.set_activity(self.ideal_activity.get());
+        }
+    }
+
+    pub fn route_clear(&self) {
+        let mut route = self.route.get();
+        route.clear();
+        self.route.set(route);
+        self.route_truncated.set(false);
+        self.move_goal.set(MoveGoal::None);
+        self.forget(Memory::MOVE_FAILED);
+    }
+
+    pub fn route(&self) -> Route {
+        self.route.get()
+    }
+
+    /// Builds a route to the goal position.
+    ///
+    /// Returns `false` if the goal can not be reached.
+    pub fn build_route(&self, goal: vec3_t, move_goal: MoveGoal) -> bool {
+        self.route_clear();
+
+        let engine = self.engine();
+        let v = self.vars();
+        let dist = (goal - v.origin()).with_z(0.0).length();
+        let walked = check_local_move(&engine, v, v.origin(), goal);
+        // the goal entity blocks the last step
+        let tolerance = match move_goal {
+            MoveGoal::Enemy | MoveGoal::Target => v.max_size().x * 2.0 + LOCAL_STEP_SIZE,
+            MoveGoal::None | MoveGoal::Location => LOCAL_STEP_SIZE,
+        };
+        let mut route = Route::default();
+        if walked + tolerance >= dist {
+            route.push(goal);
+        } else {
+            let global_state = self.global_state();
+            let graph = global_state.get_or_default::<NodeGraph>();
+            let hull = Hull::from_entity(v);
+            let Some(path) = graph.find_route(v.origin(), goal, hull, self.capabilities.get())
+            else {
+                return false;
+            };
+            // the rest of a long path is built when the route is finished
+            let truncated = path.len() >= Route::MAX_WAYPOINTS;
+            for &position in path.iter().take(Route::MAX_WAYPOINTS - 1) {
+                route.push(position);
+            }
+            if !truncated {
+                route.push(goal);
+            }
+            self.route_truncated.set(truncated);
+        }
+        self.route.set(route);
+        self.move_goal.set(move_goal);
+        self.move_goal_position.set(goal);
+        true
+    }
+
+    /// Builds a route to a node hidden from the threat.
+    ///
+    /// Returns `false` if there is no cover nearby.
+    pub fn find_cover(&self, threat_eye: vec3_t, min_dist: f32, max_dist: f32) -> bool {
+        let v = self.vars();
+        let global_state = self.global_state();
+        let graph = global_state.get_or_default::<NodeGraph>();
+        let cover = graph.find_cover(
+            v.origin(),
+            v.view_ofs(),
+            threat_eye,
+            min_dist,
+            max_dist,
+            Hull::from_entity(v),
+            self.capabilities.get(),
+        );
+        drop(graph);
+        cover.is_some_and(|cover| self.build_route(cover, MoveGoal::Location))
+    }
+
+    pub fn is_movement_complete(&self) -> bool {
+        self.route.get().is_finished()
+    }
+
+    fn move_step(&self, interval: f32) {
+        let mut route = self.route.get();
+        let Some(waypoint) = route.current() else {
+            return;
+        };
+
+        let engine = self.engine();
+        let v = self.vars();
+        if self.move_wait_finished.get() > engine.globals.map_time() {
+            return;
+        }
+
+        let delta = (waypoint - v.origin()).with_z(0.0);
+        let step = self.animating.ground_speed() * interval;
+        if delta.length() <= Self::WAYPOINT_RADIUS.max(step) {
+            route.advance();
+            self.route.set(route);
+            if route.is_finished() {
+                if !self.route_truncated.get() {
+                    self.remember(Memory::PATH_FINISHED);
+                    self.route_clear();
+                } else if !self.build_route(self.move_goal_position.get(), self.move_goal.get()) {
+                    self.remember(Memory::MOVE_FAILED);
+                    self.task_fail();
+                }
+            }
+            return;
+        }
+
+        if step <= 0.0 {
+            // the sequence does not move
+            return;
+        }
+
+        let step = step.min(delta.length());
+        let end = v.origin() + delta.normalize() * step;
+        if check_local_move(&engine, v, v.origin(), end) < step {
+            self.remember(Memory::MOVE_FAILED);
+            self.route_clear();
+            self.task_fail();
+            return;
+        }
+
+        v.set_ideal_yaw(engine.vec_to_yaw(delta));
+        engine.change_yaw(v);
+        engine.move_to_origin(v, waypoint, step);
+    }
+}
+
+impl Entity for BaseMonster {
+    delegate_entity!(base not { think, take_damage, body_target, trace_attack, killed });
+
+    fn think(&self) {
+        if self.fade_out.get() {
+            self.fade_out_think();
+        } else if self.started.get() {
+            self.monster_think();
+        } else {
+            self.start_monster();
+        }
+    }
+
+    fn take_damage(
+        &self,
+        damage: f32,
+        damage_type: DamageFlags,
+        inflictor: &EntityVars,
+        attacker: Option<&EntityVars>,
+    ) -> bool {
+        let v = self.vars();
+        if v.take_damage() == TakeDamage::No {
+            return false;
+        }
+        if !self.is_alive() {
+            // the body can be gibbed
+            if damage_type.intersects(DamageFlags::ALWAYSGIB)
+                || v.health() - damage < Self::GIB_HEALTH
+            {
+                v.set_health(v.health() - damage);
+                self.monster().gib();
+            }
+            return true;
+        }
+
+        let monster = self.monster();
+        if v.dead() == Dead::No {
+            monster.pain_sound();
+        }
+
+        let attack_dir = (inflictor.abs_center() - v.abs_center()).normalize();
+        v.set_health(v.health() - damage);
+
+        if self.state.get() == MonsterState::Script {
+            self.set_conditions(Conditions::LIGHT_DAMAGE);
+            return false;
+        }
+
+        if v.health() <= 0.0 {
+            let gib = if damage_type.intersects(DamageFlags::ALWAYSGIB) {
+                Gib::Always
+            } else if damage_type.intersects(DamageFlags::NEVERGIB) {
+                Gib::Never
+            } else {
+                Gib::Normal
+            };
+            self.private()
+                .as_entity()
+                .killed(attacker.unwrap_or(inflictor), gib);
+            return false;
+        }
+
+        let attacker_is_active = attacker.is_some_and(|a| {
+            a.flags()
+                .intersects(EdictFlags::MONSTER | EdictFlags::CLIENT)
+        });
+        if attacker_is_active {
+            let enemy = self.enemy.get();
+            if enemy.is_none()
+                || enemy == Some(inflictor.entity_handle())
+                || !self.has_conditions(Conditions::SEE_ENEMY)
+            {
+                self.enemy_lkp.set(inflictor.origin());
+            } else {
+                self.enemy_lkp.set(v.origin() + attack_dir * 64.0);
+            }
+            self.make_ideal_yaw(self.enemy_lkp.get());
+
+            if damage > 0.0 {
+                self.set_conditions(Conditions::LIGHT_DAMAGE);
+            }
+            if damage >= 20.0 {
+                self.set_conditions(Conditions::HEAVY_DAMAGE);
+            }
+        }
+
+        true
+    }
+
+    fn body_target(&self, _src: vec3_t) -> vec3_t {
+        let v = self.vars();
+        v.abs_center() * 0.75 + eye_position(v) * 0.25
+    }
+
+    fn trace_attack(
+        &self,
+        attacker: &EntityVars,
+        damage: f32,
+        dir: vec3_t,
+        trace: &TraceResult,
+        damage_type: DamageFlags,
+    ) {
+        if self.vars().take_damage() != TakeDamage::No {
+            self.last_hit_group.set(HitGroup::from_trace(trace));
+            self.base
+                .trace_attack(attacker, damage, dir, trace, damage_type);
+        }
+    }
+
+    fn killed(&self, _attacker: &EntityVars, gib: Gib) {
+        let v = self.vars();
+        if self.has_memory(Memory::KILLED) {
+            if gib == Gib::Always || (gib == Gib::Normal && v.health() < Self::GIB_HEALTH) {
+                self.monster().gib();
+            }
+            return;
+        }
+
+        self.remember(Memory::KILLED);
+        self.set_conditions(Conditions::LIGHT_DAMAGE);
+        self.ideal_state.set(MonsterState::Dead);
+        self.route_clear();
+
+        if gib == Gib::Always || (gib == Gib::Normal && v.health() < Self::GIB_HEALTH) {
+            self.monster().gib();
+            return;
+        }
+
+        // the body can still be damaged and gibbed
+        v.set_take_damage(TakeDamage::Yes);
+        v.set_health(v.max_health() / 2.0);
+        v.set_max_health(5.0);
+        v.set_move_type(MoveType::Toss);
+        v.set_dead(Dead::Dying);
+    }
+}
+
+impl EntityMonster for BaseMonster {
+    fn base_monster(&self) -> &BaseMonster {
+        self
+    }
+
+    fn relationship(&self, other: &dyn Entity) -> Relationship {
+        self.monster().classify().relationship(other.classify())
+    }
+
+    fn get_schedule(&self) -> &'static Schedule {
+        let monster = self.monster();
+        let conditions = self.conditions.get();
+        let damaged = conditions.intersects(Conditions::LIGHT_DAMAGE | Conditions::HEAVY_DAMAGE);
+        let ty = match self.state.get() {
+            MonsterState::Idle => {
+                if conditions.intersects(Conditions::HEAR_SOUND) {
+                    ScheduleType::AlertFace
+                } else if !self.route.get().is_finished() {
+                    ScheduleType::IdleWalk
+                } else {
+                    ScheduleType::IdleStand
+                }
+            }
+            MonsterState::Alert => {
+                if conditions.intersects(Conditions::ENEMY_DEAD) {
+                    ScheduleType::VictoryDance
+                } else if damaged {
+                    ScheduleType::AlertSmallFlinch
+                } else if conditions.intersects(Conditions::HEAR_SOUND) {
+                    ScheduleType::AlertFace
+                } else {
+                    ScheduleType::AlertStand
+                }
+            }
+            MonsterState::Combat => {
+                if conditions.intersects(Conditions::ENEMY_DEAD) {
+                    self.enemy.set(None);
+                    if self.get_enemy() {
+                        self.clear_conditions(Conditions::ENEMY_DEAD);
+                    } else {
+                        self.set_state(MonsterState::Alert);
+                    }
+                    return monster.get_schedule();
+                }
+
+                if conditions.intersects(Conditions::NEW_ENEMY) {
+                    ScheduleType::WakeAngry
+                } else if conditions.intersects(Conditions::LIGHT_DAMAGE)
+                    && !self.has_memory(Memory::FLINCHED)
+                {
+                    ScheduleType::SmallFlinch
+                } else if !conditions.intersects(Conditions::SEE_ENEMY) {
+                    if !conditions.intersects(Conditions::ENEMY_OCCLUDED) {
+                        ScheduleType::CombatFace
+                    } else {
+                        ScheduleType::ChaseEnemy
+                    }
+                } else if conditions.intersects(Conditions::CAN_RANGE_ATTACK1) {
+                    ScheduleType::RangeAttack1
+                } else if conditions.intersects(Conditions::CAN_RANGE_ATTACK2) {
+                    ScheduleType::RangeAttack2
+                } else if conditions.intersects(Conditions::CAN_MELEE_ATTACK1) {
+                    ScheduleType::MeleeAttack1
+                } else if conditions.intersects(Conditions::CAN_MELEE_ATTACK2) {
+                    ScheduleType::MeleeAttack2
+                } else if !self.is_facing_ideal() {
+                    ScheduleType::CombatFace
+                } else {
+                    ScheduleType::ChaseEnemy
+                }
+            }
+            MonsterState::Dead => ScheduleType::Die,
+            MonsterState::Script => {
+                if self.script.get().is_none() {
+                    self.cine_cleanup();
+                    ScheduleType::IdleStand
+                } else {
+                    self.script_schedule_type()
+                }
+            }
+            MonsterState::None
+            | MonsterState::Hunt
+            | MonsterState::Prone
+            | MonsterState::PlayDead => ScheduleType::Fail,
+        };
+        monster.schedule_of_type(ty)
+    }
+
+    fn schedule_of_type(&self, ty: ScheduleType) -> &'static Schedule {
+        ty.default_schedule()
+    }
+
+    fn start_task(&self, task: &Task) {
+        let engine = self.engine();
+        let v = self.vars();
+        let now = engine.globals.map_time();
+        match *task {
+            Task::Wait(time) | Task::WaitFaceEnemy(time) => {
+                if matches!(task, Task::WaitFaceEnemy(_)) {
+                    self.make_ideal_yaw(self.enemy_lkp.get());
+                }
+                self.wait_finished.set(now + time);
+            }
+            Task::WaitRandom(time) => {
+                self.wait_finished.set(now + engine.random_float(0.1, time));
+            }
+            Task::WaitPvs | Task::WaitIndefinite => {}
+            Task::StopMoving => {
+                if self.ideal_activity.get() == self.movement_activity.get() {
+                    self.ideal_activity.set(Activity::Idle);
+                }
+                self.route_clear();
+                self.task_complete();
+            }
+            Task::SetActivity(activity) => {
+                self.ideal_activity.set(activity);
+                self.task_complete();
+            }
+            Task::PlaySequence(activity) | Task::PlaySequenceFaceEnemy(activity) => {
+                self.ideal_activity.set(activity);
+            }
+            Task::FaceIdeal => {}
+            Task::FaceEnemy => self.make_ideal_yaw(self.enemy_lkp.get()),
+            Task::FaceTarget => match self.target_ent.get() {
+                Some(target) => self.make_ideal_yaw(target.vars().origin()),
+                None => self.task_fail(),
+            },
+            Task::FaceRoute => match self.route.get().current() {
+                Some(waypoint) => self.make_ideal_yaw(waypoint),
+                None => self.task_fail(),
+            },
+            Task::GetPathToEnemy => {
+                let enemy = self.enemy.get().get_entity();
+                match enemy {
+                    Some(enemy) if self.build_route(enemy.vars().origin(), MoveGoal::Enemy) => {
+                        self.task_complete();
+                    }
+                    _ => self.task_fail(),
+                }
+            }
+            Task::GetPathToEnemyLkp => {
+                if self.build_route(self.enemy_lkp.get(), MoveGoal::Location) {
+                    self.task_complete();
+                } else {
+                    self.task_fail();
+                }
+            }
+            Task::GetPathToTarget => {
+                let target = self.target_ent.get().get_entity();
+                match target {
+                    Some(target) if self.build_route(target.vars().origin(), MoveGoal::Target) => {
+                        self.task_complete();
+                    }
+                    _ => self.task_fail(),
+                }
+            }
+            Task::GetPathToBestSound => match self.best_sound.get() {
+                Some(sound) if self.build_route(sound.origin, MoveGoal::Location) => {
+                    self.task_complete();
+                }
+                _ => self.task_fail(),
+            },
+            Task::FindCoverFromOrigin | Task::FindCoverFromEnemy | Task::FindCoverFromBestSound => {
+                let threat = match *task {
+                    Task::FindCoverFromEnemy => self
+                        .enemy
+                        .get()
+                        .get_entity()
+                        .map(|enemy| self.enemy_lkp.get() + enemy.vars().view_ofs()),
+                    Task::FindCoverFromBestSound => self.best_sound.get().map(|i| i.origin),
+                    _ => Some(eye_position(v)),
+                };
+                if threat.is_some_and(|threat| self.find_cover(threat, 0.0, Self::COVER_RADIUS)) {
+                    self.task_complete();
+                } else {
+                    self.task_fail();
+                }
+            }
+            Task::MoveToTargetRange(range) => {
+                let target = self.target_ent.get().get_entity();
+                match target {
+                    Some(target) => {
+                        let origin = target.vars().origin();
+                        if (origin - v.origin()).length() < range {
+                            self.task_complete();
+                        } else if self.build_route(origin, MoveGoal::Target) {
+                            self.ideal_activity.set(self.movement_activity.get());
+                        } else {
+                            self.task_fail();
+                        }
+                    }
+                    None => self.task_fail(),
+                }
+            }
+            Task::WalkPath | Task::RunPath => {
+                let activity = if matches!(task, Task::RunPath) {
+                    Activity::Run
+                } else {
+                    Activity::Walk
+                };
+                self.movement_activity.set(activity);
+                if !self.is_movement_complete() {
+                    self.ideal_activity.set(activity);
+                }
+                self.task_complete();
+            }
+            Task::WalkToTarget | Task::RunToTarget => {
+                let activity = if matches!(task, Task::RunToTarget) {
+                    Activity::Run
+                } else {
+                    Activity::Walk
+                };
+                let target = self.target_ent.get().get_entity();
+                let has_sequence = self
+                    .animating
+                    .lookup_activity(v, activity.into_raw())
+                    .is_some();
+                match target {
+                    Some(target) if (target.vars().origin() - v.origin()).length() < 1.0 => {}
+                    Some(_) if !has_sequence => {}
+                    Some(target) if self.build_route(target.vars().origin(), MoveGoal::Target) => {
+                        self.movement_activity.set(activity);
+                        self.ideal_activity.set(activity);
+                    }
+                    _ => {
+                        self.task_fail();
+                        self.route_clear();
+                    }
+                }
+                self.task_complete();
+            }
+            Task::WaitForMovement => {
+                if self.is_movement_complete() {
+                    self.task_complete();
+                }
+            }
+            Task::RangeAttack1 => self.ideal_activity.set(Activity::RangeAttack1),
+            Task::RangeAttack2 => self.ideal_activity.set(Activity::RangeAttack2),
+            Task::MeleeAttack1 => self.ideal_activity.set(Activity::MeleeAttack1),
+            Task::MeleeAttack2 => self.ideal_activity.set(Activity::MeleeAttack2),
+            Task::SmallFlinch => self.ideal_activity.set(Activity::SmallFlinch),
+            Task::Die => {
+                self.route_clear();
+                self.ideal_activity.set(Activity::DieSimple);
+                v.set_dead(Dead::Dying);
+            }
+            Task::SoundWake => {
+                self.monster().alert_sound();
+                self.task_complete();
+            }
+            Task::SoundDie => {
+                self.monster().death_sound();
+                self.task_complete();
+            }
+            Task::ClearMoveWait => {
+                self.move_wait_finished.set(now);
+                self.task_complete();
+            }
+            Task::Remember(memory) => {
+                self.remember(memory);
+                self.task_complete();
+            }
+            Task::Forget(memory) => {
+                self.forget(memory);
+                self.task_complete();
+            }
+            Task::SuggestState(state) => {
+                self.ideal_state.set(state);
+                self.task_complete();
+            }
+            Task::SetSchedule(ty) => {
+                let schedule = self.monster().schedule_of_type(ty);
+                self.change_schedule(schedule);
+            }
+            Task::SetFailSchedule(ty) => {
+                self.fail_schedule.set(ty);
+                self.task_complete();
+            }
+            Task::PlantOnScript => {
+                if let Some(target) = self.target_ent.get() {
+                    v.set_origin(target.vars().origin());
+                }
+                self.task_complete();
+            }
+            Task::FaceScript => {
+                if let Some(target) = self.target_ent.get() {
+                    v.set_ideal_yaw(angle_mod(target.vars().angles().y));
+                }
+                self.task_complete();
+                self.ideal_activity.set(Activity::Idle);
+                self.route_clear();
+            }
+            Task::EnableScript => {
+                if let Some(script) = self.script() {
+                    script.delay_start(false);
+                }
+                self.task_complete();
+            }
+            Task::WaitForScript => match self.script() {
+                Some(script) => match script.idle_sequence() {
+                    Some(idle) => {
+                        script.start_sequence(self, Some(idle), false);
+                        if script.play_sequence() == Some(idle) {
+                            v.set_framerate(0.0);
+                        }
+                    }
+                    None => self.ideal_activity.set(Activity::Idle),
+                },
+                None => self.task_fail(),
+            },
+            Task::PlayScript => {
+                v.set_move_type(MoveType::Fly);
+                v.with_flags(|f| f.difference(EdictFlags::ONGROUND));
+                self.script_state.set(ScriptState::Playing);
+            }
+            Task::Custom(id, _) => {
+                let name = self.pretty_name();
+                warn!("{name}: no start_task entry for custom task {id}");
+                self.task_fail();
+            }
+        }
+    }
+
+    fn run_task(&self, task: &Task) {
+        let engine = self.engine();
+        let v = self.vars();
+        let now = engine.globals.map_time();
+        match *task {
+            Task::Wait(_) | Task::WaitRandom(_) => {
+                if now >= self.wait_finished.get() {
+                    self.task_complete();
+                }
+            }
+            Task::WaitFaceEnemy(_) => {
+                self.make_ideal_yaw(self.enemy_lkp.get());
+                engine.change_yaw(v);
+                if now >= self.wait_finished.get() {
+                    self.task_complete();
+                }
+            }
+            Task::WaitPvs => {
+                if engine.find_client_in_pvs(v).is_some() {
+                    self.task_complete();
+                }
+            }
+            Task::FaceIdeal | Task::FaceEnemy | Task::FaceTarget | Task::FaceRoute => {
+                if matches!(task, Task::FaceEnemy) {
+                    self.make_ideal_yaw(self.enemy_lkp.get());
+                }
+                engine.change_yaw(v);
+                if self.is_facing_ideal() {
+                    self.task_complete();
+                }
+            }
+            Task::PlaySequence(_) | Task::PlaySequenceFaceEnemy(_) => {
+                if matches!(task, Task::PlaySequenceFaceEnemy(_)) {
+                    self.make_ideal_yaw(self.enemy_lkp.get());
+                    engine.change_yaw(v);
+                }
+                if self.animating.is_sequence_finished() {
+                    self.task_complete();
+                }
+            }
+            Task::MoveToTargetRange(range) => match self.target_ent.get().get_entity() {
+                Some(target) => {
+                    let origin = target.vars().origin();
+                    if (origin - v.origin()).length() < range {
+                        self.route_clear();
+                        self.task_complete();
+                    } else if self.is_movement_complete()
+                        && !self.build_route(origin, MoveGoal::Target)
+                    {
+                        self.task_fail();
+                    }
+                }
+                None => self.task_fail(),
+            },
+            Task::WaitForMovement => {
+                if self.is_movement_complete() {
+                    self.task_complete();
+                }
+            }
+            Task::RangeAttack1
+            | Task::RangeAttack2
+            | Task::MeleeAttack1
+            | Task::MeleeAttack2
+            | Task::SmallFlinch => {
+                if !matches!(task, Task::SmallFlinch) {
+                    self.make_ideal_yaw(self.enemy_lkp.get());
+                    engine.change_yaw(v);
+                }
+                if self.animating.is_sequence_finished() {
+                    self.activity.set(Activity::Reset);
+                    self.task_complete();
+                }
+            }
+            Task::WaitForScript => match self.script() {
+                Some(script) => {
+                    if script.is_ready() {
+                        self.task_complete();
+                        script.start_sequence(self, script.play_sequence(), true);
+                        if self.animating.is_sequence_finished() {
+                            self.clear_schedule();
+                        }
+                        v.set_framerate(1.0);
+                    }
+                }
+                None => self.task_fail(),
+            },
+            Task::PlayScript => match self.script() {
+                Some(script) => {
+                    if self.animating.is_sequence_finished() {
+                        script.sequence_done(self);
+                    }
+                }
+                None => self.task_fail(),
+            },
+            Task::Die => {
+                if self.animating.is_sequence_finished() {
+                    v.set_dead(Dead::Yes);
+                    v.stop_thinking();
+                    let global_state = self.global_state();
+                    let sounds = global_state.get_or_default::<AiSounds>();
+                    sounds.insert(SoundTypes::CARCASS, v.origin(), 384, 30.0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_range_attack1(&self, dot: f32, dist: f32) -> bool {
+        dist > 64.0 && dist <= 784.0 && dot >= 0.5
+    }
+
+    fn check_range_attack2(&self, dot: f32, dist: f32) -> bool {
+        dist > 64.0 && dist <= 512.0 && dot >= 0.5
+    }
+
+    fn check_melee_attack1(&self, dot: f32, dist: f32) -> bool {
+        dist <= 64.0 && dot >= 0.7
+    }
+
+    fn check_melee_attack2(&self, dot: f32, dist: f32) -> bool {
+        dist <= 64.0 && dot >= 0.7
+    }
+
+    fn ignore_conditions(&self) -> Conditions {
+        match self.script() {
+            Some(script) if self.state.get() == MonsterState::Script && !script.can_interrupt() => {
+                SCRIPT_BREAK_CONDITIONS
+            }
+            _ => Conditions::empty(),
+        }
+    }
+
+    fn sound_mask(&self) -> SoundTypes {
+        SoundTypes::WORLD | SoundTypes::COMBAT | SoundTypes::PLAYER
+    }
+
+    fn set_activity(&self, activity: Activity) {
+        let v = self.vars();
+        match self.animating.lookup_activity(v, activity.into_raw()) {
+            Some(sequence) => {
+                if v.sequence() != sequence || !self.animating.is_sequence_looping() {
+                    // keep the frame when switching between walk and run
+                    let moving = |a| matches!(a, Activity::Walk | Activity::Run);
+                    if !moving(self.activity.get()) || !moving(activity) {
+                        v.set_frame(0.0);
+                    }
+                }
+                v.set_sequence(sequence);
+                self.animating.reset_sequence_info(v);
+            }
+            None => {
+                debug!(
+                    "{}: has no sequence for activity {activity:?}",
+                    v.pretty_name()
+                );
+                v.set_sequence(0);
+                self.animating.set_sequence_finished(false);
+            }
+        }
+        self.activity.set(activity);
+        self.ideal_activity.set(activity);
+    }
+
+    fn frame_advance(&self, interval: f32) {
+        let v = self.vars();
+        if self.engine().get_studio_model(v).is_none() {
+            // monsters without a model finish sequences immediately
+            self.animating.set_sequence_finished(true);
+            return;
+        }
+        let interval = self.animating.frame_advance(v, interval);
+        let monster = self.monster();
+        self.animating
+            .dispatch_events(v, interval, |event| monster.handle_anim_event(event));
+    }
+
+    fn handle_anim_event(&self, event: &Event) {
+        let engine = self.engine();
+        let v = self.vars();
+        let in_script = self.state.get() == MonsterState::Script;
+        match event.event {
+            script_event::DEAD if in_script => {
+                v.set_dead(Dead::Dying);
+                v.set_health(0.0);
+            }
+            script_event::NOT_DEAD if in_script => {
+                v.set_dead(Dead::No);
+                v.set_health(v.max_health());
+            }
+            script_event::SOUND | script_event::SOUND_VOICE => {
+                let sound = engine.build_sound().attenuation(Attenuation::IDLE);
+                let sound = if event.event == script_event::SOUND {
+                    sound.channel_body()
+                } else {
+                    sound.channel_voice()
+                };
+                sound.emit_dyn(event.options(), v);
+            }
+            script_event::SENTENCE | script_event::SENTENCE_RND1 => {
+                if event.event == script_event::SENTENCE_RND1 && engine.random_int(0, 2) == 0 {
+                    return;
+                }
+                let group = event.options().to_engine_str();
+                engine
+                    .build_sound()
+                    .attenuation(Attenuation::IDLE)
+                    .emit_random_sentence(group.as_ref(), v);
+            }
+            script_event::FIRE_EVENT => {
+                let target = event.options().to_engine_str();
+                let this = self.private().as_entity();
+                utils::fire_targets(target.as_ref(), UseType::Toggle, Some(this), this);
+            }
+            script_event::NO_INTERRUPT | script_event::CAN_INTERRUPT => {
+                if let Some(script) = self.script() {
+                    script.allow_interrupt(event.event == script_event::CAN_INTERRUPT);
+                }
+            }
+            _ => {
+                trace!(
+                    "{}: unhandled animation event {} ({})",
+                    v.pretty_name(),
+                    event.event,
+                    event.options()
+                );
+            }
+        }
+    }
+
+    fn can_play_sentence(&self, _disregard_state: bool) -> bool {
+        self.is_alive()
+    }
+
+    fn play_scripted_sentence(
+        &self,
+        sentence: &CStrThin,
+        _duration: f32,
+        volume: f32,
+        attenuation: Attenuation,
+        _concurrent: bool,
+        _listener: Option<&dyn Entity>,
+    ) {
+        if !self.is_alive() {
+            return;
+        }
+        let v = self.vars();
+        let sound = self
+            .engine()
+            .build_sound()
+            .channel_voice()
+            .volume(volume)
+            .attenuation(attenuation);
+        if sentence.bytes().next() == Some(b'!') {
+            sound.emit_dyn(sentence, v);
+        } else {
+            sound.emit_random_sentence(sentence, v);
+        }
+    }
+
+    fn idle_sound(&self) {}
+
+    fn alert_sound(&self) {}
+
+    fn pain_sound(&self) {}
+
+    fn death_sound(&self) {}
+
+    fn gib(&self) {
+        let v = self.vars();
+        v.set_take_damage(TakeDamage::No);
+        v.set_dead(Dead::Yes);
+        self.remove_from_world();
+    }
+}
+
+impl_private!(BaseMonster { EntityMonster });
+
+#[cfg(all(test, feature = "std", feature = "save"))]
+mod tests {
+    use core::{ffi::c_int, mem};
+
+    use crate::{ffi, testing};
+
+    use super::*;
+
+    #[derive(Save, Restore)]
+    struct TestMonster {
+        base: BaseMonster,
+    }
+
+    impl CreateEntity for TestMonster {
+        fn create(base: BaseEntity) -> Self {
+            Self {
+                base: BaseMonster::create(base),
+            }
+        }
+    }
+
+    impl Entity for TestMonster {
+        delegate_entity!(base not { classify });
+
+        fn classify(&self) -> Class {
+            Class::HumanMilitary
+        }
+    }
+
+    impl EntityMonster for TestMonster {
+        delegate_monster!(base);
+    }
+
+    impl_private!(TestMonster { EntityMonster });
+
+    #[derive(Save, Restore)]
+    struct Target {
+        base: BaseEntity,
+        #[save(skip)]
+        class: Cell<Class>,
+    }
+
+    impl CreateEntity for Target {
+        fn create(base: BaseEntity) -> Self {
+            Self {
+                base,
+                class: Cell::new(Class::None),
+            }
+        }
+    }
+
+    impl Entity for Target {
+        delegate_entity!(base not { classify });
+
+        fn classify(&self) -> Class {
+            self.class.get()
+        }
+    }
+
+    impl_private!(Target {});
+
+    fn new_monster(engine: &ServerEngine) -> &mut TestMonster {
+        let monster = engine.new_entity::<TestMonster>().build();
+        let v = monster.vars();
+        v.set_health(100.0);
+        v.set_max_health(100.0);
+        v.set_take_damage(TakeDamage::Yes);
+        monster
+    }
+
+    #[test]
+    fn relationship_by_class() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let monster = new_monster(&engine);
+        let target = engine.new_entity::<Target>().build();
+
+        assert_eq!(monster.relationship(&*target), Relationship::None);
+        target.class.set(Class::Player);
+        assert_eq!(monster.relationship(&*target), Relationship::Hate);
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+            engine.remove_entity_now(target.vars());
+        }
+    }
+
+    #[test]
+    fn body_target() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let monster = new_monster(&engine);
+        let v = monster.vars();
+        v.set_abs_min(vec3_t::new(-16.0, -16.0, 0.0));
+        v.set_abs_max(vec3_t::new(16.0, 16.0, 72.0));
+        v.set_view_ofs(vec3_t::new(0.0, 0.0, 64.0));
+
+        // between the center and the eyes
+        let target = monster.body_target(vec3_t::ZERO);
+        assert_eq!(target, vec3_t::new(0.0, 0.0, 43.0));
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+        }
+    }
+
+    #[test]
+    fn trace_attack_hit_group() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let global_state = test.global_state();
+        let monster = new_monster(&engine);
+        let attacker = engine.new_entity::<Target>().build();
+        let attacker = attacker.vars();
+
+        let mut raw: ffi::server::TraceResult = unsafe { mem::zeroed() };
+        raw.iHitgroup = HitGroup::Head.into_raw() as c_int;
+        let trace = TraceResult::new(&engine, raw);
+
+        let multi_damage = global_state.multi_damage();
+        multi_damage.clear();
+        let dir = vec3_t::new(1.0, 0.0, 0.0);
+        monster.trace_attack(attacker, 10.0, dir, &trace, DamageFlags::BULLET);
+        assert_eq!(monster.base.last_hit_group(), HitGroup::Head);
+        // the damage is applied with the multi damage
+        assert_eq!(monster.vars().health(), 100.0);
+        multi_damage.apply(attacker, attacker);
+        assert_eq!(monster.vars().health(), 90.0);
+
+        // the hit group is not changed if the monster can not be damaged
+        monster.vars().set_take_damage(TakeDamage::No);
+        raw.iHitgroup = HitGroup::Chest.into_raw() as c_int;
+        let trace = TraceResult::new(&engine, raw);
+        monster.trace_attack(attacker, 10.0, dir, &trace, DamageFlags::BULLET);
+        assert_eq!(monster.base.last_hit_group(), HitGroup::Head);
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+            engine.remove_entity_now(attacker);
+        }
+    }
+
+    #[test]
+    fn take_damage_turns_to_attacker() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let monster = new_monster(&engine);
+        let attacker = engine.new_entity::<Target>().build();
+        let attacker = attacker.vars();
+        attacker.set_flags(EdictFlags::CLIENT);
+        attacker.set_origin(vec3_t::new(0.0, 128.0, 0.0));
+
+        assert!(monster.take_damage(25.0, DamageFlags::BULLET, attacker, Some(attacker)));
+        let base = &monster.base;
+        assert_eq!(monster.vars().health(), 75.0);
+        assert!(base.has_all_conditions(Conditions::LIGHT_DAMAGE | Conditions::HEAVY_DAMAGE));
+        assert_eq!(base.enemy_lkp(), attacker.origin());
+        assert_eq!(monster.vars().ideal_yaw(), 90.0);
+        assert_eq!(base.get_ideal_state(), MonsterState::Alert);
+
+        base.set_conditions(Conditions::NEW_ENEMY);
+        assert_eq!(base.get_ideal_state(), MonsterState::Combat);
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+            engine.remove_entity_now(attacker);
+        }
+    }
+
+    #[test]
+    fn killed_leaves_body() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let monster = new_monster(&engine);
+        let attacker = engine.new_entity::<Target>().build();
+        let attacker = attacker.vars();
+
+        assert!(!monster.take_damage(110.0, DamageFlags::BULLET, attacker, None));
+        let v = monster.vars();
+        assert_eq!(v.dead(), Dead::Dying);
+        assert_eq!(v.take_damage(), TakeDamage::Yes);
+        assert_eq!(v.health(), 50.0);
+        assert_eq!(v.max_health(), 5.0);
+        assert!(monster.base.has_memory(Memory::KILLED));
+        assert_eq!(monster.base.ideal_state(), MonsterState::Dead);
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+            engine.remove_entity_now(attacker);
+        }
+    }
+}
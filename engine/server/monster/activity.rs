@@ -0,0 +1,126 @@
+use xash3d_shared::macros::define_enum_for_primitive;
+
+#[cfg(feature = "save")]
+use crate::save;
+
+define_enum_for_primitive! {
+    /// An animation activity of a monster.
+    ///
+    /// Studio model sequences are tagged with activities, so the AI can play an animation
+    /// without knowing its name.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub enum Activity: i32 {
+        /// Set to force a sequence change.
+        #[default]
+        Reset(0),
+        Idle(1),
+        Guard(2),
+        Walk(3),
+        Run(4),
+        Fly(5),
+        Swim(6),
+        Hop(7),
+        Leap(8),
+        Fall(9),
+        Land(10),
+        StrafeLeft(11),
+        StrafeRight(12),
+        RollLeft(13),
+        RollRight(14),
+        TurnLeft(15),
+        TurnRight(16),
+        Crouch(17),
+        CrouchIdle(18),
+        Stand(19),
+        Use(20),
+        Signal1(21),
+        Signal2(22),
+        Signal3(23),
+        Twitch(24),
+        Cower(25),
+        SmallFlinch(26),
+        BigFlinch(27),
+        RangeAttack1(28),
+        RangeAttack2(29),
+        MeleeAttack1(30),
+        MeleeAttack2(31),
+        Reload(32),
+        Arm(33),
+        Disarm(34),
+        Eat(35),
+        DieSimple(36),
+        DieBackward(37),
+        DieForward(38),
+        DieViolent(39),
+        BarnacleHit(40),
+        BarnaclePull(41),
+        BarnacleChomp(42),
+        BarnacleChew(43),
+        Sleep(44),
+        InspectFloor(45),
+        InspectWall(46),
+        IdleAngry(47),
+        WalkHurt(48),
+        RunHurt(49),
+        Hover(50),
+        Glide(51),
+        FlyLeft(52),
+        FlyRight(53),
+        DetectScent(54),
+        Sniff(55),
+        Bite(56),
+        ThreatDisplay(57),
+        FearDisplay(58),
+        Excited(59),
+        SpecialAttack1(60),
+        SpecialAttack2(61),
+        CombatIdle(62),
+        WalkScared(63),
+        RunScared(64),
+        VictoryDance(65),
+        DieHeadshot(66),
+        DieChestshot(67),
+        DieGutshot(68),
+        DieBackshot(69),
+        FlinchHead(70),
+        FlinchChest(71),
+        FlinchStomach(72),
+        FlinchLeftArm(73),
+        FlinchRightArm(74),
+        FlinchLeftLeg(75),
+        FlinchRightLeg(76),
+    }
+}
+
+impl Activity {
+    /// Returns `true` if this is a movement activity.
+    pub fn is_movement(self) -> bool {
+        matches!(
+            self,
+            Self::Walk
+                | Self::Run
+                | Self::Fly
+                | Self::Swim
+                | Self::WalkHurt
+                | Self::RunHurt
+                | Self::WalkScared
+                | Self::RunScared
+        )
+    }
+}
+
+#[cfg(feature = "save")]
+impl save::Save for Activity {
+    fn save(&self, _: &mut save::SaveState, cur: &mut save::CursorMut) -> save::SaveResult<()> {
+        cur.write_leb_i32(self.into_raw())?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "save")]
+impl save::Restore for Activity {
+    fn restore(&mut self, _: &save::RestoreState, cur: &mut save::Cursor) -> save::SaveResult<()> {
+        *self = Self::from_raw(cur.read_leb_i32()?).unwrap_or_default();
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use xash3d_shared::macros::define_enum_for_primitive;
+
+define_enum_for_primitive! {
+    /// A class of an entity used to find out how monsters feel about each other.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub enum Class: i32 {
+        #[default]
+        None(0),
+        Machine(1),
+        Player(2),
+        HumanPassive(3),
+        HumanMilitary(4),
+        AlienMilitary(5),
+        AlienPassive(6),
+        AlienMonster(7),
+        AlienPrey(8),
+        AlienPredator(9),
+        Insect(10),
+        PlayerAlly(11),
+        /// Hornets and snarks launched by players.
+        PlayerBioweapon(12),
+        /// Hornets and snarks launched by the alien menace.
+        AlienBioweapon(13),
+        /// A special case for barnacles, they are not in the relationship table.
+        Barnacle(99),
+    }
+}
+
+/// How a monster feels about another entity.
+///
+/// Variants are ordered from the most friendly to the most hostile.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Relationship {
+    /// A pal, good alliance.
+    Ally = -2,
+    /// Will run.
+    Fear = -1,
+    /// Disregard.
+    #[default]
+    None = 0,
+    /// Will attack.
+    Dislike = 1,
+    /// Will attack this character instead of any visible disliked characters.
+    Hate = 2,
+    /// A monster will attack.
+    Nemesis = 3,
+}
+
+impl Relationship {
+    /// Returns `true` if a monster will attack.
+    pub fn is_hostile(self) -> bool {
+        self >= Self::Dislike
+    }
+}
+
+impl Class {
+    /// Returns how a monster of this class feels about an entity of the `other` class.
+    pub fn relationship(self, other: Class) -> Relationship {
+        use Relationship::{Ally as AL, Dislike as DL, Fear as FR, Hate as HT, None as NO};
+
+        #[rustfmt::skip]
+        const TABLE: [[Relationship; 14]; 14] = [
+            // NONE MACH PLYR HPASS HMIL AMIL APASS AMONST APREY APRED INSECT PLRALY PBWPN ABWPN
+            [NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO], // NONE
+            [NO, NO, DL, DL, NO, DL, DL, DL, DL, DL, NO, DL, DL, DL], // MACHINE
+            [NO, DL, NO, NO, DL, DL, DL, DL, DL, DL, NO, NO, DL, DL], // PLAYER
+            [NO, NO, AL, AL, HT, FR, NO, HT, DL, FR, NO, AL, NO, NO], // HUMAN_PASSIVE
+            [NO, NO, HT, DL, NO, HT, DL, DL, DL, DL, NO, HT, NO, NO], // HUMAN_MILITARY
+            [NO, DL, HT, DL, HT, NO, NO, NO, NO, NO, NO, DL, NO, NO], // ALIEN_MILITARY
+            [NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO, NO], // ALIEN_PASSIVE
+            [NO, DL, DL, DL, DL, NO, NO, NO, NO, NO, NO, DL, NO, NO], // ALIEN_MONSTER
+            [NO, NO, DL, DL, DL, NO, NO, NO, NO, FR, NO, DL, NO, NO], // ALIEN_PREY
+            [NO, NO, DL, DL, DL, NO, NO, NO, HT, DL, NO, DL, NO, NO], // ALIEN_PREDATOR
+            [FR, FR, FR, FR, FR, NO, FR, FR, FR, FR, NO, FR, NO, NO], // INSECT
+            [NO, DL, DL, DL, DL, DL, NO, DL, DL, DL, NO, NO, NO, NO], // PLAYER_ALLY
+            [NO, NO, DL, DL, DL, DL, DL, DL, DL, DL, NO, DL, NO, DL], // PLAYER_BIOWEAPON
+            [NO, NO, DL, DL, DL, AL, NO, DL, DL, NO, NO, DL, DL, NO], // ALIEN_BIOWEAPON
+        ];
+
+        let index = |class: Class| TABLE.get(class.into_raw() as usize);
+        match (index(self), index(other)) {
+            (Some(row), Some(_)) => row[other.into_raw() as usize],
+            _ => Relationship::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relationship_table() {
+        assert_eq!(
+            Class::Player.relationship(Class::Player),
+            Relationship::None
+        );
+        assert_eq!(
+            Class::HumanMilitary.relationship(Class::Player),
+            Relationship::Hate
+        );
+        assert_eq!(
+            Class::HumanPassive.relationship(Class::Player),
+            Relationship::Ally
+        );
+        assert_eq!(
+            Class::HumanPassive.relationship(Class::AlienMilitary),
+            Relationship::Fear
+        );
+        assert_eq!(
+            Class::AlienBioweapon.relationship(Class::AlienMilitary),
+            Relationship::Ally
+        );
+        assert_eq!(
+            Class::Barnacle.relationship(Class::Player),
+            Relationship::None
+        );
+        assert_eq!(
+            Class::Player.relationship(Class::Barnacle),
+            Relationship::None
+        );
+    }
+
+    #[test]
+    fn relationship_order() {
+        assert!(Relationship::Nemesis > Relationship::Hate);
+        assert!(Relationship::Hate > Relationship::Dislike);
+        assert!(Relationship::Dislike.is_hostile());
+        assert!(!Relationship::Fear.is_hostile());
+    }
+}
@@ -0,0 +1,92 @@
+use xash3d_shared::ffi::common::vec3_t;
+
+#[cfg(feature = "save")]
+use crate::save::{Restore, Save};
+use crate::{engine::WalkMove, entity::EntityVars, prelude::*};
+
+/// A type of the monster movement goal.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum MoveGoal {
+    #[default]
+    None,
+    Enemy,
+    Target,
+    Location,
+}
+
+/// A list of positions a monster walks through to reach the goal.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Route {
+    waypoints: [vec3_t; Self::MAX_WAYPOINTS],
+    len: usize,
+    index: usize,
+}
+
+impl Route {
+    pub const MAX_WAYPOINTS: usize = 8;
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.index = 0;
+    }
+
+    /// Appends a waypoint to the route.
+    ///
+    /// Returns `false` if the route is full.
+    pub fn push(&mut self, position: vec3_t) -> bool {
+        if self.len == Self::MAX_WAYPOINTS {
+            return false;
+        }
+        self.waypoints[self.len] = position;
+        self.len += 1;
+        true
+    }
+
+    /// Returns `true` if all waypoints have been reached.
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.len
+    }
+
+    /// Returns the waypoint the monster is moving to.
+    pub fn current(&self) -> Option<vec3_t> {
+        self.waypoints[..self.len].get(self.index).copied()
+    }
+
+    /// Returns the final waypoint of the route.
+    pub fn goal(&self) -> Option<vec3_t> {
+        self.waypoints[..self.len].last().copied()
+    }
+
+    /// Moves to the next waypoint.
+    pub fn advance(&mut self) {
+        if self.index < self.len {
+            self.index += 1;
+        }
+    }
+}
+
+/// The distance checked at once by [check_local_move].
+pub const LOCAL_STEP_SIZE: f32 = 16.0;
+
+/// Returns the distance a monster can walk in a straight line from `start` to `end`.
+pub fn check_local_move(engine: &ServerEngine, v: &EntityVars, start: vec3_t, end: vec3_t) -> f32 {
+    let saved = v.origin();
+    v.set_origin_and_link(start);
+
+    let dir = (end - start).with_z(0.0);
+    let dist = dir.length();
+    let yaw = engine.vec_to_yaw(dir);
+    let mut moved = 0.0;
+    while moved < dist {
+        let step = (dist - moved).min(LOCAL_STEP_SIZE);
+        if !engine.walk_move(v, yaw, step, WalkMove::CheckOnly) {
+            break;
+        }
+        moved += step;
+    }
+
+    v.set_origin_and_link(saved);
+    moved
+}
@@ -0,0 +1,555 @@
+use super::{Activity, Conditions, Memory, MonsterState, SoundTypes};
+
+#[cfg(feature = "save")]
+use crate::save::{Restore, Save};
+
+/// A single step of a [Schedule].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Task {
+    /// Waits for the given number of seconds.
+    Wait(f32),
+    /// Waits for the given number of seconds and turns to the enemy.
+    WaitFaceEnemy(f32),
+    /// Waits for a random time up to the given number of seconds.
+    WaitRandom(f32),
+    /// Waits until a player is in the monster PVS.
+    WaitPvs,
+    /// Waits until the schedule is interrupted.
+    WaitIndefinite,
+    StopMoving,
+    SetActivity(Activity),
+    /// Plays a sequence of the activity until it is finished.
+    PlaySequence(Activity),
+    /// Plays a sequence of the activity and turns to the enemy.
+    PlaySequenceFaceEnemy(Activity),
+    FaceIdeal,
+    FaceEnemy,
+    FaceTarget,
+    FaceRoute,
+    GetPathToEnemy,
+    GetPathToEnemyLkp,
+    GetPathToTarget,
+    GetPathToBestSound,
+    /// Finds a position hidden from the origin of a sound or damage.
+    FindCoverFromOrigin,
+    FindCoverFromEnemy,
+    FindCoverFromBestSound,
+    /// Moves to the target entity until it is within the given distance.
+    MoveToTargetRange(f32),
+    WalkPath,
+    RunPath,
+    /// Walks to the target entity.
+    WalkToTarget,
+    /// Runs to the target entity.
+    RunToTarget,
+    WaitForMovement,
+    RangeAttack1,
+    RangeAttack2,
+    MeleeAttack1,
+    MeleeAttack2,
+    SmallFlinch,
+    Die,
+    SoundWake,
+    SoundDie,
+    ClearMoveWait,
+    Remember(Memory),
+    Forget(Memory),
+    /// Changes the ideal monster state.
+    SuggestState(MonsterState),
+    /// Completes the current schedule and starts a new one.
+    SetSchedule(ScheduleType),
+    /// Sets a schedule to run if a task of the current schedule fails.
+    SetFailSchedule(ScheduleType),
+    /// Moves the monster to the origin of the scripted sequence.
+    PlantOnScript,
+    /// Turns to the angles of the scripted sequence.
+    FaceScript,
+    /// Allows scripted sequences with the same name to start.
+    EnableScript,
+    /// Plays the idle sequence until the script is ready.
+    WaitForScript,
+    /// Plays the sequence of the script until it is finished.
+    PlayScript,
+    /// A monster specific task with an id and a parameter.
+    Custom(u32, f32),
+}
+
+/// A status of the current task.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum TaskStatus {
+    /// Just started.
+    #[default]
+    New,
+    /// Running task and movement.
+    Running,
+    /// Just running movement.
+    RunningMovement,
+    /// Just running task.
+    RunningTask,
+    /// Completed, get the next task.
+    Complete,
+}
+
+/// Schedules shared by all monsters.
+///
+/// Monsters can replace them with [EntityMonster::schedule_of_type](super::EntityMonster::schedule_of_type).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum ScheduleType {
+    #[default]
+    None,
+    IdleStand,
+    IdleWalk,
+    WakeAngry,
+    AlertFace,
+    AlertSmallFlinch,
+    AlertStand,
+    InvestigateSound,
+    CombatFace,
+    CombatStand,
+    ChaseEnemy,
+    ChaseEnemyFailed,
+    VictoryDance,
+    TargetFace,
+    TargetChase,
+    SmallFlinch,
+    TakeCoverFromEnemy,
+    TakeCoverFromOrigin,
+    TakeCoverFromBestSound,
+    RangeAttack1,
+    RangeAttack2,
+    MeleeAttack1,
+    MeleeAttack2,
+    Die,
+    Fail,
+    /// Waits for an enemy.
+    Ambush,
+    ScriptedWait,
+    ScriptedWalk,
+    ScriptedRun,
+    ScriptedFace,
+}
+
+/// A list of tasks that a monster runs until it is done or interrupted.
+#[derive(Debug)]
+pub struct Schedule {
+    pub name: &'static str,
+    pub tasks: &'static [Task],
+    /// Conditions that interrupt this schedule.
+    pub interrupt: Conditions,
+    /// Sounds a monster listens for while running this schedule.
+    pub sounds: SoundTypes,
+}
+
+impl Schedule {
+    pub const fn new(name: &'static str, tasks: &'static [Task]) -> Self {
+        Self {
+            name,
+            tasks,
+            interrupt: Conditions::empty(),
+            sounds: SoundTypes::NONE,
+        }
+    }
+
+    pub const fn interrupt(mut self, conditions: Conditions) -> Self {
+        self.interrupt = conditions;
+        self
+    }
+
+    pub const fn sounds(mut self, sounds: SoundTypes) -> Self {
+        self.sounds = sounds;
+        self
+    }
+
+    /// Returns a task by an index or `None` if the schedule is done.
+    pub fn task(&self, index: usize) -> Option<&'static Task> {
+        self.tasks.get(index)
+    }
+}
+
+const IDLE_SOUNDS: SoundTypes = SoundTypes::COMBAT
+    .union(SoundTypes::WORLD)
+    .union(SoundTypes::PLAYER)
+    .union(SoundTypes::DANGER)
+    .union(SoundTypes::SCENTS);
+
+const DAMAGE: Conditions = Conditions::LIGHT_DAMAGE.union(Conditions::HEAVY_DAMAGE);
+
+const IDLE_INTERRUPT: Conditions = Conditions::NEW_ENEMY
+    .union(Conditions::SEE_FEAR)
+    .union(DAMAGE)
+    .union(Conditions::HEAR_SOUND)
+    .union(Conditions::SMELL_FOOD)
+    .union(Conditions::SMELL)
+    .union(Conditions::PROVOKED);
+
+static FAIL: Schedule = Schedule::new(
+    "Fail",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::Wait(2.0),
+        Task::WaitPvs,
+    ],
+)
+.interrupt(Conditions::CAN_ATTACK);
+
+static IDLE_STAND: Schedule = Schedule::new(
+    "IdleStand",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::Wait(5.0),
+    ],
+)
+.interrupt(IDLE_INTERRUPT)
+.sounds(IDLE_SOUNDS);
+
+static IDLE_WALK: Schedule = Schedule::new("IdleWalk", &[Task::WalkPath, Task::WaitForMovement])
+    .interrupt(IDLE_INTERRUPT)
+    .sounds(IDLE_SOUNDS);
+
+static WAKE_ANGRY: Schedule = Schedule::new(
+    "WakeAngry",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::SoundWake,
+        Task::FaceIdeal,
+    ],
+);
+
+static ALERT_FACE: Schedule = Schedule::new("AlertFace", &[Task::StopMoving, Task::FaceIdeal])
+    .interrupt(
+        Conditions::NEW_ENEMY
+            .union(Conditions::SEE_FEAR)
+            .union(DAMAGE)
+            .union(Conditions::PROVOKED),
+    );
+
+static ALERT_SMALL_FLINCH: Schedule = Schedule::new(
+    "AlertSmallFlinch",
+    &[
+        Task::StopMoving,
+        Task::Remember(Memory::FLINCHED),
+        Task::SmallFlinch,
+        Task::SetSchedule(ScheduleType::AlertFace),
+    ],
+);
+
+static ALERT_STAND: Schedule = Schedule::new(
+    "AlertStand",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::Wait(20.0),
+        Task::SuggestState(MonsterState::Idle),
+    ],
+)
+.interrupt(IDLE_INTERRUPT.union(Conditions::SEE_ENEMY))
+.sounds(IDLE_SOUNDS);
+
+static INVESTIGATE_SOUND: Schedule = Schedule::new(
+    "InvestigateSound",
+    &[
+        Task::StopMoving,
+        Task::GetPathToBestSound,
+        Task::FaceIdeal,
+        Task::WalkPath,
+        Task::WaitForMovement,
+        Task::PlaySequence(Activity::Idle),
+        Task::Wait(10.0),
+    ],
+)
+.interrupt(
+    Conditions::NEW_ENEMY
+        .union(Conditions::SEE_FEAR)
+        .union(DAMAGE)
+        .union(Conditions::HEAR_SOUND),
+)
+.sounds(SoundTypes::DANGER);
+
+static COMBAT_FACE: Schedule = Schedule::new(
+    "CombatFace",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::FaceEnemy,
+    ],
+)
+.interrupt(
+    Conditions::CAN_ATTACK
+        .union(Conditions::NEW_ENEMY)
+        .union(Conditions::ENEMY_DEAD),
+);
+
+static COMBAT_STAND: Schedule = Schedule::new(
+    "CombatStand",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::WaitIndefinite,
+    ],
+)
+.interrupt(
+    Conditions::NEW_ENEMY
+        .union(Conditions::ENEMY_DEAD)
+        .union(DAMAGE)
+        .union(Conditions::CAN_ATTACK),
+);
+
+static CHASE_ENEMY: Schedule = Schedule::new(
+    "ChaseEnemy",
+    &[
+        Task::SetFailSchedule(ScheduleType::ChaseEnemyFailed),
+        Task::GetPathToEnemy,
+        Task::RunPath,
+        Task::WaitForMovement,
+    ],
+)
+.interrupt(
+    Conditions::NEW_ENEMY
+        .union(Conditions::CAN_ATTACK)
+        .union(Conditions::TASK_FAILED)
+        .union(Conditions::HEAR_SOUND),
+)
+.sounds(SoundTypes::DANGER);
+
+static CHASE_ENEMY_FAILED: Schedule = Schedule::new(
+    "ChaseEnemyFailed",
+    &[
+        Task::StopMoving,
+        Task::Wait(0.2),
+        Task::FindCoverFromEnemy,
+        Task::RunPath,
+        Task::WaitForMovement,
+        Task::Remember(Memory::IN_COVER),
+        Task::FaceEnemy,
+        Task::Wait(1.0),
+    ],
+)
+.interrupt(
+    Conditions::NEW_ENEMY
+        .union(Conditions::CAN_ATTACK)
+        .union(Conditions::HEAR_SOUND),
+)
+.sounds(SoundTypes::DANGER);
+
+static VICTORY_DANCE: Schedule = Schedule::new(
+    "VictoryDance",
+    &[
+        Task::StopMoving,
+        Task::PlaySequence(Activity::VictoryDance),
+        Task::Wait(0.0),
+    ],
+);
+
+static TARGET_FACE: Schedule = Schedule::new(
+    "TargetFace",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::FaceTarget,
+    ],
+)
+.interrupt(Conditions::NEW_ENEMY.union(Conditions::HEAR_SOUND));
+
+static TARGET_CHASE: Schedule = Schedule::new(
+    "TargetChase",
+    &[Task::GetPathToTarget, Task::RunPath, Task::WaitForMovement],
+)
+.interrupt(
+    Conditions::NEW_ENEMY
+        .union(DAMAGE)
+        .union(Conditions::PROVOKED)
+        .union(Conditions::HEAR_SOUND),
+);
+
+static SMALL_FLINCH: Schedule = Schedule::new(
+    "SmallFlinch",
+    &[
+        Task::Remember(Memory::FLINCHED),
+        Task::StopMoving,
+        Task::SmallFlinch,
+    ],
+);
+
+static TAKE_COVER_FROM_ENEMY: Schedule = Schedule::new(
+    "TakeCoverFromEnemy",
+    &[
+        Task::StopMoving,
+        Task::Wait(0.2),
+        Task::FindCoverFromEnemy,
+        Task::RunPath,
+        Task::WaitForMovement,
+        Task::Remember(Memory::IN_COVER),
+        Task::FaceEnemy,
+        Task::Wait(1.0),
+    ],
+)
+.interrupt(Conditions::NEW_ENEMY);
+
+static TAKE_COVER_FROM_ORIGIN: Schedule = Schedule::new(
+    "TakeCoverFromOrigin",
+    &[
+        Task::StopMoving,
+        Task::FindCoverFromOrigin,
+        Task::RunPath,
+        Task::WaitForMovement,
+        Task::Remember(Memory::IN_COVER),
+        Task::FaceIdeal,
+    ],
+)
+.interrupt(Conditions::NEW_ENEMY);
+
+static TAKE_COVER_FROM_BEST_SOUND: Schedule = Schedule::new(
+    "TakeCoverFromBestSound",
+    &[
+        Task::StopMoving,
+        Task::FindCoverFromBestSound,
+        Task::RunPath,
+        Task::WaitForMovement,
+        Task::Remember(Memory::IN_COVER),
+        Task::FaceIdeal,
+    ],
+)
+.interrupt(Conditions::NEW_ENEMY);
+
+const ATTACK_INTERRUPT: Conditions = Conditions::NEW_ENEMY
+    .union(Conditions::ENEMY_DEAD)
+    .union(DAMAGE)
+    .union(Conditions::ENEMY_OCCLUDED);
+
+static RANGE_ATTACK1: Schedule = Schedule::new(
+    "RangeAttack1",
+    &[Task::StopMoving, Task::FaceEnemy, Task::RangeAttack1],
+)
+.interrupt(
+    ATTACK_INTERRUPT
+        .union(Conditions::NO_AMMO_LOADED)
+        .union(Conditions::HEAR_SOUND),
+)
+.sounds(SoundTypes::DANGER);
+
+static RANGE_ATTACK2: Schedule = Schedule::new(
+    "RangeAttack2",
+    &[Task::StopMoving, Task::FaceEnemy, Task::RangeAttack2],
+)
+.interrupt(ATTACK_INTERRUPT.union(Conditions::HEAR_SOUND))
+.sounds(SoundTypes::DANGER);
+
+static MELEE_ATTACK1: Schedule = Schedule::new(
+    "MeleeAttack1",
+    &[Task::StopMoving, Task::FaceEnemy, Task::MeleeAttack1],
+)
+.interrupt(ATTACK_INTERRUPT);
+
+static MELEE_ATTACK2: Schedule = Schedule::new(
+    "MeleeAttack2",
+    &[Task::StopMoving, Task::FaceEnemy, Task::MeleeAttack2],
+)
+.interrupt(ATTACK_INTERRUPT);
+
+static DIE: Schedule = Schedule::new("Die", &[Task::StopMoving, Task::SoundDie, Task::Die]);
+
+static AMBUSH: Schedule = Schedule::new(
+    "Ambush",
+    &[
+        Task::StopMoving,
+        Task::SetActivity(Activity::Idle),
+        Task::WaitIndefinite,
+    ],
+)
+.interrupt(
+    Conditions::NEW_ENEMY
+        .union(DAMAGE)
+        .union(Conditions::PROVOKED),
+);
+
+static SCRIPTED_WAIT: Schedule = Schedule::new(
+    "ScriptedWait",
+    &[Task::StopMoving, Task::WaitForScript, Task::PlayScript],
+)
+.interrupt(DAMAGE);
+
+static SCRIPTED_WALK: Schedule = Schedule::new(
+    "ScriptedWalk",
+    &[
+        Task::WalkToTarget,
+        Task::WaitForMovement,
+        Task::PlantOnScript,
+        Task::FaceScript,
+        Task::FaceIdeal,
+        Task::EnableScript,
+        Task::WaitForScript,
+        Task::PlayScript,
+    ],
+)
+.interrupt(DAMAGE);
+
+static SCRIPTED_RUN: Schedule = Schedule::new(
+    "ScriptedRun",
+    &[
+        Task::RunToTarget,
+        Task::WaitForMovement,
+        Task::PlantOnScript,
+        Task::FaceScript,
+        Task::FaceIdeal,
+        Task::EnableScript,
+        Task::WaitForScript,
+        Task::PlayScript,
+    ],
+)
+.interrupt(DAMAGE);
+
+static SCRIPTED_FACE: Schedule = Schedule::new(
+    "ScriptedFace",
+    &[
+        Task::StopMoving,
+        Task::FaceScript,
+        Task::FaceIdeal,
+        Task::WaitForScript,
+        Task::PlayScript,
+    ],
+)
+.interrupt(DAMAGE);
+
+impl ScheduleType {
+    /// Returns a default schedule of this type.
+    pub fn default_schedule(self) -> &'static Schedule {
+        match self {
+            Self::None | Self::Fail => &FAIL,
+            Self::IdleStand => &IDLE_STAND,
+            Self::IdleWalk => &IDLE_WALK,
+            Self::WakeAngry => &WAKE_ANGRY,
+            Self::AlertFace => &ALERT_FACE,
+            Self::AlertSmallFlinch => &ALERT_SMALL_FLINCH,
+            Self::AlertStand => &ALERT_STAND,
+            Self::InvestigateSound => &INVESTIGATE_SOUND,
+            Self::CombatFace => &COMBAT_FACE,
+            Self::CombatStand => &COMBAT_STAND,
+            Self::ChaseEnemy => &CHASE_ENEMY,
+            Self::ChaseEnemyFailed => &CHASE_ENEMY_FAILED,
+            Self::VictoryDance => &VICTORY_DANCE,
+            Self::TargetFace => &TARGET_FACE,
+            Self::TargetChase => &TARGET_CHASE,
+            Self::SmallFlinch => &SMALL_FLINCH,
+            Self::TakeCoverFromEnemy => &TAKE_COVER_FROM_ENEMY,
+            Self::TakeCoverFromOrigin => &TAKE_COVER_FROM_ORIGIN,
+            Self::TakeCoverFromBestSound => &TAKE_COVER_FROM_BEST_SOUND,
+            Self::RangeAttack1 => &RANGE_ATTACK1,
+            Self::RangeAttack2 => &RANGE_ATTACK2,
+            Self::MeleeAttack1 => &MELEE_ATTACK1,
+            Self::MeleeAttack2 => &MELEE_ATTACK2,
+            Self::Die => &DIE,
+            Self::Ambush => &AMBUSH,
+            Self::ScriptedWait => &SCRIPTED_WAIT,
+            Self::ScriptedWalk => &SCRIPTED_WALK,
+            Self::ScriptedRun => &SCRIPTED_RUN,
+            Self::ScriptedFace => &SCRIPTED_FACE,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use bitflags::bitflags;
+
+#[cfg(feature = "save")]
+use crate::save::{Restore, Save};
+use crate::{entity::Entity, str::MapString};
+
+use super::{BaseMonster, Conditions};
+
+/// Conditions that interrupt a scripted sequence if it can be interrupted.
+pub const SCRIPT_BREAK_CONDITIONS: Conditions =
+    Conditions::LIGHT_DAMAGE.union(Conditions::HEAVY_DAMAGE);
+
+/// Animation events handled by all monsters.
+pub mod script_event {
+    /// The monster is dead at the end of the script.
+    pub const DEAD: i32 = 1000;
+    pub const NO_INTERRUPT: i32 = 1001;
+    pub const CAN_INTERRUPT: i32 = 1002;
+    /// Fires targets by name.
+    pub const FIRE_EVENT: i32 = 1003;
+    /// Plays a sound on the body channel.
+    pub const SOUND: i32 = 1004;
+    /// Plays a random sentence from a group.
+    pub const SENTENCE: i32 = 1005;
+    /// Leaves the monster in the air at the end of the script.
+    pub const IN_AIR: i32 = 1006;
+    pub const END_ANIMATION: i32 = 1007;
+    /// Plays a sound on the voice channel.
+    pub const SOUND_VOICE: i32 = 1008;
+    /// Sometimes plays a random sentence from a group.
+    pub const SENTENCE_RND1: i32 = 1009;
+    /// The monster is alive at the end of the script.
+    pub const NOT_DEAD: i32 = 1010;
+}
+
+/// A state of a monster controlled by a scripted sequence.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum ScriptState {
+    #[default]
+    Playing,
+    Wait,
+    Cleanup,
+    WalkToMark,
+    RunToMark,
+}
+
+/// How a monster gets to a scripted sequence.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum ScriptMoveTo {
+    /// Plays the sequence where the monster stands.
+    #[default]
+    No,
+    Walk,
+    Run,
+    /// Teleports the monster to the script.
+    Instant,
+    /// Turns to the script angles.
+    Turn,
+}
+
+impl ScriptMoveTo {
+    pub fn from_raw(raw: i32) -> Option<Self> {
+        match raw {
+            0 => Some(Self::No),
+            1 => Some(Self::Walk),
+            2 => Some(Self::Run),
+            4 => Some(Self::Instant),
+            5 => Some(Self::Turn),
+            _ => None,
+        }
+    }
+}
+
+/// A level of monster states a scripted sequence can interrupt.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InterruptLevel {
+    Idle,
+    ByName,
+    Ai,
+}
+
+bitflags! {
+    /// Spawn flags of scripted sequences.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub struct ScriptSpawnFlags: u32 {
+        const WAIT_TILL_SEEN        = 1 << 0;
+        const EXIT_AGITATED         = 1 << 1;
+        const REPEATABLE            = 1 << 2;
+        /// Do not fade the monster if it dies in the script.
+        const LEAVE_CORPSE          = 1 << 3;
+        const NO_INTERRUPT          = 1 << 5;
+        /// Possess monsters in any state.
+        const OVERRIDE_STATE        = 1 << 6;
+        /// Do not move the monster to the root bone position after the script.
+        const NO_SCRIPT_MOVEMENT    = 1 << 7;
+    }
+}
+
+/// An entity that controls monsters with scripted sequences.
+pub trait EntityScript: Entity {
+    fn script_flags(&self) -> ScriptSpawnFlags;
+
+    fn idle_sequence(&self) -> Option<MapString>;
+
+    fn play_sequence(&self) -> Option<MapString>;
+
+    fn move_to(&self) -> ScriptMoveTo;
+
+    /// Returns `true` if the play sequence can be started.
+    fn is_ready(&self) -> bool;
+
+    /// Delays or resumes scripts with the same name until all monsters are at their marks.
+    fn delay_start(&self, delay: bool);
+
+    fn can_interrupt(&self) -> bool;
+
+    fn allow_interrupt(&self, allow: bool);
+
+    /// Starts the sequence on the monster.
+    ///
+    /// Finishes the script if `sequence` is `None` and `complete_on_empty` is set.
+    fn start_sequence(
+        &self,
+        monster: &BaseMonster,
+        sequence: Option<MapString>,
+        complete_on_empty: bool,
+    ) -> bool;
+
+    /// Called by the monster when the play sequence is finished.
+    fn sequence_done(&self, monster: &BaseMonster);
+
+    /// Restores the state of the monster saved when the script possessed it and forgets the
+    /// monster.
+    fn release(&self, monster: &BaseMonster);
+
+    /// Stops all scripts with the same name.
+    fn cancel(&self);
+}
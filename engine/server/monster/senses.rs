@@ -0,0 +1,166 @@
+use core::cell::{Ref, RefCell};
+
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use xash3d_shared::{entity::EdictFlags, ffi::common::vec3_t, math::ToAngleVectors};
+
+use crate::{
+    engine::TraceIgnore,
+    entity::{EntityVars, WaterLevel},
+    global_state::DefaultGlobal,
+    prelude::*,
+    time::MapTime,
+    utils::ViewField,
+};
+
+bitflags! {
+    /// Types of sounds and smells monsters can perceive.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct SoundTypes: u32 {
+        const NONE      = 0;
+        /// Gunshots, explosions.
+        const COMBAT    = 1 << 0;
+        /// Doors, breaking glass.
+        const WORLD     = 1 << 1;
+        /// All noises generated by players.
+        const PLAYER    = 1 << 2;
+        /// A dead body.
+        const CARCASS   = 1 << 3;
+        /// Gib or a piece of meat.
+        const MEAT      = 1 << 4;
+        /// Pending danger, a grenade that is about to explode.
+        const DANGER    = 1 << 5;
+        /// Trash cans, banana peels, old fast food bags.
+        const GARBAGE   = 1 << 6;
+
+        /// Sounds that can only be smelled.
+        const SCENTS    = Self::CARCASS.bits() | Self::MEAT.bits() | Self::GARBAGE.bits();
+        const ALL       = u32::MAX;
+    }
+}
+
+pub const QUIET_GUN_VOLUME: i32 = 200;
+pub const NORMAL_GUN_VOLUME: i32 = 600;
+pub const LOUD_GUN_VOLUME: i32 = 1000;
+pub const SMALL_EXPLOSION_VOLUME: i32 = 512;
+pub const NORMAL_EXPLOSION_VOLUME: i32 = 2048;
+
+/// A sound or a smell in the world.
+///
+/// A monster can hear a sound within the volume distance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AiSound {
+    pub kind: SoundTypes,
+    pub origin: vec3_t,
+    /// The distance at which the sound can be heard.
+    pub volume: i32,
+    pub expire_time: MapTime,
+}
+
+impl AiSound {
+    /// Returns `true` if it is a sound and not a smell.
+    pub fn is_sound(&self) -> bool {
+        !self.kind.intersects(SoundTypes::SCENTS)
+    }
+
+    /// Returns `true` if it is a smell.
+    pub fn is_scent(&self) -> bool {
+        self.kind.intersects(SoundTypes::SCENTS)
+    }
+}
+
+/// Sounds and smells monsters can perceive.
+///
+/// Entities insert sounds when they make noise and monsters listen for them every think.
+pub struct AiSounds {
+    engine: ServerEngineRef,
+    list: RefCell<Vec<AiSound>>,
+}
+
+impl DefaultGlobal for AiSounds {
+    fn default_global(engine: ServerEngineRef) -> Self {
+        Self {
+            engine,
+            list: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl AiSounds {
+    /// The maximum number of active sounds.
+    pub const MAX_SOUNDS: usize = 64;
+
+    /// Adds a new sound to the world for `duration` seconds.
+    ///
+    /// The sound that expires first is replaced if there are too many sounds.
+    pub fn insert(&self, kind: SoundTypes, origin: vec3_t, volume: i32, duration: f32) {
+        let now = self.engine.globals.map_time();
+        let sound = AiSound {
+            kind,
+            origin,
+            volume,
+            expire_time: now + duration,
+        };
+        let mut list = self.list.borrow_mut();
+        list.retain(|i| i.expire_time > now);
+        if list.len() < Self::MAX_SOUNDS {
+            list.push(sound);
+        } else if let Some(oldest) = list.iter_mut().min_by(|a, b| {
+            a.expire_time
+                .partial_cmp(&b.expire_time)
+                .unwrap_or(core::cmp::Ordering::Equal)
+        }) {
+            *oldest = sound;
+        }
+    }
+
+    /// Returns sounds that are not expired yet.
+    pub fn active(&self) -> Ref<'_, [AiSound]> {
+        let now = self.engine.globals.map_time();
+        self.list.borrow_mut().retain(|i| i.expire_time > now);
+        Ref::map(self.list.borrow(), |i| i.as_slice())
+    }
+
+    pub fn clear(&self) {
+        self.list.borrow_mut().clear();
+    }
+}
+
+/// Returns the position of the entity eyes.
+pub fn eye_position(v: &EntityVars) -> vec3_t {
+    v.origin() + v.view_ofs()
+}
+
+/// Returns `true` if `position` is in the view cone of `looker`.
+pub fn is_in_view_cone(looker: &EntityVars, position: vec3_t, view_field: ViewField) -> bool {
+    let forward = looker.angles().angle_vectors().forward().with_z(0.0);
+    let los = (position - looker.origin()).with_z(0.0).normalize();
+    los.dot(forward.normalize()) > view_field.to_dot()
+}
+
+/// Returns `true` if `looker` can see `target` eyes.
+///
+/// Monsters can not see through the water surface.
+pub fn is_visible(engine: &ServerEngine, looker: &EntityVars, target: &EntityVars) -> bool {
+    if target.flags().intersects(EdictFlags::NOTARGET) {
+        return false;
+    }
+
+    let looker_water = looker.water_level();
+    let target_water = target.water_level();
+    if (looker_water != WaterLevel::Head && target_water == WaterLevel::Head)
+        || (looker_water == WaterLevel::Head && target_water == WaterLevel::Dry)
+    {
+        return false;
+    }
+
+    is_visible_position(engine, looker, eye_position(target))
+}
+
+/// Returns `true` if nothing blocks the line of sight from `looker` eyes to `position`.
+pub fn is_visible_position(engine: &ServerEngine, looker: &EntityVars, position: vec3_t) -> bool {
+    let ignore = TraceIgnore::MONSTERS | TraceIgnore::GLASS;
+    let trace = engine.trace_line(eye_position(looker), position, ignore, Some(looker));
+    trace.fraction() == 1.0
+}
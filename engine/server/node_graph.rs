@@ -0,0 +1,992 @@
+//! Navigation graph for monsters.
+//!
+//! Nodes are placed by `info_node` and `info_node_air` entities. After all nodes are spawned
+//! [NodeGraph::build] links nodes that can see each other if a hull can move between them. The
+//! built graph is cached in `maps/graphs/<map>.nod` and is loaded instead of node entities while
+//! the cache is newer than the map file.
+//!
+//! The cache starts with a magic that is not a Half-Life graph version, so mods with different
+//! graph formats rebuild the cache instead of reading a foreign one.
+//!
+//! The engine does not export a function to write files, so the cache is written with `std::fs`
+//! only if the `node-graph-cache` feature is enabled.
+
+use core::{
+    cell::{Cell, Ref, RefCell},
+    cmp::Ordering,
+    fmt,
+};
+
+use alloc::{collections::binary_heap::BinaryHeap, vec::Vec};
+use bitflags::bitflags;
+use xash3d_shared::{consts::Contents, ffi::common::vec3_t};
+
+use crate::{
+    engine::TraceIgnore,
+    entity::{EdictFlags, EntityHandle, EntityVars, MoveType, Solid},
+    global_state::DefaultGlobal,
+    monster::{Capabilities, LOCAL_STEP_SIZE, check_local_move},
+    prelude::*,
+};
+
+/// The maximum number of nodes in a graph.
+pub const MAX_NODES: usize = 1024;
+
+/// The maximum number of links tested for a single node.
+const MAX_NODE_INITIAL_LINKS: usize = 128;
+
+/// Land nodes are placed this high above the floor.
+const NODE_HEIGHT: f32 = 8.0;
+
+/// The maximum distance from a position to the nearest node.
+const MAX_NEAREST_NODE_DIST: f32 = 1024.0;
+
+const GRAPH_MAGIC: [u8; 4] = *b"XNOD";
+const GRAPH_VERSION: u32 = 1;
+
+/// Hull sizes used to test links.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Hull {
+    /// Headcrab sized monsters.
+    Small,
+    /// Human sized monsters.
+    Human,
+    /// Big monsters like a gargantua.
+    Large,
+    /// Flying and swimming monsters.
+    Fly,
+}
+
+impl Hull {
+    pub const ALL: [Hull; 4] = [Hull::Small, Hull::Human, Hull::Large, Hull::Fly];
+
+    /// Returns the minimum and maximum extents of the hull.
+    pub const fn size(self) -> (vec3_t, vec3_t) {
+        match self {
+            Self::Small => (
+                vec3_t::new(-12.0, -12.0, 0.0),
+                vec3_t::new(12.0, 12.0, 24.0),
+            ),
+            Self::Human => (
+                vec3_t::new(-16.0, -16.0, 0.0),
+                vec3_t::new(16.0, 16.0, 72.0),
+            ),
+            Self::Large | Self::Fly => (
+                vec3_t::new(-32.0, -32.0, 0.0),
+                vec3_t::new(32.0, 32.0, 64.0),
+            ),
+        }
+    }
+
+    /// Returns the link flag for this hull.
+    pub const fn link_flag(self) -> LinkFlags {
+        match self {
+            Self::Small => LinkFlags::SMALL_HULL,
+            Self::Human => LinkFlags::HUMAN_HULL,
+            Self::Large => LinkFlags::LARGE_HULL,
+            Self::Fly => LinkFlags::FLY_HULL,
+        }
+    }
+
+    /// Returns node types this hull can move through.
+    pub const fn node_kind(self) -> NodeKind {
+        match self {
+            Self::Fly => NodeKind::AIR.union(NodeKind::WATER),
+            _ => NodeKind::LAND,
+        }
+    }
+
+    /// Returns the smallest hull the entity fits in.
+    pub fn from_entity(v: &EntityVars) -> Self {
+        let flags = v.flags();
+        if v.move_type() == MoveType::Fly || flags.intersects(EdictFlags::FLY | EdictFlags::SWIM) {
+            return Self::Fly;
+        }
+        let max = v.max_size();
+        if max.x <= 12.0 && max.z <= 24.0 {
+            Self::Small
+        } else if max.x <= 16.0 {
+            Self::Human
+        } else {
+            Self::Large
+        }
+    }
+}
+
+bitflags! {
+    /// A type of a node.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub struct NodeKind: u8 {
+        const LAND  = 1 << 0;
+        const AIR   = 1 << 1;
+        const WATER = 1 << 2;
+    }
+}
+
+bitflags! {
+    /// Hulls that can move through a link.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub struct LinkFlags: u8 {
+        const SMALL_HULL    = 1 << 0;
+        const HUMAN_HULL    = 1 << 1;
+        const LARGE_HULL    = 1 << 2;
+        const FLY_HULL      = 1 << 3;
+        /// The link is not used by pathfinding.
+        const DISABLED      = 1 << 4;
+    }
+}
+
+/// A position monsters can move through.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Node {
+    pub origin: vec3_t,
+    pub kind: NodeKind,
+    /// Something interesting at this position for monsters.
+    pub hint_type: u16,
+    pub hint_activity: u16,
+    /// A monster should face this yaw to see the hint.
+    pub hint_yaw: f32,
+    first_link: u32,
+    link_count: u32,
+}
+
+impl Node {
+    pub fn new(origin: vec3_t, kind: NodeKind) -> Self {
+        Self {
+            origin,
+            kind,
+            ..Self::default()
+        }
+    }
+}
+
+/// A one way connection between two nodes.
+#[derive(Copy, Clone, Default, PartialEq)]
+pub struct Link {
+    pub src: u16,
+    pub dest: u16,
+    pub flags: LinkFlags,
+    pub weight: f32,
+    /// A brush model name of the entity between nodes like a door.
+    model: [u8; 4],
+    entity: Option<EntityHandle>,
+}
+
+impl Link {
+    pub fn new(src: usize, dest: usize, flags: LinkFlags, weight: f32) -> Self {
+        Self {
+            src: src as u16,
+            dest: dest as u16,
+            flags,
+            weight,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if the hull can move through this link.
+    pub fn allows(&self, hull: Hull) -> bool {
+        self.flags.contains(hull.link_flag()) && !self.flags.intersects(LinkFlags::DISABLED)
+    }
+
+    /// Returns an entity between nodes.
+    pub fn entity(&self) -> Option<EntityHandle> {
+        self.entity
+    }
+
+    fn model_name(&self) -> Option<&str> {
+        let len = self.model.iter().position(|&i| i == 0).unwrap_or(4);
+        match &self.model[..len] {
+            [] => None,
+            model => core::str::from_utf8(model).ok(),
+        }
+    }
+
+    fn set_model_name(&mut self, name: &str) {
+        self.model = [0; 4];
+        let len = name.len().min(self.model.len());
+        self.model[..len].copy_from_slice(&name.as_bytes()[..len]);
+    }
+}
+
+impl fmt::Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Link")
+            .field("src", &self.src)
+            .field("dest", &self.dest)
+            .field("flags", &self.flags)
+            .field("weight", &self.weight)
+            .field("model", &self.model_name())
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphError {
+    Magic,
+    Version(u32),
+    UnexpectedEnd,
+    InvalidLink(usize),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Magic => write!(f, "not a node graph"),
+            Self::Version(version) => {
+                write!(f, "graph version is {version}, expected {GRAPH_VERSION}")
+            }
+            Self::UnexpectedEnd => write!(f, "unexpected end of file"),
+            Self::InvalidLink(index) => write!(f, "link {index} is invalid"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct OpenNode {
+    cost: f32,
+    node: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed for the min-heap
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Nodes and links without world checks.
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    links: Vec<Link>,
+}
+
+impl Graph {
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> Option<&Node> {
+        self.nodes.get(index)
+    }
+
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Returns links that start at the node.
+    pub fn node_links(&self, index: usize) -> &[Link] {
+        match self.nodes.get(index) {
+            Some(node) => {
+                let start = node.first_link as usize;
+                &self.links[start..start + node.link_count as usize]
+            }
+            None => &[],
+        }
+    }
+
+    /// Adds a new node to the graph.
+    ///
+    /// Returns `None` if the graph is full.
+    pub fn push_node(&mut self, node: Node) -> Option<usize> {
+        if self.nodes.len() >= MAX_NODES {
+            return None;
+        }
+        self.nodes.push(node);
+        Some(self.nodes.len() - 1)
+    }
+
+    /// Replaces all links in the graph.
+    pub fn set_links(&mut self, mut links: Vec<Link>) {
+        links.retain(|i| {
+            (i.src as usize) < self.nodes.len() && (i.dest as usize) < self.nodes.len()
+        });
+        links.sort_by_key(|i| i.src);
+        for node in &mut self.nodes {
+            node.first_link = 0;
+            node.link_count = 0;
+        }
+        for (i, link) in links.iter().enumerate() {
+            let node = &mut self.nodes[link.src as usize];
+            if node.link_count == 0 {
+                node.first_link = i as u32;
+            }
+            node.link_count += 1;
+        }
+        self.links = links;
+    }
+
+    /// Finds the shortest path between nodes with A*.
+    ///
+    /// Returns node indices from `start` to `goal` including both.
+    pub fn find_path(
+        &self,
+        start: usize,
+        goal: usize,
+        hull: Hull,
+        mut can_pass: impl FnMut(&Link) -> bool,
+    ) -> Option<Vec<usize>> {
+        let goal_origin = self.nodes.get(goal)?.origin;
+        self.nodes.get(start)?;
+
+        let len = self.nodes.len();
+        let mut cost = vec![f32::INFINITY; len];
+        let mut previous = vec![usize::MAX; len];
+        let mut open = BinaryHeap::new();
+        cost[start] = 0.0;
+        open.push(OpenNode {
+            cost: (goal_origin - self.nodes[start].origin).length(),
+            node: start,
+        });
+
+        while let Some(OpenNode { node, .. }) = open.pop() {
+            if node == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+                while current != start {
+                    current = previous[current];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for link in self.node_links(node) {
+                if !link.allows(hull) || !can_pass(link) {
+                    continue;
+                }
+                let dest = link.dest as usize;
+                let new_cost = cost[node] + link.weight;
+                if new_cost < cost[dest] {
+                    cost[dest] = new_cost;
+                    previous[dest] = node;
+                    let estimate = (goal_origin - self.nodes[dest].origin).length();
+                    open.push(OpenNode {
+                        cost: new_cost + estimate,
+                        node: dest,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Encodes the graph to the cache file format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&GRAPH_MAGIC);
+        out.extend_from_slice(&GRAPH_VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.links.len() as u32).to_le_bytes());
+        for node in &self.nodes {
+            for i in [node.origin.x, node.origin.y, node.origin.z] {
+                out.extend_from_slice(&i.to_le_bytes());
+            }
+            out.push(node.kind.bits());
+            out.extend_from_slice(&node.hint_type.to_le_bytes());
+            out.extend_from_slice(&node.hint_activity.to_le_bytes());
+            out.extend_from_slice(&node.hint_yaw.to_le_bytes());
+        }
+        for link in &self.links {
+            out.extend_from_slice(&link.src.to_le_bytes());
+            out.extend_from_slice(&link.dest.to_le_bytes());
+            out.push(link.flags.bits());
+            out.extend_from_slice(&link.weight.to_le_bytes());
+            out.extend_from_slice(&link.model);
+        }
+        out
+    }
+
+    /// Decodes the graph from the cache file format.
+    pub fn decode(data: &[u8]) -> Result<Self, GraphError> {
+        let mut reader = Reader(data);
+        if reader.bytes::<4>()? != GRAPH_MAGIC {
+            return Err(GraphError::Magic);
+        }
+        let version = reader.u32()?;
+        if version != GRAPH_VERSION {
+            return Err(GraphError::Version(version));
+        }
+        let node_count = reader.u32()? as usize;
+        let link_count = reader.u32()? as usize;
+        if node_count > MAX_NODES {
+            return Err(GraphError::UnexpectedEnd);
+        }
+
+        let mut graph = Self::default();
+        for _ in 0..node_count {
+            let origin = vec3_t::new(reader.f32()?, reader.f32()?, reader.f32()?);
+            let kind = NodeKind::from_bits_retain(reader.u8()?);
+            graph.nodes.push(Node {
+                hint_type: reader.u16()?,
+                hint_activity: reader.u16()?,
+                hint_yaw: reader.f32()?,
+                ..Node::new(origin, kind)
+            });
+        }
+
+        let mut links = Vec::with_capacity(link_count.min(MAX_NODES * MAX_NODE_INITIAL_LINKS));
+        for index in 0..link_count {
+            let mut link = Link::new(
+                reader.u16()? as usize,
+                reader.u16()? as usize,
+                LinkFlags::from_bits_retain(reader.u8()?),
+                0.0,
+            );
+            link.weight = reader.f32()?;
+            link.model = reader.bytes::<4>()?;
+            if link.src as usize >= node_count || link.dest as usize >= node_count {
+                return Err(GraphError::InvalidLink(index));
+            }
+            links.push(link);
+        }
+        graph.set_links(links);
+        Ok(graph)
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], GraphError> {
+        let (head, tail) = self
+            .0
+            .split_first_chunk::<N>()
+            .ok_or(GraphError::UnexpectedEnd)?;
+        self.0 = tail;
+        Ok(*head)
+    }
+
+    fn u8(&mut self) -> Result<u8, GraphError> {
+        self.bytes::<1>().map(|[i]| i)
+    }
+
+    fn u16(&mut self) -> Result<u16, GraphError> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, GraphError> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Result<f32, GraphError> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+}
+
+/// The world navigation graph.
+pub struct NodeGraph {
+    engine: ServerEngineRef,
+    graph: RefCell<Graph>,
+    /// The graph is loaded or built and does not accept new nodes.
+    ready: Cell<bool>,
+    /// Link entities are found by model names after the graph is loaded.
+    link_entities_set: Cell<bool>,
+}
+
+impl DefaultGlobal for NodeGraph {
+    fn default_global(engine: ServerEngineRef) -> Self {
+        let graph = Self {
+            engine,
+            graph: RefCell::default(),
+            ready: Cell::new(false),
+            link_entities_set: Cell::new(false),
+        };
+        graph.init();
+        graph
+    }
+}
+
+impl NodeGraph {
+    /// Clears the graph and loads the cache for the current map.
+    pub fn init(&self) {
+        *self.graph.borrow_mut() = Graph::default();
+        self.ready.set(false);
+        self.link_entities_set.set(false);
+
+        if let Some(graph) = self.load() {
+            debug!("Node graph loaded, {} nodes", graph.nodes.len());
+            *self.graph.borrow_mut() = graph;
+            self.ready.set(true);
+        }
+    }
+
+    /// Returns `true` if the graph is loaded or built.
+    pub fn is_ready(&self) -> bool {
+        self.ready.get()
+    }
+
+    pub fn graph(&self) -> Ref<'_, Graph> {
+        self.graph.borrow()
+    }
+
+    fn map_name(&self) -> Option<alloc::string::String> {
+        let map_name = self.engine.globals.map_name()?;
+        map_name.as_thin().to_str().ok().map(Into::into)
+    }
+
+    fn load(&self) -> Option<Graph> {
+        let engine = self.engine;
+        let map_name = self.map_name()?;
+        let bsp_path = format!("maps/{map_name}.bsp");
+        let nod_path = format!("maps/graphs/{map_name}.nod");
+        match engine.compare_file_time(bsp_path.as_str(), nod_path.as_str()) {
+            Some(Ordering::Less | Ordering::Equal) => {}
+            _ => return None,
+        }
+        let file = engine.load_file(nod_path.as_str()).ok()?;
+        match Graph::decode(file.as_bytes()) {
+            Ok(graph) => Some(graph),
+            Err(err) => {
+                warn!("{nod_path}: {err}, the graph will be rebuilt");
+                None
+            }
+        }
+    }
+
+    #[cfg(feature = "node-graph-cache")]
+    fn save(&self) {
+        let Some(map_name) = self.map_name() else {
+            return;
+        };
+        let game_dir = self.engine.get_game_dir();
+        let Ok(game_dir) = game_dir.to_str() else {
+            return;
+        };
+        let dir = std::path::Path::new(game_dir).join("maps/graphs");
+        let path = dir.join(format!("{map_name}.nod"));
+        let result = std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(&path, self.graph.borrow().encode()));
+        if let Err(err) = result {
+            warn!("Failed to save the node graph to {}: {err}", path.display());
+        }
+    }
+
+    #[cfg(not(feature = "node-graph-cache"))]
+    fn save(&self) {}
+
+    /// Adds a node placed by a node entity.
+    ///
+    /// Returns `None` if the graph does not accept new nodes.
+    pub fn add_node(
+        &self,
+        origin: vec3_t,
+        air: bool,
+        hint_type: u16,
+        hint_activity: u16,
+        hint_yaw: f32,
+    ) -> Option<usize> {
+        if self.ready.get() {
+            return None;
+        }
+
+        let engine = self.engine;
+        let mut node = Node::new(origin, NodeKind::LAND);
+        if air {
+            node.kind = NodeKind::AIR;
+        } else if engine.point_contents(origin) == Contents::Water {
+            node.kind = NodeKind::WATER;
+        } else {
+            // place land nodes near the floor
+            let end = origin - vec3_t::new(0.0, 0.0, 384.0);
+            let trace = engine.trace_line(origin, end, TraceIgnore::MONSTERS, None::<&EntityVars>);
+            node.origin.z = trace.end_position().z + NODE_HEIGHT;
+        }
+        node.hint_type = hint_type;
+        node.hint_activity = hint_activity;
+        node.hint_yaw = hint_yaw;
+
+        let index = self.graph.borrow_mut().push_node(node);
+        if index.is_none() {
+            warn!("Too many nodes, the maximum is {MAX_NODES}");
+        }
+        index
+    }
+
+    /// Links visible nodes and saves the graph to the cache.
+    ///
+    /// The `test` entity is moved and resized to check hulls.
+    pub fn build(&self, test: &EntityVars) {
+        if self.ready.get() {
+            return;
+        }
+
+        let engine = self.engine;
+        let nodes = self.graph.borrow().nodes.clone();
+        let saved_origin = test.origin();
+        test.set_move_type(MoveType::Step);
+        test.set_solid(Solid::Not);
+        test.with_flags(|f| f | EdictFlags::MONSTER | EdictFlags::ONGROUND);
+
+        let mut links = Vec::new();
+        for (src_index, src) in nodes.iter().enumerate() {
+            let mut count = 0;
+            for (dest_index, dest) in nodes.iter().enumerate() {
+                if src_index == dest_index || src.kind != dest.kind {
+                    continue;
+                }
+                if count >= MAX_NODE_INITIAL_LINKS {
+                    warn!("Node {src_index} has too many links");
+                    break;
+                }
+
+                let Some(entity) = self.visible_link_entity(src.origin, dest.origin) else {
+                    continue;
+                };
+
+                let mut flags = LinkFlags::empty();
+                for hull in Hull::ALL {
+                    if hull.node_kind().intersects(src.kind)
+                        && self.test_hull(test, hull, src.origin, dest.origin, entity)
+                    {
+                        flags |= hull.link_flag();
+                    }
+                }
+                if flags.is_empty() {
+                    continue;
+                }
+
+                let weight = (dest.origin - src.origin).length();
+                let mut link = Link::new(src_index, dest_index, flags, weight);
+                if let Some(entity) = entity {
+                    let ev = entity.vars();
+                    ev.with_flags(|f| f | EdictFlags::GRAPHED);
+                    if let Some(model) = ev.model_name() {
+                        link.set_model_name(model.as_thin().to_str().unwrap_or(""));
+                    }
+                    link.entity = Some(entity);
+                }
+                links.push(link);
+                count += 1;
+            }
+        }
+
+        test.set_origin_and_link(saved_origin);
+
+        let mut graph = self.graph.borrow_mut();
+        graph.set_links(links);
+        info!(
+            "Node graph built, {} nodes, {} links",
+            graph.nodes.len(),
+            graph.links.len()
+        );
+        drop(graph);
+
+        self.ready.set(true);
+        self.link_entities_set.set(true);
+        self.save();
+    }
+
+    /// Returns `Some` if nodes can see each other with an optional brush entity between them.
+    fn visible_link_entity(&self, src: vec3_t, dest: vec3_t) -> Option<Option<EntityHandle>> {
+        let engine = self.engine;
+        let trace = engine.trace_line(src, dest, TraceIgnore::MONSTERS, None::<&EntityVars>);
+        if trace.fraction() == 1.0 {
+            return Some(None);
+        }
+
+        let hit = trace.hit_entity()?;
+        if hit.is_world_spawn() {
+            return None;
+        }
+        let entity = EntityHandle::from(hit);
+        // only brush entities like doors can be between nodes
+        let model = entity.vars().model_name()?;
+        if !model.as_thin().to_bytes().starts_with(b"*") {
+            return None;
+        }
+
+        // nothing else is between nodes
+        let trace = engine.trace_line(src, dest, TraceIgnore::MONSTERS, Some(&entity));
+        (trace.fraction() == 1.0).then_some(Some(entity))
+    }
+
+    fn test_hull(
+        &self,
+        test: &EntityVars,
+        hull: Hull,
+        src: vec3_t,
+        dest: vec3_t,
+        entity: Option<EntityHandle>,
+    ) -> bool {
+        let engine = self.engine;
+        let (mins, maxs) = hull.size();
+        engine.set_size(test, mins, maxs);
+
+        // a door between nodes is expected to be open
+        let door = entity.map(|i| (i.vars(), i.vars().solid()));
+        if let Some((v, _)) = &door {
+            v.set_solid(Solid::Not);
+        }
+
+        let result = if hull == Hull::Fly {
+            test.set_origin_and_link(src);
+            engine
+                .trace_monster_hull(src, dest, test, TraceIgnore::MONSTERS, None::<&EntityVars>)
+                .is_none()
+        } else {
+            let dist = (dest - src).with_z(0.0).length();
+            let moved = check_local_move(&engine, test, src, dest);
+            moved + LOCAL_STEP_SIZE >= dist
+        };
+
+        if let Some((v, solid)) = door {
+            v.set_solid(solid);
+            v.set_origin_and_link(v.origin());
+        }
+
+        result
+    }
+
+    /// Finds link entities by model names after the graph was loaded from the cache.
+    fn set_link_entities(&self) {
+        if self.link_entities_set.replace(true) {
+            return;
+        }
+        let engine = self.engine;
+        let mut graph = self.graph.borrow_mut();
+        for link in &mut graph.links {
+            let Some(model) = link.model_name() else {
+                continue;
+            };
+            link.entity = engine
+                .entities()
+                .by_string(c"model", model)
+                .next()
+                .map(EntityHandle::from);
+            match link.entity {
+                Some(entity) => entity.vars().with_flags(|f| f | EdictFlags::GRAPHED),
+                None => warn!("Node graph link entity {model} not found"),
+            }
+        }
+    }
+
+    /// Called when a link entity is removed from the world.
+    pub fn remove_entity(&self, entity: EntityHandle) {
+        let mut graph = self.graph.borrow_mut();
+        for link in graph.links.iter_mut().filter(|i| i.entity == Some(entity)) {
+            link.entity = None;
+        }
+    }
+
+    /// Returns `true` if an entity with the capabilities can move through the link.
+    fn can_pass(&self, link: &Link, capabilities: Capabilities) -> bool {
+        let Some(entity) = link.entity else {
+            return true;
+        };
+        let v = entity.vars();
+        let Some(classname) = v.classname() else {
+            return false;
+        };
+        match classname.as_thin().to_bytes() {
+            b"func_door" | b"func_door_rotating" => {
+                const SF_DOOR_USE_ONLY: u32 = 1 << 8;
+                if v.spawn_flags() & SF_DOOR_USE_ONLY != 0 {
+                    capabilities.intersects(Capabilities::OPEN_DOORS)
+                } else {
+                    // doors opened by triggers can not be opened by monsters
+                    capabilities.intersects(Capabilities::AUTO_DOORS) && v.target_name().is_none()
+                }
+            }
+            _ => {
+                let name = v.pretty_name();
+                trace!("{name}: unhandled entity in the node graph path");
+                false
+            }
+        }
+    }
+
+    /// Returns the nearest node visible from the position.
+    pub fn nearest_node(&self, position: vec3_t, hull: Hull) -> Option<usize> {
+        let engine = self.engine;
+        let graph = self.graph.borrow();
+        let kind = hull.node_kind();
+        let mut nodes = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.kind.intersects(kind))
+            .map(|(i, node)| (i, (node.origin - position).length()))
+            .filter(|&(_, dist)| dist <= MAX_NEAREST_NODE_DIST)
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        nodes.into_iter().map(|(i, _)| i).find(|&i| {
+            let trace = engine.trace_line(
+                position,
+                graph.nodes[i].origin,
+                TraceIgnore::MONSTERS,
+                None::<&EntityVars>,
+            );
+            trace.fraction() == 1.0
+        })
+    }
+
+    /// Finds a path of node positions from `start` to `goal`.
+    pub fn find_route(
+        &self,
+        start: vec3_t,
+        goal: vec3_t,
+        hull: Hull,
+        capabilities: Capabilities,
+    ) -> Option<Vec<vec3_t>> {
+        if !self.ready.get() {
+            return None;
+        }
+        self.set_link_entities();
+        let start = self.nearest_node(start, hull)?;
+        let goal = self.nearest_node(goal, hull)?;
+        let graph = self.graph.borrow();
+        let path = graph.find_path(start, goal, hull, |link| self.can_pass(link, capabilities))?;
+        Some(path.into_iter().map(|i| graph.nodes[i].origin).collect())
+    }
+
+    /// Finds a node that is hidden from the threat and has a route from the origin.
+    ///
+    /// `view_ofs` is the eye offset of the monster looking for cover.
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_cover(
+        &self,
+        origin: vec3_t,
+        view_ofs: vec3_t,
+        threat_eye: vec3_t,
+        min_dist: f32,
+        max_dist: f32,
+        hull: Hull,
+        capabilities: Capabilities,
+    ) -> Option<vec3_t> {
+        if !self.ready.get() {
+            return None;
+        }
+        self.set_link_entities();
+
+        let engine = self.engine;
+        let start = self.nearest_node(origin, hull)?;
+        let kind = hull.node_kind();
+        let graph = self.graph.borrow();
+        let mut nodes = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.kind.intersects(kind))
+            .map(|(i, node)| (i, (node.origin - origin).length()))
+            .filter(|&(_, dist)| dist >= min_dist && dist <= max_dist)
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        nodes.into_iter().find_map(|(i, _)| {
+            let position = graph.nodes[i].origin;
+            let trace = engine.trace_line(
+                threat_eye,
+                position + view_ofs,
+                TraceIgnore::MONSTERS,
+                None::<&EntityVars>,
+            );
+            if trace.fraction() == 1.0 {
+                return None;
+            }
+            graph
+                .find_path(start, i, hull, |link| self.can_pass(link, capabilities))
+                .map(|_| position)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Graph {
+        // 0 - 1 - 2
+        // |       |
+        // 3 ----- 4
+        let mut graph = Graph::default();
+        for (x, y) in [
+            (0.0, 0.0),
+            (100.0, 0.0),
+            (200.0, 0.0),
+            (0.0, 100.0),
+            (200.0, 100.0),
+        ] {
+            graph.push_node(Node::new(vec3_t::new(x, y, 0.0), NodeKind::LAND));
+        }
+        let all = LinkFlags::SMALL_HULL | LinkFlags::HUMAN_HULL;
+        let mut links = Vec::new();
+        for (a, b, flags) in [
+            (0, 1, all),
+            (1, 2, LinkFlags::SMALL_HULL),
+            (0, 3, all),
+            (3, 4, all),
+            (4, 2, all),
+        ] {
+            let weight = (graph.nodes[a].origin - graph.nodes[b].origin).length();
+            links.push(Link::new(a, b, flags, weight));
+            links.push(Link::new(b, a, flags, weight));
+        }
+        graph.set_links(links);
+        graph
+    }
+
+    #[test]
+    fn find_path() {
+        let graph = grid();
+        assert_eq!(
+            graph.find_path(0, 2, Hull::Small, |_| true),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(
+            graph.find_path(0, 2, Hull::Human, |_| true),
+            Some(vec![0, 3, 4, 2])
+        );
+        assert_eq!(graph.find_path(0, 2, Hull::Large, |_| true), None);
+        assert_eq!(graph.find_path(2, 2, Hull::Small, |_| true), Some(vec![2]));
+        assert_eq!(
+            graph.find_path(0, 2, Hull::Small, |link| link.dest != 1),
+            Some(vec![0, 3, 4, 2])
+        );
+    }
+
+    #[test]
+    fn node_links() {
+        let graph = grid();
+        let dests = graph
+            .node_links(0)
+            .iter()
+            .map(|i| i.dest)
+            .collect::<Vec<_>>();
+        assert_eq!(dests, [1, 3]);
+        assert!(graph.node_links(5).is_empty());
+    }
+
+    #[test]
+    fn encode_decode() {
+        let mut graph = grid();
+        graph.nodes[1].hint_type = 3;
+        graph.nodes[1].hint_yaw = 90.0;
+        graph.links[0].set_model_name("*12");
+        let data = graph.encode();
+        let decoded = Graph::decode(&data).unwrap();
+        assert_eq!(decoded.nodes, graph.nodes);
+        assert_eq!(decoded.links, graph.links);
+        assert_eq!(decoded.links[0].model_name(), Some("*12"));
+
+        assert_eq!(
+            Graph::decode(&data[..data.len() - 1]).unwrap_err(),
+            GraphError::UnexpectedEnd
+        );
+        assert_eq!(Graph::decode(b"\x10\0\0\0").unwrap_err(), GraphError::Magic);
+    }
+}
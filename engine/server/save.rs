@@ -2,6 +2,9 @@ mod cursor;
 mod macros;
 mod save_restore_data;
 
+#[cfg(feature = "std")]
+pub mod inspect;
+
 #[cfg(feature = "save")]
 mod derive;
 
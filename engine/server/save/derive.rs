@@ -41,6 +41,14 @@ pub trait OnRestore {
     fn on_restore(&self);
 }
 
+/// Called after a struct with `#[save(version = N)]` is restored from an older save.
+pub trait Migrate {
+    /// Updates restored fields from the `version` in the save.
+    ///
+    /// The version is zero for saves written before the struct had a version.
+    fn migrate(&mut self, version: u32);
+}
+
 macro_rules! impl_save_restore_for_num {
     ($( $ty:ty = $write:ident, $read:ident; )*) => {
         $(
@@ -242,6 +250,28 @@ impl<T: RestoreWithDefault> Restore for Vec<T> {
     }
 }
 
+impl<T: Save, const N: usize> Save for [T; N] {
+    fn save(&self, state: &mut SaveState, cur: &mut CursorMut) -> SaveResult<()> {
+        cur.write_leb_usize(N)?;
+        for i in self {
+            i.save(state, cur)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Restore, const N: usize> Restore for [T; N] {
+    fn restore(&mut self, state: &RestoreState, cur: &mut Cursor) -> SaveResult<()> {
+        if cur.read_leb_usize()? != N {
+            return Err(SaveError::InvalidNumber);
+        }
+        for i in self {
+            i.restore(state, cur)?;
+        }
+        Ok(())
+    }
+}
+
 impl Save for Attenuation {
     fn save(&self, _: &mut SaveState, cur: &mut CursorMut) -> SaveResult<()> {
         cur.write_f32((*self).into())?;
@@ -294,6 +324,10 @@ pub use impl_save_restore_for_bitflags;
 
 impl_save_restore_for_bitflags!(xash3d_shared::entity::DamageFlags);
 impl_save_restore_for_bitflags!(xash3d_shared::entity::Buttons);
+impl_save_restore_for_bitflags!(crate::monster::Capabilities);
+impl_save_restore_for_bitflags!(crate::monster::Conditions);
+impl_save_restore_for_bitflags!(crate::monster::Memory);
+impl_save_restore_for_bitflags!(crate::monster::SoundTypes);
 
 impl Save for EntityIndex {
     fn save(&self, state: &mut SaveState, cur: &mut CursorMut) -> SaveResult<()> {
@@ -0,0 +1,1020 @@
+//! Offline inspector for save files.
+//!
+//! Decodes `.sav` and `.hl1` files without a running engine. Unknown blocks, unknown fields
+//! and fields with unexpected sizes are reported as [issues](Report::issues) with the byte
+//! offset in the file.
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    ffi::CStr,
+    fmt::{self, Write},
+};
+
+use xash3d_shared::ffi::server::{TYPEDESCRIPTION, entvars_s};
+
+use crate::{
+    global_state::GlobalEntity,
+    save::{Cursor, FieldType, SaveError, SaveFields, SaveResult, TypeDescriptionExt},
+};
+
+/// The tag of a `.sav` file.
+pub const SAVE_GAME_TAG: u32 = u32::from_le_bytes(*b"JSAV");
+
+/// The tag of a level state file (`.hl1`, `.hl2` and `.hl3`).
+pub const SAVE_FILE_TAG: u32 = u32::from_le_bytes(*b"VALV");
+
+/// The save format version written by the engine.
+pub const SAVE_VERSION: u32 = 0x0071;
+
+/// The size of a file name of a level state file embedded in a `.sav` file.
+const EMBEDDED_NAME_SIZE: usize = 260;
+
+/// The name of the field written by the game after entity variables.
+const ENTITY_FIELD_NAME: &str = "ENTITY";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// A `.sav` file.
+    Game,
+    /// A level state file.
+    Level,
+    /// A raw save data buffer without a file header.
+    Data,
+}
+
+impl FileKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Game => "game",
+            Self::Level => "level",
+            Self::Data => "data",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// Byte offset in the file.
+    pub offset: usize,
+    pub message: String,
+}
+
+/// An entry of the entity table (`ETABLE` blocks).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntityEntry {
+    pub id: i32,
+    pub location: i32,
+    pub size: i32,
+    pub flags: i32,
+    pub classname: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(Vec<i32>),
+    Float(Vec<f32>),
+    Vector(Vec<[f32; 3]>),
+    Str(Vec<String>),
+    Bytes(Vec<u8>),
+    /// Nested fields written by derived [Save](crate::save::Save) implementations.
+    Fields(Vec<FieldInfo>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldInfo {
+    /// Byte offset of the field header in the file.
+    pub offset: usize,
+    pub name: String,
+    /// Size of the field data in bytes.
+    pub size: usize,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    /// Byte offset of the block header in the file.
+    pub offset: usize,
+    pub name: String,
+    pub fields: Vec<FieldInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    /// Fields written with `SaveWriteFields`.
+    Block(Block),
+    /// A single field outside of a block.
+    Field(FieldInfo),
+    /// Items stored at the location of an entity table entry.
+    Entity { index: usize, items: Vec<Item> },
+}
+
+impl Item {
+    fn offset(&self) -> usize {
+        match self {
+            Self::Block(block) => block.offset,
+            Self::Field(field) => field.offset,
+            Self::Entity { items, .. } => items.first().map_or(0, |i| i.offset()),
+        }
+    }
+}
+
+/// A level state file embedded in a `.sav` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmbeddedFile {
+    pub offset: usize,
+    pub name: String,
+    pub report: Report,
+}
+
+/// The decoded contents of a save file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub kind: FileKind,
+    pub version: Option<u32>,
+    pub tokens: Vec<String>,
+    pub entities: Vec<EntityEntry>,
+    pub items: Vec<Item>,
+    pub files: Vec<EmbeddedFile>,
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    fn new(kind: FileKind, tokens: Vec<String>) -> Self {
+        Self {
+            kind,
+            version: None,
+            tokens,
+            entities: Vec::new(),
+            items: Vec::new(),
+            files: Vec::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    fn issue(&mut self, offset: usize, message: impl Into<String>) {
+        self.issues.push(Issue {
+            offset,
+            message: message.into(),
+        });
+    }
+
+    /// Returns `true` if this report or any embedded file has issues.
+    pub fn has_issues(&self) -> bool {
+        !self.issues.is_empty() || self.files.iter().any(|i| i.report.has_issues())
+    }
+
+    /// Returns the report as a JSON document.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        json::report(&mut out, self);
+        out
+    }
+}
+
+/// Decodes a `.sav` or level state file.
+///
+/// Files without a known tag are decoded as raw save data without a token table.
+pub fn inspect(bytes: &[u8]) -> Report {
+    inspect_at(bytes, 0)
+}
+
+/// Decodes a raw save data buffer with the given token table.
+pub fn inspect_data(tokens: Vec<String>, data: &[u8]) -> Report {
+    let mut report = Report::new(FileKind::Data, tokens);
+    walk_data(&mut report, data, 0);
+    report
+}
+
+fn inspect_at(bytes: &[u8], base: usize) -> Report {
+    let mut cur = Cursor::new(bytes);
+    let kind = match cur.read_u32_le() {
+        Ok(SAVE_GAME_TAG) => FileKind::Game,
+        Ok(SAVE_FILE_TAG) => FileKind::Level,
+        _ => return inspect_data(Vec::new(), bytes),
+    };
+    let mut report = Report::new(kind, Vec::new());
+    if let Err(err) = read_file(&mut report, &mut cur, base) {
+        report.issue(base + cur.offset(), format!("truncated file header, {err}"));
+    }
+    report
+}
+
+fn read_file(report: &mut Report, cur: &mut Cursor, base: usize) -> SaveResult<()> {
+    let version = cur.read_u32_le()?;
+    report.version = Some(version);
+    if version != SAVE_VERSION {
+        report.issue(base + 4, format!("unexpected version {version:#x}"));
+    }
+
+    let data_size = cur.read_u32_le()? as usize;
+    let table_count = match report.kind {
+        FileKind::Level => Some(cur.read_u32_le()?),
+        _ => None,
+    };
+    let token_count = cur.read_u32_le()? as usize;
+    let token_size = cur.read_u32_le()? as usize;
+
+    let token_offset = base + cur.offset();
+    let token_data = cur.read(token_size)?;
+    let mut tokens = token_data.split(|&i| i == 0);
+    for _ in 0..token_count {
+        let Some(token) = tokens.next() else {
+            report.issue(token_offset, "token table is shorter than token count");
+            break;
+        };
+        report
+            .tokens
+            .push(String::from_utf8_lossy(token).into_owned());
+    }
+
+    let data_offset = base + cur.offset();
+    let data = cur.read(data_size)?;
+    walk_data(report, data, data_offset);
+
+    if let Some(count) = table_count {
+        if report.entities.len() != count as usize {
+            let found = report.entities.len();
+            report.issue(
+                data_offset,
+                format!("entity table has {found} of {count} entries"),
+            );
+        }
+    }
+
+    if report.kind == FileKind::Game {
+        while !cur.is_empty() {
+            let offset = base + cur.offset();
+            let name = cur.read(EMBEDDED_NAME_SIZE)?;
+            let name = name.split(|&i| i == 0).next().unwrap_or_default();
+            let name = String::from_utf8_lossy(name).into_owned();
+            let size = cur.read_u32_le()? as usize;
+            let file_base = base + cur.offset();
+            let file = inspect_at(cur.read(size)?, file_base);
+            report.files.push(EmbeddedFile {
+                offset,
+                name,
+                report: file,
+            });
+        }
+    } else if !cur.is_empty() {
+        let offset = base + cur.offset();
+        let len = cur.remaining();
+        report.issue(offset, format!("{len} trailing bytes after save data"));
+    }
+
+    Ok(())
+}
+
+fn walk_data(report: &mut Report, data: &[u8], base: usize) {
+    let mut items = Vec::new();
+    let mut cur = Cursor::new(data);
+    while !cur.is_empty() {
+        let offset = cur.offset();
+        match read_item(report, &mut cur, base) {
+            Ok(item) => items.push((item, base + cur.offset())),
+            Err(err) => {
+                report.issue(base + offset, format!("failed to read a block, {err}"));
+                break;
+            }
+        }
+    }
+
+    for (item, _) in &items {
+        if let Item::Block(block) = item {
+            if block.name == "ETABLE" {
+                report.entities.push(entity_entry(block));
+            }
+        }
+    }
+
+    report.items = group_entities(report, items, base);
+}
+
+fn read_item(report: &mut Report, cur: &mut Cursor, base: usize) -> SaveResult<Item> {
+    let offset = cur.offset();
+    let header = cur.read_header()?;
+    let name = token_name(report, header.token().to_usize(), base + offset);
+
+    if header.size() == 4 && name != ENTITY_FIELD_NAME {
+        let start = cur.offset();
+        if let Ok(block) = read_block(report, cur, base, offset, &name) {
+            return Ok(Item::Block(block));
+        }
+        cur.set_offset(start)?;
+    }
+
+    let data = cur.read(header.size() as usize)?;
+    let data_offset = base + offset + 4;
+    let value = if name == ENTITY_FIELD_NAME {
+        match read_nested(report, data, data_offset) {
+            Some(fields) => Value::Fields(fields),
+            None => Value::Bytes(data.to_vec()),
+        }
+    } else {
+        report.issue(
+            base + offset,
+            format!("unexpected field {name:?} outside of a block"),
+        );
+        Value::Bytes(data.to_vec())
+    };
+    Ok(Item::Field(FieldInfo {
+        offset: base + offset,
+        name,
+        size: data.len(),
+        value,
+    }))
+}
+
+fn read_block(
+    report: &mut Report,
+    cur: &mut Cursor,
+    base: usize,
+    offset: usize,
+    name: &str,
+) -> SaveResult<Block> {
+    let count = cur.read_u32_le()? as usize;
+    if count > cur.remaining() / 4 {
+        return Err(SaveError::Overflow);
+    }
+    let mut raw = Vec::with_capacity(count);
+    for _ in 0..count {
+        let field_offset = cur.offset();
+        raw.push((field_offset, cur.read_field()?));
+    }
+
+    let schema = Schema::for_block(name);
+    if schema.is_none() {
+        report.issue(base + offset, format!("unknown block {name:?}"));
+    }
+
+    let mut fields = Vec::with_capacity(count);
+    for (field_offset, field) in raw {
+        let field_offset = base + field_offset;
+        let field_name = token_name(report, field.token().to_usize(), field_offset);
+        let value = match schema.as_ref().map(|i| i.find(&field_name)) {
+            Some(Some((ty, count))) => decode(report, field.data(), ty, count, field_offset),
+            Some(None) => {
+                let msg = format!("unknown field {field_name:?} in {name:?}");
+                report.issue(field_offset, msg);
+                Value::Bytes(field.data().to_vec())
+            }
+            None => Value::Bytes(field.data().to_vec()),
+        };
+        fields.push(FieldInfo {
+            offset: field_offset,
+            name: field_name,
+            size: field.size(),
+            value,
+        });
+    }
+
+    Ok(Block {
+        offset: base + offset,
+        name: name.to_owned(),
+        fields,
+    })
+}
+
+/// Tries to decode the data as a list of fields.
+fn read_nested(report: &mut Report, data: &[u8], base: usize) -> Option<Vec<FieldInfo>> {
+    let mut cur = Cursor::new(data);
+    let mut raw = Vec::new();
+    while !cur.is_empty() {
+        let offset = cur.offset();
+        let field = cur.read_field().ok()?;
+        let name = report.tokens.get(field.token().to_usize())?;
+        if name.is_empty() {
+            return None;
+        }
+        raw.push((offset, name.clone(), field));
+    }
+
+    let mut fields = Vec::with_capacity(raw.len());
+    for (offset, name, field) in raw {
+        let offset = base + offset;
+        let value = match read_nested(report, field.data(), offset + 4) {
+            Some(fields) if !fields.is_empty() => Value::Fields(fields),
+            _ => Value::Bytes(field.data().to_vec()),
+        };
+        fields.push(FieldInfo {
+            offset,
+            name,
+            size: field.size(),
+            value,
+        });
+    }
+    Some(fields)
+}
+
+fn token_name(report: &mut Report, token: usize, offset: usize) -> String {
+    match report.tokens.get(token) {
+        Some(name) if !name.is_empty() => name.clone(),
+        _ => {
+            if !report.tokens.is_empty() {
+                report.issue(offset, format!("token({token}) not found"));
+            }
+            format!("#{token}")
+        }
+    }
+}
+
+fn decode(report: &mut Report, data: &[u8], ty: FieldType, count: usize, offset: usize) -> Value {
+    let mut cur = Cursor::new(data);
+    let result = match ty {
+        FieldType::CHARACTER => {
+            if data.len() > count {
+                let len = data.len();
+                report.issue(
+                    offset,
+                    format!("{len} bytes do not fit in {count} characters"),
+                );
+            }
+            let s = data.split(|&i| i == 0).next().unwrap_or_default();
+            return Value::Str(vec![String::from_utf8_lossy(s).into_owned()]);
+        }
+        FieldType::STRING | FieldType::MODELNAME | FieldType::SOUNDNAME => {
+            let list: Vec<_> = data
+                .split_inclusive(|&i| i == 0)
+                .map(|s| String::from_utf8_lossy(s.strip_suffix(&[0]).unwrap_or(s)).into_owned())
+                .collect();
+            check_count(report, list.len(), count, offset);
+            return Value::Str(list);
+        }
+        FieldType::FLOAT | FieldType::TIME => {
+            read_all(&mut cur, |cur| cur.read_f32()).map(Value::Float)
+        }
+        FieldType::VECTOR | FieldType::POSITION_VECTOR => read_all(&mut cur, |cur| {
+            Ok([cur.read_f32()?, cur.read_f32()?, cur.read_f32()?])
+        })
+        .map(Value::Vector),
+        FieldType::INTEGER
+        | FieldType::SHORT
+        | FieldType::BOOLEAN
+        | FieldType::ENTITY
+        | FieldType::CLASSPTR
+        | FieldType::EHANDLE
+        | FieldType::EVARS
+        | FieldType::EDICT => read_all(&mut cur, |cur| cur.read_leb_i32()).map(Value::Int),
+        FieldType::POINTER | FieldType::FUNCTION => return Value::Bytes(data.to_vec()),
+    };
+
+    match result {
+        Ok(value) => {
+            let len = match &value {
+                Value::Int(list) => list.len(),
+                Value::Float(list) => list.len(),
+                Value::Vector(list) => list.len(),
+                _ => unreachable!(),
+            };
+            check_count(report, len, count, offset);
+            value
+        }
+        Err(err) => {
+            report.issue(offset, format!("failed to decode {ty:?}, {err}"));
+            Value::Bytes(data.to_vec())
+        }
+    }
+}
+
+fn read_all<'a, T>(
+    cur: &mut Cursor<'a>,
+    mut f: impl FnMut(&mut Cursor<'a>) -> SaveResult<T>,
+) -> SaveResult<Vec<T>> {
+    let mut list = Vec::new();
+    while !cur.is_empty() {
+        list.push(f(cur)?);
+    }
+    Ok(list)
+}
+
+fn check_count(report: &mut Report, len: usize, count: usize, offset: usize) {
+    if len > count {
+        report.issue(
+            offset,
+            format!("{len} values do not fit in {count} elements"),
+        );
+    }
+}
+
+fn entity_entry(block: &Block) -> EntityEntry {
+    let mut entry = EntityEntry::default();
+    for field in &block.fields {
+        match (field.name.as_str(), &field.value) {
+            ("id", Value::Int(v)) => entry.id = v.first().copied().unwrap_or_default(),
+            ("location", Value::Int(v)) => entry.location = v.first().copied().unwrap_or_default(),
+            ("size", Value::Int(v)) => entry.size = v.first().copied().unwrap_or_default(),
+            ("flags", Value::Int(v)) => entry.flags = v.first().copied().unwrap_or_default(),
+            ("classname", Value::Str(v)) => entry.classname = v.concat(),
+            _ => {}
+        }
+    }
+    entry
+}
+
+/// Moves items stored at entity table locations into [Item::Entity].
+fn group_entities(report: &mut Report, items: Vec<(Item, usize)>, base: usize) -> Vec<Item> {
+    let mut ranges: Vec<_> = report
+        .entities
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.location >= 0 && e.size > 0)
+        .map(|(i, e)| (i, base + e.location as usize, e.size as usize))
+        .collect();
+    ranges.sort_by_key(|&(_, location, _)| location);
+
+    let mut out = Vec::with_capacity(items.len());
+    let mut items = items.into_iter().peekable();
+    for (index, start, size) in ranges {
+        let end = start + size;
+        while items.peek().is_some_and(|(i, _)| i.offset() < start) {
+            out.push(items.next().unwrap().0);
+        }
+        let mut children = Vec::new();
+        let mut actual = start;
+        while items.peek().is_some_and(|(i, _)| i.offset() < end) {
+            let (item, item_end) = items.next().unwrap();
+            actual = item_end;
+            children.push(item);
+        }
+
+        let classname = report.entities[index].classname.clone();
+        if children.first().is_none_or(|i| i.offset() != start) {
+            let msg = format!("entity {index} ({classname}) does not start at a block");
+            report.issue(start, msg);
+        }
+        if children.is_empty() {
+            continue;
+        }
+        if actual != end {
+            let found = actual - start;
+            let msg = format!("entity {index} ({classname}) has {found} bytes, table says {size}");
+            report.issue(start, msg);
+        }
+        out.push(Item::Entity {
+            index,
+            items: children,
+        });
+    }
+    out.extend(items.map(|(item, _)| item));
+    out
+}
+
+struct Schema {
+    fields: Vec<(&'static [u8], FieldType, usize)>,
+}
+
+impl Schema {
+    fn from_fields(fields: &'static [TYPEDESCRIPTION]) -> Self {
+        let fields = fields
+            .iter()
+            .map(|i| {
+                let name = unsafe { CStr::from_ptr(i.fieldName) };
+                (name.to_bytes(), i.field_type(), i.fieldSize as usize)
+            })
+            .collect();
+        Self { fields }
+    }
+
+    fn from_list(fields: &[(&'static str, FieldType, usize)]) -> Self {
+        let fields = fields
+            .iter()
+            .map(|&(name, ty, count)| (name.as_bytes(), ty, count))
+            .collect();
+        Self { fields }
+    }
+
+    /// Returns the schema for blocks written by the engine and this crate.
+    fn for_block(name: &str) -> Option<Self> {
+        use FieldType as F;
+
+        let schema = match name {
+            "ENTVARS" => Self::from_fields(entvars_s::SAVE_FIELDS),
+            "GENT" => Self::from_fields(GlobalEntity::SAVE_FIELDS),
+            "GLOBAL" => Self::from_list(&[("list_count", F::INTEGER, 1)]),
+            "GameHeader" => Self::from_list(&[
+                ("mapName", F::CHARACTER, 32),
+                ("comment", F::CHARACTER, 80),
+                ("mapCount", F::INTEGER, 1),
+            ]),
+            "Save Header" => Self::from_list(&[
+                ("skillLevel", F::INTEGER, 1),
+                ("entityCount", F::INTEGER, 1),
+                ("connectionCount", F::INTEGER, 1),
+                ("lightStyleCount", F::INTEGER, 1),
+                ("time", F::TIME, 1),
+                ("mapName", F::CHARACTER, 32),
+                ("skyName", F::CHARACTER, 32),
+                ("skyColor_r", F::INTEGER, 1),
+                ("skyColor_g", F::INTEGER, 1),
+                ("skyColor_b", F::INTEGER, 1),
+                ("skyVec_x", F::FLOAT, 1),
+                ("skyVec_y", F::FLOAT, 1),
+                ("skyVec_z", F::FLOAT, 1),
+            ]),
+            "ADJACENCY" => Self::from_list(&[
+                ("mapName", F::CHARACTER, 32),
+                ("landmarkName", F::CHARACTER, 32),
+                ("pentLandmark", F::EDICT, 1),
+                ("vecLandmarkOrigin", F::VECTOR, 1),
+            ]),
+            "LIGHTSTYLE" => {
+                Self::from_list(&[("index", F::INTEGER, 1), ("style", F::CHARACTER, 64)])
+            }
+            "ETABLE" => Self::from_list(&[
+                ("id", F::INTEGER, 1),
+                ("location", F::INTEGER, 1),
+                ("size", F::INTEGER, 1),
+                ("flags", F::INTEGER, 1),
+                ("classname", F::STRING, 1),
+            ]),
+            _ => return None,
+        };
+        Some(schema)
+    }
+
+    fn find(&self, name: &str) -> Option<(FieldType, usize)> {
+        self.fields
+            .iter()
+            .find(|(i, ..)| i.eq_ignore_ascii_case(name.as_bytes()))
+            .map(|&(_, ty, count)| (ty, count))
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fn list<T>(
+            fmt: &mut fmt::Formatter,
+            list: &[T],
+            mut f: impl FnMut(&mut fmt::Formatter, &T) -> fmt::Result,
+        ) -> fmt::Result {
+            if let [value] = list {
+                return f(fmt, value);
+            }
+            fmt.write_char('[')?;
+            for (i, value) in list.iter().enumerate() {
+                if i != 0 {
+                    fmt.write_str(", ")?;
+                }
+                f(fmt, value)?;
+            }
+            fmt.write_char(']')
+        }
+
+        match self {
+            Self::Int(v) => list(fmt, v, |fmt, i| write!(fmt, "{i}")),
+            Self::Float(v) => list(fmt, v, |fmt, i| write!(fmt, "{i}")),
+            Self::Vector(v) => list(fmt, v, |fmt, [x, y, z]| write!(fmt, "({x} {y} {z})")),
+            Self::Str(v) => list(fmt, v, |fmt, i| write!(fmt, "{i:?}")),
+            Self::Bytes(v) => {
+                fmt.write_char('<')?;
+                for (i, b) in v.iter().enumerate() {
+                    if i != 0 {
+                        fmt.write_char(' ')?;
+                    }
+                    write!(fmt, "{b:02x}")?;
+                }
+                fmt.write_char('>')
+            }
+            Self::Fields(v) => write!(fmt, "{{{} fields}}", v.len()),
+        }
+    }
+}
+
+fn write_fields(fmt: &mut fmt::Formatter, fields: &[FieldInfo], depth: usize) -> fmt::Result {
+    for field in fields {
+        let indent = depth * 2;
+        let FieldInfo {
+            offset,
+            name,
+            value,
+            ..
+        } = field;
+        writeln!(fmt, "{:indent$}{offset:#08x} {name}: {value}", "")?;
+        if let Value::Fields(fields) = value {
+            write_fields(fmt, fields, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_items(
+    fmt: &mut fmt::Formatter,
+    report: &Report,
+    items: &[Item],
+    depth: usize,
+) -> fmt::Result {
+    let indent = depth * 2;
+    for item in items {
+        match item {
+            Item::Block(block) => {
+                writeln!(
+                    fmt,
+                    "{:indent$}{:#08x} block {:?}",
+                    "", block.offset, block.name
+                )?;
+                write_fields(fmt, &block.fields, depth + 1)?;
+            }
+            Item::Field(field) => write_fields(fmt, core::slice::from_ref(field), depth)?,
+            Item::Entity { index, items } => {
+                let classname = &report.entities[*index].classname;
+                writeln!(fmt, "{:indent$}entity {index} ({classname})", "")?;
+                write_items(fmt, report, items, depth + 1)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_report(fmt: &mut fmt::Formatter, report: &Report, depth: usize) -> fmt::Result {
+    let indent = depth * 2;
+    write!(fmt, "{:indent$}{} file", "", report.kind.as_str())?;
+    if let Some(version) = report.version {
+        write!(fmt, ", version {version:#x}")?;
+    }
+    let tokens = report.tokens.len();
+    let entities = report.entities.len();
+    writeln!(fmt, ", {tokens} tokens, {entities} entities")?;
+    write_items(fmt, report, &report.items, depth)?;
+    if !report.issues.is_empty() {
+        writeln!(fmt, "{:indent$}issues:", "")?;
+        for Issue { offset, message } in &report.issues {
+            writeln!(fmt, "{:indent$}  {offset:#08x}: {message}", "")?;
+        }
+    }
+    for file in &report.files {
+        writeln!(
+            fmt,
+            "{:indent$}{:#08x} embedded {:?}",
+            "", file.offset, file.name
+        )?;
+        write_report(fmt, &file.report, depth + 1)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write_report(fmt, self, 0)
+    }
+}
+
+mod json {
+    use super::*;
+
+    fn string(out: &mut String, s: &str) {
+        out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => {
+                    let _ = write!(out, "\\u{:04x}", c as u32);
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+
+    fn float(out: &mut String, f: f32) {
+        if f.is_finite() {
+            let _ = write!(out, "{f}");
+        } else {
+            out.push_str("null");
+        }
+    }
+
+    fn list<T>(out: &mut String, list: &[T], mut f: impl FnMut(&mut String, &T)) {
+        out.push('[');
+        for (i, value) in list.iter().enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            f(out, value);
+        }
+        out.push(']');
+    }
+
+    fn value(out: &mut String, value: &Value) {
+        match value {
+            Value::Int(v) => list(out, v, |out, i| out.push_str(&i.to_string())),
+            Value::Float(v) => list(out, v, |out, &i| float(out, i)),
+            Value::Vector(v) => list(out, v, |out, v| list(out, v, |out, &i| float(out, i))),
+            Value::Str(v) => list(out, v, |out, i| string(out, i)),
+            Value::Bytes(v) => list(out, v, |out, i| out.push_str(&i.to_string())),
+            Value::Fields(v) => fields(out, v),
+        }
+    }
+
+    fn fields(out: &mut String, fields: &[FieldInfo]) {
+        list(out, fields, |out, field| {
+            let _ = write!(out, "{{\"offset\":{},\"name\":", field.offset);
+            string(out, &field.name);
+            let _ = write!(out, ",\"size\":{},\"value\":", field.size);
+            value(out, &field.value);
+            out.push('}');
+        });
+    }
+
+    fn items(out: &mut String, values: &[Item]) {
+        list(out, values, |out, item| match item {
+            Item::Block(block) => {
+                let _ = write!(out, "{{\"block\":{{\"offset\":{},\"name\":", block.offset);
+                string(out, &block.name);
+                out.push_str(",\"fields\":");
+                fields(out, &block.fields);
+                out.push_str("}}");
+            }
+            Item::Field(field) => {
+                out.push_str("{\"field\":");
+                fields(out, core::slice::from_ref(field));
+                out.push('}');
+            }
+            Item::Entity {
+                index,
+                items: children,
+            } => {
+                let _ = write!(out, "{{\"entity\":{{\"index\":{index},\"items\":");
+                items(out, children);
+                out.push_str("}}");
+            }
+        });
+    }
+
+    pub(super) fn report(out: &mut String, report: &Report) {
+        out.push_str("{\"kind\":");
+        string(out, report.kind.as_str());
+        out.push_str(",\"version\":");
+        match report.version {
+            Some(version) => out.push_str(&version.to_string()),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"tokens\":");
+        list(out, &report.tokens, |out, i| string(out, i));
+        out.push_str(",\"entities\":");
+        list(out, &report.entities, |out, e| {
+            let _ = write!(
+                out,
+                "{{\"id\":{},\"location\":{},\"size\":{},\"flags\":{},\"classname\":",
+                e.id, e.location, e.size, e.flags,
+            );
+            string(out, &e.classname);
+            out.push('}');
+        });
+        out.push_str(",\"items\":");
+        items(out, &report.items);
+        out.push_str(",\"files\":");
+        list(out, &report.files, |out, file| {
+            let _ = write!(out, "{{\"offset\":{},\"name\":", file.offset);
+            string(out, &file.name);
+            out.push_str(",\"report\":");
+            self::report(out, &file.report);
+            out.push('}');
+        });
+        out.push_str(",\"issues\":");
+        list(out, &report.issues, |out, issue| {
+            let _ = write!(out, "{{\"offset\":{},\"message\":", issue.offset);
+            string(out, &issue.message);
+            out.push('}');
+        });
+        out.push('}');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKENS: &[&str] = &[
+        "",
+        "ETABLE",
+        "id",
+        "location",
+        "size",
+        "flags",
+        "classname",
+        "ENTVARS",
+        "health",
+        "ENTITY",
+        "m_foo",
+        "bogus",
+    ];
+
+    fn field(out: &mut Vec<u8>, token: u16, data: &[u8]) {
+        out.extend((data.len() as u16).to_le_bytes());
+        out.extend(token.to_le_bytes());
+        out.extend(data);
+    }
+
+    fn block(out: &mut Vec<u8>, token: u16, count: u32) {
+        field(out, token, &count.to_le_bytes());
+    }
+
+    fn tokens() -> Vec<String> {
+        TOKENS.iter().map(|i| i.to_string()).collect()
+    }
+
+    fn level_file() -> Vec<u8> {
+        let mut data = Vec::new();
+        block(&mut data, 7, 1);
+        field(&mut data, 8, &[0xfd]);
+        let mut nested = Vec::new();
+        field(&mut nested, 10, &[5]);
+        field(&mut data, 9, &nested);
+        let size = data.len() as u8;
+
+        block(&mut data, 1, 5);
+        field(&mut data, 2, &[0]);
+        field(&mut data, 3, &[0]);
+        field(&mut data, 4, &[size]);
+        field(&mut data, 5, &[0]);
+        field(&mut data, 6, b"info_target\0");
+
+        let tokens: Vec<u8> = TOKENS.iter().flat_map(|i| i.bytes().chain([0])).collect();
+        let mut out = Vec::new();
+        out.extend(SAVE_FILE_TAG.to_le_bytes());
+        out.extend(SAVE_VERSION.to_le_bytes());
+        out.extend((data.len() as u32).to_le_bytes());
+        out.extend(1_u32.to_le_bytes());
+        out.extend((TOKENS.len() as u32).to_le_bytes());
+        out.extend((tokens.len() as u32).to_le_bytes());
+        out.extend(tokens);
+        out.extend(data);
+        out
+    }
+
+    #[test]
+    fn inspect_level_file() {
+        let report = inspect(&level_file());
+        assert_eq!(report.kind, FileKind::Level);
+        assert_eq!(report.version, Some(SAVE_VERSION));
+        assert_eq!(report.tokens, tokens());
+        assert_eq!(report.issues, []);
+        assert_eq!(report.entities.len(), 1);
+        assert_eq!(report.entities[0].classname, "info_target");
+
+        let [Item::Entity { index: 0, items }, Item::Block(table)] = &report.items[..] else {
+            panic!("unexpected items {:?}", report.items);
+        };
+        assert_eq!(table.name, "ETABLE");
+        let [Item::Block(vars), Item::Field(entity)] = &items[..] else {
+            panic!("unexpected entity items {items:?}");
+        };
+        assert_eq!(vars.fields[0].name, "health");
+        assert_eq!(vars.fields[0].value, Value::Float(vec![1.0]));
+        let Value::Fields(fields) = &entity.value else {
+            panic!("unexpected entity value {:?}", entity.value);
+        };
+        assert_eq!(fields[0].name, "m_foo");
+        assert_eq!(fields[0].value, Value::Bytes(vec![5]));
+    }
+
+    #[test]
+    fn inspect_issues() {
+        let mut data = Vec::new();
+        block(&mut data, 7, 2);
+        field(&mut data, 11, &[1]);
+        field(&mut data, 8, &[0xfd, 0xfe]);
+        block(&mut data, 42, 0);
+
+        let report = inspect_data(tokens(), &data);
+        let issues: Vec<_> = report
+            .issues
+            .iter()
+            .map(|i| (i.offset, i.message.as_str()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (8, "unknown field \"bogus\" in \"ENTVARS\""),
+                (13, "2 values do not fit in 1 elements"),
+                (19, "token(42) not found"),
+                (19, "unknown block \"#42\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn inspect_truncated() {
+        let mut file = level_file();
+        file.truncate(file.len() - 4);
+        let report = inspect(&file);
+        assert!(report.has_issues());
+        assert!(report.items.is_empty());
+    }
+
+    #[test]
+    fn report_json() {
+        let json = inspect(&level_file()).to_json();
+        assert!(json.starts_with("{\"kind\":\"level\",\"version\":113,"));
+        assert!(json.contains("\"classname\":\"info_target\""));
+        assert!(json.ends_with("\"issues\":[]}"));
+    }
+}
@@ -0,0 +1,704 @@
+//! Test support without the engine.
+//!
+//! [lock] installs a fake engine with a string table and an entity list. It is enough to create
+//! entities and to run save/restore round trips with [RoundTrip].
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    ffi::{CStr, c_char, c_int, c_long, c_void},
+    fmt, mem, ptr,
+};
+use std::{
+    alloc::{self as std_alloc, Layout},
+    collections::HashMap,
+    ffi::CString,
+    sync::{Mutex, MutexGuard, Once},
+};
+
+use xash3d_shared::{
+    export::UnsyncGlobal,
+    ffi::{
+        common::vec3_t,
+        server::{ENTITYTABLE, SAVERESTOREDATA, edict_s, enginefuncs_s, globalvars_t},
+    },
+};
+
+use crate::{
+    engine::ServerEngineRef,
+    entity::CreateEntity,
+    game_rules::StubGameRules,
+    global_state::{GlobalState, GlobalStateRef},
+    private::{PrivateData, PrivateEntity},
+    save::{
+        self, Cursor, CursorMut, Restore, RestoreState, Save, SaveError, SaveRestoreData,
+        SaveState,
+        inspect::{self, FieldInfo, Item, Value},
+    },
+};
+
+/// The maximum number of entities in the fake engine.
+pub const MAX_EDICTS: usize = 256;
+
+const PRIVATE_DATA_ALIGN: usize = 16;
+
+struct Fake {
+    edicts: *mut edict_s,
+    strings: Vec<CString>,
+    private_data: HashMap<usize, Layout>,
+}
+
+unsafe impl Send for Fake {}
+
+static FAKE: Mutex<Option<Fake>> = Mutex::new(None);
+static LOCK: Mutex<()> = Mutex::new(());
+static INIT: Once = Once::new();
+
+fn with_fake<R>(f: impl FnOnce(&mut Fake) -> R) -> R {
+    let mut fake = FAKE.lock().unwrap_or_else(|err| err.into_inner());
+    f(fake.as_mut().expect("fake engine is not installed"))
+}
+
+fn edicts() -> &'static mut [edict_s] {
+    let edicts = with_fake(|fake| fake.edicts);
+    unsafe { core::slice::from_raw_parts_mut(edicts, MAX_EDICTS) }
+}
+
+unsafe extern "C" fn alloc_string(s: *const c_char) -> c_int {
+    let s = unsafe { CStr::from_ptr(s) }.to_owned();
+    with_fake(|fake| {
+        fake.strings.push(s);
+        fake.strings.len() as c_int
+    })
+}
+
+unsafe extern "C" fn string_from_index(index: c_int) -> *const c_char {
+    with_fake(
+        |fake| match fake.strings.get((index as usize).wrapping_sub(1)) {
+            Some(s) => s.as_ptr(),
+            None => c"".as_ptr(),
+        },
+    )
+}
+
+unsafe extern "C" fn create_entity() -> *mut edict_s {
+    for ent in edicts().iter_mut().skip(1) {
+        if ent.free != 0 {
+            unsafe {
+                ptr::write_bytes(ent as *mut edict_s, 0, 1);
+            }
+            ent.v.pContainingEntity = ent;
+            return ent;
+        }
+    }
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn remove_entity(ent: *mut edict_s) {
+    unsafe {
+        PrivateData::drop_in_place(ent);
+        let data = mem::replace(&mut (*ent).pvPrivateData, ptr::null_mut());
+        if let Some(layout) = with_fake(|fake| fake.private_data.remove(&(data as usize))) {
+            std_alloc::dealloc(data.cast(), layout);
+        }
+        (*ent).free = 1;
+    }
+}
+
+unsafe extern "C" fn alloc_private_data(ent: *mut edict_s, cb: c_long) -> *mut c_void {
+    let layout = Layout::from_size_align(cb as usize, PRIVATE_DATA_ALIGN).unwrap();
+    let data = unsafe { std_alloc::alloc_zeroed(layout) };
+    with_fake(|fake| fake.private_data.insert(data as usize, layout));
+    unsafe {
+        (*ent).pvPrivateData = data.cast();
+    }
+    data.cast()
+}
+
+unsafe extern "C" fn entity_offset(ent: *const edict_s) -> c_int {
+    let base = with_fake(|fake| fake.edicts);
+    (ent as usize - base as usize) as c_int
+}
+
+unsafe extern "C" fn entity_by_offset(offset: c_int) -> *mut edict_s {
+    let base = with_fake(|fake| fake.edicts);
+    base.cast::<u8>().wrapping_add(offset as usize).cast()
+}
+
+unsafe extern "C" fn entity_index(ent: *const edict_s) -> c_int {
+    let base = with_fake(|fake| fake.edicts);
+    unsafe { ent.offset_from(base) as c_int }
+}
+
+unsafe extern "C" fn entity_by_index(index: c_int) -> *mut edict_s {
+    match edicts().get_mut(index as usize) {
+        Some(ent) if ent.free == 0 => ent as *mut edict_s,
+        _ => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn time() -> f32 {
+    0.0
+}
+
+unsafe extern "C" fn precache(_: *const c_char) -> c_int {
+    0
+}
+
+unsafe extern "C" fn set_model(_: *mut edict_s, _: *const c_char) {}
+
+unsafe extern "C" fn set_size(_: *mut edict_s, _: *const f32, _: *const f32) {}
+
+unsafe extern "C" fn set_origin(_: *mut edict_s, _: *const f32) {}
+
+unsafe extern "C" fn emit_sound(
+    _: *mut edict_s,
+    _: c_int,
+    _: *const c_char,
+    _: f32,
+    _: f32,
+    _: c_int,
+    _: c_int,
+) {
+}
+
+unsafe extern "C" fn vec_to_yaw(v: *const f32) -> f32 {
+    let [x, y, _] = unsafe { *v.cast::<[f32; 3]>() };
+    if x == 0.0 && y == 0.0 {
+        return 0.0;
+    }
+    let yaw = y.atan2(x).to_degrees().trunc();
+    if yaw < 0.0 { yaw + 360.0 } else { yaw }
+}
+
+unsafe extern "C" fn random_int(min: c_int, _: c_int) -> c_int {
+    min
+}
+
+unsafe extern "C" fn random_float(min: f32, _: f32) -> f32 {
+    min
+}
+
+fn install() {
+    let edicts: Box<[edict_s]> = (0..MAX_EDICTS)
+        .map(|_| {
+            let mut ent: edict_s = unsafe { mem::zeroed() };
+            ent.free = 1;
+            ent
+        })
+        .collect();
+    let edicts = Box::leak(edicts);
+    // world spawn entity
+    let world: *mut edict_s = &mut edicts[0];
+    edicts[0].free = 0;
+    edicts[0].v.pContainingEntity = world;
+
+    *FAKE.lock().unwrap() = Some(Fake {
+        edicts: edicts.as_mut_ptr(),
+        strings: Vec::new(),
+        private_data: HashMap::new(),
+    });
+
+    let mut funcs: enginefuncs_s = unsafe { mem::zeroed() };
+    funcs.pfnPrecacheModel = Some(precache);
+    funcs.pfnPrecacheSound = Some(precache);
+    funcs.pfnModelIndex = Some(precache);
+    funcs.pfnSetModel = Some(set_model);
+    funcs.pfnSetSize = Some(set_size);
+    funcs.pfnSetOrigin = Some(set_origin);
+    funcs.pfnCreateEntity = Some(create_entity);
+    funcs.pfnRemoveEntity = Some(remove_entity);
+    funcs.pfnPvAllocEntPrivateData = Some(alloc_private_data);
+    funcs.pfnAllocString = Some(alloc_string);
+    funcs.pfnSzFromIndex = Some(string_from_index);
+    funcs.pfnEntOffsetOfPEntity = Some(entity_offset);
+    funcs.pfnPEntityOfEntOffset = Some(entity_by_offset);
+    funcs.pfnIndexOfEdict = Some(entity_index);
+    funcs.pfnPEntityOfEntIndex = Some(entity_by_index);
+    funcs.pfnTime = Some(time);
+    funcs.pfnEmitSound = Some(emit_sound);
+    funcs.pfnVecToYaw = Some(vec_to_yaw);
+    funcs.pfnRandomLong = Some(random_int);
+    funcs.pfnRandomFloat = Some(random_float);
+
+    let globals: &mut globalvars_t = Box::leak(Box::new(unsafe { mem::zeroed() }));
+    unsafe {
+        crate::instance::init_engine(&funcs, globals);
+        let engine = ServerEngineRef::new();
+        (*GlobalState::global_as_mut_ptr()).write(GlobalState::new(engine));
+    }
+}
+
+/// Exclusive access to the fake engine.
+pub struct TestEngine {
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl TestEngine {
+    pub fn engine(&self) -> ServerEngineRef {
+        unsafe { ServerEngineRef::new() }
+    }
+
+    pub fn global_state(&self) -> GlobalStateRef {
+        unsafe { GlobalStateRef::new() }
+    }
+
+    /// Sets the number of player slots, entities with indices up to `count` are players.
+    pub fn set_max_clients(&self, count: c_int) {
+        unsafe {
+            (*self.engine().globals.raw_mut()).maxClients = count;
+        }
+    }
+
+    /// Sets the map time returned by `globals.map_time()`.
+    pub fn set_time(&self, time: f32) {
+        unsafe {
+            (*self.engine().globals.raw_mut()).time = time;
+        }
+    }
+
+    /// Resets the state changed by previous tests.
+    fn reset(&self) {
+        self.set_max_clients(0);
+        self.set_time(0.0);
+        StubGameRules::install(self.engine(), self.global_state());
+    }
+}
+
+/// Installs the fake engine and locks it for the current test.
+///
+/// Tests run in parallel and the engine is a global, so every test that uses the engine must
+/// hold the lock.
+pub fn lock() -> TestEngine {
+    let guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    INIT.call_once(install);
+    let test = TestEngine { _guard: guard };
+    test.reset();
+    test
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoundTripError {
+    Save(SaveError),
+    Restore(SaveError),
+    /// The restored value was saved differently.
+    Mismatch(String),
+}
+
+impl fmt::Display for RoundTripError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Save(err) => write!(fmt, "failed to save, {err}"),
+            Self::Restore(err) => write!(fmt, "failed to restore, {err}"),
+            Self::Mismatch(diff) => write!(fmt, "restored value mismatch, {diff}"),
+        }
+    }
+}
+
+const ROUND_TRIP_FIELD_NAME: &CStr = c"ENTITY";
+
+/// An in-memory save buffer with a token table and an entity table.
+struct TestSaveData {
+    raw: SAVERESTOREDATA,
+    buffer: Vec<u8>,
+    tokens: Vec<*mut c_char>,
+    table: Vec<ENTITYTABLE>,
+}
+
+impl TestSaveData {
+    fn new(time: f32, landmark: Option<vec3_t>) -> Self {
+        let mut ret = Self {
+            raw: unsafe { mem::zeroed() },
+            buffer: vec![0; 0x10000],
+            tokens: vec![ptr::null_mut(); 0x1000],
+            table: Vec::new(),
+        };
+
+        for (i, ent) in edicts().iter_mut().enumerate() {
+            if ent.free == 0 {
+                let mut entry: ENTITYTABLE = unsafe { mem::zeroed() };
+                entry.id = i as c_int;
+                entry.pent = ent;
+                ret.table.push(entry);
+            }
+        }
+
+        let raw = &mut ret.raw;
+        raw.pBaseData = ret.buffer.as_mut_ptr().cast();
+        raw.pCurrentData = raw.pBaseData;
+        raw.bufferSize = ret.buffer.len() as c_int;
+        raw.pTokens = ret.tokens.as_mut_ptr();
+        raw.tokenCount = ret.tokens.len() as c_int;
+        raw.pTable = ret.table.as_mut_ptr();
+        raw.tableCount = ret.table.len() as c_int;
+        raw.time = time;
+        if let Some(offset) = landmark {
+            raw.fUseLandmark = 1;
+            raw.vecLandmarkOffset = offset;
+        }
+        ret
+    }
+
+    fn save<T: Save + ?Sized>(&mut self, value: &T) -> save::SaveResult<Vec<u8>> {
+        let engine = unsafe { ServerEngineRef::new() };
+        let data = SaveRestoreData::new(&mut self.raw);
+        let (buffer, state) = data.split_mut();
+        let mut state = SaveState::new(engine, state);
+        let mut cur = CursorMut::new(buffer.as_slice_mut());
+        cur.write_field(&mut state, ROUND_TRIP_FIELD_NAME, value)?;
+        let len = cur.offset();
+        Ok(buffer.as_slice()[..len].to_vec())
+    }
+
+    fn restore<T: Restore + ?Sized>(
+        &mut self,
+        bytes: &[u8],
+        value: &mut T,
+    ) -> save::SaveResult<()> {
+        let engine = unsafe { ServerEngineRef::new() };
+        let data = SaveRestoreData::new(&mut self.raw);
+        let state = RestoreState::new(engine, data.split_mut().1);
+        let field = Cursor::new(bytes).read_field()?;
+        value.restore(&state, &mut field.cursor())
+    }
+
+    fn token_names(&self) -> Vec<String> {
+        self.tokens
+            .iter()
+            .map(|&s| {
+                if s.is_null() {
+                    String::new()
+                } else {
+                    unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Saves a value, restores it into a fresh instance and compares the saved data of both.
+///
+/// Fields that are saved but not restored, restored from a wrong name or changed by the
+/// restore are reported as [RoundTripError::Mismatch].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RoundTrip {
+    time: f32,
+    landmark: Option<vec3_t>,
+}
+
+impl RoundTrip {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the map time used to save relative times.
+    pub fn time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Sets the landmark offset used for [PositionVector](crate::save::PositionVector) fields.
+    pub fn landmark(mut self, offset: vec3_t) -> Self {
+        self.landmark = Some(offset);
+        self
+    }
+
+    /// Runs the round trip for `value`, `fresh` receives the restored value.
+    pub fn run<T>(&self, value: &T, fresh: &mut T) -> Result<(), RoundTripError>
+    where
+        T: Save + Restore + ?Sized,
+    {
+        let mut data = TestSaveData::new(self.time, self.landmark);
+        let saved = data.save(value).map_err(RoundTripError::Save)?;
+        data.restore(&saved, fresh)
+            .map_err(RoundTripError::Restore)?;
+        let restored = data.save(fresh).map_err(RoundTripError::Save)?;
+        if saved == restored {
+            return Ok(());
+        }
+
+        let tokens = data.token_names();
+        let saved = entity_fields(inspect::inspect_data(tokens.clone(), &saved));
+        let restored = entity_fields(inspect::inspect_data(tokens, &restored));
+        let diff =
+            diff_fields("", &saved, &restored).unwrap_or_else(|| "saved data differs".to_string());
+        Err(RoundTripError::Mismatch(diff))
+    }
+
+    /// Saves `value` and restores it into `fresh` of another type.
+    ///
+    /// Used to restore saves written by an older version of a type.
+    pub fn restore_into<T, U>(&self, value: &T, fresh: &mut U) -> Result<(), RoundTripError>
+    where
+        T: Save + ?Sized,
+        U: Restore + ?Sized,
+    {
+        let mut data = TestSaveData::new(self.time, self.landmark);
+        let saved = data.save(value).map_err(RoundTripError::Save)?;
+        data.restore(&saved, fresh).map_err(RoundTripError::Restore)
+    }
+
+    /// Creates an entity, saves its private data and restores it into a new entity.
+    ///
+    /// `setup` is called for the first entity before save.
+    pub fn entity<P>(&self, setup: impl FnOnce(&mut P::Entity)) -> Result<(), RoundTripError>
+    where
+        P: PrivateEntity,
+        P::Entity: CreateEntity,
+    {
+        let engine = unsafe { ServerEngineRef::new() };
+        unsafe {
+            (*engine.globals.raw_mut()).time = self.time;
+        }
+        let entity = engine.new_entity::<P>().build();
+        setup(entity);
+        let fresh = engine.new_entity::<P>().build();
+        let result = self.run(&*entity, fresh);
+        unsafe {
+            engine.remove_entity_now(entity.vars());
+            engine.remove_entity_now(fresh.vars());
+        }
+        result
+    }
+}
+
+fn entity_fields(report: inspect::Report) -> Vec<FieldInfo> {
+    report
+        .items
+        .into_iter()
+        .find_map(|item| match item {
+            Item::Field(FieldInfo {
+                value: Value::Fields(fields),
+                ..
+            }) => Some(fields),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn diff_fields(path: &str, saved: &[FieldInfo], restored: &[FieldInfo]) -> Option<String> {
+    for i in 0..saved.len().max(restored.len()) {
+        let (a, b) = match (saved.get(i), restored.get(i)) {
+            (Some(a), Some(b)) => (a, b),
+            (Some(a), None) => return Some(format!("{path}{} is not restored", a.name)),
+            (None, Some(b)) => return Some(format!("{path}{} is saved after restore", b.name)),
+            (None, None) => unreachable!(),
+        };
+        if a.name != b.name {
+            return Some(format!(
+                "{path}{} is saved as {} after restore",
+                a.name, b.name
+            ));
+        }
+        match (&a.value, &b.value) {
+            (Value::Fields(x), Value::Fields(y)) => {
+                let path = format!("{path}{}.", a.name);
+                if let Some(diff) = diff_fields(&path, x, y) {
+                    return Some(diff);
+                }
+            }
+            (x, y) if x != y => {
+                return Some(format!("{path}{}: saved {x}, restored {y}", a.name));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::{BaseEntity, delegate_entity},
+        prelude::*,
+        private::impl_private,
+        save::PositionVector,
+        str::MapString,
+        time::MapTime,
+    };
+
+    use super::*;
+
+    #[derive(Default, Save, Restore)]
+    struct Sample {
+        count: i32,
+        origin: PositionVector,
+        name: Option<MapString>,
+        next: MapTime,
+        ammo: [i32; 3],
+        #[save(skip_restore)]
+        skipped: u8,
+    }
+
+    #[derive(Save, Restore)]
+    struct TestEntity {
+        base: BaseEntity,
+        speed: f32,
+        target: Option<MapString>,
+    }
+
+    impl CreateEntity for TestEntity {
+        fn create(base: BaseEntity) -> Self {
+            Self {
+                base,
+                speed: 0.0,
+                target: None,
+            }
+        }
+    }
+
+    impl Entity for TestEntity {
+        delegate_entity!(base);
+    }
+
+    impl_private!(TestEntity {});
+
+    #[test]
+    fn round_trip_value() {
+        let test = lock();
+        let value = Sample {
+            count: 42,
+            origin: PositionVector(vec3_t::new(1.0, 2.0, 3.0)),
+            name: Some(test.engine().new_map_string(c"foo")),
+            next: MapTime::from_secs_f32(15.0),
+            ammo: [17, 0, -1],
+            skipped: 0,
+        };
+        let mut fresh = Sample::default();
+        let round_trip = RoundTrip::new()
+            .time(10.0)
+            .landmark(vec3_t::new(100.0, 0.0, -50.0));
+        assert_eq!(round_trip.run(&value, &mut fresh), Ok(()));
+        assert_eq!(fresh.count, 42);
+        assert_eq!(fresh.origin, value.origin);
+        assert_eq!(fresh.name.unwrap().as_c_str(), c"foo");
+        assert_eq!(fresh.ammo, [17, 0, -1]);
+    }
+
+    #[test]
+    fn round_trip_mismatch() {
+        let _test = lock();
+        let value = Sample {
+            skipped: 1,
+            ..Sample::default()
+        };
+        let mut fresh = Sample::default();
+        let err = RoundTrip::new().run(&value, &mut fresh).unwrap_err();
+        assert_eq!(
+            err,
+            RoundTripError::Mismatch("skipped: saved <01>, restored <00>".to_string())
+        );
+    }
+
+    #[test]
+    fn round_trip_entity() {
+        let test = lock();
+        let target = test.engine().new_map_string(c"door1");
+        let result = RoundTrip::new().entity::<TestEntity>(|ent| {
+            ent.speed = 100.0;
+            ent.target = Some(target);
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    mod v0 {
+        use crate::prelude::*;
+
+        #[derive(Default, Save, Restore)]
+        pub struct Versioned {
+            pub count: i32,
+            pub speed: f32,
+        }
+    }
+
+    mod v1 {
+        use crate::prelude::*;
+
+        #[derive(Default, Save, Restore)]
+        #[save(version = 1)]
+        pub struct Versioned {
+            pub count: i32,
+            pub speed: f32,
+        }
+    }
+
+    mod v2 {
+        use crate::{prelude::*, save::Migrate};
+
+        fn default_health() -> f32 {
+            100.0
+        }
+
+        #[derive(Default, Save, Restore)]
+        #[save(version = 2)]
+        pub struct Versioned {
+            #[save(alias = "count")]
+            pub amount: i32,
+            pub speed: f32,
+            #[save(default = default_health)]
+            pub health: f32,
+            #[save(skip)]
+            pub migrated_from: Option<u32>,
+        }
+
+        impl Migrate for Versioned {
+            fn migrate(&mut self, version: u32) {
+                self.migrated_from = Some(version);
+            }
+        }
+    }
+
+    #[test]
+    fn restore_alias() {
+        let _test = lock();
+        let old = v1::Versioned {
+            count: 7,
+            speed: 2.5,
+        };
+        let mut new = v2::Versioned::default();
+        assert_eq!(RoundTrip::new().restore_into(&old, &mut new), Ok(()));
+        assert_eq!(new.amount, 7);
+        assert_eq!(new.speed, 2.5);
+    }
+
+    #[test]
+    fn restore_default() {
+        let _test = lock();
+        let old = v1::Versioned::default();
+        let mut new = v2::Versioned::default();
+        assert_eq!(RoundTrip::new().restore_into(&old, &mut new), Ok(()));
+        assert_eq!(new.health, 100.0);
+
+        // the saved value wins over the default
+        let value = v2::Versioned {
+            health: 25.0,
+            ..v2::Versioned::default()
+        };
+        let mut fresh = v2::Versioned::default();
+        assert_eq!(RoundTrip::new().run(&value, &mut fresh), Ok(()));
+        assert_eq!(fresh.health, 25.0);
+    }
+
+    #[test]
+    fn restore_migrate() {
+        let _test = lock();
+        let mut new = v2::Versioned::default();
+        let result = RoundTrip::new().restore_into(&v0::Versioned::default(), &mut new);
+        assert_eq!(result, Ok(()));
+        assert_eq!(new.migrated_from, Some(0));
+
+        let mut new = v2::Versioned::default();
+        let result = RoundTrip::new().restore_into(&v1::Versioned::default(), &mut new);
+        assert_eq!(result, Ok(()));
+        assert_eq!(new.migrated_from, Some(1));
+
+        // the current version is not migrated
+        let mut new = v2::Versioned::default();
+        let result = RoundTrip::new().restore_into(&v2::Versioned::default(), &mut new);
+        assert_eq!(result, Ok(()));
+        assert_eq!(new.migrated_from, None);
+    }
+}
@@ -5,6 +5,7 @@ use xash3d_shared::{
     csz::{self, CStrSlice, CStrThin},
     entity::EdictFlags,
     ffi::common::vec3_t,
+    sound::Attenuation,
     str::ToEngineStr,
 };
 
@@ -278,6 +279,80 @@ pub fn decal_trace(engine: &ServerEngine, trace: &TraceResult, decal_index: u16)
     }
 }
 
+/// Plays a bullet impact sound for the surface material hit by the trace.
+///
+/// Returns the volume of the impact sound heard by the attacker.
+pub fn play_texture_sound(
+    engine: &ServerEngine,
+    trace: &TraceResult,
+    start: vec3_t,
+    end: vec3_t,
+) -> f32 {
+    use xash3d_player_move::{CHAR_TEX_COMPUTER, CHAR_TEX_CONCRETE, CHAR_TEX_FLESH};
+
+    let hit_entity = trace.hit_entity();
+    let is_flesh = hit_entity.as_ref().is_some_and(|ent| {
+        ent.vars()
+            .flags()
+            .intersects(EdictFlags::MONSTER | EdictFlags::CLIENT)
+    });
+
+    let materials = xash3d_player_move::materials();
+    let material = if is_flesh {
+        materials.get(CHAR_TEX_FLESH as u8)
+    } else {
+        let texture_name = match &hit_entity {
+            Some(ent) => engine.trace_texture(start, end, ent),
+            None => engine.trace_texture(start, end, &engine.get_world_spawn_entity()),
+        };
+        match texture_name {
+            Some(name) => {
+                let name = xash3d_player_move::strip_texture_prefix(name.to_bytes());
+                materials.find(name)
+            }
+            None => materials.get(CHAR_TEX_CONCRETE as u8),
+        }
+    };
+
+    let mut volume = material.impact_volume;
+    let mut volume_bar = material.impact_volume_bar;
+    let is_breakable = hit_entity
+        .as_ref()
+        .is_some_and(|ent| ent.vars().is_class_name(c"func_breakable"));
+    if is_breakable {
+        volume /= 1.5;
+        volume_bar /= 2.0;
+    } else if material.id == CHAR_TEX_COMPUTER as u8
+        && trace.fraction() != 1.0
+        && engine.random_int(0, 1) != 0
+    {
+        Sparks::new(engine.engine_ref()).emit_simple(trace.end_position());
+    }
+
+    let samples = &material.impact_sounds;
+    if samples.is_empty() {
+        return volume_bar;
+    }
+    let sample = &samples[engine.random_int(0, samples.len() as i32 - 1) as usize];
+    let attenuation = if is_flesh {
+        Attenuation::from(1.0)
+    } else {
+        Attenuation::NORM
+    };
+    engine
+        .build_sound()
+        .volume(volume)
+        .attenuation(attenuation)
+        .pitch(96 + engine.random_int(0, 0xf))
+        .ambient_emit(
+            sample,
+            trace.end_position(),
+            &engine.get_world_spawn_entity(),
+        );
+
+    volume_bar
+}
+
 #[cfg_attr(feature = "save", derive(Save, Restore))]
 pub struct Sparks {
     #[cfg_attr(feature = "save", save(skip))]
@@ -1,10 +1,14 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+mod reader;
+
 use bitflags::bitflags;
 
 use crate::ffi;
 
+pub use self::reader::*;
+
 pub const MAX_MAP_TEXTURES: usize = ffi::common::MAX_MAP_TEXTURES as usize;
 
 pub const MAX_MAP_LEAFS: usize = ffi::common::MAX_MAP_LEAFS as usize;
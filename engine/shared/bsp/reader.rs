@@ -0,0 +1,1048 @@
+use core::{fmt, str};
+
+use alloc::vec::Vec;
+
+use crate::ffi::common::vec3_t;
+
+/// The number of lumps in the BSP header.
+pub const HEADER_LUMPS: usize = 15;
+
+/// The number of collision hulls in a brush model.
+pub const MAX_MAP_HULLS: usize = 4;
+
+const MIPLEVELS: usize = 4;
+const MIPTEX_NAME_SIZE: usize = 16;
+const MIPTEX_HEADER_SIZE: usize = MIPTEX_NAME_SIZE + 4 * 2 + 4 * MIPLEVELS;
+const PALETTE_SIZE: usize = 256 * 3;
+
+const Q1BSP_VERSION: u32 = 29;
+const HLBSP_VERSION: u32 = 30;
+const QBSP2_VERSION: u32 = u32::from_le_bytes(*b"BSP2");
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BspVersion {
+    /// Quake BSP v29.
+    Quake,
+    /// Half-Life BSP v30.
+    HalfLife,
+    /// Quake BSP v29 with 32-bit nodes, leafs and clipnodes.
+    Bsp2,
+}
+
+impl BspVersion {
+    pub const fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            Q1BSP_VERSION => Some(Self::Quake),
+            HLBSP_VERSION => Some(Self::HalfLife),
+            QBSP2_VERSION => Some(Self::Bsp2),
+            _ => None,
+        }
+    }
+
+    pub const fn into_raw(self) -> u32 {
+        match self {
+            Self::Quake => Q1BSP_VERSION,
+            Self::HalfLife => HLBSP_VERSION,
+            Self::Bsp2 => QBSP2_VERSION,
+        }
+    }
+
+    /// Returns `true` if this version uses 32-bit nodes, leafs and clipnodes.
+    pub const fn is_bsp2(self) -> bool {
+        matches!(self, Self::Bsp2)
+    }
+
+    /// Returns `true` if embedded textures have a palette.
+    pub const fn has_palette(self) -> bool {
+        matches!(self, Self::HalfLife)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Lump {
+    Entities,
+    Planes,
+    Textures,
+    Vertices,
+    Visibility,
+    Nodes,
+    TexInfo,
+    Faces,
+    Lighting,
+    ClipNodes,
+    Leafs,
+    MarkSurfaces,
+    Edges,
+    SurfEdges,
+    Models,
+}
+
+impl Lump {
+    /// All lumps in the order they are stored in the header.
+    pub const ALL: [Lump; HEADER_LUMPS] = [
+        Self::Entities,
+        Self::Planes,
+        Self::Textures,
+        Self::Vertices,
+        Self::Visibility,
+        Self::Nodes,
+        Self::TexInfo,
+        Self::Faces,
+        Self::Lighting,
+        Self::ClipNodes,
+        Self::Leafs,
+        Self::MarkSurfaces,
+        Self::Edges,
+        Self::SurfEdges,
+        Self::Models,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Entities => "entities",
+            Self::Planes => "planes",
+            Self::Textures => "textures",
+            Self::Vertices => "vertices",
+            Self::Visibility => "visibility",
+            Self::Nodes => "nodes",
+            Self::TexInfo => "texinfo",
+            Self::Faces => "faces",
+            Self::Lighting => "lighting",
+            Self::ClipNodes => "clipnodes",
+            Self::Leafs => "leafs",
+            Self::MarkSurfaces => "marksurfaces",
+            Self::Edges => "edges",
+            Self::SurfEdges => "surfedges",
+            Self::Models => "models",
+        }
+    }
+}
+
+impl fmt::Display for Lump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BspError {
+    /// The data is too short to hold the header.
+    UnexpectedEnd,
+    UnsupportedVersion(u32),
+    /// The lump is out of the file bounds.
+    LumpOutOfBounds(Lump),
+    /// The lump size is not a multiple of the item size.
+    InvalidLumpSize(Lump),
+    /// An item in the lump references data that does not exist.
+    InvalidIndex {
+        lump: Lump,
+        index: usize,
+    },
+    /// An embedded texture is out of the lump bounds.
+    InvalidMipTex(usize),
+    /// Compressed visibility data is truncated.
+    InvalidVisibility,
+}
+
+impl fmt::Display for BspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "Unexpected end"),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported version {version:#x}"),
+            Self::LumpOutOfBounds(lump) => write!(f, "Lump {lump} is out of bounds"),
+            Self::InvalidLumpSize(lump) => write!(f, "Lump {lump} has invalid size"),
+            Self::InvalidIndex { lump, index } => {
+                write!(f, "Invalid reference in lump {lump} at index {index}")
+            }
+            Self::InvalidMipTex(index) => write!(f, "Invalid texture at index {index}"),
+            Self::InvalidVisibility => write!(f, "Invalid visibility data"),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.data.len() {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N).and_then(|s| s.try_into().ok())
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.array().map(i16::from_le_bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    fn vec3(&mut self) -> Option<vec3_t> {
+        Some(vec3_t::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn vec3_i16(&mut self) -> Option<vec3_t> {
+        let x = self.i16()? as f32;
+        let y = self.i16()? as f32;
+        let z = self.i16()? as f32;
+        Some(vec3_t::new(x, y, z))
+    }
+}
+
+fn parse_items<'a, T>(
+    lump: Lump,
+    data: &'a [u8],
+    size: usize,
+    mut parse: impl FnMut(&mut Reader<'a>) -> Option<T>,
+) -> Result<Vec<T>, BspError> {
+    if data.len() % size != 0 {
+        return Err(BspError::InvalidLumpSize(lump));
+    }
+    data.chunks_exact(size)
+        .map(|chunk| parse(&mut Reader::new(chunk)).ok_or(BspError::InvalidLumpSize(lump)))
+        .collect()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: vec3_t,
+    pub dist: f32,
+    /// Axial plane type, 0-2 for planes along the X, Y and Z axes.
+    pub ty: i32,
+}
+
+impl Plane {
+    const SIZE: usize = 20;
+
+    fn parse(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            normal: r.vec3()?,
+            dist: r.f32()?,
+            ty: r.i32()?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NodeChild {
+    Node(usize),
+    Leaf(usize),
+}
+
+impl NodeChild {
+    fn from_raw(raw: i32) -> Self {
+        if raw >= 0 {
+            Self::Node(raw as usize)
+        } else {
+            Self::Leaf((-1 - raw) as usize)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Node {
+    pub plane: usize,
+    pub children: [NodeChild; 2],
+    pub mins: vec3_t,
+    pub maxs: vec3_t,
+    pub first_face: u32,
+    pub face_count: u32,
+}
+
+impl Node {
+    const SIZE: usize = 24;
+    const SIZE_BSP2: usize = 44;
+
+    fn parse(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            plane: r.u32()? as usize,
+            children: [
+                NodeChild::from_raw(r.i16()? as i32),
+                NodeChild::from_raw(r.i16()? as i32),
+            ],
+            mins: r.vec3_i16()?,
+            maxs: r.vec3_i16()?,
+            first_face: r.u16()? as u32,
+            face_count: r.u16()? as u32,
+        })
+    }
+
+    fn parse_bsp2(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            plane: r.u32()? as usize,
+            children: [NodeChild::from_raw(r.i32()?), NodeChild::from_raw(r.i32()?)],
+            mins: r.vec3()?,
+            maxs: r.vec3()?,
+            first_face: r.u32()?,
+            face_count: r.u32()?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClipNodeChild {
+    Node(usize),
+    /// Leaf contents, see [Contents](crate::consts::Contents).
+    Contents(i32),
+}
+
+impl ClipNodeChild {
+    fn from_raw(raw: i32) -> Self {
+        if raw >= 0 {
+            Self::Node(raw as usize)
+        } else {
+            Self::Contents(raw)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ClipNode {
+    pub plane: usize,
+    pub children: [ClipNodeChild; 2],
+}
+
+impl ClipNode {
+    const SIZE: usize = 8;
+    const SIZE_BSP2: usize = 12;
+
+    fn parse(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            plane: r.u32()? as usize,
+            children: [
+                ClipNodeChild::from_raw(r.i16()? as i32),
+                ClipNodeChild::from_raw(r.i16()? as i32),
+            ],
+        })
+    }
+
+    fn parse_bsp2(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            plane: r.u32()? as usize,
+            children: [
+                ClipNodeChild::from_raw(r.i32()?),
+                ClipNodeChild::from_raw(r.i32()?),
+            ],
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Leaf {
+    /// Leaf contents, see [Contents](crate::consts::Contents).
+    pub contents: i32,
+    /// Offset to the compressed visibility data, `None` if the leaf sees everything.
+    pub vis_offset: Option<usize>,
+    pub mins: vec3_t,
+    pub maxs: vec3_t,
+    pub first_mark_surface: u32,
+    pub mark_surface_count: u32,
+    pub ambient_level: [u8; 4],
+}
+
+impl Leaf {
+    const SIZE: usize = 28;
+    const SIZE_BSP2: usize = 44;
+
+    fn vis_offset(raw: i32) -> Option<usize> {
+        (raw >= 0).then_some(raw as usize)
+    }
+
+    fn parse(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            contents: r.i32()?,
+            vis_offset: Self::vis_offset(r.i32()?),
+            mins: r.vec3_i16()?,
+            maxs: r.vec3_i16()?,
+            first_mark_surface: r.u16()? as u32,
+            mark_surface_count: r.u16()? as u32,
+            ambient_level: r.array()?,
+        })
+    }
+
+    fn parse_bsp2(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            contents: r.i32()?,
+            vis_offset: Self::vis_offset(r.i32()?),
+            mins: r.vec3()?,
+            maxs: r.vec3()?,
+            first_mark_surface: r.u32()?,
+            mark_surface_count: r.u32()?,
+            ambient_level: r.array()?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Model {
+    pub mins: vec3_t,
+    pub maxs: vec3_t,
+    pub origin: vec3_t,
+    /// The root node for the hull 0 and clipnodes for other hulls.
+    pub head_nodes: [i32; MAX_MAP_HULLS],
+    pub vis_leafs: i32,
+    pub first_face: i32,
+    pub face_count: i32,
+}
+
+impl Model {
+    const SIZE: usize = 64;
+
+    fn parse(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            mins: r.vec3()?,
+            maxs: r.vec3()?,
+            origin: r.vec3()?,
+            head_nodes: [r.i32()?, r.i32()?, r.i32()?, r.i32()?],
+            vis_leafs: r.i32()?,
+            first_face: r.i32()?,
+            face_count: r.i32()?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TexInfo {
+    /// Texture axes, `[s, t]` with the offset in the last component.
+    pub vecs: [[f32; 4]; 2],
+    pub miptex: usize,
+    pub flags: u32,
+}
+
+impl TexInfo {
+    const SIZE: usize = 40;
+
+    fn parse(r: &mut Reader) -> Option<Self> {
+        let mut vecs = [[0.0; 4]; 2];
+        for i in vecs.as_flattened_mut() {
+            *i = r.f32()?;
+        }
+        Some(Self {
+            vecs,
+            miptex: r.u32()? as usize,
+            flags: r.u32()?,
+        })
+    }
+}
+
+/// A texture from the textures lump.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MipTex<'a> {
+    name: &'a [u8],
+    pub width: u32,
+    pub height: u32,
+    offsets: [u32; MIPLEVELS],
+    data: &'a [u8],
+    has_palette: bool,
+}
+
+impl<'a> MipTex<'a> {
+    fn parse(data: &'a [u8], has_palette: bool) -> Option<Self> {
+        let mut r = Reader::new(data);
+        let name = r.bytes(MIPTEX_NAME_SIZE)?;
+        let name = name.split(|&c| c == 0).next().unwrap_or(name);
+        let width = r.u32()?;
+        let height = r.u32()?;
+        let offsets = [r.u32()?, r.u32()?, r.u32()?, r.u32()?];
+        let mut ret = Self {
+            name,
+            width,
+            height,
+            offsets,
+            data,
+            has_palette,
+        };
+        if !ret.is_external() {
+            let end = ret.pixels_end()?;
+            ret.data = data.get(..end)?;
+        }
+        Some(ret)
+    }
+
+    fn mip_size(&self, level: usize) -> Option<usize> {
+        let w = (self.width >> level) as usize;
+        let h = (self.height >> level) as usize;
+        w.checked_mul(h)
+    }
+
+    fn pixels_end(&self) -> Option<usize> {
+        let mut end = MIPTEX_HEADER_SIZE;
+        for level in 0..MIPLEVELS {
+            let start = self.offsets[level] as usize;
+            let level_end = start.checked_add(self.mip_size(level)?)?;
+            if start < MIPTEX_HEADER_SIZE || level_end > self.data.len() {
+                return None;
+            }
+            end = end.max(level_end);
+        }
+        if self.has_palette {
+            let mut r = Reader::new(self.data.get(end..)?);
+            let count = r.u16()? as usize;
+            r.bytes(count.checked_mul(3)?)?;
+            end += 2 + count * 3;
+        }
+        Some(end)
+    }
+
+    /// Returns the texture name as bytes.
+    pub fn name_bytes(&self) -> &'a [u8] {
+        self.name
+    }
+
+    pub fn name(&self) -> Option<&'a str> {
+        str::from_utf8(self.name).ok()
+    }
+
+    /// Returns `true` if pixels are stored in a WAD file.
+    pub fn is_external(&self) -> bool {
+        self.offsets.iter().all(|&i| i == 0)
+    }
+
+    /// Returns palette indices for the mip level.
+    pub fn mip_level(&self, level: usize) -> Option<&'a [u8]> {
+        if self.is_external() || level >= MIPLEVELS {
+            return None;
+        }
+        let start = self.offsets[level] as usize;
+        self.data.get(start..start + self.mip_size(level)?)
+    }
+
+    /// Returns the RGB palette.
+    ///
+    /// Only Half-Life maps have palettes, usually with 256 colors.
+    pub fn palette(&self) -> Option<&'a [u8]> {
+        if self.is_external() || !self.has_palette {
+            return None;
+        }
+        let end = (0..MIPLEVELS)
+            .map(|i| self.offsets[i] as usize + self.mip_size(i).unwrap_or(0))
+            .max()?;
+        let mut r = Reader::new(self.data.get(end..)?);
+        let count = r.u16()? as usize;
+        r.bytes(count * 3)
+    }
+
+    /// Returns the raw texture data including the header.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// A parsed BSP map file.
+///
+/// Lumps with variable size data like entities and visibility are borrowed from the file data.
+#[derive(Clone, Debug)]
+pub struct Bsp<'a> {
+    version: BspVersion,
+    lumps: [&'a [u8]; HEADER_LUMPS],
+    planes: Vec<Plane>,
+    nodes: Vec<Node>,
+    clip_nodes: Vec<ClipNode>,
+    leafs: Vec<Leaf>,
+    models: Vec<Model>,
+    tex_info: Vec<TexInfo>,
+    textures: Vec<Option<MipTex<'a>>>,
+}
+
+impl<'a> Bsp<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, BspError> {
+        let mut r = Reader::new(data);
+        let raw_version = r.u32().ok_or(BspError::UnexpectedEnd)?;
+        let version =
+            BspVersion::from_raw(raw_version).ok_or(BspError::UnsupportedVersion(raw_version))?;
+
+        let mut lumps = [&data[..0]; HEADER_LUMPS];
+        for (lump, slot) in Lump::ALL.into_iter().zip(&mut lumps) {
+            let offset = r.u32().ok_or(BspError::UnexpectedEnd)? as usize;
+            let len = r.u32().ok_or(BspError::UnexpectedEnd)? as usize;
+            *slot = offset
+                .checked_add(len)
+                .and_then(|end| data.get(offset..end))
+                .ok_or(BspError::LumpOutOfBounds(lump))?;
+        }
+
+        let lump = |lump: Lump| lumps[lump as usize];
+        let bsp2 = version.is_bsp2();
+
+        let planes = parse_items(Lump::Planes, lump(Lump::Planes), Plane::SIZE, Plane::parse)?;
+        let nodes = if bsp2 {
+            parse_items(
+                Lump::Nodes,
+                lump(Lump::Nodes),
+                Node::SIZE_BSP2,
+                Node::parse_bsp2,
+            )?
+        } else {
+            parse_items(Lump::Nodes, lump(Lump::Nodes), Node::SIZE, Node::parse)?
+        };
+        let clip_nodes = if bsp2 {
+            let data = lump(Lump::ClipNodes);
+            parse_items(
+                Lump::ClipNodes,
+                data,
+                ClipNode::SIZE_BSP2,
+                ClipNode::parse_bsp2,
+            )?
+        } else {
+            let data = lump(Lump::ClipNodes);
+            parse_items(Lump::ClipNodes, data, ClipNode::SIZE, ClipNode::parse)?
+        };
+        let leafs = if bsp2 {
+            parse_items(
+                Lump::Leafs,
+                lump(Lump::Leafs),
+                Leaf::SIZE_BSP2,
+                Leaf::parse_bsp2,
+            )?
+        } else {
+            parse_items(Lump::Leafs, lump(Lump::Leafs), Leaf::SIZE, Leaf::parse)?
+        };
+        let models = parse_items(Lump::Models, lump(Lump::Models), Model::SIZE, Model::parse)?;
+        let tex_info = parse_items(
+            Lump::TexInfo,
+            lump(Lump::TexInfo),
+            TexInfo::SIZE,
+            TexInfo::parse,
+        )?;
+        let textures = parse_textures(lump(Lump::Textures), version.has_palette())?;
+
+        let ret = Self {
+            version,
+            lumps,
+            planes,
+            nodes,
+            clip_nodes,
+            leafs,
+            models,
+            tex_info,
+            textures,
+        };
+        ret.validate()?;
+        Ok(ret)
+    }
+
+    fn validate(&self) -> Result<(), BspError> {
+        fn check(lump: Lump, index: usize, valid: bool) -> Result<(), BspError> {
+            if valid {
+                Ok(())
+            } else {
+                Err(BspError::InvalidIndex { lump, index })
+            }
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut valid = node.plane < self.planes.len();
+            for child in node.children {
+                valid &= match child {
+                    NodeChild::Node(n) => n < self.nodes.len(),
+                    NodeChild::Leaf(n) => n < self.leafs.len(),
+                };
+            }
+            check(Lump::Nodes, i, valid)?;
+        }
+
+        for (i, node) in self.clip_nodes.iter().enumerate() {
+            let mut valid = node.plane < self.planes.len();
+            for child in node.children {
+                if let ClipNodeChild::Node(n) = child {
+                    valid &= n < self.clip_nodes.len();
+                }
+            }
+            check(Lump::ClipNodes, i, valid)?;
+        }
+
+        let vis_len = self.visibility().len();
+        for (i, leaf) in self.leafs.iter().enumerate() {
+            let valid = leaf.vis_offset.is_none_or(|offset| offset < vis_len);
+            check(Lump::Leafs, i, valid)?;
+        }
+
+        for (i, model) in self.models.iter().enumerate() {
+            let [head, clip @ ..] = model.head_nodes;
+            let mut valid = head >= 0 && (head as usize) < self.nodes.len().max(1);
+            for node in clip {
+                valid &= node < 0 || (node as usize) < self.clip_nodes.len();
+            }
+            check(Lump::Models, i, valid)?;
+        }
+
+        for (i, info) in self.tex_info.iter().enumerate() {
+            check(Lump::TexInfo, i, info.miptex < self.textures.len())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn version(&self) -> BspVersion {
+        self.version
+    }
+
+    /// Returns the raw data of the lump.
+    pub fn lump(&self, lump: Lump) -> &'a [u8] {
+        self.lumps[lump as usize]
+    }
+
+    /// Returns the entity lump text without the trailing nul byte.
+    pub fn entities(&self) -> &'a [u8] {
+        let data = self.lump(Lump::Entities);
+        data.split(|&c| c == 0).next().unwrap_or(data)
+    }
+
+    pub fn entities_str(&self) -> Result<&'a str, str::Utf8Error> {
+        str::from_utf8(self.entities())
+    }
+
+    pub fn planes(&self) -> &[Plane] {
+        &self.planes
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn clip_nodes(&self) -> &[ClipNode] {
+        &self.clip_nodes
+    }
+
+    pub fn leafs(&self) -> &[Leaf] {
+        &self.leafs
+    }
+
+    pub fn models(&self) -> &[Model] {
+        &self.models
+    }
+
+    pub fn tex_info(&self) -> &[TexInfo] {
+        &self.tex_info
+    }
+
+    /// Returns textures from the textures lump, `None` for unused slots.
+    pub fn textures(&self) -> &[Option<MipTex<'a>>] {
+        &self.textures
+    }
+
+    /// Returns the compressed visibility data.
+    pub fn visibility(&self) -> &'a [u8] {
+        self.lump(Lump::Visibility)
+    }
+
+    /// Decompresses the visibility row of the leaf.
+    ///
+    /// Bit `n` of the row is set if leaf `n + 1` is visible. If the leaf has no visibility data
+    /// all leafs are visible.
+    pub fn leaf_pvs(&self, leaf: &Leaf) -> Result<Vec<u8>, BspError> {
+        let vis_leafs = self
+            .models
+            .first()
+            .map_or(0, |m| m.vis_leafs.max(0) as usize);
+        let row = vis_leafs.div_ceil(8);
+        match leaf.vis_offset {
+            Some(offset) => {
+                let data = self.visibility().get(offset..);
+                decompress_vis(data.ok_or(BspError::InvalidVisibility)?, row)
+            }
+            None => Ok(vec![0xff; row]),
+        }
+    }
+}
+
+fn parse_textures(data: &[u8], has_palette: bool) -> Result<Vec<Option<MipTex<'_>>>, BspError> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut r = Reader::new(data);
+    let err = BspError::InvalidLumpSize(Lump::Textures);
+    let count = r.i32().ok_or(err)?;
+    let count = usize::try_from(count).map_err(|_| err)?;
+    if count > data.len() / 4 {
+        return Err(err);
+    }
+    let mut textures = Vec::with_capacity(count);
+    for i in 0..count {
+        let offset = r.i32().ok_or(err)?;
+        if offset < 0 {
+            textures.push(None);
+            continue;
+        }
+        let tex = data
+            .get(offset as usize..)
+            .and_then(|data| MipTex::parse(data, has_palette))
+            .ok_or(BspError::InvalidMipTex(i))?;
+        textures.push(Some(tex));
+    }
+    Ok(textures)
+}
+
+/// Decompresses a run-length encoded visibility row with `len` bytes.
+pub fn decompress_vis(data: &[u8], len: usize) -> Result<Vec<u8>, BspError> {
+    let mut out = Vec::with_capacity(len);
+    let mut iter = data.iter();
+    while out.len() < len {
+        match iter.next() {
+            Some(0) => {
+                let count = *iter.next().ok_or(BspError::InvalidVisibility)? as usize;
+                let count = count.min(len - out.len());
+                out.resize(out.len() + count, 0);
+            }
+            Some(&c) => out.push(c),
+            None => return Err(BspError::InvalidVisibility),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Builder {
+        lumps: [Vec<u8>; HEADER_LUMPS],
+    }
+
+    impl Builder {
+        fn put(&mut self, lump: Lump, data: &[u8]) -> &mut Self {
+            self.lumps[lump as usize].extend_from_slice(data);
+            self
+        }
+
+        fn put_i32(&mut self, lump: Lump, values: &[i32]) -> &mut Self {
+            for i in values {
+                self.put(lump, &i.to_le_bytes());
+            }
+            self
+        }
+
+        fn put_i16(&mut self, lump: Lump, values: &[i16]) -> &mut Self {
+            for i in values {
+                self.put(lump, &i.to_le_bytes());
+            }
+            self
+        }
+
+        fn put_f32(&mut self, lump: Lump, values: &[f32]) -> &mut Self {
+            for i in values {
+                self.put(lump, &i.to_le_bytes());
+            }
+            self
+        }
+
+        fn build(&self, version: u32) -> Vec<u8> {
+            let mut out = Vec::new();
+            out.extend(version.to_le_bytes());
+            let mut offset = 4 + HEADER_LUMPS * 8;
+            for lump in &self.lumps {
+                out.extend((offset as u32).to_le_bytes());
+                out.extend((lump.len() as u32).to_le_bytes());
+                offset += lump.len();
+            }
+            for lump in &self.lumps {
+                out.extend(lump);
+            }
+            out
+        }
+    }
+
+    fn miptex(name: &[u8], size: u32) -> Vec<u8> {
+        let mut out = vec![0; MIPTEX_NAME_SIZE];
+        out[..name.len()].copy_from_slice(name);
+        out.extend(size.to_le_bytes());
+        out.extend(size.to_le_bytes());
+        let mut offset = MIPTEX_HEADER_SIZE as u32;
+        let mut pixels = Vec::new();
+        for level in 0..MIPLEVELS {
+            out.extend(offset.to_le_bytes());
+            let len = (size >> level) * (size >> level);
+            pixels.extend((0..len).map(|i| (i + level as u32) as u8));
+            offset += len;
+        }
+        out.extend(pixels);
+        out.extend(256_u16.to_le_bytes());
+        out.extend((0..PALETTE_SIZE).map(|i| i as u8));
+        out.extend([0, 0]);
+        out
+    }
+
+    fn map() -> Builder {
+        let mut b = Builder::default();
+        b.put(Lump::Entities, b"{\n\"classname\" \"worldspawn\"\n}\n\0");
+        b.put_f32(Lump::Planes, &[1.0, 0.0, 0.0, 64.0]);
+        b.put_i32(Lump::Planes, &[0]);
+        // node: plane 0, children leaf 1 and leaf 2
+        b.put_i32(Lump::Nodes, &[0]);
+        b.put_i16(Lump::Nodes, &[-2, -3, -64, -64, -64, 64, 64, 64, 0, 0]);
+        b.put_i32(Lump::ClipNodes, &[0]);
+        b.put_i16(Lump::ClipNodes, &[-1, -2]);
+        for (contents, visofs) in [(-2, -1), (-1, 0), (-1, 2)] {
+            b.put_i32(Lump::Leafs, &[contents, visofs]);
+            b.put_i16(Lump::Leafs, &[-64, -64, -64, 64, 64, 64, 0, 0]);
+            b.put(Lump::Leafs, &[0, 0, 0, 0]);
+        }
+        // leaf 1 sees leaf 2, leaf 2 sees nothing
+        b.put(Lump::Visibility, &[0x02, 0x00, 0x00, 0x01]);
+        b.put_f32(
+            Lump::Models,
+            &[-64.0, -64.0, -64.0, 64.0, 64.0, 64.0, 0.0, 0.0, 0.0],
+        );
+        b.put_i32(Lump::Models, &[0, 0, -1, -1, 2, 0, 0]);
+        b.put_f32(Lump::TexInfo, &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        b.put_i32(Lump::TexInfo, &[0, 0]);
+        let tex = miptex(b"TEST", 16);
+        b.put_i32(Lump::Textures, &[2, 12, -1]);
+        b.put(Lump::Textures, &tex);
+        b
+    }
+
+    #[test]
+    fn parse_map() {
+        let data = map().build(30);
+        let bsp = Bsp::parse(&data).unwrap();
+        assert_eq!(bsp.version(), BspVersion::HalfLife);
+        assert_eq!(
+            bsp.entities_str(),
+            Ok("{\n\"classname\" \"worldspawn\"\n}\n")
+        );
+        assert_eq!(bsp.planes().len(), 1);
+        assert_eq!(bsp.planes()[0].dist, 64.0);
+        assert_eq!(
+            bsp.nodes()[0].children,
+            [NodeChild::Leaf(1), NodeChild::Leaf(2)]
+        );
+        assert_eq!(bsp.nodes()[0].maxs, vec3_t::new(64.0, 64.0, 64.0));
+        assert_eq!(
+            bsp.clip_nodes()[0].children,
+            [ClipNodeChild::Contents(-1), ClipNodeChild::Contents(-2)]
+        );
+        assert_eq!(bsp.leafs().len(), 3);
+        assert_eq!(bsp.leafs()[0].vis_offset, None);
+        assert_eq!(bsp.models()[0].vis_leafs, 2);
+        assert_eq!(bsp.tex_info()[0].miptex, 0);
+
+        let tex = bsp.textures()[0].unwrap();
+        assert_eq!(tex.name(), Some("TEST"));
+        assert_eq!((tex.width, tex.height), (16, 16));
+        assert!(!tex.is_external());
+        assert_eq!(tex.mip_level(3), Some(&[3, 4, 5, 6][..]));
+        assert_eq!(tex.palette().map(|p| p.len()), Some(PALETTE_SIZE));
+        assert_eq!(bsp.textures()[1], None);
+    }
+
+    #[test]
+    fn parse_pvs() {
+        let data = map().build(30);
+        let bsp = Bsp::parse(&data).unwrap();
+        let leafs = bsp.leafs();
+        assert_eq!(bsp.leaf_pvs(&leafs[0]), Ok(vec![0xff]));
+        assert_eq!(bsp.leaf_pvs(&leafs[1]), Ok(vec![0x02]));
+        assert_eq!(bsp.leaf_pvs(&leafs[2]), Ok(vec![0x00]));
+        assert_eq!(decompress_vis(&[0x00], 1), Err(BspError::InvalidVisibility));
+        assert_eq!(decompress_vis(&[0x01, 0x00, 0x03], 3), Ok(vec![1, 0, 0]));
+    }
+
+    #[test]
+    fn parse_bsp2() {
+        let mut b = map();
+        b.lumps[Lump::Nodes as usize].clear();
+        b.put_i32(Lump::Nodes, &[0, -2, -3]);
+        b.put_f32(Lump::Nodes, &[-64.0, -64.0, -64.0, 64.0, 64.0, 64.0]);
+        b.put_i32(Lump::Nodes, &[0, 0]);
+        b.lumps[Lump::ClipNodes as usize].clear();
+        b.put_i32(Lump::ClipNodes, &[0, -1, -2]);
+        b.lumps[Lump::Leafs as usize].clear();
+        b.put_i32(Lump::Leafs, &[-2, -1]);
+        b.put_f32(Lump::Leafs, &[-64.0, -64.0, -64.0, 64.0, 64.0, 64.0]);
+        b.put_i32(Lump::Leafs, &[0, 0, 0]);
+        for _ in 0..2 {
+            b.put_i32(Lump::Leafs, &[-1, 0]);
+            b.put_f32(Lump::Leafs, &[-64.0, -64.0, -64.0, 64.0, 64.0, 64.0]);
+            b.put_i32(Lump::Leafs, &[0, 0, 0]);
+        }
+        b.lumps[Lump::Textures as usize].clear();
+        b.put_i32(Lump::Textures, &[0]);
+        b.lumps[Lump::TexInfo as usize].clear();
+
+        let data = b.build(QBSP2_VERSION);
+        let bsp = Bsp::parse(&data).unwrap();
+        assert_eq!(bsp.version(), BspVersion::Bsp2);
+        assert_eq!(
+            bsp.nodes()[0].children,
+            [NodeChild::Leaf(1), NodeChild::Leaf(2)]
+        );
+        assert_eq!(bsp.leafs()[2].maxs, vec3_t::new(64.0, 64.0, 64.0));
+        assert_eq!(bsp.clip_nodes().len(), 1);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let data = map().build(30);
+        assert_eq!(Bsp::parse(&data[..10]).err(), Some(BspError::UnexpectedEnd));
+        assert_eq!(
+            Bsp::parse(&map().build(31)).err(),
+            Some(BspError::UnsupportedVersion(31))
+        );
+        assert_eq!(
+            Bsp::parse(&data[..data.len() - 1]).err(),
+            Some(BspError::LumpOutOfBounds(Lump::Models))
+        );
+
+        let mut b = map();
+        b.put(Lump::Planes, &[0]);
+        assert_eq!(
+            Bsp::parse(&b.build(30)).err(),
+            Some(BspError::InvalidLumpSize(Lump::Planes))
+        );
+
+        let mut b = map();
+        b.lumps[Lump::Nodes as usize][0] = 1;
+        assert_eq!(
+            Bsp::parse(&b.build(30)).err(),
+            Some(BspError::InvalidIndex {
+                lump: Lump::Nodes,
+                index: 0
+            })
+        );
+
+        let mut b = map();
+        b.lumps[Lump::Leafs as usize][28 + 4] = 100;
+        assert_eq!(
+            Bsp::parse(&b.build(30)).err(),
+            Some(BspError::InvalidIndex {
+                lump: Lump::Leafs,
+                index: 1
+            })
+        );
+
+        let mut b = map();
+        let len = b.lumps[Lump::Textures as usize].len();
+        b.lumps[Lump::Textures as usize].truncate(len - 100);
+        assert_eq!(
+            Bsp::parse(&b.build(30)).err(),
+            Some(BspError::InvalidMipTex(0))
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Entity lump parser and writer.
+//!
+//! Parses the entity lump of a BSP file or an `.ent` file into a list of entities with ordered
+//! key/value pairs. [write] produces the same text as map compilers.
+
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::parser::{TokenError, Tokens};
+
+/// Ordered key/value pairs of an entity.
+///
+/// Duplicate keys are preserved.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntityKeys {
+    pairs: Vec<(String, String)>,
+}
+
+impl EntityKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns the value of the first pair with the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.pairs
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn classname(&self) -> Option<&str> {
+        self.get("classname")
+    }
+
+    /// Sets the value of the first pair with the given key or appends a new pair.
+    ///
+    /// Returns the old value.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        let key = key.into();
+        let value = value.into();
+        match self.get_mut(&key) {
+            Some(old) => Some(core::mem::replace(old, value)),
+            None => {
+                self.pairs.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Appends a pair even if the key already exists.
+    pub fn push(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.pairs.push((key.into(), value.into()));
+    }
+
+    /// Removes all pairs with the given key and returns the first value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let mut ret = None;
+        self.pairs.retain_mut(|(k, v)| {
+            if k != key {
+                return true;
+            }
+            if ret.is_none() {
+                ret = Some(core::mem::take(v));
+            }
+            false
+        });
+        ret
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for EntityKeys {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            pairs: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for EntityKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{{")?;
+        for (key, value) in self.iter() {
+            writeln!(f, "\"{key}\" \"{value}\"")?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+fn tokens(src: &str) -> Tokens<'_> {
+    Tokens::new(src)
+        .hash_comments(false)
+        .handle_bracket(false)
+        .handle_colon(false)
+}
+
+/// Parses entities from the entity lump text.
+///
+/// Trailing nul bytes are ignored.
+pub fn parse(src: &str) -> Result<Vec<EntityKeys>, TokenError<'_>> {
+    let mut tokens = tokens(src.trim_end_matches('\0'));
+    let mut entities = Vec::new();
+    loop {
+        match tokens.parse() {
+            Ok("{") => {}
+            Ok(token) => return Err(TokenError::UnexpectedToken(token)),
+            Err(TokenError::UnexpectedEnd) => break,
+            Err(err) => return Err(err),
+        }
+        let mut keys = EntityKeys::new();
+        loop {
+            let key = tokens.parse()?;
+            if key == "}" {
+                break;
+            }
+            let value = tokens.parse()?;
+            if value == "}" {
+                return Err(TokenError::UnexpectedToken(value));
+            }
+            keys.push(key, value);
+        }
+        entities.push(keys);
+    }
+    Ok(entities)
+}
+
+/// Writes entities in the entity lump format.
+///
+/// The output is the same as the lump produced by map compilers without the trailing nul byte.
+pub fn write(out: &mut impl fmt::Write, entities: &[EntityKeys]) -> fmt::Result {
+    entities.iter().try_for_each(|keys| write!(out, "{keys}"))
+}
+
+/// Returns entities in the entity lump format.
+pub fn to_string(entities: &[EntityKeys]) -> String {
+    let mut out = String::new();
+    write(&mut out, entities).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LUMP: &str = "{
+\"wad\" \"\\half-life\\valve\\halflife.wad\"
+\"classname\" \"worldspawn\"
+\"mapversion\" \"220\"
+}
+{
+\"origin\" \"-64 128 36\"
+\"targetname\" \"t1\"
+\"target\" \"t2\"
+\"target\" \"t3\"
+\"message\" \"(1): {value}\"
+\"classname\" \"info_target\"
+}
+";
+
+    #[test]
+    fn parse_lump() {
+        let entities = parse(LUMP).unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].classname(), Some("worldspawn"));
+        assert_eq!(
+            entities[0].get("wad"),
+            Some("\\half-life\\valve\\halflife.wad")
+        );
+        assert_eq!(entities[1].len(), 6);
+        assert_eq!(entities[1].get("target"), Some("t2"));
+        assert_eq!(entities[1].get("message"), Some("(1): {value}"));
+        assert_eq!(entities[1].get("angles"), None);
+    }
+
+    #[test]
+    fn write_lump() {
+        let entities = parse(LUMP).unwrap();
+        assert_eq!(to_string(&entities), LUMP);
+
+        let mut with_nul = String::from(LUMP);
+        with_nul.push('\0');
+        assert_eq!(parse(&with_nul), Ok(entities));
+    }
+
+    #[test]
+    fn edit_keys() {
+        let mut keys: EntityKeys = [
+            ("classname", "info_target"),
+            ("target", "a"),
+            ("target", "b"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(keys.insert("target", "c"), Some("a".into()));
+        assert_eq!(keys.insert("origin", "0 0 0"), None);
+        assert_eq!(keys.remove("target"), Some("c".into()));
+        assert_eq!(
+            keys.iter().collect::<Vec<_>>(),
+            [("classname", "info_target"), ("origin", "0 0 0")]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("\"classname\""),
+            Err(TokenError::UnexpectedToken("classname"))
+        );
+        assert_eq!(
+            parse("{\n\"classname\"\n}"),
+            Err(TokenError::UnexpectedToken("}"))
+        );
+        assert_eq!(
+            parse("{\n\"classname\" \"foo\"\n"),
+            Err(TokenError::UnexpectedEnd)
+        );
+        assert_eq!(
+            parse("{\n\"classname\" \"foo\n}"),
+            Err(TokenError::InvalidData)
+        );
+    }
+}
@@ -23,6 +23,7 @@ pub mod consts;
 pub mod cvar;
 pub mod engine;
 pub mod entity;
+pub mod entity_lump;
 pub mod export;
 pub mod file;
 pub mod global_state;
@@ -36,6 +37,7 @@ pub mod prelude;
 pub mod render;
 pub mod sound;
 pub mod str;
+pub mod studio;
 pub mod user_message;
 pub mod utils;
 
@@ -0,0 +1,894 @@
+//! Studio model (MDL v10) reader.
+//!
+//! [StudioModel] validates tables of the model header and reads items on demand, so it can be
+//! used on a model loaded by the engine without copying.
+
+use core::{fmt, slice, str};
+
+use bitflags::bitflags;
+
+use crate::ffi::common::vec3_t;
+
+/// The model header identifier.
+pub const IDSTUDIOHEADER: [u8; 4] = *b"IDST";
+/// The supported model version.
+pub const STUDIO_VERSION: i32 = 10;
+
+/// Events with numbers starting at this value are handled by the client.
+pub const EVENT_CLIENT: i32 = 5000;
+
+/// The number of bone controllers in entity variables including the mouth.
+pub const MAX_CONTROLLERS: usize = 5;
+/// The bone controller index of the mouth.
+pub const MOUTH_CONTROLLER: i32 = 4;
+
+const NAME_SIZE: usize = 32;
+const LONG_NAME_SIZE: usize = 64;
+
+const HEADER_SIZE: usize = 244;
+const BONE_SIZE: usize = 112;
+const BONE_CONTROLLER_SIZE: usize = 24;
+const HITBOX_SIZE: usize = 32;
+const SEQUENCE_SIZE: usize = 176;
+const EVENT_SIZE: usize = 76;
+const BODY_PART_SIZE: usize = 76;
+const MODEL_SIZE: usize = 112;
+const ATTACHMENT_SIZE: usize = 88;
+
+bitflags! {
+    /// Motion axes of a sequence and a bone controller type.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct MotionFlags: u32 {
+        const X     = 0x0001;
+        const Y     = 0x0002;
+        const Z     = 0x0004;
+        const XR    = 0x0008;
+        const YR    = 0x0010;
+        const ZR    = 0x0020;
+        const LX    = 0x0040;
+        const LY    = 0x0080;
+        const LZ    = 0x0100;
+        const AX    = 0x0200;
+        const AY    = 0x0400;
+        const AZ    = 0x0800;
+        const AXR   = 0x1000;
+        const AYR   = 0x2000;
+        const AZR   = 0x4000;
+        /// The controller value wraps around.
+        const RLOOP = 0x8000;
+
+        const ROTATION = Self::XR.bits() | Self::YR.bits() | Self::ZR.bits();
+    }
+}
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct SequenceFlags: u32 {
+        const LOOPING = 0x0001;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StudioError {
+    /// The data is too short to hold the header.
+    UnexpectedEnd,
+    InvalidId,
+    UnsupportedVersion(i32),
+    /// The table is out of the model bounds.
+    TableOutOfBounds(Table),
+    /// An item references data out of the model bounds.
+    InvalidIndex {
+        table: Table,
+        index: usize,
+    },
+}
+
+impl fmt::Display for StudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "Unexpected end"),
+            Self::InvalidId => write!(f, "Invalid model id"),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported version {version}"),
+            Self::TableOutOfBounds(table) => write!(f, "Table {table} is out of bounds"),
+            Self::InvalidIndex { table, index } => {
+                write!(f, "Invalid reference in table {table} at index {index}")
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Table {
+    Bones,
+    BoneControllers,
+    HitBoxes,
+    Sequences,
+    BodyParts,
+    Attachments,
+}
+
+impl Table {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Bones => "bones",
+            Self::BoneControllers => "bone controllers",
+            Self::HitBoxes => "hitboxes",
+            Self::Sequences => "sequences",
+            Self::BodyParts => "body parts",
+            Self::Attachments => "attachments",
+        }
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.data.len() {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N).and_then(|s| s.try_into().ok())
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    fn vec3(&mut self) -> Option<vec3_t> {
+        Some(vec3_t::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn name(&mut self, len: usize) -> Option<&'a [u8]> {
+        let name = self.bytes(len)?;
+        Some(name.split(|&c| c == 0).next().unwrap_or(name))
+    }
+
+    /// Reads a count and an offset of a table.
+    fn table(&mut self) -> Option<(usize, usize)> {
+        let count = self.i32()?;
+        let offset = self.i32()?;
+        Some((count.max(0) as usize, offset.max(0) as usize))
+    }
+}
+
+fn name_str(name: &[u8]) -> &str {
+    str::from_utf8(name).unwrap_or_default()
+}
+
+/// Returns a slice for `count` items of `size` bytes at `offset`.
+fn table_slice(data: &[u8], offset: usize, count: usize, size: usize) -> Option<&[u8]> {
+    let len = count.checked_mul(size)?;
+    data.get(offset..offset.checked_add(len)?)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bone<'a> {
+    name: &'a [u8],
+    /// The parent bone index or `-1`.
+    pub parent: i32,
+    /// Bone controller indices for each axis or `-1`.
+    pub bone_controller: [i32; 6],
+}
+
+impl<'a> Bone<'a> {
+    fn parse(r: &mut Reader<'a>) -> Option<Self> {
+        let name = r.name(NAME_SIZE)?;
+        let parent = r.i32()?;
+        let _flags = r.i32()?;
+        let mut bone_controller = [0; 6];
+        for i in &mut bone_controller {
+            *i = r.i32()?;
+        }
+        Some(Self {
+            name,
+            parent,
+            bone_controller,
+        })
+    }
+
+    pub fn name(&self) -> &'a str {
+        name_str(self.name)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoneController {
+    pub bone: i32,
+    pub ty: MotionFlags,
+    pub start: f32,
+    pub end: f32,
+    pub rest: i32,
+    /// The index in entity variables, [MOUTH_CONTROLLER] for the mouth.
+    pub index: i32,
+}
+
+impl BoneController {
+    fn parse(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            bone: r.i32()?,
+            ty: MotionFlags::from_bits_retain(r.u32()?),
+            start: r.f32()?,
+            end: r.f32()?,
+            rest: r.i32()?,
+            index: r.i32()?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HitBox {
+    pub bone: i32,
+    pub group: i32,
+    pub bbmin: vec3_t,
+    pub bbmax: vec3_t,
+}
+
+impl HitBox {
+    fn parse(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            bone: r.i32()?,
+            group: r.i32()?,
+            bbmin: r.vec3()?,
+            bbmax: r.vec3()?,
+        })
+    }
+}
+
+/// An animation event.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Event<'a> {
+    pub frame: i32,
+    pub event: i32,
+    pub ty: i32,
+    options: &'a [u8],
+}
+
+impl<'a> Event<'a> {
+    fn parse(r: &mut Reader<'a>) -> Option<Self> {
+        Some(Self {
+            frame: r.i32()?,
+            event: r.i32()?,
+            ty: r.i32()?,
+            options: r.name(LONG_NAME_SIZE)?,
+        })
+    }
+
+    pub fn options(&self) -> &'a str {
+        name_str(self.options)
+    }
+}
+
+/// A sequence description.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sequence<'a> {
+    label: &'a [u8],
+    pub fps: f32,
+    pub flags: SequenceFlags,
+    pub activity: i32,
+    pub activity_weight: i32,
+    events: &'a [u8],
+    pub num_frames: u32,
+    pub motion_type: MotionFlags,
+    pub motion_bone: i32,
+    pub linear_movement: vec3_t,
+    pub bbmin: vec3_t,
+    pub bbmax: vec3_t,
+    pub num_blends: u32,
+    pub blend_type: [MotionFlags; 2],
+    pub blend_start: [f32; 2],
+    pub blend_end: [f32; 2],
+    pub seq_group: i32,
+    pub entry_node: i32,
+    pub exit_node: i32,
+    pub node_flags: i32,
+    pub next_sequence: i32,
+}
+
+impl<'a> Sequence<'a> {
+    fn parse(data: &'a [u8], r: &mut Reader<'a>) -> Option<Self> {
+        let label = r.name(NAME_SIZE)?;
+        let fps = r.f32()?;
+        let flags = SequenceFlags::from_bits_retain(r.u32()?);
+        let activity = r.i32()?;
+        let activity_weight = r.i32()?;
+        let (num_events, event_index) = r.table()?;
+        let events = table_slice(data, event_index, num_events, EVENT_SIZE)?;
+        let num_frames = r.i32()?.max(0) as u32;
+        let (_num_pivots, _pivot_index) = r.table()?;
+        let motion_type = MotionFlags::from_bits_retain(r.u32()?);
+        let motion_bone = r.i32()?;
+        let linear_movement = r.vec3()?;
+        let _automove_pos_index = r.i32()?;
+        let _automove_angle_index = r.i32()?;
+        let bbmin = r.vec3()?;
+        let bbmax = r.vec3()?;
+        let num_blends = r.i32()?.max(0) as u32;
+        let _anim_index = r.i32()?;
+        let blend_type = [
+            MotionFlags::from_bits_retain(r.u32()?),
+            MotionFlags::from_bits_retain(r.u32()?),
+        ];
+        let blend_start = [r.f32()?, r.f32()?];
+        let blend_end = [r.f32()?, r.f32()?];
+        let _blend_parent = r.i32()?;
+        Some(Self {
+            label,
+            fps,
+            flags,
+            activity,
+            activity_weight,
+            events,
+            num_frames,
+            motion_type,
+            motion_bone,
+            linear_movement,
+            bbmin,
+            bbmax,
+            num_blends,
+            blend_type,
+            blend_start,
+            blend_end,
+            seq_group: r.i32()?,
+            entry_node: r.i32()?,
+            exit_node: r.i32()?,
+            node_flags: r.i32()?,
+            next_sequence: r.i32()?,
+        })
+    }
+
+    pub fn label(&self) -> &'a str {
+        name_str(self.label)
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.flags.intersects(SequenceFlags::LOOPING)
+    }
+
+    pub fn num_events(&self) -> usize {
+        self.events.len() / EVENT_SIZE
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = Event<'a>> + use<'a> {
+        self.events
+            .chunks_exact(EVENT_SIZE)
+            .filter_map(|i| Event::parse(&mut Reader::new(i)))
+    }
+
+    /// Returns the frame rate in `frame` units (0-255) per second.
+    pub fn frame_rate(&self) -> f32 {
+        if self.num_frames > 1 {
+            256.0 * self.fps / (self.num_frames - 1) as f32
+        } else {
+            256.0
+        }
+    }
+
+    /// Returns the movement speed of the sequence.
+    pub fn ground_speed(&self) -> f32 {
+        if self.num_frames > 1 {
+            self.linear_movement.length() * self.fps / (self.num_frames - 1) as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BodyPart<'a> {
+    name: &'a [u8],
+    pub num_models: u32,
+    pub base: u32,
+    models: &'a [u8],
+}
+
+impl<'a> BodyPart<'a> {
+    fn parse(data: &'a [u8], r: &mut Reader<'a>) -> Option<Self> {
+        let name = r.name(LONG_NAME_SIZE)?;
+        let num_models = r.i32()?.max(0) as usize;
+        let base = r.i32()?.max(0) as u32;
+        let model_index = r.i32()?.max(0) as usize;
+        let models = table_slice(data, model_index, num_models, MODEL_SIZE)?;
+        Some(Self {
+            name,
+            num_models: num_models as u32,
+            base,
+            models,
+        })
+    }
+
+    pub fn name(&self) -> &'a str {
+        name_str(self.name)
+    }
+
+    /// Returns names of submodels in this body part.
+    pub fn model_names(&self) -> impl Iterator<Item = &'a str> + use<'a> {
+        self.models
+            .chunks_exact(MODEL_SIZE)
+            .filter_map(|i| Reader::new(i).name(LONG_NAME_SIZE))
+            .map(name_str)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Attachment<'a> {
+    name: &'a [u8],
+    pub bone: i32,
+    /// The position relative to the bone.
+    pub origin: vec3_t,
+}
+
+impl<'a> Attachment<'a> {
+    fn parse(r: &mut Reader<'a>) -> Option<Self> {
+        let name = r.name(NAME_SIZE)?;
+        let _ty = r.i32()?;
+        Some(Self {
+            name,
+            bone: r.i32()?,
+            origin: r.vec3()?,
+        })
+    }
+
+    pub fn name(&self) -> &'a str {
+        name_str(self.name)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct TableRef<'a> {
+    data: &'a [u8],
+    size: usize,
+}
+
+impl<'a> TableRef<'a> {
+    fn read(
+        r: &mut Reader,
+        data: &'a [u8],
+        table: Table,
+        size: usize,
+    ) -> Result<Self, StudioError> {
+        let (count, offset) = r.table().ok_or(StudioError::UnexpectedEnd)?;
+        let data =
+            table_slice(data, offset, count, size).ok_or(StudioError::TableOutOfBounds(table))?;
+        Ok(Self { data, size })
+    }
+
+    fn len(&self) -> usize {
+        self.data.len() / self.size
+    }
+
+    fn get(&self, index: usize) -> Option<&'a [u8]> {
+        let start = index.checked_mul(self.size)?;
+        self.data.get(start..start + self.size)
+    }
+
+    fn chunks(&self) -> slice::ChunksExact<'a, u8> {
+        self.data.chunks_exact(self.size)
+    }
+}
+
+/// A studio model.
+#[derive(Copy, Clone, Debug)]
+pub struct StudioModel<'a> {
+    data: &'a [u8],
+    name: &'a [u8],
+    pub eye_position: vec3_t,
+    /// The movement hull of the model.
+    pub min: vec3_t,
+    pub max: vec3_t,
+    /// The clipping bounding box of the model.
+    pub bbmin: vec3_t,
+    pub bbmax: vec3_t,
+    pub flags: u32,
+    bones: TableRef<'a>,
+    bone_controllers: TableRef<'a>,
+    hitboxes: TableRef<'a>,
+    sequences: TableRef<'a>,
+    body_parts: TableRef<'a>,
+    attachments: TableRef<'a>,
+}
+
+impl<'a> StudioModel<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, StudioError> {
+        let mut r = Reader::new(data);
+        if data.len() < HEADER_SIZE {
+            return Err(StudioError::UnexpectedEnd);
+        }
+        if r.array::<4>() != Some(IDSTUDIOHEADER) {
+            return Err(StudioError::InvalidId);
+        }
+        let version = r.i32().ok_or(StudioError::UnexpectedEnd)?;
+        if version != STUDIO_VERSION {
+            return Err(StudioError::UnsupportedVersion(version));
+        }
+
+        // the header size is checked above
+        let name = r.name(LONG_NAME_SIZE).unwrap();
+        let _length = r.i32().unwrap();
+        let eye_position = r.vec3().unwrap();
+        let min = r.vec3().unwrap();
+        let max = r.vec3().unwrap();
+        let bbmin = r.vec3().unwrap();
+        let bbmax = r.vec3().unwrap();
+        let flags = r.u32().unwrap();
+
+        let bones = TableRef::read(&mut r, data, Table::Bones, BONE_SIZE)?;
+        let bone_controllers =
+            TableRef::read(&mut r, data, Table::BoneControllers, BONE_CONTROLLER_SIZE)?;
+        let hitboxes = TableRef::read(&mut r, data, Table::HitBoxes, HITBOX_SIZE)?;
+        let sequences = TableRef::read(&mut r, data, Table::Sequences, SEQUENCE_SIZE)?;
+        let _sequence_groups = r.table().unwrap();
+        let _textures = r.table().unwrap();
+        let _texture_data_index = r.i32().unwrap();
+        let _skins = r.table().unwrap();
+        let _skin_index = r.i32().unwrap();
+        let body_parts = TableRef::read(&mut r, data, Table::BodyParts, BODY_PART_SIZE)?;
+        let attachments = TableRef::read(&mut r, data, Table::Attachments, ATTACHMENT_SIZE)?;
+
+        let ret = Self {
+            data,
+            name,
+            eye_position,
+            min,
+            max,
+            bbmin,
+            bbmax,
+            flags,
+            bones,
+            bone_controllers,
+            hitboxes,
+            sequences,
+            body_parts,
+            attachments,
+        };
+        ret.validate()?;
+        Ok(ret)
+    }
+
+    /// Creates a model from a pointer to the model header loaded by the engine.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be null or point to a loaded studio model that lives for `'a`.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Result<Self, StudioError> {
+        if ptr.is_null() {
+            return Err(StudioError::UnexpectedEnd);
+        }
+        // SAFETY: the caller guarantees that the pointer points to a model header
+        let header = unsafe { slice::from_raw_parts(ptr, HEADER_SIZE) };
+        let length = i32::from_le_bytes(header[72..76].try_into().unwrap());
+        // a negative length must not wrap into a huge slice
+        let length = match usize::try_from(length) {
+            Ok(length) if length >= HEADER_SIZE => length,
+            _ => return Err(StudioError::UnexpectedEnd),
+        };
+        // SAFETY: the model length is stored in the header
+        Self::parse(unsafe { slice::from_raw_parts(ptr, length) })
+    }
+
+    fn validate(&self) -> Result<(), StudioError> {
+        // sequences and body parts reference other data in the model
+        for index in 0..self.sequences.len() {
+            if self.sequence(index).is_none() {
+                let table = Table::Sequences;
+                return Err(StudioError::InvalidIndex { table, index });
+            }
+        }
+        for index in 0..self.body_parts.len() {
+            if self.body_part(index).is_none() {
+                let table = Table::BodyParts;
+                return Err(StudioError::InvalidIndex { table, index });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> &'a str {
+        name_str(self.name)
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn num_bones(&self) -> usize {
+        self.bones.len()
+    }
+
+    pub fn bone(&self, index: usize) -> Option<Bone<'a>> {
+        Bone::parse(&mut Reader::new(self.bones.get(index)?))
+    }
+
+    pub fn bones(&self) -> impl Iterator<Item = Bone<'a>> + use<'a> {
+        self.bones
+            .chunks()
+            .filter_map(|i| Bone::parse(&mut Reader::new(i)))
+    }
+
+    pub fn bone_controllers(&self) -> impl Iterator<Item = BoneController> + use<'a> {
+        self.bone_controllers
+            .chunks()
+            .filter_map(|i| BoneController::parse(&mut Reader::new(i)))
+    }
+
+    /// Returns a bone controller with the given entity variables index.
+    pub fn bone_controller(&self, index: i32) -> Option<BoneController> {
+        self.bone_controllers().find(|i| i.index == index)
+    }
+
+    pub fn hitboxes(&self) -> impl Iterator<Item = HitBox> + use<'a> {
+        self.hitboxes
+            .chunks()
+            .filter_map(|i| HitBox::parse(&mut Reader::new(i)))
+    }
+
+    pub fn num_sequences(&self) -> usize {
+        self.sequences.len()
+    }
+
+    pub fn sequence(&self, index: usize) -> Option<Sequence<'a>> {
+        Sequence::parse(self.data, &mut Reader::new(self.sequences.get(index)?))
+    }
+
+    pub fn sequences(&self) -> impl Iterator<Item = Sequence<'a>> + use<'a> {
+        let data = self.data;
+        self.sequences
+            .chunks()
+            .filter_map(move |i| Sequence::parse(data, &mut Reader::new(i)))
+    }
+
+    /// Returns the index of a sequence with the label ignoring case.
+    pub fn find_sequence(&self, label: &str) -> Option<usize> {
+        self.sequences()
+            .position(|i| i.label().eq_ignore_ascii_case(label))
+    }
+
+    pub fn num_body_parts(&self) -> usize {
+        self.body_parts.len()
+    }
+
+    pub fn body_part(&self, index: usize) -> Option<BodyPart<'a>> {
+        BodyPart::parse(self.data, &mut Reader::new(self.body_parts.get(index)?))
+    }
+
+    pub fn body_parts(&self) -> impl Iterator<Item = BodyPart<'a>> + use<'a> {
+        let data = self.data;
+        self.body_parts
+            .chunks()
+            .filter_map(move |i| BodyPart::parse(data, &mut Reader::new(i)))
+    }
+
+    pub fn num_attachments(&self) -> usize {
+        self.attachments.len()
+    }
+
+    pub fn attachment(&self, index: usize) -> Option<Attachment<'a>> {
+        Attachment::parse(&mut Reader::new(self.attachments.get(index)?))
+    }
+
+    pub fn attachments(&self) -> impl Iterator<Item = Attachment<'a>> + use<'a> {
+        self.attachments
+            .chunks()
+            .filter_map(|i| Attachment::parse(&mut Reader::new(i)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn put_name(out: &mut Vec<u8>, name: &str, size: usize) {
+        let start = out.len();
+        out.extend(name.as_bytes());
+        out.resize(start + size, 0);
+    }
+
+    fn put_i32(out: &mut Vec<u8>, values: &[i32]) {
+        for i in values {
+            out.extend(i.to_le_bytes());
+        }
+    }
+
+    fn put_f32(out: &mut Vec<u8>, values: &[f32]) {
+        for i in values {
+            out.extend(i.to_le_bytes());
+        }
+    }
+
+    fn set_i32(out: &mut [u8], offset: usize, value: i32) {
+        out[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Builds a model with one bone controller, two sequences, one body part and one attachment.
+    fn model() -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(IDSTUDIOHEADER);
+        put_i32(&mut out, &[STUDIO_VERSION]);
+        put_name(&mut out, "test.mdl", LONG_NAME_SIZE);
+        put_i32(&mut out, &[0]);
+        put_f32(&mut out, &[0.0, 0.0, 64.0]);
+        put_f32(&mut out, &[-16.0, -16.0, 0.0, 16.0, 16.0, 72.0]);
+        put_f32(&mut out, &[0.0; 6]);
+        put_i32(&mut out, &[0]);
+        out.resize(HEADER_SIZE, 0);
+
+        // bone controller
+        let controllers = out.len();
+        put_i32(&mut out, &[0, MotionFlags::YR.bits() as i32]);
+        put_f32(&mut out, &[-90.0, 90.0]);
+        put_i32(&mut out, &[0, 0]);
+
+        // events
+        let events = out.len();
+        for (frame, event) in [(2, 1), (5, EVENT_CLIENT)] {
+            put_i32(&mut out, &[frame, event, 0]);
+            put_name(&mut out, "opt", LONG_NAME_SIZE);
+        }
+
+        // sequences
+        let sequences = out.len();
+        for (label, activity, events_count, looping) in [("idle", 1, 0, 1), ("walk", 3, 2, 0)] {
+            let start = out.len();
+            put_name(&mut out, label, NAME_SIZE);
+            put_f32(&mut out, &[10.0]);
+            put_i32(
+                &mut out,
+                &[looping, activity, 1, events_count, events as i32, 11],
+            );
+            out.resize(start + 76, 0);
+            put_f32(&mut out, &[50.0, 0.0, 0.0]);
+            out.resize(start + SEQUENCE_SIZE, 0);
+        }
+
+        // submodels
+        let models = out.len();
+        for name in ["body0", "body1"] {
+            let start = out.len();
+            put_name(&mut out, name, LONG_NAME_SIZE);
+            out.resize(start + MODEL_SIZE, 0);
+        }
+
+        // body part
+        let body_parts = out.len();
+        put_name(&mut out, "body", LONG_NAME_SIZE);
+        put_i32(&mut out, &[2, 1, models as i32]);
+
+        // attachment
+        let attachments = out.len();
+        put_name(&mut out, "muzzle", NAME_SIZE);
+        put_i32(&mut out, &[0, 0]);
+        put_f32(&mut out, &[1.0, 2.0, 3.0]);
+        out.resize(attachments + ATTACHMENT_SIZE, 0);
+
+        let len = out.len() as i32;
+        set_i32(&mut out, 72, len);
+        set_i32(&mut out, 148, 1);
+        set_i32(&mut out, 152, controllers as i32);
+        set_i32(&mut out, 164, 2);
+        set_i32(&mut out, 168, sequences as i32);
+        set_i32(&mut out, 204, 1);
+        set_i32(&mut out, 208, body_parts as i32);
+        set_i32(&mut out, 212, 1);
+        set_i32(&mut out, 216, attachments as i32);
+        out
+    }
+
+    #[test]
+    fn parse() {
+        let data = model();
+        let model = StudioModel::parse(&data).unwrap();
+        assert_eq!(model.name(), "test.mdl");
+        assert_eq!(model.eye_position, vec3_t::new(0.0, 0.0, 64.0));
+        assert_eq!(model.num_bones(), 0);
+
+        let controller = model.bone_controller(0).unwrap();
+        assert_eq!(controller.ty, MotionFlags::YR);
+        assert_eq!((controller.start, controller.end), (-90.0, 90.0));
+        assert!(model.bone_controller(1).is_none());
+
+        assert_eq!(model.num_sequences(), 2);
+        assert_eq!(model.find_sequence("WALK"), Some(1));
+        assert_eq!(model.find_sequence("run"), None);
+        let idle = model.sequence(0).unwrap();
+        assert!(idle.is_looping());
+        assert_eq!(idle.num_events(), 0);
+        let walk = model.sequence(1).unwrap();
+        assert_eq!(walk.activity, 3);
+        assert_eq!(walk.frame_rate(), 256.0);
+        assert_eq!(walk.ground_speed(), 50.0);
+        let events = walk.events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].frame, events[0].event), (2, 1));
+        assert_eq!(events[0].options(), "opt");
+
+        let body = model.body_part(0).unwrap();
+        assert_eq!((body.name(), body.num_models, body.base), ("body", 2, 1));
+        assert_eq!(body.model_names().collect::<Vec<_>>(), ["body0", "body1"]);
+
+        let attachment = model.attachment(0).unwrap();
+        assert_eq!(attachment.name(), "muzzle");
+        assert_eq!(attachment.origin, vec3_t::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn errors() {
+        let data = model();
+        assert_eq!(
+            StudioModel::parse(&data[..100]).unwrap_err(),
+            StudioError::UnexpectedEnd
+        );
+
+        let mut bad = data.clone();
+        bad[0] = b'X';
+        assert_eq!(
+            StudioModel::parse(&bad).unwrap_err(),
+            StudioError::InvalidId
+        );
+
+        let mut bad = data.clone();
+        set_i32(&mut bad, 4, 6);
+        assert_eq!(
+            StudioModel::parse(&bad).unwrap_err(),
+            StudioError::UnsupportedVersion(6)
+        );
+
+        let mut bad = data.clone();
+        set_i32(&mut bad, 164, 100);
+        assert_eq!(
+            StudioModel::parse(&bad).unwrap_err(),
+            StudioError::TableOutOfBounds(Table::Sequences)
+        );
+
+        let mut bad = data.clone();
+        set_i32(&mut bad, 208, data.len() as i32 - 4);
+        assert_eq!(
+            StudioModel::parse(&bad).unwrap_err(),
+            StudioError::TableOutOfBounds(Table::BodyParts)
+        );
+    }
+
+    #[test]
+    fn from_ptr_length() {
+        let data = model();
+        let model = unsafe { StudioModel::from_ptr(data.as_ptr()) }.unwrap();
+        assert_eq!(model.num_sequences(), 2);
+
+        for length in [-1, 0, HEADER_SIZE as i32 - 1] {
+            let mut bad = data.clone();
+            set_i32(&mut bad, 72, length);
+            assert_eq!(
+                unsafe { StudioModel::from_ptr(bad.as_ptr()) }.unwrap_err(),
+                StudioError::UnexpectedEnd
+            );
+        }
+    }
+}
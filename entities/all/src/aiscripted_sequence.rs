@@ -0,0 +1,7 @@
+pub type AiScriptedSequence = crate::scripted_sequence::ScriptedSequence;
+
+define_export! {
+    export_aiscripted_sequence as export if "aiscripted-sequence" {
+        aiscripted_sequence = aiscripted_sequence::AiScriptedSequence,
+    }
+}
@@ -1,8 +1,10 @@
 use bitflags::bitflags;
 use xash3d_server::{
+    damage,
     engine::TraceIgnore,
     entity::{
-        create_entity, delegate_entity, BaseEntity, Effects, KeyValue, MoveType, Solid, UseType,
+        create_entity, delegate_entity, BaseEntity, DamageFlags, Effects, KeyValue, MoveType,
+        Solid, UseType,
     },
     ffi::common::vec3_t,
     prelude::*,
@@ -100,7 +102,17 @@ impl Entity for Explosion {
         engine.msg_pas(v.origin(), &msg);
 
         if !sf.intersects(SpawnFlags::NO_DAMAGE) {
-            warn!("{}: damage is not implemented yet", self.pretty_name());
+            let damage = self.magnitude as f32;
+            let src = v.origin();
+            damage::radius_damage(
+                &engine,
+                src,
+                v,
+                Some(v),
+                damage,
+                damage * 2.5,
+                DamageFlags::BLAST,
+            );
         }
 
         if !sf.intersects(SpawnFlags::NO_SPARKS) {
@@ -0,0 +1,175 @@
+use bitflags::bitflags;
+use xash3d_server::{
+    entities::point_entity::PointEntity,
+    entity::{BaseEntity, KeyValue, UseType, delegate_entity},
+    global_state::EntityState,
+    prelude::*,
+    private::impl_private,
+    str::MapString,
+};
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        /// Set the initial state on spawn if the global state does not exist yet.
+        const SET = 1 << 0;
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+enum TriggerMode {
+    #[default]
+    Off = 0,
+    On,
+    Dead,
+    Toggle,
+}
+
+impl TriggerMode {
+    /// Returns a new state of the global entity.
+    fn next_state(self, state: EntityState) -> EntityState {
+        match self {
+            Self::Off => EntityState::Off,
+            Self::On => EntityState::On,
+            Self::Dead => EntityState::Dead,
+            Self::Toggle => match state {
+                EntityState::On => EntityState::Off,
+                EntityState::Off => EntityState::On,
+                EntityState::Dead => EntityState::Dead,
+            },
+        }
+    }
+}
+
+/// Sets the state of a global entity that persists across level changes.
+///
+/// The state can be checked by `multisource` and `trigger_auto` with the `globalstate` key.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct EnvGlobal {
+    base: PointEntity,
+    global_state_name: Option<MapString>,
+    trigger_mode: TriggerMode,
+    initial_state: EntityState,
+}
+
+impl CreateEntity for EnvGlobal {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: PointEntity::create(base),
+            global_state_name: None,
+            trigger_mode: TriggerMode::Off,
+            initial_state: EntityState::Off,
+        }
+    }
+}
+
+impl EnvGlobal {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+}
+
+impl Entity for EnvGlobal {
+    delegate_entity!(base not { key_value, spawn, used });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        match data.key_name().to_bytes() {
+            b"globalstate" => {
+                self.global_state_name = Some(self.engine().new_map_string(data.value()));
+            }
+            b"triggermode" => {
+                self.trigger_mode = match data.value().to_bytes() {
+                    b"0" => TriggerMode::Off,
+                    b"1" => TriggerMode::On,
+                    b"2" => TriggerMode::Dead,
+                    _ => TriggerMode::Toggle,
+                };
+            }
+            b"initialstate" => {
+                self.initial_state = match data.value().to_bytes() {
+                    b"1" => EntityState::On,
+                    b"2" => EntityState::Dead,
+                    _ => EntityState::Off,
+                };
+            }
+            _ => return self.base.key_value(data),
+        }
+        data.set_handled(true);
+    }
+
+    fn spawn(&mut self) {
+        let Some(name) = self.global_state_name else {
+            self.remove_from_world();
+            return;
+        };
+
+        if self.spawn_flags().intersects(SpawnFlags::SET) {
+            let global_state = self.global_state();
+            let mut entities = global_state.entities_mut();
+            if entities.find(name).is_none() {
+                let Some(map_name) = self.engine().globals.map_name() else {
+                    error!("{}: map name is not set", self.pretty_name());
+                    return;
+                };
+                entities.add(name, map_name, self.initial_state);
+            }
+        }
+    }
+
+    fn used(&self, _: UseType, _: Option<&dyn Entity>, _: &dyn Entity) {
+        let Some(name) = self.global_state_name else {
+            return;
+        };
+
+        let global_state = self.global_state();
+        let mut entities = global_state.entities_mut();
+        let ent = entities.find_mut(name);
+        let old_state = ent.as_ref().map_or(EntityState::Off, |ent| ent.state());
+        let new_state = self.trigger_mode.next_state(old_state);
+
+        trace!(
+            "{}: {name} {old_state:?} -> {new_state:?}",
+            self.pretty_name()
+        );
+
+        if let Some(ent) = ent {
+            ent.set_state(new_state);
+        } else {
+            let Some(map_name) = self.engine().globals.map_name() else {
+                error!("{}: map name is not set", self.pretty_name());
+                return;
+            };
+            entities.add(name, map_name, new_state);
+        }
+    }
+}
+
+impl_private!(EnvGlobal {});
+
+define_export! {
+    export_env_global as export if "env-global" {
+        env_global = env_global::EnvGlobal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_state() {
+        use EntityState::*;
+
+        for state in [Off, On, Dead] {
+            assert_eq!(TriggerMode::Off.next_state(state), Off);
+            assert_eq!(TriggerMode::On.next_state(state), On);
+            assert_eq!(TriggerMode::Dead.next_state(state), Dead);
+        }
+
+        assert_eq!(TriggerMode::Toggle.next_state(Off), On);
+        assert_eq!(TriggerMode::Toggle.next_state(On), Off);
+        assert_eq!(TriggerMode::Toggle.next_state(Dead), Dead);
+    }
+}
@@ -0,0 +1,99 @@
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const FIRE_ONCE = 1 << 0;
+        const RESET     = 1 << 1;
+    }
+}
+
+/// Counts up or down and fires targets when the limit is hit.
+///
+/// The counter value is stored in `frags`, the limit in `health` and the initial value
+/// in `dmg`.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GameCounter {
+    base: RuleEntity,
+}
+
+impl CreateEntity for GameCounter {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+        }
+    }
+}
+
+impl GameCounter {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn count_value(&self) -> i32 {
+        self.vars().frags() as i32
+    }
+
+    fn set_count_value(&self, value: i32) {
+        self.vars().set_frags(value as f32);
+    }
+
+    fn limit_value(&self) -> i32 {
+        self.vars().health() as i32
+    }
+
+    fn reset_count(&self) {
+        let v = self.vars();
+        v.set_frags(v.damage());
+    }
+}
+
+impl Entity for GameCounter {
+    delegate_entity!(base not { spawn, used });
+
+    fn spawn(&mut self) {
+        // save off the initial count
+        let v = self.vars();
+        v.set_damage(v.frags());
+        self.base.spawn();
+    }
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        match use_type {
+            UseType::On | UseType::Toggle => self.set_count_value(self.count_value() + 1),
+            UseType::Off => self.set_count_value(self.count_value() - 1),
+            UseType::Set(value) => self.set_count_value(value as i32),
+        }
+
+        if self.count_value() == self.limit_value() {
+            utils::use_targets(UseType::Toggle, activator, self);
+            let sf = self.spawn_flags();
+            if sf.intersects(SpawnFlags::FIRE_ONCE) {
+                self.remove_from_world();
+            }
+            if sf.intersects(SpawnFlags::RESET) {
+                self.reset_count();
+            }
+        }
+    }
+}
+
+impl_private!(GameCounter {});
+
+define_export! {
+    export_game_counter as export if "game-counter" {
+        game_counter = game_counter::GameCounter,
+    }
+}
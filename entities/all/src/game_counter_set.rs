@@ -0,0 +1,61 @@
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const FIRE_ONCE = 1 << 0;
+    }
+}
+
+/// Sets the value of `game_counter` targets to `frags`.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GameCounterSet {
+    base: RuleEntity,
+}
+
+impl CreateEntity for GameCounterSet {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+        }
+    }
+}
+
+impl GameCounterSet {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+}
+
+impl Entity for GameCounterSet {
+    delegate_entity!(base not { used });
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        let value = self.vars().frags();
+        utils::use_targets(UseType::Set(value), activator, self);
+
+        if self.spawn_flags().intersects(SpawnFlags::FIRE_ONCE) {
+            self.remove_from_world();
+        }
+    }
+}
+
+impl_private!(GameCounterSet {});
+
+define_export! {
+    export_game_counter_set as export if "game-counter-set" {
+        game_counter_set = game_counter_set::GameCounterSet,
+    }
+}
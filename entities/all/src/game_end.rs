@@ -0,0 +1,40 @@
+use xash3d_server::{
+    entity::{BaseEntity, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+};
+
+use crate::game_rule::RuleEntity;
+
+/// Ends the multiplayer game.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GameEnd {
+    base: RuleEntity,
+}
+
+impl CreateEntity for GameEnd {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+        }
+    }
+}
+
+impl Entity for GameEnd {
+    delegate_entity!(base not { used });
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+        self.global_state().game_rules().end_multiplayer_game();
+    }
+}
+
+impl_private!(GameEnd {});
+
+define_export! {
+    export_game_end as export if "game-end" {
+        game_end = game_end::GameEnd,
+    }
+}
@@ -0,0 +1,129 @@
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use xash3d_server::{
+    csz::CStrArray,
+    entity::{BaseEntity, KeyValue, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    str::MapString,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+const MAX_EQUIP: usize = 32;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        /// Equip only when used, not touched.
+        const USE_ONLY = 1 << 0;
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+struct Equipment {
+    name: Option<MapString>,
+    count: u32,
+}
+
+/// Gives items to the player.
+///
+/// Each unknown key is an item class name and the value is a number of items.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GamePlayerEquip {
+    base: RuleEntity,
+    items: Vec<Equipment>,
+}
+
+impl CreateEntity for GamePlayerEquip {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+            items: Vec::new(),
+        }
+    }
+}
+
+impl GamePlayerEquip {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn equip_player(&self, entity: &dyn Entity) {
+        let Some(player) = entity.as_player() else {
+            return;
+        };
+        for item in &self.items {
+            let Some(name) = item.name else { continue };
+            for _ in 0..item.count {
+                player.give_named_item(&name);
+            }
+        }
+    }
+}
+
+impl Entity for GamePlayerEquip {
+    delegate_entity!(base not { key_value, touched, used });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        self.base.key_value(data);
+        if data.handled() {
+            return;
+        }
+
+        // entity variables are parsed by the engine
+        if let b"origin" | b"angles" | b"targetname" | b"target" | b"spawnflags" =
+            data.key_name().to_bytes()
+        {
+            return;
+        }
+
+        if self.items.len() >= MAX_EQUIP {
+            warn!("{}: too many items", self.pretty_name());
+            return;
+        }
+
+        let mut tmp = CStrArray::<128>::new();
+        match utils::strip_token(data.key_name().into(), &mut tmp) {
+            Ok(()) => {
+                let name = self.engine().new_map_string(&tmp);
+                let count = data.parse_or_default::<u32>().max(1);
+                self.items.push(Equipment {
+                    name: Some(name),
+                    count,
+                });
+                data.set_handled(true);
+            }
+            Err(_) => {
+                let name = self.pretty_name();
+                error!("{name}: failed to strip token {:?}", data.key_name());
+            }
+        }
+    }
+
+    fn touched(&self, other: &dyn Entity) {
+        if !self.base.can_fire_for_activator(Some(other)) {
+            return;
+        }
+        if self.spawn_flags().intersects(SpawnFlags::USE_ONLY) {
+            return;
+        }
+        self.equip_player(other);
+    }
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if let Some(activator) = activator {
+            self.equip_player(activator);
+        }
+    }
+}
+
+impl_private!(GamePlayerEquip {});
+
+define_export! {
+    export_game_player_equip as export if "game-player-equip" {
+        game_player_equip = game_player_equip::GamePlayerEquip,
+    }
+}
@@ -0,0 +1,70 @@
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, DamageFlags, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const FIRE_ONCE = 1 << 0;
+    }
+}
+
+/// Hurts the activator by `dmg` or heals it if the damage is negative.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GamePlayerHurt {
+    base: RuleEntity,
+}
+
+impl CreateEntity for GamePlayerHurt {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+        }
+    }
+}
+
+impl GamePlayerHurt {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+}
+
+impl Entity for GamePlayerHurt {
+    delegate_entity!(base not { used });
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        if let Some(player) = activator.filter(|i| i.is_player()) {
+            let v = self.vars();
+            let damage = v.damage();
+            if damage < 0.0 {
+                player.take_health(-damage, DamageFlags::GENERIC);
+            } else {
+                player.take_damage(damage, DamageFlags::GENERIC, v, Some(v));
+            }
+        }
+
+        utils::use_targets(use_type, activator, self);
+
+        if self.spawn_flags().intersects(SpawnFlags::FIRE_ONCE) {
+            self.remove_from_world();
+        }
+    }
+}
+
+impl_private!(GamePlayerHurt {});
+
+define_export! {
+    export_game_player_hurt as export if "game-player-hurt" {
+        game_player_hurt = game_player_hurt::GamePlayerHurt,
+    }
+}
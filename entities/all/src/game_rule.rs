@@ -0,0 +1,45 @@
+use xash3d_server::{
+    entity::{BaseEntity, Effects, KeyValue, MoveType, Solid, delegate_entity},
+    prelude::*,
+    str::MapString,
+    utils,
+};
+
+/// Base type for game logic entities.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct RuleEntity {
+    base: BaseEntity,
+    master: Option<MapString>,
+}
+
+impl CreateEntity for RuleEntity {
+    fn create(base: BaseEntity) -> Self {
+        Self { base, master: None }
+    }
+}
+
+impl RuleEntity {
+    pub fn can_fire_for_activator(&self, activator: Option<&dyn Entity>) -> bool {
+        utils::is_master_triggered(&self.engine(), self.master, activator)
+    }
+}
+
+impl Entity for RuleEntity {
+    delegate_entity!(base not { key_value, spawn });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        if data.key_name() == c"master" {
+            self.master = Some(self.engine().new_map_string(data.value()));
+            data.set_handled(true);
+        } else {
+            self.base.key_value(data);
+        }
+    }
+
+    fn spawn(&mut self) {
+        let v = self.vars();
+        v.set_solid(Solid::Not);
+        v.set_move_type(MoveType::None);
+        v.set_effects(Effects::NODRAW);
+    }
+}
@@ -0,0 +1,178 @@
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, KeyValue, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+};
+
+use crate::game_rule::RuleEntity;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        /// Allow a negative score.
+        const NEGATIVE  = 1 << 0;
+        /// Award points to the team.
+        const TEAM      = 1 << 1;
+    }
+}
+
+/// Awards points to the activator.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GameScore {
+    base: RuleEntity,
+    points: i32,
+}
+
+impl CreateEntity for GameScore {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+            points: 0,
+        }
+    }
+}
+
+impl GameScore {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+}
+
+impl Entity for GameScore {
+    delegate_entity!(base not { key_value, used });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        if data.key_name() == c"points" {
+            self.points = data.parse_or_default();
+            data.set_handled(true);
+        } else {
+            self.base.key_value(data);
+        }
+    }
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        let Some(player) = activator.and_then(|i| i.as_player()) else {
+            return;
+        };
+
+        let sf = self.spawn_flags();
+        let allow_negative = sf.intersects(SpawnFlags::NEGATIVE);
+        let engine = self.engine();
+        let game_rules = self.global_state().game_rules();
+        if !sf.intersects(SpawnFlags::TEAM) {
+            game_rules.add_points(player, self.points, allow_negative);
+            return;
+        }
+
+        // only teammates get points, the activator does not
+        let handle = player.entity_handle();
+        let team = game_rules.get_team_id(player.as_entity());
+        for i in engine.players().filter_map(|i| i.as_player()) {
+            if i.entity_handle() == handle {
+                continue;
+            }
+            if team.is_some() && game_rules.get_team_id(i.as_entity()) == team {
+                game_rules.add_points(i, self.points, allow_negative);
+            }
+        }
+    }
+}
+
+impl_private!(GameScore {});
+
+define_export! {
+    export_game_score as export if "game-score" {
+        game_score = game_score::GameScore,
+    }
+}
+
+#[cfg(all(test, feature = "save", feature = "player"))]
+mod tests {
+    use core::ffi::CStr;
+
+    use xash3d_server::{
+        entity::{Entity, EntityPlayer, UseType},
+        ffi::common::vec3_t,
+        game_rules::GameRules,
+        prelude::*,
+        str::MapString,
+        testing,
+        time::MapTime,
+    };
+
+    use crate::player::Player;
+
+    use super::{GameScore, SpawnFlags};
+
+    struct TeamRules {
+        engine: ServerEngineRef,
+    }
+
+    impl GameRules for TeamRules {
+        fn engine(&self) -> ServerEngineRef {
+            self.engine
+        }
+
+        fn get_game_description(&self) -> &'static CStr {
+            c"Team test"
+        }
+
+        fn can_have_item(&self, _: &dyn EntityPlayer, _: &dyn Entity) -> bool {
+            false
+        }
+
+        fn player_got_item(&self, _: &dyn EntityPlayer, _: &dyn Entity) {}
+
+        fn item_respawn(&self, _: &dyn Entity) -> Option<(MapTime, vec3_t)> {
+            None
+        }
+
+        fn get_team_id(&self, entity: &dyn Entity) -> Option<MapString> {
+            let team = match entity.vars().team() {
+                1 => c"red",
+                2 => c"blue",
+                _ => return None,
+            };
+            Some(self.engine.new_map_string(team))
+        }
+    }
+
+    #[test]
+    fn team_points() {
+        let test = testing::lock();
+        let engine = test.engine();
+        test.set_max_clients(testing::MAX_EDICTS as i32 - 1);
+        test.global_state().set_game_rules(TeamRules { engine });
+
+        let players = [1, 1, 2, 0].map(|team| {
+            let player = engine.new_entity::<Player>().build();
+            player.vars().set_team(team);
+            &*player
+        });
+        let score = engine.new_entity::<GameScore>().build();
+        score.points = 3;
+        score.vars().set_spawn_flags(SpawnFlags::TEAM.bits());
+
+        let activator = players[0];
+        score.used(UseType::Toggle, Some(activator), activator);
+        let frags = players.map(|i| i.vars().frags());
+        // the teammate gets points, the activator and other teams do not
+        assert_eq!(frags, [0.0, 3.0, 0.0, 0.0]);
+
+        score.vars().set_spawn_flags(0);
+        score.used(UseType::Toggle, Some(activator), activator);
+        assert_eq!(activator.vars().frags(), 3.0);
+
+        unsafe {
+            engine.remove_entity_now(score.vars());
+            for player in players {
+                engine.remove_entity_now(player.vars());
+            }
+        }
+    }
+}
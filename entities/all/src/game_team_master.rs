@@ -0,0 +1,132 @@
+use core::cell::Cell;
+
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, KeyValue, ObjectCaps, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const FIRE_ONCE = 1 << 0;
+        const ANY_TEAM  = 1 << 1;
+    }
+}
+
+/// Fires targets only if the activator is in the specified team.
+///
+/// Can be used as a master.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GameTeamMaster {
+    base: RuleEntity,
+    /// A team index or `-1` for no team.
+    team_index: Cell<i32>,
+    trigger_type: UseType,
+}
+
+impl CreateEntity for GameTeamMaster {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+            team_index: Cell::new(-1),
+            trigger_type: UseType::On,
+        }
+    }
+}
+
+impl GameTeamMaster {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn team_match(&self, activator: Option<&dyn Entity>) -> bool {
+        let team_index = self.team_index.get();
+        if team_index < 0 && self.spawn_flags().intersects(SpawnFlags::ANY_TEAM) {
+            return true;
+        }
+
+        let Some(activator) = activator else {
+            return false;
+        };
+
+        let game_rules = self.global_state().game_rules();
+        // everyone matches unless it is teamplay
+        if !game_rules.is_teamplay() {
+            return true;
+        }
+
+        let Ok(team_index) = usize::try_from(team_index) else {
+            return false;
+        };
+        game_rules
+            .get_team_id(activator)
+            .and_then(|team| game_rules.get_team_index(&team))
+            == Some(team_index)
+    }
+}
+
+impl Entity for GameTeamMaster {
+    delegate_entity!(base not { object_caps, key_value, used, is_triggered });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base.object_caps().union(ObjectCaps::MASTER)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        match data.key_name().to_bytes() {
+            b"teamindex" => self.team_index.set(data.parse_or(-1)),
+            b"triggerstate" => {
+                self.trigger_type = match data.parse_or_default() {
+                    0 => UseType::Off,
+                    2 => UseType::Toggle,
+                    _ => UseType::On,
+                };
+            }
+            _ => return self.base.key_value(data),
+        }
+        data.set_handled(true);
+    }
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        if let UseType::Set(value) = use_type {
+            let team_index = if value < 0.0 {
+                None
+            } else {
+                let game_rules = self.global_state().game_rules();
+                activator
+                    .and_then(|i| game_rules.get_team_id(i))
+                    .and_then(|team| game_rules.get_team_index(&team))
+            };
+            self.team_index.set(team_index.map_or(-1, |i| i as i32));
+            return;
+        }
+
+        if self.team_match(activator) {
+            utils::use_targets(self.trigger_type, activator, self);
+            if self.spawn_flags().intersects(SpawnFlags::FIRE_ONCE) {
+                self.remove_from_world();
+            }
+        }
+    }
+
+    fn is_triggered(&self, activator: Option<&dyn Entity>) -> bool {
+        self.team_match(activator)
+    }
+}
+
+impl_private!(GameTeamMaster {});
+
+define_export! {
+    export_game_team_master as export if "game-team-master" {
+        game_team_master = game_team_master::GameTeamMaster,
+    }
+}
@@ -0,0 +1,67 @@
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const FIRE_ONCE     = 1 << 0;
+        const CLEAR_TEAM    = 1 << 1;
+    }
+}
+
+/// Sets the team of `game_team_master` targets to the activator team.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GameTeamSet {
+    base: RuleEntity,
+}
+
+impl CreateEntity for GameTeamSet {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+        }
+    }
+}
+
+impl GameTeamSet {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+}
+
+impl Entity for GameTeamSet {
+    delegate_entity!(base not { used });
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        let sf = self.spawn_flags();
+        let value = if sf.intersects(SpawnFlags::CLEAR_TEAM) {
+            -1.0
+        } else {
+            0.0
+        };
+        utils::use_targets(UseType::Set(value), activator, self);
+
+        if sf.intersects(SpawnFlags::FIRE_ONCE) {
+            self.remove_from_world();
+        }
+    }
+}
+
+impl_private!(GameTeamSet {});
+
+define_export! {
+    export_game_team_set as export if "game-team-set" {
+        game_team_set = game_team_set::GameTeamSet,
+    }
+}
@@ -0,0 +1,158 @@
+use core::fmt::Write;
+
+use bitflags::bitflags;
+use xash3d_server::{
+    color::RGBA,
+    csz::CStrArray,
+    entity::{BaseEntity, KeyValue, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    user_message::TextMessage,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const ALL_PLAYERS = 1 << 0;
+    }
+}
+
+fn parse_color(s: &str) -> [u8; 4] {
+    let mut rgba = [0; 4];
+    for (dst, src) in rgba.iter_mut().zip(s.split_ascii_whitespace()) {
+        *dst = src.parse().unwrap_or(0);
+    }
+    rgba
+}
+
+/// Displays a text message on the HUD.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GameText {
+    base: RuleEntity,
+    channel: u8,
+    x: f32,
+    y: f32,
+    effect: u8,
+    color: [u8; 4],
+    color2: [u8; 4],
+    fade_in: f32,
+    fade_out: f32,
+    hold_time: f32,
+    fx_time: f32,
+}
+
+impl CreateEntity for GameText {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+            channel: 0,
+            x: -1.0,
+            y: -1.0,
+            effect: 0,
+            color: RGBA::WHITE.to_bytes(),
+            color2: RGBA::WHITE.to_bytes(),
+            fade_in: 0.0,
+            fade_out: 0.0,
+            hold_time: 0.0,
+            fx_time: 0.0,
+        }
+    }
+}
+
+impl GameText {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn text_message(&self) -> Option<TextMessage> {
+        let message = self.vars().message()?;
+        let mut text_message = CStrArray::new();
+        if write!(text_message.cursor(), "{message}").is_err() {
+            warn!("{}: message is too long", self.pretty_name());
+        }
+        Some(TextMessage {
+            channel: self.channel,
+            x: self.x.into(),
+            y: self.y.into(),
+            effect: match self.effect {
+                1 => TextMessage::EFFECT_FLICKERY,
+                2 => TextMessage::EFFECT_WRITE_OUT,
+                _ => TextMessage::EFFECT_FADE_IN_OUT,
+            },
+            text_color: RGBA::from_bytes(self.color),
+            effect_color: RGBA::from_bytes(self.color2),
+            fade_in: self.fade_in.into(),
+            fade_out: self.fade_out.into(),
+            hold_time: self.hold_time.into(),
+            fx_time: self.fx_time.into(),
+            text_message,
+        })
+    }
+}
+
+impl Entity for GameText {
+    delegate_entity!(base not { key_value, used });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        match data.key_name().to_bytes() {
+            b"channel" => self.channel = data.parse_or_default(),
+            b"x" => self.x = data.parse_or_default(),
+            b"y" => self.y = data.parse_or_default(),
+            b"effect" => self.effect = data.parse_or_default(),
+            b"color" => self.color = parse_color(data.value().to_str().unwrap_or("")),
+            b"color2" => self.color2 = parse_color(data.value().to_str().unwrap_or("")),
+            b"fadein" => self.fade_in = data.parse_or_default(),
+            b"fadeout" => self.fade_out = data.parse_or_default(),
+            b"holdtime" => self.hold_time = data.parse_or_default(),
+            b"fxtime" => self.fx_time = data.parse_or_default(),
+            _ => return self.base.key_value(data),
+        }
+        data.set_handled(true);
+    }
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        let engine = self.engine();
+        let all = self.spawn_flags().intersects(SpawnFlags::ALL_PLAYERS);
+        let Some(message) = self.vars().message() else {
+            return;
+        };
+
+        // a reference to the titles.txt is resolved on the client
+        if message.to_bytes().starts_with(b"#") {
+            if all {
+                utils::show_message_all(&engine, message.as_c_str());
+            } else if let Some(player) = activator.and_then(|i| i.as_player()) {
+                utils::show_message(player, message.as_c_str());
+            }
+            return;
+        }
+
+        let Some(msg) = self.text_message() else {
+            return;
+        };
+        if all {
+            for player in engine.players() {
+                engine.msg_one(player.vars(), &msg);
+            }
+        } else if let Some(player) = activator.and_then(|i| i.as_player()) {
+            if player.is_net_client() {
+                engine.msg_one(player.vars(), &msg);
+            }
+        }
+    }
+}
+
+impl_private!(GameText {});
+
+define_export! {
+    export_game_text as export if "game-text" {
+        game_text = game_text::GameText,
+    }
+}
@@ -0,0 +1,102 @@
+use xash3d_server::{
+    entity::{BaseEntity, EdictFlags, KeyValue, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    str::MapString,
+    utils,
+};
+
+use crate::game_rule::RuleEntity;
+
+const HULL_HUMAN: i32 = 1;
+const HULL_HEAD: i32 = 3;
+
+/// Fires targets for players inside or outside of the zone.
+///
+/// Also sends the number of players in each state to the count targets.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct GamePlayerZone {
+    base: RuleEntity,
+    in_target: Option<MapString>,
+    out_target: Option<MapString>,
+    in_count: Option<MapString>,
+    out_count: Option<MapString>,
+}
+
+impl CreateEntity for GamePlayerZone {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: RuleEntity::create(base),
+            in_target: None,
+            out_target: None,
+            in_count: None,
+            out_count: None,
+        }
+    }
+}
+
+impl Entity for GamePlayerZone {
+    delegate_entity!(base not { key_value, spawn, used });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        let field = match data.key_name().to_bytes() {
+            b"intarget" => &mut self.in_target,
+            b"outtarget" => &mut self.out_target,
+            b"incount" => &mut self.in_count,
+            b"outcount" => &mut self.out_count,
+            _ => return self.base.key_value(data),
+        };
+        *field = Some(self.base.engine().new_map_string(data.value()));
+        data.set_handled(true);
+    }
+
+    fn spawn(&mut self) {
+        self.vars().reload_model();
+        self.base.spawn();
+    }
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if !self.base.can_fire_for_activator(activator) {
+            return;
+        }
+
+        let engine = self.engine();
+        let mut players_in = 0;
+        let mut players_out = 0;
+        for player in engine.players() {
+            let pv = player.vars();
+            let hull = if pv.flags().intersects(EdictFlags::DUCKING) {
+                HULL_HEAD
+            } else {
+                HULL_HUMAN
+            };
+            let trace = engine.trace_model(pv.origin(), pv.origin(), hull, self.vars());
+            let target = if trace.start_solid() {
+                players_in += 1;
+                self.in_target
+            } else {
+                players_out += 1;
+                self.out_target
+            };
+            if let Some(target) = target {
+                let caller = activator.unwrap_or(self);
+                utils::fire_targets(&target, UseType::Toggle, Some(player), caller);
+            }
+        }
+
+        if let Some(target) = self.in_count {
+            utils::fire_targets(&target, UseType::Set(players_in as f32), activator, self);
+        }
+        if let Some(target) = self.out_count {
+            utils::fire_targets(&target, UseType::Set(players_out as f32), activator, self);
+        }
+    }
+}
+
+impl_private!(GamePlayerZone {});
+
+define_export! {
+    export_game_zone_player as export if "game-zone-player" {
+        game_zone_player = game_zone_player::GamePlayerZone,
+    }
+}
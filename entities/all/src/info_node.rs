@@ -1,5 +1,6 @@
 use xash3d_server::{
     entity::{delegate_entity, BaseEntity, KeyValue, MoveType, ObjectCaps, Solid},
+    node_graph::NodeGraph,
     prelude::*,
     private::impl_private,
 };
@@ -24,7 +25,7 @@ impl CreateEntity for InfoNode {
 }
 
 impl Entity for InfoNode {
-    delegate_entity!(base not { object_caps, key_value, spawn });
+    delegate_entity!(base not { object_caps, key_value, spawn, think });
 
     fn object_caps(&self) -> ObjectCaps {
         self.base
@@ -46,18 +47,28 @@ impl Entity for InfoNode {
         v.set_solid(Solid::Not);
         v.set_move_type(MoveType::None);
 
-        // TODO: add node entity to the world graph
-        let name = self.pretty_name();
-        warn!("{name}: spawn is not implemented");
+        let air = self.is_classname(c"info_node_air".into());
+        let global_state = self.global_state();
+        let graph = global_state.get_or_default::<NodeGraph>();
+        let yaw = v.angles().y;
+        match graph.add_node(v.origin(), air, self.hint_type, self.hint_activity, yaw) {
+            // the first node builds the graph after all nodes are spawned
+            Some(0) => v.set_next_think_time_from_now(1.0),
+            // the node is added or the graph is loaded from the cache
+            _ => v.delayed_remove(),
+        }
+    }
 
+    fn think(&self) {
+        self.global_state()
+            .get_or_default::<NodeGraph>()
+            .build(self.vars());
         self.vars().delayed_remove();
     }
 }
 
 impl_private!(InfoNode {});
 
-// TODO: add the world graph
-
 define_export! {
     export_info_node as export if "info-node" {
         info_node = info_node::InfoNode,
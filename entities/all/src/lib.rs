@@ -22,13 +22,24 @@ import_with_export! {
     use xash3d_entity_beam::env_beam as env_beam if "env-beam";
     use xash3d_entity_beam::env_laser as env_laser if "env-laser";
     use xash3d_entity_beam::env_lightning as env_lightning if "env-lightning";
+    use xash3d_entity_breakable::func_breakable as func_breakable if "func-breakable";
+    use xash3d_entity_breakable::func_pushable as func_pushable if "func-pushable";
     use xash3d_entity_button::func_button as func_button if "func-button";
     use xash3d_entity_button::func_rot_button as func_rot_button if "func-rot-button";
+    use xash3d_entity_button::momentary_rot_button as momentary_rot_button if "momentary-rot-button";
     use xash3d_entity_door::func_door as func_door if "func-door";
     use xash3d_entity_door::func_door_rotating as func_door_rotating if "func-door-rotating";
+    use xash3d_entity_door::momentary_door as momentary_door if "momentary-door";
     use xash3d_entity_platform::func_plat as func_plat if "func-plat";
     use xash3d_entity_platform::func_platrot as func_platrot if "func-platrot";
     use xash3d_entity_sprite::env_sprite as env_sprite if "env-sprite";
+    use xash3d_entity_tank::func_tank as func_tank if "func-tank";
+    use xash3d_entity_tank::func_tankcontrols as func_tankcontrols if "func-tankcontrols";
+    use xash3d_entity_tank::func_tanklaser as func_tanklaser if "func-tanklaser";
+    use xash3d_entity_tank::func_tankmortar as func_tankmortar if "func-tankmortar";
+    use xash3d_entity_tank::func_tankrocket as func_tankrocket if "func-tankrocket";
+    use xash3d_entity_tracktrain::func_trackautochange as func_trackautochange if "func-trackautochange";
+    use xash3d_entity_tracktrain::func_trackchange as func_trackchange if "func-trackchange";
     use xash3d_entity_tracktrain::func_tracktrain as func_tracktrain if "func-tracktrain";
     use xash3d_entity_tracktrain::path_track as path_track if "path-track";
     use xash3d_entity_train::func_train as func_train if "func-train";
@@ -38,10 +49,12 @@ import_with_export! {
 define_with_export! {
     export_defined;
 
+    mod aiscripted_sequence if "aiscripted-sequence";
     mod env_bubbles if "env-bubbles";
     mod env_debris if "env-debris";
     mod env_explosion if "env-explosion";
     mod env_fade if "env-fade";
+    mod env_global if "env-global";
     mod env_glow if "env-glow";
     mod env_message if "env-message" or "world";
     mod env_render if "env-render";
@@ -56,6 +69,16 @@ define_with_export! {
     mod func_wall if "func-wall" or "func-wall-toggle";
     mod func_wall_toggle if "func-wall-toggle";
     mod func_water if "func-water";
+    mod game_counter if "game-counter";
+    mod game_counter_set if "game-counter-set";
+    mod game_end if "game-end";
+    mod game_player_equip if "game-player-equip";
+    mod game_player_hurt if "game-player-hurt";
+    mod game_score if "game-score";
+    mod game_team_master if "game-team-master";
+    mod game_team_set if "game-team-set";
+    mod game_text if "game-text";
+    mod game_zone_player if "game-zone-player";
     mod info_landmark if "info-landmark";
     mod info_node if "info-node" or "info-node-air";
     mod info_node_air if "info-node-air";
@@ -69,17 +92,23 @@ define_with_export! {
     mod light_environment if "light-environment";
     mod multi_manager if "multi-manager" or "multisource";
     mod multisource if "multisource";
+    mod scripted_sentence if "scripted-sentence";
+    mod scripted_sequence if "scripted-sequence" or "aiscripted-sequence";
     mod spark_shower if "spark-shower";
     mod speaker if "speaker";
     mod target_cdaudio if "target-cdaudio";
     mod trigger if "trigger";
     mod trigger_auto if "trigger-auto";
     mod trigger_autosave if "trigger-autosave";
+    mod trigger_camera if "trigger-camera";
     mod trigger_cdaudio if "trigger-cdaudio";
     mod trigger_changelevel if "trigger-changelevel";
+    mod trigger_changetarget if "trigger-changetarget";
+    mod trigger_counter if "trigger-counter";
     mod trigger_endsection if "trigger-endsection";
     mod trigger_gravity if "trigger-gravity";
     mod trigger_hurt if "trigger-hurt";
+    mod trigger_monsterjump if "trigger-monsterjump";
     mod trigger_multiple if "trigger-multiple" or "trigger-once";
     mod trigger_once if "trigger-once";
     mod trigger_push if "trigger-push";
@@ -89,6 +118,9 @@ define_with_export! {
 }
 
 define! {
+    mod game_rule if "game-counter" or "game-counter-set" or "game-end"
+        or "game-player-equip" or "game-player-hurt" or "game-score"
+        or "game-team-master" or "game-team-set" or "game-text" or "game-zone-player";
     mod item if "item";
     mod player if "player";
     mod stub if "stub";
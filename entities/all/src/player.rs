@@ -4,18 +4,24 @@ use core::{
     ptr,
 };
 
+use alloc::vec::Vec;
+
 use xash3d_server::{
     csz::CStrThin,
     engine::TraceIgnore,
     entities::item::SF_ITEM_NO_RESPAWN,
     entity::{
-        BaseEntity, Buttons, Dead, EdictFlags, EntityHandle, EntityItem, EntityPlayer, EntityVars,
-        LastSound, MoveType, ObjectCaps, Solid, TakeDamage, UseType, delegate_entity,
+        BaseEntity, Buttons, DamageFlags, Dead, EdictFlags, Effects, EntityHandle, EntityItem,
+        EntityPlayer, EntityVars, Gib, LastSound, MoveType, ObjectCaps, Solid, TakeDamage, UseType,
+        delegate_entity,
     },
     ffi::common::vec3_t,
+    inventory::Inventory,
     math::ToAngleVectors,
+    monster::Class,
     prelude::*,
     private::impl_private,
+    time::MapTime,
     utils::{self, ViewField},
 };
 
@@ -88,8 +94,20 @@ pub struct Player {
 
     #[cfg_attr(feature = "save", save(skip))]
     last_sound: Cell<Option<LastSound>>,
+    #[cfg_attr(feature = "save", save(skip))]
+    random_seed: Cell<u32>,
 
     pub input: Input,
+    inventory: Inventory,
+
+    /// The entity that inflicted the last damage.
+    last_inflictor: Cell<Option<EntityHandle>>,
+    /// The time when the player became respawnable.
+    dead_time: Cell<MapTime>,
+    /// The number of frames since the death.
+    respawn_frames: Cell<u32>,
+    #[cfg_attr(feature = "save", save(skip))]
+    respawn_pending: Cell<bool>,
 }
 
 impl CreateEntity for Player {
@@ -98,8 +116,15 @@ impl CreateEntity for Player {
             base,
 
             last_sound: Default::default(),
+            random_seed: Default::default(),
 
             input: Input::default(),
+            inventory: Inventory::default(),
+
+            last_inflictor: Cell::default(),
+            dead_time: Cell::default(),
+            respawn_frames: Cell::default(),
+            respawn_pending: Cell::default(),
         }
     }
 }
@@ -113,6 +138,21 @@ impl Player {
     /// Default view field for player use action.
     pub const USE_VIEW_FIELD: ViewField = ViewField::NARROW;
 
+    /// The fraction of damage absorbed by the armor.
+    const ARMOR_RATIO: f32 = 0.2;
+
+    /// Each point of the armor absorbs this amount of damage.
+    const ARMOR_BONUS: f32 = 0.5;
+
+    /// The player is gibbed if health drops below this value.
+    const GIB_HEALTH: f32 = -40.0;
+
+    /// The number of frames to wait before the dead player can respawn.
+    const DEATH_FRAMES: u32 = 120;
+
+    /// The delay after which the dead player is respawned with `mp_forcerespawn`.
+    const FORCE_RESPAWN_DELAY: f32 = 5.0;
+
     fn is_use_button_active(&self) -> bool {
         self.vars().buttons().intersects(Buttons::USE) || self.input.is_changed(Buttons::USE)
     }
@@ -262,6 +302,173 @@ impl Player {
         self.player_use_with(Self::USE_SEARCH_RADIUS, Self::USE_VIEW_FIELD)
     }
 
+    /// Returns `true` if the player can spawn at the given spot.
+    fn is_spawn_point_valid(&self, spot: &dyn Entity) -> bool {
+        if !spot.is_triggered(Some(self)) {
+            return false;
+        }
+        let engine = self.engine();
+        let origin = spot.vars().origin();
+        !engine
+            .entities()
+            .in_sphere(origin, 128.0)
+            .filter_map(|i| i.get_entity())
+            .any(|i| i.is_player() && i.entity_handle() != self.entity_handle())
+    }
+
+    /// Selects the next valid spawn spot after the last one.
+    ///
+    /// The `skip` count is used to randomize the start spot.
+    fn select_next_spawn_point(&self, class_name: &CStr, skip: usize) -> Option<EntityHandle> {
+        let engine = self.engine();
+        let spots: Vec<EntityHandle> = engine
+            .entities()
+            .by_class_name(class_name)
+            .map(|i| i.into())
+            .collect();
+        if spots.is_empty() {
+            return None;
+        }
+
+        let last = self.global_state().last_spawn();
+        let start = spots
+            .iter()
+            .position(|i| Some(*i) == last)
+            .map_or(skip, |i| i + skip);
+        (0..spots.len())
+            .map(|i| spots[(start + i) % spots.len()])
+            // skip spots placed at the world origin
+            .filter(|spot| spot.vars().origin() != vec3_t::ZERO)
+            .find(|spot| {
+                spot.get_entity()
+                    .is_some_and(|i| self.is_spawn_point_valid(i))
+            })
+            .or(Some(spots[start % spots.len()]))
+    }
+
+    fn select_deathmatch_spawn_point(&self) -> Option<EntityHandle> {
+        let engine = self.engine();
+        let skip = engine.random_int(1, 5) as usize;
+        let spot = self.select_next_spawn_point(c"info_player_deathmatch", skip)?;
+        if spot
+            .get_entity()
+            .is_some_and(|i| self.is_spawn_point_valid(i))
+        {
+            return Some(spot);
+        }
+
+        // no valid spots, kill anything standing on the spot
+        let world = engine.get_world_spawn_entity();
+        for ent in engine.entities().in_sphere(spot.vars().origin(), 128.0) {
+            if let Some(entity) = ent.get_entity() {
+                if entity.is_player() && entity.entity_handle() != self.entity_handle() {
+                    entity.take_damage(300.0, DamageFlags::GENERIC, world.vars(), None);
+                }
+            }
+        }
+        Some(spot)
+    }
+
+    fn select_coop_spawn_point(&self) -> Option<EntityHandle> {
+        let spot = self.select_next_spawn_point(c"info_player_coop", 1)?;
+        spot.get_entity()
+            .is_some_and(|i| self.is_spawn_point_valid(i))
+            .then_some(spot)
+    }
+
+    /// Returns the damage left after the armor absorbed its part.
+    fn absorb_damage(&self, damage: f32, damage_type: DamageFlags) -> f32 {
+        let v = self.vars();
+        let armor = v.armor_value();
+        if armor == 0.0 || damage_type.intersects(DamageFlags::FALL | DamageFlags::DROWN) {
+            return damage;
+        }
+
+        let new_damage = damage * Self::ARMOR_RATIO;
+        let armor_damage = (damage - new_damage) * Self::ARMOR_BONUS;
+        if armor_damage > armor {
+            // the armor is not enough to absorb the damage
+            v.set_armor_value(0.0);
+            damage - armor / Self::ARMOR_BONUS
+        } else {
+            v.set_armor_value(armor - armor_damage);
+            new_damage
+        }
+    }
+
+    /// Waits for the death animation and the respawn conditions.
+    fn death_think(&self) {
+        let engine = self.engine();
+        let global_state = self.global_state();
+        let game_rules = global_state.game_rules();
+        let v = self.vars();
+        let now = engine.globals.map_time();
+
+        if v.flags().intersects(EdictFlags::ONGROUND) {
+            let velocity = v.velocity();
+            let speed = velocity.length() - 20.0;
+            if speed <= 0.0 {
+                v.set_velocity(vec3_t::ZERO);
+            } else {
+                v.set_velocity(velocity.normalize() * speed);
+            }
+        }
+
+        if self.inventory.weapons().next().is_some() {
+            game_rules.pack_dead_player_items(self);
+            self.inventory.remove_all();
+        }
+
+        if v.dead() == Dead::Dying {
+            let frames = self.respawn_frames.get() + 1;
+            self.respawn_frames.set(frames);
+            if frames < Self::DEATH_FRAMES {
+                return;
+            }
+        }
+
+        if v.move_type() != MoveType::None && v.flags().intersects(EdictFlags::ONGROUND) {
+            v.set_move_type(MoveType::None);
+        }
+
+        if v.dead() == Dead::Dying {
+            v.set_dead(Dead::Yes);
+        }
+
+        v.with_effects(|f| f.union(Effects::NOINTERP));
+        v.set_framerate(0.0);
+
+        let any_button = !v.buttons().difference(Buttons::SCORE).is_empty();
+
+        if v.dead() == Dead::Yes {
+            // wait for all buttons released
+            if any_button {
+                return;
+            }
+            if game_rules.player_can_respawn(self) {
+                self.dead_time.set(now);
+                v.set_dead(Dead::Respawnable);
+            }
+            return;
+        }
+
+        if self.is_observer() || now < game_rules.player_spawn_time(self) {
+            return;
+        }
+
+        // wait for any button down or until mp_forcerespawn respawns the player
+        let force_respawn = game_rules.is_multiplayer()
+            && engine.get_cvar::<bool>(c"mp_forcerespawn")
+            && now > self.dead_time.get() + Self::FORCE_RESPAWN_DELAY;
+        if !any_button && !force_respawn {
+            return;
+        }
+
+        v.set_buttons(Buttons::empty());
+        self.respawn_frames.set(0);
+        self.respawn_pending.set(true);
+    }
+
     pub fn set_custom_decal_frames(&mut self, frames: c_int) {
         debug!("Player::set_custom_decal_frames({frames})");
     }
@@ -291,13 +498,11 @@ impl save::OnRestore for Player {
         v.set_angles(v.view_angle());
         v.set_fix_angle(1);
 
+        let config = xash3d_player_move::movement_config();
         if v.flags().intersects(EdictFlags::DUCKING) {
-            v.set_size_and_link(
-                xash3d_player_move::DUCK_HULL_MIN,
-                xash3d_player_move::DUCK_HULL_MAX,
-            );
+            v.set_size_and_link(config.duck_hull_min, config.duck_hull_max);
         } else {
-            v.set_size_and_link(xash3d_player_move::HULL_MIN, xash3d_player_move::HULL_MAX);
+            v.set_size_and_link(config.hull_min, config.hull_max);
         }
 
         engine.set_physics_key_value(self, c"hl", c"1");
@@ -305,7 +510,9 @@ impl save::OnRestore for Player {
 }
 
 impl Entity for Player {
-    delegate_entity!(base not { object_caps, restore, spawn, is_player });
+    delegate_entity!(base not {
+        object_caps, restore, spawn, is_player, classify, take_damage, killed
+    });
 
     fn object_caps(&self) -> ObjectCaps {
         self.base
@@ -315,6 +522,7 @@ impl Entity for Player {
 
     fn spawn(&mut self) {
         let engine = self.engine();
+        let config = xash3d_player_move::movement_config();
         let v = self.base.vars();
         v.set_classname(engine.new_map_string(Self::CLASS_NAME));
         v.set_health(100.0);
@@ -334,7 +542,11 @@ impl Entity for Player {
         v.set_friction(1.0);
         v.set_gravity(1.0);
         v.set_fov(0.0);
-        v.set_view_ofs(xash3d_player_move::VIEW_OFFSET);
+        v.set_view_ofs(config.view_offset);
+
+        self.last_inflictor.set(None);
+        self.respawn_frames.set(0);
+        self.respawn_pending.set(false);
 
         engine.set_physics_key_value(self, c"slj", c"0");
         engine.set_physics_key_value(self, c"hl", c"1");
@@ -344,18 +556,94 @@ impl Entity for Player {
         v.set_model(res::valve::models::PLAYER);
 
         if v.flags().intersects(EdictFlags::DUCKING) {
-            v.set_size_and_link(
-                xash3d_player_move::DUCK_HULL_MIN,
-                xash3d_player_move::DUCK_HULL_MAX,
-            );
+            v.set_size_and_link(config.duck_hull_min, config.duck_hull_max);
         } else {
-            v.set_size_and_link(xash3d_player_move::HULL_MIN, xash3d_player_move::HULL_MAX);
+            v.set_size_and_link(config.hull_min, config.hull_max);
         }
+
+        self.global_state().game_rules().player_spawn(self);
     }
 
     fn is_player(&self) -> bool {
         true
     }
+
+    fn classify(&self) -> Class {
+        Class::Player
+    }
+
+    fn take_damage(
+        &self,
+        damage: f32,
+        damage_type: DamageFlags,
+        inflictor: &EntityVars,
+        attacker: Option<&EntityVars>,
+    ) -> bool {
+        let v = self.vars();
+        if v.take_damage() == TakeDamage::No || !self.is_alive() {
+            return false;
+        }
+
+        let damage = self.absorb_damage(damage, damage_type).trunc();
+
+        self.last_inflictor.set(Some(inflictor.entity_handle()));
+        v.set_damage_inflictor(inflictor);
+        v.set_damage_take(v.damage_take() + damage);
+        v.set_health(v.health() - damage);
+
+        if v.health() <= 0.0 {
+            let gib = if damage_type.intersects(DamageFlags::ALWAYSGIB) {
+                Gib::Always
+            } else if damage_type.intersects(DamageFlags::NEVERGIB) {
+                Gib::Never
+            } else {
+                Gib::Normal
+            };
+            self.private()
+                .as_entity()
+                .killed(attacker.unwrap_or(inflictor), gib);
+            return false;
+        }
+
+        true
+    }
+
+    fn killed(&self, attacker: &EntityVars, gib: Gib) {
+        let engine = self.engine();
+        let v = self.vars();
+
+        if let Some(weapon) = self.inventory.active() {
+            weapon.holster();
+        }
+
+        let inflictor = self.last_inflictor.take().map(|i| i.vars());
+        self.global_state().game_rules().player_killed(
+            self,
+            attacker,
+            inflictor.as_ref().unwrap_or(attacker),
+        );
+
+        v.set_dead(Dead::Dying);
+        v.set_move_type(MoveType::Toss);
+        v.with_flags(|f| f.difference(EdictFlags::ONGROUND));
+        if v.velocity().z < 10.0 {
+            let z = engine.random_float(0.0, 300.0);
+            v.with_velocity(|v| v + vec3_t::new(0.0, 0.0, z));
+        }
+        v.set_fov(0.0);
+        v.set_take_damage(TakeDamage::No);
+        self.respawn_frames.set(0);
+
+        if (v.health() < Self::GIB_HEALTH && gib != Gib::Never) || gib == Gib::Always {
+            v.set_solid(Solid::Not);
+            v.with_effects(|f| f.union(Effects::NODRAW));
+            v.set_health(0.0);
+            return;
+        }
+        v.set_health(v.health().min(0.0));
+
+        v.with_angles(|v| vec3_t::new(0.0, v.y, 0.0));
+    }
 }
 
 impl EntityPlayer for Player {
@@ -368,10 +656,16 @@ impl EntityPlayer for Player {
         let global_state = self.global_state();
         let game_rules = global_state.game_rules();
 
-        if game_rules.is_coop() {
-            todo!();
+        let spot = if game_rules.is_coop() {
+            self.select_coop_spawn_point()
         } else if game_rules.is_deathmatch() {
-            todo!();
+            self.select_deathmatch_spawn_point()
+        } else {
+            None
+        };
+        if let Some(spot) = spot {
+            global_state.set_last_spawn(Some(spot));
+            return spot;
         }
 
         let start_spot = engine.globals.start_spot();
@@ -390,7 +684,13 @@ impl EntityPlayer for Player {
     }
 
     fn pre_think(&self) {
-        self.input.pre_think(self.base.vars());
+        let v = self.base.vars();
+        self.input.pre_think(v);
+        self.respawn_pending.set(false);
+
+        if v.dead() != Dead::No {
+            self.death_think();
+        }
     }
 
     fn post_think(&self) {
@@ -432,6 +732,22 @@ impl EntityPlayer for Player {
 
         false
     }
+
+    fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    fn random_seed(&self) -> u32 {
+        self.random_seed.get()
+    }
+
+    fn set_random_seed(&self, seed: u32) {
+        self.random_seed.set(seed);
+    }
+
+    fn is_respawn_pending(&self) -> bool {
+        self.respawn_pending.get()
+    }
 }
 
 impl_private!(Player { EntityPlayer });
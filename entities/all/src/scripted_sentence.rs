@@ -0,0 +1,247 @@
+use core::cell::Cell;
+
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, EdictFlags, KeyValue, ObjectCaps, Solid, UseType, delegate_entity},
+    monster::EntityMonster,
+    prelude::*,
+    private::impl_private,
+    sound::Attenuation,
+    str::MapString,
+    utils,
+};
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const ONCE          = 1 << 0;
+        /// Only monsters following the player can speak.
+        const FOLLOWERS     = 1 << 1;
+        /// Speak in any state except dead.
+        const INTERRUPT     = 1 << 2;
+        /// Do not stop other monsters talking.
+        const CONCURRENT    = 1 << 3;
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+enum Think {
+    #[default]
+    None,
+    Find,
+    Delay,
+}
+
+/// Makes a monster speak a sentence.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct ScriptedSentence {
+    base: BaseEntity,
+    sentence: Option<MapString>,
+    /// A target name or a class name of the speaker.
+    entity: Option<MapString>,
+    duration: f32,
+    radius: f32,
+    refire: f32,
+    attenuation: Attenuation,
+    volume: f32,
+    /// A target name or a class name of the entity to look at.
+    listener: Option<MapString>,
+    active: Cell<bool>,
+    think: Cell<Think>,
+}
+
+impl CreateEntity for ScriptedSentence {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+            sentence: None,
+            entity: None,
+            duration: 0.0,
+            radius: 512.0,
+            refire: 0.0,
+            attenuation: Attenuation::IDLE,
+            volume: 1.0,
+            listener: None,
+            active: Cell::default(),
+            think: Cell::default(),
+        }
+    }
+}
+
+impl ScriptedSentence {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn is_acceptable_speaker(&self, monster: &dyn EntityMonster) -> bool {
+        let spawn_flags = self.spawn_flags();
+        if spawn_flags.intersects(SpawnFlags::FOLLOWERS) {
+            let target = monster.base_monster().target_ent();
+            if !target
+                .get_entity()
+                .is_some_and(|i| i.is_classname(c"player".into()))
+            {
+                return false;
+            }
+        }
+        monster.can_play_sentence(spawn_flags.intersects(SpawnFlags::INTERRUPT))
+    }
+
+    fn find_entity(&self) -> Option<&dyn EntityMonster> {
+        let name = self.entity?;
+        let engine = self.engine();
+        let by_name = engine
+            .entities()
+            .by_target_name(name)
+            .filter_map(|i| i.downcast_ref::<dyn EntityMonster>())
+            .find(|i| self.is_acceptable_speaker(*i));
+        if by_name.is_some() {
+            return by_name;
+        }
+
+        engine
+            .entities()
+            .in_sphere(self.vars().origin(), self.radius)
+            .filter(|i| i.vars().flags().intersects(EdictFlags::MONSTER))
+            .filter_map(|i| i.downcast_ref::<dyn EntityMonster>())
+            .find(|i| i.is_classname(name.as_thin()) && self.is_acceptable_speaker(*i))
+    }
+
+    fn start_sentence(&self, monster: &dyn EntityMonster) {
+        let Some(sentence) = self.sentence else {
+            return;
+        };
+
+        let listener = self.listener.and_then(|name| {
+            let radius = if name == c"player" {
+                // always find the player
+                4096.0
+            } else {
+                self.radius
+            };
+            let origin = monster.vars().origin();
+            self.engine()
+                .entities()
+                .find_generic(name.as_thin(), origin, radius)
+        });
+
+        let concurrent = self.spawn_flags().intersects(SpawnFlags::CONCURRENT);
+        monster.play_scripted_sentence(
+            sentence.as_thin(),
+            self.duration,
+            self.volume,
+            self.attenuation,
+            concurrent,
+            listener.get_entity(),
+        );
+        debug!(
+            "{}: playing sentence {sentence} ({:.1})",
+            self.pretty_name(),
+            self.duration
+        );
+        utils::use_targets(UseType::Toggle, None, self);
+    }
+
+    fn find_think(&self) {
+        let v = self.vars();
+        let Some(monster) = self.find_entity() else {
+            v.set_next_think_time_from_now(self.refire + 0.5);
+            return;
+        };
+        self.start_sentence(monster);
+        if self.spawn_flags().intersects(SpawnFlags::ONCE) {
+            v.delayed_remove();
+            return;
+        }
+        self.think.set(Think::Delay);
+        v.set_next_think_time_from_now(self.duration + self.refire);
+        self.active.set(false);
+    }
+
+    fn delay_think(&self) {
+        let v = self.vars();
+        self.active.set(true);
+        if v.target_name().is_none() {
+            v.set_next_think_time_from_now(0.1);
+        }
+        self.think.set(Think::Find);
+    }
+}
+
+impl Entity for ScriptedSentence {
+    delegate_entity!(base not { object_caps, key_value, spawn, think, used });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        let engine = self.engine();
+        match data.key_name().to_bytes() {
+            b"sentence" => self.sentence = Some(engine.new_map_string(data.value())),
+            b"entity" => self.entity = Some(engine.new_map_string(data.value())),
+            b"duration" => self.duration = data.parse_or_default(),
+            b"radius" => self.radius = data.parse_or_default(),
+            b"refire" => self.refire = data.parse_or_default(),
+            b"attenuation" => {
+                self.attenuation = match data.parse_or_default() {
+                    // medium radius
+                    1 => Attenuation::STATIC,
+                    // large radius
+                    2 => Attenuation::NORM,
+                    // everywhere
+                    3 => Attenuation::NONE,
+                    // small radius
+                    _ => Attenuation::IDLE,
+                };
+            }
+            b"volume" => self.volume = data.parse_or_default::<f32>() * 0.1,
+            b"listener" => self.listener = Some(engine.new_map_string(data.value())),
+            _ => return self.base.key_value(data),
+        }
+        data.set_handled(true);
+    }
+
+    fn spawn(&mut self) {
+        let v = self.vars();
+        v.set_solid(Solid::Not);
+        self.active.set(true);
+
+        if v.target_name().is_none() {
+            // start now
+            self.think.set(Think::Find);
+            v.set_next_think_time_from_now(1.0);
+        }
+
+        if self.volume <= 0.0 {
+            self.volume = 1.0;
+        }
+    }
+
+    fn think(&self) {
+        match self.think.get() {
+            Think::None => {}
+            Think::Find => self.find_think(),
+            Think::Delay => self.delay_think(),
+        }
+    }
+
+    fn used(&self, _: UseType, _: Option<&dyn Entity>, _: &dyn Entity) {
+        if self.active.get() {
+            self.think.set(Think::Find);
+            self.vars().set_next_think_time_from_now(0.0);
+        }
+    }
+}
+
+impl_private!(ScriptedSentence {});
+
+define_export! {
+    export_scripted_sentence as export if "scripted-sentence" {
+        scripted_sentence = scripted_sentence::ScriptedSentence,
+    }
+}
@@ -0,0 +1,607 @@
+use core::cell::Cell;
+
+use xash3d_server::{
+    entities::delayed_use::DelayedUse,
+    entity::{
+        BaseEntity, Dead, EdictFlags, Effects, EntityHandle, KeyValue, ObjectCaps, Solid, UseType,
+        delegate_entity,
+    },
+    ffi::common::vec3_t,
+    monster::{
+        BaseMonster, EntityMonster, EntityScript, InterruptLevel, MonsterState, ScheduleType,
+        ScriptMoveTo, ScriptSpawnFlags, ScriptState, script_event,
+    },
+    prelude::*,
+    private::impl_private,
+    str::MapString,
+    time::MapTime,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+enum Think {
+    #[default]
+    None,
+    /// Searches for a monster to possess.
+    Cine,
+    Remove,
+}
+
+/// Plays a sequence on a monster found by a target name or by a class name in the radius.
+///
+/// The same type is used for `aiscripted_sequence` that can possess monsters in any state,
+/// does not wait for other scripts and can leave the monster in an ambush schedule.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct ScriptedSequence {
+    base: BaseEntity,
+    delayed: DelayedUse,
+
+    idle: Option<MapString>,
+    play: Option<MapString>,
+    /// A target name or a class name of the monster.
+    entity: Option<MapString>,
+    move_to: ScriptMoveTo,
+    radius: f32,
+    /// Switch the monster to an ambush schedule when the script is done.
+    finish_ambush: bool,
+
+    /// The number of monsters still walking to their marks.
+    delay: Cell<i32>,
+    start_time: Cell<MapTime>,
+    saved_move_type: Cell<i32>,
+    saved_solid: Cell<i32>,
+    saved_effects: Cell<i32>,
+    interruptible: Cell<bool>,
+    target: Cell<Option<EntityHandle>>,
+    think: Cell<Think>,
+}
+
+impl CreateEntity for ScriptedSequence {
+    fn create(base: BaseEntity) -> Self {
+        let engine = base.engine();
+        Self {
+            base,
+            delayed: DelayedUse::new(engine),
+
+            idle: None,
+            play: None,
+            entity: None,
+            move_to: ScriptMoveTo::No,
+            radius: 512.0,
+            finish_ambush: false,
+
+            delay: Cell::default(),
+            start_time: Cell::default(),
+            saved_move_type: Cell::default(),
+            saved_solid: Cell::default(),
+            saved_effects: Cell::default(),
+            interruptible: Cell::new(true),
+            target: Cell::default(),
+            think: Cell::default(),
+        }
+    }
+}
+
+impl ScriptedSequence {
+    fn is_ai(&self) -> bool {
+        self.is_classname(c"aiscripted_sequence".into())
+    }
+
+    fn can_override_state(&self) -> bool {
+        self.is_ai()
+            || self
+                .script_flags()
+                .intersects(ScriptSpawnFlags::OVERRIDE_STATE)
+    }
+
+    fn target_monster(&self) -> Option<&dyn EntityMonster> {
+        self.target.get().downcast_ref::<dyn EntityMonster>()
+    }
+
+    /// Calls `f` for all scripts with the same target name or for this script if it has no
+    /// target name.
+    fn for_each_script(&self, mut f: impl FnMut(&Self)) {
+        let Some(name) = self.vars().target_name() else {
+            f(self);
+            return;
+        };
+        for i in self.engine().entities().by_target_name(name) {
+            if let Some(script) = i.downcast_ref::<Self>() {
+                f(script);
+            }
+        }
+    }
+
+    fn find_entity(&self) -> bool {
+        self.target.set(None);
+        let Some(name) = self.entity else {
+            return false;
+        };
+
+        let engine = self.engine();
+        let override_state = self.can_override_state();
+        for i in engine.entities().by_target_name(name) {
+            if !i.vars().flags().intersects(EdictFlags::MONSTER) {
+                continue;
+            }
+            let Some(monster) = i.downcast_ref::<dyn EntityMonster>() else {
+                continue;
+            };
+            if monster
+                .base_monster()
+                .can_play_sequence(override_state, InterruptLevel::ByName)
+            {
+                self.target.set(Some(i.into()));
+                return true;
+            }
+            debug!("{}: found {name}, but can't play", self.pretty_name());
+        }
+
+        let origin = self.vars().origin();
+        for i in engine.entities().in_sphere(origin, self.radius) {
+            if !i.vars().flags().intersects(EdictFlags::MONSTER) {
+                continue;
+            }
+            let Some(monster) = i.downcast_ref::<dyn EntityMonster>() else {
+                continue;
+            };
+            if monster.is_classname(name.as_thin())
+                && monster
+                    .base_monster()
+                    .can_play_sequence(override_state, InterruptLevel::Idle)
+            {
+                self.target.set(Some(i.into()));
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn possess_entity(&self) {
+        let Some(monster) = self.target_monster() else {
+            return;
+        };
+        let base = monster.base_monster();
+        let ai = self.is_ai();
+        if ai && !base.can_play_sequence(true, InterruptLevel::Ai) {
+            debug!(
+                "{}: can't possess {}",
+                self.pretty_name(),
+                base.pretty_name()
+            );
+            return;
+        }
+
+        let v = self.vars();
+        let mv = monster.vars();
+        base.set_script(Some(self.entity_handle()));
+        base.set_target_ent(Some(self.entity_handle()));
+        self.saved_move_type.set(mv.move_type_raw());
+        self.saved_solid.set(mv.solid_raw());
+        self.saved_effects.set(mv.effects_raw());
+        mv.with_effects_raw(|f| f | v.effects_raw());
+
+        let state = match self.move_to {
+            ScriptMoveTo::No | ScriptMoveTo::Turn => ScriptState::Wait,
+            ScriptMoveTo::Walk | ScriptMoveTo::Run => {
+                if !ai {
+                    self.delay_start(true);
+                }
+                if self.move_to == ScriptMoveTo::Run {
+                    ScriptState::RunToMark
+                } else {
+                    ScriptState::WalkToMark
+                }
+            }
+            ScriptMoveTo::Instant => {
+                let yaw = v.angles().y;
+                mv.set_origin_and_link(v.origin());
+                mv.set_ideal_yaw(yaw);
+                mv.set_angular_velocity(vec3_t::ZERO);
+                mv.set_velocity(vec3_t::ZERO);
+                mv.with_effects(|f| f | Effects::NOINTERP);
+                mv.with_angles(|a| a.with_y(yaw));
+                if ai {
+                    mv.with_flags(|f| f.difference(EdictFlags::ONGROUND));
+                }
+                ScriptState::Wait
+            }
+        };
+        base.set_script_state(state);
+        base.set_ideal_state(MonsterState::Script);
+
+        if ai {
+            if base.state() == MonsterState::Script {
+                // already in a script, so the schedule is not changed by the state
+                let schedule = monster.schedule_of_type(base.script_schedule_type());
+                base.change_schedule(schedule);
+            }
+        } else if let Some(idle) = self.idle {
+            self.start_sequence(base, Some(idle), false);
+            if self.play == Some(idle) {
+                mv.set_framerate(0.0);
+            }
+        }
+    }
+
+    fn cine_think(&self) {
+        if self.find_entity() {
+            self.possess_entity();
+            let name = self.pretty_name();
+            debug!("{name}: using monster {:?}", self.entity);
+        } else {
+            self.cancel();
+            let name = self.pretty_name();
+            debug!("{name}: can't find monster {:?}", self.entity);
+            self.vars().set_next_think_time_from_now(1.0);
+        }
+    }
+
+    fn fix_schedule(&self, monster: &BaseMonster) {
+        if self.is_ai() {
+            if self.finish_ambush {
+                let schedule = monster
+                    .private()
+                    .downcast_ref::<dyn EntityMonster>()
+                    .unwrap_or(monster)
+                    .schedule_of_type(ScheduleType::Ambush);
+                monster.change_schedule(schedule);
+            } else {
+                monster.clear_schedule();
+            }
+        } else {
+            if monster.ideal_state() != MonsterState::Dead {
+                monster.set_ideal_state(MonsterState::Idle);
+            }
+            monster.clear_schedule();
+        }
+    }
+
+    /// Stops the script on the possessed monster.
+    fn script_entity_cancel(&self) {
+        let Some(monster) = self.target_monster() else {
+            return;
+        };
+        let base = monster.base_monster();
+        if base.state() == MonsterState::Script {
+            base.set_script_state(ScriptState::Cleanup);
+            base.cine_cleanup();
+        }
+    }
+
+    /// Precaches sounds played by events of the sequence.
+    fn precache_sequence(&self, monster: &dyn Entity, label: Option<MapString>) {
+        let Some(label) = label else {
+            return;
+        };
+        let engine = self.engine();
+        let Some(model) = engine.get_studio_model(monster.vars()) else {
+            return;
+        };
+        let name = label.as_c_str().to_str().unwrap_or_default();
+        let Some(sequence) = model.find_sequence(name).and_then(|i| model.sequence(i)) else {
+            return;
+        };
+        for event in sequence.events() {
+            if !matches!(event.event, script_event::SOUND | script_event::SOUND_VOICE) {
+                continue;
+            }
+            if event.options().is_empty() {
+                error!(
+                    "{}: bad sound event in sequence {label}",
+                    self.pretty_name()
+                );
+                continue;
+            }
+            let sample = engine.new_map_string(event.options());
+            engine.precache_sound(sample);
+        }
+    }
+}
+
+impl Entity for ScriptedSequence {
+    delegate_entity!(base not { object_caps, key_value, spawn, activate, think, used });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        let engine = self.engine();
+        match data.key_name().to_bytes() {
+            b"m_iszIdle" => self.idle = Some(engine.new_map_string(data.value())),
+            b"m_iszPlay" => self.play = Some(engine.new_map_string(data.value())),
+            b"m_iszEntity" => self.entity = Some(engine.new_map_string(data.value())),
+            b"m_fMoveTo" => {
+                let raw = data.parse_or_default();
+                self.move_to = ScriptMoveTo::from_raw(raw).unwrap_or_else(|| {
+                    warn!("{}: invalid move to value {raw}", self.pretty_name());
+                    ScriptMoveTo::No
+                });
+            }
+            b"m_flRadius" => self.radius = data.parse_or_default(),
+            b"m_iFinishSchedule" => self.finish_ambush = data.parse_or_default::<i32>() == 1,
+            // not used
+            b"m_flRepeat" => {}
+            _ => {
+                if !self.delayed.key_value(data) {
+                    self.base.key_value(data);
+                }
+                return;
+            }
+        }
+        data.set_handled(true);
+    }
+
+    fn spawn(&mut self) {
+        let engine = self.engine();
+        let v = self.vars();
+        v.set_solid(Solid::Not);
+
+        let named = v.target_name().is_some();
+        if !named || self.idle.is_some() {
+            self.think.set(Think::Cine);
+            v.set_next_think_time_from_now(1.0);
+            if named {
+                // wait to be used
+                self.start_time.set(engine.globals.map_time() + 1e6);
+            }
+        }
+
+        let no_interrupt = self
+            .script_flags()
+            .intersects(ScriptSpawnFlags::NO_INTERRUPT);
+        self.interruptible.set(!no_interrupt);
+    }
+
+    fn activate(&self) {
+        let Some(name) = self.entity else {
+            return;
+        };
+        let engine = self.engine();
+        let entities = engine.entities();
+        let monster = entities
+            .by_target_name(name)
+            .chain(entities.by_class_name(name))
+            .filter(|i| i.vars().flags().intersects(EdictFlags::MONSTER))
+            .find_map(|i| i.downcast_ref::<dyn EntityMonster>());
+        if let Some(monster) = monster {
+            self.precache_sequence(monster.as_entity(), self.idle);
+            self.precache_sequence(monster.as_entity(), self.play);
+        }
+    }
+
+    fn think(&self) {
+        match self.think.get() {
+            Think::None => {}
+            Think::Cine => self.cine_think(),
+            Think::Remove => self.remove_from_world(),
+        }
+    }
+
+    fn used(&self, _: UseType, _: Option<&dyn Entity>, _: &dyn Entity) {
+        match self.target_monster() {
+            Some(monster) => {
+                if monster.base_monster().script_state() == ScriptState::Playing {
+                    return;
+                }
+                let now = self.engine().globals.map_time();
+                self.start_time.set(now + 0.05);
+            }
+            None => {
+                // try to find the monster
+                self.think.set(Think::Cine);
+                self.vars().set_next_think_time_from_now(0.0);
+            }
+        }
+    }
+}
+
+impl EntityScript for ScriptedSequence {
+    fn script_flags(&self) -> ScriptSpawnFlags {
+        ScriptSpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn idle_sequence(&self) -> Option<MapString> {
+        self.idle
+    }
+
+    fn play_sequence(&self) -> Option<MapString> {
+        self.play
+    }
+
+    fn move_to(&self) -> ScriptMoveTo {
+        self.move_to
+    }
+
+    fn is_ready(&self) -> bool {
+        self.delay.get() <= 0 && self.engine().globals.map_time() >= self.start_time.get()
+    }
+
+    fn delay_start(&self, delay: bool) {
+        let now = self.engine().globals.map_time();
+        self.for_each_script(|script| {
+            if script.is_ai() {
+                return;
+            }
+            if delay {
+                script.delay.set(script.delay.get() + 1);
+            } else {
+                script.delay.set(script.delay.get() - 1);
+                if script.delay.get() <= 0 {
+                    script.start_time.set(now + 0.05);
+                }
+            }
+        });
+    }
+
+    fn can_interrupt(&self) -> bool {
+        self.interruptible.get()
+            && self
+                .target
+                .get()
+                .is_some_and(|target| target.vars().dead() == Dead::No)
+    }
+
+    fn allow_interrupt(&self, allow: bool) {
+        if !self
+            .script_flags()
+            .intersects(ScriptSpawnFlags::NO_INTERRUPT)
+        {
+            self.interruptible.set(allow);
+        }
+    }
+
+    fn start_sequence(
+        &self,
+        monster: &BaseMonster,
+        sequence: Option<MapString>,
+        complete_on_empty: bool,
+    ) -> bool {
+        match sequence {
+            Some(sequence) => monster.start_named_sequence(sequence),
+            None if complete_on_empty => {
+                // nothing to play, but targets must be fired
+                self.sequence_done(monster);
+                self.is_ai()
+            }
+            None => false,
+        }
+    }
+
+    fn sequence_done(&self, monster: &BaseMonster) {
+        if !self.script_flags().intersects(ScriptSpawnFlags::REPEATABLE) {
+            self.think.set(Think::Remove);
+            self.vars().set_next_think_time_from_now(0.1);
+        }
+
+        // another script can take over the monster when triggered by this one
+        monster.cine_cleanup();
+        self.fix_schedule(monster);
+        self.delayed.use_targets(UseType::Toggle, None, self);
+    }
+
+    fn release(&self, monster: &BaseMonster) {
+        let mv = monster.vars();
+        mv.set_move_type_raw(self.saved_move_type.get());
+        mv.set_solid_raw(self.saved_solid.get());
+        mv.set_effects_raw(self.saved_effects.get());
+        self.target.set(None);
+    }
+
+    fn cancel(&self) {
+        debug!("{}: cancel script {:?}", self.pretty_name(), self.play);
+        self.for_each_script(|script| script.script_entity_cancel());
+    }
+}
+
+impl_private!(ScriptedSequence { EntityScript });
+
+define_export! {
+    export_scripted_sequence as export if "scripted-sequence" {
+        scripted_sequence = scripted_sequence::ScriptedSequence,
+    }
+}
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{
+        entity::{Dead, UseType},
+        ffi::common::vec3_t,
+        monster::{
+            BaseMonster, EntityScript, MonsterState, ScriptMoveTo, ScriptSpawnFlags, ScriptState,
+        },
+        prelude::*,
+        testing,
+    };
+
+    use super::ScriptedSequence;
+
+    fn new_monster(engine: &ServerEngine) -> &mut BaseMonster {
+        let monster = engine.new_entity::<BaseMonster>().build();
+        monster.vars().set_health(100.0);
+        monster
+    }
+
+    #[test]
+    fn possess_instant() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let monster = new_monster(&engine);
+        let script = engine.new_entity::<ScriptedSequence>().build();
+        script.move_to = ScriptMoveTo::Instant;
+        script.vars().set_origin(vec3_t::new(64.0, 0.0, 0.0));
+        script.vars().set_angles(vec3_t::new(0.0, 90.0, 0.0));
+        script.target.set(Some(monster.entity_handle()));
+
+        script.possess_entity();
+        assert!(monster.script().is_some());
+        assert_eq!(monster.script_state(), ScriptState::Wait);
+        assert_eq!(monster.ideal_state(), MonsterState::Script);
+        // the monster is moved to the mark
+        let v = monster.vars();
+        assert_eq!(v.origin(), vec3_t::new(64.0, 0.0, 0.0));
+        assert_eq!(v.angles().y, 90.0);
+        assert_eq!(v.ideal_yaw(), 90.0);
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+            engine.remove_entity_now(script.vars());
+        }
+    }
+
+    #[test]
+    fn used_starts_sequence() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let monster = new_monster(&engine);
+        let script = engine.new_entity::<ScriptedSequence>().build();
+        script.target.set(Some(monster.entity_handle()));
+        let start_time = engine.globals.map_time() + 0.05;
+
+        monster.set_script_state(ScriptState::Playing);
+        script.used(UseType::Toggle, None, &*monster);
+        assert_ne!(script.start_time.get(), start_time);
+
+        monster.set_script_state(ScriptState::Wait);
+        script.used(UseType::Toggle, None, &*monster);
+        assert_eq!(script.start_time.get(), start_time);
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+            engine.remove_entity_now(script.vars());
+        }
+    }
+
+    #[test]
+    fn no_interrupt() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let monster = new_monster(&engine);
+        let script = engine.new_entity::<ScriptedSequence>().build();
+        script.target.set(Some(monster.entity_handle()));
+
+        script.spawn();
+        assert!(script.can_interrupt());
+        monster.vars().set_dead(Dead::Dying);
+        assert!(!script.can_interrupt());
+        monster.vars().set_dead(Dead::No);
+
+        let flags = ScriptSpawnFlags::NO_INTERRUPT;
+        script.vars().set_spawn_flags(flags.bits());
+        script.spawn();
+        assert!(!script.can_interrupt());
+        // the flag can not be overridden by the monster
+        script.allow_interrupt(true);
+        assert!(!script.can_interrupt());
+
+        unsafe {
+            engine.remove_entity_now(monster.vars());
+            engine.remove_entity_now(script.vars());
+        }
+    }
+}
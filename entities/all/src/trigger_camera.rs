@@ -0,0 +1,336 @@
+use core::cell::Cell;
+
+use bitflags::bitflags;
+use xash3d_entity_train::path_corner::PathCorner;
+use xash3d_server::{
+    entities::delayed_use::DelayedUse,
+    entity::{
+        BaseEntity, EdictFlags, EntityHandle, KeyValue, MoveType, ObjectCaps, Solid, UseType,
+        delegate_entity,
+    },
+    ffi::common::vec3_t,
+    math::{angle_distance, approach},
+    prelude::*,
+    private::impl_private,
+    render::RenderMode,
+    str::MapString,
+    time::MapTime,
+};
+
+bitflags! {
+    #[derive(Copy, Clone, Debug)]
+    struct SpawnFlags: u32 {
+        /// Start at the player view position.
+        const PLAYER_POSITION       = 1 << 0;
+        /// Look at the player instead of the target.
+        const PLAYER_TARGET         = 1 << 1;
+        /// Freeze the player while the camera is active.
+        const PLAYER_TAKE_CONTROL   = 1 << 2;
+    }
+}
+
+/// Switches the player view to a camera that looks at the target.
+///
+/// The camera can follow a chain of `path_corner` entities starting at `moveto`.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TriggerCamera {
+    base: BaseEntity,
+    delayed: DelayedUse,
+
+    wait: f32,
+    path_name: Option<MapString>,
+    acceleration: f32,
+    deceleration: f32,
+    initial_speed: f32,
+
+    player: Cell<Option<EntityHandle>>,
+    target: Cell<Option<EntityHandle>>,
+    path: Cell<Option<EntityHandle>>,
+    return_time: Cell<MapTime>,
+    stop_time: Cell<MapTime>,
+    move_distance: Cell<f32>,
+    target_speed: Cell<f32>,
+    active: Cell<bool>,
+}
+
+impl CreateEntity for TriggerCamera {
+    fn create(base: BaseEntity) -> Self {
+        let engine = base.engine();
+        Self {
+            base,
+            delayed: DelayedUse::new(engine),
+
+            wait: 0.0,
+            path_name: None,
+            acceleration: 0.0,
+            deceleration: 0.0,
+            initial_speed: 0.0,
+
+            player: Cell::default(),
+            target: Cell::default(),
+            path: Cell::default(),
+            return_time: Cell::default(),
+            stop_time: Cell::default(),
+            move_distance: Cell::default(),
+            target_speed: Cell::default(),
+            active: Cell::default(),
+        }
+    }
+}
+
+impl TriggerCamera {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn set_player_control(&self, player: &dyn Entity, enable: bool) {
+        player.vars().with_flags(|f| {
+            if enable {
+                f.difference(EdictFlags::FROZEN)
+            } else {
+                f.union(EdictFlags::FROZEN)
+            }
+        });
+    }
+
+    fn follow_target(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+        let Some(player) = self.player.get().get_entity() else {
+            return;
+        };
+
+        let target = self.target.get().get_entity();
+        let target = match target {
+            Some(target) if self.return_time.get() >= engine.globals.map_time() => target,
+            _ => {
+                if player.is_alive() {
+                    engine.set_view(&player, &player);
+                    self.set_player_control(player, true);
+                }
+                self.delayed.use_targets(UseType::Toggle, Some(self), self);
+                v.set_angular_velocity(vec3_t::ZERO);
+                self.active.set(false);
+                return;
+            }
+        };
+
+        let mut goal = engine.vec_to_angles(target.vars().origin() - v.origin());
+        goal.x = -goal.x;
+
+        let mut angles = v.angles();
+        if angles.y > 360.0 {
+            angles.y -= 360.0;
+        }
+        if angles.y < 0.0 {
+            angles.y += 360.0;
+        }
+        v.set_angles(angles);
+
+        let frame_time = engine.globals.frame_time();
+        let dx = angle_distance(goal.x, angles.x);
+        let dy = angle_distance(goal.y, angles.y);
+        v.set_angular_velocity(vec3_t::new(dx, dy, 0.0) * (40.0 * frame_time));
+
+        if !self
+            .spawn_flags()
+            .intersects(SpawnFlags::PLAYER_TAKE_CONTROL)
+        {
+            let velocity = v.velocity() * 0.8;
+            if velocity.length() < 10.0 {
+                v.set_velocity(vec3_t::ZERO);
+            } else {
+                v.set_velocity(velocity);
+            }
+        }
+
+        v.set_next_think_time_from_now(0.0);
+        self.move_camera();
+    }
+
+    fn move_camera(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+        let Some(mut path) = self.path.get().get_entity() else {
+            return;
+        };
+
+        let now = engine.globals.map_time();
+        let frame_time = engine.globals.frame_time();
+
+        // subtract movement from the distance left to the next corner
+        let move_distance = self.move_distance.get() - v.speed() * frame_time;
+        self.move_distance.set(move_distance);
+
+        if move_distance <= 0.0 {
+            if let Some(corner) = path.downcast_ref::<PathCorner>() {
+                corner.fire_targets(self);
+            }
+
+            let Some(next) = path.target_entity() else {
+                self.path.set(None);
+                v.set_velocity(vec3_t::ZERO);
+                return;
+            };
+            path = next;
+            self.path.set(Some(path.entity_handle()));
+
+            let path_v = path.vars();
+            if path_v.speed() != 0.0 {
+                self.target_speed.set(path_v.speed());
+            }
+
+            let delta = path_v.origin() - v.origin();
+            self.move_distance.set(delta.length());
+            v.set_move_dir(delta.normalize());
+            self.stop_time.set(now + path_delay(path));
+        }
+
+        let speed = if self.stop_time.get() > now {
+            approach(0.0, v.speed(), self.deceleration * frame_time)
+        } else {
+            let target_speed = self.target_speed.get();
+            approach(target_speed, v.speed(), self.acceleration * frame_time)
+        };
+        v.set_speed(speed);
+
+        let fraction = 2.0 * frame_time;
+        v.set_velocity(v.move_dir() * speed * fraction + v.velocity() * (1.0 - fraction));
+    }
+}
+
+fn path_delay(path: &dyn Entity) -> f32 {
+    path.downcast_ref::<PathCorner>()
+        .map_or(0.0, |corner| corner.delay())
+}
+
+impl Entity for TriggerCamera {
+    delegate_entity!(base not { object_caps, key_value, spawn, used, think });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        match data.key_name().to_bytes() {
+            b"wait" => self.wait = data.parse_or_default(),
+            b"moveto" => self.path_name = Some(self.engine().new_map_string(data.value())),
+            b"acceleration" => self.acceleration = data.parse_or_default(),
+            b"deceleration" => self.deceleration = data.parse_or_default(),
+            _ => {
+                if !self.delayed.key_value(data) {
+                    self.base.key_value(data);
+                }
+                return;
+            }
+        }
+        data.set_handled(true);
+    }
+
+    fn spawn(&mut self) {
+        let v = self.base.vars();
+        v.set_move_type(MoveType::NoClip);
+        v.set_solid(Solid::Not);
+        v.set_render_amount(0.0);
+        v.set_render_mode(RenderMode::TransTexture);
+
+        self.initial_speed = v.speed();
+        if self.acceleration == 0.0 {
+            self.acceleration = 500.0;
+        }
+        if self.deceleration == 0.0 {
+            self.deceleration = 500.0;
+        }
+    }
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        let engine = self.engine();
+        let v = self.vars();
+        let now = engine.globals.map_time();
+
+        if !use_type.should_toggle(self.active.get()) {
+            return;
+        }
+        self.active.set(!self.active.get());
+        if !self.active.get() {
+            self.return_time.set(now);
+            return;
+        }
+
+        let player = match activator {
+            Some(activator) if activator.is_player() => Some(activator),
+            _ => engine.get_single_player().get_entity(),
+        };
+        let Some(player) = player else {
+            warn!("{}: no player to activate the camera", self.pretty_name());
+            return;
+        };
+        self.player.set(Some(player.entity_handle()));
+
+        self.return_time.set(now + self.wait);
+        v.set_speed(self.initial_speed);
+        self.target_speed.set(self.initial_speed);
+
+        let sf = self.spawn_flags();
+        let target = if sf.intersects(SpawnFlags::PLAYER_TARGET) {
+            Some(player)
+        } else {
+            self.target_entity()
+        };
+        self.target.set(target.map(|i| i.entity_handle()));
+        if target.is_none() {
+            return;
+        }
+
+        if sf.intersects(SpawnFlags::PLAYER_TAKE_CONTROL) {
+            self.set_player_control(player, false);
+        }
+
+        let path = self
+            .path_name
+            .and_then(|name| engine.entities().by_target_name(name).first());
+        self.path.set(path);
+
+        self.stop_time.set(now);
+        if let Some(path) = path.get_entity() {
+            let path_v = path.vars();
+            if path_v.speed() != 0.0 {
+                self.target_speed.set(path_v.speed());
+            }
+            self.stop_time.set(now + path_delay(path));
+        }
+
+        let player_v = player.vars();
+        if sf.intersects(SpawnFlags::PLAYER_POSITION) {
+            v.set_origin_and_link(player_v.origin() + player_v.view_ofs());
+            let angles = player_v.angles();
+            v.set_angles(vec3_t::new(-angles.x, angles.y, 0.0));
+            v.set_velocity(player_v.velocity());
+        } else {
+            v.set_velocity(vec3_t::ZERO);
+        }
+
+        engine.set_view(&player, self);
+        if let Some(model) = player_v.model_name() {
+            v.set_model(model);
+        }
+
+        v.set_next_think_time_from_now(0.0);
+        self.move_distance.set(0.0);
+        self.move_camera();
+    }
+
+    fn think(&self) {
+        self.follow_target();
+    }
+}
+
+impl_private!(TriggerCamera {});
+
+define_export! {
+    export_trigger_camera as export if "trigger-camera" {
+        trigger_camera = trigger_camera::TriggerCamera,
+    }
+}
@@ -0,0 +1,63 @@
+use xash3d_server::{
+    entity::{BaseEntity, KeyValue, ObjectCaps, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    str::MapString,
+};
+
+/// Changes the target of the entity named by `target` to `m_iszNewTarget`.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TriggerChangeTarget {
+    base: BaseEntity,
+    new_target: Option<MapString>,
+}
+
+impl CreateEntity for TriggerChangeTarget {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+            new_target: None,
+        }
+    }
+}
+
+impl Entity for TriggerChangeTarget {
+    delegate_entity!(base not { object_caps, key_value, spawn, used });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        if data.key_name() == c"m_iszNewTarget" {
+            self.new_target = Some(self.engine().new_map_string(data.value()));
+            data.set_handled(true);
+        } else {
+            self.base.key_value(data);
+        }
+    }
+
+    fn spawn(&mut self) {}
+
+    fn used(&self, _: UseType, _: Option<&dyn Entity>, _: &dyn Entity) {
+        let Some(target) = self.target_entity() else {
+            return;
+        };
+        trace!(
+            "{}: change target of {}",
+            self.pretty_name(),
+            target.pretty_name()
+        );
+        target.vars().set_target(self.new_target);
+    }
+}
+
+impl_private!(TriggerChangeTarget {});
+
+define_export! {
+    export_trigger_changetarget as export if "trigger-changetarget" {
+        trigger_changetarget = trigger_changetarget::TriggerChangeTarget,
+    }
+}
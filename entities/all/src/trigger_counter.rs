@@ -0,0 +1,154 @@
+use core::{cell::Cell, ffi::CStr};
+
+use bitflags::bitflags;
+use xash3d_server::{
+    entities::{delayed_use::DelayedUse, trigger::Trigger},
+    entity::{BaseEntity, KeyValue, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    str::MapString,
+    utils,
+};
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const NO_MESSAGE = 1 << 0;
+    }
+}
+
+/// Fires targets after it has been used the `count` times.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TriggerCounter {
+    base: Trigger,
+    delayed: DelayedUse,
+    master: Option<MapString>,
+    count: Cell<i32>,
+}
+
+impl CreateEntity for TriggerCounter {
+    fn create(base: BaseEntity) -> Self {
+        let engine = base.engine();
+        Self {
+            base: Trigger::create(base),
+            delayed: DelayedUse::new(engine),
+            master: None,
+            count: Cell::default(),
+        }
+    }
+}
+
+/// Decrements the remaining uses, returns `None` if the counter has already fired.
+fn count_use(count: &Cell<i32>) -> Option<i32> {
+    let remaining = count.get() - 1;
+    count.set(remaining);
+    (remaining >= 0).then_some(remaining)
+}
+
+/// Returns a message for the player who used the counter.
+///
+/// Returns `None` if the counter has completed.
+fn remaining_message(count: i32) -> Option<&'static CStr> {
+    Some(match count {
+        0 => return None,
+        1 => c"Only 1 more to go...",
+        2 => c"Only 2 more to go...",
+        3 => c"Only 3 more to go...",
+        _ => c"Only a few more to go...",
+    })
+}
+
+impl TriggerCounter {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn activate_trigger(&self, activator: Option<&dyn Entity>) {
+        let engine = self.engine();
+        if !utils::is_master_triggered(&engine, self.master, activator) {
+            return;
+        }
+
+        if let Some(noise) = self.vars().noise() {
+            engine.build_sound().channel_voice().emit(noise, self);
+        }
+
+        self.delayed.use_targets(UseType::Toggle, activator, self);
+        self.remove_from_world();
+    }
+}
+
+impl Entity for TriggerCounter {
+    delegate_entity!(base not { key_value, spawn, used });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        match data.key_name().to_bytes() {
+            b"master" => self.master = Some(self.engine().new_map_string(data.value())),
+            b"count" => self.count.set(data.parse_or_default::<f32>() as i32),
+            _ => {
+                if !self.delayed.key_value(data) {
+                    self.base.key_value(data);
+                }
+                return;
+            }
+        }
+        data.set_handled(true);
+    }
+
+    fn spawn(&mut self) {
+        // not a touch trigger, only counts uses
+        if self.count.get() == 0 {
+            self.count.set(2);
+        }
+    }
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        let Some(count) = count_use(&self.count) else {
+            return;
+        };
+
+        if !self.spawn_flags().intersects(SpawnFlags::NO_MESSAGE) {
+            if let Some(player) = activator.and_then(|i| i.as_player()) {
+                match remaining_message(count) {
+                    Some(message) => utils::show_message(player, message),
+                    None => debug!("{}: sequence completed", self.pretty_name()),
+                }
+            }
+        }
+
+        if count == 0 {
+            self.activate_trigger(activator);
+        }
+    }
+}
+
+impl_private!(TriggerCounter {});
+
+define_export! {
+    export_trigger_counter as export if "trigger-counter" {
+        trigger_counter = trigger_counter::TriggerCounter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting() {
+        let count = Cell::new(3);
+        assert_eq!(count_use(&count), Some(2));
+        assert_eq!(count_use(&count), Some(1));
+        assert_eq!(count_use(&count), Some(0));
+        assert_eq!(count_use(&count), None);
+        assert_eq!(count_use(&count), None);
+    }
+
+    #[test]
+    fn messages() {
+        assert_eq!(remaining_message(5), Some(c"Only a few more to go..."));
+        assert_eq!(remaining_message(3), Some(c"Only 3 more to go..."));
+        assert_eq!(remaining_message(1), Some(c"Only 1 more to go..."));
+        assert_eq!(remaining_message(0), None);
+    }
+}
@@ -110,10 +110,24 @@ impl Entity for TriggerHurt {
         let v = self.base.vars();
         let now = engine.globals.map_time();
         let is_multiplayer = global_state.game_rules().is_multiplayer();
+        // each player is hurt once per damage interval in multiplayer
+        let player_mask = if is_player {
+            1_u32.wrapping_shl(other.entity_index().to_u16() as u32 - 1)
+        } else {
+            0
+        };
         if is_multiplayer {
-            let name = self.pretty_name();
-            warn!("{name}: touched is not implemented in multiplayer");
-            return;
+            if v.damage_time() > now {
+                if now != v.pain_finished_time() {
+                    // too early to hurt again and not the same frame with a different entity
+                    if !is_player || v.impulse() & player_mask != 0 {
+                        return;
+                    }
+                    v.with_impulse(|f| f | player_mask);
+                }
+            } else {
+                v.set_impulse(player_mask);
+            }
         } else if now <= v.damage_time() && now != v.pain_finished_time() {
             return;
         }
@@ -124,7 +138,10 @@ impl Entity for TriggerHurt {
                 other.take_health(-dmg, self.damage_type);
             }
         } else {
-            other.take_damage(dmg, self.damage_type, v, None);
+            let multi_damage = global_state.multi_damage();
+            multi_damage.clear();
+            multi_damage.add(v, other, dmg, self.damage_type);
+            multi_damage.apply(v, v);
         }
 
         v.set_pain_finished_time(now);
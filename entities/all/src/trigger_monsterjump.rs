@@ -0,0 +1,90 @@
+use xash3d_server::{
+    entities::trigger::Trigger,
+    entity::{BaseEntity, EdictFlags, KeyValue, Solid, UseType, delegate_entity},
+    ffi::common::vec3_t,
+    prelude::*,
+    private::impl_private,
+};
+
+/// Makes monsters jump in the `angles` direction.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TriggerMonsterJump {
+    base: Trigger,
+    height: f32,
+}
+
+impl CreateEntity for TriggerMonsterJump {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: Trigger::create(base),
+            height: 0.0,
+        }
+    }
+}
+
+impl Entity for TriggerMonsterJump {
+    delegate_entity!(base not { key_value, spawn, used, touched, think });
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        if data.key_name() == c"height" {
+            self.height = data.parse_or_default();
+            data.set_handled(true);
+        } else {
+            self.base.key_value(data);
+        }
+    }
+
+    fn spawn(&mut self) {
+        self.base.vars().set_move_dir_from_angles();
+        self.base.spawn();
+
+        let v = self.base.vars();
+        if v.speed() == 0.0 {
+            v.set_speed(200.0);
+        }
+        if self.height == 0.0 {
+            self.height = 150.0;
+        }
+
+        if v.target_name().is_some() {
+            // spawn turned off if targeted
+            v.set_solid(Solid::Not);
+        }
+        v.link();
+    }
+
+    fn used(&self, _: UseType, _: Option<&dyn Entity>, _: &dyn Entity) {
+        if self.vars().target_name().is_some() {
+            self.base.toggle_use();
+        }
+    }
+
+    fn touched(&self, other: &dyn Entity) {
+        let other_v = other.vars();
+        if !other_v.flags().intersects(EdictFlags::MONSTER) {
+            return;
+        }
+
+        let v = self.vars();
+        other_v.set_origin(other_v.origin() + vec3_t::Z);
+        other_v.with_flags(|f| f.difference(EdictFlags::ONGROUND));
+        other_v.set_velocity(v.move_dir() * v.speed() + vec3_t::Z * self.height);
+
+        // turn off the trigger in the next frame
+        v.set_next_think_time_from_now(0.0);
+    }
+
+    fn think(&self) {
+        let v = self.vars();
+        v.set_solid(Solid::Not);
+        v.link();
+    }
+}
+
+impl_private!(TriggerMonsterJump {});
+
+define_export! {
+    export_trigger_monsterjump as export if "trigger-monsterjump" {
+        trigger_monsterjump = trigger_monsterjump::TriggerMonsterJump,
+    }
+}
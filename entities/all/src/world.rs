@@ -4,6 +4,7 @@ use bitflags::bitflags;
 use xash3d_server::{
     entity::{delegate_entity, BaseEntity, KeyValue},
     global_state::{decals::DefaultDecals, sprites::DefaultSprites, GlobalStateRef},
+    node_graph::NodeGraph,
     prelude::*,
     private::impl_private,
 };
@@ -184,7 +185,7 @@ impl Entity for World {
 
         global_state.set_decals(DefaultDecals::new(engine));
 
-        // TODO: init world graph
+        global_state.get_or_default::<NodeGraph>().init();
 
         let v = self.vars();
         let zmax = if v.speed() > 0.0 { v.speed() } else { 4096.0 };
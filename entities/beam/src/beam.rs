@@ -6,8 +6,8 @@ use xash3d_server::{
     csz::CStrThin,
     engine::TraceResult,
     entity::{
-        BaseEntity, BeamEntity, EdictFlags, EntityHandle, EntityIndex, EntityVars, ObjectCaps,
-        TakeDamage, delegate_entity,
+        BaseEntity, BeamEntity, DamageFlags, EdictFlags, EntityHandle, EntityIndex, EntityVars,
+        ObjectCaps, delegate_entity,
     },
     ffi::common::vec3_t,
     prelude::*,
@@ -342,17 +342,16 @@ impl Beam {
         let now = self.engine().globals.map_time();
         if trace.fraction() != 1.0 {
             if let Some(hit) = trace.hit_entity().get_entity() {
-                // TODO: do beam damage
-                // TODO: multi damage
-
-                if hit.vars().take_damage() != TakeDamage::No {
-                    let name = self.pretty_name();
-                    warn!("{name}: beam damage is not implemented yet");
-                }
+                let global_state = self.global_state();
+                let multi_damage = global_state.multi_damage();
+                let damage = v.damage() * (now - v.damage_time()).as_secs_f32();
+                let dir = (trace.end_position() - v.origin()).normalize();
+                multi_damage.clear();
+                hit.trace_attack(v, damage, dir, trace, DamageFlags::ENERGYBEAM);
+                multi_damage.apply(v, v);
 
                 let sf = self.spawn_flags();
                 if sf.intersects(SpawnFlags::DECALS) && hit.is_bsp_model() {
-                    let global_state = self.global_state();
                     let decals = global_state.decals();
                     utils::decal_trace(&engine, trace, decals.get_random_bigshot());
                 }
@@ -509,3 +509,33 @@ macro_rules! export_env_beam {
 }
 #[doc(inline)]
 pub use export_env_beam as export;
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::testing::{self, RoundTrip};
+
+    use super::{EnvBeam, Think, Used};
+
+    #[test]
+    fn round_trip() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let sprite_name = engine.new_map_string(c"sprites/laserbeam.spr");
+        let start_entity = engine.new_map_string(c"beam_start");
+        let result = RoundTrip::new().time(1.0).entity::<EnvBeam>(|beam| {
+            beam.sprite_name = Some(sprite_name);
+            beam.start_entity = Some(start_entity);
+            beam.radius = 256.0;
+            beam.life = 0.5;
+            beam.bolt_width = 20;
+            beam.noise_amplitude = 8;
+            beam.scroll_speed = 35;
+            beam.start_frame = 1;
+            beam.restrike = -1.0;
+            beam.active.set(true);
+            beam.think.set(Think::Strike);
+            beam.used.set(Used::Toggle);
+        });
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -44,11 +44,11 @@ impl Laser {
         self.base.spawn_flags()
     }
 
-    fn is_on(&self) -> bool {
+    pub fn is_on(&self) -> bool {
         !self.vars().effects().intersects(Effects::NODRAW)
     }
 
-    fn turn_off(&self) {
+    pub fn turn_off(&self) {
         if let Some(sprite) = self.sprite.downcast_ref::<Sprite>() {
             sprite.turn_off();
         }
@@ -57,7 +57,7 @@ impl Laser {
         v.stop_thinking();
     }
 
-    fn turn_on(&self) {
+    pub fn turn_on(&self) {
         if let Some(sprite) = self.sprite.downcast_ref::<Sprite>() {
             sprite.turn_on();
         }
@@ -68,7 +68,7 @@ impl Laser {
         v.set_next_think_time(now);
     }
 
-    fn fire_at_point(&self, trace: &TraceResult) {
+    pub fn fire_at_point(&self, trace: &TraceResult) {
         let end = trace.end_position();
         self.base.set_end_pos(end);
         if let Some(sprite) = self.sprite {
@@ -0,0 +1,718 @@
+use core::{cell::Cell, ffi::CStr};
+
+use bitflags::bitflags;
+use res::valve::{
+    models,
+    sound::{buttons, debris},
+};
+use xash3d_server::{
+    damage,
+    engine::TraceResult,
+    entities::delayed_use::DelayedUse,
+    entity::{
+        BaseEntity, DamageFlags, EdictFlags, EntityVars, Gib, KeyValue, MoveType, ObjectCaps,
+        Solid, TakeDamage, UseType, create_entity, delegate_entity,
+    },
+    ffi::common::vec3_t,
+    math::fabsf,
+    prelude::*,
+    private::impl_private,
+    render::RenderMode,
+    str::MapString,
+    user_message::{self, BreakModelFlags},
+    utils::{self, Sparks},
+};
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    pub(crate) struct SpawnFlags: u32 {
+        /// May only be broken by a trigger.
+        const TRIGGER_ONLY  = 1 << 0;
+        /// Can be crashed through by a running player.
+        const TOUCH         = 1 << 1;
+        /// Can be broken by a player standing on it.
+        const PRESSURE      = 1 << 2;
+        /// Only for func_pushable.
+        const BREAKABLE     = 1 << 7;
+        /// Instant break if hit with a crowbar.
+        const CROWBAR       = 1 << 8;
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum Material {
+    Glass = 0,
+    #[default]
+    Wood,
+    Metal,
+    Flesh,
+    CinderBlock,
+    CeilingTile,
+    Computer,
+    UnbreakableGlass,
+    Rocks,
+    None,
+}
+
+impl Material {
+    fn from_raw(raw: i32) -> Option<Self> {
+        Some(match raw {
+            0 => Self::Glass,
+            1 => Self::Wood,
+            2 => Self::Metal,
+            3 => Self::Flesh,
+            4 => Self::CinderBlock,
+            5 => Self::CeilingTile,
+            6 => Self::Computer,
+            7 => Self::UnbreakableGlass,
+            8 => Self::Rocks,
+            9 => Self::None,
+            _ => return None,
+        })
+    }
+
+    fn gib_model(&self) -> Option<&'static CStr> {
+        Some(match self {
+            Self::Glass | Self::UnbreakableGlass => models::GLASSGIBS,
+            Self::Wood => models::WOODGIBS,
+            Self::Metal => models::METALPLATEGIBS,
+            Self::Flesh => models::FLESHGIBS,
+            Self::CinderBlock => models::CINDERGIBS,
+            Self::CeilingTile => models::CEILINGGIBS,
+            Self::Computer => models::COMPUTERGIBS,
+            Self::Rocks => models::ROCKGIBS,
+            Self::None => return None,
+        })
+    }
+
+    /// Sounds played when the object takes damage.
+    fn damage_sounds(&self) -> &'static [&'static CStr] {
+        match self {
+            Self::Glass | Self::UnbreakableGlass | Self::Computer => {
+                &[debris::GLASS1, debris::GLASS2, debris::GLASS3]
+            }
+            Self::Wood => &[debris::WOOD1, debris::WOOD2, debris::WOOD3],
+            Self::Metal => &[debris::METAL1, debris::METAL2, debris::METAL3],
+            Self::Flesh => &[
+                debris::FLESH1,
+                debris::FLESH2,
+                debris::FLESH3,
+                debris::FLESH5,
+                debris::FLESH6,
+                debris::FLESH7,
+            ],
+            Self::CinderBlock | Self::Rocks => {
+                &[debris::CONCRETE1, debris::CONCRETE2, debris::CONCRETE3]
+            }
+            Self::CeilingTile | Self::None => &[],
+        }
+    }
+
+    /// Sounds played when the object breaks.
+    fn break_sounds(&self) -> &'static [&'static CStr] {
+        match self {
+            Self::Glass | Self::UnbreakableGlass => &[debris::BUSTGLASS1, debris::BUSTGLASS2],
+            Self::Wood => &[debris::BUSTCRATE1, debris::BUSTCRATE2],
+            Self::Metal | Self::Computer => &[debris::BUSTMETAL1, debris::BUSTMETAL2],
+            Self::Flesh => &[debris::BUSTFLESH1, debris::BUSTFLESH2],
+            Self::CinderBlock | Self::Rocks => &[debris::BUSTCONCRETE1, debris::BUSTCONCRETE2],
+            Self::CeilingTile => &[debris::BUSTCEILING],
+            Self::None => &[],
+        }
+    }
+
+    fn break_flags(&self) -> BreakModelFlags {
+        match self {
+            Self::Glass => BreakModelFlags::GLASS,
+            Self::Wood => BreakModelFlags::WOOD,
+            Self::Metal | Self::Computer => BreakModelFlags::METAL,
+            Self::Flesh => BreakModelFlags::FLESH,
+            Self::CinderBlock | Self::Rocks => BreakModelFlags::CONCRETE,
+            _ => BreakModelFlags::empty(),
+        }
+    }
+}
+
+/// Items that can be spawned when the object breaks.
+const SPAWN_OBJECTS: &[&CStr] = &[
+    c"",
+    c"item_battery",
+    c"item_healthkit",
+    c"weapon_9mmhandgun",
+    c"ammo_9mmclip",
+    c"weapon_9mmAR",
+    c"ammo_9mmAR",
+    c"ammo_ARgrenades",
+    c"weapon_shotgun",
+    c"ammo_buckshot",
+    c"weapon_crossbow",
+    c"ammo_crossbow",
+    c"weapon_357",
+    c"ammo_357",
+    c"weapon_rpg",
+    c"ammo_rpgclip",
+    c"ammo_gaussclip",
+    c"weapon_handgrenade",
+    c"weapon_tripmine",
+    c"weapon_satchel",
+    c"weapon_snark",
+    c"weapon_hornetgun",
+];
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+enum Think {
+    #[default]
+    None,
+    Die,
+}
+
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Breakable {
+    base: BaseEntity,
+    delayed: DelayedUse,
+    material: Material,
+    /// Throw the gibs in the attack direction.
+    directed: bool,
+    /// Gib model index.
+    #[cfg_attr(feature = "save", save(skip))]
+    shard: u16,
+    angle: f32,
+    gib_model: Option<MapString>,
+    spawn_object: Option<MapString>,
+    explode_magnitude: u16,
+    attack_dir: Cell<vec3_t>,
+    touch_enabled: Cell<bool>,
+    think: Cell<Think>,
+}
+
+impl CreateEntity for Breakable {
+    fn create(base: BaseEntity) -> Self {
+        let engine = base.engine();
+        Self {
+            base,
+            delayed: DelayedUse::new(engine),
+            material: Material::default(),
+            directed: false,
+            shard: 0,
+            angle: 0.0,
+            gib_model: None,
+            spawn_object: None,
+            explode_magnitude: 0,
+            attack_dir: Cell::new(vec3_t::ZERO),
+            touch_enabled: Cell::new(false),
+            think: Cell::default(),
+        }
+    }
+}
+
+impl Breakable {
+    pub(crate) fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    pub fn is_breakable(&self) -> bool {
+        self.material != Material::UnbreakableGlass
+    }
+
+    fn damage_sound(&self) {
+        let engine = self.engine();
+        let pitch = if engine.random_int(0, 2) != 0 {
+            100
+        } else {
+            95 + engine.random_int(0, 34)
+        };
+        let volume = engine.random_float(0.75, 1.0);
+
+        let mut material = self.material;
+        if material == Material::Computer && engine.random_int(0, 1) != 0 {
+            material = Material::Metal;
+        }
+
+        let sounds = material.damage_sounds();
+        if sounds.is_empty() {
+            return;
+        }
+        let sample = sounds[engine.random_int(0, sounds.len() as i32 - 1) as usize];
+        engine
+            .build_sound()
+            .channel_voice()
+            .volume(volume)
+            .pitch(pitch)
+            .emit_dyn(sample, self);
+    }
+
+    fn break_sound(&self) {
+        let engine = self.engine();
+        let mut pitch = 95 + engine.random_int(0, 29);
+        if pitch > 97 && pitch < 103 {
+            pitch = 100;
+        }
+        // the more negative health, the louder the sound should be
+        let volume = engine.random_float(0.85, 1.0) + fabsf(self.vars().health()) / 100.0;
+
+        let sounds = self.material.break_sounds();
+        if sounds.is_empty() {
+            return;
+        }
+        let sample = sounds[engine.random_int(0, sounds.len() as i32 - 1) as usize];
+        engine
+            .build_sound()
+            .channel_voice()
+            .volume(volume.min(1.0))
+            .pitch(pitch)
+            .emit_dyn(sample, self);
+    }
+
+    /// Drops entities standing on top of this object.
+    fn clear_ground_entities(&self) {
+        let v = self.vars();
+        let mut min = v.abs_min();
+        let mut max = v.abs_max();
+        min.z = max.z;
+        max.z += 8.0;
+        let center = (min + max) * 0.5;
+        let radius = (max - min).length() * 0.5;
+        for i in self.engine().entities().in_sphere(center, radius) {
+            let ev = i.vars();
+            if !ev.flags().intersects(EdictFlags::ONGROUND) {
+                continue;
+            }
+            let (a, b) = (ev.abs_min(), ev.abs_max());
+            if a.x > max.x
+                || a.y > max.y
+                || a.z > max.z
+                || b.x < min.x
+                || b.y < min.y
+                || b.z < min.z
+            {
+                continue;
+            }
+            ev.with_flags(|f| f.difference(EdictFlags::ONGROUND));
+            ev.set_ground_entity(None::<&EntityVars>);
+        }
+    }
+
+    fn explode(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+        let magnitude = self.explode_magnitude as f32;
+        let center = v.abs_center();
+
+        let scale = (self.explode_magnitude as i32 / 10 - 5) as f32 * 0.6;
+        let msg = user_message::Explosion {
+            position: center.into(),
+            sprite_index: self.global_state().sprites().fireball(),
+            scale: (scale as u8).max(1).into(),
+            frame_rate: 15,
+            flags: user_message::ExplosionFlags::NONE,
+        };
+        engine.msg_pas(center, &msg);
+
+        damage::radius_damage(
+            &engine,
+            center,
+            v,
+            Some(v),
+            magnitude,
+            magnitude * 2.5,
+            DamageFlags::BLAST,
+        );
+    }
+
+    /// Smashes this object.
+    pub fn die(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+
+        self.break_sound();
+
+        let velocity = if self.directed {
+            self.attack_dir.get() * 200.0
+        } else {
+            vec3_t::ZERO
+        };
+        let position = v.origin() + (v.min_size() + v.max_size()) * 0.5;
+        let msg = user_message::BreakModel {
+            position: position.into(),
+            size: v.size().into(),
+            velocity: velocity.into(),
+            random_velocity: 100_u32.into(),
+            model_index: self.shard,
+            // let client decide
+            count: 0,
+            duration: 2.5_f32.into(),
+            flags: self.material.break_flags(),
+        };
+        engine.msg_pvs(position, &msg);
+
+        self.clear_ground_entities();
+
+        // do not fire something that could fire this object
+        v.set_target_name(None);
+        v.set_solid(Solid::Not);
+        v.set_take_damage(TakeDamage::No);
+        self.touch_enabled.set(false);
+        self.think.set(Think::None);
+
+        self.delayed.use_targets(UseType::Toggle, None, self);
+
+        if let Some(spawn_object) = self.spawn_object {
+            let owner = Some(v.entity_handle());
+            let name = spawn_object.as_c_str();
+            create_entity(&engine, name, v.bmodel_origin(), v.angles(), owner).ok();
+        }
+
+        if self.explode_magnitude != 0 {
+            self.explode();
+        }
+
+        v.delayed_remove();
+    }
+
+    fn break_touch(&self, other: &dyn Entity) {
+        // only players can break these right now
+        if !other.is_player() || !self.is_breakable() {
+            return;
+        }
+
+        let sf = self.spawn_flags();
+        let ov = other.vars();
+        if sf.intersects(SpawnFlags::TOUCH) {
+            // can be broken when run into
+            let damage = ov.velocity().length() * 0.01;
+            if damage >= self.vars().health() {
+                self.touch_enabled.set(false);
+                self.take_damage(damage, DamageFlags::CRUSH, ov, Some(ov));
+                // do a little damage to player if we broke glass or computer
+                let v = self.vars();
+                other.take_damage(damage / 4.0, DamageFlags::SLASH, v, Some(v));
+            }
+        }
+
+        let v = self.vars();
+        if sf.intersects(SpawnFlags::PRESSURE) && ov.abs_min().z >= v.max_size().z - 2.0 {
+            // can be broken when stood upon
+            self.damage_sound();
+            self.touch_enabled.set(false);
+            self.think.set(Think::Die);
+            let delay = self.delayed.delay();
+            v.set_next_think_time_from_last(if delay != 0.0 { delay } else { 0.1 });
+        }
+    }
+}
+
+impl Entity for Breakable {
+    delegate_entity!(base not {
+        object_caps, key_value, precache, spawn, think, touched, used, take_damage, trace_attack,
+        killed,
+    });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        if self.delayed.key_value(data) {
+            return;
+        }
+
+        let engine = self.engine();
+        match data.key_name().to_bytes() {
+            b"explosion" => {
+                self.directed = data.value().to_bytes().eq_ignore_ascii_case(b"directed");
+            }
+            b"material" => {
+                let raw = data.parse_or_default();
+                self.material = Material::from_raw(raw).unwrap_or_else(|| {
+                    warn!("{}: invalid material {raw}", self.pretty_name());
+                    Material::default()
+                });
+            }
+            b"gibmodel" => self.gib_model = Some(engine.new_map_string(data.value())),
+            b"spawnobject" => {
+                let index = data.parse_or_default::<usize>();
+                if index > 0 && index < SPAWN_OBJECTS.len() {
+                    self.spawn_object = Some(engine.new_map_string(SPAWN_OBJECTS[index]));
+                }
+            }
+            b"explodemagnitude" => self.explode_magnitude = data.parse_or_default(),
+            // explicitly ignored
+            b"deadmodel" | b"shards" | b"lip" => {}
+            _ => return self.base.key_value(data),
+        }
+        data.set_handled(true);
+    }
+
+    fn precache(&mut self) {
+        let engine = self.engine();
+
+        if self.material == Material::Computer {
+            engine.precache_sound(buttons::SPARK5);
+            engine.precache_sound(buttons::SPARK6);
+        }
+
+        let sounds = self.material.damage_sounds();
+        for &sound in sounds.iter().chain(self.material.break_sounds()) {
+            engine.precache_sound(sound);
+        }
+
+        if let Some(gib_model) = self.gib_model {
+            self.shard = engine.precache_model(gib_model) as u16;
+        } else if let Some(gib_model) = self.material.gib_model() {
+            self.shard = engine.precache_model(gib_model) as u16;
+        }
+
+        if let Some(spawn_object) = self.spawn_object {
+            utils::precache_other(&engine, spawn_object);
+        }
+    }
+
+    fn spawn(&mut self) {
+        self.precache();
+
+        let sf = self.spawn_flags();
+        let v = self.base.vars();
+        if sf.intersects(SpawnFlags::TRIGGER_ONLY) {
+            v.set_take_damage(TakeDamage::No);
+        } else {
+            v.set_take_damage(TakeDamage::Yes);
+        }
+        v.set_solid(Solid::Bsp);
+        v.set_move_type(MoveType::Push);
+        self.angle = v.angles().y;
+        v.with_angles(|a| a.with_y(0.0));
+
+        // glass can receive decals, the client uses the class to know about this
+        if self.material == Material::Glass {
+            v.set_player_class(1);
+        }
+
+        v.reload_model();
+
+        // only break on trigger
+        self.touch_enabled
+            .set(!sf.intersects(SpawnFlags::TRIGGER_ONLY));
+
+        // unbreakable glass blocks all tracelines
+        if !self.is_breakable() && v.render_mode() != RenderMode::Normal {
+            v.with_flags(|f| f | EdictFlags::WORLDBRUSH);
+        }
+    }
+
+    fn think(&self) {
+        match self.think.get() {
+            Think::None => {}
+            Think::Die => self.die(),
+        }
+    }
+
+    fn touched(&self, other: &dyn Entity) {
+        if self.touch_enabled.get() {
+            self.break_touch(other);
+        }
+    }
+
+    fn used(&self, _: UseType, _: Option<&dyn Entity>, _: &dyn Entity) {
+        if self.is_breakable() {
+            let v = self.vars();
+            v.with_angles(|a| a.with_y(self.angle));
+            self.attack_dir.set(v.angles().angle_vectors().forward());
+            self.die();
+        }
+    }
+
+    fn take_damage(
+        &self,
+        mut damage: f32,
+        damage_type: DamageFlags,
+        inflictor: &EntityVars,
+        attacker: Option<&EntityVars>,
+    ) -> bool {
+        let v = self.vars();
+
+        // if a client hit the breakable with a crowbar and the breakable is
+        // crowbar-sensitive, break it now
+        if let Some(attacker) = attacker {
+            if attacker.entity_handle() == inflictor.entity_handle()
+                && attacker.flags().intersects(EdictFlags::CLIENT)
+                && self.spawn_flags().intersects(SpawnFlags::CROWBAR)
+                && damage_type.intersects(DamageFlags::CLUB)
+            {
+                damage = v.health();
+            }
+        }
+
+        if !self.is_breakable() {
+            return false;
+        }
+
+        // breakables take double damage from the crowbar
+        if damage_type.intersects(DamageFlags::CLUB) {
+            damage *= 2.0;
+        }
+
+        // boxes, glass, etc. do not take much poison damage, just the impact of the dart
+        if damage_type.intersects(DamageFlags::POISON) {
+            damage *= 0.1;
+        }
+
+        let dir = inflictor.origin() - v.bmodel_origin();
+        self.attack_dir.set(dir.normalize());
+
+        v.with_health(|health| health - damage);
+        if v.health() <= 0.0 {
+            self.killed(attacker.unwrap_or(inflictor), Gib::Normal);
+            return false;
+        }
+
+        // do not play shard noise if the object actually died
+        self.damage_sound();
+
+        true
+    }
+
+    fn trace_attack(
+        &self,
+        attacker: &EntityVars,
+        damage: f32,
+        dir: vec3_t,
+        trace: &TraceResult,
+        damage_type: DamageFlags,
+    ) {
+        let engine = self.engine();
+        if engine.random_int(0, 1) != 0 {
+            match self.material {
+                Material::Computer => {
+                    Sparks::new(engine).emit_simple(trace.end_position());
+                    let sample = if engine.random_int(0, 1) != 0 {
+                        buttons::SPARK5
+                    } else {
+                        buttons::SPARK6
+                    };
+                    engine
+                        .build_sound()
+                        .channel_voice()
+                        .volume(engine.random_float(0.7, 1.0))
+                        .emit(sample, self);
+                }
+                Material::UnbreakableGlass => {
+                    let msg = user_message::ArmorRicochet {
+                        position: trace.end_position().into(),
+                        scale: engine.random_float(0.5, 1.5).into(),
+                    };
+                    engine.msg_pas(trace.end_position(), &msg);
+                }
+                _ => {}
+            }
+        }
+
+        self.base
+            .trace_attack(attacker, damage, dir, trace, damage_type);
+    }
+
+    fn killed(&self, _: &EntityVars, _: Gib) {
+        self.die();
+    }
+}
+
+impl_private!(Breakable {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_breakable {
+    () => {
+        $crate::export_entity!(func_breakable, $crate::func_breakable::Breakable);
+    };
+}
+#[doc(inline)]
+pub use export_func_breakable as export;
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{
+        entity::{DamageFlags, TakeDamage},
+        prelude::*,
+        testing,
+    };
+
+    use super::{Breakable, Material, SpawnFlags};
+
+    #[test]
+    fn damage_by_type() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let breakable = engine.new_entity::<Breakable>().build();
+        // no damage sounds to emit
+        breakable.material = Material::None;
+        let v = breakable.vars();
+        v.set_health(100.0);
+
+        assert!(breakable.take_damage(10.0, DamageFlags::CLUB, v, None));
+        assert_eq!(v.health(), 80.0);
+        assert!(breakable.take_damage(10.0, DamageFlags::POISON, v, None));
+        assert_eq!(v.health(), 79.0);
+        assert!(breakable.take_damage(10.0, DamageFlags::BULLET, v, None));
+        assert_eq!(v.health(), 69.0);
+
+        unsafe {
+            engine.remove_entity_now(v);
+        }
+    }
+
+    #[test]
+    fn unbreakable_glass() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let breakable = engine.new_entity::<Breakable>().build();
+        breakable.material = Material::UnbreakableGlass;
+        let v = breakable.vars();
+        v.set_health(100.0);
+
+        assert!(!breakable.is_breakable());
+        assert!(!breakable.take_damage(200.0, DamageFlags::BULLET, v, None));
+        assert_eq!(v.health(), 100.0);
+
+        unsafe {
+            engine.remove_entity_now(v);
+        }
+    }
+
+    #[test]
+    fn spawn_by_material() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let glass = engine.new_entity::<Breakable>().build();
+        glass.material = Material::Glass;
+        glass.spawn();
+        let v = glass.vars();
+        assert_eq!(v.take_damage(), TakeDamage::Yes);
+        // the client allows decals on glass
+        assert_eq!(v.player_class(), 1);
+        assert!(glass.touch_enabled.get());
+
+        let trigger = engine.new_entity::<Breakable>().build();
+        trigger
+            .vars()
+            .set_spawn_flags(SpawnFlags::TRIGGER_ONLY.bits());
+        trigger.spawn();
+        let v = trigger.vars();
+        assert_eq!(v.take_damage(), TakeDamage::No);
+        assert_eq!(v.player_class(), 0);
+        assert!(!trigger.touch_enabled.get());
+
+        unsafe {
+            engine.remove_entity_now(glass.vars());
+            engine.remove_entity_now(trigger.vars());
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use core::{cell::Cell, ffi::CStr};
+
+use res::valve::sound::debris;
+use xash3d_server::{
+    entity::{
+        BaseEntity, Buttons, DamageFlags, EdictFlags, EntityVars, KeyValue, MoveType, ObjectCaps,
+        Solid, UseType, WaterLevel, delegate_entity,
+    },
+    ffi::common::vec3_t,
+    math::sqrtf,
+    prelude::*,
+    private::impl_private,
+    time::MapTime,
+};
+
+use crate::func_breakable::{Breakable, SpawnFlags};
+
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Pushable {
+    base: Breakable,
+    max_speed: f32,
+    sound_time: Cell<MapTime>,
+    /// Keeps the same sound from playing twice in a row.
+    #[cfg_attr(feature = "save", save(skip))]
+    last_sound: Cell<usize>,
+}
+
+impl CreateEntity for Pushable {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: Breakable::create(base),
+            max_speed: 0.0,
+            sound_time: Cell::new(MapTime::ZERO),
+            last_sound: Cell::new(0),
+        }
+    }
+}
+
+impl Pushable {
+    const SOUNDS: [&'static CStr; 3] = [debris::PUSHBOX1, debris::PUSHBOX2, debris::PUSHBOX3];
+
+    fn is_breakable(&self) -> bool {
+        self.base.spawn_flags().intersects(SpawnFlags::BREAKABLE)
+    }
+
+    fn push(&self, other: &dyn Entity, push: bool) {
+        let engine = self.engine();
+        let v = self.vars();
+        let ov = other.vars();
+
+        // is the entity standing on this pushable?
+        if ov.flags().intersects(EdictFlags::ONGROUND)
+            && ov.ground_entity() == Some(v.entity_handle())
+        {
+            // only push if floating
+            if v.water_level() != WaterLevel::Dry {
+                v.set_velocity(v.velocity().with_z(v.velocity().z + ov.velocity().z * 0.1));
+            }
+            return;
+        }
+
+        let player_touch = other.is_player();
+        if player_touch && push && !ov.buttons().intersects(Buttons::FORWARD | Buttons::USE) {
+            // do not push unless the player is pushing forward and not pulling
+            return;
+        }
+
+        let factor = if !player_touch {
+            0.25
+        } else if ov.flags().intersects(EdictFlags::ONGROUND) {
+            1.0
+        } else if v.water_level() != WaterLevel::Dry {
+            0.1
+        } else {
+            // do not push away from jumping or falling players unless in water
+            return;
+        };
+
+        let mut velocity = v.velocity();
+        velocity.x += ov.velocity().x * factor;
+        velocity.y += ov.velocity().y * factor;
+
+        let length = sqrtf(velocity.x * velocity.x + velocity.y * velocity.y);
+        if push && length > self.max_speed {
+            velocity.x = velocity.x * self.max_speed / length;
+            velocity.y = velocity.y * self.max_speed / length;
+        }
+        v.set_velocity(velocity);
+
+        if !player_touch {
+            return;
+        }
+
+        ov.set_velocity(vec3_t::new(velocity.x, velocity.y, ov.velocity().z));
+
+        let now = engine.globals.map_time();
+        if (now - self.sound_time.get()).as_secs_f32() > 0.7 {
+            self.sound_time.set(now);
+            if length > 0.0 && v.flags().intersects(EdictFlags::ONGROUND) {
+                let index = engine.random_int(0, Self::SOUNDS.len() as i32 - 1) as usize;
+                self.last_sound.set(index);
+                engine
+                    .build_sound()
+                    .channel_weapon()
+                    .volume(0.5)
+                    .emit(Self::SOUNDS[index], v);
+            } else {
+                engine
+                    .build_sound()
+                    .channel_weapon()
+                    .stop(Self::SOUNDS[self.last_sound.get()], v);
+            }
+        }
+    }
+}
+
+impl Entity for Pushable {
+    delegate_entity!(base not { object_caps, key_value, precache, spawn, touched, used, take_damage });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base.object_caps().union(ObjectCaps::CONTINUOUS_USE)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        let v = self.vars();
+        match data.key_name().to_bytes() {
+            b"size" => {
+                let config = xash3d_player_move::movement_config();
+                match data.parse_or_default::<i32>() {
+                    // point
+                    0 => v.set_size_and_link(vec3_t::splat(-8.0), vec3_t::splat(8.0)),
+                    // big hull
+                    2 => {
+                        v.set_size_and_link(config.duck_hull_min * 2.0, config.duck_hull_max * 2.0)
+                    }
+                    // player duck
+                    3 => v.set_size_and_link(config.duck_hull_min, config.duck_hull_max),
+                    // player
+                    _ => v.set_size_and_link(config.hull_min, config.hull_max),
+                }
+            }
+            b"buoyancy" => v.set_skin(data.parse_or_default::<f32>() as i32),
+            _ => return self.base.key_value(data),
+        }
+        data.set_handled(true);
+    }
+
+    fn precache(&mut self) {
+        let engine = self.engine();
+        for sound in Self::SOUNDS {
+            engine.precache_sound(sound);
+        }
+
+        if self.is_breakable() {
+            self.base.precache();
+        }
+    }
+
+    fn spawn(&mut self) {
+        if self.is_breakable() {
+            self.base.spawn();
+        }
+        self.precache();
+
+        let v = self.vars();
+        v.set_move_type(MoveType::PushStep);
+        v.set_solid(Solid::BBox);
+        v.reload_model();
+
+        let friction = v.friction().min(399.0);
+        self.max_speed = 400.0 - friction;
+        v.with_flags(|f| f | EdictFlags::FLOAT);
+        v.set_friction(0.0);
+
+        // pick up off of the floor
+        v.set_origin_and_link(v.origin().with_z(v.origin().z + 1.0));
+
+        // multiply by area of the box's cross-section (assume 1000 units^3 standard volume)
+        let size = v.max_size() - v.min_size();
+        let skin = v.skin() as f32 * size.x * size.y * 0.0005;
+        v.set_skin(skin as i32);
+
+        self.sound_time.set(MapTime::ZERO);
+    }
+
+    fn touched(&self, other: &dyn Entity) {
+        if other.is_classname(c"worldspawn".into()) {
+            return;
+        }
+        self.push(other, true);
+    }
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, caller: &dyn Entity) {
+        match activator {
+            Some(activator) if activator.is_player() => {
+                if activator.vars().velocity() != vec3_t::ZERO {
+                    self.push(activator, false);
+                }
+            }
+            _ => {
+                if self.is_breakable() {
+                    self.base.used(use_type, activator, caller);
+                }
+            }
+        }
+    }
+
+    fn take_damage(
+        &self,
+        damage: f32,
+        damage_type: DamageFlags,
+        inflictor: &EntityVars,
+        attacker: Option<&EntityVars>,
+    ) -> bool {
+        if self.is_breakable() {
+            return self
+                .base
+                .take_damage(damage, damage_type, inflictor, attacker);
+        }
+        true
+    }
+}
+
+impl_private!(Pushable {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_pushable {
+    () => {
+        $crate::export_entity!(func_pushable, $crate::func_pushable::Pushable);
+    };
+}
+#[doc(inline)]
+pub use export_func_pushable as export;
@@ -0,0 +1,10 @@
+#![no_std]
+
+#[macro_use]
+extern crate log;
+
+pub mod func_breakable;
+pub mod func_pushable;
+
+#[doc(hidden)]
+pub use xash3d_server::export::export_entity;
@@ -107,3 +107,25 @@ macro_rules! export_func_button {
 }
 #[doc(inline)]
 pub use export_func_button as export;
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{
+        ffi::common::vec3_t,
+        testing::{self, RoundTrip},
+    };
+
+    use super::Button;
+
+    #[test]
+    fn round_trip() {
+        let _test = testing::lock();
+        let result = RoundTrip::new().time(2.0).entity::<Button>(|button| {
+            let button_move = &mut button.base.button_move;
+            button_move.set_lip(4.0);
+            button_move.set_start(vec3_t::new(16.0, 0.0, 32.0));
+            button_move.set_end(vec3_t::new(20.0, 0.0, 32.0));
+        });
+        assert_eq!(result, Ok(()));
+    }
+}
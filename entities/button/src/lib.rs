@@ -7,6 +7,7 @@ mod base_button;
 
 pub mod func_button;
 pub mod func_rot_button;
+pub mod momentary_rot_button;
 
 #[doc(hidden)]
 pub use xash3d_server::export::export_entity;
@@ -0,0 +1,344 @@
+use core::cell::Cell;
+
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{BaseEntity, KeyValue, MoveType, ObjectCaps, Solid, UseType, delegate_entity},
+    ffi::common::vec3_t,
+    prelude::*,
+    private::impl_private,
+    sound::{LockSounds, button_sound_or_default},
+    str::MapString,
+    utils::{self, AngularMove, Move},
+};
+
+bitflags! {
+    #[derive(Copy, Clone, Debug)]
+    struct SpawnFlags: u32 {
+        /// Solid and can not be used by players directly.
+        const DOOR          = 1 << 0;
+        const AUTO_RETURN   = 1 << 4;
+        const ROTATE_Z      = 1 << 6;
+        const ROTATE_X      = 1 << 7;
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+enum Think {
+    #[default]
+    None = 0,
+    Off,
+    Return,
+}
+
+/// A valve or a crank that passes its position to targets with [UseType::Set].
+///
+/// The position is in range from `0.0` at the start angles to `1.0` at the end angles.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct MomentaryRotButton {
+    base: BaseEntity,
+    master: Option<MapString>,
+    sounds: u8,
+    lock_sounds: LockSounds,
+    return_speed: f32,
+
+    button_move: AngularMove,
+    direction: Cell<f32>,
+    last_used: Cell<bool>,
+    think: Cell<Think>,
+}
+
+impl CreateEntity for MomentaryRotButton {
+    fn create(base: BaseEntity) -> Self {
+        let engine = base.engine();
+        Self {
+            base,
+            master: None,
+            sounds: 0,
+            lock_sounds: LockSounds::new(engine),
+            return_speed: 0.0,
+
+            button_move: AngularMove::default(),
+            direction: Cell::default(),
+            last_used: Cell::default(),
+            think: Cell::default(),
+        }
+    }
+}
+
+impl MomentaryRotButton {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    /// Returns the button position for the given angles.
+    fn position(&self, angles: vec3_t) -> f32 {
+        let sf = self.spawn_flags();
+        let start = self.button_move.start();
+        let delta = if sf.intersects(SpawnFlags::ROTATE_Z) {
+            angles.z - start.z
+        } else if sf.intersects(SpawnFlags::ROTATE_X) {
+            angles.x - start.x
+        } else {
+            angles.y - start.y
+        };
+        delta / self.button_move.distance()
+    }
+
+    fn play_sound(&self) {
+        let v = self.vars();
+        if let Some(noise) = v.noise() {
+            self.engine()
+                .build_sound()
+                .channel_voice()
+                .emit_dyn(noise, v);
+        }
+    }
+
+    /// Updates all buttons attached to the same target.
+    fn update_all_buttons(&self, value: f32, start: bool) {
+        let update = |button: &Self| {
+            if start {
+                button.update_self(value);
+            } else {
+                button.update_self_return(value);
+            }
+        };
+
+        let Some(target) = self.vars().target() else {
+            update(self);
+            return;
+        };
+
+        for i in self.engine().entities().by_target(&*target) {
+            if let Some(button) = i.downcast_ref::<Self>() {
+                update(button);
+            }
+        }
+    }
+
+    fn update_self(&self, value: f32) {
+        let v = self.vars();
+        let start_use = !self.last_used.get();
+        if start_use {
+            self.direction.set(-self.direction.get());
+        }
+        self.last_used.set(true);
+
+        self.think.set(Think::Off);
+        v.set_next_think_time_from_last(0.1);
+
+        let direction = self.direction.get();
+        if direction > 0.0 && value >= 1.0 {
+            v.set_angular_velocity(vec3_t::ZERO);
+            v.set_angles(self.button_move.end());
+            return;
+        } else if direction < 0.0 && value <= 0.0 {
+            v.set_angular_velocity(vec3_t::ZERO);
+            v.set_angles(self.button_move.start());
+            return;
+        }
+
+        if start_use {
+            self.play_sound();
+        }
+
+        // keep rotating until the button is used
+        v.set_next_think_time_from_last(0.2);
+        v.set_angular_velocity(v.move_dir() * (direction * v.speed()));
+    }
+
+    fn update_self_return(&self, value: f32) {
+        let v = self.vars();
+        if value <= 0.0 {
+            v.set_angular_velocity(vec3_t::ZERO);
+            v.set_angles(self.button_move.start());
+            v.stop_thinking();
+            self.think.set(Think::None);
+        } else {
+            v.set_angular_velocity(v.move_dir() * -self.return_speed);
+            v.set_next_think_time_from_last(0.1);
+            self.think.set(Think::Return);
+        }
+    }
+
+    fn update_target(&self, value: f32) {
+        utils::use_targets(UseType::Set(value), Some(self), self);
+    }
+
+    fn off(&self) {
+        let v = self.vars();
+        v.set_angular_velocity(vec3_t::ZERO);
+        self.last_used.set(false);
+        if self.spawn_flags().intersects(SpawnFlags::AUTO_RETURN) && self.return_speed > 0.0 {
+            self.think.set(Think::Return);
+            v.set_next_think_time_from_last(0.1);
+            self.direction.set(-1.0);
+        }
+    }
+
+    fn return_to_start(&self) {
+        let value = self.position(self.vars().angles());
+        self.update_all_buttons(value, false);
+        if value > 0.0 {
+            self.update_target(value);
+        }
+    }
+}
+
+impl Entity for MomentaryRotButton {
+    delegate_entity!(base not { object_caps, key_value, spawn, used, think });
+
+    fn object_caps(&self) -> ObjectCaps {
+        let caps = self
+            .base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION);
+        if self.spawn_flags().intersects(SpawnFlags::DOOR) {
+            caps
+        } else {
+            caps.union(ObjectCaps::CONTINUOUS_USE)
+        }
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        match data.key_name().to_bytes() {
+            b"master" => self.master = Some(self.engine().new_map_string(data.value())),
+            b"returnspeed" => self.return_speed = data.parse_or_default(),
+            b"sounds" => self.sounds = data.parse_or_default(),
+            _ => {
+                if self.lock_sounds.key_value(data) {
+                    return;
+                }
+                if self.button_move.key_value(data) {
+                    return;
+                }
+                self.base.key_value(data);
+                return;
+            }
+        }
+        data.set_handled(true);
+    }
+
+    fn spawn(&mut self) {
+        let engine = self.engine();
+        let sf = self.spawn_flags();
+        let v = self.base.vars();
+
+        v.set_move_dir_from_spawn_flags(SpawnFlags::ROTATE_X.bits(), SpawnFlags::ROTATE_Z.bits());
+
+        if v.speed() == 0.0 {
+            v.set_speed(100.0);
+        }
+
+        let distance = self.button_move.distance();
+        if distance < 0.0 {
+            self.button_move
+                .set_start(v.angles() + v.move_dir() * distance);
+            self.button_move.set_end(v.angles());
+            self.button_move.set_distance(-distance);
+            self.direction.set(1.0);
+        } else {
+            self.button_move.set_start(v.angles());
+            self.button_move
+                .set_end(v.angles() + v.move_dir() * distance);
+            self.direction.set(-1.0);
+        }
+
+        if sf.intersects(SpawnFlags::DOOR) {
+            v.set_solid(Solid::Bsp);
+        } else {
+            v.set_solid(Solid::Not);
+        }
+        v.set_move_type(MoveType::Push);
+        v.set_origin_and_link(v.origin());
+        v.reload_model();
+
+        let sound = button_sound_or_default(self.sounds as usize);
+        engine.precache_sound(sound);
+        v.set_noise(engine.new_map_string(sound));
+
+        self.lock_sounds.precache();
+        self.last_used.set(false);
+    }
+
+    fn used(&self, _: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        let engine = self.engine();
+        let v = self.vars();
+        if !utils::is_master_triggered(&engine, self.master, activator) {
+            self.lock_sounds.play_button(true, v);
+            return;
+        }
+        if !self.last_used.get() {
+            self.lock_sounds.play_button(false, v);
+        }
+
+        let value = self.position(v.angles());
+        self.update_all_buttons(value, true);
+
+        // predict the value from the destination angles, so the targets
+        // do not move in the wrong direction on retriggering
+        let time = v.next_think_time() - v.last_think_time();
+        let dest = v.angles() + v.angular_velocity() * time;
+        self.update_target(self.position(dest));
+    }
+
+    fn think(&self) {
+        match self.think.take() {
+            Think::None => {}
+            Think::Off => self.off(),
+            Think::Return => self.return_to_start(),
+        }
+    }
+}
+
+impl_private!(MomentaryRotButton {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_momentary_rot_button {
+    () => {
+        $crate::export_entity!(
+            momentary_rot_button,
+            $crate::momentary_rot_button::MomentaryRotButton
+        );
+    };
+}
+#[doc(inline)]
+pub use export_momentary_rot_button as export;
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{entity::UseType, ffi::common::vec3_t, prelude::*, testing};
+
+    use super::MomentaryRotButton;
+
+    #[test]
+    fn rotate_while_used() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let button = engine.new_entity::<MomentaryRotButton>().build();
+        button.button_move.set_distance(90.0);
+        button.spawn();
+        let v = button.vars();
+        assert_eq!(button.position(vec3_t::new(0.0, 45.0, 0.0)), 0.5);
+
+        // rotates to the end
+        button.used(UseType::Toggle, None, &*button);
+        assert_eq!(v.angular_velocity(), vec3_t::new(0.0, 100.0, 0.0));
+
+        // stops when not used
+        button.think();
+        assert_eq!(v.angular_velocity(), vec3_t::ZERO);
+
+        // rotates back to the start on the next use
+        v.set_angles(button.button_move.end());
+        button.used(UseType::Toggle, None, &*button);
+        assert_eq!(v.angular_velocity(), vec3_t::new(0.0, -100.0, 0.0));
+
+        unsafe {
+            engine.remove_entity_now(v);
+        }
+    }
+}
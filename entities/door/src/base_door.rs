@@ -41,7 +41,7 @@ const STOP_SOUNDS: &[&CStr] = &[
     res::valve::sound::doors::DOORSTOP8,
 ];
 
-trait EntityVarsExt {
+pub(crate) trait EntityVarsExt {
     fn noise_moving(&self) -> Option<MapString>;
 
     fn set_noise_moving(&self, sound: MapString);
@@ -69,6 +69,21 @@ impl EntityVarsExt for EntityVars {
     }
 }
 
+/// Precaches the door sounds and sets the moving and arrived noises.
+pub(crate) fn precache_door_sounds(v: &EntityVars, move_sound: u8, stop_sound: u8) {
+    let engine = v.engine();
+    let get_sound = |sounds: &[&'static CStr], index: u8| {
+        let sound = sounds.get(index as usize).copied().unwrap_or(sounds[0]);
+        if sound != res::valve::sound::common::NULL {
+            engine.precache_sound(sound);
+        }
+        engine.new_map_string(sound)
+    };
+
+    v.set_noise_moving(get_sound(MOVE_SOUNDS, move_sound));
+    v.set_noise_arrived(get_sound(STOP_SOUNDS, stop_sound));
+}
+
 bitflags! {
     #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
     #[repr(transparent)]
@@ -372,20 +387,7 @@ impl<T: Move> Entity for BaseDoor<T> {
     }
 
     fn precache(&mut self) {
-        let engine = self.engine();
-        let v = self.base.vars();
-
-        let get_sound = |sounds: &[&'static CStr], default: &'static CStr, index: u8| {
-            let sound = sounds.get(index as usize).copied().unwrap_or(default);
-            if sound != res::valve::sound::common::NULL {
-                engine.precache_sound(sound);
-            }
-            engine.new_map_string(sound)
-        };
-
-        v.set_noise_moving(get_sound(MOVE_SOUNDS, MOVE_SOUNDS[0], self.move_sound));
-        v.set_noise_arrived(get_sound(STOP_SOUNDS, STOP_SOUNDS[0], self.stop_sound));
-
+        precache_door_sounds(self.base.vars(), self.move_sound, self.stop_sound);
         self.lock_sounds.precache();
     }
 
@@ -510,3 +512,61 @@ impl<T: Move> Entity for BaseDoor<T> {
 impl<T: Move> PrivateEntity for BaseDoor<T> {
     type Entity = Self;
 }
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{
+        ffi::common::vec3_t,
+        prelude::*,
+        testing::{self, RoundTrip},
+        utils::MoveState,
+    };
+
+    use crate::func_door::Door;
+
+    use super::DoorThink;
+
+    #[test]
+    fn round_trip() {
+        let test = testing::lock();
+        let master = test.engine().new_map_string(c"door_master");
+        let result = RoundTrip::new().time(5.0).entity::<Door>(|door| {
+            let door = &mut door.base;
+            door.wait = 4.0;
+            door.master = Some(master);
+            door.state.set(MoveState::GoingToEnd);
+            door.enable_touch.set(true);
+            door.think.set(DoorThink::DoorGoDown);
+            door.door_move.set_lip(2.0);
+            door.door_move.set_start(vec3_t::new(0.0, 0.0, 8.0));
+            door.door_move.set_end(vec3_t::new(0.0, 0.0, 64.0));
+            door.move_sound = 3;
+            door.stop_sound = 1;
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn round_trip_landmark() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let start = vec3_t::new(128.0, -32.0, 8.0);
+        let end = vec3_t::new(128.0, -32.0, 72.0);
+        let door = engine.new_entity::<Door>().build();
+        door.base.door_move.set_start(start);
+        door.base.door_move.set_end(end);
+        let fresh = engine.new_entity::<Door>().build();
+
+        let result = RoundTrip::new()
+            .landmark(vec3_t::new(1024.0, 512.0, -64.0))
+            .run(&*door, fresh);
+        assert_eq!(result, Ok(()));
+        assert_eq!(fresh.base.door_move.start(), start);
+        assert_eq!(fresh.base.door_move.end(), end);
+
+        unsafe {
+            engine.remove_entity_now(door.vars());
+            engine.remove_entity_now(fresh.vars());
+        }
+    }
+}
@@ -8,6 +8,7 @@ mod base_door;
 
 pub mod func_door;
 pub mod func_door_rotating;
+pub mod momentary_door;
 
 #[doc(hidden)]
 pub use xash3d_server::export::export_entity;
@@ -0,0 +1,188 @@
+use core::cell::Cell;
+
+use xash3d_server::{
+    entity::{BaseEntity, KeyValue, MoveType, ObjectCaps, Solid, UseType, delegate_entity},
+    prelude::*,
+    private::impl_private,
+    utils::{LinearMove, Move},
+};
+
+use crate::base_door::{EntityVarsExt, SpawnFlags, precache_door_sounds};
+
+/// A door that follows the position passed with [UseType::Set] by a `momentary_rot_button`.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct MomentaryDoor {
+    base: BaseEntity,
+    door_move: LinearMove,
+    move_sound: u8,
+    stop_sound: u8,
+    moving: Cell<bool>,
+}
+
+impl CreateEntity for MomentaryDoor {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+            door_move: LinearMove::default(),
+            move_sound: 0,
+            stop_sound: 0,
+            moving: Cell::default(),
+        }
+    }
+}
+
+impl MomentaryDoor {
+    fn move_done(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+        self.moving.set(false);
+        if let Some(noise_moving) = v.noise_moving() {
+            engine.build_sound().channel_static().stop(noise_moving, v);
+        }
+        if let Some(noise_arrived) = v.noise_arrived() {
+            engine
+                .build_sound()
+                .channel_static()
+                .emit_dyn(noise_arrived, v);
+        }
+    }
+}
+
+impl Entity for MomentaryDoor {
+    delegate_entity!(base not { object_caps, key_value, precache, spawn, used, think });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        match data.key_name().to_bytes() {
+            b"movesnd" => self.move_sound = data.parse_or_default(),
+            b"stopsnd" => self.stop_sound = data.parse_or_default(),
+            b"healthvalue" => {}
+            _ => {
+                if self.door_move.key_value(data) {
+                    return;
+                }
+                self.base.key_value(data);
+                return;
+            }
+        }
+        data.set_handled(true);
+    }
+
+    fn precache(&mut self) {
+        precache_door_sounds(self.base.vars(), self.move_sound, self.stop_sound);
+    }
+
+    fn spawn(&mut self) {
+        let v = self.base.vars();
+        v.set_move_dir_from_angles();
+        v.set_solid(Solid::Bsp);
+        v.set_move_type(MoveType::Push);
+        v.link();
+        v.reload_model();
+
+        if v.speed() == 0.0 {
+            v.set_speed(100.0);
+        }
+
+        self.door_move.init(v);
+        let sf = SpawnFlags::from_bits_retain(v.spawn_flags());
+        if sf.intersects(SpawnFlags::START_OPEN) {
+            self.door_move.swap(v);
+        }
+
+        self.precache();
+    }
+
+    fn used(&self, use_type: UseType, _: Option<&dyn Entity>, _: &dyn Entity) {
+        // momentary buttons pass down their position
+        let UseType::Set(value) = use_type else {
+            return;
+        };
+
+        let v = self.vars();
+        let start = self.door_move.start();
+        let dest = start + (self.door_move.end() - start) * value.min(1.0);
+        let speed = (dest - v.origin()).length() * 10.0;
+        if speed == 0.0 {
+            return;
+        }
+
+        if !self.moving.get() {
+            if let Some(noise_moving) = v.noise_moving() {
+                self.engine()
+                    .build_sound()
+                    .channel_static()
+                    .emit_dyn(noise_moving, v);
+            }
+        }
+
+        self.moving.set(true);
+        if self.door_move.start_move(v, speed, dest) {
+            self.move_done();
+        }
+    }
+
+    fn think(&self) {
+        if self.moving.get() && self.door_move.move_done(self.vars()) {
+            self.move_done();
+        }
+    }
+}
+
+impl_private!(MomentaryDoor {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_momentary_door {
+    () => {
+        $crate::export_entity!(momentary_door, $crate::momentary_door::MomentaryDoor);
+    };
+}
+#[doc(inline)]
+pub use export_momentary_door as export;
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{entity::UseType, ffi::common::vec3_t, prelude::*, testing};
+
+    use super::MomentaryDoor;
+
+    #[test]
+    fn position_from_use_set() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let door = engine.new_entity::<MomentaryDoor>().build();
+        let end = vec3_t::new(0.0, 0.0, 100.0);
+        door.door_move.set_start(vec3_t::ZERO);
+        door.door_move.set_end(end);
+        let v = door.vars();
+
+        // only the position is passed down
+        door.used(UseType::Toggle, None, &*door);
+        assert!(!door.moving.get());
+
+        door.used(UseType::Set(0.5), None, &*door);
+        assert!(door.moving.get());
+        assert!(v.velocity().z > 0.0);
+
+        // the door arrives at the middle
+        v.set_origin(vec3_t::new(0.0, 0.0, 50.0));
+        door.think();
+        assert!(!door.moving.get());
+        assert_eq!(v.velocity(), vec3_t::ZERO);
+
+        // the position is clamped to the end
+        v.set_origin(end);
+        door.used(UseType::Set(2.0), None, &*door);
+        assert!(!door.moving.get());
+
+        unsafe {
+            engine.remove_entity_now(v);
+        }
+    }
+}
@@ -22,6 +22,7 @@ enum Think {
     #[default]
     None,
     Animate,
+    AnimateUntilDead,
 }
 
 bitflags! {
@@ -38,6 +39,7 @@ pub struct Sprite {
     base: PointEntity,
     max_frame: f32,
     last_time: Cell<MapTime>,
+    die_time: Cell<MapTime>,
     think: Cell<Think>,
 }
 
@@ -47,6 +49,7 @@ impl CreateEntity for Sprite {
             base: PointEntity::create(base),
             max_frame: 0.0,
             last_time: Default::default(),
+            die_time: Default::default(),
             think: Default::default(),
         }
     }
@@ -121,6 +124,19 @@ impl Sprite {
         v.stop_thinking();
     }
 
+    /// Plays the animation once with the given frame rate and removes the sprite.
+    pub fn animate_and_die(&self, framerate: f32) {
+        let v = self.vars();
+        v.with_spawn_flags(|f| f | SpawnFlags::TEMPORARY.bits());
+        v.set_framerate(framerate);
+        let engine = self.engine();
+        let max_frame = (engine.model_frames(v.model_index_raw()) - 1) as f32;
+        let now = engine.globals.map_time();
+        self.die_time.set(now + max_frame / framerate);
+        self.think.set(Think::AnimateUntilDead);
+        self.set_next_animate_time(0.0);
+    }
+
     fn animate(&self, frames: f32) {
         let v = self.vars();
         v.set_frame(v.frame() + frames);
@@ -201,6 +217,14 @@ impl Entity for Sprite {
                 let delta = self.set_next_animate_time(0.1);
                 self.animate(self.vars().framerate() * delta);
             }
+            Think::AnimateUntilDead => {
+                if self.engine().globals.map_time() > self.die_time.get() {
+                    self.remove_from_world();
+                } else {
+                    let delta = self.set_next_animate_time(0.0);
+                    self.animate(self.vars().framerate() * delta);
+                }
+            }
         }
     }
 }
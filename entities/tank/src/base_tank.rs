@@ -0,0 +1,738 @@
+use core::cell::Cell;
+
+use bitflags::bitflags;
+use xash3d_entity_sprite::env_sprite::Sprite;
+use xash3d_server::{
+    color::RGBA,
+    engine::{TraceIgnore, TraceResult},
+    entity::{
+        BaseEntity, Buttons, EdictFlags, EntityHandle, EntityPlayer, EntityVars, KeyValue,
+        MoveType, ObjectCaps, Solid, UseType, delegate_entity,
+    },
+    ffi::common::vec3_t,
+    math::{angle_distance, atanf, fabsf, sqrtf},
+    monster::{AiSounds, LOUD_GUN_VOLUME, SoundTypes},
+    prelude::*,
+    render::{RenderFx, RenderMode},
+    save::{Restore, Save},
+    str::MapString,
+    time::MapTime,
+    utils::{self, ViewField},
+};
+
+bitflags! {
+    #[derive(Copy, Clone, Debug)]
+    pub struct SpawnFlags: u32 {
+        const ACTIVE        = 1 << 0;
+        const PLAYER        = 1 << 1;
+        const HUMANS        = 1 << 2;
+        const ALIENS        = 1 << 3;
+        const LINE_OF_SIGHT = 1 << 4;
+        const CAN_CONTROL   = 1 << 5;
+        const SOUND_ON      = 1 << 15;
+    }
+}
+
+/// Firing cones selected by the `firespread` key: 0, 1, 3, 5 and 10 degrees.
+const SPREADS: [f32; 5] = [0.0, 0.025, 0.05, 0.1, 0.25];
+
+/// The maximum distance for traced shots.
+pub const TANK_RANGE: f32 = 4096.0;
+
+/// The distance a controller can move away from the controls.
+const CONTROLS_RANGE: f32 = 30.0;
+
+/// A firing mode of a [BaseTank].
+pub trait TankWeapon: Save + Restore + Sized + 'static {
+    #[allow(unused_variables)]
+    fn key_value(&mut self, v: &EntityVars, data: &mut KeyValue) -> bool {
+        false
+    }
+
+    #[allow(unused_variables)]
+    fn precache(&self, engine: &ServerEngine) {}
+
+    /// Called after all entities have been spawned.
+    #[allow(unused_variables)]
+    fn activate(&self, tank: &BaseTank<Self>) {}
+
+    /// Called before the tank tracks a target.
+    #[allow(unused_variables)]
+    fn think(&self, tank: &BaseTank<Self>) {}
+
+    /// Fires `shots` rounds from `barrel_end` in the `forward` direction.
+    fn fire(
+        &self,
+        tank: &BaseTank<Self>,
+        barrel_end: vec3_t,
+        forward: vec3_t,
+        attacker: &EntityVars,
+        shots: u32,
+    );
+}
+
+/// A mounted gun that tracks players or follows a controlling player's view.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct BaseTank<T> {
+    pub base: BaseEntity,
+    pub weapon: T,
+    master: Option<MapString>,
+
+    controller: Cell<Option<EntityHandle>>,
+    controller_use_pos: Cell<vec3_t>,
+    next_attack: Cell<MapTime>,
+
+    yaw_center: f32,
+    yaw_rate: f32,
+    yaw_range: f32,
+    yaw_tolerance: f32,
+    pitch_center: f32,
+    pitch_rate: f32,
+    pitch_range: f32,
+    pitch_tolerance: f32,
+    view_field: ViewField,
+
+    fire_last: Cell<Option<MapTime>>,
+    fire_rate: f32,
+    last_sight_time: Cell<MapTime>,
+    /// Seconds to keep firing after the target is lost.
+    persist: f32,
+    min_range: f32,
+    max_range: f32,
+    /// The barrel position relative to the origin (forward, right, up).
+    barrel_pos: vec3_t,
+    sprite_scale: f32,
+    sprite_smoke: Option<MapString>,
+    sprite_flash: Option<MapString>,
+    spread: u8,
+    sight_origin: Cell<vec3_t>,
+}
+
+impl<T: TankWeapon + Default> CreateEntity for BaseTank<T> {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+            weapon: T::default(),
+            master: None,
+
+            controller: Default::default(),
+            controller_use_pos: Default::default(),
+            next_attack: Default::default(),
+
+            yaw_center: 0.0,
+            yaw_rate: 0.0,
+            yaw_range: 0.0,
+            yaw_tolerance: 0.0,
+            pitch_center: 0.0,
+            pitch_rate: 0.0,
+            pitch_range: 0.0,
+            pitch_tolerance: 0.0,
+            view_field: ViewField::FULL,
+
+            fire_last: Default::default(),
+            fire_rate: 0.0,
+            last_sight_time: Default::default(),
+            persist: 0.0,
+            min_range: 0.0,
+            max_range: 0.0,
+            barrel_pos: vec3_t::ZERO,
+            sprite_scale: 0.0,
+            sprite_smoke: None,
+            sprite_flash: None,
+            spread: 0,
+            sight_origin: Default::default(),
+        }
+    }
+}
+
+impl<T: TankWeapon> BaseTank<T> {
+    pub fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.spawn_flags().intersects(SpawnFlags::ACTIVE)
+    }
+
+    fn tank_activate(&self) {
+        let v = self.vars();
+        v.with_spawn_flags(|f| f | SpawnFlags::ACTIVE.bits());
+        v.set_next_think_time_from_last(0.1);
+        self.fire_last.set(None);
+    }
+
+    fn tank_deactivate(&self) {
+        let v = self.vars();
+        v.with_spawn_flags(|f| f & !SpawnFlags::ACTIVE.bits());
+        self.fire_last.set(None);
+        self.stop_rot_sound();
+    }
+
+    pub fn barrel_position(&self) -> vec3_t {
+        let v = self.vars();
+        let av = v.angles().angle_vectors().all();
+        let b = self.barrel_pos;
+        v.origin() + av.forward * b.x + av.right * b.y + av.up * b.z
+    }
+
+    /// Returns a random direction in the firing cone around `forward`.
+    pub fn spread_direction(&self, forward: vec3_t) -> vec3_t {
+        let engine = self.engine();
+        let spread = SPREADS[self.spread as usize];
+        let av = self.vars().angles().angle_vectors().all();
+        let (x, y) = loop {
+            let x = engine.random_float(-0.5, 0.5) + engine.random_float(-0.5, 0.5);
+            let y = engine.random_float(-0.5, 0.5) + engine.random_float(-0.5, 0.5);
+            if x * x + y * y <= 1.0 {
+                break (x, y);
+            }
+        };
+        forward + av.right * (x * spread) + av.up * (y * spread)
+    }
+
+    /// Traces a shot from `start` in the firing cone around `forward`.
+    pub fn tank_trace<'a>(
+        &self,
+        engine: &'a ServerEngine,
+        start: vec3_t,
+        forward: vec3_t,
+    ) -> TraceResult<'a> {
+        let end = start + self.spread_direction(forward) * TANK_RANGE;
+        engine.trace_line(start, end, TraceIgnore::NONE, Some(self.vars()))
+    }
+
+    fn can_fire(&self) -> bool {
+        let now = self.engine().globals.map_time();
+        (now - self.last_sight_time.get()).as_secs_f32() < self.persist
+    }
+
+    fn in_range(&self, range: f32) -> bool {
+        range >= self.min_range && (self.max_range <= 0.0 || range <= self.max_range)
+    }
+
+    fn in_view_cone(&self, position: vec3_t) -> bool {
+        let v = self.vars();
+        let forward = vec3_t::new(0.0, self.yaw_center, 0.0)
+            .angle_vectors()
+            .forward();
+        let los = (position - v.origin()).with_z(0.0).normalize();
+        los.dot(forward) > self.view_field.to_dot()
+    }
+
+    fn controller(&self) -> Option<&dyn EntityPlayer> {
+        self.controller.get().downcast_ref::<dyn EntityPlayer>()
+    }
+
+    fn on_controls(&self, v: &EntityVars) -> bool {
+        self.spawn_flags().intersects(SpawnFlags::CAN_CONTROL)
+            && (self.controller_use_pos.get() - v.origin()).length() < CONTROLS_RANGE
+    }
+
+    fn start_control(&self, player: &dyn EntityPlayer) -> bool {
+        if self.controller.get().is_some() {
+            return false;
+        }
+
+        let engine = self.engine();
+        if !utils::is_master_triggered(&engine, self.master, Some(player.as_entity())) {
+            return false;
+        }
+
+        trace!("{}: start control", self.pretty_name());
+        self.controller.set(Some(player.entity_handle()));
+        if let Some(weapon) = player.inventory().active() {
+            weapon.holster();
+        }
+        let pv = player.vars();
+        pv.set_weapon_model_name(None);
+        pv.set_view_model_name(None);
+        self.controller_use_pos.set(pv.origin());
+
+        self.vars().set_next_think_time_from_last(0.1);
+        true
+    }
+
+    fn stop_control(&self) {
+        let Some(controller) = self.controller.take() else {
+            return;
+        };
+
+        trace!("{}: stop control", self.pretty_name());
+        if let Some(player) = controller.downcast_ref::<dyn EntityPlayer>() {
+            if let Some(weapon) = player.inventory().active() {
+                weapon.deploy();
+            }
+        }
+
+        let v = self.vars();
+        v.stop_thinking();
+        if self.is_active() {
+            v.set_next_think_time_from_last(1.0);
+        }
+    }
+
+    fn controller_post_frame(&self, player: &dyn EntityPlayer) {
+        // keep the player weapons holstered while on the controls
+        player.inventory().set_next_attack(0.1);
+
+        let engine = self.engine();
+        let now = engine.globals.map_time();
+        if now < self.next_attack.get() {
+            return;
+        }
+
+        let pv = player.vars();
+        if pv.buttons().intersects(Buttons::ATTACK) {
+            let forward = self.vars().angles().angle_vectors().forward();
+            let delay = 1.0 / self.fire_rate;
+            self.fire_last.set(Some(now - delay - 0.01));
+            self.fire(self.barrel_position(), forward, pv);
+
+            // let monsters hear the shot
+            let global_state = self.global_state();
+            let sounds = global_state.get_or_default::<AiSounds>();
+            sounds.insert(SoundTypes::COMBAT, pv.origin(), LOUD_GUN_VOLUME, 0.3);
+
+            self.next_attack.set(now + delay);
+        }
+    }
+
+    /// Returns the nearest alive player in the view cone.
+    fn find_target(&self) -> Option<EntityHandle> {
+        let engine = self.engine();
+        let origin = self.vars().origin();
+        engine
+            .players()
+            .filter(|i| i.as_player().is_some() && i.is_alive())
+            .filter(|i| !i.vars().flags().intersects(EdictFlags::NOTARGET))
+            .filter(|i| self.in_view_cone(i.vars().origin()))
+            .min_by(|a, b| {
+                let a = (a.vars().origin() - origin).length();
+                let b = (b.vars().origin() - origin).length();
+                a.total_cmp(&b)
+            })
+            .map(|i| i.entity_handle())
+    }
+
+    /// Rotates the angles to point the end of the barrel at the target.
+    fn adjust_angles_for_barrel(&self, mut angles: vec3_t, distance: f32) -> vec3_t {
+        let b = self.barrel_pos;
+        if b.y == 0.0 && b.z == 0.0 {
+            return angles;
+        }
+        let distance = distance - b.z;
+        let d2 = distance * distance;
+        if b.y != 0.0 && d2 > b.y * b.y {
+            angles.y += atanf(b.y / sqrtf(d2 - b.y * b.y)).to_degrees();
+        }
+        if b.z != 0.0 && d2 > b.z * b.z {
+            angles.x += atanf(-b.z / sqrtf(d2 - b.z * b.z)).to_degrees();
+        }
+        angles
+    }
+
+    fn track_target(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+        let mut update_time = false;
+        let barrel_end = self.barrel_position();
+
+        let (mut angles, direction, target) = if let Some(controller) = self.controller() {
+            // mirror the controller view angles
+            v.set_next_think_time_from_last(0.05);
+            let angles = controller.vars().view_angle();
+            (angles.with_x(-angles.x), vec3_t::ZERO, None)
+        } else {
+            if !self.is_active() {
+                return;
+            }
+            v.set_next_think_time_from_last(0.1);
+
+            if engine.find_client_in_pvs(v).is_none() {
+                v.set_next_think_time_from_last(2.0);
+                return;
+            }
+            let Some(enemy) = self.find_target().get_entity() else {
+                return;
+            };
+
+            let ev = enemy.vars();
+            let target_position = ev.origin() + ev.view_ofs();
+            let range = (target_position - barrel_end).length();
+            if !self.in_range(range) {
+                return;
+            }
+
+            let trace = engine.trace_line(barrel_end, target_position, TraceIgnore::NONE, Some(v));
+            let hit = trace.hit_entity().map(EntityHandle::from);
+            if trace.fraction() == 1.0 || hit == Some(enemy.entity_handle()) {
+                update_time = true;
+                self.sight_origin.set(enemy.body_target(v.origin()));
+            }
+
+            // track the last seen position
+            let direction = self.sight_origin.get() - v.origin();
+            let angles = engine.vec_to_angles(direction);
+            let angles = self.adjust_angles_for_barrel(angles, direction.length());
+            (angles, direction, Some(enemy.entity_handle()))
+        };
+
+        angles.x = -angles.x;
+
+        // force the angles to be relative to the center position
+        angles.y = self.yaw_center + angle_distance(angles.y, self.yaw_center);
+        angles.x = self.pitch_center + angle_distance(angles.x, self.pitch_center);
+
+        if angles.y > self.yaw_center + self.yaw_range {
+            angles.y = self.yaw_center + self.yaw_range;
+            update_time = false;
+        } else if angles.y < self.yaw_center - self.yaw_range {
+            angles.y = self.yaw_center - self.yaw_range;
+            update_time = false;
+        }
+
+        if update_time {
+            self.last_sight_time.set(engine.globals.map_time());
+        }
+
+        // move toward the target at the rate or less
+        let dist_y = angle_distance(angles.y, v.angles().y);
+        let yaw_speed = (dist_y * 10.0).max(-self.yaw_rate).min(self.yaw_rate);
+
+        angles.x = angles
+            .x
+            .max(self.pitch_center - self.pitch_range)
+            .min(self.pitch_center + self.pitch_range);
+        let dist_x = angle_distance(angles.x, v.angles().x);
+        let pitch_speed = (dist_x * 10.0).max(-self.pitch_rate).min(self.pitch_rate);
+
+        v.set_angular_velocity(vec3_t::new(pitch_speed, yaw_speed, 0.0));
+
+        if self.controller.get().is_some() {
+            return;
+        }
+
+        let line_of_sight = self.spawn_flags().intersects(SpawnFlags::LINE_OF_SIGHT);
+        let aimed = fabsf(dist_x) < self.pitch_tolerance && fabsf(dist_y) < self.yaw_tolerance;
+        if self.can_fire() && (aimed || line_of_sight) {
+            let forward = v.angles().angle_vectors().forward();
+            let fire = if line_of_sight {
+                let end = barrel_end + forward * direction.length();
+                let trace = engine.trace_line(barrel_end, end, TraceIgnore::NONE, Some(v));
+                let hit = trace.hit_entity().map(EntityHandle::from);
+                hit.is_some() && hit == target
+            } else {
+                true
+            };
+            if fire {
+                self.fire(self.barrel_position(), forward, v);
+                return;
+            }
+        }
+        self.fire_last.set(None);
+    }
+
+    fn start_rot_sound(&self) {
+        let v = self.vars();
+        let Some(noise) = v.noise() else {
+            return;
+        };
+        if self.spawn_flags().intersects(SpawnFlags::SOUND_ON) {
+            return;
+        }
+        v.with_spawn_flags(|f| f | SpawnFlags::SOUND_ON.bits());
+        self.engine()
+            .build_sound()
+            .channel_static()
+            .volume(0.85)
+            .emit_dyn(noise, v);
+    }
+
+    fn stop_rot_sound(&self) {
+        let v = self.vars();
+        if self.spawn_flags().intersects(SpawnFlags::SOUND_ON) {
+            if let Some(noise) = v.noise() {
+                self.engine().build_sound().channel_static().stop(noise, v);
+            }
+        }
+        v.with_spawn_flags(|f| f & !SpawnFlags::SOUND_ON.bits());
+    }
+
+    fn fire_effects(&self, barrel_end: vec3_t) {
+        let engine = self.engine();
+        let v = self.vars();
+
+        if let Some(sprite_name) = self.sprite_smoke {
+            let sprite = Sprite::new(&engine, sprite_name, barrel_end, true);
+            sprite.animate_and_die(engine.random_float(15.0, 20.0));
+            let color = v.render_color_to_rgb().rgba(255);
+            sprite.set_transparency(RenderMode::TransAlpha, color, RenderFx::None);
+            let sv = sprite.vars();
+            sv.set_velocity(vec3_t::new(0.0, 0.0, engine.random_float(40.0, 80.0)));
+            sv.set_scale(self.sprite_scale);
+        }
+
+        if let Some(sprite_name) = self.sprite_flash {
+            let sprite = Sprite::new(&engine, sprite_name, barrel_end, true);
+            sprite.animate_and_die(60.0);
+            sprite.set_transparency(RenderMode::TransAdd, RGBA::WHITE, RenderFx::NoDissipation);
+            let sv = sprite.vars();
+            sv.set_scale(self.sprite_scale);
+            // stay visible for at least 100 ms
+            sv.set_next_think_time(sv.next_think_time() + 0.1);
+        }
+    }
+
+    fn fire(&self, barrel_end: vec3_t, forward: vec3_t, attacker: &EntityVars) {
+        let now = self.engine().globals.map_time();
+        if let Some(fire_last) = self.fire_last.get() {
+            let shots = ((now - fire_last).as_secs_f32() * self.fire_rate) as u32;
+            if shots == 0 {
+                return;
+            }
+            self.weapon.fire(self, barrel_end, forward, attacker, shots);
+            self.fire_effects(barrel_end);
+            utils::use_targets(UseType::Toggle, Some(self), self);
+        }
+        self.fire_last.set(Some(now));
+    }
+}
+
+impl<T: TankWeapon> Entity for BaseTank<T> {
+    delegate_entity!(base not { object_caps, key_value, precache, spawn, activate, used, think });
+
+    fn object_caps(&self) -> ObjectCaps {
+        let caps = self
+            .base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION);
+        if self.spawn_flags().intersects(SpawnFlags::CAN_CONTROL) {
+            caps.union(ObjectCaps::IMPULSE_USE)
+        } else {
+            caps
+        }
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        let engine = self.engine();
+        match data.key_name().to_bytes() {
+            b"yawrate" => self.yaw_rate = data.parse_or_default(),
+            b"yawrange" => self.yaw_range = data.parse_or_default(),
+            b"yawtolerance" => self.yaw_tolerance = data.parse_or_default(),
+            b"pitchrate" => self.pitch_rate = data.parse_or_default(),
+            b"pitchrange" => self.pitch_range = data.parse_or_default(),
+            b"pitchtolerance" => self.pitch_tolerance = data.parse_or_default(),
+            b"firerate" => self.fire_rate = data.parse_or_default(),
+            b"barrel" => self.barrel_pos.x = data.parse_or_default(),
+            b"barrely" => self.barrel_pos.y = data.parse_or_default(),
+            b"barrelz" => self.barrel_pos.z = data.parse_or_default(),
+            b"spritescale" => self.sprite_scale = data.parse_or_default(),
+            b"spritesmoke" => self.sprite_smoke = Some(engine.new_map_string(data.value())),
+            b"spriteflash" => self.sprite_flash = Some(engine.new_map_string(data.value())),
+            b"rotatesound" => self.vars().set_noise(engine.new_map_string(data.value())),
+            b"persistence" => self.persist = data.parse_or_default(),
+            b"firespread" => self.spread = data.parse_or_default(),
+            b"minRange" => self.min_range = data.parse_or_default(),
+            b"maxRange" => self.max_range = data.parse_or_default(),
+            b"master" => self.master = Some(engine.new_map_string(data.value())),
+            _ => {
+                if self.weapon.key_value(self.base.vars(), data) {
+                    return;
+                }
+                self.base.key_value(data);
+                return;
+            }
+        }
+        data.set_handled(true);
+    }
+
+    fn precache(&mut self) {
+        let engine = self.engine();
+        if let Some(sprite_name) = self.sprite_smoke {
+            engine.precache_model(sprite_name);
+        }
+        if let Some(sprite_name) = self.sprite_flash {
+            engine.precache_model(sprite_name);
+        }
+        if let Some(noise) = self.vars().noise() {
+            engine.precache_sound(noise);
+        }
+        self.weapon.precache(&engine);
+    }
+
+    fn spawn(&mut self) {
+        self.precache();
+
+        let v = self.base.vars();
+        v.set_move_type(MoveType::Push);
+        v.set_solid(Solid::Bsp);
+        v.reload_model();
+
+        self.yaw_center = v.angles().y;
+        self.pitch_center = v.angles().x;
+        let cone = (self.yaw_range + self.yaw_tolerance).min(180.0);
+        self.view_field = ViewField::from_degress(cone);
+
+        if self.is_active() {
+            v.set_next_think_time_from_last(1.0);
+        }
+
+        self.sight_origin.set(self.barrel_position());
+
+        if self.fire_rate <= 0.0 {
+            self.fire_rate = 1.0;
+        }
+        if self.spread as usize >= SPREADS.len() {
+            self.spread = 0;
+        }
+
+        v.set_old_origin(v.origin());
+    }
+
+    fn activate(&self) {
+        self.base.activate();
+        self.weapon.activate(self);
+    }
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if self.spawn_flags().intersects(SpawnFlags::CAN_CONTROL) {
+            let Some(player) = activator.and_then(|i| i.as_player()) else {
+                return;
+            };
+            if self.controller.get().is_none() && !matches!(use_type, UseType::Off) {
+                self.start_control(player);
+            } else {
+                self.stop_control();
+            }
+        } else if use_type.should_toggle(self.is_active()) {
+            if self.is_active() {
+                self.tank_deactivate();
+            } else {
+                self.tank_activate();
+            }
+        }
+    }
+
+    fn think(&self) {
+        self.weapon.think(self);
+
+        if let Some(controller) = self.controller() {
+            let cv = controller.vars();
+            if controller.is_alive() && self.on_controls(cv) && cv.weapon_model_name().is_none() {
+                self.controller_post_frame(controller);
+            } else {
+                self.stop_control();
+            }
+        }
+
+        let v = self.vars();
+        v.set_angular_velocity(vec3_t::ZERO);
+        self.track_target();
+
+        let avelocity = v.angular_velocity();
+        if fabsf(avelocity.x) > 1.0 || fabsf(avelocity.y) > 1.0 {
+            self.start_rot_sound();
+        } else {
+            self.stop_rot_sound();
+        }
+    }
+}
+
+impl<T: TankWeapon> PrivateEntity for BaseTank<T> {
+    type Entity = Self;
+}
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use core::cell::Cell;
+
+    use xash3d_server::{
+        entity::{EntityVars, UseType},
+        ffi::common::vec3_t,
+        math::{fabsf, sqrtf},
+        prelude::*,
+        testing,
+    };
+
+    use super::{BaseTank, TankWeapon};
+
+    /// Counts fired rounds.
+    #[derive(Default, Save, Restore)]
+    struct Counter {
+        shots: Cell<u32>,
+    }
+
+    impl TankWeapon for Counter {
+        fn fire(&self, _: &BaseTank<Self>, _: vec3_t, _: vec3_t, _: &EntityVars, shots: u32) {
+            self.shots.set(self.shots.get() + shots);
+        }
+    }
+
+    #[test]
+    fn used_toggles_active() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let tank = engine.new_entity::<BaseTank<Counter>>().build();
+
+        assert!(!tank.is_active());
+        tank.used(UseType::Toggle, None, &*tank);
+        assert!(tank.is_active());
+        tank.used(UseType::On, None, &*tank);
+        assert!(tank.is_active());
+        tank.used(UseType::Toggle, None, &*tank);
+        assert!(!tank.is_active());
+
+        unsafe {
+            engine.remove_entity_now(tank.vars());
+        }
+    }
+
+    #[test]
+    fn fire_rate() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let tank = engine.new_entity::<BaseTank<Counter>>().build();
+        tank.fire_rate = 3.0;
+        let v = tank.vars();
+        let forward = vec3_t::new(1.0, 0.0, 0.0);
+
+        // the first call only starts the burst
+        tank.fire(vec3_t::ZERO, forward, v);
+        assert_eq!(tank.weapon.shots.get(), 0);
+
+        test.set_time(1.0);
+        tank.fire(vec3_t::ZERO, forward, v);
+        assert_eq!(tank.weapon.shots.get(), 3);
+
+        // too early for the next round
+        test.set_time(1.2);
+        tank.fire(vec3_t::ZERO, forward, v);
+        assert_eq!(tank.weapon.shots.get(), 3);
+
+        unsafe {
+            engine.remove_entity_now(v);
+        }
+    }
+
+    #[test]
+    fn adjust_angles_for_barrel() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let tank = engine.new_entity::<BaseTank<Counter>>().build();
+        let angles = vec3_t::new(0.0, 90.0, 0.0);
+        let distance = sqrtf(200.0);
+
+        // the barrel is in the center
+        assert_eq!(tank.adjust_angles_for_barrel(angles, distance), angles);
+
+        // the barrel is offset to the right by 45 degrees at the distance
+        tank.barrel_pos = vec3_t::new(0.0, 10.0, 0.0);
+        let adjusted = tank.adjust_angles_for_barrel(angles, distance);
+        assert!(fabsf(adjusted.y - 135.0) < 0.001);
+        assert_eq!(adjusted.x, 0.0);
+
+        unsafe {
+            engine.remove_entity_now(tank.vars());
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use xash3d_server::{
+    engine::TraceIgnore,
+    entity::{BaseEntity, DamageFlags, EntityVars, KeyValue, delegate_entity},
+    ffi::common::vec3_t,
+    prelude::*,
+    private::impl_private,
+    utils,
+};
+
+use crate::base_tank::{BaseTank, TANK_RANGE, TankWeapon};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+pub enum TankBullet {
+    #[default]
+    None = 0,
+    Monster9mm,
+    MonsterMp5,
+    Monster12mm,
+}
+
+impl TankBullet {
+    fn from_key(value: i32) -> Self {
+        match value {
+            1 => Self::Monster9mm,
+            2 => Self::MonsterMp5,
+            3 => Self::Monster12mm,
+            _ => Self::None,
+        }
+    }
+
+    /// Returns the medium skill damage.
+    fn default_damage(&self) -> f32 {
+        match self {
+            Self::None => 0.0,
+            Self::Monster9mm => 5.0,
+            Self::MonsterMp5 => 4.0,
+            Self::Monster12mm => 10.0,
+        }
+    }
+}
+
+/// Fires traced bullets.
+#[derive(Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Gun {
+    bullet: TankBullet,
+    /// Overrides the default bullet damage if not zero.
+    bullet_damage: f32,
+}
+
+impl Gun {
+    fn damage(&self) -> f32 {
+        if self.bullet_damage != 0.0 {
+            self.bullet_damage
+        } else {
+            self.bullet.default_damage()
+        }
+    }
+}
+
+impl TankWeapon for Gun {
+    fn key_value(&mut self, _: &EntityVars, data: &mut KeyValue) -> bool {
+        match data.key_name().to_bytes() {
+            b"bullet" => self.bullet = TankBullet::from_key(data.parse_or_default()),
+            b"bullet_damage" => self.bullet_damage = data.parse_or_default(),
+            _ => return false,
+        }
+        data.set_handled(true);
+        true
+    }
+
+    fn fire(
+        &self,
+        tank: &BaseTank<Self>,
+        barrel_end: vec3_t,
+        forward: vec3_t,
+        attacker: &EntityVars,
+        shots: u32,
+    ) {
+        let engine = tank.engine();
+        let global_state = tank.global_state();
+        let damage = self.damage();
+        let tv = tank.vars();
+
+        let multi_damage = global_state.multi_damage();
+        multi_damage.clear();
+
+        for _ in 0..shots {
+            let dir = tank.spread_direction(forward);
+            let end = barrel_end + dir * TANK_RANGE;
+            let trace = engine.trace_line(barrel_end, end, TraceIgnore::NONE, Some(tv));
+            if trace.fraction() == 1.0 {
+                continue;
+            }
+
+            if let Some(entity) = trace.hit_entity().get_entity() {
+                entity.trace_attack(attacker, damage, dir, &trace, DamageFlags::BULLET);
+            }
+
+            utils::play_texture_sound(&engine, &trace, barrel_end, end);
+            let decal = global_state.decals().get_random_gunshot();
+            utils::decal_trace(&engine, &trace, decal);
+        }
+
+        multi_damage.apply(tv, attacker);
+    }
+}
+
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Tank {
+    base: BaseTank<Gun>,
+}
+
+impl CreateEntity for Tank {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: BaseTank::create(base),
+        }
+    }
+}
+
+impl Entity for Tank {
+    delegate_entity!(base);
+}
+
+impl_private!(Tank {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_tank {
+    () => {
+        $crate::export_entity!(func_tank, $crate::func_tank::Tank);
+    };
+}
+#[doc(inline)]
+pub use export_func_tank as export;
@@ -0,0 +1,86 @@
+use core::cell::Cell;
+
+use xash3d_server::{
+    entity::{
+        BaseEntity, Effects, EntityHandle, MoveType, ObjectCaps, Solid, UseType, delegate_entity,
+    },
+    prelude::*,
+    private::impl_private,
+};
+
+/// A use area that passes player use to a `func_tank*` entity.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TankControls {
+    base: BaseEntity,
+    tank: Cell<Option<EntityHandle>>,
+}
+
+impl CreateEntity for TankControls {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+            tank: Cell::default(),
+        }
+    }
+}
+
+impl Entity for TankControls {
+    delegate_entity!(base not { object_caps, spawn, think, used });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+            .union(ObjectCaps::IMPULSE_USE)
+    }
+
+    fn spawn(&mut self) {
+        let v = self.base.vars();
+        v.set_solid(Solid::Trigger);
+        v.set_move_type(MoveType::None);
+        v.with_effects(|f| f | Effects::NODRAW);
+        v.reload_model();
+        v.set_size_and_link(v.min_size(), v.max_size());
+        v.set_origin_and_link(v.origin());
+
+        // after all tanks have been spawned
+        v.set_next_think_time_from_now(0.3);
+    }
+
+    fn think(&self) {
+        let Some(target) = self.vars().target() else {
+            return;
+        };
+        let tank = self
+            .engine()
+            .entities()
+            .by_target_name(&target)
+            .map(EntityHandle::from)
+            .find(|i| {
+                let class_name = i.vars().classname();
+                class_name.is_some_and(|i| i.to_bytes().starts_with(b"func_tank"))
+            });
+        if tank.is_none() {
+            warn!("{}: no tank {target}", self.pretty_name());
+        }
+        self.tank.set(tank);
+    }
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if let Some(tank) = self.tank.get().get_entity() {
+            tank.used(use_type, activator, self);
+        }
+    }
+}
+
+impl_private!(TankControls {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_tankcontrols {
+    () => {
+        $crate::export_entity!(func_tankcontrols, $crate::func_tankcontrols::TankControls);
+    };
+}
+#[doc(inline)]
+pub use export_func_tankcontrols as export;
@@ -0,0 +1,125 @@
+use core::cell::Cell;
+
+use xash3d_entity_beam::env_laser::Laser;
+use xash3d_server::{
+    entity::{BaseEntity, EntityHandle, EntityVars, KeyValue, delegate_entity},
+    ffi::common::vec3_t,
+    prelude::*,
+    private::impl_private,
+    time::MapTime,
+};
+
+use crate::base_tank::{BaseTank, TankWeapon};
+
+/// Fires an `env_laser` named by the `laserentity` key.
+#[derive(Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct LaserGun {
+    laser: Cell<Option<EntityHandle>>,
+    laser_time: Cell<MapTime>,
+}
+
+impl LaserGun {
+    fn laser(&self, tank: &BaseTank<Self>) -> Option<&Laser> {
+        if let Some(laser) = self.laser.get().downcast_ref::<Laser>() {
+            return Some(laser);
+        }
+
+        let engine = tank.engine();
+        let name = tank.vars().message()?;
+        let laser = engine
+            .entities()
+            .by_target_name(&name)
+            .map(EntityHandle::from)
+            .find(|i| i.downcast_ref::<Laser>().is_some())?;
+        self.laser.set(Some(laser));
+        laser.downcast_ref::<Laser>()
+    }
+}
+
+impl TankWeapon for LaserGun {
+    fn key_value(&mut self, v: &EntityVars, data: &mut KeyValue) -> bool {
+        if data.key_name() == c"laserentity" {
+            v.set_message(v.engine().new_map_string(data.value()));
+            data.set_handled(true);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn activate(&self, tank: &BaseTank<Self>) {
+        match self.laser(tank) {
+            Some(laser) => laser.turn_off(),
+            None => {
+                error!("{}: laser tank without env_laser", tank.pretty_name());
+                tank.remove_from_world();
+            }
+        }
+    }
+
+    fn think(&self, tank: &BaseTank<Self>) {
+        let now = tank.engine().globals.map_time();
+        if let Some(laser) = self.laser.get().downcast_ref::<Laser>() {
+            if now > self.laser_time.get() && laser.is_on() {
+                laser.turn_off();
+            }
+        }
+    }
+
+    fn fire(
+        &self,
+        tank: &BaseTank<Self>,
+        barrel_end: vec3_t,
+        forward: vec3_t,
+        _: &EntityVars,
+        shots: u32,
+    ) {
+        let Some(laser) = self.laser(tank) else {
+            return;
+        };
+
+        let engine = tank.engine();
+        let now = engine.globals.map_time();
+        let lv = laser.vars();
+        for _ in 0..shots {
+            lv.set_origin(barrel_end);
+            let trace = tank.tank_trace(&engine, barrel_end, forward);
+            self.laser_time.set(now);
+            laser.turn_on();
+            // deal one second of the laser damage
+            lv.set_damage_time(now - 1.0);
+            laser.fire_at_point(&trace);
+            lv.stop_thinking();
+        }
+    }
+}
+
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TankLaser {
+    base: BaseTank<LaserGun>,
+}
+
+impl CreateEntity for TankLaser {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: BaseTank::create(base),
+        }
+    }
+}
+
+impl Entity for TankLaser {
+    delegate_entity!(base);
+}
+
+impl_private!(TankLaser {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_tanklaser {
+    () => {
+        $crate::export_entity!(func_tanklaser, $crate::func_tanklaser::TankLaser);
+    };
+}
+#[doc(inline)]
+pub use export_func_tanklaser as export;
@@ -0,0 +1,101 @@
+use xash3d_server::{
+    damage,
+    entity::{BaseEntity, DamageFlags, EntityVars, KeyValue, delegate_entity},
+    ffi::common::vec3_t,
+    prelude::*,
+    private::impl_private,
+    user_message, utils,
+};
+
+use crate::base_tank::{BaseTank, TankWeapon};
+
+/// Creates an explosion where the shot hits.
+#[derive(Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Mortar {
+    magnitude: i16,
+}
+
+impl TankWeapon for Mortar {
+    fn key_value(&mut self, _: &EntityVars, data: &mut KeyValue) -> bool {
+        if data.key_name() == c"iMagnitude" {
+            self.magnitude = data.parse_or_default();
+            data.set_handled(true);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn fire(
+        &self,
+        tank: &BaseTank<Self>,
+        barrel_end: vec3_t,
+        forward: vec3_t,
+        attacker: &EntityVars,
+        _: u32,
+    ) {
+        // only one explosion regardless of the number of shots
+        let engine = tank.engine();
+        let global_state = tank.global_state();
+        let trace = tank.tank_trace(&engine, barrel_end, forward);
+
+        let decal = global_state.decals().get_random_scorch();
+        utils::decal_trace(&engine, &trace, decal);
+
+        let magnitude = self.magnitude as f32;
+        let origin = if trace.fraction() != 1.0 {
+            trace.end_position() + trace.plane_normal() * (magnitude - 24.0) * 0.6
+        } else {
+            trace.end_position()
+        };
+        let scale = ((self.magnitude / 10 - 5) as f32 * 0.6) as u8;
+        let msg = user_message::Explosion {
+            position: origin.into(),
+            sprite_index: global_state.sprites().fireball(),
+            scale: scale.max(1).into(),
+            frame_rate: 15,
+            flags: user_message::ExplosionFlags::NONE,
+        };
+        engine.msg_pas(origin, &msg);
+
+        damage::radius_damage(
+            &engine,
+            origin,
+            tank.vars(),
+            Some(attacker),
+            magnitude,
+            magnitude * 2.5,
+            DamageFlags::BLAST,
+        );
+    }
+}
+
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TankMortar {
+    base: BaseTank<Mortar>,
+}
+
+impl CreateEntity for TankMortar {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: BaseTank::create(base),
+        }
+    }
+}
+
+impl Entity for TankMortar {
+    delegate_entity!(base);
+}
+
+impl_private!(TankMortar {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_tankmortar {
+    () => {
+        $crate::export_entity!(func_tankmortar, $crate::func_tankmortar::TankMortar);
+    };
+}
+#[doc(inline)]
+pub use export_func_tankmortar as export;
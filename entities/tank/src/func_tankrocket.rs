@@ -0,0 +1,72 @@
+use core::ffi::CStr;
+
+use xash3d_server::{
+    entity::{BaseEntity, EntityVars, create_entity, delegate_entity},
+    ffi::common::vec3_t,
+    prelude::*,
+    private::impl_private,
+    utils,
+};
+
+use crate::base_tank::{BaseTank, TankWeapon};
+
+const ROCKET_CLASS_NAME: &CStr = c"rpg_rocket";
+
+/// Launches `rpg_rocket` entities provided by the game.
+#[derive(Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct RocketLauncher;
+
+impl TankWeapon for RocketLauncher {
+    fn precache(&self, engine: &ServerEngine) {
+        utils::precache_other(engine, ROCKET_CLASS_NAME);
+    }
+
+    fn fire(
+        &self,
+        tank: &BaseTank<Self>,
+        barrel_end: vec3_t,
+        _: vec3_t,
+        _: &EntityVars,
+        shots: u32,
+    ) {
+        let engine = tank.engine();
+        let angles = tank.vars().angles();
+        let owner = Some(tank.entity_handle());
+        for _ in 0..shots {
+            if create_entity(&engine, ROCKET_CLASS_NAME, barrel_end, angles, owner).is_err() {
+                warn!("{}: failed to create a rocket", tank.pretty_name());
+                break;
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TankRocket {
+    base: BaseTank<RocketLauncher>,
+}
+
+impl CreateEntity for TankRocket {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: BaseTank::create(base),
+        }
+    }
+}
+
+impl Entity for TankRocket {
+    delegate_entity!(base);
+}
+
+impl_private!(TankRocket {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_tankrocket {
+    () => {
+        $crate::export_entity!(func_tankrocket, $crate::func_tankrocket::TankRocket);
+    };
+}
+#[doc(inline)]
+pub use export_func_tankrocket as export;
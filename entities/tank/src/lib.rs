@@ -0,0 +1,15 @@
+#![no_std]
+
+#[macro_use]
+extern crate log;
+
+mod base_tank;
+
+pub mod func_tank;
+pub mod func_tankcontrols;
+pub mod func_tanklaser;
+pub mod func_tankmortar;
+pub mod func_tankrocket;
+
+#[doc(hidden)]
+pub use xash3d_server::export::export_entity;
@@ -0,0 +1,40 @@
+use xash3d_server::{
+    entity::{BaseEntity, delegate_entity},
+    prelude::*,
+    private::impl_private,
+};
+
+use crate::func_trackchange::TrackChange;
+
+/// A track change that is moved by the train and switches the tracks ahead of it.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TrackAutoChange {
+    base: TrackChange,
+}
+
+impl CreateEntity for TrackAutoChange {
+    fn create(base: BaseEntity) -> Self {
+        let mut base = TrackChange::create(base);
+        base.auto = true;
+        Self { base }
+    }
+}
+
+impl Entity for TrackAutoChange {
+    delegate_entity!(base);
+}
+
+impl_private!(TrackAutoChange {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_trackautochange {
+    () => {
+        $crate::export_entity!(
+            func_trackautochange,
+            $crate::func_trackautochange::TrackAutoChange
+        );
+    };
+}
+#[doc(inline)]
+pub use export_func_trackautochange as export;
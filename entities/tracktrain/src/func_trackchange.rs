@@ -0,0 +1,621 @@
+use core::cell::Cell;
+
+use bitflags::bitflags;
+use xash3d_server::{
+    entity::{
+        BaseEntity, DamageFlags, EntityHandle, KeyValue, MoveType, ObjectCaps, Solid, UseType,
+        delegate_entity,
+    },
+    ffi::common::vec3_t,
+    prelude::*,
+    private::impl_private,
+    sound::PlatformSounds,
+    str::MapString,
+    utils::{AngularMove, LinearMove, Move, MoveState},
+};
+
+use crate::{func_tracktrain::TrackTrain, path_track::PathTrack};
+
+bitflags! {
+    #[derive(Copy, Clone)]
+    struct SpawnFlags: u32 {
+        const START_BOTTOM      = 1 << 3;
+        const DONT_MOVE         = 1 << 4;
+        const ROTATE_Z          = 1 << 6;
+        const ROTATE_X          = 1 << 7;
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+enum TrainCode {
+    /// The train is away from the track change.
+    #[default]
+    Safe = 0,
+    /// The train is moving or stands too close to the track change.
+    Blocking,
+    /// The train stands on the track change and moves with it.
+    Following,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+#[repr(u8)]
+enum Think {
+    #[default]
+    None = 0,
+    Find,
+    MoveDone,
+}
+
+/// A platform that moves and rotates a `func_tracktrain` between two tracks.
+///
+/// The top track is used at the start position and the bottom track at the end position.
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct TrackChange {
+    base: BaseEntity,
+
+    platform_sounds: PlatformSounds,
+    height: f32,
+    rotation: f32,
+    linear: LinearMove,
+    angular: AngularMove,
+
+    top_track_name: Option<MapString>,
+    bottom_track_name: Option<MapString>,
+    train_name: Option<MapString>,
+
+    /// Switches tracks ahead of the train instead of the tracks under the platform.
+    pub(crate) auto: bool,
+
+    top_track: Cell<Option<EntityHandle>>,
+    bottom_track: Cell<Option<EntityHandle>>,
+    train: Cell<Option<EntityHandle>>,
+    code: Cell<TrainCode>,
+    state: Cell<MoveState>,
+    target_state: Cell<MoveState>,
+    enable_use: Cell<bool>,
+    think: Cell<Think>,
+}
+
+impl CreateEntity for TrackChange {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+
+            platform_sounds: Default::default(),
+            height: 0.0,
+            rotation: 0.0,
+            linear: LinearMove::default(),
+            angular: AngularMove::default(),
+
+            top_track_name: None,
+            bottom_track_name: None,
+            train_name: None,
+
+            auto: false,
+
+            top_track: Cell::default(),
+            bottom_track: Cell::default(),
+            train: Cell::default(),
+            code: Cell::default(),
+            state: Cell::default(),
+            target_state: Cell::default(),
+            enable_use: Cell::default(),
+            think: Cell::default(),
+        }
+    }
+}
+
+impl TrackChange {
+    fn spawn_flags(&self) -> SpawnFlags {
+        SpawnFlags::from_bits_retain(self.vars().spawn_flags())
+    }
+
+    fn top_track(&self) -> Option<&PathTrack> {
+        self.top_track.get().downcast_ref::<PathTrack>()
+    }
+
+    fn bottom_track(&self) -> Option<&PathTrack> {
+        self.bottom_track.get().downcast_ref::<PathTrack>()
+    }
+
+    fn train(&self) -> Option<&TrackTrain> {
+        self.train.get().downcast_ref::<TrackTrain>()
+    }
+
+    /// Returns the track at the current position if the platform is not moving.
+    fn current_track(&self) -> Option<&PathTrack> {
+        match self.state.get() {
+            MoveState::AtStart => self.top_track(),
+            MoveState::AtEnd => self.bottom_track(),
+            _ => None,
+        }
+    }
+
+    fn find_target<T: Entity>(&self, target: Option<MapString>, what: &str) -> Option<&T> {
+        let name = self.pretty_name();
+        let Some(target) = target else {
+            error!("{name}: no {what}");
+            return None;
+        };
+        let entity = self.engine().entities().by_target_name(target).first();
+        let entity = entity.downcast_ref::<T>();
+        if entity.is_none() {
+            error!("{name}: can't find {what} {target}");
+        }
+        entity
+    }
+
+    fn find(&self) {
+        let Some(top) = self.find_target::<PathTrack>(self.top_track_name, "top track") else {
+            return;
+        };
+        let Some(bottom) = self.find_target::<PathTrack>(self.bottom_track_name, "bottom track")
+        else {
+            return;
+        };
+        let Some(train) = self.find_target::<TrackTrain>(self.train_name, "train") else {
+            return;
+        };
+
+        let v = self.vars();
+        let center = (v.abs_min() + v.abs_max()) * 0.5;
+        let top = top.nearest(center);
+        let bottom = bottom.nearest(center);
+        self.top_track.set(Some(top.entity_handle()));
+        self.bottom_track.set(Some(bottom.entity_handle()));
+        self.train.set(Some(train.entity_handle()));
+
+        self.update_auto_targets(self.state.get());
+    }
+
+    fn evaluate_train(&self, path: Option<&PathTrack>) -> TrainCode {
+        // nothing to switch, just be an elevator
+        let (Some(path), Some(train)) = (path, self.train()) else {
+            return TrainCode::Safe;
+        };
+
+        let on_path = train
+            .path()
+            .is_some_and(|i| path.is_adjacent(i.entity_handle()));
+        if !on_path {
+            return TrainCode::Safe;
+        }
+
+        let tv = train.vars();
+        if tv.speed() != 0.0 {
+            return TrainCode::Blocking;
+        }
+
+        let length = (self.vars().origin() - tv.origin()).with_z(0.0).length();
+        if length < train.length() {
+            TrainCode::Following
+        } else if length > train.length() + 150.0 {
+            TrainCode::Safe
+        } else {
+            TrainCode::Blocking
+        }
+    }
+
+    fn update_auto_targets(&self, state: MoveState) {
+        let (Some(top), Some(bottom)) = (self.top_track(), self.bottom_track()) else {
+            return;
+        };
+
+        if !self.auto {
+            top.set_disabled(state != MoveState::AtStart);
+            bottom.set_disabled(state != MoveState::AtEnd);
+            return;
+        }
+
+        let (target, next) = if self.target_state.get() == MoveState::AtStart {
+            (top.next(), bottom.next())
+        } else {
+            (bottom.next(), top.next())
+        };
+
+        if let Some(target) = target {
+            target.set_disabled(false);
+            if self.code.get() == TrainCode::Following {
+                if let Some(train) = self.train().filter(|i| i.vars().speed() == 0.0) {
+                    train.used(UseType::On, Some(self), self);
+                }
+            }
+        }
+
+        if let Some(next) = next {
+            next.set_disabled(true);
+        }
+    }
+
+    /// Moves the train with the platform and rotates it around the platform origin.
+    fn update_train(&self, train: &TrackTrain, dest: vec3_t) {
+        let v = self.vars();
+        let tv = train.vars();
+        let time = v.next_think_time() - v.last_think_time();
+        let mut velocity = v.velocity();
+        if time > 0.0 {
+            let offset = tv.origin() - v.origin();
+            let av = (dest - v.angles()).angle_vectors().transpose_all();
+            let local = vec3_t::new(
+                offset.dot(av.forward),
+                offset.dot(av.right),
+                offset.dot(av.up),
+            );
+            velocity += (local - offset) / time;
+        }
+        train.carry(velocity, v.angular_velocity(), time);
+    }
+
+    fn rot_move(&self, dest: vec3_t) {
+        let v = self.vars();
+        let delta = dest - v.angles();
+        let time = v.next_think_time() - v.last_think_time();
+        if time >= 0.1 {
+            v.set_angular_velocity(delta / time);
+        } else {
+            v.set_angular_velocity(delta);
+            v.set_next_think_time_from_last(1.0);
+        }
+    }
+
+    /// Returns `true` if movement is finished.
+    fn start_move(&self, to_end: bool) -> bool {
+        let v = self.vars();
+        let speed = v.speed();
+
+        if self.spawn_flags().intersects(SpawnFlags::DONT_MOVE) {
+            return if to_end {
+                self.angular.move_to_end(v, speed, false)
+            } else {
+                self.angular.move_to_start(v, speed)
+            };
+        }
+
+        let (done, dest) = if to_end {
+            (self.linear.move_to_end(v, speed, false), self.angular.end())
+        } else {
+            (self.linear.move_to_start(v, speed), self.angular.start())
+        };
+        if !done && self.rotation != 0.0 {
+            self.rot_move(dest);
+        }
+        done
+    }
+
+    fn carry_train(&self, dest: vec3_t) {
+        if self.code.get() != TrainCode::Following {
+            return;
+        }
+        if let Some(train) = self.train() {
+            self.update_train(train, dest);
+            train.clear_path();
+        }
+    }
+
+    fn go_start(&self) {
+        if self.code.get() == TrainCode::Blocking {
+            return;
+        }
+
+        self.update_auto_targets(MoveState::GoingToStart);
+        self.state.set(MoveState::GoingToStart);
+        self.platform_sounds.emit_moving_noise(self.vars());
+
+        if self.start_move(false) {
+            self.hit_start();
+        } else {
+            self.think.set(Think::MoveDone);
+            self.carry_train(self.angular.start());
+        }
+    }
+
+    fn go_end(&self) {
+        if self.code.get() == TrainCode::Blocking {
+            return;
+        }
+
+        self.update_auto_targets(MoveState::GoingToEnd);
+        self.state.set(MoveState::GoingToEnd);
+        self.platform_sounds.emit_moving_noise(self.vars());
+
+        if self.start_move(true) {
+            self.hit_end();
+        } else {
+            self.think.set(Think::MoveDone);
+            self.carry_train(self.angular.end());
+        }
+    }
+
+    fn hit_start(&self) {
+        self.move_finished(MoveState::AtStart, self.angular.start(), self.top_track());
+    }
+
+    fn hit_end(&self) {
+        self.move_finished(MoveState::AtEnd, self.angular.end(), self.bottom_track());
+    }
+
+    fn move_finished(&self, state: MoveState, angles: vec3_t, track: Option<&PathTrack>) {
+        let v = self.vars();
+        self.platform_sounds.emit_moving_stop_noise(v);
+        self.state.set(state);
+
+        v.set_velocity(vec3_t::ZERO);
+        v.set_angular_velocity(vec3_t::ZERO);
+        v.set_angles(angles);
+        self.think.set(Think::None);
+        v.stop_thinking();
+
+        if self.code.get() == TrainCode::Following {
+            if let (Some(train), Some(track)) = (self.train(), track) {
+                train.set_track(track);
+            }
+        }
+
+        self.update_auto_targets(state);
+        self.enable_use.set(true);
+    }
+
+    /// Moves the platform to the opposite position.
+    fn toggle(&self) {
+        self.enable_use.set(false);
+        if self.state.get() == MoveState::AtStart {
+            self.go_end();
+        } else {
+            self.go_start();
+        }
+    }
+
+    fn auto_used(&self, use_type: UseType, activator: Option<&dyn Entity>) {
+        if !self.enable_use.get() {
+            return;
+        }
+
+        let track = self.current_track();
+        let state = self.state.get();
+        let target_state = self.target_state.get();
+
+        if activator.is_some_and(|i| i.downcast_ref::<TrackTrain>().is_some()) {
+            self.code.set(self.evaluate_train(track));
+            if self.code.get() == TrainCode::Following && state != target_state {
+                self.toggle();
+            }
+            return;
+        }
+
+        let next = track.and_then(|i| i.next());
+        if let Some(next) = next {
+            let train_path = self.train().and_then(|i| i.path());
+            let on_next = train_path.is_some_and(|i| i.entity_handle() == next.entity_handle());
+            if !on_next && use_type.should_toggle(target_state == MoveState::AtEnd) {
+                if target_state == MoveState::AtStart {
+                    self.target_state.set(MoveState::AtEnd);
+                } else {
+                    self.target_state.set(MoveState::AtStart);
+                }
+            }
+        }
+
+        self.update_auto_targets(self.target_state.get());
+    }
+}
+
+impl Entity for TrackChange {
+    delegate_entity!(base not { object_caps, key_value, precache, spawn, used, blocked, think, override_reset });
+
+    fn object_caps(&self) -> ObjectCaps {
+        self.base
+            .object_caps()
+            .difference(ObjectCaps::ACROSS_TRANSITION)
+    }
+
+    fn key_value(&mut self, data: &mut KeyValue) {
+        let engine = self.engine();
+        match data.key_name().to_bytes() {
+            b"train" => self.train_name = Some(engine.new_map_string(data.value())),
+            b"toptrack" => self.top_track_name = Some(engine.new_map_string(data.value())),
+            b"bottomtrack" => self.bottom_track_name = Some(engine.new_map_string(data.value())),
+            b"height" => self.height = data.parse_or_default(),
+            b"rotation" => self.rotation = data.parse_or_default(),
+            _ => {
+                if self.platform_sounds.key_value(data) {
+                    return;
+                }
+                return self.base.key_value(data);
+            }
+        }
+        data.set_handled(true);
+    }
+
+    fn precache(&mut self) {
+        self.platform_sounds.precache(self.base.vars());
+        self.engine()
+            .precache_sound(res::valve::sound::buttons::BUTTON11);
+    }
+
+    fn spawn(&mut self) {
+        let sf = self.spawn_flags();
+        let v = self.base.vars();
+        v.set_angles(vec3_t::ZERO);
+        v.set_solid(Solid::Bsp);
+        v.set_move_type(MoveType::Push);
+        v.link();
+        v.set_size_and_link(v.min_size(), v.max_size());
+        v.reload_model();
+
+        if v.speed() == 0.0 {
+            v.set_speed(150.0);
+        }
+
+        self.platform_sounds.init();
+
+        self.linear.set_start(v.origin());
+        let mut end = v.origin();
+        // rotate only if the platform does not move
+        if !sf.intersects(SpawnFlags::DONT_MOVE) {
+            if self.height != 0.0 {
+                end.z -= self.height;
+            } else {
+                end.z = end.z - v.size().z + 8.0;
+            }
+        }
+        self.linear.set_end(end);
+
+        if self.rotation != 0.0 {
+            let x = SpawnFlags::ROTATE_X.bits();
+            let z = SpawnFlags::ROTATE_Z.bits();
+            v.set_move_dir_from_spawn_flags(x, z);
+            self.angular
+                .set_start(v.angles() + v.move_dir() * self.rotation);
+            self.angular.set_end(v.angles());
+        }
+
+        if sf.intersects(SpawnFlags::START_BOTTOM) {
+            v.set_origin_and_link(self.linear.end());
+            v.set_angles(self.angular.end());
+            self.state.set(MoveState::AtEnd);
+            self.target_state.set(MoveState::AtStart);
+        } else {
+            v.set_origin_and_link(self.linear.start());
+            v.set_angles(self.angular.start());
+            self.state.set(MoveState::AtStart);
+            self.target_state.set(MoveState::AtEnd);
+        }
+
+        self.enable_use.set(true);
+        self.think.set(Think::Find);
+        v.set_next_think_time_from_last(2.0);
+
+        self.precache();
+    }
+
+    fn used(&self, use_type: UseType, activator: Option<&dyn Entity>, _: &dyn Entity) {
+        if self.auto {
+            return self.auto_used(use_type, activator);
+        }
+
+        if self.state.get().is_moving() {
+            return;
+        }
+
+        self.code.set(self.evaluate_train(self.current_track()));
+        if self.code.get() == TrainCode::Blocking {
+            // the train is in the way, play the alarm
+            self.engine()
+                .build_sound()
+                .channel_voice()
+                .emit(res::valve::sound::buttons::BUTTON11, self.vars());
+            return;
+        }
+
+        self.toggle();
+    }
+
+    fn blocked(&self, other: &dyn Entity) {
+        trace!("{}: blocked by {}", self.pretty_name(), other.pretty_name());
+
+        let v = self.vars();
+        other.take_damage(1.0, DamageFlags::CRUSH, v, Some(v));
+
+        self.platform_sounds.stop_moving_noise(v);
+
+        match self.state.get() {
+            MoveState::GoingToStart => self.go_end(),
+            MoveState::GoingToEnd => self.go_start(),
+            _ => {}
+        }
+    }
+
+    fn think(&self) {
+        match self.think.take() {
+            Think::None => {}
+            Think::Find => self.find(),
+            Think::MoveDone => match self.state.get() {
+                MoveState::GoingToStart => self.hit_start(),
+                MoveState::GoingToEnd => self.hit_end(),
+                state => {
+                    warn!(
+                        "{}: move done in unexpected state {state:?}",
+                        self.pretty_name()
+                    );
+                }
+            },
+        }
+    }
+
+    fn override_reset(&self) {
+        self.think.set(Think::Find);
+        self.vars().set_next_think_time_from_last(1.0);
+    }
+}
+
+impl_private!(TrackChange {});
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_func_trackchange {
+    () => {
+        $crate::export_entity!(func_trackchange, $crate::func_trackchange::TrackChange);
+    };
+}
+#[doc(inline)]
+pub use export_func_trackchange as export;
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{
+        entity::UseType, ffi::common::vec3_t, prelude::*, testing, utils::MoveState,
+    };
+
+    use super::{Think, TrackChange};
+
+    #[test]
+    fn elevator_without_train() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let change = engine.new_entity::<TrackChange>().build();
+        change.height = 64.0;
+        change.vars().set_origin(vec3_t::new(0.0, 0.0, 128.0));
+        change.spawn();
+        assert_eq!(change.state.get(), MoveState::AtStart);
+        assert_eq!(change.linear.end(), vec3_t::new(0.0, 0.0, 64.0));
+
+        change.used(UseType::Toggle, None, &*change);
+        assert_eq!(change.state.get(), MoveState::GoingToEnd);
+        // moving down
+        assert!(change.vars().velocity().z < 0.0);
+
+        // ignored while moving
+        change.used(UseType::Toggle, None, &*change);
+        assert_eq!(change.state.get(), MoveState::GoingToEnd);
+
+        change.think();
+        assert_eq!(change.state.get(), MoveState::AtEnd);
+        assert_eq!(change.vars().velocity(), vec3_t::ZERO);
+        assert!(change.enable_use.get());
+
+        unsafe {
+            engine.remove_entity_now(change.vars());
+        }
+    }
+
+    #[test]
+    fn move_done_when_stopped() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let change = engine.new_entity::<TrackChange>().build();
+        change.spawn();
+
+        // a stale think does not change the state
+        change.think.set(Think::MoveDone);
+        change.think();
+        assert_eq!(change.state.get(), MoveState::AtStart);
+        assert_eq!(change.think.get(), Think::None);
+
+        unsafe {
+            engine.remove_entity_now(change.vars());
+        }
+    }
+}
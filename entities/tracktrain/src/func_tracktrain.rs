@@ -394,6 +394,33 @@ impl TrackTrain {
             utils::fire_targets(net_name, UseType::Toggle, Some(self), self);
         }
     }
+
+    pub(crate) fn length(&self) -> f32 {
+        self.length
+    }
+
+    pub(crate) fn path(&self) -> Option<&PathTrack> {
+        self.path.get().downcast_ref::<PathTrack>()
+    }
+
+    /// Detaches the train from its path while a track change carries it.
+    pub(crate) fn clear_path(&self) {
+        self.path.set(None);
+    }
+
+    /// Attaches the train to the track of the `path` nearest to the train.
+    pub(crate) fn set_track(&self, path: &PathTrack) {
+        let nearest = path.nearest(self.vars().origin());
+        self.path.set(Some(nearest.entity_handle()));
+    }
+
+    /// Moves the train with a track change for the `time` seconds.
+    pub(crate) fn carry(&self, velocity: vec3_t, angular_velocity: vec3_t, time: f32) {
+        let v = self.vars();
+        v.set_velocity(velocity);
+        v.set_angular_velocity(angular_velocity);
+        self.set_next_think_time_from_last(self.think.get(), time, false);
+    }
 }
 
 fn fix_angles(mut v: vec3_t) -> vec3_t {
@@ -570,3 +597,41 @@ macro_rules! export_func_tracktrain {
 }
 #[doc(inline)]
 pub use export_func_tracktrain as export;
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use xash3d_server::{
+        ffi::common::vec3_t,
+        prelude::*,
+        testing::{self, RoundTrip},
+    };
+
+    use super::{Think, TrackTrain};
+
+    #[test]
+    fn round_trip() {
+        let test = testing::lock();
+        let engine = test.engine();
+        let path = engine.new_entity::<TrackTrain>().build();
+        let path_handle = path.entity_handle();
+        let result = RoundTrip::new().time(3.0).entity::<TrackTrain>(|train| {
+            train.height = 4.0;
+            train.length = 256.0;
+            train.start_speed = 100.0;
+            train.speed = 400.0;
+            train.direction = -1.0;
+            train.bank = 15.0;
+            train.control_min_size = vec3_t::new(-8.0, -8.0, 0.0);
+            train.control_max_size = vec3_t::new(8.0, 8.0, 16.0);
+            train.sounds = 2;
+            train.volume = 0.8;
+            train.think.set(Think::DeadEnd(true));
+            train.path.set(Some(path_handle));
+            train.sound_playing.set(true);
+        });
+        assert_eq!(result, Ok(()));
+        unsafe {
+            engine.remove_entity_now(path.vars());
+        }
+    }
+}
@@ -4,6 +4,8 @@
 #[macro_use]
 extern crate log;
 
+pub mod func_trackautochange;
+pub mod func_trackchange;
 pub mod func_tracktrain;
 pub mod path_track;
 
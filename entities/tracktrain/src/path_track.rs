@@ -88,7 +88,7 @@ impl PathTrack {
         self.spawn_flags().intersects(SpawnFlags::DISABLED)
     }
 
-    fn set_disabled(&self, disabled: bool) {
+    pub(crate) fn set_disabled(&self, disabled: bool) {
         self.set_spawn_flags(SpawnFlags::DISABLED, disabled);
     }
 
@@ -155,6 +155,40 @@ impl PathTrack {
         self.next.get().downcast_ref()
     }
 
+    /// Returns `true` if the `path` is this track or its direct neighbour.
+    pub(crate) fn is_adjacent(&self, path: EntityHandle) -> bool {
+        self.entity_handle() == path
+            || self.previous.get() == Some(path)
+            || self.next.get() == Some(path)
+    }
+
+    /// Returns the track on this path nearest to the `origin` in the XY plane.
+    pub fn nearest(&self, origin: vec3_t) -> &Self {
+        let distance = |path: &Self| (origin - path.vars().origin()).with_z(0.0).length();
+        let mut nearest = self;
+        let mut min_dist = distance(self);
+        let mut cur = self.next();
+        // guard against badly linked looped paths
+        let mut count = 0;
+        while let Some(path) = cur {
+            if path.entity_handle() == self.entity_handle() {
+                break;
+            }
+            count += 1;
+            if count > 9999 {
+                error!("{}: bad sequence of path_tracks", self.pretty_name());
+                break;
+            }
+            let dist = distance(path);
+            if dist < min_dist {
+                min_dist = dist;
+                nearest = path;
+            }
+            cur = path.next();
+        }
+        nearest
+    }
+
     pub fn first(&self, is_move: bool) -> &Self {
         let mut cur = self;
         while let Some(next) = valid_path(cur.previous(), is_move) {
@@ -9,7 +9,7 @@ use xash3d_server::{
     prelude::*,
     private::impl_private,
     sound::PlatformSounds,
-    utils::{self, LinearMove, Move},
+    utils::{LinearMove, Move},
 };
 
 use crate::path_corner::PathCorner;
@@ -140,14 +140,8 @@ impl Train {
             .expect("current target must be a valid path corner");
 
         let v = self.vars();
-        let target_v = target.vars();
 
-        if let Some(message) = target_v.message().as_deref() {
-            utils::fire_targets(message, UseType::Toggle, Some(self), self);
-            if target.spawn_flags().has_fire_once() {
-                target_v.set_message(None);
-            }
-        }
+        target.fire_targets(self);
 
         if target.spawn_flags().has_wait_for_retrigger() || self.wait_for_retrigger() {
             self.set_wait_for_retrigger(true);
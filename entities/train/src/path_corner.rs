@@ -1,9 +1,10 @@
 use bitflags::bitflags;
 use xash3d_server::{
     entities::point_entity::PointEntity,
-    entity::{BaseEntity, KeyValue, delegate_entity},
+    entity::{BaseEntity, KeyValue, UseType, delegate_entity},
     prelude::*,
     private::impl_private,
+    utils,
 };
 
 bitflags! {
@@ -49,9 +50,21 @@ impl PathCorner {
         SpawnFlags::from_bits_retain(self.vars().spawn_flags())
     }
 
-    pub(crate) fn delay(&self) -> f32 {
+    /// Returns the time to wait at this corner.
+    pub fn delay(&self) -> f32 {
         self.wait
     }
+
+    /// Fires the targets in the `message` field.
+    pub fn fire_targets(&self, activator: &dyn Entity) {
+        let v = self.vars();
+        if let Some(message) = v.message().as_deref() {
+            utils::fire_targets(message, UseType::Toggle, Some(activator), activator);
+            if self.spawn_flags().has_fire_once() {
+                v.set_message(None);
+            }
+        }
+    }
 }
 
 impl Entity for PathCorner {
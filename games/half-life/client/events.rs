@@ -35,26 +35,11 @@ use xash3d_client::{
     render::RenderMode,
     sound::Attenuation,
 };
+use xash3d_hl_shared::weapons::Bullet;
 use xash3d_player_move as pm;
 
 use crate::export::events;
 
-#[allow(dead_code)]
-#[derive(Copy, Clone, PartialEq, Eq)]
-#[repr(C)]
-enum Bullet {
-    None = 0,
-    Player9mm,
-    PlayerMp5,
-    Player357,
-    PlayerBuckshot,
-    PlayerCrowbar,
-
-    Monster9mm,
-    MonsterMp5,
-    Monster12mm,
-}
-
 struct ShellInfo {
     origin: vec3_t,
     velocity: vec3_t,
@@ -68,9 +53,6 @@ pub struct Events {
 
 impl Events {
     pub fn new(engine: ClientEngineRef) -> Self {
-        // FIXME: force cl_lw to 0.0 because it is not implemented
-        engine.set_cvar(c"cl_lw", false);
-
         macro_rules! hook {
             ($engine:expr; $($event:expr => $func:ident),* $(,)?) => (
                 $(hook_event!($engine, $event, |args| {
@@ -126,14 +108,15 @@ impl Events {
 
     fn get_player_view_height(&self, args: &EventArgs) -> vec3_t {
         let idx = args.entindex();
+        let config = pm::movement_config();
         if self.is_player(idx) {
             if self.is_local(idx) {
                 return self.engine.event_api().local_player_view_height();
             } else if args.ducking() {
-                return vec3_t::new(0.0, 0.0, pm::DUCK_VIEW_OFFSET.z);
+                return vec3_t::new(0.0, 0.0, config.duck_view_offset.z);
             }
         }
-        vec3_t::new(0.0, 0.0, pm::VIEW_OFFSET.z)
+        vec3_t::new(0.0, 0.0, config.view_offset.z)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -193,7 +176,7 @@ impl Events {
         let engine = self.engine;
         let ev = engine.event_api();
 
-        let mut ch_texture_type = 0;
+        let mut ch_texture_type = pm::CHAR_TEX_CONCRETE;
 
         let entity = ev.index_from_trace(tr);
         if entity == 0 {
@@ -212,93 +195,24 @@ impl Events {
             }
         }
 
-        let fvol;
-        let fvolbar;
-        let samples: &[&CStr];
+        let materials = pm::materials();
+        let material = materials.get(ch_texture_type as u8);
         let mut fattn = Attenuation::NORM;
-
-        match ch_texture_type {
-            pm::CHAR_TEX_METAL => {
-                fvol = 0.9;
-                fvolbar = 0.3;
-                samples = &[sound::player::PL_METAL1, sound::player::PL_METAL2];
-            }
-            pm::CHAR_TEX_DIRT => {
-                fvol = 0.9;
-                fvolbar = 0.1;
-                samples = &[
-                    sound::player::PL_DIRT1,
-                    sound::player::PL_DIRT2,
-                    sound::player::PL_DIRT3,
-                ];
-            }
-            pm::CHAR_TEX_VENT => {
-                fvol = 0.5;
-                fvolbar = 0.3;
-                samples = &[sound::player::PL_DUCT1, sound::player::PL_DUCT2];
-            }
-            pm::CHAR_TEX_GRATE => {
-                fvol = 0.9;
-                fvolbar = 0.5;
-                samples = &[sound::player::PL_GRATE1, sound::player::PL_GRATE4];
-            }
-            pm::CHAR_TEX_TILE => {
-                fvol = 0.8;
-                fvolbar = 0.2;
-                samples = &[
-                    sound::player::PL_TILE1,
-                    sound::player::PL_TILE3,
-                    sound::player::PL_TILE2,
-                    sound::player::PL_TILE4,
-                ];
+        if material.id == pm::CHAR_TEX_FLESH as u8 {
+            if bullet == Bullet::PlayerCrowbar {
+                return 0.0;
             }
-            pm::CHAR_TEX_SLOSH => {
-                fvol = 0.9;
-                fvolbar = 0.0;
-                samples = &[
-                    sound::player::PL_SLOSH1,
-                    sound::player::PL_SLOSH3,
-                    sound::player::PL_SLOSH2,
-                    sound::player::PL_SLOSH4,
-                ];
-            }
-            pm::CHAR_TEX_WOOD => {
-                fvol = 0.9;
-                fvolbar = 0.2;
-                samples = &[
-                    sound::debris::WOOD1,
-                    sound::debris::WOOD2,
-                    sound::debris::WOOD3,
-                ];
-            }
-            pm::CHAR_TEX_GLASS | pm::CHAR_TEX_COMPUTER => {
-                fvol = 0.8;
-                fvolbar = 0.2;
-                samples = &[
-                    sound::debris::GLASS1,
-                    sound::debris::GLASS1,
-                    sound::debris::GLASS2,
-                    sound::debris::GLASS3,
-                ];
-            }
-            pm::CHAR_TEX_FLESH => {
-                if bullet == Bullet::PlayerCrowbar {
-                    return 0.0;
-                }
+            fattn = Attenuation::from(1.0);
+        }
 
-                fvol = 1.0;
-                fvolbar = 0.2;
-                samples = &[sound::weapons::BULLET_HIT1, sound::weapons::BULLET_HIT2];
-                fattn = Attenuation::from(1.0);
-            }
-            _ => {
-                fvol = 0.9;
-                fvolbar = 0.6;
-                samples = &[sound::player::PL_STEP1, sound::player::PL_STEP2];
-            }
+        let fvol = material.impact_volume;
+        let fvolbar = material.impact_volume_bar;
+        let samples = &material.impact_sounds;
+        if samples.is_empty() {
+            return fvolbar;
         }
 
-        let sample = samples[engine.random_int(0, samples.len() as c_int - 1) as usize];
+        let sample = &samples[engine.random_int(0, samples.len() as c_int - 1) as usize];
         ev.build_sound_at(tr.endpos)
             .entity(EntityIndex::WORLD_SPAWN)
             .channel_static()
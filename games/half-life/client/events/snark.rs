@@ -2,7 +2,7 @@ use core::ffi::c_int;
 
 use xash3d_client::{consts::PM_NORMAL, engine::event::EventArgs, prelude::*};
 use xash3d_hl_shared::weapons::snark::SqueakAnimation;
-use xash3d_player_move::{DUCK_HULL_MIN, HULL_MIN};
+use xash3d_player_move as pm;
 
 impl super::Events {
     pub(super) fn fire_snark(&self, args: &mut EventArgs) {
@@ -15,7 +15,8 @@ impl super::Events {
         let angles = args.angles();
         let mut src = origin;
         if args.ducking() {
-            src -= HULL_MIN.z - DUCK_HULL_MIN.z;
+            let config = pm::movement_config();
+            src -= config.hull_min - config.duck_hull_min;
         }
 
         let engine = self.engine;
@@ -871,6 +871,11 @@ fn hook_messages_and_commands(engine: ClientEngineRef) {
         true
     });
 
+    hook_user_message!(engine, TeamInfo, {
+        trace!("message TeamInfo is not implemented");
+        true
+    });
+
     hook_user_message!(engine, SetFOV, |_, msg| {
         let msg = msg.read::<user_message::SetFOV>()?;
         let hud = hud();
@@ -1,32 +1,346 @@
-use core::ffi::c_uint;
+use core::ffi::{CStr, c_uint};
 
 use xash3d_client::{
     cvar::Cvar,
-    ffi::common::{local_state_s, usercmd_s},
+    entity::Buttons,
+    ffi::common::{local_state_s, usercmd_s, vec3_t, weapon_data_s},
     prelude::*,
 };
+use xash3d_hl_shared::weapons::{
+    AmmoType, Bullet, Weapon, WeaponInfo,
+    crowbar::Crowbar,
+    glock::Glock,
+    mp5::Mp5,
+    python::Python,
+    shotgun::Shotgun,
+    state::{EventParams, WeaponContext, WeaponLogic, WeaponState, bullet_spread, item_post_frame},
+};
 
 use crate::export::hud;
 
+/// The weapon logic context for the predicted local player.
+struct ClientContext {
+    engine: ClientEngineRef,
+    /// `true` the first time this command is predicted.
+    run_funcs: bool,
+    buttons: Buttons,
+    random_seed: u32,
+    underwater: bool,
+    origin: vec3_t,
+    angles: vec3_t,
+    next_attack: f32,
+    view_model: i32,
+    weapon_anim: i32,
+    /// The last animation played on the view model.
+    anim_sent: Option<i32>,
+    /// Ammo counts indexed by [AmmoType::index].
+    ammo: [i32; AMMO_SLOTS],
+}
+
+const AMMO_SLOTS: usize = AmmoType::ALL.len() + 1;
+
+impl WeaponContext for ClientContext {
+    fn buttons(&self) -> Buttons {
+        self.buttons
+    }
+
+    fn random_seed(&self) -> u32 {
+        self.random_seed
+    }
+
+    fn is_underwater(&self) -> bool {
+        self.underwater
+    }
+
+    fn next_attack(&self) -> f32 {
+        self.next_attack
+    }
+
+    fn set_next_attack(&mut self, delay: f32) {
+        self.next_attack = delay;
+    }
+
+    fn ammo(&self, ty: AmmoType) -> i32 {
+        self.ammo[ty.index()]
+    }
+
+    fn set_ammo(&mut self, ty: AmmoType, count: i32) {
+        self.ammo[ty.index()] = count;
+    }
+
+    fn set_models(&mut self, view_model: Option<&'static CStr>, _: Option<&'static CStr>) {
+        let ev = self.engine.event_api();
+        self.view_model = view_model.map_or(0, |i| ev.find_model_index(i));
+    }
+
+    fn send_weapon_anim(&mut self, anim: i32, body: i32) {
+        self.weapon_anim = anim;
+        if self.run_funcs {
+            self.engine.event_api().weapon_animation(anim, body);
+            self.anim_sent = Some(anim);
+        }
+    }
+
+    fn player_attack(&mut self) {
+        // the muzzle flash is played by weapon events
+    }
+
+    fn play_weapon_sound(&mut self, _: &'static CStr, _: f32, _: i32) {
+        // sounds are played by the server and weapon events
+    }
+
+    fn play_item_sound(&mut self, _: &'static CStr, _: f32, _: i32) {
+        // sounds are played by the server and weapon events
+    }
+
+    fn fire_bullets(&mut self, shots: u32, spread: f32, _: f32, _: Bullet) -> (f32, f32) {
+        // bullets are traced by weapon events
+        bullet_spread(self.random_seed, shots, spread)
+    }
+
+    fn melee_attack(&mut self, _: f32, _: Bullet, _: f32) -> bool {
+        // hits are reported by the server
+        false
+    }
+
+    fn playback_event(&mut self, event: &'static CStr, params: EventParams) {
+        if !self.run_funcs {
+            return;
+        }
+        let ev = self.engine.event_api();
+        let index = ev.precache_event(event);
+        ev.playback_event(
+            0,
+            index,
+            0.0,
+            self.origin,
+            self.angles,
+            params.fparam1,
+            params.fparam2,
+            params.iparam1,
+            params.iparam2,
+            params.bparam1.into(),
+            params.bparam2.into(),
+        );
+    }
+
+    fn switch_to_next_best(&mut self) -> bool {
+        // the server selects the next weapon
+        false
+    }
+}
+
+/// A predicted weapon state.
+trait LocalWeapon {
+    fn read(&mut self, data: &weapon_data_s);
+
+    fn write(&self, data: &mut weapon_data_s);
+
+    fn info(&self) -> &'static WeaponInfo;
+
+    fn deploy(&mut self, ctx: &mut ClientContext);
+
+    fn holster(&mut self, ctx: &mut ClientContext);
+
+    fn item_post_frame(&mut self, ctx: &mut ClientContext);
+
+    fn decrement_timers(&mut self, time: f32);
+}
+
+#[derive(Default)]
+struct Local<L> {
+    logic: L,
+    state: WeaponState,
+}
+
+impl<L: WeaponLogic> LocalWeapon for Local<L> {
+    fn read(&mut self, data: &weapon_data_s) {
+        self.state.read_weapon_data(data);
+    }
+
+    fn write(&self, data: &mut weapon_data_s) {
+        self.state.write_weapon_data(L::ID, data);
+    }
+
+    fn info(&self) -> &'static WeaponInfo {
+        L::info()
+    }
+
+    fn deploy(&mut self, ctx: &mut ClientContext) {
+        self.logic.deploy(&mut self.state, ctx);
+    }
+
+    fn holster(&mut self, ctx: &mut ClientContext) {
+        self.logic.holster(&mut self.state, ctx);
+    }
+
+    fn item_post_frame(&mut self, ctx: &mut ClientContext) {
+        item_post_frame(&mut self.logic, &mut self.state, ctx);
+    }
+
+    fn decrement_timers(&mut self, time: f32) {
+        self.state.decrement_timers(time);
+    }
+}
+
+#[derive(Default)]
+struct LocalWeapons {
+    crowbar: Local<Crowbar>,
+    glock: Local<Glock>,
+    python: Local<Python>,
+    mp5: Local<Mp5>,
+    shotgun: Local<Shotgun>,
+}
+
+impl LocalWeapons {
+    const PREDICTED: [Weapon; 5] = [
+        Weapon::Crowbar,
+        Weapon::Glock,
+        Weapon::Python,
+        Weapon::Mp5,
+        Weapon::Shotgun,
+    ];
+
+    fn get_mut(&mut self, id: Weapon) -> Option<&mut dyn LocalWeapon> {
+        Some(match id {
+            Weapon::Crowbar => &mut self.crowbar,
+            Weapon::Glock => &mut self.glock,
+            Weapon::Python => &mut self.python,
+            Weapon::Mp5 => &mut self.mp5,
+            Weapon::Shotgun => &mut self.shotgun,
+            _ => return None,
+        })
+    }
+}
+
 pub struct Weapons {
+    engine: ClientEngineRef,
     cl_lw: Option<Cvar<bool>>,
+    local: LocalWeapons,
+    /// The last animation played on the view model.
+    current_anim: i32,
 }
 
 impl Weapons {
     pub fn new(engine: ClientEngineRef) -> Self {
         let cl_lw = engine.find_cvar(c"cl_lw");
-        Self { cl_lw }
+        Self {
+            engine,
+            cl_lw,
+            local: LocalWeapons::default(),
+            current_anim: 0,
+        }
     }
 
     fn weapons_post_think(
         &mut self,
-        _from: &mut local_state_s,
-        _to: &mut local_state_s,
-        _cmd: &mut usercmd_s,
-        _time: f64,
-        _random_seed: c_uint,
+        from: &mut local_state_s,
+        to: &mut local_state_s,
+        cmd: &mut usercmd_s,
+        run_funcs: bool,
+        random_seed: c_uint,
     ) {
-        // TODO:
+        let Some(id) = Weapon::from_raw(from.client.m_iId as u8) else {
+            return;
+        };
+
+        for weapon in LocalWeapons::PREDICTED {
+            let index = weapon.into_raw() as usize;
+            if let Some(local) = self.local.get_mut(weapon) {
+                local.read(&from.weapondata[index]);
+            }
+        }
+
+        let client = &from.client;
+        let mut ammo = [0; AMMO_SLOTS];
+        for ty in AmmoType::ALL {
+            ammo[ty.index()] = ty.client_ammo(client).unwrap_or(0);
+        }
+        // the active weapon ammo is sent even if the type has no slot in client data
+        if let Some(ty) = AmmoType::from_raw(client.vuser4.x as u8) {
+            ammo[ty.index()] = client.vuser4.y as i32;
+        }
+        if let Some(ty) = AmmoType::from_raw(client.vuser3.z as u8) {
+            ammo[ty.index()] = client.vuser4.z as i32;
+        }
+        let mut ctx = ClientContext {
+            engine: self.engine,
+            run_funcs,
+            buttons: Buttons::from_bits_retain(cmd.buttons.into()),
+            random_seed,
+            underwater: client.waterlevel == 3,
+            origin: to.playerstate.origin,
+            angles: cmd.viewangles,
+            next_attack: client.m_flNextAttack,
+            view_model: client.viewmodel,
+            weapon_anim: client.weaponanim,
+            anim_sent: None,
+            ammo,
+        };
+
+        // don't fire if dead, spectating or without a view model
+        let alive = client.health > 0.0 && client.iuser1 == 0;
+        if alive && ctx.view_model != 0 && ctx.next_attack <= 0.0 {
+            if let Some(active) = self.local.get_mut(id) {
+                active.item_post_frame(&mut ctx);
+            }
+        }
+
+        to.client.m_iId = from.client.m_iId;
+
+        let select = Weapon::from_raw(cmd.weaponselect);
+        if let Some(select) = select.filter(|&i| i != id && i != Weapon::None) {
+            let index = select.into_raw() as usize;
+            let owned = from.weapondata[index].m_iId == index as i32;
+            if alive && owned && self.local.get_mut(select).is_some() {
+                if let Some(active) = self.local.get_mut(id) {
+                    active.holster(&mut ctx);
+                }
+                if let Some(new) = self.local.get_mut(select) {
+                    new.deploy(&mut ctx);
+                }
+                to.client.m_iId = index as i32;
+            }
+        }
+
+        to.client.viewmodel = ctx.view_model;
+        to.client.weaponanim = ctx.weapon_anim;
+        to.client.m_flNextAttack = ctx.next_attack;
+        for ty in AmmoType::ALL {
+            ty.set_client_ammo(&mut to.client, ctx.ammo(ty));
+        }
+        let active = Weapon::from_raw(to.client.m_iId as u8);
+        if let Some(active) = active.and_then(|i| self.local.get_mut(i)) {
+            let info = active.info();
+            let ammo = |ty: Option<AmmoType>| ty.map_or(0, |ty| ctx.ammo(ty));
+            to.client.vuser4.x = info.ammo1.map_or(0, |i| i.into_raw()) as f32;
+            to.client.vuser4.y = ammo(info.ammo1) as f32;
+            to.client.vuser3.z = info.ammo2.map_or(0, |i| i.into_raw()) as f32;
+            to.client.vuser4.z = ammo(info.ammo2) as f32;
+        }
+
+        if let Some(anim) = ctx.anim_sent {
+            self.current_anim = anim;
+        }
+
+        // play animations from the server that were not predicted
+        if run_funcs && self.current_anim != to.client.weaponanim {
+            self.current_anim = to.client.weaponanim;
+            self.engine
+                .event_api()
+                .weapon_animation(to.client.weaponanim, 0);
+        }
+
+        // the server decrements timers at the same time in post think
+        let time = cmd.msec as f32 / 1000.0;
+        for weapon in LocalWeapons::PREDICTED {
+            let index = weapon.into_raw() as usize;
+            if let Some(local) = self.local.get_mut(weapon) {
+                local.decrement_timers(time);
+                local.write(&mut to.weapondata[index]);
+            }
+        }
+        to.client.m_flNextAttack = (to.client.m_flNextAttack - time).max(-0.001);
     }
 
     pub fn post_run_cmd(
@@ -34,15 +348,14 @@ impl Weapons {
         from: &mut local_state_s,
         to: &mut local_state_s,
         cmd: &mut usercmd_s,
-        _runfuncs: bool,
-        time: f64,
+        run_funcs: bool,
+        _time: f64,
         random_seed: c_uint,
     ) {
         if cfg!(feature = "client-weapons") && self.cl_lw.as_ref().is_some_and(|i| i.get()) {
-            self.weapons_post_think(from, to, cmd, time, random_seed);
-        } else {
-            to.client.fov = hud().last_fov() as f32;
+            self.weapons_post_think(from, to, cmd, run_funcs, random_seed);
         }
+        to.client.fov = hud().last_fov() as f32;
 
         // TODO: gauss predication
 
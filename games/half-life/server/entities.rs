@@ -1,9 +1,12 @@
+pub mod ammo;
 pub mod env_beverage;
 pub mod healthkit;
 pub mod item_battery;
 pub mod item_sodacan;
 pub mod item_suit;
 pub mod player;
+pub mod weaponbox;
+pub mod weapons;
 pub mod world_items;
 
 xash3d_entities::export_enabled!();
@@ -0,0 +1,138 @@
+use core::{ffi::CStr, marker::PhantomData};
+
+use res::valve::{models, sound};
+use xash3d_entities::item::BaseItem;
+use xash3d_hl_shared::weapons::AmmoType;
+use xash3d_server::{
+    entity::{BaseEntity, EntityItem, delegate_entity},
+    export::export_entity,
+    prelude::*,
+    save::{Restore, Save},
+};
+
+use crate::user_message;
+
+/// Properties of an ammo item.
+pub trait AmmoKind: 'static {
+    const MODEL_NAME: &'static CStr;
+    const AMMO_TYPE: AmmoType;
+    const COUNT: i32;
+    /// The amount of ammo given in multiplayer.
+    const MULTIPLAYER_COUNT: i32 = Self::COUNT;
+}
+
+#[derive(Save, Restore)]
+pub struct Ammo<K> {
+    base: BaseItem,
+    #[save(skip)]
+    kind: PhantomData<K>,
+}
+
+impl<K: AmmoKind> CreateEntity for Ammo<K> {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base: BaseItem::create(base),
+            kind: Default::default(),
+        }
+    }
+}
+
+impl<K: AmmoKind> Ammo<K> {
+    const PICKUP_SOUND: &'static CStr = sound::items::_9MMCLIP1;
+}
+
+impl<K: AmmoKind> Entity for Ammo<K> {
+    delegate_entity!(base not { precache, spawn, touched });
+
+    fn precache(&mut self) {
+        let engine = self.engine();
+        engine.precache_model(K::MODEL_NAME);
+        engine.precache_sound(Self::PICKUP_SOUND);
+    }
+
+    fn spawn(&mut self) {
+        self.precache();
+        self.vars().set_model(K::MODEL_NAME);
+        self.base.spawn();
+    }
+
+    fn touched(&self, other: &dyn Entity) {
+        self.try_give(other);
+    }
+}
+
+impl<K: AmmoKind> EntityItem for Ammo<K> {
+    fn try_give(&self, other: &dyn Entity) -> bool {
+        self.base.try_give_to_player(self, other, |player| {
+            if !player.is_alive() {
+                return false;
+            }
+            let ty = K::AMMO_TYPE;
+            let count = if self.global_state().game_rules().is_multiplayer() {
+                K::MULTIPLAYER_COUNT
+            } else {
+                K::COUNT
+            };
+            let added = player
+                .inventory()
+                .give_ammo(ty.index(), count, ty.max_carry());
+            if added == 0 {
+                return false;
+            }
+
+            let engine = self.engine();
+            let player_v = player.vars();
+            let msg = user_message::AmmoPickup {
+                index: ty.into_raw(),
+                count: added as u8,
+            };
+            engine.msg_one(player_v, &msg);
+            engine
+                .build_sound()
+                .channel_item()
+                .emit_dyn(Self::PICKUP_SOUND, player_v);
+            true
+        })
+    }
+}
+
+macro_rules! define_ammo {
+    ($( $name:ident = $model:expr, $ty:ident, $count:expr $(, $mp_count:expr)?; )*) => {
+        $(
+            pub struct $name;
+
+            impl AmmoKind for $name {
+                const MODEL_NAME: &'static CStr = $model;
+                const AMMO_TYPE: AmmoType = AmmoType::$ty;
+                const COUNT: i32 = $count;
+                $(const MULTIPLAYER_COUNT: i32 = $mp_count;)?
+            }
+        )*
+    };
+}
+
+define_ammo! {
+    GlockClip = models::W_9MMCLIP, NineMm, 17;
+    Mp5Clip = models::W_9MMARCLIP, NineMm, 50;
+    ChainBox = models::W_CHAINAMMO, NineMm, 200;
+    Mp5Grenades = models::W_ARGRENADE, ArGrenades, 2;
+    PythonBox = models::W_357AMMOBOX, Magnum, 6;
+    Buckshot = models::W_SHOTBOX, Buckshot, 12;
+    CrossbowClip = models::W_CROSSBOW_CLIP, Bolts, 5;
+    RpgClip = models::W_RPGAMMO, Rockets, 1, 2;
+    GaussClip = models::W_GAUSSAMMO, Uranium, 20;
+}
+
+export_entity!(ammo_9mmclip, Ammo<GlockClip> { EntityItem });
+export_entity!(ammo_glockclip, Ammo<GlockClip>);
+export_entity!(ammo_9mmAR, Ammo<Mp5Clip> { EntityItem });
+export_entity!(ammo_mp5clip, Ammo<Mp5Clip>);
+export_entity!(ammo_9mmbox, Ammo<ChainBox> { EntityItem });
+export_entity!(ammo_ARgrenades, Ammo<Mp5Grenades> { EntityItem });
+export_entity!(ammo_mp5grenades, Ammo<Mp5Grenades>);
+export_entity!(ammo_357, Ammo<PythonBox> { EntityItem });
+export_entity!(ammo_buckshot, Ammo<Buckshot> { EntityItem });
+export_entity!(ammo_crossbow, Ammo<CrossbowClip> { EntityItem });
+export_entity!(ammo_rpgclip, Ammo<RpgClip> { EntityItem });
+export_entity!(ammo_gaussclip, Ammo<GaussClip> { EntityItem });
+export_entity!(ammo_egonclip, Ammo<GaussClip>);
@@ -7,13 +7,18 @@ use xash3d_entities::{
     beam::{Beam, BeamType},
     player::Player as BasePlayer,
 };
+use xash3d_hl_shared::weapons::{AmmoType, WeaponInfo};
 use xash3d_server::{
     color::RGB,
     csz::CStrThin,
+    damage::HitGroup,
+    engine::TraceResult,
     entity::{
-        BaseEntity, Buttons, Effects, EntityHandle, EntityPlayer, EntityVars, UseType,
+        BaseEntity, DamageFlags, Dead, Effects, EntityHandle, EntityPlayer, EntityVars, UseType,
         delegate_entity, delegate_player,
     },
+    ffi::common::vec3_t,
+    inventory::MAX_AMMO_SLOTS,
     prelude::*,
     private::impl_private,
     save::{Restore, Save},
@@ -22,7 +27,7 @@ use xash3d_server::{
     utils,
 };
 
-use crate::user_message;
+use crate::{game_rules::SkillData, user_message};
 
 pub const WEAPON_SUIT: u32 = 1_u32 << 31;
 pub const MAX_NORMAL_BATTERY: f32 = 100.0;
@@ -92,6 +97,22 @@ struct Flashlight {
 struct ClientState {
     health: Cell<f32>,
     battery: Cell<f32>,
+    weapon_list: Cell<bool>,
+    ammo: [Cell<i32>; MAX_AMMO_SLOTS],
+    /// The active weapon id and clip.
+    weapon: Cell<Option<(usize, i32)>>,
+}
+
+impl ClientState {
+    fn reset(&self) {
+        self.health.set(-1.0);
+        self.battery.set(-1.0);
+        self.weapon_list.set(false);
+        for i in &self.ammo {
+            i.set(-1);
+        }
+        self.weapon.set(None);
+    }
 }
 
 #[derive(Save, Restore)]
@@ -197,7 +218,21 @@ impl TestPlayer {
                 }
             }
             101 => {
-                self.give_named_item(c"item_suit".into());
+                const ITEMS: &[&CStr] = &[
+                    c"item_suit",
+                    c"weapon_crowbar",
+                    c"weapon_9mmhandgun",
+                    c"ammo_9mmclip",
+                    c"weapon_357",
+                    c"ammo_357",
+                    c"weapon_9mmAR",
+                    c"ammo_9mmAR",
+                    c"weapon_shotgun",
+                    c"ammo_buckshot",
+                ];
+                for name in ITEMS {
+                    self.give_named_item((*name).into());
+                }
             }
             impulse => {
                 warn!("unimplemented impulse command {impulse}");
@@ -232,6 +267,7 @@ impl TestPlayer {
             if !self.game_hud_initialized.get() {
                 self.game_hud_initialized.set(true);
                 engine.msg_one_reliable(self, &user_message::InitHUD::default());
+                global_state.game_rules().init_hud(self);
             }
 
             utils::fire_targets(c"game_playerspawn".into(), UseType::Toggle, None, self);
@@ -245,6 +281,15 @@ impl TestPlayer {
             engine.msg_one_reliable(self, &user_message::Geiger::default());
         }
 
+        if !self.client.weapon_list.get() {
+            self.client.weapon_list.set(true);
+            for info in WeaponInfo::all() {
+                engine.msg_one_reliable(v, &info.weapon_list());
+            }
+        }
+
+        self.update_weapons();
+
         if v.health() != self.client.health.get() {
             let health = if v.health() > 0.0 && v.health() < 1.0 {
                 1
@@ -286,9 +331,38 @@ impl TestPlayer {
         }
     }
 
+    fn update_weapons(&self) {
+        let engine = self.engine();
+        let v = self.vars();
+        let inventory = self.inventory();
+
+        for ty in AmmoType::ALL {
+            let count = inventory.ammo(ty.index()).clamp(0, 254);
+            let last = &self.client.ammo[ty.index()];
+            if last.get() != count {
+                last.set(count);
+                let msg = user_message::AmmoX {
+                    ty: ty.into_raw(),
+                    count: count as u8,
+                };
+                engine.msg_one(v, &msg);
+            }
+        }
+
+        let weapon = inventory.active().map(|i| (i.weapon_id(), i.clip()));
+        if weapon != self.client.weapon.get() {
+            self.client.weapon.set(weapon);
+            let (state, id, clip) = match weapon {
+                Some((id, clip)) => (1, id as i8, clip as i8),
+                None => (0, 0, 0),
+            };
+            let msg = user_message::CurWeapon { state, id, clip };
+            engine.msg_one_reliable(v, &msg);
+        }
+    }
+
     pub fn force_update_client_data(&self) {
-        self.client.health.set(-1.0);
-        self.client.battery.set(1.0);
+        self.client.reset();
         self.init_hud.set(true);
 
         self.update_client_data();
@@ -354,7 +428,7 @@ impl TestPlayer {
 }
 
 impl Entity for TestPlayer {
-    delegate_entity!(base not { precache, spawn, think });
+    delegate_entity!(base not { precache, spawn, think, trace_attack });
 
     fn precache(&mut self) {
         self.base.precache();
@@ -417,6 +491,22 @@ impl Entity for TestPlayer {
             v.set_next_think_time_from_now(0.2);
         }
     }
+
+    fn trace_attack(
+        &self,
+        attacker: &EntityVars,
+        damage: f32,
+        dir: vec3_t,
+        trace: &TraceResult,
+        damage_type: DamageFlags,
+    ) {
+        let scale = match self.global_state().try_get::<SkillData>() {
+            Some(skill_data) => skill_data.player_hit_group_scale(HitGroup::from_trace(trace)),
+            None => 1.0,
+        };
+        self.base
+            .trace_attack(attacker, damage * scale, dir, trace, damage_type);
+    }
 }
 
 impl EntityPlayer for TestPlayer {
@@ -425,7 +515,7 @@ impl EntityPlayer for TestPlayer {
     fn pre_think(&self) {
         self.base.pre_think();
 
-        if self.base.check_player_use() {
+        if self.vars().dead() == Dead::No && self.base.check_player_use() {
             self.base.player_use_custom(|target, use_type| {
                 target.used(use_type, Some(self), self);
             });
@@ -435,62 +525,6 @@ impl EntityPlayer for TestPlayer {
 
         self.check_suit_update();
 
-        let pressed = self.base.input.pressed();
-        if pressed.intersects(Buttons::ATTACK | Buttons::ATTACK2) {
-            use xash3d_server::color::RGB;
-            use xash3d_server::engine::TraceIgnore;
-            use xash3d_server::user_message;
-            use xash3d_server::utils;
-
-            let engine = self.engine();
-            let global_state = engine.global_state_ref();
-            let v = self.vars();
-            let start = v.origin() + v.view_ofs() * 0.5;
-            let forward = v.view_angle().angle_vectors().forward();
-            let end = start + forward * 1000.0;
-            let trace = engine.trace_line(start, end, TraceIgnore::MONSTERS, Some(v));
-
-            if true {
-                let decals = global_state.decals();
-                let decal_index = if pressed.intersects(Buttons::ATTACK) {
-                    decals.get_random_blood()
-                } else {
-                    use xash3d_server::global_state::decals::DefaultDecals;
-                    let decals: &DefaultDecals = decals.downcast_ref().unwrap();
-                    decals.get_random_yellow_blood()
-                };
-                utils::decal_trace(&engine, &trace, decal_index);
-            }
-
-            if true {
-                let blood = utils::Blood::Red;
-                let end = trace.end_position();
-                if pressed.intersects(Buttons::ATTACK) {
-                    blood.emit_blood_drips(&engine, end, 10);
-                } else {
-                    let amount = engine.random_int(50, 150) as u8;
-                    blood.emit_blood_stream(&engine, end, -forward, amount);
-                }
-            }
-
-            for _ in 0..engine.random_int(1, 3) {
-                use xash3d_server::entity::create_entity;
-                let pos = trace.end_position();
-                let angles = trace.plane_normal();
-                create_entity(&engine, c"spark_shower", pos, angles, None).ok();
-            }
-
-            if false {
-                let msg = user_message::Line {
-                    start: start.into(),
-                    end: trace.end_position().into(),
-                    duration: 3.0.into(),
-                    color: RGB::GREEN,
-                };
-                engine.msg_one(v, &msg);
-            }
-        }
-
         if let Some(beam) = self.test_beam.downcast_ref::<Beam>() {
             let v = self.vars();
             let start = v.origin() + v.view_ofs() * 0.75;
@@ -504,7 +538,14 @@ impl EntityPlayer for TestPlayer {
     fn post_think(&self) {
         self.impulse_commands();
 
+        let inventory = self.inventory();
+        if self.is_alive() {
+            inventory.item_post_frame();
+        }
+
         self.base.post_think();
+
+        inventory.decrement_timers(self.engine().globals.frame_time());
     }
 
     fn set_geiger_range(&self, range: f32) {
@@ -0,0 +1,168 @@
+use core::ffi::CStr;
+
+use res::valve::{models, sound};
+use xash3d_hl_shared::weapons::AmmoType;
+use xash3d_server::{
+    entities::item::SF_ITEM_NO_RESPAWN,
+    entity::{
+        BaseEntity, EdictFlags, Effects, EntityPlayer, EntityVars, MoveType, PlayerWeapon, Solid,
+        delegate_entity,
+    },
+    export::export_entity,
+    ffi::common::vec3_t,
+    inventory::{Inventory, MAX_WEAPONS},
+    prelude::*,
+    save::{Restore, Save},
+};
+
+use crate::user_message;
+
+/// A box with weapons and ammo dropped by a dead player.
+#[derive(Save, Restore)]
+pub struct WeaponBox {
+    base: BaseEntity,
+    items: Inventory,
+}
+
+impl CreateEntity for WeaponBox {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+            items: Inventory::default(),
+        }
+    }
+}
+
+impl WeaponBox {
+    pub const CLASS_NAME: &'static CStr = c"weaponbox";
+
+    /// The time before an untouched box is removed.
+    const LIFETIME: f32 = 120.0;
+
+    /// Moves the active weapon and its ammo from the player inventory into a new box.
+    pub fn pack_active_weapon(engine: &ServerEngine, player: &dyn EntityPlayer) {
+        let inventory = player.inventory();
+        let Some(weapon) = inventory.active() else {
+            return;
+        };
+        let v = player.vars();
+        let weapon_box = engine
+            .new_entity::<Self>()
+            .class_name(Self::CLASS_NAME)
+            .vars(|i| {
+                i.set_origin(v.origin());
+                i.set_angles(vec3_t::new(0.0, v.angles().y, 0.0));
+            })
+            .build_and_spawn();
+        let box_v = weapon_box.vars();
+        box_v.set_velocity(v.velocity() * 1.2);
+        box_v.set_next_think_time(engine.globals.map_time() + Self::LIFETIME);
+
+        for ammo in [weapon.primary_ammo(), weapon.secondary_ammo()]
+            .into_iter()
+            .flatten()
+        {
+            weapon_box.items.set_ammo(ammo, inventory.ammo(ammo));
+            inventory.set_ammo(ammo, 0);
+        }
+
+        inventory.remove(weapon.weapon_id());
+        v.with_weapons(|f| f & !(1 << weapon.weapon_id()));
+        weapon_box.pack_weapon(weapon);
+    }
+
+    fn pack_weapon(&self, weapon: &dyn PlayerWeapon) {
+        if !self.items.insert(weapon) {
+            weapon.remove_from_world();
+            return;
+        }
+        let v = weapon.vars();
+        v.set_move_type(MoveType::None);
+        v.set_solid(Solid::Not);
+        v.set_effects(Effects::NODRAW);
+        v.set_aim_entity(None::<&EntityVars>);
+        v.set_owner(self.vars());
+        // a picked up weapon must not spawn a copy
+        v.with_spawn_flags(|f| f | SF_ITEM_NO_RESPAWN);
+    }
+
+    fn take_weapon(&self, id: usize) -> Option<&dyn PlayerWeapon> {
+        let weapon = self.items.remove(id).downcast_ref::<dyn PlayerWeapon>()?;
+        weapon.vars().set_owner(None::<&EntityVars>);
+        Some(weapon)
+    }
+}
+
+impl Entity for WeaponBox {
+    delegate_entity!(base not { precache, spawn, think, touched });
+
+    fn precache(&mut self) {
+        self.engine().precache_model(models::W_WEAPONBOX);
+    }
+
+    fn spawn(&mut self) {
+        self.precache();
+        let engine = self.engine();
+        let v = self.vars();
+        v.set_move_type(MoveType::Toss);
+        v.set_solid(Solid::Trigger);
+        v.set_model(models::W_WEAPONBOX);
+        engine.set_size(v, vec3_t::ZERO, vec3_t::ZERO);
+    }
+
+    fn think(&self) {
+        for id in 0..MAX_WEAPONS {
+            if let Some(weapon) = self.take_weapon(id) {
+                weapon.remove_from_world();
+            }
+        }
+        self.remove_from_world();
+    }
+
+    fn touched(&self, other: &dyn Entity) {
+        if !self.vars().flags().intersects(EdictFlags::ONGROUND) {
+            return;
+        }
+        let Some(player) = other.as_player() else {
+            return;
+        };
+        if !player.is_alive() {
+            return;
+        }
+
+        let engine = self.engine();
+        let player_v = player.vars();
+        let inventory = player.inventory();
+        for ty in AmmoType::ALL {
+            let count = self.items.ammo(ty.index());
+            if count <= 0 {
+                continue;
+            }
+            let added = inventory.give_ammo(ty.index(), count, ty.max_carry());
+            if added > 0 {
+                let msg = user_message::AmmoPickup {
+                    index: ty.into_raw(),
+                    count: added as u8,
+                };
+                engine.msg_one(player_v, &msg);
+            }
+        }
+
+        for id in 0..MAX_WEAPONS {
+            if let Some(weapon) = self.take_weapon(id) {
+                // the player can not take a duplicate
+                if !weapon.try_give(other) {
+                    weapon.remove_from_world();
+                }
+            }
+        }
+
+        engine
+            .build_sound()
+            .channel_item()
+            .emit_dyn(sound::items::GUNPICKUP2, player_v);
+        self.remove_from_world();
+    }
+}
+
+export_entity!(weaponbox, WeaponBox);
@@ -0,0 +1,623 @@
+use core::{cell::Cell, ffi::CStr};
+
+use res::valve::sound;
+use xash3d_hl_shared::weapons::{
+    AmmoType, Bullet,
+    crowbar::Crowbar,
+    glock::Glock,
+    mp5::Mp5,
+    python::Python,
+    shotgun::Shotgun,
+    state::{EventParams, WeaponContext, WeaponLogic, WeaponState, bullet_spread, item_post_frame},
+};
+use xash3d_server::{
+    engine::TraceIgnore,
+    entity::{
+        BaseEntity, Buttons, DamageFlags, Effects, EntityItem, EntityPlayer, MoveType,
+        PlayerWeapon, Solid, UseType, WaterLevel, delegate_entity,
+    },
+    export::export_entity,
+    ffi::common::{clientdata_s, vec3_t, weapon_data_s},
+    game_rules::GameRules,
+    inventory::Inventory,
+    monster::{AiSounds, LOUD_GUN_VOLUME, SoundTypes},
+    prelude::*,
+    save::{Restore, Save},
+    utils,
+};
+
+use crate::{game_rules::SkillData, user_message};
+
+/// Precaches weapons and ammo that can be given to players.
+pub fn precache_weapons(engine: &ServerEngine) {
+    const WEAPONS: &[&CStr] = &[
+        c"weapon_crowbar",
+        c"weapon_9mmhandgun",
+        c"weapon_357",
+        c"weapon_9mmAR",
+        c"weapon_shotgun",
+        c"ammo_9mmclip",
+        c"ammo_9mmAR",
+        c"ammo_9mmbox",
+        c"ammo_357",
+        c"ammo_buckshot",
+        c"ammo_crossbow",
+        c"ammo_rpgclip",
+        c"ammo_gaussclip",
+        c"weaponbox",
+    ];
+    for name in WEAPONS {
+        utils::precache_other(engine, *name);
+    }
+}
+
+fn bullet_damage(skill_data: &SkillData, bullet: Bullet) -> f32 {
+    match bullet {
+        Bullet::None => 0.0,
+        Bullet::Player9mm => skill_data.player_dmg_9mm,
+        Bullet::PlayerMp5 => skill_data.player_dmg_mp5,
+        Bullet::Player357 => skill_data.player_dmg_357,
+        Bullet::PlayerBuckshot => skill_data.player_dmg_buckshot,
+        Bullet::PlayerCrowbar => skill_data.player_dmg_crowbar,
+        Bullet::Monster9mm => skill_data.monster_dmg_9mm,
+        Bullet::MonsterMp5 => skill_data.monster_dmg_mp5,
+        Bullet::Monster12mm => skill_data.monster_dmg_12mm,
+    }
+}
+
+/// The weapon logic context for a player on the server.
+struct ServerContext<'a> {
+    player: &'a dyn EntityPlayer,
+    switch_to_next_best: bool,
+}
+
+impl<'a> ServerContext<'a> {
+    fn new(player: &'a dyn EntityPlayer) -> Self {
+        Self {
+            player,
+            switch_to_next_best: false,
+        }
+    }
+
+    fn inventory(&self) -> &Inventory {
+        self.player.inventory()
+    }
+
+    fn gun_position(&self) -> vec3_t {
+        let v = self.player.vars();
+        v.origin() + v.view_ofs()
+    }
+
+    fn play_sound(&self, sample: &'static CStr, volume: f32, pitch: i32, weapon: bool) {
+        let engine = self.player.engine();
+        let sound = engine.build_sound().volume(volume).pitch(pitch);
+        let sound = if weapon {
+            sound.channel_weapon()
+        } else {
+            sound.channel_item()
+        };
+        sound.emit_dyn(sample, self.player.vars());
+    }
+}
+
+impl WeaponContext for ServerContext<'_> {
+    fn buttons(&self) -> Buttons {
+        self.player.vars().buttons()
+    }
+
+    fn random_seed(&self) -> u32 {
+        self.player.random_seed()
+    }
+
+    fn is_underwater(&self) -> bool {
+        self.player.vars().water_level() == WaterLevel::Head
+    }
+
+    fn next_attack(&self) -> f32 {
+        self.inventory().next_attack()
+    }
+
+    fn set_next_attack(&mut self, delay: f32) {
+        self.inventory().set_next_attack(delay);
+    }
+
+    fn ammo(&self, ty: AmmoType) -> i32 {
+        self.inventory().ammo(ty.index())
+    }
+
+    fn set_ammo(&mut self, ty: AmmoType, count: i32) {
+        self.inventory().set_ammo(ty.index(), count);
+    }
+
+    fn set_models(
+        &mut self,
+        view_model: Option<&'static CStr>,
+        weapon_model: Option<&'static CStr>,
+    ) {
+        let engine = self.player.engine();
+        let v = self.player.vars();
+        v.set_view_model_name(view_model.map(|i| engine.new_map_string(i)));
+        v.set_weapon_model_name(weapon_model.map(|i| engine.new_map_string(i)));
+    }
+
+    fn send_weapon_anim(&mut self, anim: i32, body: i32) {
+        let engine = self.player.engine();
+        let v = self.player.vars();
+        v.set_weapon_animation(anim);
+
+        // the local player predicts animations
+        #[cfg(feature = "client-weapons")]
+        if engine.can_skip_player(v) {
+            return;
+        }
+
+        let msg = user_message::WeaponAnimation {
+            sequence: anim as u8,
+            weapon_model: body as u8,
+        };
+        engine.msg_one(v, &msg);
+    }
+
+    fn player_attack(&mut self) {
+        let v = self.player.vars();
+        v.with_effects(|f| f | Effects::MUZZLEFLASH);
+        // let monsters hear the shot
+        let global_state = self.player.global_state();
+        let sounds = global_state.get_or_default::<AiSounds>();
+        sounds.insert(SoundTypes::COMBAT, v.origin(), LOUD_GUN_VOLUME, 0.3);
+    }
+
+    fn play_weapon_sound(&mut self, sample: &'static CStr, volume: f32, pitch: i32) {
+        self.play_sound(sample, volume, pitch, true);
+    }
+
+    fn play_item_sound(&mut self, sample: &'static CStr, volume: f32, pitch: i32) {
+        self.play_sound(sample, volume, pitch, false);
+    }
+
+    fn fire_bullets(
+        &mut self,
+        shots: u32,
+        spread: f32,
+        distance: f32,
+        bullet: Bullet,
+    ) -> (f32, f32) {
+        let engine = self.player.engine();
+        let global_state = self.player.global_state();
+        let damage = bullet_damage(&global_state.get::<SkillData>(), bullet);
+        let v = self.player.vars();
+        let src = self.gun_position();
+        let av = (v.view_angle() + v.punch_angle()).angle_vectors().all();
+        let seed = self.random_seed();
+
+        let multi_damage = global_state.multi_damage();
+        multi_damage.clear();
+
+        let mut last = (0.0, 0.0);
+        for shot in 1..=shots {
+            last = bullet_spread(seed, shot, spread);
+            let dir = av.forward + av.right * last.0 + av.up * last.1;
+            let end = src + dir * distance;
+            let trace = engine.trace_line(src, end, TraceIgnore::NONE, Some(v));
+            if trace.fraction() == 1.0 {
+                continue;
+            }
+
+            if let Some(entity) = trace.hit_entity().get_entity() {
+                entity.trace_attack(v, damage, dir, &trace, DamageFlags::BULLET);
+            }
+
+            // the client plays impact effects for predicted weapons
+            #[cfg(not(feature = "client-weapons"))]
+            {
+                utils::play_texture_sound(&engine, &trace, src, end);
+                let decal = global_state.decals().get_random_gunshot();
+                utils::decal_trace(&engine, &trace, decal);
+            }
+        }
+        multi_damage.apply(v, v);
+        last
+    }
+
+    fn melee_attack(&mut self, distance: f32, bullet: Bullet, damage_scale: f32) -> bool {
+        let engine = self.player.engine();
+        let global_state = self.player.global_state();
+        let v = self.player.vars();
+        let src = self.gun_position();
+        let forward = v.view_angle().angle_vectors().forward();
+        let end = src + forward * distance;
+        let trace = engine.trace_line(src, end, TraceIgnore::NONE, Some(v));
+        if trace.fraction() == 1.0 {
+            return false;
+        }
+
+        if let Some(entity) = trace.hit_entity().get_entity() {
+            let damage = bullet_damage(&global_state.get::<SkillData>(), bullet) * damage_scale;
+            let multi_damage = global_state.multi_damage();
+            multi_damage.clear();
+            entity.trace_attack(v, damage, forward, &trace, DamageFlags::CLUB);
+            multi_damage.apply(v, v);
+
+            if entity.is_alive() && !entity.is_bsp_model() {
+                let sample = match engine.random_int(0, 2) {
+                    0 => sound::weapons::CBAR_HITBOD1,
+                    1 => sound::weapons::CBAR_HITBOD2,
+                    _ => sound::weapons::CBAR_HITBOD3,
+                };
+                self.play_item_sound(sample, 1.0, 100);
+                return true;
+            }
+        }
+
+        let volume = utils::play_texture_sound(&engine, &trace, src, src + (end - src) * 2.0);
+        let sample = match engine.random_int(0, 1) {
+            0 => sound::weapons::CBAR_HIT1,
+            _ => sound::weapons::CBAR_HIT2,
+        };
+        let pitch = 98 + engine.random_int(0, 3);
+        self.play_item_sound(sample, volume, pitch);
+
+        let decal = global_state.decals().get_random_gunshot();
+        utils::decal_trace(&engine, &trace, decal);
+        true
+    }
+
+    fn playback_event(&mut self, event: &'static CStr, params: EventParams) {
+        let engine = self.player.engine();
+        let index = engine.precache_event(event);
+        let builder = engine.build_playback_event();
+        #[cfg(feature = "client-weapons")]
+        let builder = builder.not_host();
+        builder
+            .fparam1(params.fparam1)
+            .fparam2(params.fparam2)
+            .iparam1(params.iparam1)
+            .iparam2(params.iparam2)
+            .bparam1(params.bparam1)
+            .bparam2(params.bparam2)
+            .build(index, self.player.vars());
+    }
+
+    fn switch_to_next_best(&mut self) -> bool {
+        let inventory = self.inventory();
+        let active = inventory.active().map(|i| i.entity_handle());
+        self.switch_to_next_best = inventory
+            .weapons()
+            .any(|i| Some(i.entity_handle()) != active && i.can_deploy());
+        self.switch_to_next_best
+    }
+}
+
+/// A player weapon with the logic shared with the client.
+#[derive(Save, Restore)]
+pub struct Weapon<L: WeaponLogic> {
+    base: BaseEntity,
+    state: Cell<WeaponState>,
+    logic: Cell<L>,
+    /// The ammo given with the first pickup.
+    default_ammo: Cell<i32>,
+}
+
+impl<L: WeaponLogic> CreateEntity for Weapon<L> {
+    fn create(base: BaseEntity) -> Self {
+        Self {
+            base,
+            state: Cell::new(WeaponState::new(L::info())),
+            logic: Cell::default(),
+            default_ammo: Cell::new(L::info().default_ammo as i32),
+        }
+    }
+}
+
+impl<L: WeaponLogic + Save + Restore + 'static> Weapon<L> {
+    fn owner(&self) -> Option<&dyn EntityPlayer> {
+        self.vars().owner().downcast_ref::<dyn EntityPlayer>()
+    }
+
+    /// Runs the weapon logic for the owner.
+    ///
+    /// The state is copied out so the logic can switch weapons safely.
+    fn run(&self, f: impl FnOnce(&mut L, &mut WeaponState, &mut dyn WeaponContext)) {
+        let Some(player) = self.owner() else {
+            return;
+        };
+        let mut ctx = ServerContext::new(player);
+        let mut logic = self.logic.get();
+        let mut state = self.state.get();
+        f(&mut logic, &mut state, &mut ctx);
+        self.logic.set(logic);
+        self.state.set(state);
+        if ctx.switch_to_next_best {
+            player.inventory().select_best();
+        }
+    }
+
+    /// Gives the primary ammo, filling the empty clip first.
+    ///
+    /// Returns the ammo added to the inventory, `None` if nothing was added.
+    fn add_primary_ammo(&self, inventory: &Inventory, mut count: i32) -> Option<(AmmoType, i32)> {
+        let info = L::info();
+        let ammo1 = info.ammo1?;
+        let mut state = self.state.get();
+        let mut clip_added = false;
+        if let Some(max_clip) = info.max_clip {
+            if state.clip == 0 {
+                let n = count.min(max_clip as i32);
+                state.clip += n;
+                count -= n;
+                clip_added = n > 0;
+                self.state.set(state);
+            }
+        }
+        let added = inventory.give_ammo(ammo1.index(), count, ammo1.max_carry());
+        (clip_added || added > 0).then_some((ammo1, added))
+    }
+
+    /// Spawns a copy of this weapon if the game rules allow it to respawn.
+    fn respawn(&self, game_rules: &dyn GameRules) {
+        let Some((time, origin)) = game_rules.item_respawn(self) else {
+            return;
+        };
+        let engine = self.engine();
+        let Some(mut weapon) = engine.create_named_entity(self.classname()) else {
+            return;
+        };
+        let v = weapon.vars();
+        v.set_origin(origin);
+        v.set_angles(self.vars().angles());
+        v.set_spawn_flags(self.vars().spawn_flags());
+        if let Some(weapon) = unsafe { weapon.get_entity_mut() } {
+            weapon.spawn();
+        }
+
+        // hide until materialized
+        let v = weapon.vars();
+        v.with_effects(|f| f.union(Effects::NODRAW));
+        v.set_solid(Solid::Not);
+        v.link();
+        v.set_next_think_time(time);
+    }
+
+    fn materialize(&self) {
+        let v = self.vars();
+        self.engine()
+            .build_sound()
+            .channel_weapon()
+            .pitch(150)
+            .emit_dyn(sound::items::SUITCHARGEOK1, v);
+        v.with_effects(|f| f.difference(Effects::NODRAW).union(Effects::MUZZLEFLASH));
+        v.set_solid(Solid::Trigger);
+        v.link();
+    }
+
+    fn attach_to_player(&self, player: &dyn EntityPlayer) {
+        let v = self.vars();
+        v.set_move_type(MoveType::Follow);
+        v.set_solid(Solid::Not);
+        v.set_aim_entity(player.vars());
+        v.set_owner(player.vars());
+        v.set_effects(Effects::NODRAW);
+        v.set_model_index_raw(0);
+        v.set_model_name(None);
+    }
+}
+
+impl<L: WeaponLogic + Save + Restore + 'static> Entity for Weapon<L> {
+    delegate_entity!(base not { precache, spawn, think, touched });
+
+    fn precache(&mut self) {
+        let engine = self.engine();
+        engine.precache_model(L::WORLD_MODEL);
+        for i in L::MODELS {
+            engine.precache_model(*i);
+        }
+        for i in L::SOUNDS {
+            engine.precache_sound(*i);
+        }
+        for i in L::EVENTS {
+            engine.precache_event(*i);
+        }
+        engine.precache_sound(sound::items::GUNPICKUP2);
+        engine.precache_sound(sound::items::_9MMCLIP1);
+        engine.precache_sound(sound::weapons::DRYFIRE1);
+    }
+
+    fn spawn(&mut self) {
+        self.precache();
+        let engine = self.engine();
+        let v = self.vars();
+        v.set_model(L::WORLD_MODEL);
+        v.set_solid(Solid::Trigger);
+        v.set_move_type(MoveType::Toss);
+        v.link();
+        engine.set_size(v, vec3_t::ZERO, vec3_t::ZERO);
+        engine.drop_to_floor(v);
+    }
+
+    fn think(&self) {
+        let v = self.vars();
+        if v.owner().is_none() && v.effects().intersects(Effects::NODRAW) {
+            self.materialize();
+        }
+    }
+
+    fn touched(&self, other: &dyn Entity) {
+        self.try_give(other);
+    }
+}
+
+impl<L: WeaponLogic + Save + Restore + 'static> EntityItem for Weapon<L> {
+    fn try_give(&self, other: &dyn Entity) -> bool {
+        if self.vars().owner().is_some() {
+            return false;
+        }
+        let Some(player) = other.as_player() else {
+            return false;
+        };
+        if !player.is_alive() {
+            return false;
+        }
+        let global_state = self.global_state();
+        let game_rules = global_state.game_rules();
+        if !game_rules.can_have_item(player, self) {
+            return false;
+        }
+
+        let engine = self.engine();
+        let player_v = player.vars();
+        let inventory = player.inventory();
+
+        if let Some(weapon) = inventory.weapon(self.weapon_id()) {
+            // take only the ammo from the duplicate
+            let Some(weapon) = weapon.private().downcast_ref::<Self>() else {
+                return false;
+            };
+            let default_ammo = self.default_ammo.get();
+            let Some((ammo, added)) = weapon.add_primary_ammo(inventory, default_ammo) else {
+                return false;
+            };
+            if added > 0 {
+                let msg = user_message::AmmoPickup {
+                    index: ammo.into_raw(),
+                    count: added as u8,
+                };
+                engine.msg_one(player_v, &msg);
+            }
+            engine
+                .build_sound()
+                .channel_item()
+                .emit_dyn(sound::items::_9MMCLIP1, player_v);
+            utils::use_targets(UseType::Toggle, Some(player.as_entity()), self);
+            game_rules.player_got_item(player, self);
+            self.respawn(&*game_rules);
+            self.remove_from_world();
+            return true;
+        }
+
+        if !inventory.insert(self) {
+            return false;
+        }
+        self.respawn(&*game_rules);
+        self.attach_to_player(player);
+        player_v.with_weapons(|f| f | (1 << self.weapon_id()));
+
+        let msg = user_message::WeapPickup {
+            index: self.weapon_id() as u8,
+        };
+        engine.msg_one(player_v, &msg);
+
+        let default_ammo = self.default_ammo.replace(0);
+        if let Some((ammo, added)) = self.add_primary_ammo(inventory, default_ammo) {
+            if added > 0 {
+                let msg = user_message::AmmoPickup {
+                    index: ammo.into_raw(),
+                    count: added as u8,
+                };
+                engine.msg_one(player_v, &msg);
+            }
+        }
+
+        engine
+            .build_sound()
+            .channel_item()
+            .emit_dyn(sound::items::GUNPICKUP2, player_v);
+
+        utils::use_targets(UseType::Toggle, Some(player.as_entity()), self);
+        game_rules.player_got_item(player, self);
+
+        if inventory
+            .active()
+            .is_none_or(|i| i.weight() < self.weight())
+        {
+            inventory.switch_weapon(self);
+        }
+        true
+    }
+}
+
+impl<L: WeaponLogic + Save + Restore + 'static> PlayerWeapon for Weapon<L> {
+    fn weapon_id(&self) -> usize {
+        L::ID.into_raw() as usize
+    }
+
+    fn weight(&self) -> i32 {
+        L::info().weight
+    }
+
+    fn clip(&self) -> i32 {
+        self.state.get().clip
+    }
+
+    fn primary_ammo(&self) -> Option<usize> {
+        L::info().ammo1.map(|i| i.index())
+    }
+
+    fn secondary_ammo(&self) -> Option<usize> {
+        L::info().ammo2.map(|i| i.index())
+    }
+
+    fn can_deploy(&self) -> bool {
+        match self.owner() {
+            Some(player) => self
+                .state
+                .get()
+                .can_deploy(L::info(), &ServerContext::new(player)),
+            None => false,
+        }
+    }
+
+    fn deploy(&self) {
+        self.run(|logic, state, ctx| {
+            logic.deploy(state, ctx);
+        });
+    }
+
+    fn holster(&self) {
+        self.run(|logic, state, ctx| logic.holster(state, ctx));
+    }
+
+    fn item_post_frame(&self) {
+        self.run(|logic, state, ctx| item_post_frame(logic, state, ctx));
+    }
+
+    fn decrement_timers(&self, time: f32) {
+        let mut state = self.state.get();
+        state.decrement_timers(time);
+        self.state.set(state);
+    }
+
+    fn weapon_data(&self, data: &mut weapon_data_s) {
+        self.state.get().write_weapon_data(L::ID, data);
+    }
+
+    fn client_data(&self, data: &mut clientdata_s) {
+        let Some(player) = self.owner() else {
+            return;
+        };
+        let inventory = player.inventory();
+        for ty in AmmoType::ALL {
+            ty.set_client_ammo(data, inventory.ammo(ty.index()));
+        }
+        let info = L::info();
+        if let Some(ammo1) = info.ammo1 {
+            data.vuser4.x = ammo1.into_raw() as f32;
+            data.vuser4.y = inventory.ammo(ammo1.index()) as f32;
+        }
+        if let Some(ammo2) = info.ammo2 {
+            data.vuser3.z = ammo2.into_raw() as f32;
+            data.vuser4.z = inventory.ammo(ammo2.index()) as f32;
+        }
+    }
+}
+
+// Only melee and hitscan weapons are ported. Weapons that fire projectiles (crossbow, egon, gauss,
+// hornetgun, rpg, handgrenade, satchel, tripmine, snark and the MP5 grenade launcher) need
+// grenade, rocket and monster entities that do not exist yet. Their ammo can still be picked up.
+export_entity!(weapon_crowbar, Weapon<Crowbar> { EntityItem, PlayerWeapon });
+export_entity!(weapon_9mmhandgun, Weapon<Glock> { EntityItem, PlayerWeapon });
+export_entity!(weapon_glock, Weapon<Glock>);
+export_entity!(weapon_357, Weapon<Python> { EntityItem, PlayerWeapon });
+export_entity!(weapon_python, Weapon<Python>);
+export_entity!(weapon_9mmAR, Weapon<Mp5> { EntityItem, PlayerWeapon });
+export_entity!(weapon_mp5, Weapon<Mp5>);
+export_entity!(weapon_shotgun, Weapon<Shotgun> { EntityItem, PlayerWeapon });
@@ -1,11 +1,14 @@
 use core::ffi::{CStr, c_int};
 
+use alloc::string::ToString;
+
 use xash3d_entities::world::World;
 use xash3d_server::{
-    engine::RegisterUserMessageError,
+    engine::{RegisterUserMessageError, add_command},
     entity::{BaseEntity, EntityHandle, EntityPlayer},
     export::{ServerDll, export_dll, impl_unsync_global},
     global_state::GlobalStateRef,
+    map_check,
     prelude::*,
     user_message::register_user_message,
 };
@@ -39,7 +42,7 @@ impl Dll {
         register_user_message!(engine, user_message::GameTitle)?;
         register_user_message!(engine, user_message::DeathMsg)?;
         register_user_message!(engine, user_message::ScoreInfo)?;
-        // register_user_message!(engine, user_message::TeamInfo)?;
+        register_user_message!(engine, user_message::TeamInfo)?;
         // register_user_message!(engine, user_message::TeamScore)?;
         register_user_message!(engine, user_message::GameMode)?;
         // register_user_message!(engine, user_message::MOTD)?;
@@ -69,6 +72,28 @@ impl ServerDll for Dll {
         if let Err(err) = Self::register_user_messages(engine) {
             panic!("{err}");
         }
+        add_command!(engine, c"sv_check_map", |engine| {
+            let arg = engine.cmd_argv(1);
+            let map_name = if arg.is_empty() {
+                match engine.globals.map_name() {
+                    Some(name) => name.to_string(),
+                    None => {
+                        info!("usage: sv_check_map [map]");
+                        return;
+                    }
+                }
+            } else {
+                arg.to_string()
+            };
+            match map_check::check_map(engine, &map_name) {
+                Ok(report) => {
+                    for line in report.to_string().lines() {
+                        info!("{map_name}: {line}");
+                    }
+                }
+                Err(err) => error!("{map_name}: {err}"),
+            }
+        });
         Self {
             engine,
             global_state,
@@ -141,6 +166,16 @@ impl ServerDll for Dll {
                     }
                 }
             }
+            b"lastinv" => {
+                if let Some(player) = ent.downcast_ref::<dyn EntityPlayer>() {
+                    player.inventory().select_last();
+                }
+            }
+            name if name.starts_with(b"weapon_") => {
+                if let Some(player) = ent.downcast_ref::<dyn EntityPlayer>() {
+                    player.inventory().select_weapon(engine.cmd_argv(0));
+                }
+            }
             _ => {
                 if let Some(args) = self.engine.cmd_args_raw() {
                     warn!("unimplemented client command \"{name} {args}\"");
@@ -1,6 +1,10 @@
+mod multiplay;
+mod teamplay;
+
 use core::{ffi::CStr, fmt};
 
 use xash3d_server::{
+    damage::HitGroup,
     entity::{Entity, EntityPlayer},
     ffi::common::vec3_t,
     game_rules::GameRules,
@@ -9,6 +13,10 @@ use xash3d_server::{
     time::MapTime,
 };
 
+use crate::{cvar::MP_TEAMPLAY, entities::weapons::precache_weapons};
+
+pub use self::{multiplay::HalfLifeMultiplay, teamplay::HalfLifeTeamplay};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SkillLevel {
     Easy,
@@ -373,6 +381,18 @@ impl SkillData {
             player_arm: skill_cvar("sk_player_arm"),
         }
     }
+
+    /// Returns a damage multiplier for the player body part.
+    pub fn player_hit_group_scale(&self, hit_group: HitGroup) -> f32 {
+        match hit_group {
+            HitGroup::Generic => 1.0,
+            HitGroup::Head => self.player_head,
+            HitGroup::Chest => self.player_chest,
+            HitGroup::Stomach => self.player_stomach,
+            HitGroup::LeftArm | HitGroup::RightArm => self.player_arm,
+            HitGroup::LeftLeg | HitGroup::RightLeg => self.player_leg,
+        }
+    }
 }
 
 pub struct HalfLifeRules {
@@ -421,12 +441,13 @@ pub fn install_game_rules(engine: ServerEngineRef, global_state: GlobalStateRef)
     engine.server_command(c"exec game.cfg\n");
     engine.server_execute();
 
+    precache_weapons(&engine);
+
     if !engine.globals.is_deathmatch() {
-        // TODO: g_teamplay = 0;
         global_state.set_game_rules(HalfLifeRules::new(engine));
-        return;
+    } else if engine.get_cvar::<bool>(MP_TEAMPLAY.name()) {
+        global_state.set_game_rules(HalfLifeTeamplay::new(engine));
     } else {
-        // TODO:
+        global_state.set_game_rules(HalfLifeMultiplay::new(engine));
     }
-    todo!();
 }
@@ -0,0 +1,477 @@
+use core::{cell::Cell, ffi::CStr};
+
+use alloc::vec::Vec;
+
+use xash3d_server::{
+    csz::CStrThin,
+    entities::item::SF_ITEM_NO_RESPAWN,
+    entity::{EdictFlags, Entity, EntityPlayer, EntityVars, UseType},
+    ffi::common::vec3_t,
+    game_rules::{GameRules, add_points_clamped},
+    prelude::*,
+    time::MapTime,
+    utils,
+};
+
+use crate::{
+    cvar::{
+        MP_ALLOWMONSTERS, MP_CHATTIME, MP_FLASHLIGHT, MP_FRAGLIMIT, MP_FRAGSLEFT, MP_TIMELEFT,
+        MP_TIMELIMIT,
+    },
+    entities::{player::WEAPON_SUIT, weaponbox::WeaponBox},
+    game_rules::SkillData,
+    user_message,
+};
+
+const ITEM_RESPAWN_TIME: f32 = 30.0;
+const WEAPON_RESPAWN_TIME: f32 = 20.0;
+const AMMO_RESPAWN_TIME: f32 = 20.0;
+
+/// Weapons and ammo given to spawned players if the map has no `game_player_equip`.
+const DEFAULT_EQUIPMENT: &[&CStr] = &[
+    c"weapon_crowbar",
+    c"weapon_9mmhandgun",
+    c"ammo_9mmclip",
+    c"ammo_9mmclip",
+    c"ammo_9mmclip",
+    c"ammo_9mmclip",
+];
+
+/// Fires map targets named after a game event with the player as the activator.
+fn fire_game_event(name: &CStr, player: &dyn Entity) {
+    utils::fire_targets(name.into(), UseType::Toggle, Some(player), player);
+}
+
+/// Strips an entity class prefix from the weapon name for death notices.
+fn death_notice_weapon_name(class_name: &CStr) -> &CStr {
+    const PREFIXES: &[&[u8]] = &[b"weapon_", b"monster_", b"func_"];
+    let bytes = class_name.to_bytes_with_nul();
+    PREFIXES
+        .iter()
+        .find(|prefix| bytes.starts_with(prefix))
+        .and_then(|prefix| CStr::from_bytes_with_nul(&bytes[prefix.len()..]).ok())
+        .unwrap_or(class_name)
+}
+
+/// Returns the next valid map from the map cycle file.
+fn next_map_in_cycle<'a>(
+    map_cycle: &'a str,
+    current: Option<&str>,
+    is_map_valid: impl Fn(&str) -> bool,
+) -> Option<&'a str> {
+    let maps: Vec<&str> = map_cycle
+        .lines()
+        .filter_map(|line| line.split("//").next()?.split_whitespace().next())
+        .filter(|map| is_map_valid(map))
+        .collect();
+    let next = maps
+        .iter()
+        .position(|map| current.is_some_and(|i| i.eq_ignore_ascii_case(map)))
+        .map_or(0, |i| (i + 1) % maps.len());
+    maps.get(next).copied()
+}
+
+/// Deathmatch game rules.
+pub struct HalfLifeMultiplay {
+    engine: ServerEngineRef,
+    /// Deaths count by a client index.
+    deaths: Vec<Cell<i16>>,
+    intermission_end: Cell<Option<MapTime>>,
+    changing_level: Cell<bool>,
+    frags_left: Cell<i32>,
+    time_left: Cell<i32>,
+}
+
+impl HalfLifeMultiplay {
+    pub fn new(engine: ServerEngineRef) -> Self {
+        if engine.is_dedicated_server() {
+            let config = engine.get_cvar::<&CStrThin>(c"servercfgfile");
+            if !config.is_empty() {
+                engine.server_command(format_args!("exec {config}\n"));
+                engine.server_execute();
+            }
+        } else {
+            engine.server_command(c"exec listenserver.cfg\n");
+            engine.server_execute();
+        }
+
+        let mut skill_data = SkillData::new(engine);
+        // override some values for multiplayer
+        skill_data.suitcharger_capacity = 30.0;
+        skill_data.player_dmg_crowbar = 25.0;
+        skill_data.player_dmg_9mm = 12.0;
+        skill_data.player_dmg_357 = 40.0;
+        skill_data.player_dmg_mp5 = 12.0;
+        skill_data.player_dmg_m203_grenade = 100.0;
+        skill_data.player_dmg_buckshot = 20.0;
+        skill_data.player_dmg_crossbow_client = 20.0;
+        skill_data.player_dmg_rpg = 120.0;
+        skill_data.player_dmg_egon_wide = 20.0;
+        skill_data.player_dmg_egon_narrow = 10.0;
+        skill_data.player_dmg_hand_grenade = 100.0;
+        skill_data.player_dmg_satchel = 120.0;
+        skill_data.player_dmg_tripmine = 150.0;
+        skill_data.player_dmg_hornet = 10.0;
+        engine.global_state_ref().add(skill_data);
+
+        let max_clients = engine.globals.max_clients() as usize;
+        Self {
+            engine,
+            deaths: (0..=max_clients).map(|_| Cell::new(0)).collect(),
+            intermission_end: Cell::new(None),
+            changing_level: Cell::new(false),
+            frags_left: Cell::new(0),
+            time_left: Cell::new(0),
+        }
+    }
+
+    fn deaths(&self, player: &dyn Entity) -> Option<&Cell<i16>> {
+        self.deaths.get(player.entity_index().to_u16() as usize)
+    }
+
+    /// Returns a team index of the entity from the installed game rules.
+    fn team_index(&self, entity: &dyn Entity) -> Option<usize> {
+        let global_state = self.engine.global_state_ref();
+        let game_rules = global_state.game_rules();
+        game_rules
+            .get_team_id(entity)
+            .and_then(|team| game_rules.get_team_index(&team))
+    }
+
+    fn score_info(&self, player: &dyn Entity) -> user_message::ScoreInfo {
+        user_message::ScoreInfo {
+            cl: player.entity_index().to_u16() as u8,
+            frags: player.vars().frags() as i16,
+            deaths: self.deaths(player).map_or(0, |i| i.get()),
+            player_class: 0,
+            teamnumber: self.team_index(player).map_or(0, |i| i as i16 + 1),
+        }
+    }
+
+    /// Sends the score of the player to all clients.
+    pub fn update_score(&self, player: &dyn Entity) {
+        self.engine.msg_all(&self.score_info(player));
+    }
+
+    fn points_for_kill(&self, attacker: &dyn Entity, victim: &dyn Entity) -> i32 {
+        let global_state = self.engine.global_state_ref();
+        if global_state.game_rules().is_teamplay() {
+            let team = self.team_index(attacker);
+            if team.is_some() && team == self.team_index(victim) {
+                // teammates lose a frag
+                return -1;
+            }
+        }
+        1
+    }
+
+    fn death_notice(&self, victim: &dyn EntityPlayer, killer: &EntityVars, inflictor: &EntityVars) {
+        let is_client = killer.flags().intersects(EdictFlags::CLIENT);
+        let killer_index = if is_client {
+            killer.entity_index().to_u16() as u8
+        } else {
+            0
+        };
+
+        let killer_handle = killer.entity_handle();
+        let weapon = if is_client && inflictor.entity_handle() == killer_handle {
+            // killed by the active weapon of the player
+            killer_handle
+                .downcast_ref::<dyn EntityPlayer>()
+                .and_then(|i| i.inventory().active())
+                .and_then(|i| i.vars().classname())
+        } else {
+            inflictor.classname()
+        };
+        let weapon = weapon.as_ref().map_or(c"world", |i| i.as_c_str());
+
+        let msg = user_message::DeathMsg {
+            killer: killer_index,
+            victim: victim.entity_index().to_u16() as u8,
+            killed_with: death_notice_weapon_name(weapon),
+        };
+        self.engine.msg_all(&msg);
+    }
+
+    pub fn is_intermission(&self) -> bool {
+        self.intermission_end.get().is_some()
+    }
+
+    fn go_to_intermission(&self) {
+        if self.is_intermission() {
+            return;
+        }
+
+        let engine = &self.engine;
+        engine.msg_all(&user_message::Intermission);
+
+        let chat_time = engine.get_cvar::<f32>(MP_CHATTIME.name()).clamp(1.0, 120.0);
+        self.intermission_end
+            .set(Some(engine.globals.map_time() + chat_time));
+    }
+
+    fn change_level_to_next(&self) {
+        if self.changing_level.replace(true) {
+            return;
+        }
+
+        let engine = &self.engine;
+        let current = engine.globals.map_name();
+        let mut map_cycle_file = engine.get_cvar::<&CStrThin>(c"mapcyclefile");
+        if map_cycle_file.is_empty() {
+            map_cycle_file = c"mapcycle.txt".into();
+        }
+
+        if let Ok(file) = engine.load_file(map_cycle_file) {
+            let map_cycle = core::str::from_utf8(file.as_bytes()).unwrap_or_default();
+            let current = current.as_deref().and_then(|i| i.to_str().ok());
+            if let Some(next) = next_map_in_cycle(map_cycle, current, |i| engine.is_map_valid(i)) {
+                info!("CHANGE LEVEL: {next}");
+                engine.change_level(next, c"");
+                return;
+            }
+        } else {
+            warn!("failed to load the map cycle file {map_cycle_file}");
+        }
+
+        if let Some(current) = current {
+            info!("CHANGE LEVEL: {current}");
+            engine.change_level(current, c"");
+        }
+    }
+
+    fn check_limits(&self) {
+        let engine = &self.engine;
+        let time = engine.globals.map_time().as_secs_f32();
+
+        let time_limit = engine.get_cvar::<f32>(MP_TIMELIMIT.name()) * 60.0;
+        if time_limit > 0.0 && time >= time_limit {
+            self.go_to_intermission();
+            return;
+        }
+
+        let frag_limit = engine.get_cvar::<i32>(MP_FRAGLIMIT.name());
+        let max_frags = engine
+            .players()
+            .map(|i| i.vars().frags() as i32)
+            .max()
+            .unwrap_or(0);
+        if frag_limit > 0 && max_frags >= frag_limit {
+            self.go_to_intermission();
+            return;
+        }
+
+        let frags_left = if frag_limit > 0 {
+            frag_limit - max_frags
+        } else {
+            0
+        };
+        if self.frags_left.replace(frags_left) != frags_left {
+            engine.set_cvar(MP_FRAGSLEFT.name(), frags_left);
+        }
+
+        let time_left = if time_limit > 0.0 {
+            (time_limit - time) as i32
+        } else {
+            0
+        };
+        if self.time_left.replace(time_left) != time_left {
+            engine.set_cvar(MP_TIMELEFT.name(), time_left);
+        }
+    }
+}
+
+impl GameRules for HalfLifeMultiplay {
+    fn engine(&self) -> ServerEngineRef {
+        self.engine
+    }
+
+    fn is_multiplayer(&self) -> bool {
+        true
+    }
+
+    fn is_deathmatch(&self) -> bool {
+        true
+    }
+
+    fn get_game_description(&self) -> &'static CStr {
+        c"HL Deathmatch"
+    }
+
+    fn player_spawn(&self, player: &dyn EntityPlayer) {
+        player.vars().with_weapons(|f| f | WEAPON_SUIT);
+
+        let mut equipped = false;
+        for equip in self.engine.entities().by_class_name(c"game_player_equip") {
+            if let Some(equip) = equip.get_entity() {
+                equip.touched(player.as_entity());
+                equipped = true;
+            }
+        }
+
+        if !equipped {
+            for name in DEFAULT_EQUIPMENT {
+                player.give_named_item((*name).into());
+            }
+        }
+    }
+
+    fn think(&self) {
+        if let Some(end) = self.intermission_end.get() {
+            if self.engine.globals.map_time() >= end {
+                self.change_level_to_next();
+            }
+            return;
+        }
+        self.check_limits();
+    }
+
+    fn init_hud(&self, player: &dyn EntityPlayer) {
+        let engine = &self.engine;
+        let global_state = engine.global_state_ref();
+        let mode = global_state.game_rules().is_teamplay() as u8;
+        engine.msg_one(player.vars(), &user_message::GameMode { mode });
+
+        self.update_score(player.as_entity());
+
+        fire_game_event(c"game_playerjoin", player.as_entity());
+
+        let handle = player.entity_handle();
+        for other in engine.players() {
+            if other.entity_handle() != handle {
+                engine.msg_one(player.vars(), &self.score_info(other));
+            }
+        }
+
+        if self.is_intermission() {
+            engine.msg_one(player.vars(), &user_message::Intermission);
+        }
+    }
+
+    fn client_disconnected(&self, player: &dyn EntityPlayer) {
+        fire_game_event(c"game_playerleave", player.as_entity());
+
+        if let Some(deaths) = self.deaths(player.as_entity()) {
+            deaths.set(0);
+        }
+    }
+
+    fn player_killed(
+        &self,
+        victim: &dyn EntityPlayer,
+        killer: &EntityVars,
+        inflictor: &EntityVars,
+    ) {
+        if let Some(deaths) = self.deaths(victim.as_entity()) {
+            deaths.set(deaths.get().saturating_add(1));
+        }
+
+        fire_game_event(c"game_playerdie", victim.as_entity());
+
+        let killer_handle = killer.entity_handle();
+        let killer_player = killer_handle.downcast_ref::<dyn EntityPlayer>();
+        match killer_player {
+            Some(killer) if killer.entity_handle() == victim.entity_handle() => {
+                // suicide
+                self.add_points(victim, -1, true);
+            }
+            Some(killer) => {
+                let points = self.points_for_kill(killer.as_entity(), victim.as_entity());
+                self.add_points(killer, points, true);
+                fire_game_event(c"game_playerkill", killer.as_entity());
+            }
+            // killed by the world or a monster, the victim keeps the score
+            None => {}
+        }
+
+        self.death_notice(victim, killer, inflictor);
+        self.update_score(victim.as_entity());
+    }
+
+    fn pack_dead_player_items(&self, player: &dyn EntityPlayer) {
+        WeaponBox::pack_active_weapon(&self.engine, player);
+    }
+
+    fn allow_flashlight(&self) -> bool {
+        self.engine.get_cvar(MP_FLASHLIGHT.name())
+    }
+
+    fn allow_monsters(&self) -> bool {
+        self.engine.get_cvar(MP_ALLOWMONSTERS.name())
+    }
+
+    fn can_have_item(&self, _: &dyn EntityPlayer, _: &dyn Entity) -> bool {
+        true
+    }
+
+    fn player_got_item(&self, player: &dyn EntityPlayer, item: &dyn Entity) {
+        trace!(
+            "{} got an item {}",
+            player.pretty_name(),
+            item.pretty_name()
+        );
+    }
+
+    fn item_respawn(&self, item: &dyn Entity) -> Option<(MapTime, vec3_t)> {
+        let v = item.vars();
+        if v.spawn_flags() & SF_ITEM_NO_RESPAWN != 0 {
+            return None;
+        }
+        let class_name = v.classname()?;
+        let delay = if class_name.to_bytes().starts_with(b"weapon_") {
+            WEAPON_RESPAWN_TIME
+        } else if class_name.to_bytes().starts_with(b"ammo_") {
+            AMMO_RESPAWN_TIME
+        } else {
+            ITEM_RESPAWN_TIME
+        };
+        Some((self.engine.globals.map_time() + delay, v.origin()))
+    }
+
+    fn add_points(&self, player: &dyn EntityPlayer, score: i32, allow_negative: bool) {
+        add_points_clamped(player, score, allow_negative);
+        self.update_score(player.as_entity());
+    }
+
+    fn end_multiplayer_game(&self) {
+        self.go_to_intermission();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn death_notice_weapon() {
+        assert_eq!(death_notice_weapon_name(c"weapon_357"), c"357");
+        assert_eq!(death_notice_weapon_name(c"monster_zombie"), c"zombie");
+        assert_eq!(death_notice_weapon_name(c"func_tank"), c"tank");
+        assert_eq!(death_notice_weapon_name(c"player"), c"player");
+        assert_eq!(death_notice_weapon_name(c"weapon"), c"weapon");
+    }
+
+    #[test]
+    fn map_cycle() {
+        let map_cycle = "// comment\ncrossfire\n  bounce  // note\n\nmissing\nstalkyard\n";
+        let valid = |map: &str| map != "missing";
+        assert_eq!(next_map_in_cycle(map_cycle, None, valid), Some("crossfire"));
+        assert_eq!(
+            next_map_in_cycle(map_cycle, Some("crossfire"), valid),
+            Some("bounce")
+        );
+        assert_eq!(
+            next_map_in_cycle(map_cycle, Some("BOUNCE"), valid),
+            Some("stalkyard")
+        );
+        assert_eq!(
+            next_map_in_cycle(map_cycle, Some("stalkyard"), valid),
+            Some("crossfire")
+        );
+        assert_eq!(
+            next_map_in_cycle(map_cycle, Some("unknown"), valid),
+            Some("crossfire")
+        );
+        assert_eq!(next_map_in_cycle("// empty\n", None, valid), None);
+    }
+}
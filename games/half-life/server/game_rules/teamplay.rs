@@ -0,0 +1,273 @@
+use core::{
+    cell::{Cell, RefCell},
+    ffi::CStr,
+};
+
+use alloc::vec::Vec;
+
+use xash3d_server::{
+    csz::CStrThin,
+    entity::{Entity, EntityPlayer, EntityVars, Gib},
+    ffi::common::vec3_t,
+    game_rules::GameRules,
+    prelude::*,
+    str::MapString,
+    time::MapTime,
+};
+
+use crate::{
+    cvar::{MP_DEFAULTTEAM, MP_TEAMLIST},
+    game_rules::HalfLifeMultiplay,
+    user_message,
+};
+
+const MAX_TEAMS: usize = 32;
+
+/// Team deathmatch game rules.
+///
+/// Teams are selected by player models.
+pub struct HalfLifeTeamplay {
+    base: HalfLifeMultiplay,
+    /// Teams from `mp_teamlist` or player models if the list is empty.
+    teams: RefCell<Vec<MapString>>,
+    fixed_teams: bool,
+    /// Team indices by a client index.
+    player_teams: Vec<Cell<Option<usize>>>,
+    /// Do not count the next death for a team change.
+    disable_death_penalty: Cell<bool>,
+}
+
+impl HalfLifeTeamplay {
+    pub fn new(engine: ServerEngineRef) -> Self {
+        let base = HalfLifeMultiplay::new(engine);
+
+        let team_list = engine.get_cvar::<&CStrThin>(MP_TEAMLIST.name());
+        let teams: Vec<MapString> = team_list
+            .to_str()
+            .unwrap_or_default()
+            .split(';')
+            .map(|i| i.trim())
+            .filter(|i| !i.is_empty())
+            .take(MAX_TEAMS)
+            .map(|i| engine.new_map_string(i))
+            .collect();
+
+        let max_clients = engine.globals.max_clients() as usize;
+        Self {
+            base,
+            fixed_teams: !teams.is_empty(),
+            teams: RefCell::new(teams),
+            player_teams: (0..=max_clients).map(|_| Cell::new(None)).collect(),
+            disable_death_penalty: Cell::new(false),
+        }
+    }
+
+    fn player_team(&self, entity: &dyn Entity) -> Option<&Cell<Option<usize>>> {
+        if !entity.is_player() {
+            return None;
+        }
+        self.player_teams
+            .get(entity.entity_index().to_u16() as usize)
+    }
+
+    fn team_with_fewest_players(&self) -> Option<usize> {
+        let mut counts = [0; MAX_TEAMS];
+        for team in self.player_teams.iter().filter_map(|i| i.get()) {
+            counts[team] += 1;
+        }
+        (0..self.teams.borrow().len()).min_by_key(|&i| counts[i])
+    }
+
+    /// Returns a team index for the model, adds a new team if teams are not fixed.
+    fn team_for_model(&self, model: &CStrThin) -> Option<usize> {
+        if let Some(index) = self.get_team_index(model) {
+            return Some(index);
+        }
+        let mut teams = self.teams.borrow_mut();
+        if self.fixed_teams || model.is_empty() || teams.len() >= MAX_TEAMS {
+            return None;
+        }
+        teams.push(self.engine().new_map_string(model));
+        Some(teams.len() - 1)
+    }
+
+    fn send_team_info(&self, player: &dyn Entity, to: Option<&EntityVars>) {
+        let Some(team) = self.get_team_id(player) else {
+            return;
+        };
+        let msg = user_message::TeamInfo {
+            client_index: player.entity_index().to_u16() as u8,
+            team_name: team.as_c_str(),
+        };
+        let engine = self.engine();
+        match to {
+            Some(to) => engine.msg_one(to, &msg),
+            None => engine.msg_all(&msg),
+        }
+    }
+}
+
+impl GameRules for HalfLifeTeamplay {
+    fn engine(&self) -> ServerEngineRef {
+        self.base.engine()
+    }
+
+    fn is_multiplayer(&self) -> bool {
+        true
+    }
+
+    fn is_deathmatch(&self) -> bool {
+        true
+    }
+
+    fn is_teamplay(&self) -> bool {
+        true
+    }
+
+    fn get_game_description(&self) -> &'static CStr {
+        c"HL Teamplay"
+    }
+
+    fn player_spawn(&self, player: &dyn EntityPlayer) {
+        self.base.player_spawn(player);
+    }
+
+    fn think(&self) {
+        self.base.think();
+    }
+
+    fn init_hud(&self, player: &dyn EntityPlayer) {
+        let engine = self.engine();
+        let team = if engine.get_cvar::<bool>(MP_DEFAULTTEAM.name()) {
+            None
+        } else {
+            let info = engine.get_info_buffer(player.vars());
+            self.team_for_model(info.get(c"model"))
+        };
+        let team = team.or_else(|| self.team_with_fewest_players());
+        if let Some(team) = team.and_then(|i| self.get_indexed_team_name(i)) {
+            self.change_player_team(player, &team, false, false);
+        }
+
+        self.base.init_hud(player);
+
+        // send teams of other players to the new player
+        let handle = player.entity_handle();
+        for other in engine.players() {
+            if other.entity_handle() != handle {
+                self.send_team_info(other, Some(player.vars()));
+            }
+        }
+    }
+
+    fn client_disconnected(&self, player: &dyn EntityPlayer) {
+        if let Some(team) = self.player_team(player.as_entity()) {
+            team.set(None);
+        }
+        self.base.client_disconnected(player);
+    }
+
+    fn player_killed(
+        &self,
+        victim: &dyn EntityPlayer,
+        killer: &EntityVars,
+        inflictor: &EntityVars,
+    ) {
+        if self.disable_death_penalty.replace(false) {
+            return;
+        }
+        self.base.player_killed(victim, killer, inflictor);
+    }
+
+    fn player_can_respawn(&self, player: &dyn EntityPlayer) -> bool {
+        self.base.player_can_respawn(player)
+    }
+
+    fn player_spawn_time(&self, player: &dyn EntityPlayer) -> MapTime {
+        self.base.player_spawn_time(player)
+    }
+
+    fn pack_dead_player_items(&self, player: &dyn EntityPlayer) {
+        self.base.pack_dead_player_items(player);
+    }
+
+    fn allow_flashlight(&self) -> bool {
+        self.base.allow_flashlight()
+    }
+
+    fn allow_monsters(&self) -> bool {
+        self.base.allow_monsters()
+    }
+
+    fn can_have_item(&self, player: &dyn EntityPlayer, item: &dyn Entity) -> bool {
+        self.base.can_have_item(player, item)
+    }
+
+    fn player_got_item(&self, player: &dyn EntityPlayer, item: &dyn Entity) {
+        self.base.player_got_item(player, item);
+    }
+
+    fn item_respawn(&self, item: &dyn Entity) -> Option<(MapTime, vec3_t)> {
+        self.base.item_respawn(item)
+    }
+
+    fn add_points(&self, player: &dyn EntityPlayer, score: i32, allow_negative: bool) {
+        self.base.add_points(player, score, allow_negative);
+    }
+
+    fn get_team_id(&self, entity: &dyn Entity) -> Option<MapString> {
+        let team = self.player_team(entity)?.get()?;
+        self.get_indexed_team_name(team)
+    }
+
+    fn get_team_index(&self, team_name: &CStrThin) -> Option<usize> {
+        let team_name = team_name.to_bytes();
+        self.teams
+            .borrow()
+            .iter()
+            .position(|i| i.to_bytes().eq_ignore_ascii_case(team_name))
+    }
+
+    fn get_indexed_team_name(&self, index: usize) -> Option<MapString> {
+        self.teams.borrow().get(index).copied()
+    }
+
+    fn change_player_team(
+        &self,
+        player: &dyn EntityPlayer,
+        team_name: &CStrThin,
+        kill: bool,
+        gib: bool,
+    ) {
+        let Some(team) = self.get_team_index(team_name) else {
+            warn!("{}: unknown team {team_name}", player.pretty_name());
+            return;
+        };
+        let Some(player_team) = self.player_team(player.as_entity()) else {
+            return;
+        };
+
+        if kill && player.is_alive() {
+            let engine = self.engine();
+            let world = engine.get_world_spawn_entity();
+            self.disable_death_penalty.set(true);
+            player.killed(world.vars(), if gib { Gib::Always } else { Gib::Never });
+            self.disable_death_penalty.set(false);
+        }
+
+        player_team.set(Some(team));
+        if let Some(team_name) = self.get_indexed_team_name(team) {
+            let engine = self.engine();
+            let mut info = engine.get_info_buffer(player.vars());
+            info.set(c"model", &team_name);
+            info.set(c"team", &team_name);
+        }
+
+        self.send_team_info(player.as_entity(), None);
+        self.base.update_score(player.as_entity());
+    }
+
+    fn end_multiplayer_game(&self) {
+        self.base.end_multiplayer_game();
+    }
+}
@@ -1,4 +1,5 @@
 #![no_std]
 
+pub mod random;
 pub mod user_message;
 pub mod weapons;
@@ -163,7 +163,13 @@ define_user_message! {
     }
 }
 
-// TODO: define_user_message!(TeamInfo)
+define_user_message! {
+    pub struct TeamInfo<'a> {
+        pub client_index: u8,
+        pub team_name: &'a CStr,
+    }
+}
+
 // TODO: define_user_message!(TeamScore)
 
 define_user_message! {
@@ -9,9 +9,18 @@ pub mod python;
 pub mod rpg;
 pub mod shotgun;
 pub mod snark;
+pub mod state;
 pub mod tripmine;
 
+use core::ffi::CStr;
+
 use bitflags::bitflags;
+use xash3d_shared::{ffi::common::clientdata_s, macros::define_enum_for_primitive};
+
+use crate::user_message::WeaponList;
+
+/// The maximum number of weapons a player can carry.
+pub const MAX_WEAPONS: usize = 32;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Items {
@@ -21,27 +30,36 @@ pub enum Items {
     Battery,
 }
 
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
-pub enum Weapon {
-    #[default]
-    None = 0,
-    Crowbar = 1,
-    Glock = 2,
-    Python = 3,
-    Mp5 = 4,
-    Chaingun = 5,
-    Crossbow = 6,
-    Shotgun = 7,
-    Rpg = 8,
-    Gauss = 9,
-    Egon = 10,
-    HornetGun = 11,
-    HandGrenade = 12,
-    Tripmine = 13,
-    Satchel = 14,
-    Snark = 15,
-
-    Suit = 31,
+define_enum_for_primitive! {
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    pub enum Weapon: u8 {
+        #[default]
+        None(0),
+        Crowbar(1),
+        Glock(2),
+        Python(3),
+        Mp5(4),
+        Chaingun(5),
+        Crossbow(6),
+        Shotgun(7),
+        Rpg(8),
+        Gauss(9),
+        Egon(10),
+        HornetGun(11),
+        HandGrenade(12),
+        Tripmine(13),
+        Satchel(14),
+        Snark(15),
+
+        Suit(31),
+    }
+}
+
+impl Weapon {
+    /// Returns static properties of this weapon.
+    pub fn info(self) -> Option<&'static WeaponInfo> {
+        WEAPON_INFO.iter().find(|i| i.id == self)
+    }
 }
 
 bitflags! {
@@ -70,3 +88,278 @@ bitflags! {
         const SUIT          = 1 << Weapon::Suit as u32;
     }
 }
+
+define_enum_for_primitive! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum AmmoType: u8 {
+        Buckshot(1),
+        NineMm(2),
+        ArGrenades(3),
+        Magnum(4),
+        Uranium(5),
+        Rockets(6),
+        Bolts(7),
+        TripMine(8),
+        SatchelCharge(9),
+        HandGrenade(10),
+        Snarks(11),
+        Hornets(12),
+    }
+}
+
+impl AmmoType {
+    pub const ALL: [AmmoType; 12] = [
+        Self::Buckshot,
+        Self::NineMm,
+        Self::ArGrenades,
+        Self::Magnum,
+        Self::Uranium,
+        Self::Rockets,
+        Self::Bolts,
+        Self::TripMine,
+        Self::SatchelCharge,
+        Self::HandGrenade,
+        Self::Snarks,
+        Self::Hornets,
+    ];
+
+    /// Returns an index in the player ammo slots.
+    pub fn index(self) -> usize {
+        self.into_raw() as usize
+    }
+
+    pub fn name(self) -> &'static CStr {
+        match self {
+            Self::Buckshot => c"buckshot",
+            Self::NineMm => c"9mm",
+            Self::ArGrenades => c"ARgrenades",
+            Self::Magnum => c"357",
+            Self::Uranium => c"uranium",
+            Self::Rockets => c"rockets",
+            Self::Bolts => c"bolts",
+            Self::TripMine => c"Trip Mine",
+            Self::SatchelCharge => c"Satchel Charge",
+            Self::HandGrenade => c"Hand Grenade",
+            Self::Snarks => c"Snarks",
+            Self::Hornets => c"Hornets",
+        }
+    }
+
+    /// Returns the maximum amount of ammo a player can carry.
+    pub fn max_carry(self) -> i32 {
+        match self {
+            Self::Buckshot => 125,
+            Self::NineMm => 250,
+            Self::ArGrenades => 10,
+            Self::Magnum => 36,
+            Self::Uranium => 100,
+            Self::Rockets => 5,
+            Self::Bolts => 50,
+            Self::TripMine => 5,
+            Self::SatchelCharge => 5,
+            Self::HandGrenade => 10,
+            Self::Snarks => 15,
+            Self::Hornets => 8,
+        }
+    }
+
+    /// Returns the ammo count sent to the client for weapon prediction.
+    ///
+    /// Returns `None` if the ammo type is not sent.
+    pub fn client_ammo(self, cd: &clientdata_s) -> Option<i32> {
+        Some(match self {
+            Self::NineMm => cd.vuser1.x as i32,
+            Self::Magnum => cd.vuser1.y as i32,
+            Self::ArGrenades => cd.vuser1.z as i32,
+            Self::Bolts => cd.ammo_nails,
+            Self::Buckshot => cd.ammo_shells,
+            Self::Uranium => cd.ammo_cells,
+            Self::Rockets => cd.ammo_rockets,
+            Self::Hornets => cd.vuser2.x as i32,
+            _ => return None,
+        })
+    }
+
+    /// Writes the ammo count sent to the client for weapon prediction.
+    pub fn set_client_ammo(self, cd: &mut clientdata_s, count: i32) {
+        match self {
+            Self::NineMm => cd.vuser1.x = count as f32,
+            Self::Magnum => cd.vuser1.y = count as f32,
+            Self::ArGrenades => cd.vuser1.z = count as f32,
+            Self::Bolts => cd.ammo_nails = count,
+            Self::Buckshot => cd.ammo_shells = count,
+            Self::Uranium => cd.ammo_cells = count,
+            Self::Rockets => cd.ammo_rockets = count,
+            Self::Hornets => cd.vuser2.x = count as f32,
+            _ => {}
+        }
+    }
+}
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct WeaponFlags: u8 {
+        /// The weapon can be selected without ammo.
+        const SELECT_ON_EMPTY       = 1 << 0;
+        const NO_AUTO_RELOAD        = 1 << 1;
+        const NO_AUTO_SWITCH_EMPTY  = 1 << 2;
+        /// Only a limited number of these weapons can be in the world.
+        const LIMIT_IN_WORLD        = 1 << 3;
+        /// The weapon is removed from the inventory when out of ammo.
+        const EXHAUSTIBLE           = 1 << 4;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub enum Bullet {
+    None = 0,
+    Player9mm,
+    PlayerMp5,
+    Player357,
+    PlayerBuckshot,
+    PlayerCrowbar,
+
+    Monster9mm,
+    MonsterMp5,
+    Monster12mm,
+}
+
+/// Static weapon properties.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WeaponInfo {
+    pub id: Weapon,
+    pub name: &'static CStr,
+    /// The HUD slot.
+    pub slot: u8,
+    /// The position in the HUD slot.
+    pub position: u8,
+    pub ammo1: Option<AmmoType>,
+    pub ammo2: Option<AmmoType>,
+    /// The clip size or `None` if the weapon does not use clips.
+    pub max_clip: Option<u8>,
+    /// The ammo given with a new weapon.
+    pub default_ammo: u8,
+    /// The priority for an automatic selection.
+    pub weight: i32,
+    pub flags: WeaponFlags,
+}
+
+impl WeaponInfo {
+    /// Returns properties of all weapons.
+    pub fn all() -> &'static [WeaponInfo] {
+        WEAPON_INFO
+    }
+
+    const fn new(id: Weapon, name: &'static CStr, slot: u8, position: u8) -> Self {
+        Self {
+            id,
+            name,
+            slot,
+            position,
+            ammo1: None,
+            ammo2: None,
+            max_clip: None,
+            default_ammo: 0,
+            weight: 0,
+            flags: WeaponFlags::empty(),
+        }
+    }
+
+    const fn ammo(mut self, ammo1: AmmoType, max_clip: Option<u8>, default_ammo: u8) -> Self {
+        self.ammo1 = Some(ammo1);
+        self.max_clip = max_clip;
+        self.default_ammo = default_ammo;
+        self
+    }
+
+    const fn ammo2(mut self, ammo2: AmmoType) -> Self {
+        self.ammo2 = Some(ammo2);
+        self
+    }
+
+    const fn weight(mut self, weight: i32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    const fn flags(mut self, flags: WeaponFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Returns a message that registers this weapon in the client HUD.
+    pub fn weapon_list(&self) -> WeaponList<'static> {
+        let ammo = |ty: Option<AmmoType>| match ty {
+            Some(ty) => (ty.into_raw() as i8, ty.max_carry() as u8),
+            None => (-1, u8::MAX),
+        };
+        let (ammo1, max1) = ammo(self.ammo1);
+        let (ammo2, max2) = ammo(self.ammo2);
+        WeaponList {
+            name: self.name,
+            ammo1,
+            max1,
+            ammo2,
+            max2,
+            slot: self.slot as i8,
+            slot_pos: self.position as i8,
+            id: self.id.into_raw() as i8,
+            flags: self.flags.bits(),
+        }
+    }
+}
+
+const WEAPON_INFO: &[WeaponInfo] = &[
+    WeaponInfo::new(Weapon::Crowbar, c"weapon_crowbar", 0, 0),
+    WeaponInfo::new(Weapon::Glock, c"weapon_9mmhandgun", 1, 0)
+        .ammo(AmmoType::NineMm, Some(17), 17)
+        .weight(10),
+    WeaponInfo::new(Weapon::Python, c"weapon_357", 1, 1)
+        .ammo(AmmoType::Magnum, Some(6), 6)
+        .weight(15),
+    WeaponInfo::new(Weapon::Mp5, c"weapon_9mmAR", 2, 0)
+        .ammo(AmmoType::NineMm, Some(50), 25)
+        .ammo2(AmmoType::ArGrenades)
+        .weight(15),
+    WeaponInfo::new(Weapon::Shotgun, c"weapon_shotgun", 2, 1)
+        .ammo(AmmoType::Buckshot, Some(8), 12)
+        .weight(15),
+    WeaponInfo::new(Weapon::Crossbow, c"weapon_crossbow", 2, 2)
+        .ammo(AmmoType::Bolts, Some(5), 5)
+        .weight(10),
+    WeaponInfo::new(Weapon::Rpg, c"weapon_rpg", 3, 0)
+        .ammo(AmmoType::Rockets, Some(1), 1)
+        .weight(20),
+    WeaponInfo::new(Weapon::Gauss, c"weapon_gauss", 3, 1)
+        .ammo(AmmoType::Uranium, None, 20)
+        .weight(20),
+    WeaponInfo::new(Weapon::Egon, c"weapon_egon", 3, 2)
+        .ammo(AmmoType::Uranium, None, 20)
+        .weight(20),
+    WeaponInfo::new(Weapon::HornetGun, c"weapon_hornetgun", 3, 3)
+        .ammo(AmmoType::Hornets, None, 8)
+        .weight(10)
+        .flags(WeaponFlags::NO_AUTO_SWITCH_EMPTY.union(WeaponFlags::NO_AUTO_RELOAD)),
+    WeaponInfo::new(Weapon::HandGrenade, c"weapon_handgrenade", 4, 0)
+        .ammo(AmmoType::HandGrenade, None, 5)
+        .weight(5)
+        .flags(WeaponFlags::LIMIT_IN_WORLD.union(WeaponFlags::EXHAUSTIBLE)),
+    WeaponInfo::new(Weapon::Satchel, c"weapon_satchel", 4, 1)
+        .ammo(AmmoType::SatchelCharge, None, 1)
+        .weight(-10)
+        .flags(
+            WeaponFlags::SELECT_ON_EMPTY
+                .union(WeaponFlags::LIMIT_IN_WORLD)
+                .union(WeaponFlags::EXHAUSTIBLE),
+        ),
+    WeaponInfo::new(Weapon::Tripmine, c"weapon_tripmine", 4, 2)
+        .ammo(AmmoType::TripMine, None, 1)
+        .weight(-10)
+        .flags(WeaponFlags::LIMIT_IN_WORLD.union(WeaponFlags::EXHAUSTIBLE)),
+    WeaponInfo::new(Weapon::Snark, c"weapon_snark", 4, 3)
+        .ammo(AmmoType::Snarks, None, 5)
+        .weight(5)
+        .flags(WeaponFlags::LIMIT_IN_WORLD.union(WeaponFlags::EXHAUSTIBLE)),
+];
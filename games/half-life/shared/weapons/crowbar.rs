@@ -1,3 +1,15 @@
+use core::ffi::CStr;
+
+use res::valve::{events, models, sound::weapons as sound};
+
+use crate::weapons::{
+    Bullet, Weapon,
+    state::{EventParams, WeaponContext, WeaponLogic, WeaponState},
+};
+
+#[cfg(feature = "save")]
+use xash3d_server::save::{Restore, Save};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum CrowbarAnimation {
     #[default]
@@ -11,3 +23,57 @@ pub enum CrowbarAnimation {
     Attack3Miss,
     Attack3Hit,
 }
+
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Crowbar {
+    swing: u32,
+}
+
+impl WeaponLogic for Crowbar {
+    const ID: Weapon = Weapon::Crowbar;
+    const WORLD_MODEL: &'static CStr = models::W_CROWBAR;
+    const MODELS: &'static [&'static CStr] = &[models::V_CROWBAR, models::P_CROWBAR];
+    const SOUNDS: &'static [&'static CStr] = &[
+        sound::CBAR_HIT1,
+        sound::CBAR_HIT2,
+        sound::CBAR_HITBOD1,
+        sound::CBAR_HITBOD2,
+        sound::CBAR_HITBOD3,
+        sound::CBAR_MISS1,
+    ];
+    const EVENTS: &'static [&'static CStr] = &[events::CROWBAR];
+
+    fn deploy(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) -> bool {
+        let anim = CrowbarAnimation::Draw as i32;
+        state.default_deploy(ctx, models::V_CROWBAR, models::P_CROWBAR, anim, 0)
+    }
+
+    fn holster(&mut self, _: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        ctx.set_next_attack(0.5);
+        ctx.send_weapon_anim(CrowbarAnimation::Holster as i32, 0);
+    }
+
+    fn primary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        // the first swing after a pause does full damage
+        let damage_scale = if state.next_primary_attack + 1.0 < 0.0 {
+            1.0
+        } else {
+            0.5
+        };
+        ctx.playback_event(events::CROWBAR, EventParams::default());
+        ctx.player_attack();
+        if ctx.melee_attack(32.0, Bullet::PlayerCrowbar, damage_scale) {
+            let anim = match self.swing % 3 {
+                0 => CrowbarAnimation::Attack1Hit,
+                1 => CrowbarAnimation::Attack2Hit,
+                _ => CrowbarAnimation::Attack3Hit,
+            };
+            self.swing = self.swing.wrapping_add(1);
+            ctx.send_weapon_anim(anim as i32, 0);
+            state.next_primary_attack = 0.25;
+        } else {
+            state.next_primary_attack = 0.5;
+        }
+    }
+}
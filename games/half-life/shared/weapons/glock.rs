@@ -1,3 +1,18 @@
+use core::ffi::CStr;
+
+use res::valve::{events, models, sound::weapons as sound};
+
+use crate::{
+    random::shared_random_float,
+    weapons::{
+        Bullet, Weapon,
+        state::{EventParams, WeaponContext, WeaponLogic, WeaponState},
+    },
+};
+
+#[cfg(feature = "save")]
+use xash3d_server::save::{Restore, Save};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum GlockAnimation {
     #[default]
@@ -12,3 +27,88 @@ pub enum GlockAnimation {
     Holster,
     AddSilencer,
 }
+
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Glock;
+
+impl Glock {
+    fn fire(
+        state: &mut WeaponState,
+        ctx: &mut dyn WeaponContext,
+        spread: f32,
+        cycle_time: f32,
+        event: &'static CStr,
+    ) {
+        if state.clip <= 0 {
+            if state.fire_on_empty {
+                state.play_empty_sound(ctx);
+                state.next_primary_attack = 0.2;
+            }
+            return;
+        }
+
+        state.clip -= 1;
+        ctx.player_attack();
+        let spread = ctx.fire_bullets(1, spread, 8192.0, Bullet::Player9mm);
+        let params = EventParams {
+            bparam1: state.clip == 0,
+            ..EventParams::spread(spread)
+        };
+        ctx.playback_event(event, params);
+
+        state.next_primary_attack = cycle_time;
+        state.next_secondary_attack = cycle_time;
+        state.time_weapon_idle = state.random_idle_time(ctx);
+    }
+}
+
+impl WeaponLogic for Glock {
+    const ID: Weapon = Weapon::Glock;
+    const WORLD_MODEL: &'static CStr = models::W_9MMHANDGUN;
+    const MODELS: &'static [&'static CStr] =
+        &[models::V_9MMHANDGUN, models::P_9MMHANDGUN, models::SHELL];
+    const SOUNDS: &'static [&'static CStr] = &[sound::PL_GUN1, sound::PL_GUN2, sound::PL_GUN3];
+    const EVENTS: &'static [&'static CStr] = &[events::GLOCK1, events::GLOCK2];
+
+    fn deploy(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) -> bool {
+        let anim = GlockAnimation::Draw as i32;
+        state.default_deploy(ctx, models::V_9MMHANDGUN, models::P_9MMHANDGUN, anim, 0)
+    }
+
+    fn primary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        Self::fire(state, ctx, 0.01, 0.3, events::GLOCK1);
+    }
+
+    fn secondary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        Self::fire(state, ctx, 0.1, 0.2, events::GLOCK2);
+    }
+
+    fn reload(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        let anim = if state.clip == 0 {
+            GlockAnimation::Reload
+        } else {
+            GlockAnimation::ReloadNotEmpty
+        };
+        if state.default_reload(ctx, Self::info(), anim as i32, 1.5, 0) {
+            state.time_weapon_idle = state.random_idle_time(ctx);
+        }
+    }
+
+    fn weapon_idle(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        state.reset_empty_sound();
+        if state.time_weapon_idle > 0.0 {
+            return;
+        }
+        // only idle if the slide is not back
+        if state.clip != 0 {
+            let (anim, time) = match shared_random_float(ctx.random_seed(), 0.0, 1.0) {
+                r if r <= 0.3 => (GlockAnimation::Idle3, 49.0 / 16.0),
+                r if r <= 0.6 => (GlockAnimation::Idle1, 60.0 / 16.0),
+                _ => (GlockAnimation::Idle2, 40.0 / 16.0),
+            };
+            state.time_weapon_idle = time;
+            ctx.send_weapon_anim(anim as i32, 0);
+        }
+    }
+}
@@ -1,3 +1,18 @@
+use core::ffi::CStr;
+
+use res::valve::{events, models, sound::weapons as sound};
+
+use crate::{
+    random::shared_random_long,
+    weapons::{
+        Bullet, Weapon,
+        state::{CONE_3_DEGREES, EventParams, WeaponContext, WeaponLogic, WeaponState},
+    },
+};
+
+#[cfg(feature = "save")]
+use xash3d_server::save::{Restore, Save};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum Mp5Animation {
     #[default]
@@ -10,3 +25,55 @@ pub enum Mp5Animation {
     Fire2,
     Fire3,
 }
+
+/// The MP5 without the grenade launcher.
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Mp5;
+
+impl WeaponLogic for Mp5 {
+    const ID: Weapon = Weapon::Mp5;
+    const WORLD_MODEL: &'static CStr = models::W_9MMAR;
+    const MODELS: &'static [&'static CStr] = &[models::V_9MMAR, models::P_9MMAR, models::SHELL];
+    const SOUNDS: &'static [&'static CStr] = &[sound::HKS1, sound::HKS2, sound::HKS3];
+    const EVENTS: &'static [&'static CStr] = &[events::MP5];
+
+    fn deploy(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) -> bool {
+        let anim = Mp5Animation::Deploy as i32;
+        state.default_deploy(ctx, models::V_9MMAR, models::P_9MMAR, anim, 0)
+    }
+
+    fn primary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        // don't fire underwater
+        if ctx.is_underwater() || state.clip <= 0 {
+            state.play_empty_sound(ctx);
+            state.next_primary_attack = 0.15;
+            return;
+        }
+
+        state.clip -= 1;
+        ctx.player_attack();
+        let spread = ctx.fire_bullets(1, CONE_3_DEGREES, 8192.0, Bullet::PlayerMp5);
+        ctx.playback_event(events::MP5, EventParams::spread(spread));
+
+        state.next_primary_attack = 0.1;
+        state.time_weapon_idle = state.random_idle_time(ctx);
+    }
+
+    fn reload(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        state.default_reload(ctx, Self::info(), Mp5Animation::Reload as i32, 1.5, 0);
+    }
+
+    fn weapon_idle(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        state.reset_empty_sound();
+        if state.time_weapon_idle > 0.0 {
+            return;
+        }
+        let anim = match shared_random_long(ctx.random_seed(), 0, 1) {
+            0 => Mp5Animation::Longidle,
+            _ => Mp5Animation::Idle1,
+        };
+        ctx.send_weapon_anim(anim as i32, 0);
+        state.time_weapon_idle = state.random_idle_time(ctx);
+    }
+}
@@ -1,3 +1,18 @@
+use core::ffi::CStr;
+
+use res::valve::{events, models, sound::weapons as sound};
+
+use crate::{
+    random::shared_random_float,
+    weapons::{
+        Bullet, Weapon,
+        state::{CONE_1_DEGREES, EventParams, WeaponContext, WeaponLogic, WeaponState},
+    },
+};
+
+#[cfg(feature = "save")]
+use xash3d_server::save::{Restore, Save};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum PythonAnimation {
     #[default]
@@ -10,3 +25,83 @@ pub enum PythonAnimation {
     Idle2,
     Idle3,
 }
+
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Python;
+
+impl WeaponLogic for Python {
+    const ID: Weapon = Weapon::Python;
+    const WORLD_MODEL: &'static CStr = models::W_357;
+    const MODELS: &'static [&'static CStr] = &[models::V_357, models::P_357];
+    const SOUNDS: &'static [&'static CStr] =
+        &[sound::_357_RELOAD1, sound::_357_SHOT1, sound::_357_SHOT2];
+    const EVENTS: &'static [&'static CStr] = &[events::PYTHON];
+
+    fn deploy(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) -> bool {
+        let anim = PythonAnimation::Draw as i32;
+        state.default_deploy(ctx, models::V_357, models::P_357, anim, 0)
+    }
+
+    fn holster(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        state.in_reload = false;
+        ctx.set_next_attack(1.0);
+        state.time_weapon_idle = state.random_idle_time(ctx);
+        ctx.send_weapon_anim(PythonAnimation::Holster as i32, 0);
+    }
+
+    fn primary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        // don't fire underwater
+        if ctx.is_underwater() {
+            state.play_empty_sound(ctx);
+            state.next_primary_attack = 0.15;
+            return;
+        }
+
+        if state.clip <= 0 {
+            if state.fire_on_empty {
+                state.play_empty_sound(ctx);
+                state.next_primary_attack = 0.15;
+            } else {
+                self.reload(state, ctx);
+            }
+            return;
+        }
+
+        state.clip -= 1;
+        ctx.player_attack();
+        let spread = ctx.fire_bullets(1, CONE_1_DEGREES, 8192.0, Bullet::Player357);
+        ctx.playback_event(events::PYTHON, EventParams::spread(spread));
+
+        state.next_primary_attack = 0.75;
+        state.time_weapon_idle = state.random_idle_time(ctx);
+    }
+
+    fn reload(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        let anim = PythonAnimation::Reload as i32;
+        if state.default_reload(ctx, Self::info(), anim, 2.0, 0) {
+            state.sound_time = Some(1.5);
+        }
+    }
+
+    fn weapon_idle(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        state.reset_empty_sound();
+
+        if state.take_sound_time() {
+            ctx.play_weapon_sound(sound::_357_RELOAD1, 0.85, 100);
+        }
+
+        if state.time_weapon_idle > 0.0 {
+            return;
+        }
+
+        let (anim, time) = match shared_random_float(ctx.random_seed(), 0.0, 1.0) {
+            r if r <= 0.5 => (PythonAnimation::Idle1, 70.0 / 30.0),
+            r if r <= 0.7 => (PythonAnimation::Idle2, 60.0 / 30.0),
+            r if r <= 0.9 => (PythonAnimation::Idle3, 88.0 / 30.0),
+            _ => (PythonAnimation::Fidget, 170.0 / 30.0),
+        };
+        state.time_weapon_idle = time;
+        ctx.send_weapon_anim(anim as i32, 0);
+    }
+}
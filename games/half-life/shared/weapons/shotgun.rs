@@ -1,3 +1,18 @@
+use core::ffi::CStr;
+
+use res::valve::{events, models, sound::weapons as sound};
+
+use crate::{
+    random::{shared_random_float, shared_random_long},
+    weapons::{
+        Bullet, Weapon,
+        state::{CONE_10_DEGREES, EventParams, WeaponContext, WeaponLogic, WeaponState},
+    },
+};
+
+#[cfg(feature = "save")]
+use xash3d_server::save::{Restore, Save};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum ShotgunAnimation {
     #[default]
@@ -12,3 +27,177 @@ pub enum ShotgunAnimation {
     Idle4,
     IdleDeep,
 }
+
+/// Reload stages stored in [WeaponState::in_special_reload].
+const RELOAD_NONE: i32 = 0;
+const RELOAD_START: i32 = 1;
+const RELOAD_SHELL: i32 = 2;
+
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct Shotgun;
+
+impl Shotgun {
+    fn fire(
+        &mut self,
+        state: &mut WeaponState,
+        ctx: &mut dyn WeaponContext,
+        shells: i32,
+        event: &'static CStr,
+    ) -> bool {
+        // don't fire underwater
+        if ctx.is_underwater() {
+            state.play_empty_sound(ctx);
+            state.next_primary_attack = 0.15;
+            return false;
+        }
+
+        if state.clip < shells {
+            self.reload(state, ctx);
+            if state.clip < shells {
+                state.play_empty_sound(ctx);
+            }
+            return false;
+        }
+
+        state.clip -= shells;
+        ctx.player_attack();
+        let pellets = shells as u32 * 6;
+        let spread = ctx.fire_bullets(pellets, CONE_10_DEGREES, 2048.0, Bullet::PlayerBuckshot);
+        ctx.playback_event(event, EventParams::spread(spread));
+        state.in_special_reload = RELOAD_NONE;
+        true
+    }
+
+    fn play_pump_sound(ctx: &mut dyn WeaponContext) {
+        let pitch = 95 + shared_random_long(ctx.random_seed(), 0, 0x1f);
+        ctx.play_item_sound(sound::SCOCK1, 1.0, pitch);
+    }
+}
+
+impl WeaponLogic for Shotgun {
+    const ID: Weapon = Weapon::Shotgun;
+    const WORLD_MODEL: &'static CStr = models::W_SHOTGUN;
+    const MODELS: &'static [&'static CStr] =
+        &[models::V_SHOTGUN, models::P_SHOTGUN, models::SHOTGUNSHELL];
+    const SOUNDS: &'static [&'static CStr] = &[
+        sound::DBARREL1,
+        sound::SBARREL1,
+        sound::RELOAD1,
+        sound::RELOAD3,
+        sound::SCOCK1,
+    ];
+    const EVENTS: &'static [&'static CStr] = &[events::SHOTGUN1, events::SHOTGUN2];
+
+    fn deploy(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) -> bool {
+        let anim = ShotgunAnimation::Draw as i32;
+        state.default_deploy(ctx, models::V_SHOTGUN, models::P_SHOTGUN, anim, 0)
+    }
+
+    fn primary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        if !self.fire(state, ctx, 1, events::SHOTGUN1) {
+            return;
+        }
+        if state.clip != 0 {
+            state.sound_time = Some(0.5);
+        }
+        state.next_primary_attack = 0.75;
+        state.next_secondary_attack = 0.75;
+        state.time_weapon_idle = if state.clip != 0 { 5.0 } else { 0.75 };
+    }
+
+    fn secondary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        if !self.fire(state, ctx, 2, events::SHOTGUN2) {
+            return;
+        }
+        if state.clip != 0 {
+            state.sound_time = Some(0.95);
+        }
+        state.next_primary_attack = 1.5;
+        state.next_secondary_attack = 1.5;
+        state.time_weapon_idle = if state.clip != 0 { 6.0 } else { 1.5 };
+    }
+
+    fn reload(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        let info = Self::info();
+        let Some(ammo1) = info.ammo1 else { return };
+        let max_clip = info.max_clip.unwrap_or(0) as i32;
+        if ctx.ammo(ammo1) <= 0 || state.clip >= max_clip {
+            return;
+        }
+
+        // don't reload until recoil is done
+        if state.next_primary_attack > 0.0 {
+            return;
+        }
+
+        match state.in_special_reload {
+            RELOAD_NONE => {
+                ctx.send_weapon_anim(ShotgunAnimation::StartReload as i32, 0);
+                state.in_special_reload = RELOAD_START;
+                ctx.set_next_attack(0.6);
+                state.time_weapon_idle = 0.6;
+                state.next_primary_attack = 1.0;
+                state.next_secondary_attack = 1.0;
+            }
+            RELOAD_START => {
+                if state.time_weapon_idle > 0.0 {
+                    return;
+                }
+                // was waiting for gun to move to side
+                state.in_special_reload = RELOAD_SHELL;
+                let seed = ctx.random_seed();
+                let sample = if shared_random_long(seed, 0, 1) != 0 {
+                    sound::RELOAD1
+                } else {
+                    sound::RELOAD3
+                };
+                ctx.play_item_sound(sample, 1.0, 85 + shared_random_long(seed, 0, 0x1f));
+                ctx.send_weapon_anim(ShotgunAnimation::Reload as i32, 0);
+                state.time_weapon_idle = 0.5;
+            }
+            _ => {
+                state.clip += 1;
+                ctx.set_ammo(ammo1, ctx.ammo(ammo1) - 1);
+                state.in_special_reload = RELOAD_START;
+            }
+        }
+    }
+
+    fn weapon_idle(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        state.reset_empty_sound();
+
+        if state.take_sound_time() {
+            Self::play_pump_sound(ctx);
+        }
+
+        if state.time_weapon_idle > 0.0 {
+            return;
+        }
+
+        let info = Self::info();
+        let has_ammo = info.ammo1.is_some_and(|i| ctx.ammo(i) > 0);
+        let max_clip = info.max_clip.unwrap_or(0) as i32;
+        if state.clip == 0 && state.in_special_reload == RELOAD_NONE && has_ammo {
+            self.reload(state, ctx);
+        } else if state.in_special_reload != RELOAD_NONE {
+            if state.clip != max_clip && has_ammo {
+                self.reload(state, ctx);
+            } else {
+                // reload debounce has timed out
+                ctx.send_weapon_anim(ShotgunAnimation::Pump as i32, 0);
+                Self::play_pump_sound(ctx);
+                state.in_special_reload = RELOAD_NONE;
+                state.time_weapon_idle = 1.5;
+            }
+        } else {
+            let (anim, time) = match shared_random_float(ctx.random_seed(), 0.0, 1.0) {
+                r if r <= 0.8 => (ShotgunAnimation::IdleDeep, 60.0 / 12.0),
+                r if r <= 0.95 => (ShotgunAnimation::Idle, 20.0 / 9.0),
+                _ => (ShotgunAnimation::Idle4, 20.0 / 9.0),
+            };
+            state.time_weapon_idle = time;
+            ctx.send_weapon_anim(anim as i32, 0);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! Weapon logic shared by the server and the client.
+//!
+//! The server runs it for carried weapons and the client for prediction. All timers are
+//! relative to the current frame, so the state can be sent to the client with `weapon_data_s`.
+
+use core::ffi::CStr;
+
+use xash3d_shared::{entity::Buttons, ffi::common::weapon_data_s};
+
+use crate::{
+    random::shared_random_float,
+    weapons::{AmmoType, Bullet, Weapon, WeaponFlags, WeaponInfo},
+};
+
+#[cfg(feature = "save")]
+use xash3d_server::save::{Restore, Save};
+
+pub const CONE_1_DEGREES: f32 = 0.00873;
+pub const CONE_3_DEGREES: f32 = 0.02618;
+pub const CONE_10_DEGREES: f32 = 0.08716;
+
+/// Parameters of a weapon event.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EventParams {
+    pub fparam1: f32,
+    pub fparam2: f32,
+    pub iparam1: i32,
+    pub iparam2: i32,
+    pub bparam1: bool,
+    pub bparam2: bool,
+}
+
+impl EventParams {
+    /// Returns parameters with a bullet spread returned by [WeaponContext::fire_bullets].
+    pub fn spread((x, y): (f32, f32)) -> Self {
+        Self {
+            fparam1: x,
+            fparam2: y,
+            ..Self::default()
+        }
+    }
+}
+
+/// The player that uses a weapon.
+///
+/// The server implements it for real players and the client for the predicted local player.
+pub trait WeaponContext {
+    /// Returns buttons of the current user command.
+    fn buttons(&self) -> Buttons;
+
+    /// Returns the random seed of the current user command.
+    fn random_seed(&self) -> u32;
+
+    /// Returns `true` if the player view is underwater.
+    fn is_underwater(&self) -> bool;
+
+    /// Returns the delay before the player can use weapons.
+    fn next_attack(&self) -> f32;
+
+    fn set_next_attack(&mut self, delay: f32);
+
+    fn ammo(&self, ty: AmmoType) -> i32;
+
+    fn set_ammo(&mut self, ty: AmmoType, count: i32);
+
+    /// Sets the view model and the model attached to the player.
+    fn set_models(
+        &mut self,
+        view_model: Option<&'static CStr>,
+        weapon_model: Option<&'static CStr>,
+    );
+
+    fn send_weapon_anim(&mut self, anim: i32, body: i32);
+
+    /// Starts the muzzle flash and the player attack animation.
+    fn player_attack(&mut self);
+
+    /// Plays a sound on the weapon channel of the player.
+    fn play_weapon_sound(&mut self, sample: &'static CStr, volume: f32, pitch: i32);
+
+    /// Plays a sound on the item channel of the player.
+    fn play_item_sound(&mut self, sample: &'static CStr, volume: f32, pitch: i32);
+
+    /// Fires bullets from the player view.
+    ///
+    /// Returns the spread of the last bullet from [bullet_spread].
+    fn fire_bullets(
+        &mut self,
+        shots: u32,
+        spread: f32,
+        distance: f32,
+        bullet: Bullet,
+    ) -> (f32, f32);
+
+    /// Traces a melee attack from the player view.
+    ///
+    /// Returns `true` if something was hit.
+    fn melee_attack(&mut self, distance: f32, bullet: Bullet, damage_scale: f32) -> bool;
+
+    /// Plays a precached weapon event.
+    fn playback_event(&mut self, event: &'static CStr, params: EventParams);
+
+    /// Switches to the next best weapon after the current frame.
+    ///
+    /// Returns `false` if the player has no other usable weapons.
+    fn switch_to_next_best(&mut self) -> bool;
+}
+
+/// Returns the spread of a bullet fired with [WeaponContext::fire_bullets].
+pub fn bullet_spread(seed: u32, shot: u32, spread: f32) -> (f32, f32) {
+    let seed = seed.wrapping_add(shot);
+    let random = |i: u32| shared_random_float(seed.wrapping_add(i), -0.5, 0.5);
+    let x = random(0) + random(1);
+    let y = random(2) + random(3);
+    (x * spread, y * spread)
+}
+
+/// The common state of a weapon.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "save", derive(Save, Restore))]
+pub struct WeaponState {
+    /// Rounds in the clip or `-1` if the weapon does not use clips.
+    pub clip: i32,
+    pub next_primary_attack: f32,
+    pub next_secondary_attack: f32,
+    pub time_weapon_idle: f32,
+    pub in_reload: bool,
+    /// A weapon specific reload stage.
+    pub in_special_reload: i32,
+    /// A weapon specific delay before a sound.
+    pub sound_time: Option<f32>,
+    /// The attack button was pressed without ammo.
+    pub fire_on_empty: bool,
+    pub play_empty_sound: bool,
+}
+
+impl Default for WeaponState {
+    fn default() -> Self {
+        Self {
+            clip: -1,
+            next_primary_attack: 0.0,
+            next_secondary_attack: 0.0,
+            time_weapon_idle: 0.0,
+            in_reload: false,
+            in_special_reload: 0,
+            sound_time: None,
+            fire_on_empty: false,
+            play_empty_sound: true,
+        }
+    }
+}
+
+impl WeaponState {
+    pub fn new(info: &WeaponInfo) -> Self {
+        Self {
+            clip: if info.max_clip.is_some() { 0 } else { -1 },
+            ..Self::default()
+        }
+    }
+
+    pub fn decrement_timers(&mut self, time: f32) {
+        self.next_primary_attack = (self.next_primary_attack - time).max(-1.1);
+        self.next_secondary_attack = (self.next_secondary_attack - time).max(-0.001);
+        self.time_weapon_idle = (self.time_weapon_idle - time).max(-0.001);
+        if let Some(sound_time) = &mut self.sound_time {
+            *sound_time -= time;
+        }
+    }
+
+    /// Returns `true` if the sound delay has expired and resets it.
+    pub fn take_sound_time(&mut self) -> bool {
+        if self.sound_time.is_some_and(|i| i <= 0.0) {
+            self.sound_time = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn write_weapon_data(&self, id: Weapon, data: &mut weapon_data_s) {
+        data.m_iId = id.into_raw().into();
+        data.m_iClip = self.clip;
+        data.m_flNextPrimaryAttack = self.next_primary_attack;
+        data.m_flNextSecondaryAttack = self.next_secondary_attack;
+        data.m_flTimeWeaponIdle = self.time_weapon_idle;
+        data.m_fInReload = self.in_reload.into();
+        data.m_fInSpecialReload = self.in_special_reload;
+        // an expired delay is still pending until the logic takes it
+        data.m_flPumpTime = self.sound_time.map_or(-1.0, |i| i.max(0.0));
+    }
+
+    pub fn read_weapon_data(&mut self, data: &weapon_data_s) {
+        self.clip = data.m_iClip;
+        self.next_primary_attack = data.m_flNextPrimaryAttack;
+        self.next_secondary_attack = data.m_flNextSecondaryAttack;
+        self.time_weapon_idle = data.m_flTimeWeaponIdle;
+        self.in_reload = data.m_fInReload != 0;
+        self.in_special_reload = data.m_fInSpecialReload;
+        self.sound_time = (data.m_flPumpTime >= 0.0).then_some(data.m_flPumpTime);
+    }
+
+    /// Returns `true` if the weapon can be deployed.
+    pub fn can_deploy(&self, info: &WeaponInfo, ctx: &dyn WeaponContext) -> bool {
+        if info.flags.intersects(WeaponFlags::SELECT_ON_EMPTY) {
+            return true;
+        }
+        let Some(ammo1) = info.ammo1 else {
+            return true;
+        };
+        self.clip > 0 || ctx.ammo(ammo1) != 0 || info.ammo2.is_some_and(|i| ctx.ammo(i) != 0)
+    }
+
+    /// Returns `false` if the clip is empty and the player has no ammo for it.
+    pub fn is_useable(&self, info: &WeaponInfo, ctx: &dyn WeaponContext) -> bool {
+        self.clip > 0 || info.ammo1.is_none_or(|i| ctx.ammo(i) > 0)
+    }
+
+    pub fn default_deploy(
+        &mut self,
+        ctx: &mut dyn WeaponContext,
+        view_model: &'static CStr,
+        weapon_model: &'static CStr,
+        anim: i32,
+        body: i32,
+    ) -> bool {
+        ctx.set_models(Some(view_model), Some(weapon_model));
+        ctx.send_weapon_anim(anim, body);
+        ctx.set_next_attack(0.5);
+        self.time_weapon_idle = 1.0;
+        true
+    }
+
+    /// Starts a reload of the clip from the primary ammo.
+    ///
+    /// The clip is filled by [item_post_frame] after `delay`.
+    pub fn default_reload(
+        &mut self,
+        ctx: &mut dyn WeaponContext,
+        info: &WeaponInfo,
+        anim: i32,
+        delay: f32,
+        body: i32,
+    ) -> bool {
+        let (Some(ammo1), Some(max_clip)) = (info.ammo1, info.max_clip) else {
+            return false;
+        };
+        if (max_clip as i32 - self.clip).min(ctx.ammo(ammo1)) <= 0 {
+            return false;
+        }
+        ctx.set_next_attack(delay);
+        ctx.send_weapon_anim(anim, body);
+        self.in_reload = true;
+        self.time_weapon_idle = 3.0;
+        true
+    }
+
+    pub fn play_empty_sound(&mut self, ctx: &mut dyn WeaponContext) {
+        if self.play_empty_sound {
+            ctx.play_weapon_sound(res::valve::sound::weapons::DRYFIRE1, 0.8, 100);
+            self.play_empty_sound = false;
+        }
+    }
+
+    pub fn reset_empty_sound(&mut self) {
+        self.play_empty_sound = true;
+    }
+
+    /// Returns a random delay before the next idle animation.
+    pub fn random_idle_time(&self, ctx: &dyn WeaponContext) -> f32 {
+        shared_random_float(ctx.random_seed(), 10.0, 15.0)
+    }
+
+    fn complete_reload(&mut self, info: &WeaponInfo, ctx: &mut dyn WeaponContext) {
+        if let (Some(ammo1), Some(max_clip)) = (info.ammo1, info.max_clip) {
+            let ammo = ctx.ammo(ammo1);
+            let count = (max_clip as i32 - self.clip).min(ammo).max(0);
+            self.clip += count;
+            ctx.set_ammo(ammo1, ammo - count);
+        }
+        self.in_reload = false;
+    }
+}
+
+/// The logic of a weapon type.
+pub trait WeaponLogic: Copy + Default {
+    const ID: Weapon;
+
+    /// The model of the weapon lying in the world.
+    const WORLD_MODEL: &'static CStr;
+
+    /// Models, sounds and events to precache.
+    const MODELS: &'static [&'static CStr];
+    const SOUNDS: &'static [&'static CStr];
+    const EVENTS: &'static [&'static CStr];
+
+    fn info() -> &'static WeaponInfo {
+        Self::ID.info().unwrap()
+    }
+
+    fn deploy(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) -> bool;
+
+    fn holster(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext) {
+        state.in_reload = false;
+        ctx.set_models(None, None);
+    }
+
+    fn primary_attack(&mut self, state: &mut WeaponState, ctx: &mut dyn WeaponContext);
+
+    fn secondary_attack(&mut self, _state: &mut WeaponState, _ctx: &mut dyn WeaponContext) {}
+
+    fn reload(&mut self, _state: &mut WeaponState, _ctx: &mut dyn WeaponContext) {}
+
+    fn weapon_idle(&mut self, _state: &mut WeaponState, _ctx: &mut dyn WeaponContext) {}
+}
+
+/// Runs the weapon logic for the current user command.
+pub fn item_post_frame<L: WeaponLogic>(
+    logic: &mut L,
+    state: &mut WeaponState,
+    ctx: &mut dyn WeaponContext,
+) {
+    let info = L::info();
+
+    if state.in_reload && ctx.next_attack() <= 0.0 {
+        state.complete_reload(info, ctx);
+    }
+
+    let buttons = ctx.buttons();
+    if buttons.intersects(Buttons::ATTACK2) && state.next_secondary_attack <= 0.0 {
+        if info.ammo2.is_some_and(|i| ctx.ammo(i) == 0) {
+            state.fire_on_empty = true;
+        }
+        logic.secondary_attack(state, ctx);
+    } else if buttons.intersects(Buttons::ATTACK) && state.next_primary_attack <= 0.0 {
+        let empty_clip = state.clip == 0 && info.ammo1.is_some();
+        let no_ammo = info.max_clip.is_none() && info.ammo1.is_some_and(|i| ctx.ammo(i) == 0);
+        if empty_clip || no_ammo {
+            state.fire_on_empty = true;
+        }
+        logic.primary_attack(state, ctx);
+    } else if buttons.intersects(Buttons::RELOAD) && info.max_clip.is_some() && !state.in_reload {
+        logic.reload(state, ctx);
+    } else if !buttons.intersects(Buttons::ATTACK | Buttons::ATTACK2) {
+        state.fire_on_empty = false;
+        if !state.is_useable(info, ctx) && state.next_primary_attack < 0.0 {
+            if !info.flags.intersects(WeaponFlags::NO_AUTO_SWITCH_EMPTY)
+                && ctx.switch_to_next_best()
+            {
+                state.next_primary_attack = 0.3;
+                return;
+            }
+        } else if state.clip == 0
+            && !info.flags.intersects(WeaponFlags::NO_AUTO_RELOAD)
+            && state.next_primary_attack < 0.0
+        {
+            logic.reload(state, ctx);
+            return;
+        }
+        logic.weapon_idle(state, ctx);
+    }
+}